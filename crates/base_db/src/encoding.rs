@@ -0,0 +1,240 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Source file encodings.
+//!
+//! Erlang source files are UTF-8 by default, but `epp` honours an
+//! explicit `%% coding: latin-1` comment on the first or second line
+//! of the file. We follow the same rules when turning file contents
+//! into text, and when writing text back to disk.
+//!
+//! Contents are decoded once, when read from disk: the VFS and the
+//! editor buffers always hold UTF-8. Only the CLI transcodes text when
+//! writing files back to disk.
+
+use std::fmt;
+use std::ops::Range;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Latin1,
+}
+
+lazy_static! {
+    static ref CODING_RE: Regex = Regex::new(r"coding\s*[:=]\s*([-a-zA-Z0-9_]+)").unwrap();
+}
+
+/// Returns the encoding declared in the first two lines of `text`, if
+/// any, using the same rules as `epp:read_encoding/1`.
+pub fn declared_encoding(text: &str) -> Option<Encoding> {
+    declaration(text).map(|(_, encoding)| encoding)
+}
+
+/// Returns the byte range of the `coding: <name>` declaration in the
+/// first two lines of `text`, along with the encoding it names.
+pub fn declaration(text: &str) -> Option<(Range<usize>, Encoding)> {
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n').take(2) {
+        if let Some(comment_start) = line.find('%') {
+            if let Some(captures) = CODING_RE.captures(&line[comment_start..]) {
+                let encoding = match captures[1].to_ascii_lowercase().as_str() {
+                    "latin-1" | "latin1" | "iso-8859-1" => Some(Encoding::Latin1),
+                    "utf-8" | "utf8" => Some(Encoding::Utf8),
+                    _ => None,
+                };
+                if let Some(encoding) = encoding {
+                    let range = captures.get(0)?.range();
+                    let offset = line_start + comment_start;
+                    return Some((range.start + offset..range.end + offset, encoding));
+                }
+            }
+        }
+        line_start += line.len();
+    }
+    None
+}
+
+/// Decode raw file contents into text.
+///
+/// Files declaring `latin-1` are always decoded as latin-1. Anything
+/// else is decoded as UTF-8, falling back to latin-1 if the contents
+/// are not valid UTF-8, since OTP and some third party libraries still
+/// ship undeclared latin-1 files.
+pub fn decode(bytes: Vec<u8>) -> String {
+    if declared_encoding(&latin1_prefix(&bytes)) == Some(Encoding::Latin1) {
+        return decode_latin1(&bytes);
+    }
+    match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(err) => decode_latin1(err.as_bytes()),
+    }
+}
+
+/// Decode raw file contents read from disk into the UTF-8 bytes held
+/// by the VFS.
+pub fn to_utf8(bytes: Vec<u8>) -> Vec<u8> {
+    decode(bytes).into_bytes()
+}
+
+/// A character that cannot be represented in the declared encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnencodableChar {
+    pub char: char,
+    /// Byte offset of the character in the text.
+    pub offset: usize,
+}
+
+impl fmt::Display for UnencodableChar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "character {:?} at offset {} cannot be represented in latin-1",
+            self.char, self.offset
+        )
+    }
+}
+
+impl std::error::Error for UnencodableChar {}
+
+/// Encode text for writing back to disk, using the encoding declared
+/// in the text itself. Fails on the first character that cannot be
+/// represented in latin-1, rather than writing a garbled file.
+pub fn encode(text: &str) -> Result<Vec<u8>, UnencodableChar> {
+    match declared_encoding(text) {
+        Some(Encoding::Latin1) => text
+            .char_indices()
+            .map(|(offset, c)| u8::try_from(c).map_err(|_| UnencodableChar { char: c, offset }))
+            .collect(),
+        _ => Ok(text.as_bytes().to_vec()),
+    }
+}
+
+/// Returns a description of why the content of a file declaring
+/// `latin-1` does not look like latin-1, if it does not.
+pub fn latin1_mismatch(text: &str) -> Option<&'static str> {
+    if text.chars().any(|c| u32::from(c) > 0xFF) {
+        return Some("contains characters that cannot be represented in latin-1");
+    }
+    let bytes: Vec<u8> = text.chars().map(|c| c as u8).collect();
+    let is_utf8 = std::str::from_utf8(&bytes).map_or(false, |s| !s.is_ascii());
+    if is_utf8 {
+        Some("appears to be UTF-8")
+    } else {
+        None
+    }
+}
+
+fn decode_latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&byte| byte as char).collect()
+}
+
+/// The first two lines of `bytes`, decoded as latin-1. The declaration
+/// itself is always ASCII, so this is enough to find it.
+fn latin1_prefix(bytes: &[u8]) -> String {
+    let end = bytes
+        .iter()
+        .enumerate()
+        .filter(|(_, b)| **b == b'\n')
+        .nth(1)
+        .map_or(bytes.len(), |(idx, _)| idx);
+    decode_latin1(&bytes[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn declared() {
+        assert_eq!(
+            declared_encoding("%% coding: latin-1\n"),
+            Some(Encoding::Latin1)
+        );
+        assert_eq!(
+            declared_encoding("#!/usr/bin/env escript\n%% -*- coding: LATIN1 -*-\n"),
+            Some(Encoding::Latin1)
+        );
+        assert_eq!(declared_encoding("%% coding=utf-8\n"), Some(Encoding::Utf8));
+        assert_eq!(
+            declared_encoding("-module(foo).\n\n%% coding: latin-1\n"),
+            None
+        );
+        assert_eq!(declared_encoding("coding: latin-1\n"), None);
+        assert_eq!(declared_encoding("%% coding: ebcdic\n"), None);
+    }
+
+    #[test]
+    fn declaration_range() {
+        let text = "#!/usr/bin/env escript\n%% -*- coding: latin-1 -*-\n";
+        let (range, _) = declaration(text).unwrap();
+        assert_eq!(&text[range], "coding: latin-1");
+    }
+
+    #[test]
+    fn decode_declared_latin1() {
+        // "é" in UTF-8 is two bytes which are also valid latin-1.
+        let bytes = "%% coding: latin-1\n\"é\"".as_bytes().to_vec();
+        assert_eq!(decode(bytes), "%% coding: latin-1\n\"Ã©\"");
+    }
+
+    #[test]
+    fn decode_undeclared() {
+        assert_eq!(decode("\"é\"".as_bytes().to_vec()), "\"é\"");
+        assert_eq!(decode(vec![b'"', 0xE9, b'"']), "\"é\"");
+    }
+
+    #[test]
+    fn encode_roundtrip() {
+        let bytes = vec![
+            b'%', b' ', b'c', b'o', b'd', b'i', b'n', b'g', b':', b' ', b'l', b'a', b't', b'i',
+            b'n', b'1', b'\n', 0xE9,
+        ];
+        assert_eq!(encode(&decode(bytes.clone())), Ok(bytes));
+        let text = "\"é\"";
+        assert_eq!(encode(text), Ok(text.as_bytes().to_vec()));
+    }
+
+    #[test]
+    fn encode_unencodable() {
+        assert_eq!(
+            encode("%% coding: latin-1\n\"€\""),
+            Err(UnencodableChar {
+                char: '€',
+                offset: 20
+            })
+        );
+        assert_eq!(encode("\"€\""), Ok("\"€\"".as_bytes().to_vec()));
+    }
+
+    #[test]
+    fn to_utf8_declared_latin1() {
+        let bytes = vec![
+            b'%', b' ', b'c', b'o', b'd', b'i', b'n', b'g', b':', b' ', b'l', b'a', b't', b'i',
+            b'n', b'1', b'\n', 0xE9,
+        ];
+        assert_eq!(to_utf8(bytes), "% coding: latin1\né".as_bytes());
+    }
+
+    #[test]
+    fn mismatch() {
+        assert_eq!(latin1_mismatch("%% coding: latin-1\n\"é\""), None);
+        assert_eq!(
+            latin1_mismatch("%% coding: latin-1\n\"Ã©\""),
+            Some("appears to be UTF-8")
+        );
+        assert_eq!(
+            latin1_mismatch("%% coding: latin-1\n\"€\""),
+            Some("contains characters that cannot be represented in latin-1")
+        );
+    }
+}
@@ -91,8 +91,24 @@ pub struct ChangeFixture {
 }
 
 impl ChangeFixture {
-    fn parse(text_fixture: &str) -> (ChangeFixture, Change) {
-        let fixture = Fixture::parse(text_fixture);
+    pub fn parse(text_fixture: &str) -> (ChangeFixture, Change) {
+        Self::parse_with_markers(text_fixture, true).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Parse a whole project described in the fixture format, without
+    /// looking for cursor markers, so that file contents are taken
+    /// verbatim. This lets a project be loaded in memory, with no
+    /// `Vfs` or loader involved. As the description may come from
+    /// outside, an invalid one is an error rather than a panic.
+    pub fn parse_project(text_fixture: &str) -> Result<(ChangeFixture, Change), String> {
+        Self::parse_with_markers(text_fixture, false)
+    }
+
+    fn parse_with_markers(
+        text_fixture: &str,
+        markers: bool,
+    ) -> Result<(ChangeFixture, Change), String> {
+        let fixture = Fixture::try_parse(text_fixture)?;
         let mut change = Change::new();
 
         let mut files = Vec::new();
@@ -105,18 +121,29 @@ impl ChangeFixture {
         let mut app_files = SourceRootMap::default();
 
         for entry in fixture {
-            let (text, file_pos) = Self::get_text_and_pos(&entry.text, file_id);
+            let (text, file_pos) = if markers {
+                Self::get_text_and_pos(&entry.text, file_id)
+            } else {
+                (entry.text, None)
+            };
             if file_pos.is_some() {
                 assert!(file_position.is_none());
                 file_position = file_pos;
             }
 
-            assert!(entry.path.starts_with(&source_root_prefix));
+            if !entry.path.starts_with(&source_root_prefix) {
+                return Err(format!(
+                    "fixture path does not start with `{}`: {:?}",
+                    source_root_prefix, entry.path
+                ));
+            }
 
-            let app_name = if let Some(otp) = &entry.otp {
-                otp.apps[0].name.clone()
-            } else {
-                entry.app_data.as_ref().unwrap().name.clone()
+            // `Fixture::parse_meta_line` gives every entry either an OTP
+            // app or the data of its app
+            let app_name = match (&entry.otp, &entry.app_data) {
+                (Some(otp), _) => otp.apps[0].name.clone(),
+                (None, Some(app_data)) => app_data.name.clone(),
+                (None, None) => return Err(format!("no app for {:?}", entry.path)),
             };
 
             if let Some(app_data) = entry.app_data {
@@ -162,13 +189,13 @@ impl ChangeFixture {
         }
         change.set_roots(roots);
 
-        (
+        Ok((
             ChangeFixture {
                 file_position,
                 files,
             },
             change,
-        )
+        ))
     }
 
     pub fn annotations(&self, db: &dyn SourceDatabaseExt) -> Vec<(FileRange, String)> {
@@ -810,6 +837,7 @@ bar() -> ?FOO.
                                         "/ebin",
                                     ),
                                 ),
+                                include_remaps: [],
                             },
                         ),
                         SourceRootId(
@@ -850,6 +878,7 @@ bar() -> ?FOO.
                                         "/opt/lib/comp-1.3/ebin",
                                     ),
                                 ),
+                                include_remaps: [],
                             },
                         ),
                         SourceRootId(
@@ -883,6 +912,7 @@ bar() -> ?FOO.
                                         "/ebin",
                                     ),
                                 ),
+                                include_remaps: [],
                             },
                         ),
                         SourceRootId(
@@ -940,6 +970,30 @@ bar() -> ?FOO.
                             eqwalizer_config: EqwalizerConfig {
                                 enable_all: false,
                             },
+                            lint_file_config: LintFileConfig {
+                                enabled: [],
+                                disabled: [],
+                                ignored_apps: [],
+                                severity: {},
+                                apps: {},
+                                deprecated: DeprecatedConfig {
+                                    functions: [],
+                                    replacements: {},
+                                },
+                                cross_node_eval: CrossNodeEvalConfig {
+                                    add: [],
+                                    remove: [],
+                                    wrappers: [],
+                                },
+                                table_keys: TableKeysConfig {
+                                    constructors: [],
+                                    readers: [],
+                                },
+                                atom_creation: AtomCreationConfig {
+                                    allowed: [],
+                                },
+                                plugins: [],
+                            },
                         },
                         ProjectId(
                             1,
@@ -972,6 +1026,30 @@ bar() -> ?FOO.
                             eqwalizer_config: EqwalizerConfig {
                                 enable_all: false,
                             },
+                            lint_file_config: LintFileConfig {
+                                enabled: [],
+                                disabled: [],
+                                ignored_apps: [],
+                                severity: {},
+                                apps: {},
+                                deprecated: DeprecatedConfig {
+                                    functions: [],
+                                    replacements: {},
+                                },
+                                cross_node_eval: CrossNodeEvalConfig {
+                                    add: [],
+                                    remove: [],
+                                    wrappers: [],
+                                },
+                                table_keys: TableKeysConfig {
+                                    constructors: [],
+                                    readers: [],
+                                },
+                                atom_creation: AtomCreationConfig {
+                                    allowed: [],
+                                },
+                                plugins: [],
+                            },
                         },
                     },
                 },
@@ -1046,6 +1124,7 @@ foo() -> ?BAR.
                                         "/extra/ebin",
                                     ),
                                 ),
+                                include_remaps: [],
                             },
                         ),
                         SourceRootId(
@@ -1084,6 +1163,30 @@ foo() -> ?BAR.
                             eqwalizer_config: EqwalizerConfig {
                                 enable_all: false,
                             },
+                            lint_file_config: LintFileConfig {
+                                enabled: [],
+                                disabled: [],
+                                ignored_apps: [],
+                                severity: {},
+                                apps: {},
+                                deprecated: DeprecatedConfig {
+                                    functions: [],
+                                    replacements: {},
+                                },
+                                cross_node_eval: CrossNodeEvalConfig {
+                                    add: [],
+                                    remove: [],
+                                    wrappers: [],
+                                },
+                                table_keys: TableKeysConfig {
+                                    constructors: [],
+                                    readers: [],
+                                },
+                                atom_creation: AtomCreationConfig {
+                                    allowed: [],
+                                },
+                                plugins: [],
+                            },
                         },
                         ProjectId(
                             1,
@@ -1106,6 +1209,30 @@ foo() -> ?BAR.
                             eqwalizer_config: EqwalizerConfig {
                                 enable_all: false,
                             },
+                            lint_file_config: LintFileConfig {
+                                enabled: [],
+                                disabled: [],
+                                ignored_apps: [],
+                                severity: {},
+                                apps: {},
+                                deprecated: DeprecatedConfig {
+                                    functions: [],
+                                    replacements: {},
+                                },
+                                cross_node_eval: CrossNodeEvalConfig {
+                                    add: [],
+                                    remove: [],
+                                    wrappers: [],
+                                },
+                                table_keys: TableKeysConfig {
+                                    constructors: [],
+                                    readers: [],
+                                },
+                                atom_creation: AtomCreationConfig {
+                                    allowed: [],
+                                },
+                                plugins: [],
+                            },
                         },
                     },
                 },
@@ -1230,4 +1357,52 @@ meaning_of_life() ->
         "#]]
         .assert_debug_eq(&res);
     }
+
+    #[test]
+    fn parse_project_keeps_text_verbatim() {
+        let (fixture, change) = ChangeFixture::parse_project(
+            r#"
+//- /src/foo.erl app:foo
+-module(foo).
+log(X) -> io:format("~p~n", [X]).
+//- /src/bar.erl app:bar
+-module(bar).
+"#,
+        )
+        .unwrap();
+
+        assert!(fixture.file_position.is_none());
+        assert_eq!(fixture.files.len(), 2);
+        expect![[r#"
+            [
+                "-module(foo).\nlog(X) -> io:format(\"~p~n\", [X]).\n",
+                "-module(bar).\n",
+            ]
+        "#]]
+        .assert_debug_eq(
+            &change
+                .files_changed
+                .iter()
+                .map(|(_, text)| text.as_deref().unwrap().as_str())
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(change.roots.map(|roots| roots.len()), Some(2));
+    }
+
+    #[test]
+    fn parse_project_rejects_invalid_descriptions() {
+        let err = |description: &str| ChangeFixture::parse_project(description).err().unwrap();
+        expect![[r#"
+            "fixture path does not start with `/`: \"src/foo.erl app:foo\""
+        "#]]
+        .assert_debug_eq(&err("//- src/foo.erl app:foo\n-module(foo).\n"));
+        expect![[r#"
+            "bad component: \"application:foo\""
+        "#]]
+        .assert_debug_eq(&err("//- /src/foo.erl application:foo\n-module(foo).\n"));
+        expect![[r#"
+            "path is not absolute: \"include\""
+        "#]]
+        .assert_debug_eq(&err("//- /src/foo.erl include_path:include\n"));
+    }
 }
@@ -13,8 +13,10 @@ use std::path::Path;
 use std::sync::Arc;
 
 use elp_project_model::buck::EqwalizerConfig;
+use elp_project_model::lint_file::LintFileConfig;
 use elp_project_model::AppName;
 use elp_project_model::AppType;
+use elp_project_model::IncludeRemap;
 use elp_project_model::Project;
 use elp_project_model::ProjectAppData;
 use fxhash::FxHashMap;
@@ -108,6 +110,7 @@ pub struct ProjectData {
     pub otp_project_id: Option<ProjectId>,
     pub app_roots: AppRoots,
     pub eqwalizer_config: EqwalizerConfig,
+    pub lint_file_config: LintFileConfig,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -122,6 +125,7 @@ pub struct AppData {
     pub parse_transforms: Vec<eetf::Term>,
     pub app_type: AppType,
     pub ebin_path: Option<AbsPathBuf>,
+    pub include_remaps: Vec<IncludeRemap>,
 }
 
 impl AppData {
@@ -188,6 +192,65 @@ impl AppStructure {
             db.set_project_data(project_id, Arc::new(project_data));
         }
     }
+
+    /// Set the salsa inputs which differ from those of `previous`, the
+    /// structure applied before, so that the results depending on the
+    /// others stay valid. Returns the projects which are new or changed.
+    pub fn apply_changes(
+        self,
+        previous: &AppStructure,
+        db: &mut dyn SourceDatabaseExt,
+    ) -> Vec<ProjectId> {
+        for (source_root_id, data) in &previous.app_map {
+            if data.is_some() && !self.app_map.contains_key(source_root_id) {
+                db.set_app_data(*source_root_id, None);
+            }
+        }
+        for (source_root_id, data) in self.app_map {
+            if previous.app_map.get(&source_root_id) != Some(&data) {
+                db.set_app_data(source_root_id, data.map(Arc::new));
+            }
+        }
+        let mut changed = Vec::new();
+        for (project_id, project_data) in self.project_map {
+            if previous.project_map.get(&project_id) != Some(&project_data) {
+                db.set_project_data(project_id, Arc::new(project_data));
+                changed.push(project_id);
+            }
+        }
+        changed
+    }
+}
+
+/// The source roots set in the database, so that only those which
+/// change are set again, keeping the results depending on the others
+/// valid.
+#[derive(Debug, Clone, Default)]
+pub struct SourceRoots {
+    roots: Vec<SourceRoot>,
+}
+
+impl SourceRoots {
+    /// Set the source roots of the partition of the files `sets`, as
+    /// given by `FileSetConfig::partition`.
+    pub fn apply(&mut self, sets: Vec<FileSet>, db: &mut dyn SourceDatabaseExt) {
+        let roots: Vec<SourceRoot> = sets.into_iter().map(SourceRoot::new).collect();
+        for (idx, root) in roots.iter().enumerate() {
+            let previous = self.roots.get(idx);
+            if previous == Some(root) {
+                continue;
+            }
+            let root_id = SourceRootId(idx as u32);
+            for file_id in root.iter() {
+                // The files of the previous root already belong to it
+                if previous.map_or(true, |previous| previous.path_for_file(&file_id).is_none()) {
+                    db.set_file_source_root(file_id, root_id);
+                }
+            }
+            db.set_source_root(root_id, Arc::new(root.clone()));
+        }
+        self.roots = roots;
+    }
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
@@ -322,6 +385,7 @@ impl<'a> ProjectApps<'a> {
                     app_type: app.app_type,
                     src_path: app.abs_src_dirs.clone(),
                     ebin_path: app.ebin.clone(),
+                    include_remaps: app.include_remaps.clone(),
                 };
                 app_structure.add_app_data(root_id, Some(input_data));
             }
@@ -340,6 +404,7 @@ impl<'a> ProjectApps<'a> {
                 otp_project_id: self.otp_project_id,
                 app_roots,
                 eqwalizer_config: project.eqwalizer_config(),
+                lint_file_config: project.lint_file_config(),
             };
             app_structure.add_project_data(project_id, project_data);
         }
@@ -7,6 +7,7 @@
  * of this source tree.
  */
 
+use std::borrow::Cow;
 use std::sync::Arc;
 
 use elp_project_model::AppName;
@@ -16,6 +17,7 @@ use elp_syntax::TextRange;
 use elp_syntax::TextSize;
 
 mod change;
+pub mod encoding;
 mod input;
 mod module_index;
 
@@ -37,6 +39,7 @@ pub use input::ProjectData;
 pub use input::ProjectId;
 pub use input::SourceRoot;
 pub use input::SourceRootId;
+pub use input::SourceRoots;
 pub use module_index::ModuleIndex;
 pub use module_index::ModuleName;
 pub use module_index::Modules;
@@ -163,7 +166,31 @@ fn module_index(db: &dyn SourceDatabase, project_id: ProjectId) -> Arc<ModuleInd
 
 fn parse(db: &dyn SourceDatabase, file_id: FileId) -> Parse<SourceFile> {
     let text = db.file_text(file_id);
-    SourceFile::parse_text(&text)
+    if is_escript(db, file_id) {
+        SourceFile::parse_text(&mask_escript_shebang(&text))
+    } else {
+        SourceFile::parse_text(&text)
+    }
+}
+
+fn is_escript(db: &dyn SourceDatabase, file_id: FileId) -> bool {
+    let root = db.source_root(db.file_source_root(file_id));
+    matches!(
+        root.path_for_file(&file_id)
+            .and_then(|path| path.name_and_extension()),
+        Some((_, Some("escript")))
+    )
+}
+
+/// The `#!` line an escript starts with is not Erlang syntax. Turn it
+/// into a comment of the same length, so that the ranges of the syntax
+/// tree still match the file text. The `%%!` emulator arguments line
+/// that may follow is already a comment.
+fn mask_escript_shebang(text: &str) -> Cow<str> {
+    match text.strip_prefix("#!") {
+        Some(rest) => Cow::Owned(format!("%!{}", rest)),
+        None => Cow::Borrowed(text),
+    }
 }
 
 fn is_generated(db: &dyn SourceDatabase, file_id: FileId) -> bool {
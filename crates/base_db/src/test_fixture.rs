@@ -73,6 +73,7 @@ use std::path::PathBuf;
 
 use elp_project_model::otp::Otp;
 use elp_project_model::AppName;
+use elp_project_model::IncludeRemap;
 use elp_project_model::ProjectAppData;
 use paths::AbsPath;
 use paths::AbsPathBuf;
@@ -97,6 +98,12 @@ impl Fixture {
     ///  ```
     ///
     pub fn parse(fixture: &str) -> Vec<Fixture> {
+        Fixture::try_parse(fixture).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like `parse`, but returns an error describing the first invalid
+    /// line instead of panicking, for fixtures not written by a test.
+    pub fn try_parse(fixture: &str) -> Result<Vec<Fixture>, String> {
         let fixture = trim_indent(fixture);
         let mut res: Vec<Fixture> = Vec::new();
 
@@ -111,19 +118,17 @@ impl Fixture {
             .chain(fixture.split_inclusive('\n'))
             .enumerate()
         {
-            if line.contains("//-") {
-                assert!(
-                    line.starts_with("//-"),
+            if line.contains("//-") && !line.starts_with("//-") {
+                return Err(format!(
                     "Metadata line {} has invalid indentation. \
                      All metadata lines need to have the same indentation.\n\
                      The offending line: {:?}",
-                    ix,
-                    line
-                );
+                    ix, line
+                ));
             }
 
             if line.starts_with("//-") {
-                let meta = Fixture::parse_meta_line(line);
+                let meta = Fixture::parse_meta_line(line)?;
                 res.push(meta)
             } else {
                 if line.starts_with("// ")
@@ -131,7 +136,7 @@ impl Fixture {
                     && !line.contains("::")
                     && line.chars().all(|it| !it.is_uppercase())
                 {
-                    panic!("looks like invalid metadata line: {:?}", line)
+                    return Err(format!("looks like invalid metadata line: {:?}", line));
                 }
 
                 if let Some(entry) = res.last_mut() {
@@ -140,42 +145,45 @@ impl Fixture {
             }
         }
 
-        res
+        Ok(res)
     }
 
     //- /module.erl app:foo
     //- /opt/lib/comp-1.3/include/comp.hrl otp_app:/opt/lib/comp-1.3
     //- /my_app/test/file_SUITE.erl extra:test
-    fn parse_meta_line(meta: &str) -> Fixture {
-        assert!(meta.starts_with("//-"));
+    //- /src/foo.erl app:foo generated:/gen/include generator://foo:headers
+    fn parse_meta_line(meta: &str) -> Result<Fixture, String> {
         let meta = meta["//-".len()..].trim();
         let components = meta.split_ascii_whitespace().collect::<Vec<_>>();
 
-        let path = components[0].to_string();
-        assert!(
-            path.starts_with('/'),
-            "fixture path does not start with `/`: {:?}",
-            path
-        );
+        let path = match components.first() {
+            Some(path) if path.starts_with('/') => path.to_string(),
+            _ => return Err(format!("fixture path does not start with `/`: {:?}", meta)),
+        };
 
         let mut app_name = None;
         let mut include_dirs = Vec::new();
         let mut extra_dirs = Vec::new();
+        let mut generated_dirs = Vec::new();
+        let mut generator = String::new();
         let mut otp = None;
 
         for component in components[1..].iter() {
             let (key, value) = component
                 .split_once(':')
-                .unwrap_or_else(|| panic!("invalid meta line: {:?}", meta));
+                .ok_or_else(|| format!("invalid meta line: {:?}", meta))?;
             match key {
                 "app" => app_name = Some(AppName(value.to_string())),
-                "include_path" => include_dirs
-                    .push(AbsPath::assert(&PathBuf::from(value.to_string())).normalize()),
+                "include_path" => include_dirs.push(abs_path(value)?.normalize()),
                 "otp_app" => {
                     // We have an app directory, the OTP lib dir is its parent
-                    let path = AbsPathBuf::assert(PathBuf::from(value.to_string()));
-                    let lib_dir = path.parent().unwrap().normalize();
-                    let versioned_name = path.file_name().unwrap().to_str().unwrap().to_string();
+                    let path = abs_path(value)?;
+                    let (lib_dir, versioned_name) = match (path.parent(), path.file_name()) {
+                        (Some(lib_dir), Some(name)) => {
+                            (lib_dir.normalize(), name.to_string_lossy().to_string())
+                        }
+                        _ => return Err(format!("invalid otp_app directory: {:?}", value)),
+                    };
                     let app = ProjectAppData::otp_app_data(&versioned_name, path);
 
                     otp = Some(Otp {
@@ -183,13 +191,15 @@ impl Fixture {
                         apps: vec![app],
                     });
                 }
+                "generated" => generated_dirs.push(abs_path(value)?.normalize()),
+                "generator" => generator = value.to_string(),
                 "extra" => {
                     // We have an extra directory, such as for a test suite
                     // It needs to be relative to the app dir.
                     let dir = value.to_string();
                     extra_dirs.push(dir);
                 }
-                _ => panic!("bad component: {:?}", component),
+                _ => return Err(format!("bad component: {:?}", component)),
             }
         }
 
@@ -197,7 +207,9 @@ impl Fixture {
             None
         } else {
             // Try inferring dir - parent once to get to ./src, parent twice to get to app root
-            let dir = AbsPath::assert(Path::new(&path)).parent().unwrap();
+            let dir = AbsPath::assert(Path::new(&path))
+                .parent()
+                .ok_or_else(|| format!("fixture path has no parent: {:?}", path))?;
             let dir = dir.parent().unwrap_or(dir).normalize();
             let app_name = app_name.unwrap_or(AppName("test-fixture".to_string()));
             let abs_path = AbsPathBuf::assert(PathBuf::from(path.clone()));
@@ -210,24 +222,32 @@ impl Fixture {
                     }
                 }
             }
-            Some(ProjectAppData::fixture_app_data(
-                app_name,
-                dir,
-                include_dirs,
-                src_dirs,
-                extra_dirs,
-            ))
+            let mut app_data =
+                ProjectAppData::fixture_app_data(app_name, dir, include_dirs, src_dirs, extra_dirs);
+            if !generated_dirs.is_empty() {
+                app_data.include_remaps.push(IncludeRemap {
+                    prefix: "include".to_string(),
+                    dirs: generated_dirs,
+                    target: generator,
+                });
+            }
+            Some(app_data)
         };
 
-        Fixture {
+        Ok(Fixture {
             path,
             text: String::new(),
             app_data,
             otp,
-        }
+        })
     }
 }
 
+fn abs_path(value: &str) -> Result<AbsPathBuf, String> {
+    AbsPathBuf::try_from(PathBuf::from(value))
+        .map_err(|path| format!("path is not absolute: {:?}", path))
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
@@ -339,6 +359,7 @@ bar() -> ok.
                 parse_transforms: [],
                 app_type: App,
                 include_path: [],
+                include_remaps: [],
             }"#]]
         .assert_eq(format!("{:#?}", meta0.app_data.as_ref().unwrap()).as_str());
     }
@@ -0,0 +1,266 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use elp::build::load;
+use elp::build::types::LoadResult;
+use elp::cli::Cli;
+use elp_ide::apply_assist;
+use elp_ide::apply_assist::AssistOutcome;
+use elp_ide::diff::diff_from_textedit;
+use elp_ide::elp_ide_assists::AssistConfig;
+use elp_ide::elp_ide_db::elp_base_db::encoding;
+use elp_ide::elp_ide_db::elp_base_db::AbsPath;
+use elp_ide::elp_ide_db::elp_base_db::AnchoredPathBuf;
+use elp_ide::elp_ide_db::elp_base_db::FileId;
+use elp_ide::elp_ide_db::elp_base_db::FilePosition;
+use elp_ide::elp_ide_db::elp_base_db::IncludeOtp;
+use elp_ide::elp_ide_db::elp_base_db::VfsPath;
+use elp_ide::elp_ide_db::source_change::FileSystemEdit;
+use elp_ide::elp_ide_db::LineCol;
+use elp_project_model::DiscoverConfig;
+
+use crate::args::ApplyAssist;
+
+pub fn apply_assist(args: &ApplyAssist, cli: &mut dyn Cli) -> Result<()> {
+    if args.in_place && args.to.is_some() {
+        bail!("--in-place and --to cannot be used together");
+    }
+    let mut specs = args.at.clone();
+    if let Some(path) = &args.positions {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("could not read positions from {}", path.display()))?;
+        specs.extend(
+            contents
+                .lines()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(|line| line.to_string()),
+        );
+    }
+    if specs.is_empty() {
+        bail!("no positions given, use --at or --positions");
+    }
+
+    let config = DiscoverConfig::new(args.rebar, &args.profile);
+    let mut loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::Yes)?;
+    let positions = specs
+        .iter()
+        .map(|spec| parse_position(&loaded, spec))
+        .collect::<Result<Vec<_>>>()?;
+
+    let assist_config = AssistConfig {
+        snippet_cap: None,
+        allowed: None,
+        experimental: args.experimental,
+        record_fields_alphabetical: false,
+        exports_alphabetical: false,
+    };
+    let res = apply_assist::apply_assist(
+        &mut loaded.analysis_host,
+        &assist_config,
+        &args.assist_id,
+        &positions,
+    )?;
+
+    let mut applied = 0;
+    for (spec, (_, outcome)) in specs.iter().zip(&res.outcomes) {
+        match outcome {
+            AssistOutcome::Applied => applied += 1,
+            AssistOutcome::NotApplicable => {
+                writeln!(cli.err(), "{spec}: {} is not applicable", args.assist_id)?
+            }
+            AssistOutcome::Invalidated => writeln!(
+                cli.err(),
+                "{spec}: skipped, the code was changed by another application"
+            )?,
+        }
+    }
+
+    let mut changed: Vec<_> = res
+        .changed_files
+        .iter()
+        .filter_map(|(file_id, texts)| {
+            let path = loaded.vfs.file_path(*file_id).as_path()?.to_path_buf();
+            Some((PathBuf::from(path), texts))
+        })
+        .collect();
+    changed.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (path, (before, after)) in changed {
+        if args.in_place {
+            fs::write(&path, encode(&path, after)?)?;
+        } else if let Some(to) = &args.to {
+            let out = output_path(&loaded, to, &path)?;
+            fs::write(out, encode(&path, after)?)?;
+        } else if let (_, Some(unified)) = diff_from_textedit(before, after) {
+            writeln!(cli, "--- {}", path.display())?;
+            writeln!(cli, "+++ {}", path.display())?;
+            write!(cli, "{unified}")?;
+        }
+    }
+    for edit in &res.file_system_edits {
+        apply_file_system_edit(&loaded, args, edit, cli)?;
+    }
+    writeln!(
+        cli.err(),
+        "Applied {} at {} of {} positions",
+        args.assist_id,
+        applied,
+        specs.len()
+    )?;
+    Ok(())
+}
+
+/// Create, delete or move a file as requested by an assist, or show
+/// the change when not modifying the files in place.
+fn apply_file_system_edit(
+    loaded: &LoadResult,
+    args: &ApplyAssist,
+    edit: &FileSystemEdit,
+    cli: &mut dyn Cli,
+) -> Result<()> {
+    match edit {
+        FileSystemEdit::CreateFile {
+            dst,
+            initial_contents,
+        } => {
+            let path = anchored_path(loaded, dst)?;
+            if args.in_place {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&path, encode(&path, initial_contents)?)?;
+            } else if let Some(to) = &args.to {
+                let out = output_path(loaded, to, &path)?;
+                fs::write(out, encode(&path, initial_contents)?)?;
+            } else if let (_, Some(unified)) = diff_from_textedit("", initial_contents) {
+                writeln!(cli, "--- /dev/null")?;
+                writeln!(cli, "+++ {}", path.display())?;
+                write!(cli, "{unified}")?;
+            }
+        }
+        FileSystemEdit::DeleteFile { file } => {
+            let path = file_path(loaded, *file)?;
+            if args.in_place {
+                fs::remove_file(&path)?;
+            } else if args.to.is_some() {
+                writeln!(cli.err(), "{}: not deleted, use --in-place", path.display())?;
+            } else {
+                let text = loaded.analysis().file_text(*file)?;
+                if let (_, Some(unified)) = diff_from_textedit(&text, "") {
+                    writeln!(cli, "--- {}", path.display())?;
+                    writeln!(cli, "+++ /dev/null")?;
+                    write!(cli, "{unified}")?;
+                }
+            }
+        }
+        FileSystemEdit::MoveFile { src, dst } => {
+            let from = file_path(loaded, *src)?;
+            let path = anchored_path(loaded, dst)?;
+            if args.in_place {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::rename(&from, &path)?;
+            } else if let Some(to) = &args.to {
+                let out = output_path(loaded, to, &path)?;
+                let text = loaded.analysis().file_text(*src)?;
+                fs::write(out, encode(&path, &text)?)?;
+            } else {
+                writeln!(cli, "rename from {}", from.display())?;
+                writeln!(cli, "rename to {}", path.display())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The contents to write for `path`, in the encoding it declares.
+fn encode(path: &Path, text: &str) -> Result<Vec<u8>> {
+    encoding::encode(text).with_context(|| format!("Cannot write {}", path.display()))
+}
+
+/// Where to write `path` in the `to` directory: at its path relative
+/// to the project root, so that files with the same name in different
+/// directories don't overwrite each other. Its directory is created.
+fn output_path(loaded: &LoadResult, to: &Path, path: &Path) -> Result<PathBuf> {
+    let root = loaded.project.root();
+    let relative = path.strip_prefix(&*root).with_context(|| {
+        format!(
+            "{} is outside of the project root {}",
+            path.display(),
+            root.display()
+        )
+    })?;
+    let out = to.join(relative);
+    if let Some(parent) = out.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(out)
+}
+
+fn file_path(loaded: &LoadResult, file_id: FileId) -> Result<PathBuf> {
+    let path = loaded.vfs.file_path(file_id);
+    let path = path
+        .as_path()
+        .with_context(|| format!("not a local path: {path}"))?;
+    Ok(PathBuf::from(path.to_path_buf()))
+}
+
+/// The path of `dst`, relative to the directory of its anchor file.
+fn anchored_path(loaded: &LoadResult, dst: &AnchoredPathBuf) -> Result<PathBuf> {
+    let mut base = loaded.vfs.file_path(dst.anchor);
+    base.pop();
+    let path = base
+        .join(&dst.path)
+        .with_context(|| format!("invalid path: {}", dst.path))?;
+    let path = path
+        .as_path()
+        .with_context(|| format!("not a local path: {path}"))?;
+    Ok(PathBuf::from(path.to_path_buf()))
+}
+
+/// Parse a `FILE:LINE:COL` position, with 1-based line and column.
+pub fn parse_position(loaded: &LoadResult, spec: &str) -> Result<FilePosition> {
+    let mut parts = spec.rsplitn(3, ':');
+    let (col, line, file) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(col), Some(line), Some(file)) => (col, line, file),
+        _ => bail!("{spec}: expected FILE:LINE:COL"),
+    };
+    let line: u32 = line.parse().with_context(|| format!("{spec}: bad line"))?;
+    let col: u32 = col.parse().with_context(|| format!("{spec}: bad column"))?;
+    if line == 0 || col == 0 {
+        bail!("{spec}: line and column start at 1");
+    }
+    let path_buf =
+        fs::canonicalize(file).with_context(|| format!("{spec}: could not find {file}"))?;
+    let path = AbsPath::assert(&path_buf);
+    let path = path.as_os_str().to_str().context("non UTF-8 path")?;
+    let file_id = match loaded
+        .vfs
+        .file_id(&VfsPath::new_real_path(path.to_string()))
+    {
+        Some(file_id) => file_id,
+        None => bail!("{spec}: {file} is not part of the project"),
+    };
+    let line_index = loaded.analysis().line_index(file_id)?;
+    let offset = line_index
+        .safe_offset(LineCol {
+            line: line - 1,
+            col_utf16: col - 1,
+        })
+        .with_context(|| format!("{spec}: position is outside of the file"))?;
+    Ok(FilePosition { file_id, offset })
+}
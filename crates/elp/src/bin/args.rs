@@ -79,6 +79,8 @@ pub struct Eqwalize {
     pub profile: String,
     /// Run with rebar
     pub rebar: bool,
+    /// After the first run, watch the project files and eqWAlize the module again on each change
+    pub watch: bool,
     /// Eqwalize specified module
     #[bpaf(positional::< String > ("MODULE"), complete(module_completer))]
     pub module: String,
@@ -92,18 +94,21 @@ pub struct EqwalizeAll {
     /// Rebar3 profile to pickup (default is test)
     #[bpaf(long("as"), argument("PROFILE"), fallback("test".to_string()))]
     pub profile: String,
-    /// Show diagnostics in JSON format
+    /// Show diagnostics in JSON or SARIF format
     #[bpaf(
         argument("FORMAT"),
-        complete(format_completer),
+        complete(sarif_format_completer),
         fallback(None),
-        guard(format_guard, "Please use json")
+        guard(sarif_format_guard, "Please use json or sarif")
     )]
     pub format: Option<String>,
     /// Run with rebar
     pub rebar: bool,
     /// Also eqwalize opted-in generated modules from project
     pub include_generated: bool,
+    /// Max number of eqWAlizer instances to run in parallel (default is 4)
+    #[bpaf(argument("N"))]
+    pub jobs: Option<usize>,
 }
 
 #[derive(Clone, Debug, Bpaf)]
@@ -208,12 +213,12 @@ pub struct Lint {
     /// Rebar3 profile to pickup (default is test)
     #[bpaf(long("as"), argument("PROFILE"), fallback("test".to_string()))]
     pub profile: String,
-    /// Show diagnostics in JSON format
+    /// Show diagnostics in JSON or SARIF format
     #[bpaf(
         argument("FORMAT"),
-        complete(format_completer),
+        complete(sarif_format_completer),
         fallback(None),
-        guard(format_guard, "Please use json")
+        guard(sarif_format_guard, "Please use json or sarif")
     )]
     pub format: Option<String>,
     /// Run with rebar
@@ -227,15 +232,37 @@ pub struct Lint {
     pub recursive: bool,
     /// When applying a fix, modify the original file.
     pub in_place: bool,
+    /// When applying fixes, write no files, but print a unified diff of each changed module, or write it to `<module>.diff` in the --to directory.
+    pub dry_run: bool,
+    /// Instead of applying fixes, add a `% elp:ignore` comment above each reported diagnostic, to adopt a new lint incrementally.
+    pub add_ignores: bool,
+    /// Also run the lint plugins given in the .elp_lint.toml file of the project. They run commands on each file, so only use this for a trusted project.
+    pub plugins: bool,
+    /// Report how long each diagnostic pass took on each file, instead of the diagnostics
+    pub timing: bool,
+    /// After the first run, watch the project files and lint the modules affected by each change
+    pub watch: bool,
     /// Filter out all reported diagnostics except this one
     #[bpaf(argument("FILTER"))]
     pub diagnostic_filter: Option<String>,
+    /// Report this diagnostic as an error, e.g. W0010
+    #[bpaf(long("error"), argument("CODE"))]
+    pub error: Vec<String>,
+    /// Report this diagnostic as a warning, e.g. P1700
+    #[bpaf(long("warn"), argument("CODE"))]
+    pub warn: Vec<String>,
+    /// Report the calls to replace listed in this codemod spec, a TOML or JSON file, with fixes
+    #[bpaf(argument("SPEC"))]
+    pub codemod: Option<PathBuf>,
     /// Filter out all reported diagnostics before this line. Valid only for single file
     #[bpaf(argument("LINE_FROM"))]
     pub line_from: Option<u32>,
     /// Filter out all reported diagnostics after this line. Valid only for single file
     #[bpaf(argument("LINE_TO"))]
     pub line_to: Option<u32>,
+    /// Lint files as of this git revision, read from the repository instead of the working tree
+    #[bpaf(argument("REV"))]
+    pub rev: Option<String>,
     /// Rest of args are space separated list of apps to ignore
     #[bpaf(positional("IGNORED_APPS"))]
     pub ignore_apps: Vec<String>,
@@ -248,6 +275,220 @@ pub struct Shell {
     pub project: PathBuf,
 }
 
+#[derive(Clone, Debug, Bpaf)]
+pub struct ShardTests {
+    /// Path to directory with project (defaults to `.`)
+    #[bpaf(argument("PROJECT"), fallback(PathBuf::from(".")))]
+    pub project: PathBuf,
+    /// Rebar3 profile to pickup (default is test)
+    #[bpaf(long("as"), argument("PROFILE"), fallback("test".to_string()))]
+    pub profile: String,
+    /// Run with rebar
+    pub rebar: bool,
+    /// Number of shards to split the tests into
+    #[bpaf(argument("SHARDS"))]
+    pub shards: usize,
+    /// JSON file mapping test ids to their historical duration in seconds
+    #[bpaf(argument("TIMINGS"))]
+    pub timings: Option<PathBuf>,
+    /// Show shards in JSON format
+    #[bpaf(
+        argument("FORMAT"),
+        complete(format_completer),
+        fallback(None),
+        guard(format_guard, "Please use json")
+    )]
+    pub format: Option<String>,
+}
+
+#[derive(Clone, Debug, Bpaf)]
+pub struct ApplyAssist {
+    /// Path to directory with project (defaults to `.`)
+    #[bpaf(argument("PROJECT"), fallback(PathBuf::from(".")))]
+    pub project: PathBuf,
+    /// Rebar3 profile to pickup (default is test)
+    #[bpaf(long("as"), argument("PROFILE"), fallback("test".to_string()))]
+    pub profile: String,
+    /// Run with rebar
+    pub rebar: bool,
+    /// Id of the assist to apply, e.g. inline_function
+    #[bpaf(argument("ASSIST_ID"))]
+    pub assist_id: String,
+    /// Position to apply the assist at, with 1-based line and column
+    #[bpaf(argument("FILE:LINE:COL"))]
+    pub at: Vec<String>,
+    /// File with one FILE:LINE:COL position per line
+    #[bpaf(argument("POSITIONS"))]
+    pub positions: Option<PathBuf>,
+    /// Also allow experimental assists
+    pub experimental: bool,
+    /// Path to a directory where to write the modified files, at their
+    /// path relative to the project root
+    #[bpaf(argument("TO"))]
+    pub to: Option<PathBuf>,
+    /// Modify the original files, rather than printing a diff
+    pub in_place: bool,
+}
+
+#[derive(Clone, Debug, Bpaf)]
+pub struct Ci {
+    /// Path to directory with project (defaults to `.`)
+    #[bpaf(argument("PROJECT"), fallback(PathBuf::from(".")))]
+    pub project: PathBuf,
+    /// Rebar3 profile to pickup (default is test)
+    #[bpaf(long("as"), argument("PROFILE"), fallback("test".to_string()))]
+    pub profile: String,
+    /// Run with rebar
+    pub rebar: bool,
+    /// Also check generated modules
+    pub include_generated: bool,
+    /// Report findings in JSON or SARIF format
+    #[bpaf(
+        argument("FORMAT"),
+        complete(sarif_format_completer),
+        fallback(None),
+        guard(sarif_format_guard, "Please use json or sarif")
+    )]
+    pub format: Option<String>,
+}
+
+#[derive(Clone, Debug, Bpaf)]
+pub struct Glean {
+    /// Path to directory with project (defaults to `.`)
+    #[bpaf(argument("PROJECT"), fallback(PathBuf::from(".")))]
+    pub project: PathBuf,
+    /// Rebar3 profile to pickup (default is test)
+    #[bpaf(long("as"), argument("PROFILE"), fallback("test".to_string()))]
+    pub profile: String,
+    /// Run with rebar
+    pub rebar: bool,
+    /// Path to a file where to write the facts, instead of stdout
+    #[bpaf(argument("TO"))]
+    pub to: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Bpaf)]
+pub struct Format {
+    /// Path to directory with project (defaults to `.`)
+    #[bpaf(argument("PROJECT"), fallback(PathBuf::from(".")))]
+    pub project: PathBuf,
+    /// Rebar3 profile to pickup (default is test)
+    #[bpaf(long("as"), argument("PROFILE"), fallback("test".to_string()))]
+    pub profile: String,
+    /// Run with rebar
+    pub rebar: bool,
+    /// Only format the given module, instead of the whole project
+    #[bpaf(argument("MODULE"), complete(module_completer), optional)]
+    pub module: Option<String>,
+    /// Modify the original files, rather than printing a diff
+    pub in_place: bool,
+    /// List the files which are not formatted, and fail if there are any
+    pub check: bool,
+}
+
+#[derive(Clone, Debug, Bpaf)]
+pub struct DeadCode {
+    /// Path to directory with project (defaults to `.`)
+    #[bpaf(argument("PROJECT"), fallback(PathBuf::from(".")))]
+    pub project: PathBuf,
+    /// Rebar3 profile to pickup (default is test)
+    #[bpaf(long("as"), argument("PROFILE"), fallback("test".to_string()))]
+    pub profile: String,
+    /// Run with rebar
+    pub rebar: bool,
+    /// Only report the dead code of the given module
+    #[bpaf(argument("MODULE"), complete(module_completer), optional)]
+    pub module: Option<String>,
+}
+
+#[derive(Clone, Debug, Bpaf)]
+pub struct BugReport {
+    /// Path to directory with project (defaults to `.`)
+    #[bpaf(argument("PROJECT"), fallback(PathBuf::from(".")))]
+    pub project: PathBuf,
+    /// Rebar3 profile to pickup (default is test)
+    #[bpaf(long("as"), argument("PROFILE"), fallback("test".to_string()))]
+    pub profile: String,
+    /// Run with rebar
+    pub rebar: bool,
+    /// Module the failing command was run on. Only the files touched
+    /// when analysing it are included, instead of the whole project
+    #[bpaf(argument("MODULE"), complete(module_completer), optional)]
+    pub module: Option<String>,
+    /// The failing command, to record in the bundle
+    #[bpaf(argument("COMMAND"), optional)]
+    pub command: Option<String>,
+    /// Replace the names, strings and comments of the included files, and their paths, by placeholders, and leave out the config files
+    pub anonymize: bool,
+    /// Path of the tarball to write (defaults to `elp-bug-report.tar.gz`)
+    #[bpaf(argument("TO"), fallback(PathBuf::from("elp-bug-report.tar.gz")))]
+    pub to: PathBuf,
+}
+
+#[derive(Clone, Debug, Bpaf)]
+pub struct Includes {
+    /// Path to directory with project (defaults to `.`)
+    #[bpaf(argument("PROJECT"), fallback(PathBuf::from(".")))]
+    pub project: PathBuf,
+    /// Rebar3 profile to pickup (default is test)
+    #[bpaf(long("as"), argument("PROFILE"), fallback("test".to_string()))]
+    pub profile: String,
+    /// Run with rebar
+    pub rebar: bool,
+    /// Print the graph in DOT or JSON format, instead of the include cycles and most included headers
+    #[bpaf(
+        argument("FORMAT"),
+        complete(includes_format_completer),
+        fallback(None),
+        guard(includes_format_guard, "Please use dot or json")
+    )]
+    pub format: Option<String>,
+}
+
+#[derive(Clone, Debug, Bpaf)]
+pub struct Explain {
+    /// Diagnostic code or label, e.g. W0007 or trivial_match
+    #[bpaf(positional::< String > ("CODE"))]
+    pub code: String,
+}
+
+#[derive(Clone, Debug, Bpaf)]
+pub struct Expand {
+    /// Path to directory with project (defaults to `.`)
+    #[bpaf(argument("PROJECT"), fallback(PathBuf::from(".")))]
+    pub project: PathBuf,
+    /// Rebar3 profile to pickup (default is test)
+    #[bpaf(long("as"), argument("PROFILE"), fallback("test".to_string()))]
+    pub profile: String,
+    /// Run with rebar
+    pub rebar: bool,
+    /// Expand the macro at the position and the macros it uses (recursive, the default), only the macro (single-step), or all the macros of the function there (function)
+    #[bpaf(
+        argument("MODE"),
+        complete(expand_mode_completer),
+        fallback(None),
+        guard(expand_mode_guard, "Please use recursive, single-step or function")
+    )]
+    pub mode: Option<String>,
+    /// Position of the macro or function, with 1-based line and column
+    #[bpaf(positional::< String > ("FILE:LINE:COL"))]
+    pub at: String,
+}
+
+#[derive(Clone, Debug, Bpaf)]
+pub struct InMemory {
+    /// Tarball of the project files to load, instead of a project description read from stdin
+    #[bpaf(argument("TARBALL"))]
+    pub tarball: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Bpaf)]
+pub struct CacheClear {
+    /// Path to the root directory of the project (defaults to `.`)
+    #[bpaf(argument("PROJECT"), fallback(PathBuf::from(".")))]
+    pub project: PathBuf,
+}
+
 #[derive(Clone, Debug)]
 pub enum Command {
     ParseAllElp(ParseAllElp),
@@ -264,6 +505,18 @@ pub enum Command {
     Lint(Lint),
     Version(Version),
     Shell(Shell),
+    ShardTests(ShardTests),
+    ApplyAssist(ApplyAssist),
+    Ci(Ci),
+    Glean(Glean),
+    Format(Format),
+    BugReport(BugReport),
+    DeadCode(DeadCode),
+    CacheClear(CacheClear),
+    Includes(Includes),
+    Explain(Explain),
+    Expand(Expand),
+    InMemory(InMemory),
     Help(),
 }
 
@@ -361,6 +614,81 @@ pub fn command() -> impl Parser<Command> {
         .command("shell")
         .help("Starts an interactive ELP shell");
 
+    let shard_tests = shard_tests()
+        .map(Command::ShardTests)
+        .to_options()
+        .command("shard-tests")
+        .help("Split the common test cases in a project into balanced shards");
+
+    let apply_assist = apply_assist()
+        .map(Command::ApplyAssist)
+        .to_options()
+        .command("apply-assist")
+        .help("Apply an assist at the given positions across the project");
+
+    let ci = ci()
+        .map(Command::Ci)
+        .to_options()
+        .command("ci")
+        .help("Run the lint, eqwalizer and xref checks configured in .elp.toml in one go");
+
+    let glean = glean()
+        .map(Command::Glean)
+        .to_options()
+        .command("glean")
+        .help("Export definitions, references, calls and includes of a project as JSON facts");
+
+    let format = format()
+        .map(Command::Format)
+        .to_options()
+        .command("format")
+        .help("Format the Erlang files of a project");
+
+    let bug_report = bug_report()
+        .map(Command::BugReport)
+        .to_options()
+        .command("bug-report")
+        .help("Package the files, config and version needed to reproduce a bug into a tarball");
+
+    let dead_code = dead_code()
+        .map(Command::DeadCode)
+        .to_options()
+        .command("dead-code")
+        .help("Report functions unreachable from exports and callbacks, and unused records and macros");
+
+    let includes = includes()
+        .map(Command::Includes)
+        .to_options()
+        .command("includes")
+        .help("Print which files include which headers, and the include cycles");
+
+    let cache_clear = cache_clear()
+        .map(Command::CacheClear)
+        .to_options()
+        .command("clear")
+        .help("Remove the cache of the project")
+        .to_options()
+        .command("cache")
+        .help("Manage the parse and eqWAlizer results cached in .elp/cache across runs");
+
+    let explain = explain()
+        .map(Command::Explain)
+        .to_options()
+        .command("explain")
+        .help("Print the long-form explanation of a diagnostic code");
+
+    let expand = expand()
+        .map(Command::Expand)
+        .to_options()
+        .command("expand")
+        .help("Print the expansion of the macro at a position, or of the macros of a function");
+
+    let in_memory = in_memory()
+        .map(Command::InMemory)
+        .to_options()
+        .command("in-memory")
+        .help("Report the diagnostics of a project described on stdin or given as a tarball, loaded in memory");
+
     construct!([
         eqwalize,
         eqwalize_all,
@@ -376,6 +704,18 @@ pub fn command() -> impl Parser<Command> {
         version,
         shell,
         eqwalize_stats,
+        shard_tests,
+        apply_assist,
+        ci,
+        glean,
+        format,
+        bug_report,
+        dead_code,
+        cache_clear,
+        includes,
+        explain,
+        expand,
+        in_memory,
     ])
     .fallback(Help())
 }
@@ -441,6 +781,43 @@ fn format_guard(format: &Option<String>) -> bool {
     }
 }
 
+fn sarif_format_completer(_: &Option<String>) -> Vec<(String, Option<String>)> {
+    vec![("json".to_string(), None), ("sarif".to_string(), None)]
+}
+
+fn sarif_format_guard(format: &Option<String>) -> bool {
+    match format {
+        None => true,
+        Some(f) => f == "json" || f == "sarif",
+    }
+}
+
+fn includes_format_completer(_: &Option<String>) -> Vec<(String, Option<String>)> {
+    vec![("dot".to_string(), None), ("json".to_string(), None)]
+}
+
+fn includes_format_guard(format: &Option<String>) -> bool {
+    match format {
+        None => true,
+        Some(f) => f == "dot" || f == "json",
+    }
+}
+
+fn expand_mode_completer(_: &Option<String>) -> Vec<(String, Option<String>)> {
+    vec![
+        ("recursive".to_string(), None),
+        ("single-step".to_string(), None),
+        ("function".to_string(), None),
+    ]
+}
+
+fn expand_mode_guard(mode: &Option<String>) -> bool {
+    match mode {
+        None => true,
+        Some(m) => m == "recursive" || m == "single-step" || m == "function",
+    }
+}
+
 fn shell_completer(shell: &String) -> Vec<(String, Option<String>)> {
     let completions = match shell.to_lowercase().chars().next() {
         Some('b') => vec!["bash"],
@@ -506,6 +883,12 @@ pub fn gen_completions(shell: &str) -> String {
     format!("elp --bpaf-complete-style-{}", shell)
 }
 
+impl EqwalizeAll {
+    pub fn is_format_sarif(&self) -> bool {
+        self.format == Some("sarif".to_string())
+    }
+}
+
 impl Lint {
     pub fn is_format_normal(&self) -> bool {
         self.format.is_none()
@@ -514,4 +897,34 @@ impl Lint {
     pub fn is_format_json(&self) -> bool {
         self.format == Some("json".to_string())
     }
+
+    pub fn is_format_sarif(&self) -> bool {
+        self.format == Some("sarif".to_string())
+    }
+}
+
+impl Ci {
+    pub fn is_format_json(&self) -> bool {
+        self.format == Some("json".to_string())
+    }
+
+    pub fn is_format_sarif(&self) -> bool {
+        self.format == Some("sarif".to_string())
+    }
+}
+
+impl Includes {
+    pub fn is_format_json(&self) -> bool {
+        self.format == Some("json".to_string())
+    }
+
+    pub fn is_format_dot(&self) -> bool {
+        self.format == Some("dot".to_string())
+    }
+}
+
+impl ShardTests {
+    pub fn is_format_json(&self) -> bool {
+        self.format == Some("json".to_string())
+    }
 }
@@ -0,0 +1,599 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Package a reproduction bundle for a bug report: the files needed to
+//! reproduce a problem, a description of the project, its config, the
+//! ELP version and the failing command, as a tarball that can be
+//! attached to an issue.
+//!
+//! When a module is given, only the files touched when analysing it are
+//! included: the module, the files it includes, and the files defining
+//! what it refers to. OTP files are never included.
+//!
+//! With `--anonymize`, the names, strings and comments of the files, the
+//! names of the files and directories, and those in the description of
+//! the project, are replaced by placeholders. The config files are left
+//! out, as they name apps and modules.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use elp::build::load;
+use elp::build::types::LoadResult;
+use elp::cli::Cli;
+use elp_ide::elp_ide_db::elp_base_db::AbsPathBuf;
+use elp_ide::elp_ide_db::elp_base_db::FileId;
+use elp_ide::elp_ide_db::elp_base_db::IncludeOtp;
+use elp_ide::Analysis;
+use elp_project_model::AppType;
+use elp_project_model::DiscoverConfig;
+use elp_project_model::ProjectBuildData;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use elp_syntax::SourceFile;
+use elp_syntax::SyntaxKind;
+use elp_syntax::SyntaxToken;
+use fxhash::FxHashMap;
+use fxhash::FxHashSet;
+use serde_json::json;
+use text_edit::TextEdit;
+
+use crate::args::BugReport;
+use crate::reporting;
+
+const BUNDLE_DIR: &str = "elp-bug-report";
+const CONFIG_FILES: &[&str] = &[".elp.toml", ".elp_lint.toml", "rebar.config"];
+/// Atoms too common to be worth hiding, and needed to make sense of the code.
+const KEPT_ATOMS: &[&str] = &["true", "false", "ok", "error", "undefined"];
+/// Directories named by the build tools rather than by the project.
+const KEPT_DIRS: &[&str] = &[
+    "external", "apps", "src", "include", "test", "priv", "ebin", "_build", "default", "lib",
+];
+
+pub fn bug_report(args: &BugReport, cli: &mut dyn Cli) -> Result<()> {
+    let config = DiscoverConfig::new(args.rebar, &args.profile);
+    let loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::Yes)?;
+    let analysis = loaded.analysis();
+
+    let files = touched_files(&loaded, &analysis, args.module.as_deref())?;
+
+    let tmp = tempfile::tempdir()?;
+    let bundle = tmp.path().join(BUNDLE_DIR);
+    fs::create_dir_all(&bundle)?;
+
+    let mut anonymizer = if args.anonymize {
+        Some(Anonymizer::new(&loaded, &analysis, &files)?)
+    } else {
+        None
+    };
+    for (path, file_id) in &files {
+        let text = analysis.file_text(*file_id)?;
+        let (path, text) = match &mut anonymizer {
+            Some(anonymizer) => {
+                // The module is renamed first, and its file after it
+                let text = anonymizer.anonymize(&text);
+                (anonymizer.anonymize_path(path), text)
+            }
+            None => (path.clone(), text.to_string()),
+        };
+        let target = bundle.join("files").join(path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(target, text)?;
+    }
+
+    let root = loaded.project.root();
+    let root: &Path = (*root).as_ref();
+    for name in CONFIG_FILES {
+        let path = root.join(name);
+        if path.exists() {
+            if anonymizer.is_some() {
+                writeln!(cli, "Leaving out {name}, as it is not anonymized")?;
+            } else {
+                fs::copy(&path, bundle.join(name))?;
+            }
+        }
+    }
+    fs::write(
+        bundle.join("version.txt"),
+        format!("elp {}\n", elp::version()),
+    )?;
+    if let Some(command) = &args.command {
+        fs::write(bundle.join("command.txt"), format!("{command}\n"))?;
+    }
+    fs::write(
+        bundle.join("manifest.json"),
+        serde_json::to_string_pretty(&manifest(&loaded, anonymizer.as_mut()))?,
+    )?;
+
+    let to = env::current_dir()?.join(&args.to);
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(&to)
+        .arg("-C")
+        .arg(tmp.path())
+        .arg(BUNDLE_DIR)
+        .status()
+        .context("could not run tar")?;
+    if !status.success() {
+        bail!("tar failed with {}", status);
+    }
+    writeln!(
+        cli,
+        "Wrote {} with {} files",
+        args.to.display(),
+        files.len()
+    )?;
+    Ok(())
+}
+
+/// The files to include in the bundle, by their path relative to the
+/// project root.
+fn touched_files(
+    loaded: &LoadResult,
+    analysis: &Analysis,
+    module: Option<&str>,
+) -> Result<BTreeMap<PathBuf, FileId>> {
+    let mut files = FxHashSet::default();
+    match module {
+        Some(module) => {
+            let file_id = match analysis.module_file_id(loaded.project_id, module)? {
+                Some(file_id) => file_id,
+                None => bail!("module {} not found", module),
+            };
+            // Includes are followed transitively, definitions referred
+            // to only need their own file.
+            let mut pending = vec![file_id];
+            while let Some(file_id) = pending.pop() {
+                if !files.insert(file_id) {
+                    continue;
+                }
+                let index = analysis.code_index(file_id)?;
+                pending.extend(index.includes);
+                files.extend(index.refs.iter().map(|r| r.target.file_id));
+            }
+        }
+        None => {
+            let module_index = analysis.module_index(loaded.project_id)?;
+            files.extend(module_index.iter_own().map(|(_, _, file_id)| file_id));
+        }
+    }
+
+    let mut res = BTreeMap::new();
+    for file_id in files {
+        if analysis.file_app_type(file_id)? == Some(AppType::Otp) {
+            continue;
+        }
+        res.insert(relative_path(loaded, file_id), file_id);
+    }
+    Ok(res)
+}
+
+fn relative_path(loaded: &LoadResult, file_id: FileId) -> PathBuf {
+    let root = loaded.project.root();
+    let vfs_path = loaded.vfs.file_path(file_id);
+    let path = reporting::get_relative_path(&root, &vfs_path);
+    if path.is_absolute() {
+        // Outside of the project, e.g. a dependency
+        Path::new("external").join(path.strip_prefix("/").unwrap_or(path))
+    } else {
+        path.to_path_buf()
+    }
+}
+
+fn manifest(loaded: &LoadResult, mut anonymizer: Option<&mut Anonymizer>) -> serde_json::Value {
+    let root = loaded.project.root();
+    let root: &Path = (*root).as_ref();
+    let build_system = match &loaded.project.project_build_data {
+        ProjectBuildData::Otp => "otp",
+        ProjectBuildData::Rebar(_) => "rebar",
+        ProjectBuildData::Buck(_) => "buck",
+    };
+    let mut relative = |path: &AbsPathBuf| {
+        let path: &Path = path.as_ref();
+        let path = path.strip_prefix(root).unwrap_or(path);
+        let path = match &mut anonymizer {
+            Some(anonymizer) => anonymizer.anonymize_dir_path(path),
+            None => path.to_path_buf(),
+        };
+        path.display().to_string()
+    };
+    let mut apps = Vec::new();
+    for app in loaded.project.all_apps() {
+        if app.app_type == AppType::Otp {
+            continue;
+        }
+        let src_dirs: Vec<_> = app.abs_src_dirs.iter().map(&mut relative).collect();
+        let include_dirs: Vec<_> = app.include_dirs.iter().map(&mut relative).collect();
+        let dir = relative(&app.dir);
+        apps.push((app, dir, src_dirs, include_dirs));
+    }
+    let otp_lib_dir = relative(&loaded.project.otp.lib_dir);
+    let apps = apps
+        .into_iter()
+        .map(|(app, dir, src_dirs, include_dirs)| {
+            // Apps are named after their directory, so that
+            // `-include_lib` paths still match up
+            let name = match &mut anonymizer {
+                Some(anonymizer) => anonymizer.anonymize_dir(app.name.as_str()),
+                None => app.name.as_str().to_string(),
+            };
+            json!({
+                "name": name,
+                "type": format!("{:?}", app.app_type),
+                "dir": dir,
+                "src_dirs": src_dirs,
+                "include_dirs": include_dirs,
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "build_system": build_system,
+        "otp_lib_dir": otp_lib_dir,
+        "apps": apps,
+    })
+}
+
+/// Replaces atoms, variables, strings and comments by placeholders,
+/// consistently across all the files of the bundle, so that module and
+/// function names still match up. Names of modules outside the bundle,
+/// of functions and types not defined in it (such as BIFs and builtin
+/// types), and of macros are kept. Files and directories are renamed
+/// too, and the paths of included files along with them.
+struct Anonymizer {
+    kept: FxHashSet<String>,
+    defined: FxHashSet<String>,
+    /// Directories named after OTP apps, or by the build tools
+    kept_dirs: FxHashSet<String>,
+    /// The new file names of the headers of the bundle
+    headers: FxHashMap<String, String>,
+    atoms: FxHashMap<String, String>,
+    vars: FxHashMap<String, String>,
+    strings: FxHashMap<String, String>,
+    dirs: FxHashMap<String, String>,
+}
+
+impl Anonymizer {
+    fn new(
+        loaded: &LoadResult,
+        analysis: &Analysis,
+        files: &BTreeMap<PathBuf, FileId>,
+    ) -> Result<Anonymizer> {
+        let bundled: FxHashSet<FileId> = files.values().copied().collect();
+        let mut kept: FxHashSet<String> = KEPT_ATOMS.iter().map(|a| a.to_string()).collect();
+        let module_index = analysis.module_index(loaded.project_id)?;
+        for (name, _source, file_id) in module_index.iter_own() {
+            if !bundled.contains(&file_id) {
+                kept.insert(name.as_str().to_string());
+            }
+        }
+        let mut defined = FxHashSet::default();
+        for file_id in &bundled {
+            let def_map = analysis.def_map(*file_id)?;
+            defined.extend(
+                def_map
+                    .get_functions()
+                    .keys()
+                    .chain(def_map.get_types().keys())
+                    .map(|name| name.name().as_str().to_string()),
+            );
+        }
+        let otp_apps = loaded
+            .project
+            .all_apps()
+            .into_iter()
+            .filter(|app| app.app_type == AppType::Otp)
+            .map(|app| app.name.as_str().to_string());
+        Ok(Anonymizer::with_names(
+            kept,
+            defined,
+            otp_apps.collect(),
+            files.keys(),
+        ))
+    }
+
+    fn with_names<'a>(
+        kept: FxHashSet<String>,
+        defined: FxHashSet<String>,
+        otp_apps: FxHashSet<String>,
+        paths: impl Iterator<Item = &'a PathBuf>,
+    ) -> Anonymizer {
+        let mut headers = FxHashMap::default();
+        for path in paths {
+            if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+                if name.ends_with(".hrl") {
+                    let idx = headers.len();
+                    headers
+                        .entry(name.to_string())
+                        .or_insert_with(|| format!("header_{idx}.hrl"));
+                }
+            }
+        }
+        let mut kept_dirs = otp_apps;
+        kept_dirs.extend(KEPT_DIRS.iter().map(|dir| dir.to_string()));
+        Anonymizer {
+            kept,
+            defined,
+            kept_dirs,
+            headers,
+            atoms: FxHashMap::default(),
+            vars: FxHashMap::default(),
+            strings: FxHashMap::default(),
+            dirs: FxHashMap::default(),
+        }
+    }
+
+    fn anonymize(&mut self, text: &str) -> String {
+        let parse = SourceFile::parse_text(text);
+        let mut builder = TextEdit::builder();
+        for token in parse
+            .tree()
+            .syntax()
+            .descendants_with_tokens()
+            .filter_map(|n| n.into_token())
+        {
+            let replacement = match token.kind() {
+                SyntaxKind::ATOM => self.anonymize_atom(&token),
+                SyntaxKind::VAR => self.anonymize_var(&token),
+                SyntaxKind::STRING => self.anonymize_string(&token),
+                SyntaxKind::COMMENT => anonymize_comment(token.text()),
+                _ => None,
+            };
+            if let Some(replacement) = replacement {
+                builder.replace(token.text_range(), replacement);
+            }
+        }
+        let mut text = text.to_string();
+        builder.finish().apply(&mut text);
+        text
+    }
+
+    /// Module files are renamed after their anonymized module, and
+    /// headers after the names given to them.
+    fn anonymize_path(&mut self, path: &Path) -> PathBuf {
+        let name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name,
+            None => return self.anonymize_dir_path(path),
+        };
+        let name = match (self.headers.get(name), name.strip_suffix(".erl")) {
+            (Some(header), _) => header.clone(),
+            (None, Some(module)) => match self.atoms.get(module) {
+                Some(atom) => format!("{atom}.erl"),
+                None => name.to_string(),
+            },
+            (None, None) => name.to_string(),
+        };
+        let dir = path.parent().unwrap_or(Path::new(""));
+        self.anonymize_dir_path(dir).join(name)
+    }
+
+    fn anonymize_dir_path(&mut self, path: &Path) -> PathBuf {
+        path.components()
+            .map(|component| match component {
+                Component::Normal(name) => {
+                    PathBuf::from(self.anonymize_dir(&name.to_string_lossy()))
+                }
+                other => PathBuf::from(other.as_os_str()),
+            })
+            .collect()
+    }
+
+    fn anonymize_dir(&mut self, name: &str) -> String {
+        if self.kept_dirs.contains(name) {
+            return name.to_string();
+        }
+        let idx = self.dirs.len();
+        self.dirs
+            .entry(name.to_string())
+            .or_insert_with(|| format!("dir_{idx}"))
+            .clone()
+    }
+
+    fn anonymize_atom(&mut self, token: &SyntaxToken) -> Option<String> {
+        let name = token.text().trim_matches('\'');
+        if self.kept.contains(name) {
+            return None;
+        }
+        // The function of a remote call to a module we keep
+        if let Some(colon) = prev_non_trivia(token) {
+            if colon.kind() == SyntaxKind::ANON_COLON {
+                if let Some(module) = prev_non_trivia(&colon) {
+                    if self.kept.contains(module.text().trim_matches('\'')) {
+                        return None;
+                    }
+                }
+            }
+        }
+        // A call or type not defined in the bundle, e.g. a BIF
+        if let Some(next) = next_non_trivia(token) {
+            if next.kind() == SyntaxKind::ANON_LPAREN && !self.defined.contains(name) {
+                return None;
+            }
+        }
+        let idx = self.atoms.len();
+        Some(
+            self.atoms
+                .entry(name.to_string())
+                .or_insert_with(|| format!("atom_{idx}"))
+                .clone(),
+        )
+    }
+
+    fn anonymize_var(&mut self, token: &SyntaxToken) -> Option<String> {
+        let name = token.text();
+        if name == "_" {
+            return None;
+        }
+        // Macro names, e.g. `?MODULE` or `-ifdef(TEST)`, may be defined
+        // outside of the bundle
+        let is_macro = token
+            .parent()
+            .and_then(|var| var.parent())
+            .map_or(false, |parent| {
+                matches!(
+                    parent.kind(),
+                    SyntaxKind::MACRO_CALL_EXPR
+                        | SyntaxKind::MACRO_LHS
+                        | SyntaxKind::PP_IFDEF
+                        | SyntaxKind::PP_IFNDEF
+                        | SyntaxKind::PP_UNDEF
+                )
+            });
+        if is_macro {
+            return None;
+        }
+        // Variables starting with `_` are not reported when unused
+        let prefix = if name.starts_with('_') { "_" } else { "" };
+        let idx = self.vars.len();
+        Some(
+            self.vars
+                .entry(name.to_string())
+                .or_insert_with(|| format!("{prefix}Var{idx}"))
+                .clone(),
+        )
+    }
+
+    fn anonymize_string(&mut self, token: &SyntaxToken) -> Option<String> {
+        let is_include = token.parent_ancestors().any(|node| {
+            ast::PpInclude::can_cast(node.kind()) || ast::PpIncludeLib::can_cast(node.kind())
+        });
+        if is_include {
+            let path = token.text().trim_matches('"');
+            let path = self.anonymize_path(Path::new(path));
+            return Some(format!("\"{}\"", path.display()));
+        }
+        let idx = self.strings.len();
+        Some(
+            self.strings
+                .entry(token.text().to_string())
+                .or_insert_with(|| format!("\"string_{idx}\""))
+                .clone(),
+        )
+    }
+}
+
+/// Only the `%` of a comment are kept, unless it changes what is
+/// reported, e.g. `% elp:ignore`.
+fn anonymize_comment(text: &str) -> Option<String> {
+    let body = text.trim_start_matches('%');
+    let directive = body.trim_start();
+    if directive.starts_with("elp:") || directive.starts_with("eqwalizer:") {
+        return None;
+    }
+    Some(text[..text.len() - body.len()].to_string())
+}
+
+fn prev_non_trivia(token: &SyntaxToken) -> Option<SyntaxToken> {
+    let mut token = token.prev_token()?;
+    while token.kind().is_trivia() {
+        token = token.prev_token()?;
+    }
+    Some(token)
+}
+
+fn next_non_trivia(token: &SyntaxToken) -> Option<SyntaxToken> {
+    let mut token = token.next_token()?;
+    while token.kind().is_trivia() {
+        token = token.next_token()?;
+    }
+    Some(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+
+    fn anonymizer(kept: &[&str], defined: &[&str], paths: &[&str]) -> Anonymizer {
+        let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+        Anonymizer::with_names(
+            kept.iter().map(|name| name.to_string()).collect(),
+            defined.iter().map(|name| name.to_string()).collect(),
+            ["kernel".to_string()].into_iter().collect(),
+            paths.iter(),
+        )
+    }
+
+    #[test]
+    fn anonymize_module() {
+        let mut anonymizer = anonymizer(
+            &["ok", "other_mod"],
+            &["secret_fun"],
+            &["apps/my_app/include/secret.hrl"],
+        );
+        let text = anonymizer.anonymize(
+            r#"%% Copyright Secret Corp
+-module(secret_mod).
+-include("secret.hrl").
+-include_lib("my_app/include/secret.hrl").
+-include_lib("kernel/include/logger.hrl").
+-ifdef(TEST).
+-define(NAME(Arg), {name, Arg}).
+-endif.
+secret_fun(Secret, _Unused) ->
+    % elp:ignore W0017
+    other_mod:call(Secret, "password", ?NAME(Secret), ?MODULE),
+    length([Secret]),
+    ok.
+"#,
+        );
+        expect![[r#"
+            %%
+            -module(atom_0).
+            -include("header_0.hrl").
+            -include_lib("dir_0/include/header_0.hrl").
+            -include_lib("kernel/include/logger.hrl").
+            -ifdef(TEST).
+            -define(NAME(Var0), {atom_1, Var0}).
+            -endif.
+            atom_2(Var1, _Var2) ->
+                % elp:ignore W0017
+                other_mod:call(Var1, "string_0", ?NAME(Var1), ?MODULE),
+                length([Var1]),
+                ok.
+        "#]]
+        .assert_eq(&text);
+    }
+
+    #[test]
+    fn anonymize_paths() {
+        let mut anonymizer = anonymizer(
+            &[],
+            &[],
+            &[
+                "apps/my_app/include/secret.hrl",
+                "apps/my_app/src/secret_mod.erl",
+            ],
+        );
+        anonymizer.anonymize("-module(secret_mod).");
+        assert_eq!(
+            anonymizer.anonymize_path(Path::new("apps/my_app/src/secret_mod.erl")),
+            PathBuf::from("apps/dir_0/src/atom_0.erl")
+        );
+        assert_eq!(
+            anonymizer.anonymize_path(Path::new("apps/my_app/include/secret.hrl")),
+            PathBuf::from("apps/dir_0/include/header_0.hrl")
+        );
+        assert_eq!(
+            anonymizer.anonymize_dir_path(Path::new("external/home/alice/kernel")),
+            PathBuf::from("external/dir_1/dir_2/kernel")
+        );
+        assert_eq!(anonymizer.anonymize_dir("my_app"), "dir_0");
+    }
+}
@@ -0,0 +1,31 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `elp cache clear`: remove the results kept on disk across runs for a
+//! project: the parse results of the Erlang service and the eqWAlizer
+//! results.
+
+use std::fs;
+
+use anyhow::Result;
+use elp::build::load;
+use elp::cli::Cli;
+
+use crate::args::CacheClear;
+
+pub fn cache_clear(args: &CacheClear, cli: &mut dyn Cli) -> Result<()> {
+    let dir = fs::canonicalize(&args.project)?.join(load::CACHE_DIR);
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+        writeln!(cli, "Removed {}", dir.display())?;
+    } else {
+        writeln!(cli, "No cache in {}", dir.display())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,356 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `elp ci`: run the checks configured in `.elp.toml` on a single
+//! project load, so that everything computed by one check (parse trees,
+//! def maps, ...) is reused by the others, and merge their findings in
+//! a single report.
+
+use std::io::Write;
+
+use anyhow::bail;
+use anyhow::Result;
+use elp::build;
+use elp::build::load;
+use elp::build::types::LoadResult;
+use elp::cli::Cli;
+use elp::otp_file_to_ignore;
+use elp_ide::diagnostics::Diagnostic;
+use elp_ide::diagnostics::DiagnosticsConfig;
+use elp_ide::diagnostics::Severity;
+use elp_ide::elp_ide_db::elp_base_db::FileId;
+use elp_ide::elp_ide_db::elp_base_db::IncludeOtp;
+use elp_ide::elp_ide_db::EqwalizerDiagnostics;
+use elp_ide::elp_ide_db::LineIndex;
+use elp_ide::Analysis;
+use elp_ide::Cancellable;
+use elp_project_model::buck::CiCheck;
+use elp_project_model::AppType;
+use elp_project_model::DiscoverConfig;
+use elp_syntax::TextRange;
+use indicatif::ParallelProgressIterator;
+use indicatif::ProgressBar;
+use itertools::Itertools;
+use rayon::prelude::*;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::args::Ci;
+use crate::eqwalizer_cli;
+use crate::reporting;
+use crate::sarif;
+use crate::sarif::Region;
+use crate::sarif::SarifLevel;
+use crate::sarif::SarifResult;
+
+#[derive(Debug, Clone, Serialize)]
+struct Finding {
+    check: CiCheck,
+    /// Relative to the project root
+    path: String,
+    /// One-based
+    line: u32,
+    /// One-based
+    column: u32,
+    end_line: u32,
+    end_column: u32,
+    code: String,
+    severity: FindingSeverity,
+    message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum FindingSeverity {
+    Error,
+    Warning,
+    /// Reported, but never fails the run
+    Note,
+}
+
+impl FindingSeverity {
+    fn as_sarif_level(&self) -> &'static str {
+        match self {
+            FindingSeverity::Error => "error",
+            FindingSeverity::Warning => "warning",
+            FindingSeverity::Note => "note",
+        }
+    }
+}
+
+pub fn run_ci(args: &Ci, cli: &mut dyn Cli) -> Result<()> {
+    let config = DiscoverConfig::new(args.rebar, &args.profile);
+    let mut loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::Yes)?;
+    let ci_config = loaded.project.ci_config()?;
+    if ci_config.checks.contains(&CiCheck::Eqwalizer) {
+        build::compile_deps(&mut loaded, cli)?;
+    }
+    let analysis = loaded.analysis();
+
+    let module_index = analysis.module_index(loaded.project_id)?;
+    let file_ids: Vec<FileId> = module_index
+        .iter_own()
+        .map(|(_name, _source, file_id)| file_id)
+        .filter(|&file_id| {
+            !otp_file_to_ignore(&analysis, file_id)
+                && analysis.file_app_type(file_id).ok() != Some(Some(AppType::Dep))
+        })
+        .collect();
+
+    let mut findings = Vec::new();
+    for check in ci_config.checks.iter().unique() {
+        match check {
+            CiCheck::Lint => {
+                let pb = cli.progress(file_ids.len() as u64, "Linting");
+                let config = DiagnosticsConfig::default();
+                let include_generated = args.include_generated;
+                findings.extend(per_file_findings(
+                    &loaded,
+                    &file_ids,
+                    pb,
+                    CiCheck::Lint,
+                    |analysis, file_id| analysis.diagnostics(&config, file_id, include_generated),
+                )?);
+            }
+            CiCheck::Xref => {
+                let pb = cli.progress(file_ids.len() as u64, "Checking calls");
+                findings.extend(per_file_findings(
+                    &loaded,
+                    &file_ids,
+                    pb,
+                    CiCheck::Xref,
+                    |analysis, file_id| analysis.xref_diagnostics(file_id),
+                )?);
+            }
+            CiCheck::Eqwalizer => {
+                let eqwalizer_file_ids: Vec<FileId> = file_ids
+                    .iter()
+                    .copied()
+                    .filter(|&file_id| {
+                        eqwalizer_cli::should_eqwalize(&analysis, file_id, args.include_generated)
+                    })
+                    .collect();
+                let pb = cli.progress(eqwalizer_file_ids.len() as u64, "EqWAlizing");
+                let output =
+                    eqwalizer_cli::eqwalize_files(&loaded, &eqwalizer_file_ids, pb.clone());
+                pb.finish();
+                findings.extend(eqwalizer_findings(&loaded, &analysis, output)?);
+            }
+        }
+    }
+    findings.sort_by(|a, b| {
+        (&a.path, a.line, a.column, a.check).cmp(&(&b.path, b.line, b.column, b.check))
+    });
+
+    if args.is_format_json() {
+        let report = json!({ "findings": findings });
+        cli.write_all(serde_json::to_string(&report)?.as_bytes())?;
+        writeln!(cli)?;
+    } else if args.is_format_sarif() {
+        cli.write_all(serde_json::to_string(&to_sarif(&findings))?.as_bytes())?;
+        writeln!(cli)?;
+    } else {
+        for finding in &findings {
+            writeln!(
+                cli,
+                "{}:{}:{}: [{}] {} ({}): {}",
+                finding.path,
+                finding.line,
+                finding.column,
+                finding.check.as_str(),
+                finding.severity.as_sarif_level(),
+                finding.code,
+                finding.message
+            )?;
+        }
+        for check in ci_config.checks.iter().unique() {
+            let count = findings.iter().filter(|f| f.check == *check).count();
+            writeln!(cli, "{}: {} findings", check.as_str(), count)?;
+        }
+    }
+
+    let failed: Vec<&str> = ci_config
+        .fail_on
+        .iter()
+        .unique()
+        .filter(|check| {
+            findings
+                .iter()
+                .any(|f| f.check == **check && f.severity != FindingSeverity::Note)
+        })
+        .map(|check| check.as_str())
+        .collect();
+    if !failed.is_empty() {
+        bail!("Failed checks: {}", failed.join(", "));
+    }
+    Ok(())
+}
+
+fn per_file_findings(
+    loaded: &LoadResult,
+    file_ids: &[FileId],
+    pb: ProgressBar,
+    check: CiCheck,
+    get_diagnostics: impl Fn(&Analysis, FileId) -> Cancellable<Vec<Diagnostic>> + Sync + Send,
+) -> Result<Vec<Finding>> {
+    let analysis = loaded.analysis();
+    let diagnostics: Vec<(FileId, Vec<Diagnostic>)> = file_ids
+        .par_iter()
+        .progress_with(pb.clone())
+        .map_with(
+            analysis.clone(),
+            |analysis, &file_id| -> Result<(FileId, Vec<Diagnostic>)> {
+                Ok((file_id, get_diagnostics(analysis, file_id)?))
+            },
+        )
+        .collect::<Result<Vec<_>>>()?;
+    pb.finish();
+
+    let mut findings = Vec::new();
+    for (file_id, diagnostics) in diagnostics {
+        if diagnostics.is_empty() {
+            continue;
+        }
+        let line_index = analysis.line_index(file_id)?;
+        let path = relative_path(loaded, file_id);
+        for diag in diagnostics {
+            let severity = match diag.severity {
+                Severity::Error => FindingSeverity::Error,
+                Severity::Warning => FindingSeverity::Warning,
+                Severity::WeakWarning => FindingSeverity::Note,
+            };
+            findings.push(make_finding(
+                check,
+                &path,
+                &line_index,
+                diag.range,
+                diag.code.as_code(),
+                severity,
+                diag.message,
+            ));
+        }
+    }
+    Ok(findings)
+}
+
+fn eqwalizer_findings(
+    loaded: &LoadResult,
+    analysis: &Analysis,
+    output: EqwalizerDiagnostics,
+) -> Result<Vec<Finding>> {
+    match output {
+        EqwalizerDiagnostics::Diagnostics {
+            errors: diagnostics_by_module,
+            ..
+        } => {
+            let module_index = analysis.module_index(loaded.project_id)?;
+            let mut findings = Vec::new();
+            for (module, diagnostics) in diagnostics_by_module {
+                let file_id = match module_index.file_for_module(module.as_str()) {
+                    Some(file_id) => file_id,
+                    None => bail!("module {} not found", module),
+                };
+                let line_index = analysis.line_index(file_id)?;
+                let path = relative_path(loaded, file_id);
+                for diag in diagnostics {
+                    let message = match diag.explanation {
+                        Some(explanation) => format!("{}\n{}", diag.message, explanation),
+                        None => diag.message,
+                    };
+                    findings.push(make_finding(
+                        CiCheck::Eqwalizer,
+                        &path,
+                        &line_index,
+                        diag.range,
+                        diag.code,
+                        FindingSeverity::Error,
+                        message,
+                    ));
+                }
+            }
+            Ok(findings)
+        }
+        EqwalizerDiagnostics::NoAst { module } => {
+            bail!(
+                "Could not type-check because module {} has parse errors",
+                module
+            )
+        }
+        EqwalizerDiagnostics::Error(error) => bail!("Could not eqwalize: {}", error),
+    }
+}
+
+fn make_finding(
+    check: CiCheck,
+    path: &str,
+    line_index: &LineIndex,
+    range: TextRange,
+    code: String,
+    severity: FindingSeverity,
+    message: String,
+) -> Finding {
+    let start = line_index.line_col(range.start());
+    let end = line_index.line_col(range.end());
+    Finding {
+        check,
+        path: path.to_string(),
+        line: start.line + 1,
+        column: start.col_utf16 + 1,
+        end_line: end.line + 1,
+        end_column: end.col_utf16 + 1,
+        code,
+        severity,
+        message,
+    }
+}
+
+fn relative_path(loaded: &LoadResult, file_id: FileId) -> String {
+    let root = loaded.project.root();
+    let vfs_path = loaded.vfs.file_path(file_id);
+    reporting::get_relative_path(&root, &vfs_path)
+        .display()
+        .to_string()
+}
+
+/// A SARIF 2.1.0 log, with one run per check
+fn to_sarif(findings: &[Finding]) -> serde_json::Value {
+    let runs = findings
+        .iter()
+        .into_group_map_by(|finding| finding.check)
+        .into_iter()
+        .sorted_by_key(|(check, _)| *check)
+        .map(|(check, findings)| {
+            let results: Vec<SarifResult> = findings
+                .into_iter()
+                .map(|finding| {
+                    let level = match finding.severity {
+                        FindingSeverity::Error => SarifLevel::Error,
+                        FindingSeverity::Warning => SarifLevel::Warning,
+                        FindingSeverity::Note => SarifLevel::Note,
+                    };
+                    let region = Region {
+                        start_line: finding.line,
+                        start_column: Some(finding.column),
+                        end_line: Some(finding.end_line),
+                        end_column: Some(finding.end_column),
+                    };
+                    SarifResult::new(
+                        finding.code.clone(),
+                        level,
+                        finding.message.clone(),
+                        finding.path.clone(),
+                        region,
+                    )
+                })
+                .collect();
+            sarif::sarif_run(&format!("elp {}", check.as_str()), &results)
+        })
+        .collect();
+    sarif::sarif_log(runs)
+}
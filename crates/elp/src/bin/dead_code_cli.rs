@@ -0,0 +1,64 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `elp dead-code`: report the private functions not reachable from
+//! the exported functions and behaviour callbacks of their module, and
+//! the records and macros used by no reachable code.
+
+use anyhow::bail;
+use anyhow::Result;
+use elp::build::load;
+use elp::cli::Cli;
+use elp_ide::elp_ide_db::elp_base_db::IncludeOtp;
+use elp_ide::DeadCodeKind;
+use elp_project_model::DiscoverConfig;
+
+use crate::args::DeadCode;
+use crate::reporting;
+
+pub fn dead_code(args: &DeadCode, cli: &mut dyn Cli) -> Result<()> {
+    let config = DiscoverConfig::new(args.rebar, &args.profile);
+    let loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::No)?;
+    let analysis = loaded.analysis();
+
+    let module_file_id = match &args.module {
+        Some(module) => match analysis.module_file_id(loaded.project_id, module)? {
+            Some(file_id) => Some(file_id),
+            None => bail!("module {} not found", module),
+        },
+        None => None,
+    };
+
+    let root = loaded.project.root();
+    for dead in analysis.dead_code(loaded.project_id)? {
+        if module_file_id.map_or(false, |file_id| file_id != dead.file_id) {
+            continue;
+        }
+        let vfs_path = loaded.vfs.file_path(dead.file_id);
+        let path = reporting::get_relative_path(&root, &vfs_path);
+        let start = analysis
+            .line_index(dead.file_id)?
+            .line_col(dead.range.start());
+        let what = match dead.kind {
+            DeadCodeKind::Function => "unreachable function",
+            DeadCodeKind::Record => "unused record",
+            DeadCodeKind::Macro => "unused macro",
+        };
+        writeln!(
+            cli,
+            "{}:{}:{}: {} {}",
+            path.display(),
+            start.line + 1,
+            start.col_utf16 + 1,
+            what,
+            dead.name
+        )?;
+    }
+    Ok(())
+}
@@ -7,6 +7,7 @@
  * of this source tree.
  */
 
+use std::collections::BTreeMap;
 use std::fs;
 use std::io;
 use std::path::Path;
@@ -34,6 +35,7 @@ use elp_project_model::DiscoverConfig;
 use elp_project_model::ProjectBuildData;
 use fxhash::FxHashMap;
 use indicatif::ParallelProgressIterator;
+use indicatif::ProgressBar;
 use itertools::Itertools;
 use rayon::prelude::*;
 
@@ -46,12 +48,17 @@ use crate::args::EqwalizeTarget;
 use crate::erlang_service_cli;
 use crate::reporting;
 use crate::reporting::Reporter;
+use crate::watch;
 
 /// Max parallel eqWAlizer tasks.
 ///
 /// Since eqWAlizer is frequently limited by memory, this can't be fully parallel
 const MAX_EQWALIZER_TASKS: usize = 4;
 
+/// Max modules eqWAlized by a single task, so that diagnostics are
+/// reported as they are found, rather than at the end.
+const MAX_CHUNK_SIZE: usize = 32;
+
 /// Thread stack size for eqWAlizer tasks, in bytes.
 ///
 /// Due to inefficient encoding of lists, the default stack size of 2MiB may not be
@@ -62,14 +69,31 @@ struct EqwalizerInternalArgs<'a> {
     analysis: &'a Analysis,
     loaded: &'a LoadResult,
     file_ids: Vec<FileId>,
+    /// Max parallel eqWAlizer tasks
+    jobs: usize,
     reporter: &'a mut dyn reporting::Reporter,
 }
 
 pub fn eqwalize_module(args: &Eqwalize, cli: &mut dyn Cli) -> Result<()> {
     let config = DiscoverConfig::new(args.rebar, &args.profile);
-    let loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::Yes)?;
-    build::compile_deps(&loaded, cli)?;
-    do_eqwalize_module(args, &loaded, cli)
+    let mut loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::Yes)?;
+    build::compile_deps(&mut loaded, cli)?;
+    if !args.watch {
+        return do_eqwalize_module(args, &loaded, cli);
+    }
+    // The module is checked against the specs of the modules it calls,
+    // so it is eqWAlized again on any change. Failures to eqWAlize it
+    // do not stop the watch.
+    let eqwalize_module = |cli: &mut dyn Cli, loaded: &LoadResult| -> Result<()> {
+        if let Err(err) = do_eqwalize_module(args, loaded, cli) {
+            writeln!(cli, "{err}")?;
+        }
+        Ok(())
+    };
+    eqwalize_module(cli, &loaded)?;
+    watch::watch(cli, &mut loaded, |cli, loaded, _modules| {
+        eqwalize_module(cli, loaded)
+    })
 }
 
 pub fn do_eqwalize_module(args: &Eqwalize, loaded: &LoadResult, cli: &mut dyn Cli) -> Result<()> {
@@ -82,14 +106,15 @@ pub fn do_eqwalize_module(args: &Eqwalize, loaded: &LoadResult, cli: &mut dyn Cl
         analysis,
         loaded: &loaded,
         file_ids: vec![file_id],
+        jobs: MAX_EQWALIZER_TASKS,
         reporter,
     })
 }
 
 pub fn eqwalize_all(args: &EqwalizeAll, cli: &mut dyn Cli) -> Result<()> {
     let config = DiscoverConfig::new(args.rebar, &args.profile);
-    let loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::Yes)?;
-    build::compile_deps(&loaded, cli)?;
+    let mut loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::Yes)?;
+    build::compile_deps(&mut loaded, cli)?;
     do_eqwalize_all(args, &loaded, cli)
 }
 
@@ -114,6 +139,7 @@ pub fn do_eqwalize_all(args: &EqwalizeAll, loaded: &LoadResult, cli: &mut dyn Cl
     pb.finish();
 
     let mut json_reporter;
+    let mut sarif_reporter;
     let mut pretty_reporter;
 
     let reporter: &mut dyn Reporter = match args.format {
@@ -121,6 +147,10 @@ pub fn do_eqwalize_all(args: &EqwalizeAll, loaded: &LoadResult, cli: &mut dyn Cl
             pretty_reporter = reporting::PrettyReporter::new(analysis, &loaded, cli);
             &mut pretty_reporter
         }
+        Some(_) if args.is_format_sarif() => {
+            sarif_reporter = reporting::SarifReporter::new(analysis, &loaded, cli);
+            &mut sarif_reporter
+        }
         Some(_) => {
             json_reporter = reporting::JsonReporter::new(analysis, &loaded, cli);
             &mut json_reporter
@@ -132,14 +162,15 @@ pub fn do_eqwalize_all(args: &EqwalizeAll, loaded: &LoadResult, cli: &mut dyn Cl
         analysis,
         loaded: &loaded,
         file_ids,
+        jobs: args.jobs.unwrap_or(MAX_EQWALIZER_TASKS),
         reporter,
     })
 }
 
 pub fn eqwalize_app(args: &EqwalizeApp, cli: &mut dyn Cli) -> Result<()> {
     let config = DiscoverConfig::new(args.rebar, &args.profile);
-    let loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::Yes)?;
-    build::compile_deps(&loaded, cli)?;
+    let mut loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::Yes)?;
+    build::compile_deps(&mut loaded, cli)?;
     do_eqwalize_app(args, &loaded, cli)
 }
 
@@ -164,6 +195,7 @@ pub fn do_eqwalize_app(args: &EqwalizeApp, loaded: &LoadResult, cli: &mut dyn Cl
         analysis,
         loaded: &loaded,
         file_ids,
+        jobs: MAX_EQWALIZER_TASKS,
         reporter: &mut reporter,
     })
 }
@@ -225,14 +257,15 @@ elp eqwalize-target erl/chatd #same as //erl/chatd/... but enables shell complet
         analysis,
         loaded: &loaded,
         file_ids,
+        jobs: MAX_EQWALIZER_TASKS,
         reporter: &mut reporter,
     })
 }
 
 pub fn eqwalize_passthrough(args: &EqwalizePassthrough, cli: &mut dyn Cli) -> Result<()> {
     let config = DiscoverConfig::new(!args.buck, &args.profile);
-    let loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::No)?;
-    build::compile_deps(&loaded, cli)?;
+    let mut loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::No)?;
+    build::compile_deps(&mut loaded, cli)?;
 
     let ast_dir = loaded.project.root().join("_build").join("elp").join("ast");
 
@@ -269,8 +302,8 @@ pub fn eqwalize_passthrough(args: &EqwalizePassthrough, cli: &mut dyn Cli) -> Re
 
 pub fn eqwalize_stats(args: &EqwalizeStats, cli: &mut dyn Cli) -> Result<()> {
     let config = DiscoverConfig::new(args.rebar, &args.profile);
-    let loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::Yes)?;
-    build::compile_deps(&loaded, cli)?;
+    let mut loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::Yes)?;
+    build::compile_deps(&mut loaded, cli)?;
     let analysis = &loaded.analysis();
     let module_index = analysis.module_index(loaded.project_id)?;
     let include_generated = args.include_generated;
@@ -301,7 +334,8 @@ fn eqwalize(
     EqwalizerInternalArgs {
         analysis,
         loaded,
-        file_ids,
+        mut file_ids,
+        jobs,
         reporter,
     }: EqwalizerInternalArgs,
 ) -> Result<()> {
@@ -311,53 +345,51 @@ fn eqwalize(
 
     pre_parse_for_speed(reporter, analysis.clone(), &file_ids);
 
+    // Modules are reported in order of their names, as they are
+    // eqWAlized
+    file_ids.sort_by_cached_key(|&file_id| analysis.module_name(file_id).ok().flatten());
+    let module_index = analysis.module_index(loaded.project_id)?;
     let files_count = file_ids.len();
     let pb = reporter.progress(files_count as u64, "EqWAlizing");
-    let output = loaded.with_eqwalizer_progress_bar(pb.clone(), move |analysis| {
-        let chunk_size = (files_count + MAX_EQWALIZER_TASKS - 1) / MAX_EQWALIZER_TASKS;
-        let pool = rayon::ThreadPoolBuilder::new()
-            .stack_size(THREAD_STACK_SIZE)
-            .build()
-            .unwrap();
-        let project_id = loaded.project_id;
-        pool.install(|| {
-            file_ids
-                .chunks(chunk_size)
-                .par_bridge()
-                .map_with(analysis, move |analysis, file_ids| {
-                    analysis
-                        .eqwalizer_diagnostics(project_id, file_ids.to_vec())
-                        .expect("cancelled")
-                })
-                .fold(EqwalizerDiagnostics::default, |acc, output| {
-                    acc.combine(&*output)
-                })
-                .reduce(EqwalizerDiagnostics::default, |acc, other| {
-                    acc.combine(&other)
-                })
-        })
-    });
+    let mut failure = None;
+    eqwalize_files_streaming(loaded, &file_ids, jobs, pb.clone(), |output| {
+        match output {
+            EqwalizerDiagnostics::Diagnostics {
+                errors: diagnostics_by_module,
+                ..
+            } => {
+                for (module, diagnostics) in diagnostics_by_module
+                    .iter()
+                    .sorted_by(|(name1, _), (name2, _)| Ord::cmp(name1, name2))
+                {
+                    let file_id = module_index
+                        .file_for_module(module.as_str())
+                        .with_context(|| format!("module {} not found", module))?;
+                    reporter.write_eqwalizer_diagnostics(file_id, diagnostics)?;
+                }
+            }
+            // Only the first failure is reported, as when all the
+            // files are eqWAlized together
+            _ => {
+                if failure.is_none() {
+                    failure = Some(output.clone());
+                }
+            }
+        }
+        Ok(())
+    })?;
     let eqwalized = pb.position();
     pb.finish();
-    match output {
-        EqwalizerDiagnostics::Diagnostics(diagnostics_by_module) => {
-            for (module, diagnostics) in diagnostics_by_module
-                .into_iter()
-                .sorted_by(|(name1, _), (name2, _)| Ord::cmp(name1, name2))
-            {
-                let file_id = analysis
-                    .module_index(loaded.project_id)?
-                    .file_for_module(module.as_str())
-                    .with_context(|| format!("module {} not found", module))?;
-                reporter.write_eqwalizer_diagnostics(file_id, &diagnostics)?;
-            }
+    match failure {
+        None => {
             if analysis.eqwalizer().shell {
                 reporter.write_stats(eqwalized, files_count as u64)?;
             }
             reporter.write_error_count()?;
+            reporter.finish()?;
             Ok(())
         }
-        EqwalizerDiagnostics::NoAst { module } => {
+        Some(EqwalizerDiagnostics::NoAst { module }) => {
             if let Some(file_id) = analysis.module_file_id(loaded.project_id, &module)? {
                 let parse_diagnostics = erlang_service_cli::do_parse_one(
                     analysis,
@@ -368,6 +400,7 @@ fn eqwalize(
                 // The cached parse errors must be non-empty otherwise we wouldn't have `NoAst`
                 assert!(!parse_diagnostics.is_empty());
                 reporter.write_parse_diagnostics(&parse_diagnostics)?;
+                reporter.finish()?;
                 Ok(())
             } else {
                 bail!(
@@ -376,12 +409,79 @@ fn eqwalize(
                 )
             }
         }
-        EqwalizerDiagnostics::Error(error) => {
+        Some(EqwalizerDiagnostics::Error(error)) => {
             bail!("Could not eqwalize: {}", error)
         }
+        Some(EqwalizerDiagnostics::Diagnostics { .. }) => unreachable!(),
     }
 }
 
+/// Type-check the given files, in parallel, reporting progress on `pb`
+pub fn eqwalize_files(
+    loaded: &LoadResult,
+    file_ids: &[FileId],
+    pb: ProgressBar,
+) -> EqwalizerDiagnostics {
+    let mut res = EqwalizerDiagnostics::default();
+    eqwalize_files_streaming(loaded, file_ids, MAX_EQWALIZER_TASKS, pb, |output| {
+        res = std::mem::take(&mut res).combine(output);
+        Ok(())
+    })
+    .expect("combining eqWAlizer outputs cannot fail");
+    res
+}
+
+/// Type-check the given files in chunks, with at most `jobs` eqWAlizer
+/// instances at a time, reporting progress on `pb`. The output of each
+/// chunk is passed to `on_output` as soon as it and the chunks before it
+/// are done, so that it is streamed in the order of `file_ids`.
+fn eqwalize_files_streaming(
+    loaded: &LoadResult,
+    file_ids: &[FileId],
+    jobs: usize,
+    pb: ProgressBar,
+    mut on_output: impl FnMut(&EqwalizerDiagnostics) -> Result<()>,
+) -> Result<()> {
+    if file_ids.is_empty() {
+        return Ok(());
+    }
+    let jobs = jobs.max(1);
+    let chunk_size = ((file_ids.len() + jobs - 1) / jobs).min(MAX_CHUNK_SIZE);
+    loaded.with_eqwalizer_progress_bar(pb, move |analysis| {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .stack_size(THREAD_STACK_SIZE)
+            .build()?;
+        let project_id = loaded.project_id;
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        pool.in_place_scope(|scope| {
+            for (idx, chunk) in file_ids.chunks(chunk_size).enumerate() {
+                let analysis = analysis.clone();
+                let sender = sender.clone();
+                scope.spawn(move |_| {
+                    let output = analysis
+                        .eqwalizer_diagnostics(project_id, chunk.to_vec())
+                        .expect("cancelled");
+                    // The receiver is only gone if reporting failed
+                    let _ = sender.send((idx, output));
+                });
+            }
+            drop(sender);
+
+            let mut pending = BTreeMap::new();
+            let mut next = 0;
+            for (idx, output) in receiver {
+                pending.insert(idx, output);
+                while let Some(output) = pending.remove(&next) {
+                    on_output(&*output)?;
+                    next += 1;
+                }
+            }
+            Ok(())
+        })
+    })
+}
+
 fn pre_parse_for_speed(reporter: &dyn Reporter, analysis: Analysis, file_ids: &[FileId]) {
     let pb = reporter.progress(file_ids.len() as u64, "Parsing modules");
     file_ids
@@ -393,7 +493,7 @@ fn pre_parse_for_speed(reporter: &dyn Reporter, analysis: Analysis, file_ids: &[
     pb.finish();
 }
 
-fn should_eqwalize(analysis: &Analysis, file_id: FileId, include_generated: bool) -> bool {
+pub fn should_eqwalize(analysis: &Analysis, file_id: FileId, include_generated: bool) -> bool {
     let is_in_app = analysis.file_app_type(file_id).ok() == Some(Some(AppType::App));
     is_in_app
         && analysis
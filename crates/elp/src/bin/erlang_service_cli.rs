@@ -39,8 +39,8 @@ use crate::reporting::ParseDiagnostic;
 
 pub fn parse_all(args: &ParseAll, cli: &mut dyn Cli) -> Result<()> {
     let config = DiscoverConfig::new(!args.buck, &args.profile);
-    let loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::No)?;
-    build::compile_deps(&loaded, cli)?;
+    let mut loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::No)?;
+    build::compile_deps(&mut loaded, cli)?;
     fs::create_dir_all(&args.to)?;
     let format = erlang_service::Format::OffsetEtf;
 
@@ -0,0 +1,45 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `elp expand`: print the expansion of the macro at a position, or the
+//! function there with all its macros expanded.
+
+use anyhow::bail;
+use anyhow::Result;
+use elp::build::load;
+use elp::cli::Cli;
+use elp_ide::elp_ide_db::elp_base_db::IncludeOtp;
+use elp_ide::ExpandMacroMode;
+use elp_project_model::DiscoverConfig;
+
+use crate::apply_assist_cli::parse_position;
+use crate::args::Expand;
+
+pub fn expand(args: &Expand, cli: &mut dyn Cli) -> Result<()> {
+    let mode = match args.mode.as_deref() {
+        None | Some("recursive") => ExpandMacroMode::Recursive,
+        Some("single-step") => ExpandMacroMode::SingleStep,
+        Some("function") => ExpandMacroMode::Function,
+        Some(mode) => bail!("Unknown expansion mode: {mode}"),
+    };
+    let config = DiscoverConfig::new(args.rebar, &args.profile);
+    let loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::Yes)?;
+    let position = parse_position(&loaded, &args.at)?;
+    match loaded.analysis().expand_macro(position, mode)? {
+        Some(expanded) => {
+            writeln!(cli, "{}", expanded.name)?;
+            writeln!(cli, "{}", expanded.expansion.trim())?;
+        }
+        None => match mode {
+            ExpandMacroMode::Function => bail!("{}: not in a function", args.at),
+            _ => bail!("{}: no macro to expand", args.at),
+        },
+    }
+    Ok(())
+}
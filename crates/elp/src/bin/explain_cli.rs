@@ -0,0 +1,35 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `elp explain`: print the long-form explanation of a diagnostic code,
+//! given as a code such as `W0007` or as a label such as `trivial_match`.
+
+use anyhow::bail;
+use anyhow::Result;
+use elp::cli::Cli;
+use elp_ide::diagnostics::DiagnosticCode;
+
+use crate::args::Explain;
+
+pub fn explain(args: &Explain, cli: &mut dyn Cli) -> Result<()> {
+    let code = match DiagnosticCode::maybe_from_string(&args.code)
+        .or_else(|| DiagnosticCode::maybe_from_string(&args.code.to_uppercase()))
+    {
+        Some(code) => code,
+        None => bail!("Unknown diagnostic code: {}", args.code),
+    };
+    match code.explanation() {
+        Some(explanation) => {
+            writeln!(cli, "# {} ({})\n", code.as_code(), code.as_label())?;
+            write!(cli, "{}", explanation)?;
+        }
+        None => bail!("No explanation for diagnostic code {}", code.as_code()),
+    }
+    Ok(())
+}
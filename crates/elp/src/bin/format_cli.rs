@@ -0,0 +1,84 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use elp::build::load;
+use elp::cli::Cli;
+use elp_ide::diff::diff_from_textedit;
+use elp_ide::elp_ide_db::elp_base_db::encoding;
+use elp_ide::elp_ide_db::elp_base_db::IncludeOtp;
+use elp_project_model::AppType;
+use elp_project_model::DiscoverConfig;
+
+use crate::args::Format;
+
+pub fn format(args: &Format, cli: &mut dyn Cli) -> Result<()> {
+    if args.in_place && args.check {
+        bail!("--in-place and --check cannot be used together");
+    }
+    let config = DiscoverConfig::new(args.rebar, &args.profile);
+    let loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::No)?;
+    let analysis = loaded.analysis();
+
+    let module_index = analysis.module_index(loaded.project_id)?;
+    let mut files = Vec::new();
+    for (name, _source, file_id) in module_index.iter_own() {
+        if let Some(module) = &args.module {
+            if name.as_str() != module.as_str() {
+                continue;
+            }
+        } else if analysis.file_app_type(file_id)? == Some(AppType::Dep) {
+            continue;
+        }
+        if let Some(path) = loaded.vfs.file_path(file_id).as_path() {
+            files.push((PathBuf::from(path.to_path_buf()), file_id));
+        }
+    }
+    if let Some(module) = &args.module {
+        if files.is_empty() {
+            bail!("module {} not found", module);
+        }
+    }
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut unformatted = 0;
+    for (path, file_id) in files {
+        let edit = match analysis.format_file(file_id)? {
+            Some(mut change) => match change.source_file_edits.remove(&file_id) {
+                Some(edit) => edit,
+                None => continue,
+            },
+            None => continue,
+        };
+        unformatted += 1;
+        let before = analysis.file_text(file_id)?;
+        let mut after = before.to_string();
+        edit.apply(&mut after);
+        if args.in_place {
+            let bytes = encoding::encode(&after)
+                .with_context(|| format!("Cannot write {}", path.display()))?;
+            fs::write(&path, bytes)?;
+        } else if args.check {
+            writeln!(cli, "{}", path.display())?;
+        } else if let (_, Some(unified)) = diff_from_textedit(&before, &after) {
+            writeln!(cli, "--- {}", path.display())?;
+            writeln!(cli, "+++ {}", path.display())?;
+            write!(cli, "{unified}")?;
+        }
+    }
+    if args.check && unformatted > 0 {
+        bail!("{} files are not formatted", unformatted);
+    }
+    Ok(())
+}
@@ -0,0 +1,211 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `elp glean`: export the definitions, references, call edges and
+//! include edges of all the modules of a project, one JSON object per
+//! line, for ingestion by code search backends.
+
+use std::fs;
+use std::io::BufWriter;
+use std::io::Write;
+
+use anyhow::Context;
+use anyhow::Result;
+use elp::build::load;
+use elp::build::types::LoadResult;
+use elp::cli::Cli;
+use elp::otp_file_to_ignore;
+use elp_ide::elp_ide_db::elp_base_db::FileId;
+use elp_ide::elp_ide_db::elp_base_db::IncludeOtp;
+use elp_ide::elp_ide_db::SymbolKind;
+use elp_ide::Analysis;
+use elp_ide::CodeIndex;
+use elp_ide::NavigationTarget;
+use elp_ide::TextRange;
+use elp_project_model::AppType;
+use elp_project_model::DiscoverConfig;
+use indicatif::ParallelProgressIterator;
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::args::Glean;
+use crate::reporting;
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Fact {
+    File {
+        file: String,
+        module: String,
+    },
+    Definition {
+        symbol: Symbol,
+    },
+    Reference {
+        file: String,
+        range: Range,
+        target: Symbol,
+    },
+    Call {
+        file: String,
+        range: Range,
+        caller: Symbol,
+        callee: Symbol,
+    },
+    Include {
+        file: String,
+        target: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct Symbol {
+    kind: &'static str,
+    name: String,
+    file: String,
+    range: Range,
+}
+
+/// One-based lines and columns
+#[derive(Debug, Serialize)]
+struct Range {
+    start_line: u32,
+    start_column: u32,
+    end_line: u32,
+    end_column: u32,
+}
+
+pub fn index_project(args: &Glean, cli: &mut dyn Cli) -> Result<()> {
+    let config = DiscoverConfig::new(args.rebar, &args.profile);
+    let loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::Yes)?;
+    let analysis = loaded.analysis();
+
+    let module_index = analysis.module_index(loaded.project_id)?;
+    let module_iter = module_index.iter_own();
+    let pb = cli.progress(module_iter.len() as u64, "Indexing modules (parallel)");
+    let mut indexes: Vec<(String, FileId, CodeIndex)> = module_iter
+        .par_bridge()
+        .progress_with(pb)
+        .map_with(
+            analysis.clone(),
+            |db, (module_name, _file_source, file_id)| -> Result<Option<_>> {
+                if otp_file_to_ignore(db, file_id)
+                    || db.file_app_type(file_id).ok() == Some(Some(AppType::Dep))
+                {
+                    return Ok(None);
+                }
+                let index = db.code_index(file_id)?;
+                Ok(Some((module_name.as_str().to_string(), file_id, index)))
+            },
+        )
+        .filter_map(|res| res.transpose())
+        .collect::<Result<_>>()?;
+    indexes.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut out: Box<dyn Write + '_> = match &args.to {
+        Some(path) => Box::new(BufWriter::new(
+            fs::File::create(path)
+                .with_context(|| format!("could not create {}", path.display()))?,
+        )),
+        None => Box::new(cli),
+    };
+    for (module, file_id, index) in indexes {
+        for fact in file_facts(&loaded, &analysis, module, file_id, index)? {
+            serde_json::to_writer(&mut out, &fact)?;
+            writeln!(out)?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn file_facts(
+    loaded: &LoadResult,
+    analysis: &Analysis,
+    module: String,
+    file_id: FileId,
+    index: CodeIndex,
+) -> Result<Vec<Fact>> {
+    let file = relative_path(loaded, file_id);
+    let mut res = vec![Fact::File {
+        file: file.clone(),
+        module,
+    }];
+    for def in &index.defs {
+        res.push(Fact::Definition {
+            symbol: symbol(loaded, analysis, def)?,
+        });
+    }
+    for reference in &index.refs {
+        res.push(Fact::Reference {
+            file: file.clone(),
+            range: range(analysis, file_id, reference.range)?,
+            target: symbol(loaded, analysis, &reference.target)?,
+        });
+    }
+    for call in &index.calls {
+        res.push(Fact::Call {
+            file: file.clone(),
+            range: range(analysis, file_id, call.range)?,
+            caller: symbol(loaded, analysis, &call.caller)?,
+            callee: symbol(loaded, analysis, &call.callee)?,
+        });
+    }
+    for include in index.includes {
+        res.push(Fact::Include {
+            file: file.clone(),
+            target: relative_path(loaded, include),
+        });
+    }
+    Ok(res)
+}
+
+fn symbol(loaded: &LoadResult, analysis: &Analysis, nav: &NavigationTarget) -> Result<Symbol> {
+    Ok(Symbol {
+        kind: symbol_kind(nav.kind),
+        name: nav.name.to_string(),
+        file: relative_path(loaded, nav.file_id),
+        range: range(analysis, nav.file_id, nav.range())?,
+    })
+}
+
+fn symbol_kind(kind: SymbolKind) -> &'static str {
+    match kind {
+        SymbolKind::File => "file",
+        SymbolKind::Module => "module",
+        SymbolKind::Function => "function",
+        SymbolKind::Record => "record",
+        SymbolKind::RecordField => "record_field",
+        SymbolKind::Type => "type",
+        SymbolKind::Define => "macro",
+        SymbolKind::Variable => "variable",
+        SymbolKind::Callback => "callback",
+        SymbolKind::TableKey => "table_key",
+    }
+}
+
+fn range(analysis: &Analysis, file_id: FileId, range: TextRange) -> Result<Range> {
+    let line_index = analysis.line_index(file_id)?;
+    let start = line_index.line_col(range.start());
+    let end = line_index.line_col(range.end());
+    Ok(Range {
+        start_line: start.line + 1,
+        start_column: start.col_utf16 + 1,
+        end_line: end.line + 1,
+        end_column: end.col_utf16 + 1,
+    })
+}
+
+fn relative_path(loaded: &LoadResult, file_id: FileId) -> String {
+    let root = loaded.project.root();
+    let vfs_path = loaded.vfs.file_path(file_id);
+    reporting::get_relative_path(&root, &vfs_path)
+        .display()
+        .to_string()
+}
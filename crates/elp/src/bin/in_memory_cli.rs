@@ -0,0 +1,168 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `elp in-memory`: report the diagnostics of a project loaded entirely
+//! in memory, with no Vfs or loader involved. The project is either
+//! described on stdin in the fixture format (see
+//! `elp_base_db::test_fixture`), or given as a tarball of its files.
+
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use elp::cli::Cli;
+use elp_ide::diagnostics::DiagnosticsConfig;
+use elp_ide::AnalysisHost;
+use tempfile::Builder;
+
+use crate::args::InMemory;
+
+pub fn in_memory(args: &InMemory, cli: &mut dyn Cli) -> Result<()> {
+    let description = match &args.tarball {
+        Some(tarball) => describe_tarball(tarball)?,
+        None => {
+            let mut description = String::new();
+            io::stdin().read_to_string(&mut description)?;
+            description
+        }
+    };
+    let (host, files) = AnalysisHost::from_project_description(&description)?;
+    let analysis = host.analysis();
+
+    let config = DiagnosticsConfig::default();
+    let mut count = 0;
+    for file_id in files {
+        let diagnostics = analysis.diagnostics(&config, file_id, false)?;
+        if diagnostics.is_empty() {
+            continue;
+        }
+        let line_index = analysis.line_index(file_id)?;
+        if let Some(path) = analysis.file_path(file_id)? {
+            writeln!(cli, "{}:", path)?;
+        }
+        for diagnostic in &diagnostics {
+            writeln!(cli, "      {}", diagnostic.print(&line_index))?;
+        }
+        count += diagnostics.len();
+    }
+    if count == 0 {
+        writeln!(cli, "No diagnostics reported")?;
+    }
+    Ok(())
+}
+
+/// Unpacks the tarball and describes its Erlang files. A file belongs
+/// to the app named after the directory above its `src`, `include` or
+/// `test` directory, and each `include` directory is on the include
+/// path of its app.
+fn describe_tarball(tarball: &Path) -> Result<String> {
+    let tmp = Builder::new().prefix("elp_in_memory_").tempdir()?;
+    let status = Command::new("tar")
+        .arg("-xzf")
+        .arg(tarball)
+        .arg("-C")
+        .arg(tmp.path())
+        .status()
+        .context("could not run tar")?;
+    if !status.success() {
+        bail!("tar failed with {}", status);
+    }
+    describe_dir(tmp.path())
+}
+
+fn describe_dir(root: &Path) -> Result<String> {
+    let mut paths = Vec::new();
+    collect_erlang_files(root, &mut paths)?;
+    paths.sort();
+
+    let mut description = String::new();
+    for path in paths {
+        let relative = path.strip_prefix(root)?;
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("could not read {}", relative.display()))?;
+        let dir = relative.parent().unwrap_or(Path::new(""));
+        let (app_dir, kind) = match dir.file_name().and_then(|name| name.to_str()) {
+            Some(kind @ ("src" | "include" | "test")) => {
+                (dir.parent().unwrap_or(Path::new("")), kind)
+            }
+            _ => (dir, ""),
+        };
+        let app = app_dir.file_name().map_or_else(
+            || "app".to_string(),
+            |name| name.to_string_lossy().to_string(),
+        );
+        description.push_str(&format!("//- /{} app:{}", relative.display(), app));
+        match kind {
+            "include" => description.push_str(&format!(" include_path:/{}", dir.display())),
+            "test" => description.push_str(" extra:test"),
+            _ => {}
+        }
+        description.push('\n');
+        description.push_str(&text);
+        if !text.ends_with('\n') {
+            description.push('\n');
+        }
+    }
+    Ok(description)
+}
+
+fn collect_erlang_files(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_erlang_files(&path, paths)?;
+        } else if matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("erl" | "hrl")
+        ) {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+
+    #[test]
+    fn describe_files_by_app() {
+        let tmp = Builder::new().prefix("elp_").tempdir().unwrap();
+        let write = |path: &str, text: &str| {
+            let path = tmp.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+        };
+        write("app_a/src/app_a.erl", "-module(app_a).\n");
+        write("app_a/include/app_a.hrl", "-define(A, 1).");
+        write("app_a/test/app_a_SUITE.erl", "-module(app_a_SUITE).\n");
+        write("top.erl", "-module(top).\n");
+        write("README.md", "not Erlang\n");
+
+        expect![[r#"
+            //- /app_a/include/app_a.hrl app:app_a include_path:/app_a/include
+            -define(A, 1).
+            //- /app_a/src/app_a.erl app:app_a
+            -module(app_a).
+            //- /app_a/test/app_a_SUITE.erl app:app_a extra:test
+            -module(app_a_SUITE).
+            //- /top.erl app:app
+            -module(top).
+        "#]]
+        .assert_eq(&describe_dir(tmp.path()).unwrap());
+    }
+}
@@ -0,0 +1,90 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `elp includes`: print the include graph of a project, as text, DOT
+//! or JSON, to find include cycles and the headers included the most.
+
+use anyhow::Result;
+use elp::build::load;
+use elp::cli::Cli;
+use elp_ide::elp_ide_db::elp_base_db::FileId;
+use elp_ide::elp_ide_db::elp_base_db::IncludeOtp;
+use elp_project_model::DiscoverConfig;
+use itertools::Itertools;
+use serde_json::json;
+
+use crate::args::Includes;
+use crate::reporting;
+
+pub fn includes(args: &Includes, cli: &mut dyn Cli) -> Result<()> {
+    let config = DiscoverConfig::new(args.rebar, &args.profile);
+    let loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::Yes)?;
+    let analysis = loaded.analysis();
+    let graph = analysis.include_graph(loaded.project_id)?;
+
+    let root = loaded.project.root();
+    let path = |file_id: FileId| {
+        reporting::get_relative_path(&root, &loaded.vfs.file_path(file_id))
+            .display()
+            .to_string()
+    };
+    let paths = |files: &mut dyn Iterator<Item = FileId>| files.map(path).collect::<Vec<_>>();
+
+    if args.is_format_json() {
+        let files: Vec<_> = graph
+            .files()
+            .map(|file_id| {
+                json!({
+                    "path": path(file_id),
+                    "includes": paths(&mut graph.includes(file_id)),
+                    "included_by": paths(&mut graph.included_by(file_id)),
+                })
+            })
+            .collect();
+        let cycles: Vec<_> = graph
+            .cycles()
+            .into_iter()
+            .map(|cycle| paths(&mut cycle.into_iter()))
+            .collect();
+        let report = json!({ "files": files, "cycles": cycles });
+        writeln!(cli, "{}", serde_json::to_string(&report)?)?;
+    } else if args.is_format_dot() {
+        writeln!(cli, "digraph includes {{")?;
+        for file_id in graph.files() {
+            for header in graph.includes(file_id) {
+                writeln!(cli, "  {:?} -> {:?};", path(file_id), path(header))?;
+            }
+        }
+        writeln!(cli, "}}")?;
+    } else {
+        for cycle in graph.cycles() {
+            writeln!(
+                cli,
+                "Include cycle: {}",
+                paths(&mut cycle.into_iter()).join(", ")
+            )?;
+        }
+        // The headers included by the most files first
+        let headers = graph
+            .files()
+            .map(|file_id| (file_id, graph.transitive_included_by(file_id).len()))
+            .filter(|(_, count)| *count > 0)
+            .sorted_by_key(|(file_id, count)| (std::cmp::Reverse(*count), path(*file_id)));
+        for (file_id, count) in headers {
+            writeln!(
+                cli,
+                "{}: included by {} files, {} transitively",
+                path(file_id),
+                graph.included_by(file_id).count(),
+                count
+            )?;
+        }
+    }
+    Ok(())
+}
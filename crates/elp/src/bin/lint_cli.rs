@@ -14,8 +14,10 @@ use std::path::Path;
 use std::path::PathBuf;
 use std::str;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::bail;
+use anyhow::Context;
 use anyhow::Result;
 use elp::build::load;
 use elp::build::types::LoadResult;
@@ -23,11 +25,15 @@ use elp::cli::Cli;
 use elp::convert;
 use elp::document::Document;
 use elp::otp_file_to_ignore;
+use elp_ide::codemod::Codemod;
 use elp_ide::diagnostics;
+use elp_ide::diagnostics::DiagnosticCode;
 use elp_ide::diagnostics::DiagnosticsConfig;
+use elp_ide::diagnostics::Severity;
 use elp_ide::diff::diff_from_textedit;
 use elp_ide::diff::DiffRange;
 use elp_ide::elp_ide_assists::Assist;
+use elp_ide::elp_ide_db::elp_base_db::encoding;
 use elp_ide::elp_ide_db::elp_base_db::AbsPath;
 use elp_ide::elp_ide_db::elp_base_db::Change;
 use elp_ide::elp_ide_db::elp_base_db::FileId;
@@ -43,26 +49,315 @@ use elp_ide::AnalysisHost;
 use elp_project_model::AppName;
 use elp_project_model::AppType;
 use elp_project_model::DiscoverConfig;
+use fxhash::FxHashMap;
 use fxhash::FxHashSet;
 use indicatif::ParallelProgressIterator;
+use rayon::prelude::IntoParallelIterator;
+use rayon::prelude::IntoParallelRefIterator;
 use rayon::prelude::ParallelBridge;
 use rayon::prelude::ParallelIterator;
+use serde::Serialize;
 
 use crate::args::Lint;
 use crate::reporting;
+use crate::sarif;
+use crate::sarif::SarifResult;
+use crate::watch;
 
 pub fn lint_all(args: &Lint, cli: &mut dyn Cli) -> Result<()> {
     log::info!("Loading project at: {:?}", args.project);
     let config = DiscoverConfig::new(args.rebar, &args.profile);
     let mut loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::Yes)?;
 
+    if let Some(rev) = &args.rev {
+        if args.in_place {
+            bail!("--in-place cannot be used together with --rev");
+        }
+        if args.timing {
+            bail!("--timing cannot be used together with --rev");
+        }
+        load_revision(&mut loaded, &args.project, rev)?;
+    }
+
+    if args.add_ignores && args.apply_fix {
+        bail!("--add-ignores cannot be used together with --apply-fix");
+    }
+
+    if args.dry_run {
+        if !args.apply_fix {
+            bail!("--dry-run can only be used together with --apply-fix");
+        }
+        if args.in_place {
+            bail!("--dry-run cannot be used together with --in-place");
+        }
+    }
+
+    if args.watch {
+        if args.apply_fix || args.add_ignores || args.timing || args.rev.is_some() {
+            bail!("--watch can only be used to report diagnostics");
+        }
+        if args.is_format_sarif() {
+            bail!("--watch cannot be used together with --format sarif");
+        }
+        if args.diagnostic_filter.is_none() && args.codemod.is_none() {
+            bail!("Expecting --diagnostic-filter or --codemod");
+        }
+    }
+
     if let Some(to) = &args.to {
         fs::create_dir_all(to)?
     };
 
+    if args.timing {
+        return report_timing(cli, &loaded, args);
+    }
+
+    if args.watch {
+        // Errors found by the first run do not stop the watch
+        if let Err(err) = do_codemod(cli, &mut loaded, args) {
+            writeln!(cli, "{err}")?;
+        }
+        return watch_lint(cli, &mut loaded, args);
+    }
+
     do_codemod(cli, &mut loaded, args)
 }
 
+/// Lint again the modules affected by each change to the project files,
+/// reporting the diagnostic count of each of them, even when zero.
+fn watch_lint(cli: &mut dyn Cli, loaded: &mut LoadResult, args: &Lint) -> Result<()> {
+    let codemod = args.codemod.as_deref().map(Codemod::load).transpose()?;
+    let codemod_diagnostics = codemod.as_ref().map(|codemod| codemod.diagnostics());
+    let mut cfg = diagnostics_config(args)?;
+    if let Some(codemod_diagnostics) = &codemod_diagnostics {
+        cfg.adhoc_semantic_diagnostics.push(codemod_diagnostics);
+    }
+    watch::watch(cli, loaded, |cli, loaded, file_ids| {
+        let analysis = loaded.analysis();
+        for file_id in file_ids {
+            let name = match analysis.module_name(file_id)? {
+                Some(name) => name,
+                None => continue,
+            };
+            let res = do_parse_one(
+                &analysis,
+                &cfg,
+                file_id,
+                name.as_str(),
+                args.include_generated,
+                args.plugins,
+                vec![],
+            )?
+            .map_or(vec![], |x| vec![x]);
+            let mut diags = filter_diagnostics(
+                &analysis,
+                &None,
+                args.diagnostic_filter.as_ref(),
+                None,
+                None,
+                &res,
+            )?
+            .into_iter()
+            .flat_map(|(_, _, diags)| diags)
+            .collect::<Vec<_>>();
+            if args.codemod.is_some() && args.diagnostic_filter.is_none() {
+                // Only report the calls the codemod replaces
+                diags.retain(|d| matches!(d.code, DiagnosticCode::AdHoc(_)));
+            }
+            if args.is_format_json() {
+                let vfs_path = loaded.vfs.file_path(file_id);
+                let relative_path = reporting::get_relative_path(&loaded.project.root(), &vfs_path);
+                for diag in &diags {
+                    print_diagnostic_json(diag, &analysis, file_id, relative_path, cli)?;
+                }
+            } else {
+                writeln!(cli, "  {}: {}", name, diags.len())?;
+                if args.print_diags {
+                    for diag in &diags {
+                        print_diagnostic(diag, &analysis, file_id, cli)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    })
+}
+
+#[derive(Debug, Serialize)]
+struct FileTiming {
+    module: String,
+    total_ms: f64,
+    passes: Vec<PassTiming>,
+}
+
+#[derive(Debug, Serialize)]
+struct PassTiming {
+    name: String,
+    ms: f64,
+}
+
+/// Report, for each file, how long each diagnostic pass took, slowest
+/// files first. Files are processed one at a time so that the passes
+/// of different files do not compete for CPU and skew the results.
+fn report_timing(cli: &mut dyn Cli, loaded: &LoadResult, args: &Lint) -> Result<()> {
+    let analysis = loaded.analysis();
+    let cfg = diagnostics_config(args)?;
+
+    let file_ids: Vec<(String, FileId)> = match (&args.module, &args.file) {
+        (Some(module), _) => match analysis.module_file_id(loaded.project_id, module)? {
+            Some(file_id) => vec![(module.clone(), file_id)],
+            None => bail!("Module {} not found", module),
+        },
+        (None, Some(file_name)) => {
+            let path_buf = fs::canonicalize(file_name)?;
+            let path = AbsPath::assert(&path_buf);
+            let path = path.as_os_str().to_str().unwrap();
+            match loaded
+                .vfs
+                .file_id(&VfsPath::new_real_path(path.to_string()))
+            {
+                Some(file_id) => vec![(file_name.clone(), file_id)],
+                None => bail!("File {} not found in the project", file_name),
+            }
+        }
+        (None, None) => {
+            let ignored_apps: FxHashSet<Option<Option<AppName>>> = args
+                .ignore_apps
+                .iter()
+                .map(|name| Some(Some(AppName(name.to_string()))))
+                .collect();
+            let module_index = analysis.module_index(loaded.project_id)?;
+            module_index
+                .iter_own()
+                .filter(|(_, _, file_id)| {
+                    !otp_file_to_ignore(&analysis, *file_id)
+                        && analysis.file_app_type(*file_id).ok() != Some(Some(AppType::Dep))
+                        && !ignored_apps.contains(&analysis.file_app_name(*file_id).ok())
+                })
+                .map(|(module_name, _, file_id)| (module_name.as_str().to_string(), file_id))
+                .collect()
+        }
+    };
+
+    let pb = cli.progress(file_ids.len() as u64, "Timing diagnostics");
+    let mut timings = Vec::new();
+    for (module, file_id) in file_ids {
+        let timing = analysis.diagnostics_timing(&cfg, file_id, args.include_generated)?;
+        timings.push(FileTiming {
+            module,
+            total_ms: as_millis(timing.total()),
+            passes: timing
+                .passes
+                .into_iter()
+                .map(|(name, duration)| PassTiming {
+                    name,
+                    ms: as_millis(duration),
+                })
+                .collect(),
+        });
+        pb.inc(1);
+    }
+    pb.finish();
+    timings.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+
+    for timing in &timings {
+        if args.is_format_json() {
+            writeln!(cli, "{}", serde_json::to_string(timing)?)?;
+        } else {
+            writeln!(cli, "{}: {:.1}ms", timing.module, timing.total_ms)?;
+            for pass in &timing.passes {
+                writeln!(cli, "    {:<36} {:>10.1}ms", pass.name, pass.ms)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The diagnostics config given by the command line, with the
+/// severity of the diagnostics passed to `--error` and `--warn`
+/// overridden.
+fn diagnostics_config(args: &Lint) -> Result<DiagnosticsConfig<'static>> {
+    let mut cfg = DiagnosticsConfig::default();
+    cfg.disable_experimental = args.experimental_diags;
+    let overrides = args
+        .error
+        .iter()
+        .map(|code| (code, Severity::Error))
+        .chain(args.warn.iter().map(|code| (code, Severity::Warning)));
+    for (code, severity) in overrides {
+        match DiagnosticCode::maybe_from_string(code) {
+            Some(code) => cfg = cfg.override_severity(code, severity),
+            None => bail!("Unknown diagnostic code: {}", code),
+        }
+    }
+    Ok(cfg)
+}
+
+fn as_millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Replace the contents of every loaded file in the repository
+/// containing `project` by its contents at `rev`, read directly from the
+/// git object storage. Files not present at `rev` are emptied. Only the
+/// files making up the project as currently checked out are considered.
+fn load_revision(loaded: &mut LoadResult, project: &Path, rev: &str) -> Result<()> {
+    let files = read_revision(
+        project,
+        rev,
+        loaded
+            .vfs
+            .iter()
+            .map(|(file_id, vfs_path)| (file_id, vfs_path.clone())),
+    )?;
+    let mut change = Change::new();
+    for (file_id, vfs_path, text) in files {
+        change.change_file(file_id, text.clone().map(Arc::new));
+        loaded
+            .vfs
+            .set_file_contents(vfs_path, text.map(String::into_bytes));
+    }
+    loaded.analysis_host.apply_change(change);
+    Ok(())
+}
+
+/// The contents at `rev` of those of `files` in the repository
+/// containing `project`, or `None` for the ones not present at `rev`.
+fn read_revision(
+    project: &Path,
+    rev: &str,
+    files: impl Iterator<Item = (FileId, VfsPath)>,
+) -> Result<Vec<(FileId, VfsPath, Option<String>)>> {
+    let repo = git2::Repository::discover(project)?;
+    let workdir = match repo.workdir() {
+        Some(workdir) => fs::canonicalize(workdir)?,
+        None => bail!("No working directory for git repository at {:?}", project),
+    };
+    let tree = repo.revparse_single(rev)?.peel_to_tree()?;
+    log::info!("Loading files from revision {} in {:?}", rev, workdir);
+
+    let mut res = Vec::new();
+    for (file_id, vfs_path) in files {
+        let path: &Path = match vfs_path.as_path() {
+            Some(path) => path.as_ref(),
+            None => continue,
+        };
+        let relative = match path.strip_prefix(&workdir) {
+            Ok(relative) => relative,
+            Err(_) => continue,
+        };
+        let text = match tree.get_path(relative) {
+            Ok(entry) => match entry.to_object(&repo)?.into_blob() {
+                Ok(blob) => Some(encoding::decode(blob.content().to_vec())),
+                Err(_) => None,
+            },
+            Err(_) => None,
+        };
+        res.push((file_id, vfs_path.clone(), text));
+    }
+    Ok(res)
+}
+
 /// Changed lines, from and to
 type ChangeRange = (u32, u32);
 
@@ -72,6 +367,7 @@ fn do_parse_all(
     project_id: &ProjectId,
     config: &DiagnosticsConfig,
     include_generated: bool,
+    plugins: bool,
     ignore_apps: &[String],
 ) -> Result<
     Vec<(
@@ -106,6 +402,7 @@ fn do_parse_all(
                         file_id,
                         module_name.as_str(),
                         include_generated,
+                        plugins,
                         Vec::default(),
                     )
                     .unwrap()
@@ -124,6 +421,7 @@ fn do_parse_one(
     file_id: FileId,
     name: &str,
     include_generated: bool,
+    plugins: bool,
     changes: Vec<ChangeRange>,
 ) -> Result<
     Option<(
@@ -133,7 +431,11 @@ fn do_parse_one(
         Vec<ChangeRange>,
     )>,
 > {
-    let diagnostics = db.diagnostics(config, file_id, include_generated)?;
+    let mut diagnostics = db.diagnostics(config, file_id, include_generated)?;
+    if plugins {
+        // The lint plugins configured for the project, if any
+        diagnostics.extend(db.plugin_diagnostics(file_id)?);
+    }
     if !diagnostics.is_empty() {
         let res = (name.to_string(), file_id, diagnostics, changes);
         Ok(Some(res))
@@ -161,14 +463,27 @@ pub fn do_codemod(cli: &mut dyn Cli, loaded: &mut LoadResult, args: &Lint) -> Re
             apply_fix: _,
             recursive,
             in_place,
-            diagnostic_filter: Some(diagnostic_filter),
+            dry_run,
+            add_ignores: _,
+            plugins: _,
+            diagnostic_filter,
+            error: _,
+            warn: _,
+            codemod,
             line_from,
             line_to,
             ignore_apps,
             format: _,
-        } => {
-            let mut cfg = DiagnosticsConfig::default();
-            cfg.disable_experimental = args.experimental_diags;
+            rev: _,
+            timing: _,
+            watch: _,
+        } if diagnostic_filter.is_some() || args.add_ignores || codemod.is_some() => {
+            let codemod = codemod.as_deref().map(Codemod::load).transpose()?;
+            let codemod_diagnostics = codemod.as_ref().map(|codemod| codemod.diagnostics());
+            let mut cfg = diagnostics_config(args)?;
+            if let Some(codemod_diagnostics) = &codemod_diagnostics {
+                cfg.adhoc_semantic_diagnostics.push(codemod_diagnostics);
+            }
             // Declare outside the block so it has the right lifetime for filter_diagnostics
             let res;
             let mut diags = {
@@ -216,6 +531,7 @@ pub fn do_codemod(cli: &mut dyn Cli, loaded: &mut LoadResult, args: &Lint) -> Re
                         &loaded.project_id,
                         &cfg,
                         args.include_generated,
+                        args.plugins,
                         ignore_apps,
                     )?,
                     (Some(file_id), Some(name)) => do_parse_one(
@@ -224,6 +540,7 @@ pub fn do_codemod(cli: &mut dyn Cli, loaded: &mut LoadResult, args: &Lint) -> Re
                         file_id,
                         &name,
                         args.include_generated,
+                        args.plugins,
                         vec![],
                     )?
                     .map_or(vec![], |x| vec![x]),
@@ -235,12 +552,19 @@ pub fn do_codemod(cli: &mut dyn Cli, loaded: &mut LoadResult, args: &Lint) -> Re
                 filter_diagnostics(
                     &analysis,
                     &args.module,
-                    Some(diagnostic_filter),
+                    diagnostic_filter.as_ref(),
                     *line_from,
                     *line_to,
                     &res,
                 )?
             };
+            if codemod.is_some() && diagnostic_filter.is_none() {
+                // Only report the calls the codemod replaces
+                diags.retain_mut(|(_, _, diags)| {
+                    diags.retain(|d| matches!(d.code, DiagnosticCode::AdHoc(_)));
+                    !diags.is_empty()
+                });
+            }
             if diags.is_empty() {
                 if args.is_format_normal() {
                     writeln!(cli, "No diagnostics reported")?;
@@ -277,6 +601,28 @@ pub fn do_codemod(cli: &mut dyn Cli, loaded: &mut LoadResult, args: &Lint) -> Re
                             }
                         }
                     }
+                } else if args.is_format_sarif() {
+                    let analysis = loaded.analysis();
+                    let mut results = Vec::new();
+                    for (_name, file_id, diags) in &diags {
+                        err_in_diag |= diags
+                            .iter()
+                            .any(|diag| matches!(diag.severity, diagnostics::Severity::Error));
+                        let line_index = analysis.line_index(*file_id)?;
+                        let path = reporting::get_relative_path(
+                            &loaded.project.root(),
+                            &loaded.vfs.file_path(*file_id),
+                        )
+                        .display()
+                        .to_string();
+                        results.extend(
+                            diags
+                                .iter()
+                                .map(|diag| SarifResult::from_diagnostic(diag, &line_index, &path)),
+                        );
+                    }
+                    let log = sarif::to_sarif("elp lint", &results);
+                    writeln!(cli, "{}", serde_json::to_string(&log)?)?;
                 } else {
                     writeln!(cli, "Diagnostics reported in {} modules:", diags.len())?;
 
@@ -295,6 +641,10 @@ pub fn do_codemod(cli: &mut dyn Cli, loaded: &mut LoadResult, args: &Lint) -> Re
                         }
                     }
                 }
+                if args.add_ignores {
+                    // The diagnostics are no longer reported once ignored
+                    return add_ignores(cli, loaded, args, &diags);
+                }
                 if args.apply_fix {
                     let mut changed_files = FxHashSet::default();
                     let mut lints = Lints::new(
@@ -304,6 +654,7 @@ pub fn do_codemod(cli: &mut dyn Cli, loaded: &mut LoadResult, args: &Lint) -> Re
                         &args.to,
                         args.include_generated,
                         *in_place,
+                        *dry_run,
                         *recursive,
                         &mut changed_files,
                         diags,
@@ -314,6 +665,9 @@ pub fn do_codemod(cli: &mut dyn Cli, loaded: &mut LoadResult, args: &Lint) -> Re
                             writeln!(cli, "Apply fix failed: {:?}", err).ok();
                         }
                     };
+                    if args.is_format_json() {
+                        print_applied_fixes_json(loaded, lints.applied, cli)?;
+                    }
                 }
                 if err_in_diag {
                     bail!("Errors found")
@@ -321,10 +675,50 @@ pub fn do_codemod(cli: &mut dyn Cli, loaded: &mut LoadResult, args: &Lint) -> Re
             }
             Ok(())
         }
-        _ => bail!("Expecting --diagnostic-filter"),
+        _ => bail!("Expecting --diagnostic-filter or --codemod"),
     }
 }
 
+/// Add a `% elp:ignore` comment above each of the diagnostics, writing
+/// the files to the `--to` directory, or in place.
+fn add_ignores(
+    cli: &mut dyn Cli,
+    loaded: &LoadResult,
+    args: &Lint,
+    diags: &[(String, FileId, Vec<diagnostics::Diagnostic>)],
+) -> Result<()> {
+    let analysis = loaded.analysis();
+    for (name, file_id, diags) in diags {
+        let path = if args.in_place {
+            match loaded.vfs.file_path(*file_id).as_path() {
+                Some(path) => PathBuf::from(path.to_path_buf()),
+                None => bail!("No path for module {}", name),
+            }
+        } else {
+            match &args.to {
+                Some(to) => to.join(format!("{}.erl", name)),
+                None => bail!("--add-ignores needs either --to or --in-place"),
+            }
+        };
+        let mut text = analysis.file_text(*file_id)?.to_string();
+        let line_index = analysis.line_index(*file_id)?;
+        diagnostics::ignore_all(&text, &line_index, diags).apply(&mut text);
+        // Written back in the encoding declared by the file
+        let bytes = encoding::encode(&text)
+            .with_context(|| format!("Cannot add the ignores to {}", name))?;
+        fs::write(&path, bytes)?;
+        if args.is_format_normal() {
+            writeln!(
+                cli,
+                "Added ignores for {} diagnostics in {}",
+                diags.len(),
+                name
+            )?;
+        }
+    }
+    Ok(())
+}
+
 fn print_diagnostic(
     diag: &diagnostics::Diagnostic,
     analysis: &Analysis,
@@ -336,6 +730,32 @@ fn print_diagnostic(
     Ok(())
 }
 
+/// Report which diagnostics caused which edits, one line per changed
+/// file, so that codemod runs can be audited and partially reverted.
+fn print_applied_fixes_json(
+    loaded: &LoadResult,
+    applied: FxHashMap<FileId, Vec<AppliedFix>>,
+    cli: &mut dyn Cli,
+) -> Result<()> {
+    let mut files = applied
+        .into_iter()
+        .map(|(file_id, fixes)| {
+            let path = reporting::get_relative_path(
+                &loaded.project.root(),
+                &loaded.vfs.file_path(file_id),
+            )
+            .display()
+            .to_string();
+            FileFixes { path, fixes }
+        })
+        .collect::<Vec<_>>();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    for file in files {
+        writeln!(cli, "{}", serde_json::to_string(&file)?)?;
+    }
+    Ok(())
+}
+
 fn print_diagnostic_json(
     diagnostic: &diagnostics::Diagnostic,
     analysis: &Analysis,
@@ -423,9 +843,14 @@ struct Lints<'a> {
     to: &'a Option<PathBuf>,
     include_generated: bool,
     in_place: bool,
+    dry_run: bool,
     recursive: bool,
     changed_files: &'a mut FxHashSet<(FileId, String)>,
     diags: Vec<(String, FileId, Vec<diagnostics::Diagnostic>)>,
+    /// The fixes applied to each file, in order
+    applied: FxHashMap<FileId, Vec<AppliedFix>>,
+    /// The contents of each changed file before its first fix
+    originals: FxHashMap<FileId, Arc<str>>,
 }
 
 #[derive(Debug)]
@@ -435,6 +860,25 @@ struct FixResult {
     source: String,
     changes: Vec<ChangeRange>,
     diff: Option<String>,
+    code: DiagnosticCode,
+    assist: &'static str,
+}
+
+/// The provenance of an edit made by `--apply-fix`
+#[derive(Debug, Serialize)]
+struct AppliedFix {
+    /// The code of the diagnostic whose fix was applied
+    code: String,
+    /// The id of the applied assist
+    assist: &'static str,
+    /// The unified diff hunks of the edit
+    hunks: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct FileFixes {
+    path: String,
+    fixes: Vec<AppliedFix>,
 }
 
 const LINT_APPLICATION_RECURSION_LIMIT: i32 = 10;
@@ -447,6 +891,7 @@ impl<'a> Lints<'a> {
         to: &'a Option<PathBuf>,
         include_generated: bool,
         in_place: bool,
+        dry_run: bool,
         recursive: bool,
         changed_files: &'a mut FxHashSet<(FileId, String)>,
         diags: Vec<(String, FileId, Vec<diagnostics::Diagnostic>)>,
@@ -458,9 +903,12 @@ impl<'a> Lints<'a> {
             to,
             include_generated,
             in_place,
+            dry_run,
             recursive,
             changed_files,
             diags,
+            applied: FxHashMap::default(),
+            originals: FxHashMap::default(),
         }
     }
 
@@ -478,43 +926,53 @@ impl<'a> Lints<'a> {
                 break;
             }
             recursion_limit -= 1;
-            let new_diags: Vec<_> = changes
-                .into_iter()
-                .map(
-                    |FixResult {
-                         file_id,
-                         name,
-                         source,
-                         changes,
-                         diff: _,
-                     }|
-                     -> Result<
-                        Option<(
-                            String,
-                            FileId,
-                            Vec<diagnostics::Diagnostic>,
-                            Vec<ChangeRange>,
-                        )>,
-                    > {
-                        self.changed_files.insert((file_id, name.clone()));
-                        let path = self.vfs.file_path(file_id);
-                        self.vfs
-                            .set_file_contents(path, Some(source.clone().into_bytes()));
-
-                        self.analysis_host.apply_change(Change {
-                            roots: None,
-                            files_changed: vec![(file_id, Some(Arc::new(source)))],
-                            app_structure: None,
-                        });
-
-                        do_parse_one(
-                            &self.analysis_host.analysis(),
-                            &self.cfg,
-                            file_id,
-                            &name,
-                            self.include_generated,
-                            changes,
-                        )
+            // Changing the analysis host needs exclusive access to it, so
+            // the changes are applied serially. The last change to a file
+            // is the one which sticks.
+            let mut changed: FxHashMap<FileId, (String, Vec<ChangeRange>)> = FxHashMap::default();
+            for FixResult {
+                file_id,
+                name,
+                source,
+                changes,
+                diff,
+                code,
+                assist,
+            } in changes
+            {
+                self.changed_files.insert((file_id, name.clone()));
+                if !self.originals.contains_key(&file_id) {
+                    let original = self.analysis_host.analysis().file_text(file_id)?;
+                    self.originals.insert(file_id, original);
+                }
+                self.applied.entry(file_id).or_default().push(AppliedFix {
+                    code: code.as_code(),
+                    assist,
+                    hunks: diff.as_deref().map(hunks).unwrap_or_default(),
+                });
+                let path = self.vfs.file_path(file_id);
+                self.vfs
+                    .set_file_contents(path, Some(source.clone().into_bytes()));
+
+                self.analysis_host.apply_change(Change {
+                    roots: None,
+                    files_changed: vec![(file_id, Some(Arc::new(source)))],
+                    app_structure: None,
+                });
+                changed.insert(file_id, (name, changes));
+            }
+            // The changed files are independent, so are checked again in
+            // parallel
+            let mut changed = changed.into_iter().collect::<Vec<_>>();
+            changed.sort_by(|(_, (a, _)), (_, (b, _))| a.cmp(b));
+            let (cfg, include_generated) = (self.cfg, self.include_generated);
+            let new_diags: Vec<_> = changed
+                .into_par_iter()
+                .map_with(
+                    self.analysis_host.analysis(),
+                    |db, (file_id, (name, changes))| {
+                        // Plugin diagnostics have no fixes to apply
+                        do_parse_one(db, cfg, file_id, &name, include_generated, false, changes)
                     },
                 )
                 .collect::<Result<Vec<Option<_>>>>()?
@@ -533,11 +991,50 @@ impl<'a> Lints<'a> {
                 break;
             }
         }
-        self.changed_files.iter().for_each(|(file_id, name)| {
-            let bytes = self.vfs.file_contents(*file_id);
-            let document = Document::from_bytes(bytes.to_vec());
-            self.write_fix_result(*file_id, name, &document.content);
-        });
+        if self.dry_run {
+            let mut changed_files = self.changed_files.iter().collect::<Vec<_>>();
+            changed_files.sort_by(|(_, a), (_, b)| a.cmp(b));
+            for (file_id, name) in changed_files {
+                self.write_fix_diff(*file_id, name, cli)?;
+            }
+            return Ok(());
+        }
+        for (file_id, name) in self.changed_files.iter() {
+            let document = Document::from_bytes(self.vfs.file_contents(*file_id).to_vec());
+            // Written back in the encoding declared by the file
+            let bytes = encoding::encode(&document.content)
+                .with_context(|| format!("Cannot write the fixes to {}", name))?;
+            self.write_fix_result(*file_id, name, &bytes);
+        }
+        Ok(())
+    }
+
+    /// Report the combined changes of the fixes to a file as a unified
+    /// diff, with paths relative to the project root, as `git diff` does.
+    fn write_fix_diff(&self, file_id: FileId, name: &String, cli: &mut dyn Cli) -> Result<()> {
+        let analysis = self.analysis_host.analysis();
+        let after = analysis.file_text(file_id)?;
+        let before = self.originals.get(&file_id).unwrap_or(&after);
+        let unified = match diff_from_textedit(before, &after) {
+            (_, Some(unified)) if !unified.is_empty() => unified,
+            _ => return Ok(()),
+        };
+        let vfs_path = self.vfs.file_path(file_id);
+        let path = match analysis.project_data(file_id)? {
+            Some(project_data) => reporting::get_relative_path(&project_data.root_dir, &vfs_path),
+            None => vfs_path
+                .as_path()
+                .map_or(Path::new(name.as_str()), |path| path.as_ref()),
+        };
+        let diff = format!(
+            "--- a/{}\n+++ b/{}\n{unified}",
+            path.display(),
+            path.display()
+        );
+        match self.to {
+            Some(to) => fs::write(to.join(format!("{}.diff", name)), diff)?,
+            None => write!(cli, "{diff}")?,
+        }
         Ok(())
     }
 
@@ -546,96 +1043,55 @@ impl<'a> Lints<'a> {
         format_normal: bool,
         cli: &mut dyn Cli,
     ) -> Result<Vec<FixResult>> {
-        // Only apply a single fix, then re-parse. This avoids potentially
-        // conflicting changes.
-        let changes = self
+        // Only apply a single fix per file, then re-parse. This avoids
+        // potentially conflicting changes. The files are independent, so
+        // their fixes are computed in parallel, and reported in order.
+        let analysis = self.analysis_host.analysis();
+        let fixed = self
             .diags
-            .iter()
-            .flat_map(|(m, file_id, ds)| {
-                ds.iter().next().map_or(Ok(vec![]), |d| {
-                    self.apply_fixes(m, d, *file_id, format_normal, cli)
-                })
+            .par_iter()
+            .filter_map(|(name, file_id, ds)| Some((name, *file_id, ds.first()?)))
+            .map_with(analysis.clone(), |db, (name, file_id, diagnostic)| {
+                let changed = diagnostic.fixes.as_ref().map(|fixes| {
+                    fixes
+                        .iter()
+                        .filter_map(|fix| apply_one_fix(db, fix, name, &diagnostic.code))
+                        .collect::<Vec<FixResult>>()
+                });
+                (name, file_id, diagnostic, changed)
             })
-            .flatten()
-            .collect::<Vec<FixResult>>();
-        Ok(changes)
-    }
-
-    /// Apply any assists included in the diagnostic
-    fn apply_fixes(
-        &self,
-        name: &String,
-        diagnostic: &diagnostics::Diagnostic,
-        file_id: FileId,
-        format_normal: bool,
-        cli: &mut dyn Cli,
-    ) -> Result<Vec<FixResult>> {
-        if let Some(fixes) = &diagnostic.fixes {
+            .collect::<Vec<_>>();
+        let mut changes = Vec::new();
+        for (name, file_id, diagnostic, changed) in fixed {
+            let changed = match changed {
+                Some(changed) => changed,
+                None => bail!("No fixes in {:?}", diagnostic),
+            };
             if format_normal {
                 writeln!(cli, "---------------------------------------------\n")?;
                 writeln!(cli, "Applying fix in module '{name}' for")?;
-                print_diagnostic(diagnostic, &self.analysis_host.analysis(), file_id, cli)?;
-            }
-            let changed = fixes
-                .iter()
-                .filter_map(|fix| self.apply_one_fix(fix, name))
-                .collect::<Vec<FixResult>>();
-            if format_normal {
-                changed.iter().for_each(|r| {
+                print_diagnostic(diagnostic, &analysis, file_id, cli)?;
+                for r in &changed {
                     if let Some(unified) = &r.diff {
-                        _ = writeln!(cli, "{unified}");
+                        writeln!(cli, "{unified}")?;
                     }
-                });
+                }
             }
-            Ok(changed)
-        } else {
-            bail!("No fixes in {:?}", diagnostic);
+            changes.extend(changed);
         }
+        Ok(changes)
     }
-
-    /// Apply a single assist
-    fn apply_one_fix(&self, fix: &Assist, name: &String) -> Option<FixResult> {
-        let source_change = fix.source_change.as_ref()?;
-        let file_id = *source_change.source_file_edits.keys().next().unwrap();
-        let mut actual = self
-            .analysis_host
-            .analysis()
-            .file_text(file_id)
-            .ok()?
-            .to_string();
-        let original = actual.clone();
-
-        for edit in source_change.source_file_edits.values() {
-            // The invariant for a `TextEdit` requires that they
-            // disjoint and sorted by `delete`
-            edit.apply(&mut actual);
-        }
-        let (diff, unified) = diff_from_textedit(&original, &actual);
-        let changes = diff
-            .iter()
-            .filter_map(|d| form_range_from_diff(&self.analysis_host.analysis(), file_id, d))
-            .collect::<Vec<_>>();
-
-        Some(FixResult {
-            file_id,
-            name: name.clone(),
-            source: actual,
-            changes,
-            diff: unified,
-        })
-    }
-
-    fn write_fix_result(&self, file_id: FileId, name: &String, actual: &String) -> Option<()> {
+    fn write_fix_result(&self, file_id: FileId, name: &String, actual: &[u8]) -> Option<()> {
         Some(if self.in_place {
             let file_path = self.vfs.file_path(file_id);
             let to_path = file_path.as_path()?;
             let mut output = File::create(to_path).ok()?;
-            write!(output, "{actual}").ok()?;
+            output.write_all(actual).ok()?;
         } else {
             if let Some(to) = self.to {
                 let to_path = to.join(format!("{}.erl", name));
                 let mut output = File::create(to_path).ok()?;
-                write!(output, "{actual}").ok()?;
+                output.write_all(actual).ok()?;
             } else {
                 return None;
             }
@@ -643,6 +1099,52 @@ impl<'a> Lints<'a> {
     }
 }
 
+/// Apply a single assist
+fn apply_one_fix(
+    analysis: &Analysis,
+    fix: &Assist,
+    name: &String,
+    code: &DiagnosticCode,
+) -> Option<FixResult> {
+    let source_change = fix.source_change.as_ref()?;
+    let file_id = *source_change.source_file_edits.keys().next().unwrap();
+    let mut actual = analysis.file_text(file_id).ok()?.to_string();
+    let original = actual.clone();
+
+    for edit in source_change.source_file_edits.values() {
+        // The invariant for a `TextEdit` requires that they
+        // disjoint and sorted by `delete`
+        edit.apply(&mut actual);
+    }
+    let (diff, unified) = diff_from_textedit(&original, &actual);
+    let changes = diff
+        .iter()
+        .filter_map(|d| form_range_from_diff(analysis, file_id, d))
+        .collect::<Vec<_>>();
+
+    Some(FixResult {
+        file_id,
+        name: name.clone(),
+        source: actual,
+        changes,
+        diff: unified,
+        code: code.clone(),
+        assist: fix.id.0,
+    })
+}
+
+/// Split a unified diff into its hunks, each starting with its `@@` header.
+fn hunks(unified: &str) -> Vec<String> {
+    let mut hunks: Vec<String> = Vec::new();
+    for line in unified.split_inclusive('\n') {
+        match hunks.last_mut() {
+            Some(hunk) if !line.starts_with("@@") => hunk.push_str(line),
+            _ => hunks.push(line.to_string()),
+        }
+    }
+    hunks
+}
+
 /// Take the diff location, and expand it to the start and end line of
 /// its enclosing form.
 fn form_range_from_diff(
@@ -665,3 +1167,71 @@ fn form_range_from_diff(
     let end_line = line_index.line_col(range.end()).line;
     Some((start_line, end_line))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(repo: &git2::Repository, message: &str) {
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("elp", "elp@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<_> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn read_files_at_revisions() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(tmp.path()).unwrap();
+        let repo = git2::Repository::init(&root).unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/a.erl"), "-module(a).\n").unwrap();
+        commit(&repo, "first");
+        fs::write(root.join("src/a.erl"), "-module(a).\nf() -> ok.\n").unwrap();
+        fs::write(root.join("src/b.erl"), "-module(b).\n").unwrap();
+        commit(&repo, "second");
+
+        let path = |path: &Path| VfsPath::new_real_path(path.display().to_string());
+        let files = vec![
+            (FileId(0), path(&root.join("src/a.erl"))),
+            (FileId(1), path(&root.join("src/b.erl"))),
+            (FileId(2), path(Path::new("/outside/c.erl"))),
+        ];
+        let texts = |rev: &str| {
+            read_revision(&root, rev, files.clone().into_iter())
+                .unwrap()
+                .into_iter()
+                .map(|(file_id, _, text)| (file_id, text))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            texts("HEAD~1"),
+            vec![
+                (FileId(0), Some("-module(a).\n".to_string())),
+                (FileId(1), None)
+            ]
+        );
+        assert_eq!(
+            texts("HEAD"),
+            vec![
+                (FileId(0), Some("-module(a).\nf() -> ok.\n".to_string())),
+                (FileId(1), Some("-module(b).\n".to_string())),
+            ]
+        );
+        assert!(read_revision(&root, "no-such-rev", files.into_iter()).is_err());
+    }
+}
@@ -23,14 +23,28 @@ use elp_log::FileLogger;
 use elp_log::Logger;
 use lsp_server::Connection;
 
+mod apply_assist_cli;
 mod args;
+mod bug_report_cli;
 mod build_info_cli;
+mod cache_cli;
+mod ci_cli;
+mod dead_code_cli;
 mod elp_parse_cli;
 mod eqwalizer_cli;
 mod erlang_service_cli;
+mod expand_cli;
+mod explain_cli;
+mod format_cli;
+mod glean_cli;
+mod in_memory_cli;
+mod includes_cli;
 mod lint_cli;
 mod reporting;
+mod sarif;
+mod shard_cli;
 mod shell;
+mod watch;
 
 // Use jemalloc as the global allocator
 #[cfg(not(target_env = "msvc"))]
@@ -82,6 +96,18 @@ fn try_main(cli: &mut dyn Cli, args: Args) -> Result<()> {
         }
         args::Command::Version(_) => writeln!(cli, "elp {}", elp::version())?,
         args::Command::Shell(args) => shell::run_shell(&args, cli)?,
+        args::Command::ShardTests(args) => shard_cli::shard_tests(&args, cli)?,
+        args::Command::ApplyAssist(args) => apply_assist_cli::apply_assist(&args, cli)?,
+        args::Command::Ci(args) => ci_cli::run_ci(&args, cli)?,
+        args::Command::Glean(args) => glean_cli::index_project(&args, cli)?,
+        args::Command::Format(args) => format_cli::format(&args, cli)?,
+        args::Command::BugReport(args) => bug_report_cli::bug_report(&args, cli)?,
+        args::Command::DeadCode(args) => dead_code_cli::dead_code(&args, cli)?,
+        args::Command::CacheClear(args) => cache_cli::cache_clear(&args, cli)?,
+        args::Command::Includes(args) => includes_cli::includes(&args, cli)?,
+        args::Command::Explain(args) => explain_cli::explain(&args, cli)?,
+        args::Command::Expand(args) => expand_cli::expand(&args, cli)?,
+        args::Command::InMemory(args) => in_memory_cli::in_memory(&args, cli)?,
         args::Command::Help() => {
             let help = batteries::get_usage(args::args());
             writeln!(cli, "{}", help)?
@@ -140,6 +166,7 @@ mod tests {
 
     use bpaf::Args;
     use elp::cli::Fake;
+    use expect_test::expect;
     use expect_test::expect_file;
     use expect_test::ExpectFile;
     use tempfile::Builder;
@@ -633,6 +660,68 @@ mod tests {
         .expect("Bad test");
     }
 
+    #[test_case(false ; "rebar")]
+    #[test_case(true  ; "buck")]
+    fn lint_dry_run(buck: bool) {
+        let tmp_dir = TempDir::new().expect("Could not create temporary directory");
+        let tmp_path = tmp_dir.path();
+        check_lint_fix(
+            args_vec![
+                "lint",
+                "--module",
+                "lints",
+                "--diagnostic-filter",
+                "P1700",
+                "--apply-fix",
+                "--dry-run"
+            ],
+            "diagnostics",
+            expect_file!("../resources/test/diagnostics/parse_elp_lint_fix_dry_run.stdout"),
+            101,
+            buck,
+            None,
+            &tmp_path,
+            Path::new("../resources/test/lint/head_mismatch"),
+            &[],
+            false,
+        )
+        .expect("Bad test");
+    }
+
+    #[test_case(false ; "rebar")]
+    #[test_case(true  ; "buck")]
+    fn lint_dry_run_using_to_dir(buck: bool) {
+        let tmp_dir = TempDir::new().expect("Could not create temporary directory");
+        let tmp_path = tmp_dir.path();
+        fs::create_dir_all(tmp_path).expect("Could not create temporary directory path");
+        check_lint_fix(
+            args_vec![
+                "lint",
+                "--module",
+                "lints",
+                "--diagnostic-filter",
+                "P1700",
+                "--format",
+                "json",
+                "--to",
+                tmp_path,
+                "--apply-fix",
+                "--dry-run"
+            ],
+            "diagnostics",
+            expect_file!("../resources/test/diagnostics/parse_elp_lint_fix_json.stdout"),
+            101,
+            buck,
+            None,
+            &tmp_path,
+            Path::new("../resources/test/lint/head_mismatch"),
+            &[("lints.diff", "lints.diff")],
+            false,
+        )
+        .expect("Bad test");
+        assert!(!tmp_path.join("lints.erl").exists());
+    }
+
     #[test]
     fn lint_applies_fix_in_place() {
         // These tests make changes in the source tree.
@@ -720,6 +809,50 @@ mod tests {
         expected.assert_eq(&stdout);
     }
 
+    #[test]
+    fn explain_code() {
+        let (stdout, stderr, code) = elp(args_vec!["explain", "trivial_match"]);
+        assert_eq!(code, 0);
+        assert!(stderr.is_empty());
+        expect_file!["../resources/test/explain_trivial_match.stdout"].assert_eq(&stdout);
+
+        let (stdout, stderr, code) = elp(args_vec!["explain", "not_a_code"]);
+        assert_eq!(code, 101);
+        assert!(stdout.is_empty());
+        assert_eq!(stderr, "Unknown diagnostic code: not_a_code\n");
+    }
+
+    #[test]
+    fn in_memory_tarball() {
+        let tmp = Builder::new().prefix("elp_").tempdir().unwrap();
+        let src = tmp.path().join("project/app_a/src");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(
+            src.join("app_a.erl"),
+            "-module(app_a).\n-define(UNUSED, 1).\n",
+        )
+        .unwrap();
+        let tarball = tmp.path().join("project.tar.gz");
+        let status = std::process::Command::new("tar")
+            .arg("-czf")
+            .arg(&tarball)
+            .arg("-C")
+            .arg(tmp.path().join("project"))
+            .arg("app_a")
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let (stdout, stderr, code) = elp(args_vec!["in-memory", "--tarball", &tarball]);
+        assert_eq!(code, 0, "stderr:\n{}", stderr);
+        assert!(stderr.is_empty());
+        expect![[r#"
+            /app_a/src/app_a.erl:
+                  1:8-1:14::[Warning] [W0002] Unused macro (UNUSED)
+        "#]]
+        .assert_eq(&stdout);
+    }
+
     fn simple_snapshot(
         args: Vec<OsString>,
         project: &str,
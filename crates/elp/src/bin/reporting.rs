@@ -34,6 +34,11 @@ use elp_ide::TextRange;
 use indicatif::ProgressBar;
 use lazy_static::lazy_static;
 
+use crate::sarif;
+use crate::sarif::Region;
+use crate::sarif::SarifLevel;
+use crate::sarif::SarifResult;
+
 pub trait Reporter {
     fn write_eqwalizer_diagnostics(
         &mut self,
@@ -44,6 +49,8 @@ pub trait Reporter {
     fn write_file_advice(&mut self, file_id: FileId, description: String) -> Result<()>;
     fn write_error_count(&mut self) -> Result<()>;
     fn write_stats(&mut self, count: u64, total: u64) -> Result<()>;
+    /// Called once everything has been reported
+    fn finish(&mut self) -> Result<()>;
 
     fn progress(&self, len: u64, prefix: &'static str) -> ProgressBar;
 }
@@ -71,6 +78,15 @@ pub struct JsonReporter<'a> {
     cli: &'a mut dyn Cli,
 }
 
+/// Collects the diagnostics, to write them as a single SARIF log once
+/// finished.
+pub struct SarifReporter<'a> {
+    analysis: &'a Analysis,
+    loaded: &'a LoadResult,
+    cli: &'a mut dyn Cli,
+    results: Vec<SarifResult>,
+}
+
 impl<'a> PrettyReporter<'a> {
     pub fn new(analysis: &'a Analysis, loaded: &'a LoadResult, cli: &'a mut dyn Cli) -> Self {
         Self {
@@ -193,6 +209,10 @@ impl<'a> Reporter for PrettyReporter<'a> {
         Ok(())
     }
 
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+
     fn progress(&self, len: u64, prefix: &'static str) -> ProgressBar {
         self.cli.progress(len, prefix)
     }
@@ -284,6 +304,114 @@ impl<'a> Reporter for JsonReporter<'a> {
         Ok(())
     }
 
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn progress(&self, len: u64, prefix: &'static str) -> ProgressBar {
+        self.cli.progress(len, prefix)
+    }
+}
+
+impl<'a> SarifReporter<'a> {
+    pub fn new(analysis: &'a Analysis, loaded: &'a LoadResult, cli: &'a mut dyn Cli) -> Self {
+        Self {
+            analysis,
+            loaded,
+            cli,
+            results: Vec::new(),
+        }
+    }
+
+    fn relative_path(&self, file_id: FileId) -> Result<String> {
+        let file_path = &self.loaded.vfs.file_path(file_id);
+        let root_path = &self
+            .analysis
+            .project_data(file_id)?
+            .with_context(|| "could not find project data")?
+            .root_dir;
+        Ok(get_relative_path(root_path, file_path)
+            .display()
+            .to_string())
+    }
+}
+
+impl<'a> Reporter for SarifReporter<'a> {
+    fn write_eqwalizer_diagnostics(
+        &mut self,
+        file_id: FileId,
+        diagnostics: &[EqwalizerDiagnostic],
+    ) -> Result<()> {
+        let line_index = self.analysis.line_index(file_id)?;
+        let eqwalizer_enabled = self.analysis.is_eqwalizer_enabled(file_id, true).unwrap();
+        // As for JSON, errors in modules not opted in are still reported
+        let level = if eqwalizer_enabled {
+            SarifLevel::Error
+        } else {
+            SarifLevel::Note
+        };
+        let path = self.relative_path(file_id)?;
+        for diagnostic in diagnostics {
+            let message = match &diagnostic.explanation {
+                Some(explanation) => format!("{}\n{}", diagnostic.message, explanation),
+                None => diagnostic.message.clone(),
+            };
+            let mut result = SarifResult::new(
+                diagnostic.code.clone(),
+                level,
+                message,
+                path.clone(),
+                Region::new(&line_index, diagnostic.range),
+            );
+            result.help_uri = Some(diagnostic.uri.clone());
+            self.results.push(result);
+        }
+        Ok(())
+    }
+
+    fn write_parse_diagnostics(&mut self, diagnostics: &[ParseDiagnostic]) -> Result<()> {
+        for diagnostic in diagnostics {
+            let region = match diagnostic.range {
+                Some(range) => Region::new(&self.analysis.line_index(diagnostic.file_id)?, range),
+                None => Region::line(diagnostic.line_num),
+            };
+            self.results.push(SarifResult::new(
+                "parse_error".to_string(),
+                SarifLevel::Error,
+                diagnostic.msg.clone(),
+                diagnostic.relative_path.display().to_string(),
+                region,
+            ));
+        }
+        Ok(())
+    }
+
+    fn write_file_advice(&mut self, file_id: FileId, description: String) -> Result<()> {
+        let path = self.relative_path(file_id)?;
+        self.results.push(SarifResult::new(
+            "advice".to_string(),
+            SarifLevel::Note,
+            description,
+            path,
+            Region::line(1),
+        ));
+        Ok(())
+    }
+
+    fn write_error_count(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn write_stats(&mut self, _count: u64, _total: u64) -> Result<()> {
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        let log = sarif::to_sarif("elp eqwalize", &self.results);
+        writeln!(self.cli, "{}", serde_json::to_string(&log)?)?;
+        Ok(())
+    }
+
     fn progress(&self, len: u64, prefix: &'static str) -> ProgressBar {
         self.cli.progress(len, prefix)
     }
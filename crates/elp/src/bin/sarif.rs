@@ -0,0 +1,252 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! SARIF 2.1.0 logs, as read by code scanning tools, for the
+//! `--format sarif` output of the CLI commands.
+
+use elp_ide::diagnostics::Diagnostic;
+use elp_ide::diagnostics::Severity;
+use elp_ide::elp_ide_db::LineIndex;
+use elp_syntax::TextRange;
+use itertools::Itertools;
+use serde::Serialize;
+use serde_json::json;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SarifLevel {
+    Error,
+    Warning,
+    Note,
+}
+
+impl From<Severity> for SarifLevel {
+    fn from(severity: Severity) -> SarifLevel {
+        match severity {
+            Severity::Error => SarifLevel::Error,
+            Severity::Warning => SarifLevel::Warning,
+            Severity::WeakWarning => SarifLevel::Note,
+        }
+    }
+}
+
+/// One-based lines and columns, columns counted in UTF-16 code units
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Region {
+    pub start_line: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_column: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_column: Option<u32>,
+}
+
+impl Region {
+    pub fn new(line_index: &LineIndex, range: TextRange) -> Region {
+        let start = line_index.line_col(range.start());
+        let end = line_index.line_col(range.end());
+        Region {
+            start_line: start.line + 1,
+            start_column: Some(start.col_utf16 + 1),
+            end_line: Some(end.line + 1),
+            end_column: Some(end.col_utf16 + 1),
+        }
+    }
+
+    /// A whole line, one-based
+    pub fn line(line: u32) -> Region {
+        Region {
+            start_line: line,
+            start_column: None,
+            end_line: None,
+            end_column: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SarifResult {
+    pub rule_id: String,
+    /// A short description of the rule, e.g. the label of a diagnostic code
+    pub rule_description: Option<String>,
+    pub help_uri: Option<String>,
+    pub level: SarifLevel,
+    pub message: String,
+    /// Relative to the project root
+    pub path: String,
+    pub region: Region,
+    /// Descriptions of the fixes available, without their edits
+    pub fixes: Vec<String>,
+}
+
+impl SarifResult {
+    pub fn new(
+        rule_id: String,
+        level: SarifLevel,
+        message: String,
+        path: String,
+        region: Region,
+    ) -> SarifResult {
+        SarifResult {
+            rule_id,
+            rule_description: None,
+            help_uri: None,
+            level,
+            message,
+            path,
+            region,
+            fixes: Vec::new(),
+        }
+    }
+
+    /// A result for a native diagnostic, its code being the rule
+    pub fn from_diagnostic(diagnostic: &Diagnostic, line_index: &LineIndex, path: &str) -> Self {
+        let mut result = SarifResult::new(
+            diagnostic.code.as_code(),
+            diagnostic.severity.into(),
+            diagnostic.message.clone(),
+            path.to_string(),
+            Region::new(line_index, diagnostic.range),
+        );
+        result.rule_description = Some(diagnostic.code.as_label());
+        result.fixes = diagnostic
+            .fixes
+            .iter()
+            .flatten()
+            .map(|fix| fix.label.to_string())
+            .collect();
+        result
+    }
+}
+
+/// A log with a single run of the tool
+pub fn to_sarif(tool: &str, results: &[SarifResult]) -> serde_json::Value {
+    sarif_log(vec![sarif_run(tool, results)])
+}
+
+pub fn sarif_log(runs: Vec<serde_json::Value>) -> serde_json::Value {
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": runs,
+    })
+}
+
+/// A run of the tool, with a rule for each distinct rule id of the
+/// results, which refer to it by index.
+pub fn sarif_run(tool: &str, results: &[SarifResult]) -> serde_json::Value {
+    let rules: Vec<&SarifResult> = results
+        .iter()
+        .unique_by(|result| &result.rule_id)
+        .sorted_by(|a, b| a.rule_id.cmp(&b.rule_id))
+        .collect();
+    let rule_index = |rule_id: &str| rules.iter().position(|rule| rule.rule_id == rule_id);
+    let results: Vec<_> = results
+        .iter()
+        .map(|result| {
+            let fixes: Vec<_> = result
+                .fixes
+                .iter()
+                .map(|fix| json!({ "description": { "text": fix } }))
+                .collect();
+            let mut res = json!({
+                "ruleId": result.rule_id,
+                "ruleIndex": rule_index(&result.rule_id),
+                "level": result.level,
+                "message": { "text": result.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": result.path },
+                        "region": result.region,
+                    }
+                }],
+            });
+            if !fixes.is_empty() {
+                res["fixes"] = json!(fixes);
+            }
+            res
+        })
+        .collect();
+    let rules: Vec<_> = rules
+        .into_iter()
+        .map(|rule| {
+            let mut res = json!({
+                "id": rule.rule_id,
+                "defaultConfiguration": { "level": rule.level },
+            });
+            if let Some(description) = &rule.rule_description {
+                res["name"] = json!(description);
+                res["shortDescription"] = json!({ "text": description });
+            }
+            if let Some(help_uri) = &rule.help_uri {
+                res["helpUri"] = json!(help_uri);
+            }
+            res
+        })
+        .collect();
+    json!({
+        "tool": {
+            "driver": {
+                "name": tool,
+                "version": elp::version(),
+                "rules": rules,
+            }
+        },
+        "results": results,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_are_shared_by_results() {
+        let mut unused = SarifResult::new(
+            "W0010".to_string(),
+            SarifLevel::Warning,
+            "this variable is unused".to_string(),
+            "src/a.erl".to_string(),
+            Region::line(3),
+        );
+        unused.rule_description = Some("unused_function_args".to_string());
+        unused.fixes = vec!["Prefix variable with an underscore".to_string()];
+        let parse_error = SarifResult::new(
+            "P1700".to_string(),
+            SarifLevel::Error,
+            "head mismatch".to_string(),
+            "src/b.erl".to_string(),
+            Region::line(1),
+        );
+        let run = sarif_run("elp lint", &[unused.clone(), parse_error, unused]);
+
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0]["id"], "P1700");
+        assert_eq!(rules[1]["id"], "W0010");
+        assert_eq!(rules[1]["shortDescription"]["text"], "unused_function_args");
+        assert_eq!(rules[1]["defaultConfiguration"]["level"], "warning");
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0]["ruleIndex"], 1);
+        assert_eq!(results[1]["ruleIndex"], 0);
+        assert_eq!(
+            results[0]["locations"][0]["physicalLocation"]["region"],
+            json!({ "startLine": 3 })
+        );
+        assert_eq!(
+            results[0]["fixes"][0]["description"]["text"],
+            "Prefix variable with an underscore"
+        );
+        assert!(results[1].get("fixes").is_none());
+    }
+}
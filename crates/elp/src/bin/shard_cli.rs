@@ -0,0 +1,246 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fs;
+use std::io::Write;
+
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use elp::build::load;
+use elp::cli::Cli;
+use elp::otp_file_to_ignore;
+use elp_ide::elp_ide_db::elp_base_db::IncludeOtp;
+use elp_ide::RunnableKind;
+use elp_project_model::AppType;
+use elp_project_model::DiscoverConfig;
+use elp_project_model::ProjectBuildData;
+use fxhash::FxHashMap;
+use serde::Serialize;
+
+use crate::args::ShardTests;
+
+/// A single common test case, as discovered from the runnables of
+/// a suite.
+#[derive(Debug, Clone, Serialize)]
+struct TestCase {
+    /// Same id as used by the `Run Test` code lens, `suite - group.case`
+    id: String,
+    suite: String,
+    group: Option<String>,
+    case: String,
+    /// Buck target owning the suite, for buck projects
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<String>,
+    /// Buck test regex selecting the case, for buck projects
+    #[serde(skip_serializing_if = "Option::is_none")]
+    regex: Option<String>,
+    weight: f64,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Shard {
+    weight: f64,
+    tests: Vec<TestCase>,
+}
+
+pub fn shard_tests(args: &ShardTests, cli: &mut dyn Cli) -> Result<()> {
+    if args.shards == 0 {
+        bail!("--shards must be at least 1");
+    }
+    let timings = match &args.timings {
+        Some(path) => read_timings(
+            &fs::read_to_string(path)
+                .with_context(|| format!("could not read timings from {}", path.display()))?,
+        )?,
+        None => FxHashMap::default(),
+    };
+
+    let config = DiscoverConfig::new(args.rebar, &args.profile);
+    let loaded = load::load_project_at(cli, &args.project, config, IncludeOtp::No)?;
+    let analysis = loaded.analysis_host.analysis();
+    let target_for_path = match &loaded.project.project_build_data {
+        ProjectBuildData::Buck(buck) => Some(&buck.target_info.path_to_target_name),
+        _ => None,
+    };
+
+    let mut tests = Vec::new();
+    let module_index = analysis.module_index(loaded.project_id)?;
+    for (name, _source, file_id) in module_index.iter_own() {
+        if !name.as_str().ends_with("_SUITE")
+            || otp_file_to_ignore(&analysis, file_id)
+            || analysis.file_app_type(file_id)? == Some(AppType::Dep)
+        {
+            continue;
+        }
+        let target = target_for_path.and_then(|targets| {
+            let path = loaded.vfs.file_path(file_id);
+            let path = path.as_path()?;
+            targets.get(path).map(|target| target.to_string())
+        });
+        for runnable in analysis.runnables(file_id)? {
+            if let RunnableKind::Test {
+                suite, case, group, ..
+            } = &runnable.kind
+            {
+                let id = runnable.id();
+                let group = group.name();
+                tests.push(TestCase {
+                    weight: timings.get(&id).copied().unwrap_or(f64::NAN),
+                    suite: suite.clone(),
+                    group: if group.is_empty() { None } else { Some(group) },
+                    case: case.clone(),
+                    regex: target.as_ref().map(|_| runnable.regex()),
+                    target: target.clone(),
+                    id,
+                });
+            }
+        }
+    }
+
+    let shards = plan(tests, args.shards);
+    if args.is_format_json() {
+        cli.write_all(serde_json::to_string(&shards)?.as_bytes())?;
+        writeln!(cli)?;
+    } else {
+        for (idx, shard) in shards.iter().enumerate() {
+            writeln!(
+                cli,
+                "# shard {}/{}: {} tests, estimated {:.2}s",
+                idx + 1,
+                shards.len(),
+                shard.tests.len(),
+                shard.weight
+            )?;
+            for test in &shard.tests {
+                writeln!(cli, "{}", test_selector(test))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Read historical timings, a JSON object mapping test ids (as
+/// reported by the `Run Test` code lens) to durations in seconds.
+fn read_timings(contents: &str) -> Result<FxHashMap<String, f64>> {
+    serde_json::from_str(contents).context("timings must be a JSON object of test ids to seconds")
+}
+
+/// Assign tests to `count` shards, balancing their total weight.
+///
+/// Tests without timing data are given the mean weight of the tests
+/// that have it, or a weight of one if there is no timing data at
+/// all. Tests are then assigned heaviest first to the currently
+/// lightest shard.
+fn plan(mut tests: Vec<TestCase>, count: usize) -> Vec<Shard> {
+    let known: Vec<f64> = tests
+        .iter()
+        .map(|test| test.weight)
+        .filter(|weight| !weight.is_nan())
+        .collect();
+    let default_weight = if known.is_empty() {
+        1.0
+    } else {
+        known.iter().sum::<f64>() / known.len() as f64
+    };
+    for test in &mut tests {
+        if test.weight.is_nan() {
+            test.weight = default_weight;
+        }
+    }
+    // Sort by id first, so the plan is deterministic for equal weights
+    tests.sort_by(|a, b| a.id.cmp(&b.id));
+    tests.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+
+    let mut shards: Vec<Shard> = (0..count).map(|_| Shard::default()).collect();
+    for test in tests {
+        let lightest = shards
+            .iter_mut()
+            .min_by(|a, b| a.weight.total_cmp(&b.weight))
+            .unwrap();
+        lightest.weight += test.weight;
+        lightest.tests.push(test);
+    }
+    for shard in &mut shards {
+        shard.tests.sort_by(|a, b| a.id.cmp(&b.id));
+    }
+    shards
+}
+
+/// How to select a single test case on the command line, either a
+/// buck target with a test regex, or rebar3 ct options.
+fn test_selector(test: &TestCase) -> String {
+    match (&test.target, &test.regex) {
+        (Some(target), Some(regex)) => format!("{target} -- --regex '{regex}'"),
+        _ => {
+            let group = match &test.group {
+                Some(group) => format!(" --group={group}"),
+                None => String::new(),
+            };
+            format!("--suite={}{} --case={}", test.suite, group, test.case)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(id: &str, weight: f64) -> TestCase {
+        TestCase {
+            id: id.to_string(),
+            suite: "a_SUITE".to_string(),
+            group: None,
+            case: id.to_string(),
+            target: None,
+            regex: None,
+            weight,
+        }
+    }
+
+    fn ids(shards: &[Shard]) -> Vec<(f64, Vec<&str>)> {
+        shards
+            .iter()
+            .map(|shard| {
+                let ids = shard.tests.iter().map(|t| t.id.as_str()).collect();
+                (shard.weight, ids)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn balances_by_weight() {
+        let tests = vec![
+            case("a", 5.0),
+            case("b", 4.0),
+            case("c", 3.0),
+            case("d", 3.0),
+            case("e", 1.0),
+        ];
+        assert_eq!(
+            ids(&plan(tests, 2)),
+            vec![(8.0, vec!["a", "d"]), (8.0, vec!["b", "c", "e"])]
+        );
+    }
+
+    #[test]
+    fn unknown_timings_use_mean() {
+        let tests = vec![case("a", 4.0), case("b", 2.0), case("c", f64::NAN)];
+        assert_eq!(
+            ids(&plan(tests, 2)),
+            vec![(4.0, vec!["a"]), (5.0, vec!["b", "c"])]
+        );
+    }
+
+    #[test]
+    fn more_shards_than_tests() {
+        let tests = vec![case("a", f64::NAN)];
+        assert_eq!(ids(&plan(tests, 2)), vec![(1.0, vec!["a"]), (0.0, vec![])]);
+    }
+}
@@ -18,7 +18,9 @@ use elp::build::load;
 use elp::build::types::LoadResult;
 use elp::cli::Cli;
 use elp::document::Document;
+use elp_ide::elp_ide_db::elp_base_db::encoding;
 use elp_ide::elp_ide_db::elp_base_db::AbsPathBuf;
+use elp_ide::elp_ide_db::elp_base_db::FileId;
 use elp_ide::elp_ide_db::elp_base_db::IncludeOtp;
 use elp_ide::elp_ide_db::elp_base_db::SourceDatabase;
 use elp_ide::elp_ide_db::elp_base_db::SourceDatabaseExt;
@@ -141,6 +143,7 @@ impl ShellCommand {
                             project,
                             profile,
                             rebar,
+                            watch: false,
                             module: module.into(),
                         })));
                     }
@@ -191,6 +194,7 @@ impl ShellCommand {
                         rebar,
                         format: None,
                         include_generated,
+                        jobs: None,
                     })));
                 }
                 "exit" | "quit" => return Ok(Some(ShellCommand::Quit)),
@@ -213,12 +217,12 @@ COMMANDS:
         --include-generated    Include generated modules
 ";
 
-// Adapted from elp::server
-fn process_changes_to_vfs_store(loaded: &mut LoadResult) -> bool {
+// Adapted from elp::server. Returns the changed files.
+pub(crate) fn process_changes_to_vfs_store(loaded: &mut LoadResult) -> Vec<FileId> {
     let changed_files = loaded.vfs.take_changes();
 
     if changed_files.is_empty() {
-        return false;
+        return vec![];
     }
 
     let raw_database = loaded.analysis_host.raw_database_mut();
@@ -252,7 +256,7 @@ fn process_changes_to_vfs_store(loaded: &mut LoadResult) -> bool {
         }
     }
 
-    true
+    changed_files.iter().map(|file| file.file_id).collect()
 }
 
 fn update_changes(
@@ -270,7 +274,7 @@ fn update_changes(
             vfs.set_file_contents(vfs_path, None);
         } else {
             let contents = fs::read(&path).expect(&format!("Cannot read created file {:?}", path));
-            vfs.set_file_contents(vfs_path, Some(contents));
+            vfs.set_file_contents(vfs_path, Some(encoding::to_utf8(contents)));
         }
     });
     process_changes_to_vfs_store(loaded);
@@ -0,0 +1,95 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `--watch`: after the first run of a command, watch the project files,
+//! and on each change check again the modules it affects, that is the
+//! changed modules and the modules including the changed headers.
+
+use std::collections::BTreeSet;
+use std::iter;
+
+use anyhow::Result;
+use crossbeam_channel::unbounded;
+use elp::build::types::LoadResult;
+use elp::cli::Cli;
+use elp::reload::ProjectFolders;
+use elp_ide::elp_ide_db::elp_base_db::encoding;
+use elp_ide::elp_ide_db::elp_base_db::loader;
+use elp_ide::elp_ide_db::elp_base_db::loader::Handle;
+use elp_ide::elp_ide_db::elp_base_db::FileId;
+use elp_ide::elp_ide_db::elp_base_db::IncludeOtp;
+use elp_ide::elp_ide_db::elp_base_db::ProjectApps;
+
+use crate::shell::process_changes_to_vfs_store;
+
+/// Call `check` with the modules affected by each change to the project
+/// files, until interrupted.
+pub fn watch(
+    cli: &mut dyn Cli,
+    loaded: &mut LoadResult,
+    mut check: impl FnMut(&mut dyn Cli, &LoadResult, Vec<FileId>) -> Result<()>,
+) -> Result<()> {
+    let (sender, receiver) = unbounded();
+    let mut loader =
+        vfs_notify::NotifyHandle::spawn(Box::new(move |msg| sender.send(msg).unwrap()));
+    let projects = [loaded.project.clone()];
+    let project_apps = ProjectApps::new(&projects, IncludeOtp::No);
+    let folders = ProjectFolders::new(&project_apps);
+    let watch = (0..folders.load.len()).collect();
+    loader.set_config(loader::Config {
+        load: folders.load,
+        watch,
+        version: 0,
+    });
+    writeln!(cli, "Watching for changes, press Ctrl-C to stop")?;
+
+    while let Ok(message) = receiver.recv() {
+        // A save may change several files, so the pending changes are
+        // checked together. Files loaded unchanged are not reported by
+        // the VFS.
+        for message in iter::once(message).chain(receiver.try_iter()) {
+            if let loader::Message::Loaded { files } = message {
+                for (path, contents) in files {
+                    loaded
+                        .vfs
+                        .set_file_contents(path.into(), contents.map(encoding::to_utf8));
+                }
+            }
+        }
+        let changed = process_changes_to_vfs_store(loaded);
+        if changed.is_empty() {
+            continue;
+        }
+        let modules = affected_modules(loaded, &changed)?;
+        if !modules.is_empty() {
+            check(cli, loaded, modules)?;
+        }
+    }
+    Ok(())
+}
+
+/// The modules of the project which are changed, or include a changed
+/// header, directly or not.
+fn affected_modules(loaded: &LoadResult, changed: &[FileId]) -> Result<Vec<FileId>> {
+    let analysis = loaded.analysis();
+    let graph = analysis.include_graph(loaded.project_id)?;
+    let mut affected = BTreeSet::new();
+    for &file_id in changed {
+        affected.insert(file_id);
+        affected.extend(graph.transitive_included_by(file_id));
+    }
+    let module_index = analysis.module_index(loaded.project_id)?;
+    let mut modules: Vec<_> = module_index
+        .iter_own()
+        .filter(|(_, _, file_id)| affected.contains(file_id))
+        .map(|(name, _, file_id)| (name.clone(), file_id))
+        .collect();
+    modules.sort();
+    Ok(modules.into_iter().map(|(_, file_id)| file_id).collect())
+}
@@ -16,8 +16,10 @@ use std::sync::Arc;
 use anyhow::Result;
 use crossbeam_channel::unbounded;
 use crossbeam_channel::Receiver;
+use elp_ide::elp_ide_db::elp_base_db::encoding;
 use elp_ide::elp_ide_db::elp_base_db::loader;
 use elp_ide::elp_ide_db::elp_base_db::loader::Handle;
+use elp_ide::elp_ide_db::elp_base_db::AbsPath;
 use elp_ide::elp_ide_db::elp_base_db::AbsPathBuf;
 use elp_ide::elp_ide_db::elp_base_db::FileSetConfig;
 use elp_ide::elp_ide_db::elp_base_db::IncludeOtp;
@@ -28,15 +30,31 @@ use elp_ide::elp_ide_db::elp_base_db::SourceDatabaseExt;
 use elp_ide::elp_ide_db::elp_base_db::SourceRoot;
 use elp_ide::elp_ide_db::elp_base_db::SourceRootId;
 use elp_ide::elp_ide_db::elp_base_db::Vfs;
+use elp_ide::elp_ide_db::elp_base_db::VfsPath;
+use elp_ide::elp_ide_db::EqwalizerCache;
+use elp_ide::erlang_service::ParseCache;
 use elp_ide::AnalysisHost;
+use elp_project_model::beam::BEAM_STUBS_DIR;
 use elp_project_model::DiscoverConfig;
 use elp_project_model::Project;
 use elp_project_model::ProjectManifest;
+use fxhash::FxHashSet;
 
 use crate::build::types::LoadResult;
 use crate::cli::Cli;
+use crate::document::Document;
 use crate::reload::ProjectFolders;
 
+/// Where results worth reusing in later runs are stored, relative to the
+/// project root. `elp cache clear` removes it.
+pub const CACHE_DIR: &str = ".elp/cache";
+
+/// The cache of this version of ELP, as its results may differ from
+/// those of other versions.
+fn cache_dir(root: &AbsPath) -> AbsPathBuf {
+    root.join(CACHE_DIR).join(crate::version())
+}
+
 pub fn load_project_at(
     cli: &dyn Cli,
     root: &Path,
@@ -56,6 +74,7 @@ pub fn load_project_at(
 }
 
 fn load_project(cli: &dyn Cli, project: Project, include_otp: IncludeOtp) -> Result<LoadResult> {
+    // The CLI loads a single project, the first of `ProjectApps`
     let project_id = ProjectId(0);
     let (sender, receiver) = unbounded();
     let mut vfs = Vfs::default();
@@ -65,6 +84,10 @@ fn load_project(cli: &dyn Cli, project: Project, include_otp: IncludeOtp) -> Res
         Box::new(loader)
     };
 
+    // Nothing watches the files of the project, so the stubs of the
+    // dependencies compiled before are written before they are loaded.
+    // The ones compiled later are loaded by `reload_beam_stubs`.
+    project.generate_beam_stubs();
     let projects = [project.clone()];
     let project_apps = ProjectApps::new(&projects, include_otp);
     let folders = ProjectFolders::new(&project_apps);
@@ -82,6 +105,8 @@ fn load_project(cli: &dyn Cli, project: Project, include_otp: IncludeOtp) -> Res
         &folders.file_set_config,
         &mut vfs,
         &receiver,
+        &cache_dir(&project.root()),
+        project_id,
     )?;
     Ok(LoadResult::new(
         analysis_host,
@@ -98,9 +123,15 @@ fn load_database(
     file_set_config: &FileSetConfig,
     vfs: &mut Vfs,
     receiver: &Receiver<loader::Message>,
+    cache_dir: &AbsPath,
+    project_id: ProjectId,
 ) -> Result<AnalysisHost> {
     let mut analysis_host = AnalysisHost::default();
     let db = analysis_host.raw_database_mut();
+    db.set_parse_cache(Some(ParseCache::new(cache_dir.join("ast").into())));
+    db.set_eqwalizer_cache(Some(EqwalizerCache::new(
+        cache_dir.join("eqwalizer").into(),
+    )));
 
     let pb = cli.progress(0, "Loading applications");
 
@@ -117,7 +148,7 @@ fn load_database(
             }
             loader::Message::Loaded { files } => {
                 for (path, contents) in files {
-                    vfs.set_file_contents(path.into(), contents);
+                    vfs.set_file_contents(path.into(), contents.map(encoding::to_utf8));
                 }
             }
         }
@@ -138,26 +169,15 @@ fn load_database(
     }
 
     project_apps.app_structure().apply(db);
+    db.update_parse_transforms_digests(project_id);
 
-    let project_id = ProjectId(0);
     db.ensure_erlang_service(project_id)?;
     let changes = vfs.take_changes();
     for file in changes {
         if file.exists() {
             let contents = vfs.file_contents(file.file_id).to_vec();
-            match String::from_utf8(contents) {
-                Ok(text) => {
-                    db.set_file_text(file.file_id, Arc::new(text));
-                }
-                Err(err) => {
-                    // Fall back to lossy latin1 loading of files.
-                    // This should only affect files from yaws, and
-                    // possibly OTP that are latin1 encoded.
-                    let contents = err.into_bytes();
-                    let text = contents.into_iter().map(|byte| byte as char).collect();
-                    db.set_file_text(file.file_id, Arc::new(text));
-                }
-            }
+            let document = Document::from_bytes(contents);
+            db.set_file_text(file.file_id, Arc::new(document.content));
         }
     }
 
@@ -165,3 +185,66 @@ fn load_database(
 
     Ok(analysis_host)
 }
+
+/// Load the BEAM stubs of the project again, once they were written
+/// after it was loaded, e.g. when its dependencies were compiled.
+pub fn reload_beam_stubs(loaded: &mut LoadResult) {
+    let stubs_dir = loaded.project.root().join(BEAM_STUBS_DIR);
+    // A directory per dependency, holding its stubs
+    let stubs: Vec<AbsPathBuf> = fs::read_dir(&stubs_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| fs::read_dir(entry.ok()?.path()).ok())
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? == "erl" {
+                Some(AbsPathBuf::assert(path))
+            } else {
+                None
+            }
+        })
+        .collect();
+    let current: FxHashSet<AbsPathBuf> = stubs.iter().cloned().collect();
+    let removed: Vec<VfsPath> = loaded
+        .vfs
+        .iter()
+        .filter_map(|(_, vfs_path)| {
+            let path = vfs_path.as_path()?;
+            if path.starts_with(&stubs_dir) && !current.contains(&path.to_path_buf()) {
+                Some(vfs_path.clone())
+            } else {
+                None
+            }
+        })
+        .collect();
+    for path in removed {
+        loaded.vfs.set_file_contents(path, None);
+    }
+    for path in stubs {
+        let contents = fs::read(&path).ok().map(encoding::to_utf8);
+        loaded.vfs.set_file_contents(path.into(), contents);
+    }
+
+    let changes = loaded.vfs.take_changes();
+    let db = loaded.analysis_host.raw_database_mut();
+    for file in &changes {
+        let text = if file.exists() {
+            let contents = loaded.vfs.file_contents(file.file_id).to_vec();
+            Document::from_bytes(contents).content
+        } else {
+            String::new()
+        };
+        db.set_file_text(file.file_id, Arc::new(text));
+    }
+    if changes.iter().any(|file| file.is_created_or_deleted()) {
+        let sets = loaded.file_set_config.partition(&loaded.vfs);
+        for (idx, set) in sets.into_iter().enumerate() {
+            let root_id = SourceRootId(idx as u32);
+            for file_id in set.iter() {
+                db.set_file_source_root(file_id, root_id);
+            }
+            db.set_source_root(root_id, Arc::new(SourceRoot::new(set)));
+        }
+    }
+}
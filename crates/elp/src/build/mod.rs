@@ -16,12 +16,17 @@ use elp_project_model::ProjectBuildData::Rebar;
 use crate::build::types::LoadResult;
 use crate::cli::Cli;
 
-pub fn compile_deps(loaded: &LoadResult, cli: &dyn Cli) -> Result<()> {
+pub fn compile_deps(loaded: &mut LoadResult, cli: &dyn Cli) -> Result<()> {
     match loaded.project.project_build_data {
         Rebar(_) => {
             let pb = cli.spinner("Compiling dependencies");
             loaded.project.compile_deps()?;
             loaded.update_erlang_service_paths();
+            loaded.update_parse_transforms_digests();
+            // The dependencies without sources may only be compiled now
+            if loaded.project.generate_beam_stubs() {
+                load::reload_beam_stubs(loaded);
+            }
             pb.finish();
         }
         _ => (),
@@ -100,4 +100,10 @@ impl LoadResult {
             .raw_database()
             .update_erlang_service_paths();
     }
+
+    pub fn update_parse_transforms_digests(&mut self) {
+        self.analysis_host
+            .raw_database_mut()
+            .update_parse_transforms_digests(self.project_id);
+    }
 }
@@ -8,15 +8,24 @@
  */
 
 use std::iter;
+use std::time::Duration;
 
 use elp_ide::diagnostics::DiagnosticCode;
 use elp_ide::diagnostics::DiagnosticsConfig;
 use elp_ide::elp_ide_assists::AssistConfig;
+use elp_ide::elp_ide_completion::CompletionConfig;
 use elp_ide::elp_ide_db::elp_base_db::AbsPathBuf;
 use elp_ide::elp_ide_db::helpers::SnippetCap;
+use elp_ide::elp_ide_db::EqwalizerClientConfig;
+use elp_ide::CodeLensConfig;
+use elp_ide::DocumentLinksConfig;
 use elp_ide::InlayHintsConfig;
+use elp_ide::IssuePattern;
+use fxhash::FxHashMap;
 use fxhash::FxHashSet;
+use itertools::Itertools;
 use lsp_types::ClientCapabilities;
+use regex::Regex;
 use serde::de::DeserializeOwned;
 use serde_json::json;
 
@@ -32,14 +41,59 @@ config_data! {
   struct ConfigData {
       /// Enable support for AI-based completions.
       ai_enable: bool = json! { false },
+      /// Whether to offer experimental ELP assists.
+      assists_enableExperimental: bool = json! { false },
+      /// Whether the assist reordering record fields sorts them
+      /// alphabetically, rather than as in the record definition.
+      assists_reorderRecordFields_alphabetical: bool = json! { false },
+      /// Whether the assist sorting an export list sorts the functions
+      /// alphabetically, rather than as in their definitions.
+      assists_sortExports_alphabetical: bool = json! { false },
+      /// Whether the call hierarchy includes calls through `apply/3`
+      /// and fun captures with literal names.
+      callHierarchy_dynamicCalls_enable: bool = json! { false },
+      /// Whether accepting the function of another module, offered
+      /// when completing a function name, adds it to an `-import`
+      /// attribute rather than qualifying the call with its module.
+      completion_autoImport_addImport: bool = json! { false },
+      /// Whether completing a function name also offers the exported
+      /// functions of the other modules of the project.
+      completion_autoImport_enable: bool = json! { true },
+      /// Whether to offer snippet completions, e.g. `Expr.case`
+      /// becoming a `case` expression, if the client supports them.
+      completion_snippets_enable: bool = json! { true },
       /// Whether to show experimental ELP diagnostics that might
       /// have more false positives than usual.
       diagnostics_enableExperimental: bool = json! { false },
       /// List of ELP diagnostics to disable.
       diagnostics_disabled: FxHashSet<String> = json! { [] },
+      /// Whether to run the lint plugins given in the `.elp_lint.toml`
+      /// file of a project on the open files, when they are opened or
+      /// saved.
+      diagnostics_plugins_enable: bool = json! { false },
+      /// Issue ids linked to in comments and strings, as a map from
+      /// the regex of the ids to the URL template of their target, in
+      /// which `$0` is the id and `$1`, `$2`, ... the groups of the
+      /// regex, e.g. `{"JIRA-[0-9]+": "https://jira.example.com/browse/$0"}`.
+      documentLinks_issuePatterns: FxHashMap<String, String> = json! { {} },
+      /// How many times an eqWAlizer call which timed out, or whose
+      /// process died, is retried.
+      eqwalizer_maxRetries: usize = json! { 2 },
+      /// Number of seconds eqWAlizer can go without responding before
+      /// the call is considered hung.
+      eqwalizer_timeout: usize = json! { 240 },
       /// Whether to show function parameter name inlay hints at the call
       /// site.
       inlayHints_parameterHints_enable: bool = json! { false },
+      /// Whether to only show parameter name inlay hints for literal
+      /// arguments.
+      inlayHints_parameterHints_literalsOnly: bool = json! { false },
+      /// Whether to show the number of fields a record pattern does
+      /// not match.
+      inlayHints_recordFieldHints_enable: bool = json! { false },
+      /// Whether to show the return type eqWAlizer inferred for
+      /// functions without a spec, after `->`.
+      inlayHints_returnTypeHints_enable: bool = json! { false },
       /// Whether to show Code Lenses in Erlang files.
       lens_enable: bool = json! { false },
       /// Whether to show the `Run` lenses. Only applies when
@@ -48,10 +102,20 @@ config_data! {
       /// Whether to show the `Debug` lenses. Only applies when
       /// `#elp.lens.enable#` is set.
       lens_debug_enable: bool = json! { false },
+      /// Whether to show the lenses linking a testcase to the Common
+      /// Test group it is run in. Only applies when
+      /// `#elp.lens.enable#` is set.
+      lens_groupLink_enable: bool = json! { false },
       /// Configure LSP-based logging using env_logger syntax.
       log: String = json! { "error" },
+      /// Base URL of the OTP documentation linked to by OTP modules,
+      /// functions and types, e.g. of an offline mirror.
+      otpDocs_baseUrl: String = json! { "https://erlang.org" },
       /// Whether to show Signature Help.
       signatureHelp_enable: bool = json! { false },
+      /// Maximum number of results returned for a workspace symbol
+      /// search.
+      workspaceSymbols_limit: usize = json! { 128 },
   }
 }
 
@@ -64,16 +128,13 @@ impl Default for ConfigData {
 #[derive(Clone, Debug)]
 pub struct Config {
     pub root_path: AbsPathBuf,
+    /// The roots of the workspace folders, each loaded as a project on
+    /// startup. Defaults to `root_path`.
+    pub workspace_folders: Vec<AbsPathBuf>,
     pub caps: ClientCapabilities,
     data: ConfigData,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct LensConfig {
-    pub run: bool,
-    pub debug: bool,
-}
-
 macro_rules! try_ {
     ($expr:expr) => {
         || -> _ { Some($expr) }()
@@ -88,6 +149,7 @@ macro_rules! try_or {
 impl Config {
     pub fn new(root_path: AbsPathBuf, caps: ClientCapabilities) -> Config {
         Config {
+            workspace_folders: vec![root_path.clone()],
             root_path,
             caps,
             data: ConfigData::default(),
@@ -119,15 +181,14 @@ impl Config {
     }
 
     pub fn code_action_literals(&self) -> bool {
-        try_!(
-            self.caps
-                .text_document
-                .as_ref()?
-                .code_action
-                .as_ref()?
-                .code_action_literal_support
-                .as_ref()?
-        )
+        try_!(self
+            .caps
+            .text_document
+            .as_ref()?
+            .code_action
+            .as_ref()?
+            .code_action_literal_support
+            .as_ref()?)
         .is_some()
     }
 
@@ -148,6 +209,20 @@ impl Config {
         .any(|it| it == "edit")
     }
 
+    /// Whether the client shows code actions which can't be applied,
+    /// with the reason why.
+    pub fn code_action_disabled(&self) -> bool {
+        try_or!(
+            self.caps
+                .text_document
+                .as_ref()?
+                .code_action
+                .as_ref()?
+                .disabled_support?,
+            false
+        )
+    }
+
     pub fn location_link(&self) -> bool {
         try_or!(
             self.caps.text_document.as_ref()?.definition?.link_support?,
@@ -155,6 +230,20 @@ impl Config {
         )
     }
 
+    fn completion_snippet_support(&self) -> bool {
+        try_or!(
+            self.caps
+                .text_document
+                .as_ref()?
+                .completion
+                .as_ref()?
+                .completion_item
+                .as_ref()?
+                .snippet_support?,
+            false
+        )
+    }
+
     pub fn hierarchical_symbols(&self) -> bool {
         try_or!(
             self.caps
@@ -187,6 +276,10 @@ impl Config {
         )
     }
 
+    pub fn plugin_diagnostics(&self) -> bool {
+        self.data.diagnostics_plugins_enable
+    }
+
     pub fn code_action_group(&self) -> bool {
         self.experimental("codeActionGroup")
     }
@@ -198,21 +291,68 @@ impl Config {
         self.experimental("serverStatusNotification")
     }
 
-    pub fn lens(&self) -> LensConfig {
-        LensConfig {
+    pub fn lens(&self) -> CodeLensConfig {
+        CodeLensConfig {
             run: self.data.lens_enable && self.data.lens_run_enable,
             debug: self.data.lens_enable && self.data.lens_debug_enable,
+            group_link: self.data.lens_enable && self.data.lens_groupLink_enable,
         }
     }
 
+    pub fn call_hierarchy_dynamic_calls(&self) -> bool {
+        self.data.callHierarchy_dynamicCalls_enable
+    }
+
     pub fn signature_help(&self) -> bool {
         self.data.signatureHelp_enable
     }
 
+    pub fn document_links(&self) -> DocumentLinksConfig {
+        let issue_patterns = self
+            .data
+            .documentLinks_issuePatterns
+            .iter()
+            .sorted()
+            .filter_map(|(pattern, url_template)| match Regex::new(pattern) {
+                Ok(regex) => Some(IssuePattern {
+                    regex,
+                    url_template: url_template.clone(),
+                }),
+                Err(err) => {
+                    log::warn!("Invalid issue pattern {pattern:?}: {err}");
+                    None
+                }
+            })
+            .collect();
+        DocumentLinksConfig { issue_patterns }
+    }
+
+    pub fn otp_docs_base_url(&self) -> &str {
+        &self.data.otpDocs_baseUrl
+    }
+
+    pub fn workspace_symbols_limit(&self) -> usize {
+        self.data.workspaceSymbols_limit
+    }
+
     pub fn assist(&self) -> AssistConfig {
         AssistConfig {
             snippet_cap: SnippetCap::new(self.experimental("snippetTextEdit")),
             allowed: None,
+            experimental: self.data.assists_enableExperimental,
+            record_fields_alphabetical: self.data.assists_reorderRecordFields_alphabetical,
+            exports_alphabetical: self.data.assists_sortExports_alphabetical,
+        }
+    }
+
+    pub fn completion(&self) -> CompletionConfig {
+        CompletionConfig {
+            snippet_cap: SnippetCap::new(
+                self.data.completion_snippets_enable && self.completion_snippet_support(),
+            ),
+            auto_import: self.data.completion_autoImport_enable,
+            add_import: self.data.completion_autoImport_addImport,
+            ..CompletionConfig::default()
         }
     }
 
@@ -227,6 +367,17 @@ impl Config {
     pub fn inlay_hints(&self) -> InlayHintsConfig {
         InlayHintsConfig {
             parameter_hints: self.data.inlayHints_parameterHints_enable,
+            parameter_hints_literals_only: self.data.inlayHints_parameterHints_literalsOnly,
+            record_field_hints: self.data.inlayHints_recordFieldHints_enable,
+            show_return_types: self.data.inlayHints_returnTypeHints_enable,
+        }
+    }
+
+    pub fn eqwalizer_client(&self) -> EqwalizerClientConfig {
+        EqwalizerClientConfig {
+            timeout: Duration::from_secs(self.data.eqwalizer_timeout as u64),
+            max_retries: self.data.eqwalizer_maxRetries as u32,
+            ..EqwalizerClientConfig::default()
         }
     }
 
@@ -353,6 +504,10 @@ fn field_props(
     match ty {
         "bool" => set!("type": "boolean"),
         "String" => set!("type": "string"),
+        "usize" => set! {
+            "type": "integer",
+            "minimum": 0,
+        },
         "Vec<String>" => set! {
             "type": "array",
             "items": { "type": "string" },
@@ -420,7 +575,7 @@ mod tests {
 
         let s = remove_ws(&schema);
 
-        expect![[r#""elp.ai.enable":{"default":false,"markdownDescription":"EnablesupportforAI-basedcompletions.","type":"boolean"},"elp.diagnostics.disabled":{"default":[],"items":{"type":"string"},"markdownDescription":"ListofELPdiagnosticstodisable.","type":"array","uniqueItems":true},"elp.diagnostics.enableExperimental":{"default":false,"markdownDescription":"WhethertoshowexperimentalELPdiagnosticsthatmight\nhavemorefalsepositivesthanusual.","type":"boolean"},"elp.inlayHints.parameterHints.enable":{"default":false,"markdownDescription":"Whethertoshowfunctionparameternameinlayhintsatthecall\nsite.","type":"boolean"},"elp.lens.debug.enable":{"default":false,"markdownDescription":"Whethertoshowthe`Debug`lenses.Onlyapplieswhen\n`#elp.lens.enable#`isset.","type":"boolean"},"elp.lens.enable":{"default":false,"markdownDescription":"WhethertoshowCodeLensesinErlangfiles.","type":"boolean"},"elp.lens.run.enable":{"default":false,"markdownDescription":"Whethertoshowthe`Run`lenses.Onlyapplieswhen\n`#elp.lens.enable#`isset.","type":"boolean"},"elp.log":{"default":"error","markdownDescription":"ConfigureLSP-basedloggingusingenv_loggersyntax.","type":"string"},"elp.signatureHelp.enable":{"default":false,"markdownDescription":"WhethertoshowSignatureHelp.","type":"boolean"},"#]]
+        expect![[r#""elp.ai.enable":{"default":false,"markdownDescription":"EnablesupportforAI-basedcompletions.","type":"boolean"},"elp.assists.enableExperimental":{"default":false,"markdownDescription":"WhethertoofferexperimentalELPassists.","type":"boolean"},"elp.assists.reorderRecordFields.alphabetical":{"default":false,"markdownDescription":"Whethertheassistreorderingrecordfieldssortsthem\nalphabetically,ratherthanasintherecorddefinition.","type":"boolean"},"elp.assists.sortExports.alphabetical":{"default":false,"markdownDescription":"Whethertheassistsortinganexportlistsortsthefunctions\nalphabetically,ratherthanasintheirdefinitions.","type":"boolean"},"elp.callHierarchy.dynamicCalls.enable":{"default":false,"markdownDescription":"Whetherthecallhierarchyincludescallsthrough`apply/3`\nandfuncaptureswithliteralnames.","type":"boolean"},"elp.completion.autoImport.addImport":{"default":false,"markdownDescription":"Whetheracceptingthefunctionofanothermodule,offered\nwhencompletingafunctionname,addsittoan`-import`\nattributeratherthanqualifyingthecallwithitsmodule.","type":"boolean"},"elp.completion.autoImport.enable":{"default":true,"markdownDescription":"Whethercompletingafunctionnamealsoofferstheexported\nfunctionsoftheothermodulesoftheproject.","type":"boolean"},"elp.completion.snippets.enable":{"default":true,"markdownDescription":"Whethertooffersnippetcompletions,e.g.`Expr.case`\nbecominga`case`expression,iftheclientsupportsthem.","type":"boolean"},"elp.diagnostics.disabled":{"default":[],"items":{"type":"string"},"markdownDescription":"ListofELPdiagnosticstodisable.","type":"array","uniqueItems":true},"elp.diagnostics.enableExperimental":{"default":false,"markdownDescription":"WhethertoshowexperimentalELPdiagnosticsthatmight\nhavemorefalsepositivesthanusual.","type":"boolean"},"elp.diagnostics.plugins.enable":{"default":false,"markdownDescription":"Whethertorunthelintpluginsgiveninthe`.elp_lint.toml`\nfileofaprojectontheopenfiles,whentheyareopenedor\nsaved.","type":"boolean"},"elp.documentLinks.issuePatterns":{"default":{},"markdownDescription":"Issueidslinkedtoincommentsandstrings,asamapfrom\ntheregexoftheidstotheURLtemplateoftheirtarget,in\nwhich`$0`istheidand`$1`,`$2`,...thegroupsofthe\nregex,e.g.`{\"JIRA-[0-9]+\":\"https://jira.example.com/browse/$0\"}`.","type":"object"},"elp.eqwalizer.maxRetries":{"default":2,"markdownDescription":"HowmanytimesaneqWAlizercallwhichtimedout,orwhose\nprocessdied,isretried.","minimum":0,"type":"integer"},"elp.eqwalizer.timeout":{"default":240,"markdownDescription":"NumberofsecondseqWAlizercangowithoutrespondingbefore\nthecallisconsideredhung.","minimum":0,"type":"integer"},"elp.inlayHints.parameterHints.enable":{"default":false,"markdownDescription":"Whethertoshowfunctionparameternameinlayhintsatthecall\nsite.","type":"boolean"},"elp.inlayHints.parameterHints.literalsOnly":{"default":false,"markdownDescription":"Whethertoonlyshowparameternameinlayhintsforliteral\narguments.","type":"boolean"},"elp.inlayHints.recordFieldHints.enable":{"default":false,"markdownDescription":"Whethertoshowthenumberoffieldsarecordpatterndoes\nnotmatch.","type":"boolean"},"elp.inlayHints.returnTypeHints.enable":{"default":false,"markdownDescription":"WhethertoshowthereturntypeeqWAlizerinferredfor\nfunctionswithoutaspec,after`->`.","type":"boolean"},"elp.lens.debug.enable":{"default":false,"markdownDescription":"Whethertoshowthe`Debug`lenses.Onlyapplieswhen\n`#elp.lens.enable#`isset.","type":"boolean"},"elp.lens.enable":{"default":false,"markdownDescription":"WhethertoshowCodeLensesinErlangfiles.","type":"boolean"},"elp.lens.groupLink.enable":{"default":false,"markdownDescription":"WhethertoshowthelenseslinkingatestcasetotheCommon\nTestgroupitisrunin.Onlyapplieswhen\n`#elp.lens.enable#`isset.","type":"boolean"},"elp.lens.run.enable":{"default":false,"markdownDescription":"Whethertoshowthe`Run`lenses.Onlyapplieswhen\n`#elp.lens.enable#`isset.","type":"boolean"},"elp.log":{"default":"error","markdownDescription":"ConfigureLSP-basedloggingusingenv_loggersyntax.","type":"string"},"elp.otpDocs.baseUrl":{"default":"https://erlang.org","markdownDescription":"BaseURLoftheOTPdocumentationlinkedtobyOTPmodules,\nfunctionsandtypes,e.g.ofanofflinemirror.","type":"string"},"elp.signatureHelp.enable":{"default":false,"markdownDescription":"WhethertoshowSignatureHelp.","type":"boolean"},"elp.workspaceSymbols.limit":{"default":128,"markdownDescription":"Maximumnumberofresultsreturnedforaworkspacesymbol\nsearch.","minimum":0,"type":"integer"},"#]]
         .assert_eq(s.as_str());
 
         expect![[r#"
@@ -429,6 +584,41 @@ mod tests {
               "markdownDescription": "Enable support for AI-based completions.",
               "type": "boolean"
             },
+            "elp.assists.enableExperimental": {
+              "default": false,
+              "markdownDescription": "Whether to offer experimental ELP assists.",
+              "type": "boolean"
+            },
+            "elp.assists.reorderRecordFields.alphabetical": {
+              "default": false,
+              "markdownDescription": "Whether the assist reordering record fields sorts them\nalphabetically, rather than as in the record definition.",
+              "type": "boolean"
+            },
+            "elp.assists.sortExports.alphabetical": {
+              "default": false,
+              "markdownDescription": "Whether the assist sorting an export list sorts the functions\nalphabetically, rather than as in their definitions.",
+              "type": "boolean"
+            },
+            "elp.callHierarchy.dynamicCalls.enable": {
+              "default": false,
+              "markdownDescription": "Whether the call hierarchy includes calls through `apply/3`\nand fun captures with literal names.",
+              "type": "boolean"
+            },
+            "elp.completion.autoImport.addImport": {
+              "default": false,
+              "markdownDescription": "Whether accepting the function of another module, offered\nwhen completing a function name, adds it to an `-import`\nattribute rather than qualifying the call with its module.",
+              "type": "boolean"
+            },
+            "elp.completion.autoImport.enable": {
+              "default": true,
+              "markdownDescription": "Whether completing a function name also offers the exported\nfunctions of the other modules of the project.",
+              "type": "boolean"
+            },
+            "elp.completion.snippets.enable": {
+              "default": true,
+              "markdownDescription": "Whether to offer snippet completions, e.g. `Expr.case`\nbecoming a `case` expression, if the client supports them.",
+              "type": "boolean"
+            },
             "elp.diagnostics.disabled": {
               "default": [],
               "items": {
@@ -443,11 +633,48 @@ mod tests {
               "markdownDescription": "Whether to show experimental ELP diagnostics that might\nhave more false positives than usual.",
               "type": "boolean"
             },
+            "elp.diagnostics.plugins.enable": {
+              "default": false,
+              "markdownDescription": "Whether to run the lint plugins given in the `.elp_lint.toml`\nfile of a project on the open files, when they are opened or\nsaved.",
+              "type": "boolean"
+            },
+            "elp.documentLinks.issuePatterns": {
+              "default": {},
+              "markdownDescription": "Issue ids linked to in comments and strings, as a map from\nthe regex of the ids to the URL template of their target, in\nwhich `$0` is the id and `$1`, `$2`, ... the groups of the\nregex, e.g. `{\"JIRA-[0-9]+\": \"https://jira.example.com/browse/$0\"}`.",
+              "type": "object"
+            },
+            "elp.eqwalizer.maxRetries": {
+              "default": 2,
+              "markdownDescription": "How many times an eqWAlizer call which timed out, or whose\nprocess died, is retried.",
+              "minimum": 0,
+              "type": "integer"
+            },
+            "elp.eqwalizer.timeout": {
+              "default": 240,
+              "markdownDescription": "Number of seconds eqWAlizer can go without responding before\nthe call is considered hung.",
+              "minimum": 0,
+              "type": "integer"
+            },
             "elp.inlayHints.parameterHints.enable": {
               "default": false,
               "markdownDescription": "Whether to show function parameter name inlay hints at the call\nsite.",
               "type": "boolean"
             },
+            "elp.inlayHints.parameterHints.literalsOnly": {
+              "default": false,
+              "markdownDescription": "Whether to only show parameter name inlay hints for literal\narguments.",
+              "type": "boolean"
+            },
+            "elp.inlayHints.recordFieldHints.enable": {
+              "default": false,
+              "markdownDescription": "Whether to show the number of fields a record pattern does\nnot match.",
+              "type": "boolean"
+            },
+            "elp.inlayHints.returnTypeHints.enable": {
+              "default": false,
+              "markdownDescription": "Whether to show the return type eqWAlizer inferred for\nfunctions without a spec, after `->`.",
+              "type": "boolean"
+            },
             "elp.lens.debug.enable": {
               "default": false,
               "markdownDescription": "Whether to show the `Debug` lenses. Only applies when\n`#elp.lens.enable#` is set.",
@@ -458,6 +685,11 @@ mod tests {
               "markdownDescription": "Whether to show Code Lenses in Erlang files.",
               "type": "boolean"
             },
+            "elp.lens.groupLink.enable": {
+              "default": false,
+              "markdownDescription": "Whether to show the lenses linking a testcase to the Common\nTest group it is run in. Only applies when\n`#elp.lens.enable#` is set.",
+              "type": "boolean"
+            },
             "elp.lens.run.enable": {
               "default": false,
               "markdownDescription": "Whether to show the `Run` lenses. Only applies when\n`#elp.lens.enable#` is set.",
@@ -468,11 +700,22 @@ mod tests {
               "markdownDescription": "Configure LSP-based logging using env_logger syntax.",
               "type": "string"
             },
+            "elp.otpDocs.baseUrl": {
+              "default": "https://erlang.org",
+              "markdownDescription": "Base URL of the OTP documentation linked to by OTP modules,\nfunctions and types, e.g. of an offline mirror.",
+              "type": "string"
+            },
             "elp.signatureHelp.enable": {
               "default": false,
               "markdownDescription": "Whether to show Signature Help.",
               "type": "boolean"
             },
+            "elp.workspaceSymbols.limit": {
+              "default": 128,
+              "markdownDescription": "Maximum number of results returned for a workspace symbol\nsearch.",
+              "minimum": 0,
+              "type": "integer"
+            },
         "#]].assert_eq(schema.as_str());
     }
 
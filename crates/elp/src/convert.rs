@@ -20,6 +20,7 @@ use elp_ide::elp_ide_db::assists::AssistContextDiagnostic;
 use elp_ide::elp_ide_db::assists::AssistContextDiagnosticCode;
 use elp_ide::elp_ide_db::elp_base_db::AbsPath;
 use elp_ide::elp_ide_db::elp_base_db::AbsPathBuf;
+use elp_ide::elp_ide_db::elp_base_db::FileId;
 use elp_ide::elp_ide_db::elp_base_db::VfsPath;
 use elp_ide::elp_ide_db::EqwalizerDiagnostic;
 use elp_ide::elp_ide_db::LineIndex;
@@ -62,10 +63,23 @@ pub fn diagnostic_severity(severity: Severity) -> lsp_types::DiagnosticSeverity
     }
 }
 
+/// Related information in other files than the one of the diagnostic is
+/// dropped, see `ide_to_lsp_diagnostic_with_related`.
 pub fn ide_to_lsp_diagnostic(
     line_index: &LineIndex,
     url: &Url,
     d: &Diagnostic,
+) -> lsp_types::Diagnostic {
+    ide_to_lsp_diagnostic_with_related(line_index, url, d, &|_, _| None)
+}
+
+/// Like `ide_to_lsp_diagnostic`, locating related information in other
+/// files with `location`.
+pub fn ide_to_lsp_diagnostic_with_related(
+    line_index: &LineIndex,
+    url: &Url,
+    d: &Diagnostic,
+    location: &dyn Fn(FileId, TextRange) -> Option<Location>,
 ) -> lsp_types::Diagnostic {
     lsp_types::Diagnostic {
         range: range(line_index, d.range),
@@ -74,7 +88,7 @@ pub fn ide_to_lsp_diagnostic(
         code_description: None,
         source: Some("elp".into()),
         message: d.message.clone(),
-        related_information: from_related(line_index, url, &d.related_info),
+        related_information: from_related(line_index, url, &d.related_info, location),
         tags: None,
         data: None,
     }
@@ -188,18 +202,22 @@ fn from_related(
     line_index: &LineIndex,
     url: &Url,
     r: &Option<Vec<RelatedInformation>>,
+    location: &dyn Fn(FileId, TextRange) -> Option<Location>,
 ) -> Option<Vec<DiagnosticRelatedInformation>> {
     r.as_ref().map(|ri| {
         ri.iter()
-            .map(|i| {
-                let location = Location {
-                    range: range(line_index, i.range),
-                    uri: url.clone(),
+            .filter_map(|i| {
+                let location = match i.file_id {
+                    Some(file_id) => location(file_id, i.range)?,
+                    None => Location {
+                        range: range(line_index, i.range),
+                        uri: url.clone(),
+                    },
                 };
-                DiagnosticRelatedInformation {
+                Some(DiagnosticRelatedInformation {
                     location,
                     message: i.message.clone(),
-                }
+                })
             })
             .collect()
     })
@@ -0,0 +1,179 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Renders the markdown explanations of the diagnostic codes as HTML
+//! pages, linked from the `codeDescription` of the diagnostics sent to
+//! the client, so that editors can open them.
+//!
+//! Pages are rendered on first use into a directory of the temporary
+//! directory, shared by the servers of the same version. The renderer
+//! only supports the markdown used by the explanations: paragraphs,
+//! headings, fenced code blocks and inline code.
+
+use std::fs;
+use std::path::PathBuf;
+
+use elp_ide::diagnostics::DiagnosticCode;
+use fxhash::FxHashMap;
+use lazy_static::lazy_static;
+use lsp_types::CodeDescription;
+use lsp_types::Url;
+use parking_lot::Mutex;
+
+lazy_static! {
+    /// The pages already rendered, by code, `None` when rendering failed
+    static ref PAGES: Mutex<FxHashMap<String, Option<Url>>> = Mutex::default();
+}
+
+/// The link to the rendered explanation of `code`, if it has one.
+pub(crate) fn code_description(code: &DiagnosticCode) -> Option<CodeDescription> {
+    let explanation = code.explanation()?;
+    let name = code.as_code();
+    let href = PAGES
+        .lock()
+        .entry(name.clone())
+        .or_insert_with(|| match render_page(code, explanation) {
+            Ok(path) => Url::from_file_path(path).ok(),
+            Err(err) => {
+                log::warn!("Could not render the explanation of {name}: {err}");
+                None
+            }
+        })
+        .clone()?;
+    Some(CodeDescription { href })
+}
+
+fn render_page(code: &DiagnosticCode, explanation: &str) -> std::io::Result<PathBuf> {
+    let dir = std::env::temp_dir().join("elp-docs").join(crate::version());
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.html", code.as_code()));
+    let title = escape(&format!("{} ({})", code.as_code(), code.as_label()));
+    let page = format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: sans-serif; line-height: 1.5; max-width: 50em; margin: 2em auto; padding: 0 1em; }}
+pre {{ background: #f4f4f4; padding: 1em; overflow: auto; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+{}</body>
+</html>
+"#,
+        markdown_to_html(explanation)
+    );
+    fs::write(&path, page)?;
+    Ok(path)
+}
+
+fn markdown_to_html(markdown: &str) -> String {
+    let mut html = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut code_block: Option<Vec<&str>> = None;
+    for line in markdown.lines() {
+        let is_fence = line.trim_start().starts_with("```");
+        if let Some(code) = &mut code_block {
+            if is_fence {
+                html.push_str(&format!(
+                    "<pre><code>{}</code></pre>\n",
+                    escape(&code.join("\n"))
+                ));
+                code_block = None;
+            } else {
+                code.push(line);
+            }
+        } else if is_fence {
+            end_paragraph(&mut html, &mut paragraph);
+            code_block = Some(Vec::new());
+        } else if line.trim().is_empty() {
+            end_paragraph(&mut html, &mut paragraph);
+        } else if line.starts_with('#') {
+            end_paragraph(&mut html, &mut paragraph);
+            let level = line.chars().take_while(|c| *c == '#').count().min(6);
+            let heading = inline_to_html(line[level..].trim());
+            html.push_str(&format!("<h{level}>{heading}</h{level}>\n"));
+        } else {
+            paragraph.push(line.trim());
+        }
+    }
+    if let Some(code) = code_block {
+        html.push_str(&format!(
+            "<pre><code>{}</code></pre>\n",
+            escape(&code.join("\n"))
+        ));
+    }
+    end_paragraph(&mut html, &mut paragraph);
+    html
+}
+
+fn end_paragraph(html: &mut String, paragraph: &mut Vec<&str>) {
+    if !paragraph.is_empty() {
+        html.push_str(&format!(
+            "<p>{}</p>\n",
+            inline_to_html(&paragraph.join(" "))
+        ));
+        paragraph.clear();
+    }
+}
+
+/// Inline code is the only inline markup supported.
+fn inline_to_html(text: &str) -> String {
+    text.split('`')
+        .enumerate()
+        .map(|(i, part)| {
+            if i % 2 == 1 {
+                format!("<code>{}</code>", escape(part))
+            } else {
+                escape(part)
+            }
+        })
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::markdown_to_html;
+
+    #[test]
+    fn renders_explanations() {
+        let html = markdown_to_html(
+            r#"A match always succeeds, as in
+`X = X`.
+
+```erlang
+foo() -> X = 42, X = X.
+```
+
+## Fix
+
+Remove the `<lhs>` & the `=`.
+"#,
+        );
+        expect![[r#"
+            <p>A match always succeeds, as in <code>X = X</code>.</p>
+            <pre><code>foo() -&gt; X = 42, X = X.</code></pre>
+            <h2>Fix</h2>
+            <p>Remove the <code>&lt;lhs&gt;</code> &amp; the <code>=</code>.</p>
+        "#]]
+        .assert_eq(&html);
+    }
+}
@@ -17,50 +17,106 @@ use fxhash::FxHashMap;
 use fxhash::FxHashSet;
 use lsp_types::Diagnostic;
 
+/// The passes computing the diagnostics of a file. Each of them reports
+/// its diagnostics on its own, as soon as they are computed for a file,
+/// so a slow pass doesn't hold back the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DiagnosticsPass {
+    Native,
+    ErlangService,
+    Eqwalizer,
+    Edoc,
+    Plugins,
+}
+
+/// The diagnostics of a pass for a file, tagged with the revision of
+/// the file they were computed for.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct PassDiagnostics {
+    revision: u32,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// The revision of each file, bumped whenever it changes.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Revisions(FxHashMap<FileId, u32>);
+
+impl Revisions {
+    pub fn get(&self, file_id: FileId) -> u32 {
+        self.0.get(&file_id).copied().unwrap_or_default()
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub(crate) struct DiagnosticCollection {
-    pub(crate) native: FxHashMap<FileId, Vec<Diagnostic>>,
-    pub(crate) erlang_service: FxHashMap<FileId, Vec<Diagnostic>>,
-    pub(crate) eqwalizer: FxHashMap<FileId, Vec<Diagnostic>>,
-    pub(crate) edoc: FxHashMap<FileId, Vec<Diagnostic>>,
+    pub(crate) native: FxHashMap<FileId, PassDiagnostics>,
+    pub(crate) erlang_service: FxHashMap<FileId, PassDiagnostics>,
+    pub(crate) eqwalizer: FxHashMap<FileId, PassDiagnostics>,
+    pub(crate) edoc: FxHashMap<FileId, PassDiagnostics>,
+    pub(crate) plugins: FxHashMap<FileId, PassDiagnostics>,
+    revisions: Revisions,
     changes: FxHashSet<FileId>,
 }
 
 impl DiagnosticCollection {
-    pub fn set_native(&mut self, file_id: FileId, diagnostics: Vec<Diagnostic>) {
-        if !are_all_diagnostics_equal(&self.native, file_id, &diagnostics) {
-            set_diagnostics(&mut self.native, file_id, diagnostics);
-            self.changes.insert(file_id);
-        }
+    /// The current revisions of the files, to tag the diagnostics
+    /// computed from a snapshot with.
+    pub fn revisions(&self) -> Revisions {
+        self.revisions.clone()
     }
 
-    pub fn set_eqwalizer(&mut self, file_id: FileId, diagnostics: Vec<Diagnostic>) {
-        if !are_all_diagnostics_equal(&self.eqwalizer, file_id, &diagnostics) {
-            set_diagnostics(&mut self.eqwalizer, file_id, diagnostics);
-            self.changes.insert(file_id);
-        }
+    /// Records a change to the file. Its eqWAlizer and plugin
+    /// diagnostics are cleared, as they are only computed again when it
+    /// is saved.
+    pub fn file_changed(&mut self, file_id: FileId) {
+        let revision = self.revisions.0.entry(file_id).or_default();
+        *revision += 1;
+        let revision = *revision;
+        // causes us to remove stale squiggles from the UI
+        self.set(DiagnosticsPass::Eqwalizer, file_id, revision, vec![]);
+        self.set(DiagnosticsPass::Plugins, file_id, revision, vec![]);
     }
 
-    pub fn set_edoc(&mut self, file_id: FileId, diagnostics: Vec<Diagnostic>) {
-        if !are_all_diagnostics_equal(&self.edoc, file_id, &diagnostics) {
-            set_diagnostics(&mut self.edoc, file_id, diagnostics);
-            self.changes.insert(file_id);
+    /// Replaces the diagnostics of the pass for the file, leaving the
+    /// ones of the other passes alone. Diagnostics computed for an
+    /// older revision of the file than the current ones of the pass
+    /// are out of date, and dropped.
+    pub fn set(
+        &mut self,
+        pass: DiagnosticsPass,
+        file_id: FileId,
+        revision: u32,
+        diagnostics: Vec<Diagnostic>,
+    ) {
+        let map = match pass {
+            DiagnosticsPass::Native => &mut self.native,
+            DiagnosticsPass::ErlangService => &mut self.erlang_service,
+            DiagnosticsPass::Eqwalizer => &mut self.eqwalizer,
+            DiagnosticsPass::Edoc => &mut self.edoc,
+            DiagnosticsPass::Plugins => &mut self.plugins,
+        };
+        let existing = map.entry(file_id).or_default();
+        if existing.revision > revision {
+            return;
         }
-    }
-
-    pub fn set_erlang_service(&mut self, file_id: FileId, diagnostics: Vec<Diagnostic>) {
-        if !are_all_diagnostics_equal(&self.erlang_service, file_id, &diagnostics) {
-            set_diagnostics(&mut self.erlang_service, file_id, diagnostics);
+        existing.revision = revision;
+        if !are_all_diagnostics_equal(&existing.diagnostics, &diagnostics) {
+            existing.diagnostics = diagnostics;
             self.changes.insert(file_id);
         }
     }
 
     pub fn diagnostics_for(&self, file_id: FileId) -> impl Iterator<Item = &Diagnostic> {
-        let native = self.native.get(&file_id).into_iter().flatten();
-        let erlang_service = self.erlang_service.get(&file_id).into_iter().flatten();
-        let eqwalizer = self.eqwalizer.get(&file_id).into_iter().flatten();
-        let edoc = self.edoc.get(&file_id).into_iter().flatten();
-        native.chain(erlang_service).chain(eqwalizer).chain(edoc)
+        let pass = |map: &FxHashMap<FileId, PassDiagnostics>| {
+            map.get(&file_id)
+                .into_iter()
+                .flat_map(|pass| &pass.diagnostics)
+        };
+        pass(&self.native)
+            .chain(pass(&self.erlang_service))
+            .chain(pass(&self.eqwalizer))
+            .chain(pass(&self.edoc))
+            .chain(pass(&self.plugins))
     }
 
     pub fn take_changes(&mut self) -> Option<FxHashSet<FileId>> {
@@ -71,13 +127,7 @@ impl DiagnosticCollection {
     }
 }
 
-fn are_all_diagnostics_equal(
-    map: &FxHashMap<FileId, Vec<Diagnostic>>,
-    file_id: FileId,
-    new: &[Diagnostic],
-) -> bool {
-    let existing = map.get(&file_id).map(Vec::as_slice).unwrap_or_default();
-
+fn are_all_diagnostics_equal(existing: &[Diagnostic], new: &[Diagnostic]) -> bool {
     existing.len() == new.len()
         && new
             .iter()
@@ -92,18 +142,6 @@ fn are_diagnostics_equal(left: &Diagnostic, right: &Diagnostic) -> bool {
         && left.message == right.message
 }
 
-fn set_diagnostics(
-    map: &mut FxHashMap<FileId, Vec<Diagnostic>>,
-    file_id: FileId,
-    new: Vec<Diagnostic>,
-) {
-    if new.is_empty() {
-        map.remove(&file_id);
-    } else {
-        map.insert(file_id, new);
-    }
-}
-
 #[derive(Clone, Debug)]
 pub enum DiagnosticSource {
     ErlangLsCompiler,
@@ -130,8 +168,8 @@ mod tests {
         let mut diagnostics = DiagnosticCollection::default();
         let file_id = FileId(0);
 
-        diagnostics.set_eqwalizer(file_id, vec![]);
-        diagnostics.set_native(file_id, vec![]);
+        diagnostics.set(DiagnosticsPass::Eqwalizer, file_id, 0, vec![]);
+        diagnostics.set(DiagnosticsPass::Native, file_id, 0, vec![]);
 
         assert_eq!(diagnostics.take_changes(), None);
         assert_eq!(diagnostics.diagnostics_for(file_id).next(), None);
@@ -145,7 +183,12 @@ mod tests {
         let diagnostic = Diagnostic::default();
 
         // Set some diagnostic initially
-        diagnostics.set_native(file_id, vec![diagnostic.clone()]);
+        diagnostics.set(
+            DiagnosticsPass::Native,
+            file_id,
+            0,
+            vec![diagnostic.clone()],
+        );
 
         let changes = diagnostics.take_changes();
         let mut expected_changes = FxHashSet::default();
@@ -156,10 +199,61 @@ mod tests {
         assert_eq!(stored, vec![&diagnostic]);
 
         // Reset to empty
-        diagnostics.set_native(file_id, vec![]);
+        diagnostics.set(DiagnosticsPass::Native, file_id, 0, vec![]);
 
         let changes = diagnostics.take_changes();
         assert_eq!(changes.as_ref(), Some(&expected_changes));
         assert_eq!(diagnostics.diagnostics_for(file_id).next(), None);
     }
+    #[test]
+    fn keeps_other_passes() {
+        let mut diagnostics = DiagnosticCollection::default();
+        let file_id = FileId(0);
+
+        let native = Diagnostic {
+            message: "native".to_string(),
+            ..Diagnostic::default()
+        };
+        let edoc = Diagnostic {
+            message: "edoc".to_string(),
+            ..Diagnostic::default()
+        };
+        diagnostics.set(DiagnosticsPass::Edoc, file_id, 0, vec![edoc.clone()]);
+        diagnostics.take_changes();
+
+        diagnostics.file_changed(file_id);
+        let revision = diagnostics.revisions().get(file_id);
+        diagnostics.set(
+            DiagnosticsPass::Native,
+            file_id,
+            revision,
+            vec![native.clone()],
+        );
+
+        let stored = diagnostics.diagnostics_for(file_id).collect::<Vec<_>>();
+        assert_eq!(stored, vec![&native, &edoc]);
+    }
+
+    #[test]
+    fn drops_out_of_date_diagnostics() {
+        let mut diagnostics = DiagnosticCollection::default();
+        let file_id = FileId(0);
+
+        let diagnostic = Diagnostic::default();
+        let old_revision = diagnostics.revisions().get(file_id);
+        diagnostics.file_changed(file_id);
+        let revision = diagnostics.revisions().get(file_id);
+
+        // The pass computed for the new revision finishes first
+        diagnostics.set(DiagnosticsPass::Native, file_id, revision, vec![]);
+        diagnostics.set(
+            DiagnosticsPass::Native,
+            file_id,
+            old_revision,
+            vec![diagnostic],
+        );
+
+        assert_eq!(diagnostics.take_changes(), None);
+        assert_eq!(diagnostics.diagnostics_for(file_id).next(), None);
+    }
 }
@@ -20,15 +20,11 @@ pub struct Document {
 
 impl Document {
     pub fn from_bytes(bytes: Vec<u8>) -> Document {
+        // The VFS holds UTF-8, contents read from disk are decoded
+        // according to their declared encoding when loaded.
         let content = match String::from_utf8(bytes) {
             Ok(text) => text,
-            Err(err) => {
-                // Fall back to lossy latin1 loading of files.
-                // This should only affect files from yaws, and
-                // possibly OTP that are latin1 encoded.
-                let contents = err.into_bytes();
-                contents.into_iter().map(|byte| byte as char).collect()
-            }
+            Err(err) => String::from_utf8_lossy(err.as_bytes()).into_owned(),
         };
         Document { content }
     }
@@ -15,9 +15,11 @@ use elp_ide::elp_ide_db::elp_base_db::FilePosition;
 use elp_ide::elp_ide_db::elp_base_db::FileRange;
 use elp_ide::elp_ide_db::LineCol;
 use elp_ide::elp_ide_db::LineIndex;
+use elp_ide::ExpandMacroMode;
 use elp_ide::TextRange;
 use elp_ide::TextSize;
 
+use crate::lsp_ext;
 use crate::snapshot::Snapshot;
 use crate::Result;
 
@@ -117,3 +119,11 @@ pub(crate) fn assist_kind(kind: lsp_types::CodeActionKind) -> Option<AssistKind>
 
     Some(assist_kind)
 }
+
+pub(crate) fn expand_macro_mode(mode: lsp_ext::ExpandMacroMode) -> ExpandMacroMode {
+    match mode {
+        lsp_ext::ExpandMacroMode::Recursive => ExpandMacroMode::Recursive,
+        lsp_ext::ExpandMacroMode::SingleStep => ExpandMacroMode::SingleStep,
+        lsp_ext::ExpandMacroMode::Function => ExpandMacroMode::Function,
+    }
+}
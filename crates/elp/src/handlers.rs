@@ -11,7 +11,10 @@
 //! Protocol. The majority of requests are fulfilled by calling into the
 //! `ide` crate.
 
+use std::fs;
+
 use anyhow::bail;
+use anyhow::Context;
 use anyhow::Result;
 use elp_ide::elp_ide_assists::AssistKind;
 use elp_ide::elp_ide_assists::AssistResolveStrategy;
@@ -19,15 +22,19 @@ use elp_ide::elp_ide_assists::SingleResolve;
 use elp_ide::elp_ide_completion::Completion;
 use elp_ide::elp_ide_completion::Kind;
 use elp_ide::elp_ide_db::assists::AssistContextDiagnostic;
+use elp_ide::elp_ide_db::elp_base_db::FileId;
 use elp_ide::elp_ide_db::elp_base_db::FilePosition;
 use elp_ide::elp_ide_db::elp_base_db::FileRange;
 use elp_ide::elp_ide_db::elp_base_db::ProjectId;
+use elp_ide::elp_ide_db::source_change::SourceChange;
 use elp_ide::elp_ide_db::LineIndex;
 use elp_ide::elp_ide_db::SymbolKind;
 use elp_ide::Cancellable;
 use elp_ide::HighlightedRange;
+use elp_ide::NavigationTarget;
 use elp_ide::RangeInfo;
-use elp_ide::TextRange;
+use elp_ide::TestResults;
+use elp_project_model::AppType;
 use itertools::Itertools;
 use lsp_server::ErrorCode;
 use lsp_types::CallHierarchyIncomingCall;
@@ -44,6 +51,8 @@ use lsp_types::FoldingRange;
 use lsp_types::FoldingRangeParams;
 use lsp_types::Hover;
 use lsp_types::HoverParams;
+use lsp_types::PrepareRenameResponse;
+use lsp_types::RenameFilesParams;
 use lsp_types::RenameParams;
 use lsp_types::SemanticTokensDeltaParams;
 use lsp_types::SemanticTokensFullDeltaResult;
@@ -53,12 +62,15 @@ use lsp_types::SemanticTokensRangeResult;
 use lsp_types::SemanticTokensResult;
 use lsp_types::SymbolInformation;
 use lsp_types::TextDocumentIdentifier;
+use lsp_types::TextDocumentPositionParams;
 use lsp_types::Url;
 use lsp_types::WorkspaceEdit;
 
+use crate::convert;
 use crate::convert::lsp_to_assist_context_diagnostic;
 use crate::from_proto;
 use crate::lsp_ext;
+use crate::snapshot::CachedSemanticTokens;
 use crate::snapshot::Snapshot;
 use crate::to_proto;
 use crate::LspError;
@@ -107,6 +119,9 @@ pub(crate) fn handle_code_action(
         None,
     )?;
     for (index, assist) in assists.into_iter().enumerate() {
+        if assist.disabled.is_some() && !snap.config.code_action_disabled() {
+            continue;
+        }
         let resolve_data = if code_action_resolve_cap {
             Some((index, params.clone(), assist.user_input.clone()))
         } else {
@@ -116,6 +131,29 @@ pub(crate) fn handle_code_action(
         res.push(code_action)
     }
 
+    // Organizing the module is a source action, which clients only ask
+    // for explicitly, e.g. on save.
+    let organize_requested = params.context.only.iter().flatten().any(|kind| {
+        kind == &lsp_types::CodeActionKind::SOURCE
+            || kind == &lsp_types::CodeActionKind::SOURCE_ORGANIZE_IMPORTS
+    });
+    if organize_requested {
+        if let Some(change) = snap
+            .analysis
+            .organize_module(file_id, &snap.config.assist())?
+        {
+            res.push(
+                lsp_types::CodeAction {
+                    title: "Organize exports and imports".to_string(),
+                    kind: Some(lsp_types::CodeActionKind::SOURCE_ORGANIZE_IMPORTS),
+                    edit: Some(to_proto::workspace_edit(&snap, change)?),
+                    ..Default::default()
+                }
+                .into(),
+            );
+        }
+    }
+
     Ok(Some(res))
 }
 
@@ -193,7 +231,11 @@ pub(crate) fn handle_code_action_resolve(
     }
     match to_proto::code_action(&snap, assist.clone(), None)? {
         lsp_types::CodeActionOrCommand::Command(_) => {}
-        lsp_types::CodeActionOrCommand::CodeAction(ca) => code_action.edit = ca.edit,
+        lsp_types::CodeActionOrCommand::CodeAction(ca) => {
+            // The user input given may make the assist inapplicable
+            code_action.edit = ca.edit;
+            code_action.disabled = ca.disabled;
+        }
     }
     Ok(code_action)
 }
@@ -228,9 +270,10 @@ pub(crate) fn handle_expand_macro(
     let line_index = snap.analysis.line_index(file_id)?;
     let offset = from_proto::offset(&line_index, params.position);
 
+    let mode = from_proto::expand_macro_mode(params.mode);
     let res = snap
         .analysis
-        .expand_macro(FilePosition { file_id, offset })?;
+        .expand_macro(FilePosition { file_id, offset }, mode)?;
     match res {
         Some(it) => Ok(Some(lsp_ext::ExpandedMacro {
             name: it.name,
@@ -243,6 +286,28 @@ pub(crate) fn handle_expand_macro(
     }
 }
 
+pub(crate) fn handle_diagnostics_timing(
+    snap: Snapshot,
+    params: TextDocumentIdentifier,
+) -> Result<lsp_ext::DiagnosticsTimingResult> {
+    let _p = profile::span("handle_diagnostics_timing");
+    let file_id = from_proto::file_id(&snap, &params.uri)?;
+    let timing = snap
+        .analysis
+        .diagnostics_timing(&snap.config.diagnostics(), file_id, false)?;
+    Ok(lsp_ext::DiagnosticsTimingResult {
+        total_ms: timing.total().as_secs_f64() * 1000.0,
+        passes: timing
+            .passes
+            .into_iter()
+            .map(|(name, duration)| lsp_ext::DiagnosticsPassTiming {
+                name,
+                ms: duration.as_secs_f64() * 1000.0,
+            })
+            .collect(),
+    })
+}
+
 pub(crate) fn pong(_: Snapshot, _: Vec<String>) -> Result<String> {
     Ok("pong".to_string())
 }
@@ -254,25 +319,16 @@ pub(crate) fn handle_selection_range(
     let _p = profile::span("handle_selection_range");
     let file_id = from_proto::file_id(&snap, &params.text_document.uri)?;
     let line_index = snap.analysis.line_index(file_id)?;
-    let res: Result<Vec<lsp_types::SelectionRange>> = params
+    let offsets = params
         .positions
+        .iter()
+        .map(|&position| from_proto::offset(&line_index, position))
+        .collect();
+    let res: Vec<lsp_types::SelectionRange> = snap
+        .analysis
+        .selection_ranges(file_id, offsets)?
         .into_iter()
-        .map(|position| {
-            let offset = from_proto::offset(&line_index, position);
-            let mut ranges = Vec::new();
-            {
-                let mut range = TextRange::new(offset, offset);
-                loop {
-                    ranges.push(range);
-                    let frange = FileRange { file_id, range };
-                    let next = snap.analysis.extend_selection(frange)?;
-                    if next == range {
-                        break;
-                    } else {
-                        range = next
-                    }
-                }
-            }
+        .map(|ranges| {
             let mut range = lsp_types::SelectionRange {
                 range: to_proto::range(&line_index, *ranges.last().unwrap()),
                 parent: None,
@@ -283,11 +339,11 @@ pub(crate) fn handle_selection_range(
                     parent: Some(Box::new(range)),
                 }
             }
-            Ok(range)
+            range
         })
         .collect();
 
-    Ok(Some(res?))
+    Ok(Some(res))
 }
 
 pub(crate) fn handle_goto_definition(
@@ -308,6 +364,27 @@ pub(crate) fn handle_goto_definition(
     Ok(Some(res))
 }
 
+pub(crate) fn handle_goto_implementation(
+    snap: Snapshot,
+    params: lsp_types::request::GotoImplementationParams,
+) -> Result<Option<lsp_types::request::GotoImplementationResponse>> {
+    let _p = profile::span("handle_goto_implementation");
+    let position = from_proto::file_position(&snap, params.text_document_position_params)?;
+    let nav_info = match snap.analysis.goto_implementation(position)? {
+        Some(it) => it,
+        None => match snap.analysis.message_flow(position)? {
+            None => return Ok(None),
+            Some(it) => it,
+        },
+    };
+    let src = FileRange {
+        file_id: position.file_id,
+        range: nav_info.range,
+    };
+    let res = to_proto::goto_definition_response(&snap, Some(src), nav_info.info)?;
+    Ok(Some(res))
+}
+
 pub(crate) fn handle_references(
     snap: Snapshot,
     params: lsp_types::ReferenceParams,
@@ -358,9 +435,11 @@ pub(crate) fn handle_completion(
             snap.ai_completion(position)?
         };
 
-    let mut completions = snap
-        .analysis
-        .completions(position, completion_trigger_character)?;
+    let mut completions = snap.analysis.completions(
+        &snap.config.completion(),
+        position,
+        completion_trigger_character,
+    )?;
 
     let ai_result = if let Ok(Some(ai_result)) = ai_receiver.recv() {
         ai_result
@@ -374,6 +453,8 @@ pub(crate) fn handle_completion(
             kind: Kind::AiAssist,
             contents: elp_ide::elp_ide_completion::Contents::SameAsLabel,
             position: None,
+            detail: None,
+            additional_edits: Vec::new(),
             sort_text: Some("\0".to_string()),
             deprecated: false,
         });
@@ -441,26 +522,67 @@ pub(crate) fn handle_workspace_symbol(
 ) -> Result<Option<Vec<SymbolInformation>>> {
     let _p = profile::span("handle_workspace_symbol");
 
-    let mut res = Vec::new();
+    let mut navs = Vec::new();
+    let mut remaining = snap.config.workspace_symbols_limit();
     for (project_id, _project) in snap.projects.iter().enumerate() {
+        if remaining == 0 {
+            break;
+        }
         let project_id = ProjectId(project_id as u32);
-        for nav in snap.analysis.symbol_search(project_id, &params.query)? {
-            #[allow(deprecated)]
-            let info = SymbolInformation {
-                name: nav.name.to_string(),
-                kind: to_proto::symbol_kind(nav.kind),
-                tags: None,
-                location: to_proto::location_from_nav(&snap, nav)?,
-                container_name: None,
-                deprecated: None,
-            };
-            res.push(info);
+        let page = snap
+            .analysis
+            .symbol_search(project_id, &params.query, 0, remaining)?;
+        if page.next_offset.is_some() {
+            log::info!(
+                "workspace symbol results for {:?} truncated to {}",
+                params.query,
+                remaining
+            );
         }
+        remaining -= page.symbols.len();
+        navs.extend(page.symbols);
+    }
+    // Symbols of dependencies and OTP go after those of the project
+    navs.sort_by(|a, b| {
+        let rank = |nav: &NavigationTarget| nav.app_type.unwrap_or(AppType::App);
+        (rank(a), &a.name).cmp(&(rank(b), &b.name))
+    });
+    let mut res = Vec::new();
+    for nav in navs {
+        #[allow(deprecated)]
+        let info = SymbolInformation {
+            name: nav.name.to_string(),
+            kind: to_proto::symbol_kind(nav.kind),
+            tags: None,
+            container_name: nav.app_name.as_ref().map(|app| app.as_str().to_string()),
+            location: to_proto::location_from_nav(&snap, nav)?,
+            deprecated: None,
+        };
+        res.push(info);
     }
-    res.sort_by(|a, b| a.name.cmp(&b.name));
     Ok(Some(res))
 }
 
+pub(crate) fn handle_prepare_rename(
+    snap: Snapshot,
+    params: TextDocumentPositionParams,
+) -> Result<Option<PrepareRenameResponse>> {
+    let _p = profile::span("handle_prepare_rename");
+    let position = from_proto::file_position(&snap, params)?;
+
+    let change = snap
+        .analysis
+        .prepare_rename(position)?
+        .map_err(to_proto::rename_error)?;
+
+    let line_index = snap.analysis.line_index(position.file_id)?;
+    let range = to_proto::range(&line_index, change.range);
+    Ok(Some(PrepareRenameResponse::RangeWithPlaceholder {
+        range,
+        placeholder: change.info,
+    }))
+}
+
 pub(crate) fn handle_rename(snap: Snapshot, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
     let _p = profile::span("handle_rename");
     let position = from_proto::file_position(&snap, params.text_document_position)?;
@@ -474,6 +596,40 @@ pub(crate) fn handle_rename(snap: Snapshot, params: RenameParams) -> Result<Opti
     Ok(Some(workspace_edit))
 }
 
+pub(crate) fn handle_will_rename_files(
+    snap: Snapshot,
+    params: RenameFilesParams,
+) -> Result<Option<WorkspaceEdit>> {
+    let _p = profile::span("handle_will_rename_files");
+
+    let mut change = SourceChange::default();
+    for file_rename in params.files {
+        let old_uri = Url::parse(&file_rename.old_uri)?;
+        let new_uri = Url::parse(&file_rename.new_uri)?;
+        let new_name = match convert::abs_path(&new_uri)?.name_and_extension() {
+            Some((name, Some("erl"))) => name.to_string(),
+            _ => continue,
+        };
+        let file_id = match snap.url_to_file_id(&old_uri) {
+            Ok(file_id) => file_id,
+            Err(_) => continue,
+        };
+        match snap.analysis.will_rename_file(file_id, &new_name)? {
+            Ok(Some(module_change)) => change = change.merge(module_change),
+            Ok(None) => {}
+            // The file is renamed anyway, the module can still be
+            // fixed up by hand.
+            Err(err) => log::warn!("Not renaming module for {}: {}", old_uri, err),
+        }
+    }
+
+    if change.source_file_edits.is_empty() {
+        return Ok(None);
+    }
+    let workspace_edit = to_proto::workspace_edit(&snap, change)?;
+    Ok(Some(workspace_edit))
+}
+
 fn to_assist_context_diagnostics(
     line_index: &LineIndex,
     diagnostics: Vec<Diagnostic>,
@@ -489,8 +645,9 @@ pub(crate) fn handle_hover(snap: Snapshot, params: HoverParams) -> Result<Option
     let position = from_proto::file_position(&snap, params.text_document_position_params)?;
 
     let docs = snap.analysis.get_docs_at_position(position)?;
+    let ty = snap.analysis.type_at_position(position)?;
 
-    to_proto::hover_response(&snap, docs)
+    to_proto::hover_response(&snap, docs, ty)
 }
 
 pub(crate) fn handle_folding_range(
@@ -508,6 +665,44 @@ pub(crate) fn handle_folding_range(
     Ok(Some(res))
 }
 
+pub(crate) fn handle_document_link(
+    snap: Snapshot,
+    params: lsp_types::DocumentLinkParams,
+) -> Result<Option<Vec<lsp_types::DocumentLink>>> {
+    let _p = profile::span("handle_document_link");
+    let file_id = from_proto::file_id(&snap, &params.text_document.uri)?;
+    let links = snap
+        .analysis
+        .document_links(file_id, &snap.config.document_links())?;
+    let line_index = snap.analysis.line_index(file_id)?;
+    let res = links
+        .into_iter()
+        .filter_map(|it| to_proto::document_link(&line_index, it))
+        .collect();
+    Ok(Some(res))
+}
+
+pub(crate) fn handle_linked_editing_range(
+    snap: Snapshot,
+    params: lsp_types::LinkedEditingRangeParams,
+) -> Result<Option<lsp_types::LinkedEditingRanges>> {
+    let _p = profile::span("handle_linked_editing_range");
+    let position = from_proto::file_position(&snap, params.text_document_position_params)?;
+    let ranges = match snap.analysis.linked_editing_ranges(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let line_index = snap.analysis.line_index(position.file_id)?;
+    let ranges = ranges
+        .into_iter()
+        .map(|range| to_proto::range(&line_index, range))
+        .collect();
+    Ok(Some(lsp_types::LinkedEditingRanges {
+        ranges,
+        word_pattern: None,
+    }))
+}
+
 pub(crate) fn handle_document_highlight(
     snap: Snapshot,
     params: lsp_types::DocumentHighlightParams,
@@ -572,7 +767,8 @@ pub(crate) fn handle_call_hierarchy_incoming(
         offset: frange.range.start(),
     };
 
-    let call_items = match snap.analysis.incoming_calls(fpos)? {
+    let include_dynamic = snap.config.call_hierarchy_dynamic_calls();
+    let call_items = match snap.analysis.incoming_calls(fpos, include_dynamic)? {
         None => return Ok(None),
         Some(it) => it,
     };
@@ -610,7 +806,8 @@ pub(crate) fn handle_call_hierarchy_outgoing(
         offset: frange.range.start(),
     };
 
-    let call_items = match snap.analysis.outgoing_calls(fpos)? {
+    let include_dynamic = snap.config.call_hierarchy_dynamic_calls();
+    let call_items = match snap.analysis.outgoing_calls(fpos, include_dynamic)? {
         None => return Ok(None),
         Some(it) => it,
     };
@@ -634,6 +831,63 @@ pub(crate) fn handle_call_hierarchy_outgoing(
     Ok(Some(res))
 }
 
+pub(crate) fn handle_type_hierarchy_prepare(
+    snap: Snapshot,
+    params: TextDocumentPositionParams,
+) -> Result<Option<Vec<CallHierarchyItem>>> {
+    let _p = profile::span("handle_type_hierarchy_prepare");
+    let position = from_proto::file_position(&snap, params)?;
+
+    let navs = match snap.analysis.type_hierarchy_prepare(position)? {
+        None => return Ok(None),
+        Some(it) => it.info,
+    };
+    let res = navs
+        .into_iter()
+        .map(|it| to_proto::call_hierarchy_item(&snap, it))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Some(res))
+}
+
+pub(crate) fn handle_type_hierarchy_incoming(
+    snap: Snapshot,
+    params: CallHierarchyIncomingCallsParams,
+) -> Result<Option<Vec<CallHierarchyIncomingCall>>> {
+    let _p = profile::span("handle_type_hierarchy_incoming");
+    let item = params.item;
+
+    let doc = TextDocumentIdentifier::new(item.uri);
+    let frange = from_proto::file_range(&snap, doc, item.selection_range)?;
+    let fpos = FilePosition {
+        file_id: frange.file_id,
+        offset: frange.range.start(),
+    };
+
+    let items = match snap.analysis.incoming_types(fpos)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+
+    let mut res = vec![];
+
+    for type_item in items.into_iter() {
+        let file_id = type_item.target.file_id;
+        let line_index = snap.analysis.line_index(file_id)?;
+        let item = to_proto::call_hierarchy_item(&snap, type_item.target)?;
+        res.push(CallHierarchyIncomingCall {
+            from: item,
+            from_ranges: type_item
+                .ranges
+                .into_iter()
+                .map(|it| to_proto::range(&line_index, it))
+                .collect(),
+        });
+    }
+
+    Ok(Some(res))
+}
+
 pub(crate) fn handle_signature_help(
     snap: Snapshot,
     params: lsp_types::SignatureHelpParams,
@@ -654,6 +908,67 @@ pub(crate) fn handle_signature_help(
     Ok(Some(res))
 }
 
+pub(crate) fn handle_formatting(
+    snap: Snapshot,
+    params: lsp_types::DocumentFormattingParams,
+) -> Result<Option<Vec<lsp_types::TextEdit>>> {
+    let _p = profile::span("handle_formatting");
+    let file_id = from_proto::file_id(&snap, &params.text_document.uri)?;
+    let change = snap.analysis.format_file(file_id)?;
+    formatting_edits(&snap, file_id, change)
+}
+
+pub(crate) fn handle_range_formatting(
+    snap: Snapshot,
+    params: lsp_types::DocumentRangeFormattingParams,
+) -> Result<Option<Vec<lsp_types::TextEdit>>> {
+    let _p = profile::span("handle_range_formatting");
+    let frange = from_proto::file_range(&snap, params.text_document, params.range)?;
+    let change = snap.analysis.format_range(frange)?;
+    formatting_edits(&snap, frange.file_id, change)
+}
+
+fn formatting_edits(
+    snap: &Snapshot,
+    file_id: FileId,
+    change: Option<SourceChange>,
+) -> Result<Option<Vec<lsp_types::TextEdit>>> {
+    let edit = match change.and_then(|mut change| change.source_file_edits.remove(&file_id)) {
+        Some(edit) => edit,
+        None => return Ok(None),
+    };
+    let line_index = snap.analysis.line_index(file_id)?;
+    let line_endings = snap.line_endings(file_id);
+    Ok(Some(
+        edit.into_iter()
+            .map(|indel| to_proto::text_edit(&line_index, line_endings, indel))
+            .collect(),
+    ))
+}
+
+pub(crate) fn handle_on_type_formatting(
+    snap: Snapshot,
+    params: lsp_types::DocumentOnTypeFormattingParams,
+) -> Result<Option<Vec<lsp_types::TextEdit>>> {
+    let _p = profile::span("handle_on_type_formatting");
+    let typed_char = match params.ch.chars().next() {
+        Some(typed_char) => typed_char,
+        None => return Ok(None),
+    };
+    let position = from_proto::file_position(&snap, params.text_document_position)?;
+    let edit = match snap.analysis.on_type_formatting(position, typed_char)? {
+        Some(edit) => edit,
+        None => return Ok(None),
+    };
+    let line_index = snap.analysis.line_index(position.file_id)?;
+    let line_endings = snap.line_endings(position.file_id);
+    Ok(Some(
+        edit.into_iter()
+            .map(|indel| to_proto::text_edit(&line_index, line_endings, indel))
+            .collect(),
+    ))
+}
+
 // ---------------------------------------------------------------------
 
 pub(crate) fn handle_semantic_tokens_full(
@@ -667,12 +982,16 @@ pub(crate) fn handle_semantic_tokens_full(
     let line_index = snap.analysis.line_index(file_id)?;
 
     let highlights = snap.analysis.highlight(file_id)?;
-    let semantic_tokens = to_proto::semantic_tokens(&text, &line_index, highlights);
+    let semantic_tokens = to_proto::semantic_tokens(&text, &line_index, highlights.clone());
 
     // Unconditionally cache the tokens
-    snap.semantic_tokens_cache
-        .lock()
-        .insert(params.text_document.uri, semantic_tokens.clone());
+    snap.semantic_tokens_cache.write().insert(
+        params.text_document.uri,
+        CachedSemanticTokens {
+            tokens: semantic_tokens.clone(),
+            highlights,
+        },
+    );
 
     Ok(Some(semantic_tokens.into()))
 }
@@ -687,23 +1006,40 @@ pub(crate) fn handle_semantic_tokens_full_delta(
     let text = snap.analysis.file_text(file_id)?;
     let line_index = snap.analysis.line_index(file_id)?;
 
-    let highlights = snap.analysis.highlight(file_id)?;
-    let semantic_tokens = to_proto::semantic_tokens(&text, &line_index, highlights);
-
-    let mut cache = snap.semantic_tokens_cache.lock();
-    let cached_tokens = cache.entry(params.text_document.uri).or_default();
-
-    if let Some(prev_id) = &cached_tokens.result_id {
-        if *prev_id == params.previous_result_id {
-            let delta = to_proto::semantic_token_delta(cached_tokens, &semantic_tokens);
-            *cached_tokens = semantic_tokens;
-            return Ok(Some(delta.into()));
+    let cached = snap
+        .semantic_tokens_cache
+        .read()
+        .get(&params.text_document.uri)
+        .filter(|cached| cached.tokens.result_id.as_ref() == Some(&params.previous_result_id))
+        .cloned();
+
+    let (highlights, previous) = match cached {
+        Some(cached) => {
+            // The highlights may be the same while the tokens changed,
+            // e.g. when a space before them became a newline, so the
+            // tokens are always compared
+            let delta = snap.analysis.highlight_delta(file_id, &cached.highlights)?;
+            (delta.highlights, Some(cached.tokens))
+        }
+        None => (snap.analysis.highlight(file_id)?, None),
+    };
+    let semantic_tokens = to_proto::semantic_tokens(&text, &line_index, highlights.clone());
+
+    snap.semantic_tokens_cache.write().insert(
+        params.text_document.uri,
+        CachedSemanticTokens {
+            tokens: semantic_tokens.clone(),
+            highlights,
+        },
+    );
+
+    match previous {
+        Some(previous) => {
+            let delta = to_proto::semantic_token_delta(&previous, &semantic_tokens);
+            Ok(Some(delta.into()))
         }
+        None => Ok(Some(semantic_tokens.into())),
     }
-
-    *cached_tokens = semantic_tokens.clone();
-
-    Ok(Some(semantic_tokens.into()))
 }
 
 pub(crate) fn handle_semantic_tokens_range(
@@ -727,28 +1063,79 @@ pub(crate) fn handle_code_lens(
 ) -> Result<Option<Vec<CodeLens>>> {
     let _p = profile::span("handle_code_lens");
 
-    let mut res = Vec::new();
     let lens_config = snap.config.lens();
-    if !lens_config.run {
+    if !lens_config.any() {
         // early return before any db query!
-        return Ok(Some(res));
+        return Ok(Some(Vec::new()));
     }
 
     let file_id = from_proto::file_id(&snap, &params.text_document.uri)?;
+    let line_index = snap.analysis.line_index(file_id)?;
+    let version = snap.url_file_version(&params.text_document.uri);
 
-    let annotations = snap.analysis.annotations(file_id)?;
-    let project_build_data = match snap.analysis.project_id(file_id) {
-        Ok(Some(project_id)) => snap
-            .get_project(project_id)
-            .map(|project| project.project_build_data),
-        _ => None,
+    // The commands are computed lazily, in `handle_code_lens_resolve`
+    let lenses = snap.analysis.code_lenses(file_id, &lens_config)?;
+    let res = lenses
+        .iter()
+        .enumerate()
+        .map(|(index, lens)| {
+            let data = lsp_ext::CodeLensData {
+                text_document: params.text_document.clone(),
+                version,
+                index,
+            };
+            to_proto::code_lens(&line_index, lens, data)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Some(res))
+}
+
+pub(crate) fn handle_code_lens_resolve(snap: Snapshot, mut lens: CodeLens) -> Result<CodeLens> {
+    let _p = profile::span("handle_code_lens_resolve");
+
+    let data = match lens.data.take() {
+        Some(data) => data,
+        None => return Ok(lens),
     };
+    let data: lsp_ext::CodeLensData = serde_json::from_value(data)?;
+    if snap.url_file_version(&data.text_document.uri) != data.version {
+        // The document changed, the client will ask for new lenses
+        return Ok(lens);
+    }
 
-    for a in annotations {
-        to_proto::code_lens(&mut res, &snap, a, project_build_data.clone())?;
+    let file_id = from_proto::file_id(&snap, &data.text_document.uri)?;
+    let lenses = snap.analysis.code_lenses(file_id, &snap.config.lens())?;
+    if let Some(resolved) = lenses.into_iter().nth(data.index) {
+        let project_build_data = match snap.analysis.project_id(file_id) {
+            Ok(Some(project_id)) => snap
+                .get_project(project_id)
+                .map(|project| project.project_build_data),
+            _ => None,
+        };
+        lens.command = to_proto::code_lens_command(&snap, resolved, project_build_data)?;
     }
 
-    Ok(Some(res))
+    Ok(lens)
+}
+
+pub(crate) fn handle_test_statuses(
+    snap: Snapshot,
+    params: lsp_ext::TestStatusesParams,
+) -> Result<Vec<lsp_ext::TestStatus>> {
+    let _p = profile::span("handle_test_statuses");
+    let file_id = from_proto::file_id(&snap, &params.text_document.uri)?;
+    let text = fs::read_to_string(&params.results_file)
+        .with_context(|| format!("Could not read {}", params.results_file.display()))?;
+    let results = TestResults::parse(&text);
+    let line_index = snap.analysis.line_index(file_id)?;
+    let res = snap
+        .analysis
+        .test_statuses(file_id, &results)?
+        .into_iter()
+        .map(|it| to_proto::test_status(&line_index, it))
+        .collect();
+    Ok(res)
 }
 
 pub(crate) fn handle_external_docs(
@@ -759,7 +1146,9 @@ pub(crate) fn handle_external_docs(
 
     let position = from_proto::file_position(&snap, params)?;
 
-    let docs = snap.analysis.external_docs(position)?;
+    let docs = snap
+        .analysis
+        .external_docs(position, snap.config.otp_docs_base_url())?;
     Ok(docs.map(|links| {
         links
             .iter()
@@ -780,12 +1169,13 @@ pub(crate) fn handle_inlay_hints(
         params.range,
     )?;
     let line_index = snap.analysis.line_index(file_id)?;
+    let line_endings = snap.line_endings(file_id);
     let inlay_hints_config = snap.config.inlay_hints();
     Ok(Some(
         snap.analysis
             .inlay_hints(&inlay_hints_config, file_id, Some(range))?
             .into_iter()
-            .map(|it| to_proto::inlay_hint(&snap, &line_index, it))
+            .map(|it| to_proto::inlay_hint(&snap, &line_index, line_endings, it))
             .collect::<Cancellable<Vec<_>>>()?,
     ))
 }
@@ -24,6 +24,7 @@ pub mod build;
 pub mod cli;
 pub mod config;
 pub mod convert;
+mod diagnostic_docs;
 mod diagnostics;
 pub mod document;
 mod from_proto;
@@ -38,6 +38,19 @@ pub struct CompletionData {
     pub position: TextDocumentPositionParams,
 }
 
+/// Custom data we put into the code lens 'data' field, to compute
+/// the command of the lens in the 'resolve' step
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeLensData {
+    pub text_document: TextDocumentIdentifier,
+    /// The version of the document the lens was computed for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<i32>,
+    /// The position of the lens in the lenses of the document
+    pub index: usize,
+}
+
 // ---------------------------------------------------------------------
 
 pub enum ExpandMacro {}
@@ -53,6 +66,21 @@ impl Request for ExpandMacro {
 pub struct ExpandMacroParams {
     pub text_document: TextDocumentIdentifier,
     pub position: Position,
+    /// How much to expand, `recursive` when not given
+    #[serde(default)]
+    pub mode: ExpandMacroMode,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum ExpandMacroMode {
+    /// The macro at the position, and the macros it uses
+    #[default]
+    Recursive,
+    /// Only the macro at the position, with its arguments substituted
+    SingleStep,
+    /// Every macro of the function enclosing the position
+    Function,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -62,6 +90,32 @@ pub struct ExpandedMacro {
     pub expansion: String,
 }
 
+// ---------------------------------------------------------------------
+
+/// How long each diagnostic pass took on a document, to find out which
+/// lints make diagnostics slow to appear.
+pub enum DiagnosticsTiming {}
+
+impl Request for DiagnosticsTiming {
+    type Params = TextDocumentIdentifier;
+    type Result = DiagnosticsTimingResult;
+    const METHOD: &'static str = "elp/diagnosticsTiming";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsTimingResult {
+    pub total_ms: f64,
+    pub passes: Vec<DiagnosticsPassTiming>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsPassTiming {
+    pub name: String,
+    pub ms: f64,
+}
+
 // ---------------------------------------------------------------------
 pub enum StatusNotification {}
 
@@ -83,6 +137,52 @@ impl Notification for StatusNotification {
     const METHOD: &'static str = "elp/status";
 }
 
+/// Lists the background jobs currently running in the server, and
+/// reports on the health of the eqWAlizer subprocess.
+pub enum JobsRequest {}
+
+impl Request for JobsRequest {
+    type Params = ();
+    type Result = ServerStatus;
+    const METHOD: &'static str = "elp/jobs";
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerStatus {
+    pub jobs: Vec<JobStatus>,
+    pub eqwalizer: EqwalizerHealth,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EqwalizerHealth {
+    /// Calls made to eqWAlizer, including retries
+    pub calls: u64,
+    pub timeouts: u64,
+    pub retries: u64,
+    /// Calls which failed even after being retried
+    pub failures: u64,
+    pub average_latency_ms: f64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct JobStatus {
+    /// Matches the `ELP/<id>` token used in `$/progress` notifications.
+    pub id: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<usize>,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percentage: Option<u32>,
+    pub cancellable: bool,
+    pub cancelled: bool,
+    pub elapsed_ms: u64,
+}
+
 // ---------------------------------------------------------------------
 
 pub enum Ping {}
@@ -119,6 +219,50 @@ pub struct Buck2RunnableArgs {
     pub target: String,
     pub id: String,
 }
+
+// ---------------------------------------------------------------------
+
+/// The runnables of a test suite, with their status in the results of a
+/// test run, so that the test explorer can show them.
+pub enum TestStatuses {}
+
+impl Request for TestStatuses {
+    type Params = TestStatusesParams;
+    type Result = Vec<TestStatus>;
+    const METHOD: &'static str = "elp/testStatuses";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TestStatusesParams {
+    pub text_document: TextDocumentIdentifier,
+    /// The Common Test `suite.log`, or surefire XML report, of the run
+    pub results_file: PathBuf,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TestStatus {
+    /// The id of the runnable, as in `Buck2RunnableArgs`
+    pub id: String,
+    pub range: lsp_types::Range,
+    pub status: TestStatusKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum TestStatusKind {
+    Passed,
+    Failed,
+    Skipped,
+    /// The run has no result for the runnable
+    NotRun,
+}
+
+// ---------------------------------------------------------------------
+
 pub enum ExternalDocs {}
 
 impl Request for ExternalDocs {
@@ -126,3 +270,26 @@ impl Request for ExternalDocs {
     type Result = Option<Vec<lsp_types::Url>>;
     const METHOD: &'static str = "experimental/externalDocs";
 }
+
+// ---------------------------------------------------------------------
+
+/// The type definitions at a position, in the format of a call
+/// hierarchy item so the client can render them with the same UI.
+pub enum TypeHierarchyPrepare {}
+
+impl Request for TypeHierarchyPrepare {
+    type Params = lsp_types::TextDocumentPositionParams;
+    type Result = Option<Vec<lsp_types::CallHierarchyItem>>;
+    const METHOD: &'static str = "elp/typeHierarchyPrepare";
+}
+
+/// The specs, callbacks and types referring to a type returned by
+/// `elp/typeHierarchyPrepare`. Types in the result can be expanded
+/// further with another request.
+pub enum TypeHierarchyIncoming {}
+
+impl Request for TypeHierarchyIncoming {
+    type Params = lsp_types::CallHierarchyIncomingCallsParams;
+    type Result = Option<Vec<lsp_types::CallHierarchyIncomingCall>>;
+    const METHOD: &'static str = "elp/typeHierarchyIncoming";
+}
@@ -41,12 +41,14 @@ impl ProjectLoader {
     }
 
     pub fn load_manifest_if_new(&mut self, path: &AbsPath) -> Result<Option<ProjectManifest>> {
-        let mut path_it = path;
-        while let Some(path) = path_it.parent() {
+        // The path may be a project root itself, when it is a workspace
+        // folder
+        let mut path_it = Some(path);
+        while let Some(path) = path_it {
             if self.project_roots.contains(path) {
                 return Ok(None);
             }
-            path_it = path;
+            path_it = path.parent();
         }
         let conf = DiscoverConfig::buck();
         let manifest = match ProjectManifest::discover_single(&path, &conf) {
@@ -78,6 +80,12 @@ impl ProjectLoader {
         }
     }
 
+    /// Forget the project at `root`, so that it is loaded again when
+    /// one of its files is opened.
+    pub fn unload(&mut self, root: &AbsPath) {
+        self.project_roots.remove(root);
+    }
+
     pub fn load_completed(&mut self) {
         if !self.initialized {
             self.initialized = true;
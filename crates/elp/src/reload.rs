@@ -35,6 +35,7 @@ impl ProjectFolders {
                         .iter()
                         .map(|src| VfsPath::from(src.clone()))
                         .collect();
+                    file_sets.extend(app.generated_include_dirs().into_iter().map(VfsPath::from));
                     let dir = VfsPath::from(app.dir.clone());
                     file_sets.push(dir);
                     builder.add_file_set(file_sets);
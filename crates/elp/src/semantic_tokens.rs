@@ -88,11 +88,14 @@ macro_rules! define_semantic_token_modifiers {
 
 define_semantic_token_modifiers![
     standard {
+        DEPRECATED,
     }
     custom {
         (BOUND, "bound"),
         (EXPORTED_FUNCTION, "exported_function"),
         (DEPRECATED_FUNCTION, "deprecated_function"),
+        (TEST_CODE, "test_code"),
+        (GENERATED, "generated"),
     }
 ];
 
@@ -18,9 +18,11 @@ use crossbeam_channel::select;
 use crossbeam_channel::Receiver;
 use dispatch::NotificationDispatcher;
 use elp_ai::AiCompletion;
+use elp_ide::elp_ide_db::elp_base_db::encoding;
 use elp_ide::elp_ide_db::elp_base_db::loader;
 use elp_ide::elp_ide_db::elp_base_db::AbsPath;
 use elp_ide::elp_ide_db::elp_base_db::AbsPathBuf;
+use elp_ide::elp_ide_db::elp_base_db::AppStructure;
 use elp_ide::elp_ide_db::elp_base_db::ChangeKind;
 use elp_ide::elp_ide_db::elp_base_db::ChangedFile;
 use elp_ide::elp_ide_db::elp_base_db::FileId;
@@ -30,8 +32,7 @@ use elp_ide::elp_ide_db::elp_base_db::ProjectApps;
 use elp_ide::elp_ide_db::elp_base_db::ProjectId;
 use elp_ide::elp_ide_db::elp_base_db::SourceDatabase;
 use elp_ide::elp_ide_db::elp_base_db::SourceDatabaseExt;
-use elp_ide::elp_ide_db::elp_base_db::SourceRoot;
-use elp_ide::elp_ide_db::elp_base_db::SourceRootId;
+use elp_ide::elp_ide_db::elp_base_db::SourceRoots;
 use elp_ide::elp_ide_db::elp_base_db::Vfs;
 use elp_ide::elp_ide_db::elp_base_db::VfsPath;
 use elp_ide::AnalysisHost;
@@ -40,6 +41,8 @@ use elp_log::telemetry::TelemetryMessage;
 use elp_log::timeit;
 use elp_log::Logger;
 use elp_log::TimeIt;
+use elp_project_model::lint_file::LintFileConfig;
+use elp_project_model::lint_file::ELP_LINT_FILE;
 use elp_project_model::Project;
 use lsp_server::Connection;
 use lsp_server::ErrorCode;
@@ -64,12 +67,14 @@ use self::progress::Spinner;
 use crate::config::Config;
 use crate::convert;
 use crate::diagnostics::DiagnosticCollection;
+use crate::diagnostics::DiagnosticsPass;
 use crate::document::Document;
 use crate::handlers;
 use crate::line_endings::LineEndings;
 use crate::lsp_ext;
 use crate::project_loader::ProjectLoader;
 use crate::reload::ProjectFolders;
+use crate::snapshot::CachedSemanticTokens;
 use crate::snapshot::SharedMap;
 use crate::snapshot::Snapshot;
 use crate::task_pool::TaskPool;
@@ -77,7 +82,7 @@ use crate::task_pool::TaskPool;
 mod capabilities;
 mod dispatch;
 mod logger;
-mod progress;
+pub(crate) mod progress;
 pub mod setup;
 
 const LOGGER_NAME: &str = "lsp";
@@ -95,10 +100,10 @@ enum Event {
 pub enum Task {
     Response(lsp_server::Response),
     FetchProject(Result<Project>),
-    NativeDiagnostics(Vec<(FileId, Vec<Diagnostic>)>),
-    EqwalizerDiagnostics(Spinner, Vec<(FileId, Vec<Diagnostic>)>),
-    EdocDiagnostics(Spinner, Vec<(FileId, Vec<Diagnostic>)>),
-    ParseServerDiagnostics(Vec<(FileId, Vec<Diagnostic>)>),
+    /// A project loaded again, as the files defining its apps changed
+    ReloadProject(usize, Result<Project>),
+    /// The diagnostics of a pass for a file, computed for a revision
+    Diagnostics(DiagnosticsPass, FileId, u32, Vec<Diagnostic>),
     CompileDeps(Spinner),
     Progress(ProgressTask),
     ScheduleCache,
@@ -176,14 +181,21 @@ pub struct Server {
     newly_opened_documents: Vec<ChangedFile>,
     vfs: Arc<RwLock<Vfs>>,
     file_set_config: FileSetConfig,
+    /// The app structure and source roots set in the database, so that
+    /// a reload sets again only those which changed
+    app_structure: AppStructure,
+    source_roots: SourceRoots,
     line_ending_map: SharedMap<FileId, LineEndings>,
+    semantic_tokens_cache: SharedMap<Url, CachedSemanticTokens>,
     config: Arc<Config>,
     analysis_host: AnalysisHost,
     status: Status,
     projects: Arc<Vec<Project>>,
     project_loader: Arc<Mutex<ProjectLoader>>,
+    native_diagnostics_requested: bool,
     eqwalizer_diagnostics_requested: bool,
     edoc_diagnostics_requested: bool,
+    plugin_diagnostics_requested: bool,
     logger: Logger,
     ai_completion: Arc<Mutex<AiCompletion>>,
 
@@ -215,14 +227,19 @@ impl Server {
             newly_opened_documents: Vec::default(),
             vfs: Arc::new(RwLock::new(Vfs::default())),
             file_set_config: FileSetConfig::default(),
+            app_structure: AppStructure::default(),
+            source_roots: SourceRoots::default(),
             line_ending_map: SharedMap::default(),
+            semantic_tokens_cache: SharedMap::default(),
             config: Arc::new(config.clone()),
             analysis_host: AnalysisHost::default(),
             status: Status::Initialising,
             projects: Arc::new(vec![]),
             project_loader: Arc::new(Mutex::new(ProjectLoader::new())),
+            native_diagnostics_requested: false,
             eqwalizer_diagnostics_requested: false,
             edoc_diagnostics_requested: false,
+            plugin_diagnostics_requested: false,
             logger,
             ai_completion: Arc::new(Mutex::new(ai_completion)),
             vfs_config_version: 0,
@@ -230,6 +247,9 @@ impl Server {
 
         // Run config-based initialisation
         this.update_configuration(config);
+        for folder in this.config.workspace_folders.clone() {
+            this.fetch_workspace_folder(folder);
+        }
         this
     }
 
@@ -240,6 +260,7 @@ impl Server {
             Arc::clone(&self.vfs),
             Arc::clone(&self.open_document_versions),
             Arc::clone(&self.line_ending_map),
+            Arc::clone(&self.semantic_tokens_cache),
             Arc::clone(&self.projects),
             Arc::clone(&self.ai_completion),
         )
@@ -353,17 +374,11 @@ impl Server {
                 match task {
                     Task::Response(response) => self.send_response(response),
                     Task::FetchProject(project) => self.fetch_project_completed(project)?,
-                    Task::NativeDiagnostics(diags) => self.native_diagnostics_completed(diags),
-                    Task::EqwalizerDiagnostics(spinner, diags) => {
-                        spinner.end();
-                        self.eqwalizer_diagnostics_completed(diags)
-                    }
-                    Task::EdocDiagnostics(spinner, diags) => {
-                        spinner.end();
-                        self.edoc_diagnostics_completed(diags)
+                    Task::ReloadProject(idx, project) => {
+                        self.reload_project_completed(idx, project)?
                     }
-                    Task::ParseServerDiagnostics(diags) => {
-                        self.erlang_service_diagnostics_completed(diags)
+                    Task::Diagnostics(pass, file_id, revision, diags) => {
+                        self.diagnostics.set(pass, file_id, revision, diags)
                     }
                     Task::CompileDeps(spinner) => {
                         self.analysis_host
@@ -393,7 +408,7 @@ impl Server {
         let changed = self.process_changes_to_vfs_store();
 
         if self.status == Status::Running {
-            if changed {
+            if mem::take(&mut self.native_diagnostics_requested) || changed {
                 self.update_native_diagnostics();
             }
 
@@ -405,6 +420,10 @@ impl Server {
             if mem::take(&mut self.edoc_diagnostics_requested) {
                 self.update_edoc_diagnostics();
             }
+
+            if mem::take(&mut self.plugin_diagnostics_requested) {
+                self.update_plugin_diagnostics();
+            }
         }
 
         if let Some(diagnostic_changes) = self.diagnostics.take_changes() {
@@ -464,17 +483,23 @@ impl Server {
                 this.analysis_host.request_cancellation();
                 Ok(())
             })?
+            .on_sync::<lsp_ext::JobsRequest>(|this, ()| Ok(this.status()))?
             .on::<request::CodeActionRequest>(handlers::handle_code_action)
             .on::<request::CodeActionResolveRequest>(handlers::handle_code_action_resolve)
             .on::<request::GotoDefinition>(handlers::handle_goto_definition)
+            .on::<request::GotoImplementation>(handlers::handle_goto_implementation)
             .on::<request::References>(handlers::handle_references)
             .on::<request::Completion>(handlers::handle_completion)
             .on::<request::ResolveCompletionItem>(handlers::handle_completion_resolve)
             .on::<request::DocumentSymbolRequest>(handlers::handle_document_symbol)
             .on::<request::WorkspaceSymbol>(handlers::handle_workspace_symbol)
+            .on::<request::PrepareRenameRequest>(handlers::handle_prepare_rename)
             .on::<request::Rename>(handlers::handle_rename)
+            .on::<request::WillRenameFiles>(handlers::handle_will_rename_files)
             .on::<request::HoverRequest>(handlers::handle_hover)
             .on::<request::FoldingRangeRequest>(handlers::handle_folding_range)
+            .on::<request::LinkedEditingRange>(handlers::handle_linked_editing_range)
+            .on::<request::DocumentLinkRequest>(handlers::handle_document_link)
             .on::<request::DocumentHighlightRequest>(handlers::handle_document_highlight)
             .on::<lsp_types::request::CallHierarchyPrepare>(handlers::handle_call_hierarchy_prepare)
             .on::<lsp_types::request::CallHierarchyIncomingCalls>(
@@ -484,6 +509,9 @@ impl Server {
                 handlers::handle_call_hierarchy_outgoing,
             )
             .on::<request::SignatureHelpRequest>(handlers::handle_signature_help)
+            .on::<request::Formatting>(handlers::handle_formatting)
+            .on::<request::RangeFormatting>(handlers::handle_range_formatting)
+            .on::<request::OnTypeFormatting>(handlers::handle_on_type_formatting)
             .on::<request::SelectionRangeRequest>(handlers::handle_selection_range)
             .on::<request::SemanticTokensFullRequest>(handlers::handle_semantic_tokens_full)
             .on::<request::SemanticTokensFullDeltaRequest>(
@@ -491,11 +519,16 @@ impl Server {
             )
             .on::<request::SemanticTokensRangeRequest>(handlers::handle_semantic_tokens_range)
             .on::<request::CodeLensRequest>(handlers::handle_code_lens)
+            .on::<request::CodeLensResolve>(handlers::handle_code_lens_resolve)
             .on::<request::InlayHintRequest>(handlers::handle_inlay_hints)
             .on::<request::InlayHintResolveRequest>(handlers::handle_inlay_hints_resolve)
             .on::<lsp_ext::ExpandMacro>(handlers::handle_expand_macro)
+            .on::<lsp_ext::DiagnosticsTiming>(handlers::handle_diagnostics_timing)
             .on::<lsp_ext::Ping>(handlers::pong)
             .on::<lsp_ext::ExternalDocs>(handlers::handle_external_docs)
+            .on::<lsp_ext::TestStatuses>(handlers::handle_test_statuses)
+            .on::<lsp_ext::TypeHierarchyPrepare>(handlers::handle_type_hierarchy_prepare)
+            .on::<lsp_ext::TypeHierarchyIncoming>(handlers::handle_type_hierarchy_incoming)
             .finish();
 
         Ok(())
@@ -508,9 +541,16 @@ impl Server {
                 this.cancel(id);
                 Ok(())
             })?
+            .on::<notification::WorkDoneProgressCancel>(|this, params| {
+                if !this.progress.cancel(&params.token) {
+                    log::warn!("cannot cancel progress {:?}", params.token);
+                }
+                Ok(())
+            })?
             .on::<notification::DidOpenTextDocument>(|this, params| {
                 this.eqwalizer_diagnostics_requested = true;
                 this.edoc_diagnostics_requested = true;
+                this.plugin_diagnostics_requested = true;
                 if let Ok(path) = convert::abs_path(&params.text_document.uri) {
                     this.fetch_projects_if_needed(&path);
                     let path = VfsPath::from(path);
@@ -576,6 +616,9 @@ impl Server {
                         log::error!("unexpected DidCloseTextDocument: {}", path);
                     }
                 }
+                this.semantic_tokens_cache
+                    .write()
+                    .remove(&params.text_document.uri);
 
                 // Clear the diagnostics for the previously known version of the file.
                 this.send_notification::<lsp_types::notification::PublishDiagnostics>(
@@ -592,6 +635,7 @@ impl Server {
                 if convert::vfs_path(&params.text_document.uri).is_ok() {
                     this.eqwalizer_diagnostics_requested = true;
                     this.edoc_diagnostics_requested = true;
+                    this.plugin_diagnostics_requested = true;
                 }
                 Ok(())
             })?
@@ -606,6 +650,26 @@ impl Server {
                 // Nothing to do for now
                 Ok(())
             })?
+            .on::<notification::DidChangeWorkspaceFolders>(|this, params| {
+                let folders = |folders: Vec<lsp_types::WorkspaceFolder>| -> Vec<AbsPathBuf> {
+                    folders
+                        .iter()
+                        .filter_map(|folder| convert::abs_path(&folder.uri).ok())
+                        .collect()
+                };
+                let added = folders(params.event.added);
+                let removed = folders(params.event.removed);
+                let mut config = Config::clone(&*this.config);
+                config
+                    .workspace_folders
+                    .retain(|folder| !removed.contains(folder));
+                config.workspace_folders.extend(added.iter().cloned());
+                this.update_configuration(config);
+                for folder in added {
+                    this.fetch_workspace_folder(folder);
+                }
+                this.unload_workspace_folders(&removed)
+            })?
             .on::<notification::DidChangeWatchedFiles>(|this, params| {
                 for change in params.changes {
                     if let Ok(path) = convert::abs_path(&change.uri) {
@@ -614,6 +678,16 @@ impl Server {
                                 this.open_document_versions.read().contains_key(&vfs_path)
                             })
                             .unwrap_or(false);
+                        if path.as_ref().ends_with(ELP_LINT_FILE) {
+                            this.reload_lint_file(&path);
+                        }
+                        if let Some(idx) = this
+                            .projects
+                            .iter()
+                            .position(|project| project.is_structure_file(&path))
+                        {
+                            this.reload_project(idx);
+                        }
                         if !opened {
                             this.vfs_loader.handle.invalidate(path);
                         }
@@ -621,6 +695,7 @@ impl Server {
                 }
                 this.eqwalizer_diagnostics_requested = true;
                 this.edoc_diagnostics_requested = true;
+                this.plugin_diagnostics_requested = true;
                 Ok(())
             })?
             .finish();
@@ -658,7 +733,7 @@ impl Server {
             if !self.open_document_versions.read().contains_key(&path) {
                 // This call will add the file to the changed_files, picked
                 // up in `process_changes`.
-                vfs.set_file_contents(path, contents);
+                vfs.set_file_contents(path, contents.map(encoding::to_utf8));
             }
         }
     }
@@ -699,8 +774,7 @@ impl Server {
                     .write()
                     .insert(file.file_id, line_ending);
                 raw_database.set_file_text(file.file_id, Arc::new(text));
-                // causes us to remove stale squiggles from the UI
-                self.diagnostics.set_eqwalizer(file.file_id, vec![]);
+                self.diagnostics.file_changed(file.file_id);
             } else {
                 // TODO (T105975906): Clean up stale .etf files
 
@@ -713,15 +787,8 @@ impl Server {
             .iter()
             .any(|file| file.is_created_or_deleted())
         {
-            let sets = self.file_set_config.partition(&vfs);
-            for (idx, set) in sets.into_iter().enumerate() {
-                let root_id = SourceRootId(idx as u32);
-                for file_id in set.iter() {
-                    raw_database.set_file_source_root(file_id, root_id);
-                }
-                let root = SourceRoot::new(set);
-                raw_database.set_source_root(root_id, Arc::new(root));
-            }
+            self.source_roots
+                .apply(self.file_set_config.partition(&vfs), raw_database);
         }
 
         true
@@ -738,24 +805,21 @@ impl Server {
 
     fn update_native_diagnostics(&mut self) {
         let opened_documents = self.opened_documents();
+        let revisions = self.diagnostics.revisions();
         let snapshot = self.snapshot();
 
-        self.task_pool.handle.spawn(move || {
-            let diagnostics = opened_documents
-                .into_iter()
-                .filter_map(|file_id| Some((file_id, snapshot.native_diagnostics(file_id)?)))
-                .collect();
-
-            Task::NativeDiagnostics(diagnostics)
+        self.task_pool.handle.spawn_with_sender(move |sender| {
+            for file_id in opened_documents {
+                if let Some(diagnostics) = snapshot.native_diagnostics(file_id) {
+                    let revision = revisions.get(file_id);
+                    let task =
+                        Task::Diagnostics(DiagnosticsPass::Native, file_id, revision, diagnostics);
+                    sender.send(task).unwrap();
+                }
+            }
         });
     }
 
-    fn native_diagnostics_completed(&mut self, diags: Vec<(FileId, Vec<Diagnostic>)>) {
-        for (file_id, diagnostics) in diags {
-            self.diagnostics.set_native(file_id, diagnostics);
-        }
-    }
-
     fn update_eqwalizer_diagnostics(&mut self) {
         if self.status != Status::Running {
             return;
@@ -764,17 +828,30 @@ impl Server {
         log::info!("Recomputing EqWAlizer diagnostics");
 
         let opened_documents = self.opened_documents();
+        let revisions = self.diagnostics.revisions();
         let snapshot = self.snapshot();
 
-        let spinner = self.progress.begin_spinner("EqWAlizing".to_string());
-
-        self.task_pool.handle.spawn(move || {
-            let diagnostics = opened_documents
-                .into_iter()
-                .filter_map(|file_id| Some((file_id, snapshot.eqwalizer_diagnostics(file_id)?)))
-                .collect();
+        let spinner = self
+            .progress
+            .begin_cancellable_spinner("EqWAlizing".to_string());
 
-            Task::EqwalizerDiagnostics(spinner, diagnostics)
+        self.task_pool.handle.spawn_with_sender(move |sender| {
+            for file_id in opened_documents {
+                if spinner.is_cancelled() {
+                    break;
+                }
+                if let Some(diagnostics) = snapshot.eqwalizer_diagnostics(file_id) {
+                    let revision = revisions.get(file_id);
+                    let task = Task::Diagnostics(
+                        DiagnosticsPass::Eqwalizer,
+                        file_id,
+                        revision,
+                        diagnostics,
+                    );
+                    sender.send(task).unwrap();
+                }
+            }
+            spinner.end();
         });
     }
 
@@ -786,35 +863,61 @@ impl Server {
         log::info!("Recomputing EDoc diagnostics");
 
         let opened_documents = self.opened_documents();
+        let revisions = self.diagnostics.revisions();
         let snapshot = self.snapshot();
 
-        let spinner = self.progress.begin_spinner("EDoc".to_string());
+        let spinner = self.progress.begin_cancellable_spinner("EDoc".to_string());
 
         let supported_opened_documents: Vec<FileId> = opened_documents
             .into_iter()
             .filter(|file_id| is_supported_by_edoc(&self.vfs.read(), *file_id))
             .collect();
-        self.task_pool.handle.spawn(move || {
-            let diagnostics = supported_opened_documents
-                .into_iter()
-                .filter_map(|file_id| snapshot.edoc_diagnostics(file_id))
-                .flatten()
-                .collect();
-
-            Task::EdocDiagnostics(spinner, diagnostics)
+        self.task_pool.handle.spawn_with_sender(move |sender| {
+            for file_id in supported_opened_documents {
+                if spinner.is_cancelled() {
+                    break;
+                }
+                for (file_id, diagnostics) in snapshot.edoc_diagnostics(file_id).unwrap_or_default()
+                {
+                    let revision = revisions.get(file_id);
+                    let task =
+                        Task::Diagnostics(DiagnosticsPass::Edoc, file_id, revision, diagnostics);
+                    sender.send(task).unwrap();
+                }
+            }
+            spinner.end();
         });
     }
 
-    fn eqwalizer_diagnostics_completed(&mut self, diags: Vec<(FileId, Vec<Diagnostic>)>) {
-        for (file_id, diagnostics) in diags {
-            self.diagnostics.set_eqwalizer(file_id, diagnostics);
+    fn update_plugin_diagnostics(&mut self) {
+        if self.status != Status::Running || !self.config.plugin_diagnostics() {
+            return;
         }
-    }
 
-    fn edoc_diagnostics_completed(&mut self, diags: Vec<(FileId, Vec<Diagnostic>)>) {
-        for (file_id, diagnostics) in diags {
-            self.diagnostics.set_edoc(file_id, diagnostics);
-        }
+        log::info!("Recomputing plugin diagnostics");
+
+        let opened_documents = self.opened_documents();
+        let revisions = self.diagnostics.revisions();
+        let snapshot = self.snapshot();
+
+        let spinner = self
+            .progress
+            .begin_cancellable_spinner("Lint plugins".to_string());
+
+        self.task_pool.handle.spawn_with_sender(move |sender| {
+            for file_id in opened_documents {
+                if spinner.is_cancelled() {
+                    break;
+                }
+                if let Some(diagnostics) = snapshot.plugin_diagnostics(file_id) {
+                    let revision = revisions.get(file_id);
+                    let task =
+                        Task::Diagnostics(DiagnosticsPass::Plugins, file_id, revision, diagnostics);
+                    sender.send(task).unwrap();
+                }
+            }
+            spinner.end();
+        });
     }
 
     fn update_erlang_service_diagnostics(&mut self) {
@@ -825,25 +928,48 @@ impl Server {
         log::info!("Recomputing Erlang Service diagnostics");
 
         let opened_documents = self.opened_documents();
+        let revisions = self.diagnostics.revisions();
         let snapshot = self.snapshot();
         let supported_opened_documents: Vec<FileId> = opened_documents
             .into_iter()
             .filter(|file_id| is_supported_by_parse_server(&self.vfs.read(), *file_id))
             .collect();
-        self.task_pool.handle.spawn(move || {
-            let diagnostics = supported_opened_documents
-                .into_iter()
-                .filter_map(|file_id| snapshot.erlang_service_diagnostics(file_id))
-                .flatten()
-                .collect();
-
-            Task::ParseServerDiagnostics(diagnostics)
+        self.task_pool.handle.spawn_with_sender(move |sender| {
+            for file_id in supported_opened_documents {
+                let diagnostics = snapshot
+                    .erlang_service_diagnostics(file_id)
+                    .unwrap_or_default();
+                for (file_id, diagnostics) in diagnostics {
+                    let revision = revisions.get(file_id);
+                    let task = Task::Diagnostics(
+                        DiagnosticsPass::ErlangService,
+                        file_id,
+                        revision,
+                        diagnostics,
+                    );
+                    sender.send(task).unwrap();
+                }
+            }
         });
     }
 
-    fn erlang_service_diagnostics_completed(&mut self, diags: Vec<(FileId, Vec<Diagnostic>)>) {
-        for (file_id, diagnostics) in diags.clone() {
-            self.diagnostics.set_erlang_service(file_id, diagnostics);
+    /// Re-reads the `.elp_lint.toml` file at `path`, for the projects
+    /// it belongs to.
+    fn reload_lint_file(&mut self, path: &AbsPath) {
+        let projects = Arc::clone(&self.projects);
+        let raw_db = self.analysis_host.raw_database_mut();
+        for (idx, project) in projects.iter().enumerate() {
+            if project.lint_file_path().as_deref() != Some(path) {
+                continue;
+            }
+            let project_id = ProjectId(idx as u32);
+            let mut project_data = (*raw_db.project_data(project_id)).clone();
+            project_data.lint_file_config = LintFileConfig::load(path).unwrap_or_else(|err| {
+                log::warn!("could not read {}: {err}", path.display());
+                LintFileConfig::default()
+            });
+            raw_db.set_project_data(project_id, Arc::new(project_data));
+            self.native_diagnostics_requested = true;
         }
     }
 
@@ -862,26 +988,88 @@ impl Server {
         let mut projects: Vec<Project> = self.projects.iter().cloned().collect();
         projects.push(project);
 
+        self.set_projects(projects)?;
+        self.project_loader.lock().load_completed();
+        Ok(())
+    }
+
+    /// Load the project at `idx` again, in the background, as the files
+    /// defining its apps changed.
+    fn reload_project(&mut self, idx: usize) {
+        let manifest = match self.projects[idx].manifest() {
+            Some(manifest) => manifest,
+            None => return,
+        };
+        self.project_pool.handle.spawn_with_sender(move |sender| {
+            log::info!("will reload project {:?}", manifest.root());
+            let project = Project::load(manifest);
+            sender.send(Task::ReloadProject(idx, project)).unwrap();
+        })
+    }
+
+    fn reload_project_completed(&mut self, idx: usize, project: Result<Project>) -> Result<()> {
+        let project = match project {
+            Ok(project) => project,
+            Err(err) => {
+                log::error!("ELP failed to reload the project: {:#}", err);
+                return Ok(());
+            }
+        };
+        if self.projects.get(idx) == Some(&project) {
+            return Ok(());
+        }
+        let mut projects: Vec<Project> = self.projects.iter().cloned().collect();
+        projects[idx] = project;
+        if let Err(err) = self.set_projects(projects) {
+            self.show_message(lsp_types::MessageType::ERROR, err.to_string())
+        }
+        Ok(())
+    }
+
+    /// Set the database inputs for `projects`, only those which changed
+    /// since the projects were last set, and load their files.
+    fn set_projects(&mut self, projects: Vec<Project>) -> Result<()> {
         let raw_db = self.analysis_host.raw_database_mut();
-        raw_db.clear_erlang_services();
 
         let project_apps = ProjectApps::new(&projects, IncludeOtp::Yes);
         let folders = ProjectFolders::new(&project_apps);
-        project_apps.app_structure().apply(raw_db);
-
-        for (project_id, _) in projects.iter().enumerate() {
-            let project_id = ProjectId(project_id as u32);
+        let app_structure = project_apps.app_structure();
+        let changed = app_structure
+            .clone()
+            .apply_changes(&self.app_structure, raw_db);
+        self.app_structure = app_structure;
+
+        // The Erlang services of the unchanged projects, OTP included,
+        // keep running, while those of unloaded projects stop
+        for project_id in changed {
             raw_db.ensure_erlang_service(project_id)?;
         }
-        if let Some(otp_project_id) = project_apps.otp_project_id {
-            raw_db.ensure_erlang_service(otp_project_id)?;
-        }
+        let loaded = projects.len() + usize::from(project_apps.otp_project_id.is_some());
+        raw_db.retain_erlang_services(|project_id| (project_id.0 as usize) < loaded);
 
         self.file_set_config = folders.file_set_config;
-
-        let register_options = lsp_types::DidChangeWatchedFilesRegistrationOptions {
-            watchers: folders.watch,
-        };
+        // Apps may have been added or removed, changing the source
+        // roots of the files already loaded
+        self.source_roots
+            .apply(self.file_set_config.partition(&self.vfs.read()), raw_db);
+
+        let mut watchers = folders.watch;
+        watchers.extend(projects.iter().filter_map(|project| {
+            Some(lsp_types::FileSystemWatcher {
+                glob_pattern: project.lint_file_path()?.display().to_string(),
+                kind: None,
+            })
+        }));
+        watchers.extend(projects.iter().flat_map(|project| {
+            project
+                .structure_file_globs()
+                .into_iter()
+                .map(|glob_pattern| lsp_types::FileSystemWatcher {
+                    glob_pattern,
+                    kind: None,
+                })
+        }));
+        let register_options = lsp_types::DidChangeWatchedFilesRegistrationOptions { watchers };
 
         let registrations = vec![lsp_types::Registration {
             id: "workspace/didChangeWatchedFiles".to_string(),
@@ -902,7 +1090,6 @@ impl Server {
         self.vfs_loader.handle.set_config(vfs_loader_config);
 
         self.projects = Arc::new(projects);
-        self.project_loader.lock().load_completed();
         Ok(())
     }
 
@@ -948,6 +1135,23 @@ impl Server {
         self.logger
             .reconfigure(LOGGER_NAME, self.config.log_filter());
         self.logger.reconfigure("default", self.config.log_filter());
+        self.analysis_host
+            .raw_database_mut()
+            .set_eqwalizer_client_config(self.config.eqwalizer_client());
+    }
+
+    fn status(&self) -> lsp_ext::ServerStatus {
+        let health = self.analysis_host.raw_database().eqwalizer_health();
+        lsp_ext::ServerStatus {
+            jobs: self.progress.status(),
+            eqwalizer: lsp_ext::EqwalizerHealth {
+                calls: health.calls,
+                timeouts: health.timeouts,
+                retries: health.retries,
+                failures: health.failures,
+                average_latency_ms: health.average_latency.as_secs_f64() * 1000.0,
+            },
+        }
     }
 
     fn transition(&mut self, status: Status) {
@@ -1034,6 +1238,61 @@ impl Server {
         })
     }
 
+    /// Load the project of a workspace folder, if it has one not loaded
+    /// yet. Unlike for an open file, a folder without project is not an
+    /// error.
+    fn fetch_workspace_folder(&mut self, folder: AbsPathBuf) {
+        let loader = self.project_loader.clone();
+        self.project_pool.handle.spawn_with_sender({
+            move |sender| {
+                let manifest = loader.lock().load_manifest_if_new(&folder);
+                match manifest {
+                    Ok(Some(manifest)) => {
+                        log::info!("did fetch project of folder {:?}", folder);
+                        sender
+                            .send(Task::FetchProject(Project::load(manifest)))
+                            .unwrap();
+                    }
+                    Ok(None) => {}
+                    Err(err) => log::info!("No project in folder {:?}: {:#}", folder, err),
+                }
+            }
+        })
+    }
+
+    /// Unload the projects of removed workspace folders, unless another
+    /// workspace folder still belongs to them. Project ids are indices
+    /// in `projects`, so those of the projects after them change.
+    fn unload_workspace_folders(&mut self, removed: &[AbsPathBuf]) -> Result<()> {
+        let related = |root: &AbsPath, folder: &AbsPathBuf| {
+            root.starts_with(folder) || folder.starts_with(root)
+        };
+        let (unloaded, projects): (Vec<Project>, Vec<Project>) =
+            self.projects.iter().cloned().partition(|project| {
+                let root = project.root();
+                let root = root.as_path();
+                removed.iter().any(|folder| related(root, folder))
+                    && !self
+                        .config
+                        .workspace_folders
+                        .iter()
+                        .any(|folder| related(root, folder))
+            });
+        if unloaded.is_empty() {
+            return Ok(());
+        }
+        // `ProjectApps` takes OTP from the first project, so the last one
+        // stays loaded
+        if projects.is_empty() {
+            return Ok(());
+        }
+        for project in &unloaded {
+            log::info!("Unloading project {:?}", project.root());
+            self.project_loader.lock().unload(&project.root());
+        }
+        self.set_projects(projects)
+    }
+
     fn fetch_project_completed(&mut self, project: Result<Project>) -> Result<()> {
         if let Err(err) = self.switch_workspaces(project) {
             self.show_message(lsp_types::MessageType::ERROR, err.to_string())
@@ -1049,7 +1308,7 @@ impl Server {
             .begin_spinner("ELP compiling dependencies for EqWAlizer".to_string());
 
         self.task_pool.handle.spawn_with_sender(move |sender| {
-            snapshot.set_up_projects();
+            snapshot.set_up_projects(&spinner);
 
             sender.send(Task::CompileDeps(spinner)).unwrap();
         });
@@ -1057,7 +1316,9 @@ impl Server {
 
     fn schedule_cache(&mut self) {
         let snapshot = self.snapshot();
-        let spinner = self.progress.begin_spinner("Parsing codebase".to_string());
+        let spinner = self
+            .progress
+            .begin_cancellable_spinner("Parsing codebase".to_string());
 
         self.cache_pool.handle.spawn_with_sender(move |sender| {
             let mut files = vec![];
@@ -1080,13 +1341,18 @@ impl Server {
     }
 
     fn update_cache(&mut self, spinner: Spinner, mut files: Vec<FileId>) {
-        if files.is_empty() {
+        if files.is_empty() || spinner.is_cancelled() {
             spinner.end();
             return;
         }
+        spinner.report(format!("{} files left", files.len()));
         let snapshot = self.snapshot();
         self.cache_pool.handle.spawn_with_sender(move |sender| {
             while !files.is_empty() {
+                if spinner.is_cancelled() {
+                    files.clear();
+                    break;
+                }
                 let file_id = files.remove(files.len() - 1);
                 if let Err(_) = snapshot.analysis.def_map(file_id) {
                     //got canceled
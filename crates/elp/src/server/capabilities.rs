@@ -7,6 +7,7 @@
  * of this source tree.
  */
 
+use elp_ide::ON_TYPE_FORMATTING_TRIGGERS;
 use lsp_types::CallHierarchyServerCapability;
 use lsp_types::ClientCapabilities;
 use lsp_types::CodeActionKind;
@@ -14,10 +15,18 @@ use lsp_types::CodeActionOptions;
 use lsp_types::CodeActionProviderCapability;
 use lsp_types::CodeLensOptions;
 use lsp_types::CompletionOptions;
+use lsp_types::DocumentLinkOptions;
+use lsp_types::DocumentOnTypeFormattingOptions;
+use lsp_types::FileOperationFilter;
+use lsp_types::FileOperationPattern;
+use lsp_types::FileOperationPatternKind;
+use lsp_types::FileOperationRegistrationOptions;
 use lsp_types::FoldingRangeProviderCapability;
 use lsp_types::HoverProviderCapability;
+use lsp_types::ImplementationProviderCapability;
 use lsp_types::InlayHintOptions;
 use lsp_types::InlayHintServerCapabilities;
+use lsp_types::LinkedEditingRangeServerCapabilities;
 use lsp_types::OneOf;
 use lsp_types::RenameOptions;
 use lsp_types::SaveOptions;
@@ -31,6 +40,9 @@ use lsp_types::TextDocumentSyncCapability;
 use lsp_types::TextDocumentSyncKind;
 use lsp_types::TextDocumentSyncOptions;
 use lsp_types::WorkDoneProgressOptions;
+use lsp_types::WorkspaceFileOperationsServerCapabilities;
+use lsp_types::WorkspaceFoldersServerCapabilities;
+use lsp_types::WorkspaceServerCapabilities;
 
 use crate::semantic_tokens;
 
@@ -67,7 +79,7 @@ pub fn compute(client: &ClientCapabilities) -> ServerCapabilities {
         }),
         definition_provider: Some(OneOf::Left(true)),
         type_definition_provider: None,
-        implementation_provider: None,
+        implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
         references_provider: Some(OneOf::Left(true)),
         document_highlight_provider: Some(OneOf::Left(true)),
         document_symbol_provider: Some(OneOf::Left(true)),
@@ -75,23 +87,54 @@ pub fn compute(client: &ClientCapabilities) -> ServerCapabilities {
         code_action_provider: Some(code_action_capabilities(client)),
         // TODO: This will be put behind a GK before shipping
         code_lens_provider: Some(CodeLensOptions {
-            resolve_provider: Some(false),
+            resolve_provider: Some(true),
+        }),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_range_formatting_provider: Some(OneOf::Left(true)),
+        document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+            first_trigger_character: ON_TYPE_FORMATTING_TRIGGERS[0].to_string(),
+            more_trigger_character: Some(
+                ON_TYPE_FORMATTING_TRIGGERS[1..]
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect(),
+            ),
         }),
-        document_formatting_provider: None,
-        document_range_formatting_provider: None,
-        document_on_type_formatting_provider: None,
         rename_provider: Some(OneOf::Right(RenameOptions {
-            prepare_provider: Some(false),
+            prepare_provider: Some(true),
             work_done_progress_options: WorkDoneProgressOptions {
                 work_done_progress: None,
             },
         })),
-        document_link_provider: None,
+        document_link_provider: Some(DocumentLinkOptions {
+            resolve_provider: Some(false),
+            work_done_progress_options: WorkDoneProgressOptions {
+                work_done_progress: None,
+            },
+        }),
         color_provider: None,
         folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
         declaration_provider: None,
         execute_command_provider: None,
-        workspace: None,
+        workspace: Some(WorkspaceServerCapabilities {
+            workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+                supported: Some(true),
+                change_notifications: Some(OneOf::Left(true)),
+            }),
+            file_operations: Some(WorkspaceFileOperationsServerCapabilities {
+                will_rename: Some(FileOperationRegistrationOptions {
+                    filters: vec![FileOperationFilter {
+                        scheme: Some("file".to_string()),
+                        pattern: FileOperationPattern {
+                            glob: "**/*.erl".to_string(),
+                            matches: Some(FileOperationPatternKind::File),
+                            options: None,
+                        },
+                    }],
+                }),
+                ..Default::default()
+            }),
+        }),
         call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
         semantic_tokens_provider: Some(
             SemanticTokensOptions {
@@ -113,7 +156,7 @@ pub fn compute(client: &ClientCapabilities) -> ServerCapabilities {
                 resolve_provider: Some(true),
             },
         ))),
-        linked_editing_range_provider: None,
+        linked_editing_range_provider: Some(LinkedEditingRangeServerCapabilities::Simple(true)),
         experimental: None,
     }
 }
@@ -136,6 +179,7 @@ fn code_action_capabilities(client_caps: &ClientCapabilities) -> CodeActionProvi
                     CodeActionKind::REFACTOR_EXTRACT,
                     CodeActionKind::REFACTOR_INLINE,
                     CodeActionKind::REFACTOR_REWRITE,
+                    CodeActionKind::SOURCE_ORGANIZE_IMPORTS,
                 ]),
                 resolve_provider: Some(true),
                 work_done_progress_options: Default::default(),
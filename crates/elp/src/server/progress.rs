@@ -7,8 +7,24 @@
  * of this source tree.
  */
 
+//! Progress reporting for long-lived background jobs.
+//!
+//! Every spinner or progress bar is a *job* with a unique id, which is
+//! also used as the LSP `WorkDoneProgress` token. Jobs can be nested
+//! (e.g. compiling the deps of each project is a child of the overall
+//! "compile deps" job), can optionally be cancelled by the client via
+//! `window/workDoneProgress/cancel`, and are listed by the custom
+//! `elp/jobs` request.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Instant;
+
 use crossbeam_channel::Receiver;
 use crossbeam_channel::Sender;
+use fxhash::FxHashMap;
 use lsp_types::NumberOrString;
 use lsp_types::ProgressParams;
 use lsp_types::ProgressParamsValue;
@@ -16,6 +32,9 @@ use lsp_types::WorkDoneProgress;
 use lsp_types::WorkDoneProgressBegin;
 use lsp_types::WorkDoneProgressEnd;
 use lsp_types::WorkDoneProgressReport;
+use parking_lot::Mutex;
+
+use crate::lsp_ext;
 
 #[derive(Debug)]
 pub enum ProgressTask {
@@ -23,13 +42,155 @@ pub enum ProgressTask {
     Notify(ProgressParams),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct JobId(usize);
+
+impl JobId {
+    fn token(self) -> NumberOrString {
+        NumberOrString::String(format!("ELP/{}", self.0))
+    }
+
+    fn from_token(token: &NumberOrString) -> Option<JobId> {
+        match token {
+            NumberOrString::String(s) => s.strip_prefix("ELP/")?.parse().ok().map(JobId),
+            NumberOrString::Number(_) => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Job {
+    title: String,
+    parent: Option<JobId>,
+    message: Option<String>,
+    percentage: Option<u32>,
+    cancelled: Option<Arc<AtomicBool>>,
+    started: Instant,
+}
+
+/// State shared between the manager and all the live jobs, so that
+/// jobs running on other threads can spawn children and report progress.
+#[derive(Debug)]
+struct Registry {
+    counter: AtomicUsize,
+    jobs: Mutex<FxHashMap<JobId, Job>>,
+    sender: Sender<ProgressTask>,
+}
+
+impl Registry {
+    fn begin(
+        self: &Arc<Self>,
+        title: String,
+        parent: Option<JobId>,
+        cancellable: bool,
+        message: Option<String>,
+        percentage: Option<u32>,
+    ) -> (JobId, Option<Arc<AtomicBool>>) {
+        let id = JobId(self.counter.fetch_add(1, Ordering::Relaxed));
+        // A child of a cancellable job is cancellable as well. It starts
+        // with a copy of the cancellation flag of its parent, and
+        // `cancel` marks all the descendants of a cancelled job.
+        let cancelled = {
+            let mut jobs = self.jobs.lock();
+            let inherited = parent
+                .and_then(|parent| jobs.get(&parent))
+                .and_then(|job| job.cancelled.as_ref())
+                .map(|flag| Arc::new(AtomicBool::new(flag.load(Ordering::Relaxed))));
+            let cancelled = inherited.or_else(|| cancellable.then(Default::default));
+            jobs.insert(
+                id,
+                Job {
+                    title: title.clone(),
+                    parent,
+                    message: message.clone(),
+                    percentage,
+                    cancelled: cancelled.clone(),
+                    started: Instant::now(),
+                },
+            );
+            cancelled
+        };
+        let msg = WorkDoneProgressBegin {
+            title,
+            cancellable: cancelled.as_ref().map(|_| true),
+            message,
+            percentage,
+        };
+        send_begin(&self.sender, id.token(), msg);
+        (id, cancelled)
+    }
+
+    fn report(&self, id: JobId, message: Option<String>, percentage: Option<u32>) {
+        if let Some(job) = self.jobs.lock().get_mut(&id) {
+            job.message = message.clone();
+            job.percentage = percentage;
+        }
+        let msg = WorkDoneProgress::Report(WorkDoneProgressReport {
+            cancellable: None,
+            message,
+            percentage,
+        });
+        send_progress(&self.sender, id.token(), msg);
+    }
+
+    fn end(&self, id: JobId) {
+        self.jobs.lock().remove(&id);
+        send_progress(
+            &self.sender,
+            id.token(),
+            WorkDoneProgress::End(WorkDoneProgressEnd { message: None }),
+        )
+    }
+
+    /// Marks the job and all its descendants as cancelled.
+    /// Returns false if the job is unknown or not cancellable.
+    fn cancel(&self, id: JobId) -> bool {
+        let jobs = self.jobs.lock();
+        let mut found = false;
+        let mut pending = vec![id];
+        while let Some(current) = pending.pop() {
+            if let Some(flag) = jobs.get(&current).and_then(|job| job.cancelled.as_ref()) {
+                flag.store(true, Ordering::Relaxed);
+                found = true;
+            }
+            pending.extend(
+                jobs.iter()
+                    .filter(|(_, job)| job.parent == Some(current))
+                    .map(|(child, _)| *child),
+            );
+        }
+        found
+    }
+
+    fn status(&self) -> Vec<lsp_ext::JobStatus> {
+        let jobs = self.jobs.lock();
+        let mut res: Vec<_> = jobs
+            .iter()
+            .map(|(id, job)| lsp_ext::JobStatus {
+                id: id.0,
+                parent: job.parent.map(|parent| parent.0),
+                title: job.title.clone(),
+                message: job.message.clone(),
+                percentage: job.percentage,
+                cancellable: job.cancelled.is_some(),
+                cancelled: job
+                    .cancelled
+                    .as_ref()
+                    .map_or(false, |flag| flag.load(Ordering::Relaxed)),
+                elapsed_ms: job.started.elapsed().as_millis() as u64,
+            })
+            .collect();
+        res.sort_by_key(|job| job.id);
+        res
+    }
+}
+
 // Follow structs don't derive Clone on purpose - this would violate the invariants
 // and result in duplicate messages
 
 #[derive(Debug)]
 pub struct ProgressManager {
-    counter: usize,
-    sender: Sender<ProgressTask>,
+    registry: Arc<Registry>,
     receiver: Receiver<ProgressTask>,
 }
 
@@ -37,8 +198,11 @@ impl Default for ProgressManager {
     fn default() -> Self {
         let (sender, receiver) = crossbeam_channel::unbounded();
         ProgressManager {
-            counter: 0,
-            sender,
+            registry: Arc::new(Registry {
+                counter: AtomicUsize::new(0),
+                jobs: Mutex::default(),
+                sender,
+            }),
             receiver,
         }
     }
@@ -50,37 +214,69 @@ impl ProgressManager {
     }
 
     pub fn begin_spinner(&mut self, title: String) -> Spinner {
-        Spinner::begin(self.sender.clone(), self.next_token(), title)
+        Spinner::begin(&self.registry, title, None, false)
+    }
+
+    /// Like `begin_spinner`, but the client is allowed to cancel the job.
+    /// The task doing the work is expected to poll `Spinner::is_cancelled`.
+    pub fn begin_cancellable_spinner(&mut self, title: String) -> Spinner {
+        Spinner::begin(&self.registry, title, None, true)
     }
 
     pub fn begin_bar(&mut self, title: String, total: usize) -> ProgressBar {
-        ProgressBar::begin(self.sender.clone(), self.next_token(), title, total)
+        ProgressBar::begin(&self.registry, title, total)
     }
 
-    fn next_token(&mut self) -> NumberOrString {
-        let token = NumberOrString::String(format!("ELP/{}", self.counter));
-        self.counter += 1;
-        token
+    /// Handles a `window/workDoneProgress/cancel` notification.
+    pub fn cancel(&mut self, token: &NumberOrString) -> bool {
+        match JobId::from_token(token) {
+            Some(id) => self.registry.cancel(id),
+            None => false,
+        }
+    }
+
+    /// The currently running jobs, ordered by creation.
+    pub fn status(&self) -> Vec<lsp_ext::JobStatus> {
+        self.registry.status()
     }
 }
 
 #[derive(Debug)]
 #[must_use]
 pub struct Spinner {
-    token: NumberOrString,
-    sender: Sender<ProgressTask>,
+    id: JobId,
+    cancelled: Option<Arc<AtomicBool>>,
+    registry: Arc<Registry>,
 }
 
 impl Spinner {
-    fn begin(sender: Sender<ProgressTask>, token: NumberOrString, title: String) -> Self {
-        let msg = WorkDoneProgressBegin {
-            title,
-            cancellable: None,
-            message: None,
-            percentage: None,
-        };
-        send_begin(&sender, token.clone(), msg);
-        Self { token, sender }
+    fn begin(
+        registry: &Arc<Registry>,
+        title: String,
+        parent: Option<JobId>,
+        cancellable: bool,
+    ) -> Self {
+        let (id, cancelled) = registry.begin(title, parent, cancellable, None, None);
+        Self {
+            id,
+            cancelled,
+            registry: registry.clone(),
+        }
+    }
+
+    /// Starts a nested job, which ends independently of this one.
+    pub fn begin_child(&self, title: String) -> Spinner {
+        Spinner::begin(&self.registry, title, Some(self.id), false)
+    }
+
+    pub fn report(&self, message: String) {
+        self.registry.report(self.id, Some(message), None);
+    }
+
+    /// True if the client requested this job (or one of its ancestors)
+    /// to be cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        is_cancelled(&self.cancelled)
     }
 
     pub fn end(self) {
@@ -90,47 +286,31 @@ impl Spinner {
 
 impl Drop for Spinner {
     fn drop(&mut self) {
-        send_progress(
-            &self.sender,
-            self.token.clone(),
-            WorkDoneProgress::End(WorkDoneProgressEnd { message: None }),
-        )
+        self.registry.end(self.id)
     }
 }
 
 #[derive(Debug)]
 #[must_use]
 pub struct ProgressBar {
-    token: NumberOrString,
-    sender: Sender<ProgressTask>,
+    id: JobId,
+    registry: Arc<Registry>,
 }
 
 impl ProgressBar {
-    fn begin(
-        sender: Sender<ProgressTask>,
-        token: NumberOrString,
-        title: String,
-        total: usize,
-    ) -> Self {
-        let msg = WorkDoneProgressBegin {
-            title,
-            cancellable: None,
-            message: Some(format!("0/{}", total)),
-            percentage: Some(0),
-        };
-        send_begin(&sender, token.clone(), msg);
-        Self { token, sender }
+    fn begin(registry: &Arc<Registry>, title: String, total: usize) -> Self {
+        let (id, _) = registry.begin(title, None, false, Some(format!("0/{}", total)), Some(0));
+        Self {
+            id,
+            registry: registry.clone(),
+        }
     }
 
     pub fn report(&self, done: usize, total: usize) {
         let message = format!("{}/{}", done, total);
         let percent = done as f64 / total.max(1) as f64;
-        let msg = WorkDoneProgress::Report(WorkDoneProgressReport {
-            cancellable: None,
-            message: Some(message),
-            percentage: Some((percent * 100.0) as u32),
-        });
-        send_progress(&self.sender, self.token.clone(), msg);
+        self.registry
+            .report(self.id, Some(message), Some((percent * 100.0) as u32));
     }
 
     pub fn end(self) {
@@ -140,14 +320,15 @@ impl ProgressBar {
 
 impl Drop for ProgressBar {
     fn drop(&mut self) {
-        send_progress(
-            &self.sender,
-            self.token.clone(),
-            WorkDoneProgress::End(WorkDoneProgressEnd { message: None }),
-        )
+        self.registry.end(self.id)
     }
 }
 
+fn is_cancelled(flag: &Option<Arc<AtomicBool>>) -> bool {
+    flag.as_ref()
+        .map_or(false, |flag| flag.load(Ordering::Relaxed))
+}
+
 fn send_begin(sender: &Sender<ProgressTask>, token: NumberOrString, msg: WorkDoneProgressBegin) {
     let params = ProgressParams {
         token,
@@ -163,3 +344,45 @@ fn send_progress(sender: &Sender<ProgressTask>, token: NumberOrString, msg: Work
     };
     sender.send(ProgressTask::Notify(params)).unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_propagates_to_children() {
+        let mut manager = ProgressManager::default();
+        let parent = manager.begin_cancellable_spinner("parent".to_string());
+        let child = parent.begin_child("child".to_string());
+        let other = manager.begin_spinner("other".to_string());
+
+        assert!(!manager.cancel(&other.id.token()));
+        assert!(manager.cancel(&parent.id.token()));
+        assert!(parent.is_cancelled());
+        assert!(child.is_cancelled());
+        assert!(!other.is_cancelled());
+    }
+
+    #[test]
+    fn status_lists_running_jobs() {
+        let mut manager = ProgressManager::default();
+        let bar = manager.begin_bar("bar".to_string(), 4);
+        bar.report(1, 4);
+        let parent = manager.begin_spinner("parent".to_string());
+        let child = parent.begin_child("child".to_string());
+        child.report("step".to_string());
+
+        let status = manager.status();
+        assert_eq!(status.len(), 3);
+        assert_eq!(status[0].message.as_deref(), Some("1/4"));
+        assert_eq!(status[0].percentage, Some(25));
+        assert_eq!(status[2].parent, Some(status[1].id));
+        assert_eq!(status[2].message.as_deref(), Some("step"));
+
+        drop(child);
+        bar.end();
+        assert_eq!(manager.status().len(), 1);
+        parent.end();
+        assert!(manager.status().is_empty());
+    }
+}
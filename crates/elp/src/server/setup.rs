@@ -27,6 +27,7 @@ use threadpool::ThreadPool;
 
 use super::logger::LspLogger;
 use crate::config::Config;
+use crate::convert;
 use crate::from_json;
 use crate::server::capabilities;
 use crate::server::Handle;
@@ -99,6 +100,10 @@ impl ServerSetup {
         // anything.  If they match config, that is because we
         // choose this to be so in the client.
         let mut config = Config::new(root_path, params.capabilities);
+        let workspace_folders = workspace_folders(&params);
+        if !workspace_folders.is_empty() {
+            config.workspace_folders = workspace_folders;
+        }
         if let Some(options) = params.initialization_options {
             config.update(options);
         }
@@ -164,6 +169,15 @@ fn set_up_ai_completion(config: &Config) -> AiCompletion {
     }
 }
 
+fn workspace_folders(params: &InitializeParams) -> Vec<AbsPathBuf> {
+    params
+        .workspace_folders
+        .iter()
+        .flatten()
+        .filter_map(|folder| convert::abs_path(&folder.uri).ok())
+        .collect()
+}
+
 fn root_path(params: &InitializeParams) -> Result<AbsPathBuf> {
     match params
         .root_uri
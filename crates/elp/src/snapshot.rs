@@ -15,6 +15,7 @@ use anyhow::Result;
 use elp_ai::AiCompletion;
 use elp_ai::CompletionReceiver;
 use elp_ide::elp_ide_db::elp_base_db::AbsPathBuf;
+use elp_ide::elp_ide_db::elp_base_db::AnchoredPathBuf;
 use elp_ide::elp_ide_db::elp_base_db::FileId;
 use elp_ide::elp_ide_db::elp_base_db::FilePosition;
 use elp_ide::elp_ide_db::elp_base_db::ProjectId;
@@ -22,11 +23,13 @@ use elp_ide::elp_ide_db::elp_base_db::Vfs;
 use elp_ide::elp_ide_db::elp_base_db::VfsPath;
 use elp_ide::elp_ide_db::EqwalizerDiagnostics;
 use elp_ide::Analysis;
+use elp_ide::HlRange;
+use elp_ide::TextRange;
 use elp_log::timeit_with_telemetry;
 use elp_project_model::Project;
 use fxhash::FxHashMap;
-use itertools::Itertools;
 use lsp_types::Diagnostic;
+use lsp_types::Location;
 use lsp_types::SemanticTokens;
 use lsp_types::Url;
 use parking_lot::Mutex;
@@ -36,9 +39,11 @@ use serde::Serialize;
 
 use crate::config::Config;
 use crate::convert;
+use crate::diagnostic_docs;
 use crate::line_endings::LineEndings;
 use crate::server::file_id_to_path;
 use crate::server::file_id_to_url;
+use crate::server::progress::Spinner;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TelemetryData {
@@ -46,6 +51,7 @@ pub enum TelemetryData {
     EqwalizerDiagnostics { file_url: Url },
     ParseServerDiagnostics { file_url: Url },
     EdocDiagnostics { file_url: Url },
+    PluginDiagnostics { file_url: Url },
     Initialize,
 }
 
@@ -64,6 +70,9 @@ impl fmt::Display for TelemetryData {
             TelemetryData::EdocDiagnostics { file_url } => {
                 write!(f, "EDoc Diagnostics file_url: {}", file_url)
             }
+            TelemetryData::PluginDiagnostics { file_url } => {
+                write!(f, "Plugin Diagnostics file_url: {}", file_url)
+            }
             TelemetryData::Initialize => {
                 write!(f, "Initialize")
             }
@@ -73,13 +82,21 @@ impl fmt::Display for TelemetryData {
 
 pub type SharedMap<Key, Value> = Arc<RwLock<FxHashMap<Key, Value>>>;
 
+/// The semantic tokens last sent for a document, together with the
+/// highlights they were computed from.
+#[derive(Debug, Clone, Default)]
+pub struct CachedSemanticTokens {
+    pub(crate) tokens: SemanticTokens,
+    pub(crate) highlights: Vec<HlRange>,
+}
+
 /// An immutable snapshot of the world's state at a point in time.
 pub struct Snapshot {
     pub(crate) config: Arc<Config>,
     // Note: Analysis is a salsa::Snapshot.  According to the docs,
     // any attempt to `set` an input will block.
     pub(crate) analysis: Analysis,
-    pub(crate) semantic_tokens_cache: Arc<Mutex<FxHashMap<Url, SemanticTokens>>>,
+    pub(crate) semantic_tokens_cache: SharedMap<Url, CachedSemanticTokens>,
     vfs: Arc<RwLock<Vfs>>,
     open_document_versions: SharedMap<VfsPath, i32>,
     line_ending_map: SharedMap<FileId, LineEndings>,
@@ -94,13 +111,14 @@ impl Snapshot {
         vfs: Arc<RwLock<Vfs>>,
         open_document_versions: Arc<RwLock<FxHashMap<VfsPath, i32>>>,
         line_ending_map: Arc<RwLock<FxHashMap<FileId, LineEndings>>>,
+        semantic_tokens_cache: SharedMap<Url, CachedSemanticTokens>,
         projects: Arc<Vec<Project>>,
         ai_completion: Arc<Mutex<AiCompletion>>,
     ) -> Self {
         Snapshot {
             config,
             analysis,
-            semantic_tokens_cache: Arc::new(Default::default()),
+            semantic_tokens_cache,
             vfs,
             open_document_versions,
             line_ending_map,
@@ -126,6 +144,19 @@ impl Snapshot {
         file_id_to_url(&self.vfs.read(), id)
     }
 
+    /// The url of a path relative to the directory of its anchor file.
+    pub(crate) fn anchored_path(&self, path: &AnchoredPathBuf) -> Result<Url> {
+        let mut base = self.vfs.read().file_path(path.anchor);
+        base.pop();
+        let path = base
+            .join(&path.path)
+            .context(format!("invalid path: {}", path.path))?;
+        let path = path
+            .as_path()
+            .context(format!("not a local path: {}", path))?;
+        Ok(convert::url_from_abs_path(path))
+    }
+
     pub(crate) fn url_file_version(&self, url: &Url) -> Option<i32> {
         let path = convert::vfs_path(url).ok()?;
         Some(*self.open_document_versions.read().get(&path)?)
@@ -150,12 +181,29 @@ impl Snapshot {
         let line_index = self.analysis.line_index(file_id).ok()?;
         let url = file_id_to_url(&self.vfs.read(), file_id);
 
+        let related_location = |file_id: FileId, range: TextRange| {
+            let line_index = self.analysis.line_index(file_id).ok()?;
+            Some(Location {
+                uri: self.file_id_to_url(file_id),
+                range: convert::range(&line_index, range),
+            })
+        };
+
         Some(
             self.analysis
                 .diagnostics(&self.config.diagnostics(), file_id, false)
                 .ok()?
                 .into_iter()
-                .map(|d| convert::ide_to_lsp_diagnostic(&line_index, &url, &d))
+                .map(|d| {
+                    let mut diagnostic = convert::ide_to_lsp_diagnostic_with_related(
+                        &line_index,
+                        &url,
+                        &d,
+                        &related_location,
+                    );
+                    diagnostic.code_description = diagnostic_docs::code_description(&d.code);
+                    diagnostic
+                })
                 .collect(),
         )
     }
@@ -181,7 +229,7 @@ impl Snapshot {
             .eqwalizer_diagnostics(project_id, vec![file_id])
             .ok()?;
         match &*diags {
-            EqwalizerDiagnostics::Diagnostics(diags) => Some(
+            EqwalizerDiagnostics::Diagnostics { errors: diags, .. } => Some(
                 diags
                     .iter()
                     .flat_map(|(_, diags)| {
@@ -222,6 +270,22 @@ impl Snapshot {
         )
     }
 
+    pub fn plugin_diagnostics(&self, file_id: FileId) -> Option<Vec<Diagnostic>> {
+        let file_url = self.file_id_to_url(file_id);
+        let _timer = timeit_with_telemetry!(TelemetryData::PluginDiagnostics { file_url });
+        let url = file_id_to_url(&self.vfs.read(), file_id);
+        let line_index = self.analysis.line_index(file_id).ok()?;
+
+        let diags = self.analysis.plugin_diagnostics(file_id).ok()?;
+
+        Some(
+            diags
+                .iter()
+                .map(|d| convert::ide_to_lsp_diagnostic(&line_index, &url, d))
+                .collect(),
+        )
+    }
+
     pub fn erlang_service_diagnostics(
         &self,
         file_id: FileId,
@@ -248,16 +312,16 @@ impl Snapshot {
         )
     }
 
+    /// The project with the given id, or `None` if no project is loaded
+    /// with it. Ids are indices in `projects`, and there is no fallback
+    /// to the first project for other ids.
     pub fn get_project(&self, project_id: ProjectId) -> Option<Project> {
-        self.projects
-            .iter()
-            .enumerate()
-            .find_or_first(|(id, _project)| ProjectId(*id as u32) == project_id)
-            .map(|(_id, project)| project.clone())
+        self.projects.get(project_id.0 as usize).cloned()
     }
 
-    pub fn set_up_projects(&self) {
+    pub fn set_up_projects(&self, spinner: &Spinner) {
         for project in self.projects.as_ref() {
+            let _child = spinner.begin_child(format!("Compiling deps of {}", project.name()));
             if let Err(err) = set_up_project(project) {
                 log::error!(
                     "Failed to set up project {} for parsing: {}",
@@ -270,5 +334,9 @@ impl Snapshot {
 }
 
 fn set_up_project(project: &Project) -> Result<()> {
-    project.compile_deps()
+    let res = project.compile_deps();
+    // The stubs are generated from the BEAM files of the dependencies,
+    // and picked up by the file watchers of their source directories.
+    project.generate_beam_stubs();
+    res
 }
@@ -23,12 +23,14 @@ use elp_ide::elp_ide_db::elp_base_db::FileId;
 use elp_ide::elp_ide_db::elp_base_db::FilePosition;
 use elp_ide::elp_ide_db::elp_base_db::FileRange;
 use elp_ide::elp_ide_db::rename::RenameError;
+use elp_ide::elp_ide_db::source_change::FileSystemEdit;
 use elp_ide::elp_ide_db::source_change::SourceChange;
 use elp_ide::elp_ide_db::LineIndex;
 use elp_ide::elp_ide_db::ReferenceCategory;
 use elp_ide::elp_ide_db::SymbolKind;
-use elp_ide::AnnotationKind;
 use elp_ide::Cancellable;
+use elp_ide::CodeLensKind;
+use elp_ide::DocumentLink;
 use elp_ide::Fold;
 use elp_ide::FoldKind;
 use elp_ide::Highlight;
@@ -40,7 +42,9 @@ use elp_ide::InlayHintLabelPart;
 use elp_ide::InlayKind;
 use elp_ide::NavigationTarget;
 use elp_ide::Runnable;
+use elp_ide::RunnableStatus;
 use elp_ide::SignatureHelp;
+use elp_ide::TestStatus;
 use elp_ide::TextRange;
 use elp_ide::TextSize;
 use elp_project_model::ProjectBuildData;
@@ -82,6 +86,7 @@ pub(crate) fn symbol_kind(symbol_kind: SymbolKind) -> lsp_types::SymbolKind {
         SymbolKind::RecordField => lsp_types::SymbolKind::STRUCT,
         SymbolKind::Variable => lsp_types::SymbolKind::VARIABLE,
         SymbolKind::Callback => lsp_types::SymbolKind::FUNCTION,
+        SymbolKind::TableKey => lsp_types::SymbolKind::KEY,
     }
 }
 
@@ -145,7 +150,20 @@ pub(crate) fn workspace_edit(
             edits: edit.edits.into_iter().map(From::from).collect(),
         });
     }
-    let document_changes = lsp_types::DocumentChanges::Edits(edits);
+    let document_changes = if source_change.file_system_edits.is_empty() {
+        lsp_types::DocumentChanges::Edits(edits)
+    } else {
+        // The text edits refer to the files by their original name, so
+        // they have to be applied before any file is moved or deleted.
+        let mut operations: Vec<_> = edits
+            .into_iter()
+            .map(lsp_types::DocumentChangeOperation::Edit)
+            .collect();
+        for edit in source_change.file_system_edits {
+            operations.extend(document_change_operations(snap, edit)?);
+        }
+        lsp_types::DocumentChanges::Operations(operations)
+    };
     let workspace_edit = lsp_types::WorkspaceEdit {
         changes: None,
         document_changes: Some(document_changes),
@@ -154,6 +172,58 @@ pub(crate) fn workspace_edit(
     Ok(workspace_edit)
 }
 
+fn document_change_operations(
+    snap: &Snapshot,
+    edit: FileSystemEdit,
+) -> Result<Vec<lsp_types::DocumentChangeOperation>> {
+    let res = match edit {
+        FileSystemEdit::CreateFile {
+            dst,
+            initial_contents,
+        } => {
+            let uri = snap.anchored_path(&dst)?;
+            let create = lsp_types::ResourceOp::Create(lsp_types::CreateFile {
+                uri: uri.clone(),
+                options: None,
+                annotation_id: None,
+            });
+            let contents = lsp_types::TextDocumentEdit {
+                text_document: lsp_types::OptionalVersionedTextDocumentIdentifier {
+                    uri,
+                    version: None,
+                },
+                edits: vec![lsp_types::OneOf::Left(lsp_types::TextEdit {
+                    range: lsp_types::Range::default(),
+                    new_text: initial_contents,
+                })],
+            };
+            vec![
+                lsp_types::DocumentChangeOperation::Op(create),
+                lsp_types::DocumentChangeOperation::Edit(contents),
+            ]
+        }
+        FileSystemEdit::DeleteFile { file } => {
+            let delete = lsp_types::ResourceOp::Delete(lsp_types::DeleteFile {
+                uri: snap.file_id_to_url(file),
+                options: None,
+            });
+            vec![lsp_types::DocumentChangeOperation::Op(delete)]
+        }
+        FileSystemEdit::MoveFile { src, dst } => {
+            let old_uri = snap.file_id_to_url(src);
+            let new_uri = snap.anchored_path(&dst)?;
+            let rename = lsp_types::ResourceOp::Rename(lsp_types::RenameFile {
+                old_uri,
+                new_uri,
+                options: None,
+                annotation_id: None,
+            });
+            vec![lsp_types::DocumentChangeOperation::Op(rename)]
+        }
+    };
+    Ok(res)
+}
+
 pub(crate) fn code_action_kind(kind: AssistKind) -> lsp_types::CodeActionKind {
     match kind {
         AssistKind::None | AssistKind::Generate => lsp_types::CodeActionKind::EMPTY,
@@ -184,6 +254,10 @@ pub(crate) fn code_action(
         command: None,
         disabled: None,
     };
+    if let Some(reason) = assist.disabled {
+        res.disabled = Some(lsp_types::CodeActionDisabled { reason });
+        return Ok(lsp_types::CodeActionOrCommand::CodeAction(res));
+    }
     match (assist.source_change, resolve_data) {
         (Some(it), _) => res.edit = Some(workspace_edit(snap, it)?),
         (None, Some((index, code_action_params, user_input))) => {
@@ -278,10 +352,20 @@ pub(crate) fn goto_definition_response(
 pub(crate) fn hover_response(
     snap: &Snapshot,
     maybe_doc: Option<(Doc, FileRange)>,
+    maybe_type: Option<(String, FileRange)>,
 ) -> Result<Option<lsp_types::Hover>> {
-    let (markup, id_range) = match maybe_doc {
-        Some((doc, src_range)) => (doc.markdown_text().to_string(), Some(src_range)),
-        None => return Result::Ok(None),
+    // The eqWAlizer type comes first, followed by the docs
+    let type_markup = maybe_type
+        .as_ref()
+        .map(|(ty, _)| format!("```erlang\n{}\n```", ty));
+    let (markup, id_range) = match (maybe_doc, type_markup) {
+        (Some((doc, src_range)), Some(type_markup)) => (
+            format!("{}\n\n---\n\n{}", type_markup, doc.markdown_text()),
+            Some(src_range),
+        ),
+        (Some((doc, src_range)), None) => (doc.markdown_text().to_string(), Some(src_range)),
+        (None, Some(type_markup)) => (type_markup, maybe_type.map(|(_, range)| range)),
+        (None, None) => return Result::Ok(None),
     };
     let markup_kind = MarkupKind::Markdown;
     let hover_contents = HoverContents::Markup(MarkupContent {
@@ -342,6 +426,39 @@ fn completion_item(snap: &Snapshot, c: Completion) -> lsp_types::CompletionItem
     if c.deprecated {
         tags.push(CompletionItemTag::DEPRECATED);
     };
+    // Postfix snippets also replace the expression before them
+    let (text_edit, filter_text) = match &c.contents {
+        Contents::Postfix {
+            range: file_range,
+            filter,
+            snippet,
+        } => {
+            let text_edit = snap
+                .analysis
+                .line_index(file_range.file_id)
+                .ok()
+                .map(|line_index| {
+                    lsp_types::CompletionTextEdit::Edit(lsp_types::TextEdit {
+                        range: range(&line_index, file_range.range),
+                        new_text: snippet.clone(),
+                    })
+                });
+            (text_edit, Some(filter.clone()))
+        }
+        _ => (None, None),
+    };
+    let additional_text_edits: Vec<lsp_types::TextEdit> = c
+        .additional_edits
+        .iter()
+        .filter_map(|(file_position, text)| {
+            let line_index = snap.analysis.line_index(file_position.file_id).ok()?;
+            let at = position(&line_index, file_position.offset);
+            Some(lsp_types::TextEdit {
+                range: lsp_types::Range::new(at, at),
+                new_text: text.clone(),
+            })
+        })
+        .collect();
     lsp_types::CompletionItem {
         label: c.label,
         kind: Some(match c.kind {
@@ -357,8 +474,12 @@ fn completion_item(snap: &Snapshot, c: Completion) -> lsp_types::CompletionItem
             Type => K::INTERFACE,
             Variable => K::VARIABLE,
             AiAssist => K::EVENT,
+            File => K::FILE,
+            Folder => K::FOLDER,
+            MapKey => K::PROPERTY,
+            Snippet => K::SNIPPET,
         }),
-        detail: None,
+        detail: c.detail,
         documentation: None,
         deprecated: Some(c.deprecated),
         preselect: None,
@@ -366,11 +487,17 @@ fn completion_item(snap: &Snapshot, c: Completion) -> lsp_types::CompletionItem
             Contents::SameAsLabel | Contents::String(_) => {
                 Some(lsp_types::InsertTextFormat::PLAIN_TEXT)
             }
-            Contents::Snippet(_) => Some(lsp_types::InsertTextFormat::SNIPPET),
+            Contents::Snippet(_) | Contents::Postfix { .. } => {
+                Some(lsp_types::InsertTextFormat::SNIPPET)
+            }
         },
         insert_text_mode: None,
-        text_edit: None,
-        additional_text_edits: None,
+        text_edit,
+        additional_text_edits: if additional_text_edits.is_empty() {
+            None
+        } else {
+            Some(additional_text_edits)
+        },
         commit_characters: None,
         data: match completion_item_data(snap, c.position) {
             Some(data) => match serde_json::value::to_value(data) {
@@ -380,9 +507,9 @@ fn completion_item(snap: &Snapshot, c: Completion) -> lsp_types::CompletionItem
             None => None,
         },
         sort_text: c.sort_text,
-        filter_text: None,
+        filter_text,
         insert_text: match c.contents {
-            Contents::Snippet(snippet) => Some(snippet),
+            Contents::Snippet(snippet) | Contents::Postfix { snippet, .. } => Some(snippet),
             Contents::String(string) => Some(string),
             Contents::SameAsLabel => None,
         },
@@ -405,9 +532,33 @@ fn completion_item_data(snap: &Snapshot, pos: Option<FilePosition>) -> Option<Co
     }
 }
 
+pub(crate) fn document_link(
+    line_index: &LineIndex,
+    link: DocumentLink,
+) -> Option<lsp_types::DocumentLink> {
+    let target = match lsp_types::Url::parse(&link.target) {
+        Ok(target) => target,
+        Err(err) => {
+            log::warn!("Invalid document link target {:?}: {}", link.target, err);
+            return None;
+        }
+    };
+    Some(lsp_types::DocumentLink {
+        range: range(line_index, link.range),
+        target: Some(target),
+        tooltip: None,
+        data: None,
+    })
+}
+
 pub(crate) fn folding_range(line_index: &LineIndex, fold: Fold) -> lsp_types::FoldingRange {
     let kind = match fold.kind {
-        FoldKind::Function | FoldKind::Record => Some(lsp_types::FoldingRangeKind::Region),
+        FoldKind::Function
+        | FoldKind::Record
+        | FoldKind::Comprehension
+        | FoldKind::Expr
+        | FoldKind::Export => Some(lsp_types::FoldingRangeKind::Region),
+        FoldKind::CommentBlock => Some(lsp_types::FoldingRangeKind::Comment),
     };
 
     let range = range(line_index, fold.range);
@@ -428,7 +579,7 @@ pub(crate) fn call_hierarchy_item(
     target: NavigationTarget,
 ) -> Result<lsp_types::CallHierarchyItem> {
     let name = target.name.to_string();
-    let kind = lsp_types::SymbolKind::FUNCTION;
+    let kind = symbol_kind(target.kind);
     let (uri, range, selection_range) = location_info(snap, target)?;
     Ok(lsp_types::CallHierarchyItem {
         name,
@@ -561,6 +712,7 @@ fn semantic_token_type_and_modifiers(
             SymbolKind::Define => semantic_tokens::MACRO,
             SymbolKind::Variable => semantic_tokens::VARIABLE,
             SymbolKind::Callback => semantic_tokens::FUNCTION,
+            SymbolKind::TableKey => semantic_tokens::GENERIC,
         },
         HlTag::None => semantic_tokens::GENERIC,
     };
@@ -570,6 +722,9 @@ fn semantic_token_type_and_modifiers(
             HlMod::Bound => semantic_tokens::BOUND,
             HlMod::ExportedFunction => semantic_tokens::EXPORTED_FUNCTION,
             HlMod::DeprecatedFunction => semantic_tokens::DEPRECATED_FUNCTION,
+            HlMod::Deprecated => semantic_tokens::DEPRECATED,
+            HlMod::TestCode => semantic_tokens::TEST_CODE,
+            HlMod::Generated => semantic_tokens::GENERATED,
         };
         mods |= modifier;
     }
@@ -586,6 +741,21 @@ pub(crate) fn document_highlight_kind(
     }
 }
 
+pub(crate) fn test_status(line_index: &LineIndex, it: RunnableStatus) -> lsp_ext::TestStatus {
+    let (status, message) = match it.status {
+        Some(TestStatus::Passed) => (lsp_ext::TestStatusKind::Passed, None),
+        Some(TestStatus::Failed { reason }) => (lsp_ext::TestStatusKind::Failed, reason),
+        Some(TestStatus::Skipped { reason }) => (lsp_ext::TestStatusKind::Skipped, reason),
+        None => (lsp_ext::TestStatusKind::NotRun, None),
+    };
+    lsp_ext::TestStatus {
+        id: it.runnable.id(),
+        range: range(line_index, it.runnable.nav.range()),
+        status,
+        message,
+    }
+}
+
 pub(crate) fn runnable(
     snap: &Snapshot,
     runnable: Runnable,
@@ -630,46 +800,55 @@ pub(crate) fn runnable(
     }
 }
 
+/// Converts a code lens without its command, `data` allows finding the
+/// lens again when the client asks for it to be resolved.
 pub(crate) fn code_lens(
-    acc: &mut Vec<lsp_types::CodeLens>,
+    line_index: &LineIndex,
+    lens: &elp_ide::CodeLens,
+    data: lsp_ext::CodeLensData,
+) -> Result<lsp_types::CodeLens> {
+    Ok(lsp_types::CodeLens {
+        range: range(line_index, lens.range),
+        command: None,
+        data: Some(serde_json::value::to_value(data)?),
+    })
+}
+
+pub(crate) fn code_lens_command(
     snap: &Snapshot,
-    annotation: elp_ide::Annotation,
+    lens: elp_ide::CodeLens,
     project_build_data: Option<ProjectBuildData>,
-) -> Result<()> {
-    match annotation.kind {
-        AnnotationKind::Runnable(run) => {
-            let line_index = snap.analysis.line_index(run.nav.file_id)?;
-            let annotation_range = range(&line_index, annotation.range);
-            let run_title = &run.run_title();
-            let debug_title = &run.debug_title();
+) -> Result<Option<lsp_types::Command>> {
+    let res = match lens.kind {
+        CodeLensKind::Run(run) => {
+            let run_title = run.run_title();
             match runnable(snap, run, project_build_data) {
-                Ok(r) => {
-                    let lens_config = snap.config.lens();
-                    if lens_config.run {
-                        let run_command = command::run_single(&r, &run_title);
-                        acc.push(lsp_types::CodeLens {
-                            range: annotation_range,
-                            command: Some(run_command),
-                            data: None,
-                        });
-                    }
-                    if lens_config.debug {
-                        let debug_command = command::debug_single(&r, &debug_title);
-                        acc.push(lsp_types::CodeLens {
-                            range: annotation_range,
-                            command: Some(debug_command),
-                            data: None,
-                        })
-                    }
+                Ok(r) => Some(command::run_single(&r, &run_title)),
+                Err(e) => {
+                    log::warn!("Error while extracting runnables {e}");
+                    None
                 }
+            }
+        }
+        CodeLensKind::Debug(run) => {
+            let debug_title = run.debug_title();
+            match runnable(snap, run, project_build_data) {
+                Ok(r) => Some(command::debug_single(&r, &debug_title)),
                 Err(e) => {
                     log::warn!("Error while extracting runnables {e}");
-                    ()
+                    None
                 }
-            };
+            }
         }
-    }
-    Ok(())
+        CodeLensKind::GroupLink { name, target } => {
+            let location = location(snap, target)?;
+            Some(command::goto_location(
+                &location,
+                &format!("Group: {}", name.as_str()),
+            ))
+        }
+    };
+    Ok(res)
 }
 
 pub(crate) mod command {
@@ -693,6 +872,14 @@ pub(crate) mod command {
         }
     }
 
+    pub(crate) fn goto_location(location: &lsp_types::Location, title: &str) -> lsp_types::Command {
+        lsp_types::Command {
+            title: title.to_string(),
+            command: "elp.gotoLocation".into(),
+            arguments: Some(vec![to_value(location).unwrap()]),
+        }
+    }
+
     pub(crate) fn trigger_parameter_hints() -> lsp_types::Command {
         lsp_types::Command {
             title: "triggerParameterHints".into(),
@@ -705,10 +892,12 @@ pub(crate) mod command {
 pub(crate) fn inlay_hint(
     snap: &Snapshot,
     line_index: &LineIndex,
+    line_endings: LineEndings,
     mut inlay_hint: elp_ide::InlayHint,
 ) -> Cancellable<lsp_types::InlayHint> {
     match inlay_hint.kind {
         InlayKind::Parameter => inlay_hint.label.append_str(":"),
+        InlayKind::RecordFields | InlayKind::ReturnType => {}
     }
 
     let (label, tooltip) = inlay_hint_label(snap, inlay_hint.label)?;
@@ -716,20 +905,30 @@ pub(crate) fn inlay_hint(
     Ok(lsp_types::InlayHint {
         position: match inlay_hint.kind {
             // before annotated thing
-            InlayKind::Parameter => position(line_index, inlay_hint.range.start()),
+            InlayKind::Parameter | InlayKind::RecordFields => {
+                position(line_index, inlay_hint.range.start())
+            }
             // after annotated thing
-            // _ => position(line_index, inlay_hint.range.end()),
+            InlayKind::ReturnType => position(line_index, inlay_hint.range.end()),
         },
         padding_left: Some(match inlay_hint.kind {
             InlayKind::Parameter => false,
+            InlayKind::RecordFields | InlayKind::ReturnType => true,
         }),
         padding_right: Some(match inlay_hint.kind {
-            InlayKind::Parameter => true,
+            InlayKind::Parameter | InlayKind::ReturnType => true,
+            InlayKind::RecordFields => false,
         }),
         kind: match inlay_hint.kind {
             InlayKind::Parameter => Some(lsp_types::InlayHintKind::PARAMETER),
+            InlayKind::ReturnType => Some(lsp_types::InlayHintKind::TYPE),
+            InlayKind::RecordFields => None,
         },
-        text_edits: None,
+        text_edits: inlay_hint.text_edit.map(|edit| {
+            edit.into_iter()
+                .map(|indel| text_edit(line_index, line_endings, indel))
+                .collect()
+        }),
         data: None,
         tooltip,
         label,
@@ -835,3 +1034,26 @@ pub(crate) fn document_symbol(
 }
 
 // ---------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn semantic_token_delta_of_moved_tokens() {
+        // Replacing a space by a newline keeps the highlighted ranges,
+        // but moves the tokens after it to the next line
+        let before = "f() -> Var.\n";
+        let after = "f() ->\nVar.\n";
+        let highlights = vec![HlRange {
+            range: TextRange::new(7.into(), 10.into()),
+            highlight: HlTag::Symbol(SymbolKind::Variable).into(),
+            binding_hash: None,
+        }];
+        let previous = semantic_tokens(before, &LineIndex::new(before), highlights.clone());
+        let current = semantic_tokens(after, &LineIndex::new(after), highlights);
+        let delta = semantic_token_delta(&previous, &current);
+        assert_eq!(delta.edits.len(), 1);
+        assert_eq!(delta.edits[0].data, Some(current.data));
+    }
+}
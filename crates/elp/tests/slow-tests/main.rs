@@ -26,11 +26,13 @@ use std::path::Path;
 
 use elp_ide::elp_ide_db::elp_base_db::AbsPathBuf;
 use expect_test::expect;
+use expect_test::Expect;
 use lsp_types::Position;
 use lsp_types::Range;
 
 use crate::support::code_action_project;
 use crate::support::diagnostic_project;
+use crate::support::Project;
 
 const PROFILE: &str = "";
 
@@ -127,7 +129,7 @@ fn test_run_mock_lsp() {
                     {
                       "edits": [
                         {
-                          "newText": "-spec bar(type1()) -> return_type().\n",
+                          "newText": "-spec bar(term()) -> term().\n",
                           "range": {
                             "end": {
                               "character": 0,
@@ -236,7 +238,29 @@ fn test_e2e_eqwalizer_module() {
     diagnostic_project(
         &workspace_root,
         r"app_a/src/app_a.erl",
-        expect![[r#"
+        app_a_eqwalizer_diagnostics(),
+    );
+}
+
+#[test]
+fn test_e2e_eqwalizer_module_of_second_workspace_folder() {
+    let test_projects = Path::new(env!("CARGO_WORKSPACE_DIR")).join("test_projects");
+    let end_to_end = AbsPathBuf::assert(test_projects.join("end_to_end"));
+    let workspace_root = AbsPathBuf::assert(test_projects.join("standard"));
+
+    // Both folders are loaded as projects, whichever gets the first
+    // project id, and the module is checked in its own project
+    Project::new()
+        .with_workspace_folders(vec![end_to_end, workspace_root.clone()])
+        .check_diagnostic(
+            &workspace_root,
+            r"app_a/src/app_a.erl",
+            app_a_eqwalizer_diagnostics(),
+        );
+}
+
+fn app_a_eqwalizer_diagnostics() -> Expect {
+    expect![[r#"
             {
               "diagnostics": [
                 {
@@ -354,8 +378,7 @@ fn test_e2e_eqwalizer_module() {
               ],
               "uri": "file:///[..]/test_projects/standard/app_a/src/app_a.erl",
               "version": 0
-            }"#]],
-    );
+            }"#]]
 }
 
 // This used to fail because of trigerring eqwalizer for non-modules
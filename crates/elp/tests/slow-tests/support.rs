@@ -50,15 +50,24 @@ use tempfile::TempDir;
 
 pub(crate) struct Project {
     tmp_dir: TempDir,
+    workspace_folders: Vec<AbsPathBuf>,
 }
 
 impl Project {
     pub(crate) fn new() -> Project {
         Project {
             tmp_dir: Builder::new().prefix("elp_").tempdir().unwrap(),
+            workspace_folders: Vec::new(),
         }
     }
 
+    /// The workspace folders the client opens, each loaded as a project
+    /// on startup.
+    pub(crate) fn with_workspace_folders(mut self, workspace_folders: Vec<AbsPathBuf>) -> Project {
+        self.workspace_folders = workspace_folders;
+        self
+    }
+
     pub(crate) fn check_diagnostic(
         self,
         workspace_root: &AbsPathBuf,
@@ -156,6 +165,9 @@ impl Project {
             },
         );
         config.ignore_diagnostic(DiagnosticCode::MissingCompileWarnMissingSpec);
+        if !self.workspace_folders.is_empty() {
+            config.workspace_folders = self.workspace_folders.clone();
+        }
 
         let handle = thread::spawn(|| {
             let server = setup::setup_server(config, connection, Logger::default())?;
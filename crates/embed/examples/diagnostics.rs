@@ -0,0 +1,46 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Print the diagnostics of every module of a project.
+//!
+//! ```sh
+//! cargo run -p elp_embed --example diagnostics -- path/to/project
+//! ```
+
+use std::env;
+use std::path::PathBuf;
+
+use elp_embed::LoadOptions;
+use elp_embed::Project;
+use elp_embed::Result;
+
+fn main() -> Result<()> {
+    let root = env::args()
+        .nth(1)
+        .map_or_else(|| PathBuf::from("."), PathBuf::from);
+    let project = Project::load(&root, &LoadOptions::default())?;
+    for module in project.modules()? {
+        let path = match project.module_path(&module)? {
+            Some(path) => path,
+            None => continue,
+        };
+        for diagnostic in project.diagnostics(&path)? {
+            println!(
+                "{}:{}:{}: {:?} {}: {}",
+                path.display(),
+                diagnostic.range.start.line + 1,
+                diagnostic.range.start.column + 1,
+                diagnostic.severity,
+                diagnostic.code,
+                diagnostic.message
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,54 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Print the definition of, and the references to, the symbol at a
+//! position. Lines and columns are one-based.
+//!
+//! ```sh
+//! cargo run -p elp_embed --example references -- path/to/project src/foo.erl 10 5
+//! ```
+
+use std::env;
+use std::path::PathBuf;
+
+use anyhow::bail;
+use elp_embed::LoadOptions;
+use elp_embed::Location;
+use elp_embed::Position;
+use elp_embed::Project;
+use elp_embed::Result;
+
+fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() != 4 {
+        bail!("Usage: references PROJECT FILE LINE COLUMN");
+    }
+    let root = PathBuf::from(&args[0]);
+    let file = root.join(&args[1]);
+    let position = Position::new(args[2].parse::<u32>()? - 1, args[3].parse::<u32>()? - 1);
+
+    let project = Project::load(&root, &LoadOptions::default())?;
+    for location in project.goto_definition(&file, position)? {
+        print_location("definition", &location);
+    }
+    for location in project.find_references(&file, position)? {
+        print_location("reference", &location);
+    }
+    Ok(())
+}
+
+fn print_location(what: &str, location: &Location) {
+    println!(
+        "{} {}:{}:{}",
+        what,
+        location.path.display(),
+        location.range.start.line + 1,
+        location.range.start.column + 1
+    );
+}
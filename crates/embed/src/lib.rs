@@ -0,0 +1,353 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Embed ELP analyses in other tools, without going through the CLI or
+//! the language server.
+//!
+//! ```no_run
+//! use std::path::Path;
+//!
+//! use elp_embed::LoadOptions;
+//! use elp_embed::Project;
+//!
+//! let project = Project::load(Path::new("my_project"), &LoadOptions::default())?;
+//! for module in project.modules()? {
+//!     let path = project.module_path(&module)?.unwrap();
+//!     for diagnostic in project.diagnostics(&path)? {
+//!         println!("{}: {}", module, diagnostic.message);
+//!     }
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! # Stability
+//!
+//! This crate is versioned independently of the rest of ELP and follows
+//! semver: the items exported here only change in a breaking way with a
+//! new major version. Structs and enums returned by queries are
+//! `#[non_exhaustive]`, so that adding a field or a variant is not a
+//! breaking change. The types of the other ELP crates are deliberately
+//! not part of this API, as they change with every release.
+//!
+//! Positions are zero-based, with columns counted in UTF-16 code units,
+//! as in the LSP.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use anyhow::bail;
+use elp::build::load;
+use elp::build::types::LoadResult;
+use elp::cli::Fake;
+use elp_ide::diagnostics;
+use elp_ide::diagnostics::DiagnosticsConfig;
+use elp_ide::elp_ide_db::elp_base_db::AbsPathBuf;
+use elp_ide::elp_ide_db::elp_base_db::Change;
+use elp_ide::elp_ide_db::elp_base_db::FileId;
+use elp_ide::elp_ide_db::elp_base_db::FilePosition;
+use elp_ide::elp_ide_db::elp_base_db::IncludeOtp;
+use elp_ide::elp_ide_db::elp_base_db::VfsPath;
+use elp_ide::elp_ide_db::LineCol;
+use elp_ide::Analysis;
+use elp_ide::NavigationTarget;
+use elp_ide::TextRange;
+use elp_project_model::AppType;
+use elp_project_model::DiscoverConfig;
+
+pub type Result<T> = anyhow::Result<T>;
+
+/// How to discover and load a project.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct LoadOptions {
+    /// Query rebar3 for the project structure, instead of using buck2
+    /// or `build_info.json` when they are available.
+    pub rebar: bool,
+    /// The rebar3 profile to load the project with
+    pub profile: String,
+    /// Load OTP too, so that references to OTP modules can be resolved
+    pub include_otp: bool,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions {
+            rebar: false,
+            profile: "test".to_string(),
+            include_otp: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    pub line: u32,
+    pub column: u32,
+}
+
+impl Position {
+    pub fn new(line: u32, column: u32) -> Position {
+        Position { line, column }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Location {
+    pub path: PathBuf,
+    pub range: Range,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Severity {
+    Error,
+    Warning,
+    /// Reported unobtrusively by editors, e.g. without appearing in
+    /// their problems pane.
+    Notice,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Diagnostic {
+    /// The code of the diagnostic, e.g. `W0017`, usable to filter them
+    pub code: String,
+    pub message: String,
+    pub severity: Severity,
+    pub range: Range,
+}
+
+/// A loaded project, and the state of the analyses of its files.
+pub struct Project {
+    loaded: LoadResult,
+    /// The files given a text by `set_file_text`
+    edited: HashSet<FileId>,
+}
+
+impl Project {
+    /// Discover and load the project containing `root`.
+    pub fn load(root: &Path, options: &LoadOptions) -> Result<Project> {
+        let config = DiscoverConfig::new(options.rebar, &options.profile);
+        let include_otp = if options.include_otp {
+            IncludeOtp::Yes
+        } else {
+            IncludeOtp::No
+        };
+        let loaded = load::load_project_at(&Fake::default(), root, config, include_otp)?;
+        Ok(Project {
+            loaded,
+            edited: HashSet::new(),
+        })
+    }
+
+    pub fn root(&self) -> PathBuf {
+        self.loaded.project.root().into_owned().into()
+    }
+
+    /// The modules of the project, excluding OTP and dependencies,
+    /// sorted by name.
+    pub fn modules(&self) -> Result<Vec<String>> {
+        let analysis = self.analysis();
+        let module_index = analysis.module_index(self.loaded.project_id)?;
+        let mut res = Vec::new();
+        for (name, _source, file_id) in module_index.iter_own() {
+            if analysis.file_app_type(file_id)? != Some(AppType::Dep) {
+                res.push(name.as_str().to_string());
+            }
+        }
+        res.sort();
+        Ok(res)
+    }
+
+    /// The path of the file defining `module`, if it is part of the
+    /// project.
+    pub fn module_path(&self, module: &str) -> Result<Option<PathBuf>> {
+        let file_id = self
+            .analysis()
+            .module_file_id(self.loaded.project_id, module)?;
+        Ok(file_id.and_then(|file_id| self.path(file_id)))
+    }
+
+    /// Replace the contents of a file of the project, as if it had been
+    /// edited in memory. The file on disk is left untouched, so the
+    /// Erlang compiler, which reads it, no longer reports diagnostics for
+    /// it: see `diagnostics`.
+    pub fn set_file_text(&mut self, path: &Path, text: String) -> Result<()> {
+        let file_id = self.file_id(path)?;
+        self.edited.insert(file_id);
+        let vfs_path = self.loaded.vfs.file_path(file_id);
+        self.loaded
+            .vfs
+            .set_file_contents(vfs_path, Some(text.clone().into_bytes()));
+        let mut change = Change::new();
+        change.change_file(file_id, Some(Arc::new(text)));
+        self.loaded.analysis_host.apply_change(change);
+        Ok(())
+    }
+
+    /// The diagnostics of a file, as reported by the language server
+    /// with its default configuration: ELP's own and the Erlang
+    /// compiler's, but not eqwalizer's. The Erlang compiler's are left
+    /// out for a file given a text by `set_file_text`, as it compiles
+    /// the file on disk instead.
+    pub fn diagnostics(&self, path: &Path) -> Result<Vec<Diagnostic>> {
+        let file_id = self.file_id(path)?;
+        let analysis = self.analysis();
+        let mut config = DiagnosticsConfig::default();
+        config.disable_experimental = true;
+        let mut diags = analysis.diagnostics(&config, file_id, false)?;
+        if !self.edited.contains(&file_id) {
+            for (diag_file_id, erlang_service_diags) in
+                analysis.erlang_service_diagnostics(file_id)?
+            {
+                if diag_file_id == file_id {
+                    diags.extend(erlang_service_diags);
+                }
+            }
+        }
+        let mut res = diags
+            .iter()
+            .map(|diag| {
+                Ok(Diagnostic {
+                    code: diag.code.as_code(),
+                    message: diag.message.clone(),
+                    severity: match diag.severity {
+                        diagnostics::Severity::Error => Severity::Error,
+                        diagnostics::Severity::Warning => Severity::Warning,
+                        diagnostics::Severity::WeakWarning => Severity::Notice,
+                    },
+                    range: self.range(&analysis, file_id, diag.range)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        res.sort_by_key(|diag| diag.range.start);
+        Ok(res)
+    }
+
+    /// The definitions of the symbol at `position`.
+    pub fn goto_definition(&self, path: &Path, position: Position) -> Result<Vec<Location>> {
+        let analysis = self.analysis();
+        let position = self.file_position(&analysis, path, position)?;
+        match analysis.goto_definition(position)? {
+            Some(targets) => targets
+                .info
+                .iter()
+                .filter_map(|target| self.nav_location(&analysis, target).transpose())
+                .collect(),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// The references to the symbol at `position` in the project, not
+    /// including its definitions, ordered by path and position.
+    pub fn find_references(&self, path: &Path, position: Position) -> Result<Vec<Location>> {
+        let analysis = self.analysis();
+        let position = self.file_position(&analysis, path, position)?;
+        let mut res = Vec::new();
+        for search_result in analysis.find_all_refs(position)?.unwrap_or_default() {
+            for (file_id, ranges) in search_result.references {
+                let path = match self.path(file_id) {
+                    Some(path) => path,
+                    None => continue,
+                };
+                for range in ranges {
+                    res.push(Location {
+                        path: path.clone(),
+                        range: self.range(&analysis, file_id, range)?,
+                    });
+                }
+            }
+        }
+        res.sort_by(|a, b| (&a.path, a.range.start).cmp(&(&b.path, b.range.start)));
+        res.dedup();
+        Ok(res)
+    }
+
+    fn analysis(&self) -> Analysis {
+        self.loaded.analysis()
+    }
+
+    fn file_id(&self, path: &Path) -> Result<FileId> {
+        let path = AbsPathBuf::assert(path.canonicalize()?);
+        self.loaded
+            .vfs
+            .file_id(&VfsPath::from(path.clone()))
+            .ok_or_else(|| anyhow!("{} is not part of the project", path.display()))
+    }
+
+    fn path(&self, file_id: FileId) -> Option<PathBuf> {
+        let vfs_path = self.loaded.vfs.file_path(file_id);
+        vfs_path.as_path().map(|path| path.to_path_buf().into())
+    }
+
+    fn file_position(
+        &self,
+        analysis: &Analysis,
+        path: &Path,
+        position: Position,
+    ) -> Result<FilePosition> {
+        let file_id = self.file_id(path)?;
+        let line_index = analysis.line_index(file_id)?;
+        let len = analysis.file_text(file_id)?.len();
+        let offset = line_index.safe_offset(LineCol {
+            line: position.line,
+            col_utf16: position.column,
+        });
+        match offset {
+            // A column past the end of the line would be on the next one
+            Some(offset)
+                if usize::from(offset) <= len
+                    && line_index.line_col(offset).line == position.line =>
+            {
+                Ok(FilePosition { file_id, offset })
+            }
+            _ => bail!(
+                "{}:{}:{} is not a position in the file",
+                path.display(),
+                position.line,
+                position.column
+            ),
+        }
+    }
+
+    fn range(&self, analysis: &Analysis, file_id: FileId, range: TextRange) -> Result<Range> {
+        let line_index = analysis.line_index(file_id)?;
+        let position = |line_col: LineCol| Position::new(line_col.line, line_col.col_utf16);
+        Ok(Range {
+            start: position(line_index.line_col(range.start())),
+            end: position(line_index.line_col(range.end())),
+        })
+    }
+
+    fn nav_location(
+        &self,
+        analysis: &Analysis,
+        target: &NavigationTarget,
+    ) -> Result<Option<Location>> {
+        let path = match self.path(target.file_id) {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        let range = target.focus_range.unwrap_or(target.full_range);
+        Ok(Some(Location {
+            path,
+            range: self.range(analysis, target.file_id, range)?,
+        }))
+    }
+}
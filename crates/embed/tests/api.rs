@@ -0,0 +1,111 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Exercise the public API the way an embedding tool would, so that
+//! changes to the ELP internals it wraps do not silently change its
+//! behaviour.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use elp_embed::LoadOptions;
+use elp_embed::Position;
+use elp_embed::Project;
+use elp_embed::Severity;
+
+fn standard_project() -> Project {
+    let mut options = LoadOptions::default();
+    options.rebar = true;
+    Project::load(&project_path("standard"), &options).unwrap()
+}
+
+fn project_path(project: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../../test_projects")
+        .join(project)
+}
+
+fn module_path(project: &Project, module: &str) -> PathBuf {
+    project.module_path(module).unwrap().unwrap()
+}
+
+#[test]
+fn lists_own_modules() {
+    let project = standard_project();
+    let modules = project.modules().unwrap();
+    for module in ["app_a", "app_a_mod2", "app_b"] {
+        assert!(modules.iter().any(|m| m == module), "{module} not found");
+    }
+    assert!(!modules.iter().any(|m| m == "lists"));
+    assert!(module_path(&project, "app_a").ends_with("app_a/src/app_a.erl"));
+    assert_eq!(project.module_path("no_such_module").unwrap(), None);
+}
+
+#[test]
+fn goto_remote_function_definition() {
+    let project = standard_project();
+    let app_a = module_path(&project, "app_a");
+    // app_a_mod2:id(ok)
+    let definitions = project
+        .goto_definition(&app_a, Position::new(26, 23))
+        .unwrap();
+    assert_eq!(definitions.len(), 1);
+    assert!(definitions[0].path.ends_with("app_a/src/app_a_mod2.erl"));
+    assert_eq!(definitions[0].range.start, Position::new(24, 0));
+}
+
+#[test]
+fn find_references_across_modules() {
+    let project = standard_project();
+    let app_a_mod2 = module_path(&project, "app_a_mod2");
+    let references = project
+        .find_references(&app_a_mod2, Position::new(24, 0))
+        .unwrap();
+    assert!(references.iter().any(|location| {
+        location.path.ends_with("app_a/src/app_a.erl")
+            && location.range.start == Position::new(26, 23)
+    }));
+}
+
+#[test]
+fn diagnostics_follow_in_memory_edits() {
+    let mut project = standard_project();
+    let path = module_path(&project, "app_a_no_errors");
+    assert_eq!(project.diagnostics(&path).unwrap(), vec![]);
+
+    project
+        .set_file_text(&path, "-module(app_a_no_errors).\nok() -> .\n".to_string())
+        .unwrap();
+    let diagnostics = project.diagnostics(&path).unwrap();
+    assert!(
+        diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error
+                && diagnostic.range.start.line == 1),
+        "{diagnostics:?}"
+    );
+}
+
+#[test]
+fn positions_outside_the_file_are_an_error() {
+    let project = standard_project();
+    let app_a = module_path(&project, "app_a");
+    assert!(project
+        .goto_definition(&app_a, Position::new(10_000, 0))
+        .is_err());
+    assert!(project
+        .find_references(&app_a, Position::new(0, 10_000))
+        .is_err());
+}
+
+#[test]
+fn files_outside_the_project_are_an_error() {
+    let project = standard_project();
+    assert!(project.diagnostics(Path::new("Cargo.toml")).is_err());
+}
@@ -7,6 +7,7 @@
  * of this source tree.
  */
 
+use std::io;
 use std::io::BufRead;
 use std::io::BufReader;
 use std::io::BufWriter;
@@ -27,6 +28,7 @@ use timeout_readwrite::TimeoutReader;
 use timeout_readwrite::TimeoutWriter;
 
 use crate::EqwalizerDiagnostic;
+use crate::EqwalizerTypeInfo;
 
 #[derive(Deserialize, Debug)]
 pub enum EqWAlizerASTFormat {
@@ -61,6 +63,9 @@ pub enum MsgFromEqWAlizer {
     },
     Done {
         diagnostics: FxHashMap<String, Vec<EqwalizerDiagnostic>>,
+        /// Only sent by eqWAlizer versions reporting types
+        #[serde(default)]
+        type_info: FxHashMap<String, Vec<EqwalizerTypeInfo>>,
     },
 }
 
@@ -80,10 +85,12 @@ pub struct IpcHandle {
 }
 
 const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
-const READ_TIMEOUT: Duration = Duration::from_secs(240);
+/// How long eqWAlizer can go without sending anything by default,
+/// before it is considered hung.
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(240);
 
 impl IpcHandle {
-    pub fn from_command(cmd: &mut Command) -> Result<Self> {
+    pub fn from_command(cmd: &mut Command, read_timeout: Duration) -> Result<Self> {
         cmd.stdin(Stdio::piped())
             .stdout(Stdio::piped())
             // for debugging purposes
@@ -101,7 +108,7 @@ impl IpcHandle {
 
         let _child_for_drop = JodChild(child);
         let writer = BufWriter::new(TimeoutWriter::new(stdin, WRITE_TIMEOUT));
-        let reader = BufReader::new(TimeoutReader::new(stdout, READ_TIMEOUT));
+        let reader = BufReader::new(TimeoutReader::new(stdout, read_timeout));
 
         Ok(Self {
             writer,
@@ -148,9 +155,17 @@ impl IpcHandle {
 
     fn receive_line(&mut self) -> Result<String> {
         let mut buf = String::new();
-        self.reader
+        let len = self
+            .reader
             .read_line(&mut buf)
             .context("failed read_line from eqwalizer stdout")?;
+        if len == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "eqwalizer process exited unexpectedly",
+            )
+            .into());
+        }
         Ok(buf)
     }
 }
@@ -21,6 +21,7 @@ use std::path::PathBuf;
 use std::process::Command;
 use std::process::ExitStatus;
 use std::sync::Arc;
+use std::time::Duration;
 use std::time::Instant;
 
 use anyhow::Context;
@@ -52,46 +53,79 @@ pub struct Eqwalizer {
     cmd: OsString,
     args: Vec<OsString>,
     pub shell: bool,
-    // Used only for the Drop implementation
+    // Used for the Drop implementation, set for the bundled eqWAlizer
     _file: Option<Arc<TempPath>>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum EqwalizerDiagnostics {
-    Diagnostics(FxHashMap<String, Vec<EqwalizerDiagnostic>>),
-    NoAst { module: String },
+    Diagnostics {
+        errors: FxHashMap<String, Vec<EqwalizerDiagnostic>>,
+        /// The types inferred for the expressions of each module
+        type_info: FxHashMap<String, Vec<EqwalizerTypeInfo>>,
+    },
+    NoAst {
+        module: String,
+    },
     Error(String),
 }
 
 impl Default for EqwalizerDiagnostics {
     fn default() -> Self {
-        EqwalizerDiagnostics::Diagnostics(Default::default())
+        EqwalizerDiagnostics::Diagnostics {
+            errors: Default::default(),
+            type_info: Default::default(),
+        }
     }
 }
 
-#[derive(Debug, Deserialize, PartialEq, Eq, Clone)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct EqwalizerDiagnostic {
-    #[serde(deserialize_with = "deserialize_text_range")]
+    #[serde(
+        serialize_with = "serialize_text_range",
+        deserialize_with = "deserialize_text_range"
+    )]
     pub range: TextRange,
     pub message: String,
     pub uri: String,
     pub code: String,
-    #[serde(rename(deserialize = "expressionOrNull"))]
+    #[serde(rename = "expressionOrNull")]
     pub expression: Option<String>,
-    #[serde(rename(deserialize = "explanationOrNull"))]
+    #[serde(rename = "explanationOrNull")]
     pub explanation: Option<String>,
 }
 
+/// The type eqWAlizer inferred for an expression.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct EqwalizerTypeInfo {
+    #[serde(
+        serialize_with = "serialize_text_range",
+        deserialize_with = "deserialize_text_range"
+    )]
+    pub range: TextRange,
+    /// The type, as eqWAlizer prints it
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
 impl EqwalizerDiagnostics {
     pub fn combine(mut self, other: &Self) -> Self {
         match &mut self {
             EqwalizerDiagnostics::NoAst { .. } => self,
             EqwalizerDiagnostics::Error(_) => self,
-            EqwalizerDiagnostics::Diagnostics(diags) => match other {
-                EqwalizerDiagnostics::Diagnostics(other_diags) => {
-                    diags.extend(
-                        other_diags
+            EqwalizerDiagnostics::Diagnostics { errors, type_info } => match other {
+                EqwalizerDiagnostics::Diagnostics {
+                    errors: other_errors,
+                    type_info: other_type_info,
+                } => {
+                    errors.extend(
+                        other_errors
+                            .into_iter()
+                            .map(|(k, v)| (k.to_string(), v.to_vec())),
+                    );
+                    type_info.extend(
+                        other_type_info
                             .into_iter()
                             .map(|(k, v)| (k.to_string(), v.to_vec())),
                     );
@@ -133,6 +167,24 @@ pub trait EqwalizerDiagnosticsDatabase: ast::db::EqwalizerASTDatabase + DbApi {
     ) -> Option<Arc<EqwalizerStats>>;
 }
 
+/// The inverse of `deserialize_text_range`, to store the results.
+fn serialize_text_range<S>(range: &TextRange, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    #[derive(Serialize)]
+    struct RawTextRange {
+        start: u32,
+        end: u32,
+    }
+
+    RawTextRange {
+        start: range.start().into(),
+        end: range.end().into(),
+    }
+    .serialize(serializer)
+}
+
 fn deserialize_text_range<'de, D>(deserializer: D) -> Result<TextRange, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -202,6 +254,13 @@ impl Default for Eqwalizer {
 }
 
 impl Eqwalizer {
+    /// Whether this is the eqWAlizer bundled with ELP, rather than one
+    /// given by `ELP_EQWALIZER_PATH`: its results then only depend on
+    /// the version of ELP.
+    pub fn is_bundled(&self) -> bool {
+        self._file.is_some()
+    }
+
     // Return a smart pointer to bundle lifetime with the temp file's lifetime
     pub fn cmd<'file>(&'file self) -> CommandProxy<'file> {
         let mut cmd = Command::new(&self.cmd);
@@ -209,13 +268,17 @@ impl Eqwalizer {
         CommandProxy::new(cmd)
     }
 
+    /// Typecheck `modules`, giving up when eqWAlizer sends nothing for
+    /// `read_timeout`. Errors are left to the caller, which decides
+    /// whether to retry.
     pub fn typecheck(
         &self,
         build_info_path: &Path,
         db: &dyn EqwalizerDiagnosticsDatabase,
         project_id: ProjectId,
         modules: Vec<&str>,
-    ) -> EqwalizerDiagnostics {
+        read_timeout: Duration,
+    ) -> Result<EqwalizerDiagnostics> {
         let mut cmd = self.cmd();
         cmd.arg("ipc");
         cmd.args(modules);
@@ -227,15 +290,9 @@ impl Eqwalizer {
         add_env(&mut cmd, build_info_path, None);
 
         if self.shell {
-            match shell_typecheck(cmd, db, project_id) {
-                Ok(diags) => diags,
-                Err(err) => EqwalizerDiagnostics::Error(format!("{}", err)),
-            }
+            shell_typecheck(cmd, db, project_id, read_timeout)
         } else {
-            match do_typecheck(cmd, db, project_id) {
-                Ok(diags) => diags,
-                Err(err) => EqwalizerDiagnostics::Error(format!("{}", err)),
-            }
+            do_typecheck(cmd, db, project_id, read_timeout)
         }
     }
 
@@ -257,8 +314,9 @@ fn do_typecheck(
     mut cmd: CommandProxy,
     db: &dyn EqwalizerDiagnosticsDatabase,
     project_id: ProjectId,
+    read_timeout: Duration,
 ) -> Result<EqwalizerDiagnostics, anyhow::Error> {
-    let mut handle = IpcHandle::from_command(&mut cmd)
+    let mut handle = IpcHandle::from_command(&mut cmd, read_timeout)
         .with_context(|| format!("starting eqWAlizer process: {:?}", cmd))?;
     let _pctx = stdx::panic_context::enter(format!("\neqWAlizing with command: {:?}", cmd));
     loop {
@@ -344,12 +402,18 @@ fn do_typecheck(
             }
             MsgFromEqWAlizer::EqwalizingStart { module } => db.eqwalizing_start(module),
             MsgFromEqWAlizer::EqwalizingDone { module } => db.eqwalizing_done(module),
-            MsgFromEqWAlizer::Done { diagnostics } => {
+            MsgFromEqWAlizer::Done {
+                diagnostics,
+                type_info,
+            } => {
                 log::debug!(
                     "received from eqwalizer: Done with diagnostics length {}",
                     diagnostics.len()
                 );
-                return Ok(EqwalizerDiagnostics::Diagnostics(diagnostics));
+                return Ok(EqwalizerDiagnostics::Diagnostics {
+                    errors: diagnostics,
+                    type_info,
+                });
             }
             msg => {
                 log::warn!(
@@ -365,11 +429,12 @@ fn shell_typecheck(
     mut cmd: CommandProxy,
     db: &dyn EqwalizerDiagnosticsDatabase,
     project_id: ProjectId,
+    read_timeout: Duration,
 ) -> Result<EqwalizerDiagnostics, anyhow::Error> {
     // Never cache the results of this function
     db.salsa_runtime().report_untracked_read();
     let handle = Arc::new(Mutex::new(
-        IpcHandle::from_command(&mut cmd)
+        IpcHandle::from_command(&mut cmd, read_timeout)
             .with_context(|| format!("starting eqWAlizer process: {:?}", cmd))?,
     ));
     let mut diagnostics = EqwalizerDiagnostics::default();
@@ -510,12 +575,18 @@ fn get_module_diagnostics(
             }
             MsgFromEqWAlizer::EqwalizingStart { module } => db.eqwalizing_start(module),
             MsgFromEqWAlizer::EqwalizingDone { module } => db.eqwalizing_done(module),
-            MsgFromEqWAlizer::Done { diagnostics } => {
+            MsgFromEqWAlizer::Done {
+                diagnostics,
+                type_info,
+            } => {
                 log::debug!(
                     "received from eqwalizer: Done with diagnostics length {}",
                     diagnostics.len()
                 );
-                return Ok(EqwalizerDiagnostics::Diagnostics(diagnostics));
+                return Ok(EqwalizerDiagnostics::Diagnostics {
+                    errors: diagnostics,
+                    type_info,
+                });
             }
             MsgFromEqWAlizer::Dependencies { modules } => {
                 modules.iter().for_each(|module| {
@@ -7,6 +7,7 @@
  * of this source tree.
  */
 
+use std::fs;
 use std::io::BufRead;
 use std::io::BufReader;
 use std::io::BufWriter;
@@ -223,6 +224,101 @@ pub struct RawParseResult<Error> {
     pub warnings: Vec<Error>,
 }
 
+fn decode_parse_reply(request: ParseRequest, reply: Result<UndecodedParseResult>) -> ParseResult {
+    let path = request.path.clone();
+    match reply {
+        Result::Ok(result) => match result.decode() {
+            Result::Ok(result) => result,
+            Err(error) => {
+                log::error!("Decoding parse result failed: {:?}", error);
+                ParseResult::error(ParseError {
+                    path,
+                    location: None,
+                    msg: format!("Could not parse, error: {}", error.to_string()),
+                    code: "L0001".to_string(),
+                })
+            }
+        },
+        Err(error) => {
+            log::error!(
+                "Erlang service crashed for: {:?}, error: {:?}",
+                request,
+                error
+            );
+            ParseResult::error(ParseError {
+                path,
+                location: None,
+                msg: format!("Could not parse, error: {}", error.to_string()),
+                code: "L0002".to_string(),
+            })
+        }
+    }
+}
+
+/// Parse results stored on disk, so that they outlive the process. An
+/// entry is keyed by a digest of everything the result depends on, which
+/// is up to the caller to compute, and is used as its file name.
+#[derive(Debug, Clone)]
+pub struct ParseCache {
+    dir: PathBuf,
+}
+
+impl ParseCache {
+    pub fn new(dir: PathBuf) -> ParseCache {
+        ParseCache { dir }
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(key)
+    }
+
+    /// The entry is the length-prefixed AST, stub, errors and warnings,
+    /// still encoded as returned by the Erlang service.
+    fn load(&self, key: &str) -> Option<UndecodedParseResult> {
+        let bytes = fs::read(self.entry_path(key)).ok()?;
+        let mut blobs = Vec::with_capacity(4);
+        let mut rest = &bytes[..];
+        while !rest.is_empty() {
+            let len = u64::from_le_bytes(rest.get(..8)?.try_into().ok()?) as usize;
+            let end = len.checked_add(8)?;
+            blobs.push(rest.get(8..end)?.to_vec());
+            rest = &rest[end..];
+        }
+        let [ast, stub, errors, warnings]: [Vec<u8>; 4] = blobs.try_into().ok()?;
+        Some(UndecodedParseResult {
+            ast: Arc::new(ast),
+            stub: Arc::new(stub),
+            errors,
+            warnings,
+        })
+    }
+
+    fn store(&self, key: &str, result: &UndecodedParseResult) {
+        let mut bytes = Vec::new();
+        for blob in [
+            &result.ast[..],
+            &result.stub[..],
+            &result.errors[..],
+            &result.warnings[..],
+        ] {
+            bytes.extend((blob.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(blob);
+        }
+        // Written to a temporary file first, as another process may be
+        // reading the entry
+        let res = fs::create_dir_all(&self.dir).and_then(|()| {
+            let mut file = Builder::new().tempfile_in(&self.dir)?;
+            file.write_all(&bytes)?;
+            file.persist(self.entry_path(key))
+                .map_err(|err| err.error)?;
+            Ok(())
+        });
+        if let Err(err) = res {
+            log::warn!("Could not store parse result in {:?}: {}", self.dir, err);
+        }
+    }
+}
+
 impl UndecodedParseResult {
     pub fn decode(self) -> Result<ParseResult> {
         let errors = decode_errors(&self.errors).with_context(|| "when decoding errors")?;
@@ -282,38 +378,38 @@ impl Connection {
         })
     }
 
-    pub fn request_parse(&self, request_in: ParseRequest) -> ParseResult {
-        let (sender, receiver) = bounded::<Result<UndecodedParseResult>>(0);
-        let path = request_in.path.clone();
-        let request = Request::ParseRequest(request_in.clone(), sender);
-        self.sender.send(request).unwrap();
-        match receiver.recv().unwrap() {
-            Result::Ok(result) => match result.decode() {
-                Result::Ok(result) => result,
-                Err(error) => {
-                    log::error!("Decoding parse result failed: {:?}", error);
-                    ParseResult::error(ParseError {
-                        path,
-                        location: None,
-                        msg: format!("Could not parse, error: {}", error.to_string()),
-                        code: "L0001".to_string(),
-                    })
+    pub fn request_parse(&self, request: ParseRequest) -> ParseResult {
+        let reply = self.send_parse_request(request.clone());
+        decode_parse_reply(request, reply)
+    }
+
+    /// As `request_parse`, but reads the result from `cache` if it has
+    /// an entry for `key`, and stores it there otherwise.
+    pub fn request_parse_cached(
+        &self,
+        request: ParseRequest,
+        cache: &ParseCache,
+        key: &str,
+    ) -> ParseResult {
+        let reply = match cache.load(key) {
+            Some(result) => Ok(result),
+            None => {
+                let reply = self.send_parse_request(request.clone());
+                if let Ok(result) = &reply {
+                    cache.store(key, result);
                 }
-            },
-            Err(error) => {
-                log::error!(
-                    "Erlang service crashed for: {:?}, error: {:?}",
-                    request_in,
-                    error
-                );
-                ParseResult::error(ParseError {
-                    path,
-                    location: None,
-                    msg: format!("Could not parse, error: {}", error.to_string()),
-                    code: "L0002".to_string(),
-                })
+                reply
             }
-        }
+        };
+        decode_parse_reply(request, reply)
+    }
+
+    fn send_parse_request(&self, request: ParseRequest) -> Result<UndecodedParseResult> {
+        let (sender, receiver) = bounded::<Result<UndecodedParseResult>>(0);
+        self.sender
+            .send(Request::ParseRequest(request, sender))
+            .unwrap();
+        receiver.recv().unwrap()
     }
 
     pub fn request_doc(&self, request: DocRequest) -> Result<DocResult, String> {
@@ -778,6 +874,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_cache_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ParseCache::new(dir.path().join("ast"));
+        let result = UndecodedParseResult {
+            ast: Arc::new(b"ast".to_vec()),
+            stub: Arc::new(vec![]),
+            errors: vec![1, 2],
+            warnings: vec![3],
+        };
+        assert_eq!(cache.load("2a"), None);
+        cache.store("2a", &result);
+        assert_eq!(cache.load("2a"), Some(result));
+
+        fs::write(cache.entry_path("07"), b"garbage").unwrap();
+        assert_eq!(cache.load("07"), None);
+    }
+
     fn expect_module(path: PathBuf, expected: ExpectFile, options: Vec<CompileOption>) {
         lazy_static! {
             static ref CONN: Connection = Connection::start().unwrap();
@@ -11,10 +11,12 @@ use std::sync::Arc;
 
 use elp_base_db::salsa;
 use elp_base_db::FileId;
+use elp_base_db::ProjectId;
 use elp_base_db::SourceDatabase;
 use elp_base_db::Upcast;
 use elp_syntax::ast;
 use fxhash::FxHashMap;
+use fxhash::FxHashSet;
 
 use crate::body::scope::FunctionScopes;
 use crate::body::DefineBody;
@@ -24,6 +26,7 @@ use crate::include;
 pub use crate::intern::MinInternDatabase;
 pub use crate::intern::MinInternDatabaseStorage;
 use crate::macro_exp;
+use crate::macro_exp::MacroReferenceIndex;
 use crate::macro_exp::MacroResolution;
 use crate::AttributeBody;
 use crate::AttributeId;
@@ -119,6 +122,15 @@ pub trait MinDefDatabase:
     #[salsa::invoke(macro_exp::resolve_query)]
     fn resolve_macro(&self, file_id: FileId, name: MacroName) -> Option<ResolvedMacro>;
 
+    /// The user macros defined in other files and used by a file, as
+    /// `(defining file, macro name)`.
+    #[salsa::invoke(macro_exp::file_macro_references_query)]
+    fn file_macro_references(&self, file_id: FileId) -> Arc<FxHashSet<(FileId, MacroName)>>;
+
+    /// The macro usages of all the files of a project.
+    #[salsa::invoke(macro_exp::macro_reference_index_query)]
+    fn macro_reference_index(&self, project_id: ProjectId) -> Arc<MacroReferenceIndex>;
+
     #[salsa::invoke(edoc::file_edoc_comments_query)]
     fn file_edoc_comments(
         &self,
@@ -269,11 +269,12 @@ impl<'a, T> FoldCtx<'a, T> {
                 args.iter().fold(r, |acc, arg| self.do_fold_expr(*arg, acc))
             }
             crate::Expr::Comprehension { builder, exprs } => match builder {
-                ComprehensionBuilder::List(expr) => self.fold_comprehension(expr, exprs, acc),
-                ComprehensionBuilder::Binary(expr) => self.fold_comprehension(expr, exprs, acc),
+                ComprehensionBuilder::List(expr) => self.fold_comprehension(&[*expr], exprs, acc),
+                ComprehensionBuilder::Binary(expr) => self.fold_comprehension(&[*expr], exprs, acc),
                 ComprehensionBuilder::Map(key, value) => {
-                    let r = self.fold_comprehension(key, exprs, acc);
-                    self.fold_comprehension(value, exprs, r)
+                    // Visit the generators and filters once, for both
+                    // the key and the value.
+                    self.fold_comprehension(&[*key, *value], exprs, acc)
                 }
             },
             crate::Expr::Block { exprs } => exprs
@@ -484,8 +485,15 @@ impl<'a, T> FoldCtx<'a, T> {
         })
     }
 
-    fn fold_comprehension(&mut self, expr: &ExprId, exprs: &[ComprehensionExpr], initial: T) -> T {
-        let r = self.do_fold_expr(*expr, initial);
+    fn fold_comprehension(
+        &mut self,
+        builder: &[ExprId],
+        exprs: &[ComprehensionExpr],
+        initial: T,
+    ) -> T {
+        let r = builder
+            .iter()
+            .fold(initial, |acc, expr| self.do_fold_expr(*expr, acc));
         exprs
             .iter()
             .fold(r, |acc, comprehension_expr| match comprehension_expr {
@@ -750,6 +758,66 @@ bar() ->
         .assert_debug_eq(&ast_var);
     }
 
+    #[test]
+    fn traverse_map_comprehension() {
+        let fixture_str = r#"
+bar(M) ->
+  #{K => ~V || K := V <- M, V > K}.
+"#;
+
+        let (db, file_id, range_or_offset) = TestDB::with_range_or_offset(fixture_str);
+        let sema = Semantic::new(&db);
+        let offset = match range_or_offset {
+            elp_base_db::fixture::RangeOrOffset::Range(_) => panic!(),
+            elp_base_db::fixture::RangeOrOffset::Offset(o) => o,
+        };
+        let in_file = sema.parse(file_id);
+        let source_file = in_file.value;
+        let ast_var = algo::find_node_at_offset::<ast::Var>(source_file.syntax(), offset).unwrap();
+
+        let (body, body_map) = FunctionBody::function_body_with_source_query(
+            &db,
+            InFile {
+                file_id,
+                value: Idx::from_raw(RawIdx::from(0)),
+            },
+        );
+
+        let expr = ast::Expr::ExprMax(ast::ExprMax::Var(ast_var.clone()));
+        let expr_id = body_map
+            .expr_id(InFile {
+                file_id,
+                value: &expr,
+            })
+            .unwrap();
+        let hir_var = match &body.body[expr_id] {
+            crate::Expr::Var(v) => v,
+            _ => panic!(),
+        };
+        let idx = ClauseId::from_raw(RawIdx::from(0));
+        let r: u32 = FoldCtx::fold_expr(
+            &body.body,
+            Strategy::TopDown,
+            body.clauses[idx].exprs[0],
+            0,
+            &mut |acc, ctx| match ctx.expr {
+                crate::Expr::Var(v) if &v == hir_var => acc + 1,
+                _ => acc,
+            },
+            &mut |acc, ctx| match ctx.pat {
+                crate::Pat::Var(v) if &v == hir_var => acc + 1,
+                _ => acc,
+            },
+        );
+
+        // The generator and filter are only visited once, even though
+        // both the key and the value are built from them.
+        expect![[r#"
+            3
+        "#]]
+        .assert_debug_eq(&r);
+    }
+
     #[test]
     fn traverse_term() {
         let fixture_str = r#"
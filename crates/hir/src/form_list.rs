@@ -114,6 +114,10 @@ impl FormList {
         self.data.exports.iter()
     }
 
+    pub fn imports(&self) -> impl Iterator<Item = (ImportId, &Import)> {
+        self.data.imports.iter()
+    }
+
     pub fn specs(&self) -> impl Iterator<Item = (SpecId, &Spec)> {
         self.data.specs.iter()
     }
@@ -150,6 +154,13 @@ impl FormList {
         self.data.compile_options.iter()
     }
 
+    /// Returns the -deprecated attributes in the file
+    pub fn deprecated_attributes(
+        &self,
+    ) -> impl Iterator<Item = (DeprecatedAttributeId, &DeprecatedAttribute)> {
+        self.data.deprecates.iter()
+    }
+
     pub fn find_form(&self, form: &ast::Form) -> Option<FormIdx> {
         self.map_back.get(&AstPtr::new(form)).copied()
     }
@@ -91,8 +91,15 @@ impl<'a> IncludeCtx<'a> {
         let source_root_id = project_data.app_roots.get(app_name)?;
         let source_root = self.db.source_root(source_root_id);
         let target_app_data = self.db.app_data(source_root_id)?;
-        let path = target_app_data.dir.join(path);
-        source_root.file_for_path(&path.into())
+        let app_path = target_app_data.dir.join(path);
+        source_root.file_for_path(&app_path.into()).or_else(|| {
+            // Generated headers are not where the app layout puts them
+            target_app_data
+                .include_remaps
+                .iter()
+                .flat_map(|remap| remap.remap(path))
+                .find_map(|generated| source_root.file_for_path(&generated.into()))
+        })
     }
 }
 
@@ -182,4 +189,20 @@ mod tests {
             "#]],
         )
     }
+
+    #[test]
+    fn generated_lib() {
+        check(
+            r#"
+//- /main/src/module.erl app:main
+-include_lib("another/include/generated.hrl").
+//- /another-app/src/another.erl app:another generated:/out/gen/another
+-module(another).
+//- /out/gen/another/generated.hrl app:another
+"#,
+            expect![[r#"
+                -include_lib("another/include/generated.hrl"). % => /out/gen/another/generated.hrl
+            "#]],
+        )
+    }
 }
@@ -24,6 +24,7 @@ mod intern;
 mod macro_exp;
 mod module_data;
 mod name;
+mod pat_match;
 pub mod resolver;
 mod sema;
 mod test_db;
@@ -81,6 +82,10 @@ pub use form_list::CompileOption;
 pub use form_list::CompileOptionId;
 pub use form_list::Define;
 pub use form_list::DefineId;
+pub use form_list::DeprecatedAttribute;
+pub use form_list::DeprecatedAttributeId;
+pub use form_list::DeprecatedDesc;
+pub use form_list::DeprecatedFa;
 pub use form_list::Export;
 pub use form_list::ExportId;
 pub use form_list::FaEntry;
@@ -115,6 +120,7 @@ pub use form_list::TypeExport;
 pub use form_list::TypeExportId;
 pub use intern::Atom;
 pub use intern::Var;
+pub use macro_exp::MacroReferenceIndex;
 pub use macro_exp::ResolvedMacro;
 pub use module_data::CallbackDef;
 pub use module_data::DefineDef;
@@ -133,6 +139,7 @@ pub use name::known;
 pub use name::MacroName;
 pub use name::Name;
 pub use name::NameArity;
+pub use pat_match::may_match;
 pub use sema::CallDef;
 pub use sema::DefinitionOrReference;
 pub use sema::FaDef;
@@ -7,8 +7,14 @@
  * of this source tree.
  */
 
+use std::sync::Arc;
+
 use elp_base_db::FileId;
+use elp_base_db::ProjectId;
 use elp_syntax::ast;
+use elp_syntax::AstNode;
+use fxhash::FxHashMap;
+use fxhash::FxHashSet;
 
 use crate::db::MinDefDatabase;
 use crate::form_list::FormListData;
@@ -154,6 +160,84 @@ pub(crate) fn local_resolve_query(
     MacroResolution::Unresolved
 }
 
+/// Resolve a macro call to a user definition. A call with arguments
+/// falls back to a definition without any, as `?FOO(X)` may expand
+/// `FOO` to a fun.
+pub fn resolve_call(
+    db: &dyn MinDefDatabase,
+    file_id: FileId,
+    call: &ast::MacroCallExpr,
+) -> Option<InFile<DefineId>> {
+    let name = macro_name(call)?;
+    match db.resolve_macro(file_id, name.clone()) {
+        Some(ResolvedMacro::User(resolved)) => Some(resolved),
+        Some(ResolvedMacro::BuiltIn(_)) => None,
+        None => match db.resolve_macro(file_id, name.with_arity(None)) {
+            Some(ResolvedMacro::User(resolved)) => Some(resolved),
+            _ => None,
+        },
+    }
+}
+
+/// Project wide reverse index of macro usages: for each macro, as the
+/// file defining it and its name, the other files using it.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MacroReferenceIndex {
+    users: FxHashMap<(FileId, MacroName), Vec<FileId>>,
+}
+
+impl MacroReferenceIndex {
+    /// The files using the macro `name` defined in `file_id`, ordered
+    /// by file.
+    pub fn users(&self, file_id: FileId, name: &MacroName) -> &[FileId] {
+        self.users
+            .get(&(file_id, name.clone()))
+            .map_or(&[], |files| files.as_slice())
+    }
+}
+
+pub(crate) fn file_macro_references_query(
+    db: &dyn MinDefDatabase,
+    file_id: FileId,
+) -> Arc<FxHashSet<(FileId, MacroName)>> {
+    let source = db.parse(file_id).tree();
+    let res = source
+        .syntax()
+        .descendants()
+        .filter_map(ast::MacroCallExpr::cast)
+        .filter_map(|call| resolve_call(db, file_id, &call))
+        // Local macros are always searched in their own file
+        .filter(|define| define.file_id != file_id)
+        .map(|define| {
+            let form_list = db.file_form_list(define.file_id);
+            (define.file_id, form_list[define.value].name.clone())
+        })
+        .collect();
+    Arc::new(res)
+}
+
+pub(crate) fn macro_reference_index_query(
+    db: &dyn MinDefDatabase,
+    project_id: ProjectId,
+) -> Arc<MacroReferenceIndex> {
+    let mut file_ids: Vec<FileId> = db
+        .project_data(project_id)
+        .source_roots
+        .iter()
+        .flat_map(|&source_root_id| db.source_root(source_root_id).iter().collect::<Vec<_>>())
+        .collect();
+    file_ids.sort();
+    file_ids.dedup();
+
+    let mut users: FxHashMap<(FileId, MacroName), Vec<FileId>> = FxHashMap::default();
+    for file_id in file_ids {
+        for reference in db.file_macro_references(file_id).iter() {
+            users.entry(reference.clone()).or_default().push(file_id);
+        }
+    }
+    Arc::new(MacroReferenceIndex { users })
+}
+
 // This handles the case of headers accidentally forming cycles during macro resolution.
 pub(crate) fn recover_cycle(
     _db: &dyn MinDefDatabase,
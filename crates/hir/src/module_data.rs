@@ -20,9 +20,14 @@ use elp_syntax::SyntaxNode;
 use crate::db::MinDefDatabase;
 use crate::db::MinInternDatabase;
 use crate::edoc::EdocHeader;
+use crate::known;
+use crate::name::AsName;
+use crate::CallTarget;
 use crate::Callback;
 use crate::DefMap;
 use crate::Define;
+use crate::Expr;
+use crate::ExprId;
 use crate::Function;
 use crate::FunctionId;
 use crate::InFile;
@@ -42,6 +47,9 @@ use crate::Var;
 pub enum FileKind {
     Module,
     Header,
+    /// A script run with `escript`: like a module, but without a
+    /// `-module` attribute, and starting with a `#!` line.
+    Escript,
     Other,
 }
 
@@ -65,6 +73,7 @@ impl File {
         match ext {
             Some("erl") => FileKind::Module,
             Some("hrl") => FileKind::Header,
+            Some("escript") => FileKind::Escript,
             _ => FileKind::Other,
         }
     }
@@ -106,6 +115,29 @@ impl Module {
     pub fn is_in_otp(&self, db: &dyn MinDefDatabase) -> bool {
         is_in_otp(self.file.file_id, db)
     }
+
+    /// The function named by the `-on_load` attribute of the module,
+    /// which the runtime calls when loading it, and the attribute.
+    pub fn on_load(&self, db: &dyn MinDefDatabase) -> Option<(NameArity, ast::WildAttribute)> {
+        let forms = db.file_form_list(self.file.file_id);
+        let (_, attribute) = forms
+            .attributes()
+            .find(|(_, attribute)| attribute.name == known::on_load)?;
+        let attribute = attribute.form_id.get(&self.file.source(db.upcast()));
+        let fa = match attribute.value()? {
+            ast::Expr::BinaryOpExpr(fa) => fa,
+            _ => return None,
+        };
+        let name = match fa.lhs()? {
+            ast::Expr::ExprMax(ast::ExprMax::Atom(name)) => name.as_name(),
+            _ => return None,
+        };
+        let arity = match fa.rhs()? {
+            ast::Expr::ExprMax(ast::ExprMax::Integer(arity)) => arity.text().parse().ok()?,
+            _ => return None,
+        };
+        Some((NameArity::new(name, arity), attribute))
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -141,6 +173,37 @@ impl FunctionDef {
         is_in_otp(self.file.file_id, db)
     }
 
+    /// Whether this is a NIF stub, all of whose clauses end by calling
+    /// `erlang:nif_error/1,2`. The runtime replaces these functions
+    /// when the NIF library is loaded.
+    pub fn is_nif_stub(&self, db: &dyn MinDefDatabase) -> bool {
+        let function_body = db.function_body(InFile::new(self.file.file_id, self.function_id));
+        let body = &function_body.body;
+        let is_nif_error = |expr: ExprId| match &body[expr] {
+            Expr::Call { target, args } if (1..=2).contains(&args.len()) => {
+                let is_atom = |id: ExprId, name: &Name| {
+                    body[id]
+                        .as_atom()
+                        .map_or(false, |atom| &db.lookup_atom(atom) == name)
+                };
+                match target {
+                    CallTarget::Local { name } => is_atom(*name, &known::nif_error),
+                    CallTarget::Remote { module, name } => {
+                        is_atom(*module, &known::erlang) && is_atom(*name, &known::nif_error)
+                    }
+                }
+            }
+            _ => false,
+        };
+        !function_body.clauses.is_empty()
+            && function_body.clauses.iter().all(|(_, clause)| {
+                clause
+                    .exprs
+                    .last()
+                    .map_or(false, |&expr| is_nif_error(expr))
+            })
+    }
+
     pub fn edoc_comments(&self, db: &dyn MinDefDatabase) -> Option<EdocHeader> {
         let form = InFileAstPtr::new(
             self.file.file_id,
@@ -273,6 +336,10 @@ impl TypeAliasDef {
             TypeAlias::Opaque { name, .. } => name,
         }
     }
+
+    pub fn is_in_otp(&self, db: &dyn MinDefDatabase) -> bool {
+        is_in_otp(self.file.file_id, db)
+    }
 }
 
 impl TypeAliasSource {
@@ -221,6 +221,9 @@ pub mod known {
         apply,
         export_all,
         parse_transform,
+        module_info,
+        nif_error,
+        on_load,
         // Common Test framework
         all,
         group,
@@ -0,0 +1,162 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Heuristics telling whether the value of an expression may match a
+//! pattern, possibly of another body, e.g. the message given to
+//! `gen_server:call/2` and the first argument of a `handle_call/3`
+//! clause.
+//!
+//! Only the shape known from the syntax is compared: a variable, a call
+//! or any other computed value may match anything.
+
+use crate::Body;
+use crate::Expr;
+use crate::ExprId;
+use crate::Literal;
+use crate::Pat;
+use crate::PatId;
+
+/// Whether the value of `expr`, in `expr_body`, may match `pat`, in
+/// `pat_body`.
+pub fn may_match(expr_body: &Body, expr: ExprId, pat_body: &Body, pat: PatId) -> bool {
+    match &pat_body[pat] {
+        Pat::Missing | Pat::Var(_) => return true,
+        Pat::Match { lhs, rhs } => {
+            return may_match(expr_body, expr, pat_body, *lhs)
+                && may_match(expr_body, expr, pat_body, *rhs);
+        }
+        Pat::MacroCall { expansion, .. } => {
+            return may_match(expr_body, expr, pat_body, *expansion);
+        }
+        _ => {}
+    }
+    match (&expr_body[expr], &pat_body[pat]) {
+        (Expr::MacroCall { expansion, .. }, _) => may_match(expr_body, *expansion, pat_body, pat),
+        (Expr::Match { rhs, .. }, _) => may_match(expr_body, *rhs, pat_body, pat),
+        (Expr::Literal(lit), Pat::Literal(pat_lit)) => same_literal(lit, pat_lit),
+        (Expr::Tuple { exprs }, Pat::Tuple { pats }) => {
+            exprs.len() == pats.len()
+                && exprs
+                    .iter()
+                    .zip(pats)
+                    .all(|(expr, pat)| may_match(expr_body, *expr, pat_body, *pat))
+        }
+        (
+            Expr::List { exprs, tail },
+            Pat::List {
+                pats,
+                tail: pat_tail,
+            },
+        ) => {
+            let elems_match = exprs
+                .iter()
+                .zip(pats)
+                .all(|(expr, pat)| may_match(expr_body, *expr, pat_body, *pat));
+            // Elements left on one side can only be matched by a tail
+            // on the other one
+            let lengths_match = match exprs.len().cmp(&pats.len()) {
+                std::cmp::Ordering::Less => tail.is_some(),
+                std::cmp::Ordering::Equal => true,
+                std::cmp::Ordering::Greater => pat_tail.is_some(),
+            };
+            elems_match && lengths_match
+        }
+        (
+            Expr::Record { name, fields },
+            Pat::Record {
+                name: pat_name,
+                fields: pat_fields,
+            },
+        ) => {
+            name == pat_name
+                && pat_fields.iter().all(|(field, pat)| {
+                    match fields.iter().find(|(name, _)| name == field) {
+                        Some((_, expr)) => may_match(expr_body, *expr, pat_body, *pat),
+                        // The default value of the field
+                        None => true,
+                    }
+                })
+        }
+        (Expr::Map { fields }, Pat::Map { fields: pat_fields }) => {
+            pat_fields.iter().all(|(key, pat)| {
+                let pat_key = match &pat_body[*key] {
+                    Expr::Literal(lit) => lit,
+                    _ => return true,
+                };
+                let value = fields.iter().find(|(key, _)| match &expr_body[*key] {
+                    Expr::Literal(lit) => same_literal(lit, pat_key),
+                    _ => false,
+                });
+                match value {
+                    Some((_, expr)) => may_match(expr_body, *expr, pat_body, *pat),
+                    // Unless a computed key is the one of the pattern
+                    None => fields
+                        .iter()
+                        .any(|(key, _)| !matches!(expr_body[*key], Expr::Literal(_))),
+                }
+            })
+        }
+        (expr, pat) => {
+            let (expr_shape, pat_shape) = (expr_shape(expr), pat_shape(pat));
+            expr_shape == Shape::Unknown || pat_shape == Shape::Unknown || expr_shape == pat_shape
+        }
+    }
+}
+
+/// A character is an integer
+fn same_literal(lit: &Literal, pat_lit: &Literal) -> bool {
+    match (lit, pat_lit) {
+        (Literal::Char(ch), Literal::Integer(int)) | (Literal::Integer(int), Literal::Char(ch)) => {
+            *ch as i128 == *int
+        }
+        _ => lit == pat_lit,
+    }
+}
+
+/// The kind of term a value is, as far as known from the syntax
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    Atomic,
+    Tuple,
+    List,
+    Map,
+    Binary,
+    Fun,
+    Unknown,
+}
+
+fn literal_shape(lit: &Literal) -> Shape {
+    match lit {
+        Literal::String(_) => Shape::List,
+        _ => Shape::Atomic,
+    }
+}
+
+fn expr_shape(expr: &Expr) -> Shape {
+    match expr {
+        Expr::Literal(lit) => literal_shape(lit),
+        Expr::Tuple { .. } | Expr::Record { .. } => Shape::Tuple,
+        Expr::List { .. } => Shape::List,
+        Expr::Map { .. } => Shape::Map,
+        Expr::Binary { .. } => Shape::Binary,
+        Expr::Closure { .. } | Expr::CaptureFun { .. } => Shape::Fun,
+        _ => Shape::Unknown,
+    }
+}
+
+fn pat_shape(pat: &Pat) -> Shape {
+    match pat {
+        Pat::Literal(lit) => literal_shape(lit),
+        Pat::Tuple { .. } | Pat::Record { .. } => Shape::Tuple,
+        Pat::List { .. } => Shape::List,
+        Pat::Map { .. } => Shape::Map,
+        Pat::Binary { .. } => Shape::Binary,
+        _ => Shape::Unknown,
+    }
+}
@@ -33,7 +33,6 @@ use crate::NameArity;
 use crate::Pat;
 use crate::RecordDef;
 use crate::RecordFieldDef;
-use crate::ResolvedMacro;
 use crate::Semantic;
 use crate::Term;
 use crate::TypeAliasDef;
@@ -275,18 +274,7 @@ impl ToDef for ast::MacroCallExpr {
     type Def = DefineDef;
 
     fn to_def(sema: &Semantic<'_>, ast: InFile<&Self>) -> Option<Self::Def> {
-        let name = macro_exp::macro_name(ast.value)?;
-        let resolved = match sema.db.resolve_macro(ast.file_id, name.clone()) {
-            Some(ResolvedMacro::User(resolved)) => resolved,
-            Some(ResolvedMacro::BuiltIn(_)) => return None,
-            None => {
-                let name = name.with_arity(None);
-                match sema.db.resolve_macro(ast.file_id, name) {
-                    Some(ResolvedMacro::User(resolved)) => resolved,
-                    _ => return None,
-                }
-            }
-        };
+        let resolved = macro_exp::resolve_call(sema.db, ast.file_id, ast.value)?;
         let form_list = sema.db.file_form_list(resolved.file_id);
         let define = form_list[resolved.value].clone();
         let file = File {
@@ -8,54 +8,212 @@
  */
 
 use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::elp_base_db::FilePosition;
+use elp_ide_db::elp_base_db::FileRange;
+use elp_ide_db::elp_base_db::SourceDatabase;
+use elp_ide_db::EqwalizerDatabase;
 use elp_ide_db::RootDatabase;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
 use elp_syntax::TextRange;
+use hir::Semantic;
 
+use crate::handlers::references;
 use crate::runnables::runnables;
 use crate::runnables::Runnable;
+use crate::test_results::TestResults;
+use crate::test_results::TestStatus;
 
 // Feature: Annotations
 //
-// Provides user with annotations above items (e.g. for running tests)
+// Provides user with annotations above items (e.g. for running tests,
+// or the number of references to a function)
 //
-#[derive(Debug)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnnotationConfig {
+    pub runnables: bool,
+    pub references: bool,
+    pub eqwalizer_status: bool,
+    /// The results of the last test run, to annotate the runnables with
+    /// their status.
+    pub test_results: Option<TestResults>,
+}
+
+/// Which annotations to resolve when computing them. The payload of an
+/// annotation, e.g. the references to a function, can be costly to
+/// compute, so it can be left out and computed on demand, with
+/// `resolve_annotation`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationResolveStrategy {
+    /// No annotation should be resolved.
+    None,
+    /// All annotations should be resolved.
+    All,
+}
+
+#[derive(Debug, Clone)]
 pub struct Annotation {
     pub range: TextRange,
     pub kind: AnnotationKind,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum AnnotationKind {
     Runnable(Runnable),
+    /// The status of the runnable at the same range in the last test run
+    TestStatus(TestStatus),
+    /// The references to the function defined at `position`, `None`
+    /// until resolved
+    References {
+        position: FilePosition,
+        data: Option<Vec<FileRange>>,
+    },
+    /// Whether eqWAlizer checks the module, `None` until resolved
+    EqwalizerStatus {
+        file_id: FileId,
+        data: Option<bool>,
+    },
 }
 
-pub(crate) fn annotations(db: &RootDatabase, file_id: FileId) -> Vec<Annotation> {
+pub(crate) fn annotations(
+    db: &RootDatabase,
+    file_id: FileId,
+    config: &AnnotationConfig,
+    resolve: AnnotationResolveStrategy,
+) -> Vec<Annotation> {
     let mut annotations = Vec::default();
 
-    for runnable in runnables(db, file_id) {
-        let range = runnable.nav.range();
-        annotations.push(Annotation {
-            range,
-            kind: AnnotationKind::Runnable(runnable),
-        });
+    if config.runnables {
+        for runnable in runnables(db, file_id) {
+            let range = runnable.nav.range();
+            if let Some(status) = config
+                .test_results
+                .as_ref()
+                .and_then(|results| results.status(&runnable))
+            {
+                annotations.push(Annotation {
+                    range,
+                    kind: AnnotationKind::TestStatus(status),
+                });
+            }
+            annotations.push(Annotation {
+                range,
+                kind: AnnotationKind::Runnable(runnable),
+            });
+        }
+    }
+
+    if config.eqwalizer_status {
+        let source_file = db.parse(file_id).tree();
+        if let Some(module) = source_file
+            .syntax()
+            .children()
+            .find_map(ast::ModuleAttribute::cast)
+        {
+            annotations.push(Annotation {
+                range: module.syntax().text_range(),
+                kind: AnnotationKind::EqwalizerStatus {
+                    file_id,
+                    data: None,
+                },
+            });
+        }
+    }
+
+    if config.references {
+        let sema = Semantic::new(db);
+        for (_, def) in sema.def_map(file_id).get_functions() {
+            if def.file.file_id != file_id {
+                continue;
+            }
+            let name = def.source(db).clauses().find_map(|clause| match clause {
+                ast::FunctionOrMacroClause::FunctionClause(clause) => clause.name(),
+                ast::FunctionOrMacroClause::MacroCallExpr(_) => None,
+            });
+            if let Some(name) = name {
+                let range = name.syntax().text_range();
+                annotations.push(Annotation {
+                    range,
+                    kind: AnnotationKind::References {
+                        position: FilePosition {
+                            file_id,
+                            offset: range.start(),
+                        },
+                        data: None,
+                    },
+                });
+            }
+        }
+    }
+
+    if resolve == AnnotationResolveStrategy::All {
+        annotations = annotations
+            .into_iter()
+            .map(|annotation| resolve_annotation(db, annotation))
+            .collect();
     }
     annotations
 }
 
+pub(crate) fn resolve_annotation(db: &RootDatabase, mut annotation: Annotation) -> Annotation {
+    match &mut annotation.kind {
+        AnnotationKind::Runnable(_) | AnnotationKind::TestStatus(_) => {}
+        AnnotationKind::References { position, data } => {
+            let mut refs: Vec<FileRange> = references::find_all_refs(&Semantic::new(db), *position)
+                .unwrap_or_default()
+                .into_iter()
+                .flat_map(|res| res.references)
+                .flat_map(|(file_id, ranges)| {
+                    ranges
+                        .into_iter()
+                        .map(move |range| FileRange { file_id, range })
+                })
+                .collect();
+            refs.sort_by_key(|frange| (frange.file_id, frange.range.start()));
+            *data = Some(refs);
+        }
+        AnnotationKind::EqwalizerStatus { file_id, data } => {
+            *data = Some(db.is_eqwalizer_enabled(*file_id, false));
+        }
+    }
+    annotation
+}
+
 #[cfg(test)]
 mod tests {
     use elp_ide_db::elp_base_db::FileRange;
     use stdx::trim_indent;
 
     use crate::fixture;
+    use crate::AnnotationConfig;
     use crate::AnnotationKind;
+    use crate::AnnotationResolveStrategy;
+    use crate::TestResults;
+    use crate::TestStatus;
 
     #[track_caller]
     fn check(fixture: &str) {
+        let config = AnnotationConfig {
+            runnables: true,
+            ..Default::default()
+        };
+        check_with_config(config, AnnotationResolveStrategy::All, fixture)
+    }
+
+    #[track_caller]
+    fn check_with_config(
+        config: AnnotationConfig,
+        resolve: AnnotationResolveStrategy,
+        fixture: &str,
+    ) {
         let (analysis, pos, mut annotations) = fixture::annotations(trim_indent(fixture).as_str());
-        let actual_annotations = analysis.annotations(pos.file_id).unwrap();
+        let actual_annotations = analysis.annotations(pos.file_id, &config, resolve).unwrap();
         let mut actual = Vec::new();
         for annotation in actual_annotations {
+            let frange = FileRange {
+                file_id: pos.file_id,
+                range: annotation.range,
+            };
             match annotation.kind {
                 AnnotationKind::Runnable(runnable) => {
                     let file_id = runnable.nav.file_id;
@@ -63,6 +221,29 @@ mod tests {
                     let text = runnable.nav.name;
                     actual.push((FileRange { file_id, range }, text.to_string()));
                 }
+                AnnotationKind::TestStatus(status) => {
+                    let text = match status {
+                        TestStatus::Passed => "passed",
+                        TestStatus::Failed { .. } => "failed",
+                        TestStatus::Skipped { .. } => "skipped",
+                    };
+                    actual.push((frange, text.to_string()));
+                }
+                AnnotationKind::References { data, .. } => {
+                    let text = match data {
+                        Some(refs) => format!("{} references", refs.len()),
+                        None => "references".to_string(),
+                    };
+                    actual.push((frange, text));
+                }
+                AnnotationKind::EqwalizerStatus { data, .. } => {
+                    let text = match data {
+                        Some(true) => "eqwalizer: checked",
+                        Some(false) => "eqwalizer: not checked",
+                        None => "eqwalizer",
+                    };
+                    actual.push((frange, text.to_string()));
+                }
             }
         }
         let cmp = |(frange, text): &(FileRange, String)| {
@@ -98,4 +279,77 @@ main() ->
             "#,
         );
     }
+
+    #[test]
+    fn annotations_references_resolved() {
+        check_with_config(
+            AnnotationConfig {
+                references: true,
+                eqwalizer_status: true,
+                ..Default::default()
+            },
+            AnnotationResolveStrategy::All,
+            r#"
+   ~
+   -module(main).
+%% ^^^^^^^^^^^^^^ eqwalizer: not checked
+   main() ->
+%% ^^^^ 2 references
+     foo(), foo().
+   foo() -> main(), main().
+%% ^^^ 2 references
+            "#,
+        );
+    }
+
+    #[test]
+    fn annotations_unresolved() {
+        check_with_config(
+            AnnotationConfig {
+                runnables: true,
+                references: true,
+                eqwalizer_status: true,
+                test_results: None,
+            },
+            AnnotationResolveStrategy::None,
+            r#"
+   ~
+   -module(main).
+%% ^^^^^^^^^^^^^^ eqwalizer
+   main() -> ok.
+%% ^^^^ references
+            "#,
+        );
+    }
+
+    #[test]
+    fn annotations_test_results() {
+        check_with_config(
+            AnnotationConfig {
+                runnables: true,
+                test_results: Some(TestResults::parse(
+                    "=case main_SUITE:a\n=result ok\n=case main_SUITE:b\n=result failed: oops\n",
+                )),
+                ..Default::default()
+            },
+            AnnotationResolveStrategy::All,
+            r#"
+//- /my_app/test/main_SUITE.erl
+   ~
+   -module(main_SUITE).
+%% ^^^^^^^^^^^^^^^^^^^^ main_SUITE
+%% ^^^^^^^^^^^^^^^^^^^^ failed
+   -export([all/0, a/1, b/1, c/1]).
+   all() -> [a, b, c].
+   a(_Config) -> ok.
+%% ^ a/1
+%% ^ passed
+   b(_Config) -> ok.
+%% ^ b/1
+%% ^ failed
+   c(_Config) -> ok.
+%% ^ c/1
+            "#,
+        );
+    }
 }
@@ -0,0 +1,292 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Apply a single named assist at many positions in one run, so that
+//! mechanical migrations can be scripted.
+//!
+//! Each application is resolved against the result of the previous
+//! ones. Positions are processed from the end of each file backwards,
+//! and the remaining positions are moved along with every edit, so
+//! they can all be given relative to the original text.
+
+use std::sync::Arc;
+
+use elp_ide_assists::AssistConfig;
+use elp_ide_assists::AssistResolveStrategy;
+use elp_ide_db::elp_base_db::Change;
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::elp_base_db::FilePosition;
+use elp_ide_db::elp_base_db::FileRange;
+use elp_ide_db::source_change::FileSystemEdit;
+use elp_ide_db::source_change::SourceChange;
+use elp_syntax::TextRange;
+use elp_syntax::TextSize;
+use fxhash::FxHashMap;
+use text_edit::TextEdit;
+
+use crate::Analysis;
+use crate::AnalysisHost;
+use crate::Cancellable;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssistOutcome {
+    Applied,
+    /// The assist is not offered at this position
+    NotApplicable,
+    /// An earlier application of the assist rewrote the code at this
+    /// position
+    Invalidated,
+}
+
+#[derive(Debug, Default)]
+pub struct AppliedAssists {
+    /// The outcome for each requested position, in the order given
+    pub outcomes: Vec<(FilePosition, AssistOutcome)>,
+    /// The original and final text of each changed file
+    pub changed_files: FxHashMap<FileId, (Arc<String>, Arc<String>)>,
+    /// The files created, deleted or moved by the applications, in
+    /// order, to be applied after the text edits
+    pub file_system_edits: Vec<FileSystemEdit>,
+}
+
+impl Analysis {
+    /// Resolve the assist with the given id at a position, if it is
+    /// offered there.
+    pub fn resolve_assist(
+        &self,
+        config: &AssistConfig,
+        assist_id: &str,
+        position: FilePosition,
+    ) -> Cancellable<Option<SourceChange>> {
+        self.with_db(|db| {
+            let frange = FileRange {
+                file_id: position.file_id,
+                range: TextRange::empty(position.offset),
+            };
+            elp_ide_assists::assists(db, config, AssistResolveStrategy::All, frange, &[], None)
+                .into_iter()
+                .find(|assist| assist.id.0 == assist_id)
+                .and_then(|assist| assist.source_change)
+        })
+    }
+}
+
+/// Apply the assist with the given id at each of `positions`, updating
+/// the database as we go.
+///
+/// File system edits are collected rather than applied, so created
+/// files are not seen by later applications, and positions in a
+/// deleted file are invalidated.
+pub fn apply_assist(
+    host: &mut AnalysisHost,
+    config: &AssistConfig,
+    assist_id: &str,
+    positions: &[FilePosition],
+) -> Cancellable<AppliedAssists> {
+    let mut res = AppliedAssists {
+        outcomes: positions
+            .iter()
+            .map(|position| (*position, AssistOutcome::NotApplicable))
+            .collect(),
+        ..AppliedAssists::default()
+    };
+    let mut current: Vec<Option<FilePosition>> = positions.iter().copied().map(Some).collect();
+    let mut order: Vec<usize> = (0..positions.len()).collect();
+    order.sort_by_key(|&idx| {
+        (
+            positions[idx].file_id,
+            std::cmp::Reverse(positions[idx].offset),
+        )
+    });
+
+    for idx in order {
+        let position = match current[idx] {
+            Some(position) => position,
+            None => {
+                res.outcomes[idx].1 = AssistOutcome::Invalidated;
+                continue;
+            }
+        };
+        let analysis = host.analysis();
+        let change = match analysis.resolve_assist(config, assist_id, position)? {
+            Some(change) => change,
+            None => continue,
+        };
+        let mut files_changed = Vec::new();
+        for (file_id, edit) in &change.source_file_edits {
+            let before = analysis.file_text(*file_id)?;
+            let mut after = before.to_string();
+            edit.apply(&mut after);
+            let after = Arc::new(after);
+            res.changed_files
+                .entry(*file_id)
+                .and_modify(|(_, text)| *text = after.clone())
+                .or_insert_with(|| (before, after.clone()));
+            files_changed.push((*file_id, Some(after)));
+
+            for position in current.iter_mut() {
+                if let Some(pos) = *position {
+                    if pos.file_id == *file_id {
+                        *position = map_offset(edit, pos.offset).map(|offset| FilePosition {
+                            file_id: pos.file_id,
+                            offset,
+                        });
+                    }
+                }
+            }
+        }
+        for edit in &change.file_system_edits {
+            if let FileSystemEdit::DeleteFile { file } = edit {
+                for position in current.iter_mut() {
+                    if matches!(position, Some(pos) if pos.file_id == *file) {
+                        *position = None;
+                    }
+                }
+            }
+        }
+        res.file_system_edits.extend(change.file_system_edits);
+        drop(analysis);
+        host.apply_change(Change {
+            roots: None,
+            files_changed,
+            app_structure: None,
+        });
+        res.outcomes[idx].1 = AssistOutcome::Applied;
+    }
+    Ok(res)
+}
+
+/// Where `offset` ends up after applying `edit`, or `None` if the
+/// text around it was replaced.
+fn map_offset(edit: &TextEdit, offset: TextSize) -> Option<TextSize> {
+    let mut res = offset;
+    for indel in edit.iter() {
+        if indel.delete.start() >= offset {
+            break;
+        }
+        if offset < indel.delete.end() {
+            return None;
+        }
+        res += TextSize::of(&indel.insert);
+        res -= indel.delete.len();
+    }
+    Some(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use elp_ide_db::elp_base_db::fixture::WithFixture;
+    use elp_ide_db::RootDatabase;
+    use expect_test::expect;
+
+    use super::*;
+
+    fn config() -> AssistConfig {
+        AssistConfig {
+            snippet_cap: None,
+            allowed: None,
+            experimental: false,
+            record_fields_alphabetical: false,
+            exports_alphabetical: false,
+        }
+    }
+
+    #[test]
+    fn applies_at_each_position() {
+        let fixture = r#"
+-module(main).
+foo(A) -> A.
+bar(B, C) -> {B, C}.
+baz() -> ok.
+"#;
+        let (db, file_id) = RootDatabase::with_single_file(fixture);
+        let mut host = AnalysisHost { db };
+        let text = host.analysis().file_text(file_id).unwrap();
+        let position = |needle: &str| FilePosition {
+            file_id,
+            offset: TextSize::from(text.find(needle).unwrap() as u32),
+        };
+        let positions = [position("foo"), position("bar"), position("-module")];
+        let res = apply_assist(&mut host, &config(), "add_spec", &positions).unwrap();
+        assert_eq!(
+            res.outcomes.iter().map(|(_, o)| *o).collect::<Vec<_>>(),
+            vec![
+                AssistOutcome::Applied,
+                AssistOutcome::Applied,
+                AssistOutcome::NotApplicable
+            ]
+        );
+        let (before, after) = &res.changed_files[&file_id];
+        assert_eq!(before, &text);
+        expect![[r#"
+            -module(main).
+            -spec foo(A :: term()) -> term().
+            foo(A) -> A.
+            -spec bar(B :: term(), C :: term()) -> term().
+            bar(B, C) -> {B, C}.
+            baz() -> ok.
+        "#]]
+        .assert_eq(after);
+        assert_eq!(&host.analysis().file_text(file_id).unwrap(), after);
+    }
+
+    #[test]
+    fn collects_file_system_edits() {
+        let fixture = r#"
+-module(main).
+-export([foo/0]).
+foo() -> ok.
+"#;
+        let (db, file_id) = RootDatabase::with_single_file(fixture);
+        let mut host = AnalysisHost { db };
+        let text = host.analysis().file_text(file_id).unwrap();
+        let position = FilePosition {
+            file_id,
+            offset: TextSize::from(text.rfind("foo").unwrap() as u32),
+        };
+        let res = apply_assist(&mut host, &config(), "create_test", &[position]).unwrap();
+        assert_eq!(res.outcomes, vec![(position, AssistOutcome::Applied)]);
+        assert!(res.changed_files.is_empty());
+        match &res.file_system_edits[..] {
+            [FileSystemEdit::CreateFile {
+                dst,
+                initial_contents,
+            }] => {
+                assert_eq!(dst.anchor, file_id);
+                assert!(dst.path.ends_with("test/main_SUITE.erl"));
+                assert!(initial_contents.starts_with("-module(main_SUITE)."));
+            }
+            edits => panic!("unexpected file system edits: {edits:?}"),
+        }
+    }
+
+    #[test]
+    fn map_offset_through_edit() {
+        let mut builder = TextEdit::builder();
+        builder.insert(TextSize::from(2), "abc".to_string());
+        builder.replace(
+            TextRange::new(TextSize::from(5), TextSize::from(8)),
+            "x".to_string(),
+        );
+        let edit = builder.finish();
+        assert_eq!(
+            map_offset(&edit, TextSize::from(1)),
+            Some(TextSize::from(1))
+        );
+        assert_eq!(
+            map_offset(&edit, TextSize::from(4)),
+            Some(TextSize::from(7))
+        );
+        assert_eq!(map_offset(&edit, TextSize::from(6)), None);
+        assert_eq!(
+            map_offset(&edit, TextSize::from(9)),
+            Some(TextSize::from(10))
+        );
+    }
+}
@@ -7,16 +7,27 @@
  * of this source tree.
  */
 
+use elp_ide_db::elp_base_db::FileId;
 use elp_ide_db::elp_base_db::FilePosition;
+use elp_ide_db::find_best_token;
 use elp_ide_db::FxIndexMap;
 use elp_ide_db::RootDatabase;
+use elp_ide_db::SymbolClass;
 use elp_syntax::algo;
 use elp_syntax::ast::{self};
 use elp_syntax::AstNode;
 use elp_syntax::SmolStr;
+use elp_syntax::SyntaxNode;
 use elp_syntax::TextRange;
+use fxhash::FxHashMap;
+use hir::Body;
+use hir::CallDef;
+use hir::CallTarget;
 use hir::Expr;
+use hir::ExprId;
+use hir::FunctionDef;
 use hir::InFile;
+use hir::Literal;
 use hir::Semantic;
 
 use crate::handlers::goto_definition;
@@ -38,11 +49,22 @@ pub(crate) fn call_hierarchy_prepare(
     goto_definition::goto_definition(db, position)
 }
 
-pub(crate) fn incoming_calls(db: &RootDatabase, position: FilePosition) -> Option<Vec<CallItem>> {
+/// The calls to the function at `position`. With `include_dynamic`,
+/// the `apply/2,3` calls and the fun captures naming it with literal
+/// atoms are included too.
+pub(crate) fn incoming_calls(
+    db: &RootDatabase,
+    position: FilePosition,
+    include_dynamic: bool,
+) -> Option<Vec<CallItem>> {
     let sema = Semantic::new(db);
     let mut calls = CallLocations::default();
-    let search_result = references::find_all_refs(&sema, position);
-    let references = search_result?.first()?.references.clone();
+    let references = if include_dynamic {
+        all_references(&sema, position)?
+    } else {
+        let search_result = references::find_all_refs(&sema, position);
+        search_result?.first()?.references.clone()
+    };
 
     for (file_id, ranges) in references {
         let source_file = sema.parse(file_id);
@@ -50,30 +72,101 @@ pub(crate) fn incoming_calls(db: &RootDatabase, position: FilePosition) -> Optio
         let form_list = sema.db.file_form_list(file_id);
 
         for range in ranges {
-            if let Some(call) = algo::find_node_at_offset::<ast::Call>(syntax, range.start()) {
-                let enclosing_function_id = sema.find_enclosing_function(file_id, call.syntax())?;
-                let enclosing_function_name = &form_list[enclosing_function_id].name;
-                let def_map = sema.def_map(file_id);
-                let enclosing_function_def = def_map.get_function(enclosing_function_name)?;
-                let mut enclosing_function_nav = enclosing_function_def.to_nav(db);
-                if file_id != position.file_id {
-                    if let Some(module_name) = sema.module_name(file_id) {
-                        enclosing_function_nav.name = SmolStr::new(format!(
-                            "{}:{}",
-                            module_name.as_str(),
-                            enclosing_function_nav.name
-                        ))
-                    }
+            let call = match call_site(&sema, file_id, syntax, range) {
+                Some((CallKind::Static, call)) => call,
+                Some((CallKind::Dynamic, call)) if include_dynamic => call,
+                _ => continue,
+            };
+            let enclosing_function_id = sema.find_enclosing_function(file_id, &call)?;
+            let enclosing_function_name = &form_list[enclosing_function_id].name;
+            let def_map = sema.def_map(file_id);
+            let enclosing_function_def = def_map.get_function(enclosing_function_name)?;
+            let mut enclosing_function_nav = enclosing_function_def.to_nav(db);
+            if file_id != position.file_id {
+                if let Some(module_name) = sema.module_name(file_id) {
+                    enclosing_function_nav.name = SmolStr::new(format!(
+                        "{}:{}",
+                        module_name.as_str(),
+                        enclosing_function_nav.name
+                    ))
                 }
-                calls.add(enclosing_function_nav, range);
             }
+            calls.add(enclosing_function_nav, range);
         }
     }
 
     Some(calls.into_items())
 }
 
-pub(crate) fn outgoing_calls(db: &RootDatabase, position: FilePosition) -> Option<Vec<CallItem>> {
+/// All the references to the symbol at `position`, including the
+/// indirect ones, such as the function names in `apply/2,3` calls.
+fn all_references(
+    sema: &Semantic,
+    position: FilePosition,
+) -> Option<FxHashMap<FileId, Vec<TextRange>>> {
+    let token = find_best_token(sema, position)?;
+    let def = match SymbolClass::classify(sema, token)? {
+        SymbolClass::Definition(def) => def,
+        SymbolClass::Reference { refs, typ: _ } => refs.into_iter().next()?,
+    };
+    let usages = def.usages(sema).all();
+    Some(
+        usages
+            .iter()
+            .map(|(file_id, refs)| {
+                let ranges = refs.iter().map(|name| name.syntax().text_range());
+                (file_id, ranges.collect())
+            })
+            .collect(),
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CallKind {
+    /// The function is called by name
+    Static,
+    /// The function is captured in a fun, or called through `apply/2,3`
+    Dynamic,
+}
+
+/// How the reference at `range` calls the function, and the syntax
+/// node of the call or capture. Other references, such as the ones in
+/// attributes, are not calls.
+fn call_site(
+    sema: &Semantic,
+    file_id: FileId,
+    syntax: &SyntaxNode,
+    range: TextRange,
+) -> Option<(CallKind, SyntaxNode)> {
+    let token = syntax.token_at_offset(range.start()).right_biased()?;
+    for node in token.parent_ancestors() {
+        if ast::InternalFun::can_cast(node.kind()) || ast::ExternalFun::can_cast(node.kind()) {
+            return Some((CallKind::Dynamic, node));
+        }
+        if let Some(call) = ast::Call::cast(node.clone()) {
+            if call.expr()?.syntax().text_range().contains_range(range) {
+                return Some((CallKind::Static, node));
+            }
+            let args = call.args()?;
+            if args.syntax().text_range().contains_range(range)
+                && sema.to_def(InFile::new(file_id, &args)).is_some()
+            {
+                return Some((CallKind::Dynamic, node));
+            }
+            return None;
+        }
+    }
+    None
+}
+
+/// The calls made by the function at `position`. With
+/// `include_dynamic`, the functions called through `apply/2,3` with
+/// literal atoms, and the ones captured in funs, are included too.
+pub(crate) fn outgoing_calls(
+    db: &RootDatabase,
+    position: FilePosition,
+    include_dynamic: bool,
+) -> Option<Vec<CallItem>> {
     let sema = Semantic::new(db);
     let mut calls = CallLocations::default();
     let file_id = position.file_id;
@@ -83,34 +176,57 @@ pub(crate) fn outgoing_calls(db: &RootDatabase, position: FilePosition) -> Optio
         let function_id_idx = sema.find_enclosing_function(file_id, function.syntax())?;
         let function_id = InFile::new(file_id, function_id_idx);
         let function_body = sema.to_function_body(function_id);
+        let body_map = function_body.get_body_map(db);
         sema.fold_function(
             function_id,
             (),
             &mut |acc, _clause_id, ctx| {
-                match &ctx.expr {
-                    Expr::Call { target, args } => {
-                        let arity = args.len() as u32;
-                        let body = &function_body.body();
-                        if let Some(call_def) = target.resolve_call(arity, &sema, file_id, body) {
-                            let mut nav = call_def.to_nav(db);
-                            if let Some(label) = target.label(arity, &sema, &body) {
-                                nav.name = label
+                let body = &function_body.body();
+                let node = body_map
+                    .expr(ctx.expr_id)
+                    .and_then(|expr| expr.to_node(&source_file));
+                match (&ctx.expr, node) {
+                    (Expr::Call { target, args }, Some(node)) => {
+                        let call = algo::find_node_at_offset::<ast::Call>(
+                            node.syntax(),
+                            node.syntax().text_range().start(),
+                        );
+                        if let Some(call) = call {
+                            let arity = args.len() as u32;
+                            if let Some(call_def) = target.resolve_call(arity, &sema, file_id, body)
+                            {
+                                let mut nav = call_def.to_nav(db);
+                                if let Some(label) = target.label(arity, &sema, body) {
+                                    nav.name = label
+                                }
+                                if let Some(expr) = call.expr() {
+                                    calls.add(nav, expr.syntax().text_range());
+                                }
                             }
-                            if let Some(expr) = &function_body.get_body_map(db).expr(ctx.expr_id) {
-                                if let Some(node) = expr.to_node(&source_file) {
-                                    if let Some(call) = algo::find_node_at_offset::<ast::Call>(
-                                        &node.syntax(),
-                                        node.syntax().text_range().start(),
-                                    ) {
-                                        if let Some(expr) = call.expr() {
-                                            let range = expr.syntax().text_range();
-                                            calls.add(nav.clone(), range);
-                                        }
-                                    }
+                            if include_dynamic {
+                                if let Some((nav, range)) =
+                                    apply_call(&sema, file_id, body, args, &call)
+                                {
+                                    calls.add(nav, range);
                                 }
                             }
                         }
                     }
+                    (Expr::CaptureFun { target, arity }, Some(node)) if include_dynamic => {
+                        if let Some((def, range)) = capture(&sema, file_id, &node) {
+                            let mut nav = def.to_nav(db);
+                            let arity = match body[*arity] {
+                                Expr::Literal(Literal::Integer(int)) => int.try_into().ok(),
+                                _ => None,
+                            };
+                            if let Some(label) =
+                                arity.and_then(|arity| target.label(arity, &sema, body))
+                            {
+                                nav.name = label
+                            }
+                            calls.add(nav, range);
+                        }
+                    }
                     _ => (),
                 }
                 acc
@@ -121,6 +237,60 @@ pub(crate) fn outgoing_calls(db: &RootDatabase, position: FilePosition) -> Optio
     Some(calls.into_items())
 }
 
+/// The function called by an `apply/2,3` call with literal atoms, and
+/// the range of its name in the call.
+fn apply_call(
+    sema: &Semantic,
+    file_id: FileId,
+    body: &Body,
+    args: &[ExprId],
+    call: &ast::Call,
+) -> Option<(NavigationTarget, TextRange)> {
+    let call_args = call.args()?;
+    let def = match sema.to_def(InFile::new(file_id, &call_args))? {
+        CallDef::Function(def) => def,
+        _ => return None,
+    };
+    let mut nav = def.to_nav(sema.db);
+    let (target, name_idx) = match args {
+        [name, _] => (CallTarget::Local { name: *name }, 0),
+        [module, name, _] => (
+            CallTarget::Remote {
+                module: *module,
+                name: *name,
+            },
+            1,
+        ),
+        _ => return None,
+    };
+    let arity = body[*args.last()?].list_length()? as u32;
+    if let Some(label) = target.label(arity, sema, body) {
+        nav.name = label;
+    }
+    let name = call_args.args().nth(name_idx)?;
+    Some((nav, name.syntax().text_range()))
+}
+
+/// The function captured by a `fun f/N` or `fun m:f/N` expression, and
+/// the range naming it.
+fn capture(sema: &Semantic, file_id: FileId, node: &ast::Expr) -> Option<(FunctionDef, TextRange)> {
+    match node {
+        ast::Expr::ExprMax(ast::ExprMax::InternalFun(fun)) => {
+            let def = sema.to_def(InFile::new(file_id, fun))?;
+            Some((def, fun.fun()?.syntax().text_range()))
+        }
+        ast::Expr::ExprMax(ast::ExprMax::ExternalFun(fun)) => {
+            let def = sema.to_def(InFile::new(file_id, fun))?;
+            let range = TextRange::new(
+                fun.module()?.syntax().text_range().start(),
+                fun.fun()?.syntax().text_range().end(),
+            );
+            Some((def, range))
+        }
+        _ => None,
+    }
+}
+
 #[derive(Default)]
 struct CallLocations {
     funcs: FxIndexMap<NavigationTarget, Vec<TextRange>>,
@@ -143,6 +313,7 @@ impl CallLocations {
 mod tests {
 
     use crate::tests::check_call_hierarchy;
+    use crate::tests::check_call_hierarchy_dynamic;
 
     #[test]
     fn test_call_hierarchy_on_ref() {
@@ -392,4 +563,69 @@ mod tests {
     "#,
         );
     }
+
+    #[test]
+    fn test_call_hierarchy_dynamic_incoming() {
+        check_call_hierarchy_dynamic(
+            r#"
+ //- /src/a.erl
+    -module(a).
+    caller() ->
+ %% ^^^^^^ from: a:caller/0
+      erlang:apply(b, callee, []),
+ %%                   ^^^^^^ from_range: a:caller/0
+      lists:map(fun b:callee/0, []).
+ %%                   ^^^^^^ from_range: a:caller/0
+ //- /src/b.erl
+    -module(b).
+    -export([callee/0]).
+    cal~lee() -> ok.
+    "#,
+            r#"
+ //- /src/a.erl
+    -module(a).
+    ca~ller() ->
+      erlang:apply(b, callee, []),
+ %%                   ^^^^^^ from_range: b:callee/0
+      lists:map(fun b:callee/0, []).
+ %%                 ^^^^^^^^ from_range: b:callee/0
+ //- /src/b.erl
+    -module(b).
+    -export([callee/0]).
+    callee() -> ok.
+ %% ^^^^^^ to: b:callee/0
+    "#,
+        );
+    }
+
+    #[test]
+    fn test_call_hierarchy_dynamic_excluded() {
+        check_call_hierarchy(
+            r#"
+    -module(main).
+    call~ee() -> ok.
+ %% ^^^^^^
+    caller() ->
+      apply(main, callee, []),
+      F = fun callee/0,
+      F().
+    "#,
+            r#"
+    -module(main).
+    call~ee() -> ok.
+    caller() ->
+      apply(main, callee, []),
+      F = fun callee/0,
+      F().
+    "#,
+            r#"
+    -module(main).
+    callee() -> ok.
+    call~er() ->
+      apply(main, callee, []),
+      F = fun callee/0,
+      F().
+    "#,
+        );
+    }
 }
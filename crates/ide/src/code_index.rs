@@ -0,0 +1,214 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! The definitions, references, call edges and include edges of a file,
+//! as exported by `elp glean` to code search backends.
+
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::ReferenceType;
+use elp_ide_db::RootDatabase;
+use elp_ide_db::SymbolClass;
+use elp_ide_db::SymbolDefinition;
+use elp_syntax::AstNode;
+use elp_syntax::NodeOrToken;
+use elp_syntax::SyntaxKind;
+use elp_syntax::TextRange;
+use hir::db::MinDefDatabase;
+use hir::InFile;
+use hir::Semantic;
+
+use crate::navigation_target::ToNav;
+use crate::NavigationTarget;
+
+#[derive(Debug, Clone, Default)]
+pub struct CodeIndex {
+    /// Functions, types, records, macros and callbacks defined in the file
+    pub defs: Vec<NavigationTarget>,
+    /// References from the file to definitions anywhere in the project.
+    /// Variables are not included.
+    pub refs: Vec<IndexedReference>,
+    /// References to functions made from within the body of a function
+    pub calls: Vec<IndexedCall>,
+    /// Files included by the file
+    pub includes: Vec<FileId>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexedReference {
+    pub range: TextRange,
+    pub target: NavigationTarget,
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexedCall {
+    pub range: TextRange,
+    pub caller: NavigationTarget,
+    pub callee: NavigationTarget,
+}
+
+pub(crate) fn code_index(db: &RootDatabase, file_id: FileId) -> CodeIndex {
+    let sema = Semantic::new(db);
+    let def_map = sema.def_map(file_id);
+    let mut res = CodeIndex::default();
+
+    for def in def_map.get_functions().values() {
+        if def.file.file_id == file_id {
+            res.defs.push(def.to_nav(db));
+        }
+    }
+    for def in def_map.get_types().values() {
+        if def.file.file_id == file_id {
+            res.defs.push(def.to_nav(db));
+        }
+    }
+    for def in def_map.get_records().values() {
+        if def.file.file_id == file_id {
+            res.defs.push(def.to_nav(db));
+        }
+    }
+    for def in def_map.get_macros().values() {
+        if def.file.file_id == file_id {
+            res.defs.push(def.to_nav(db));
+        }
+    }
+    for def in def_map.get_callbacks().values() {
+        if def.file.file_id == file_id {
+            res.defs.push(def.to_nav(db));
+        }
+    }
+    res.defs.sort_by_key(|nav| nav.full_range.start());
+
+    let source = sema.parse(file_id).value;
+    for token in source.syntax().descendants_with_tokens() {
+        let token = match token {
+            NodeOrToken::Token(token)
+                if matches!(token.kind(), SyntaxKind::ATOM | SyntaxKind::VAR) =>
+            {
+                token
+            }
+            _ => continue,
+        };
+        let (refs, typ) = match SymbolClass::classify(&sema, InFile::new(file_id, token.clone())) {
+            Some(SymbolClass::Reference { refs, typ }) => (refs, typ),
+            _ => continue,
+        };
+        let range = token.text_range();
+        for def in refs.into_iter() {
+            match &def {
+                SymbolDefinition::Var(_) => continue,
+                SymbolDefinition::Function(_) if typ == ReferenceType::Direct => {
+                    let caller = token.parent().and_then(|parent| {
+                        let function_id = sema.find_enclosing_function(file_id, &parent)?;
+                        def_map
+                            .get_functions()
+                            .values()
+                            .find(|def| {
+                                def.file.file_id == file_id && def.function_id == function_id
+                            })
+                            .map(|def| def.to_nav(db))
+                    });
+                    if let Some(caller) = caller {
+                        res.calls.push(IndexedCall {
+                            range,
+                            caller,
+                            callee: def.to_nav(db),
+                        });
+                    }
+                }
+                _ => {}
+            }
+            res.refs.push(IndexedReference {
+                range,
+                target: def.to_nav(db),
+            });
+        }
+    }
+
+    let form_list = db.file_form_list(file_id);
+    res.includes = form_list
+        .includes()
+        .filter_map(|(idx, _)| db.resolve_include(InFile::new(file_id, idx)))
+        .collect();
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use expect_test::Expect;
+    use stdx::format_to;
+
+    use crate::fixture;
+
+    #[track_caller]
+    fn check(fixture: &str, expect: Expect) {
+        let (analysis, position) = fixture::position(fixture);
+        let text = analysis.file_text(position.file_id).unwrap();
+        let index = analysis.code_index(position.file_id).unwrap();
+        let mut actual = String::new();
+        for def in &index.defs {
+            format_to!(actual, "def {:?} {}\n", def.kind, def.name);
+        }
+        for reference in &index.refs {
+            format_to!(
+                actual,
+                "ref {} -> {:?} {}\n",
+                &text[reference.range],
+                reference.target.kind,
+                reference.target.name
+            );
+        }
+        for call in &index.calls {
+            format_to!(
+                actual,
+                "call {} -> {}\n",
+                call.caller.name,
+                call.callee.name
+            );
+        }
+        format_to!(actual, "includes {}\n", index.includes.len());
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn defs_refs_and_calls() {
+        check(
+            r#"
+//- /src/main.erl
+~-module(main).
+-include("main.hrl").
+-export([foo/0]).
+-record(r, {}).
+foo() -> bar(?X, #r{}).
+bar(A, _) -> other:baz(A).
+//- /src/main.hrl
+-define(X, 1).
+//- /src/other.erl
+-module(other).
+-export([baz/1]).
+baz(A) -> A.
+"#,
+            expect![[r#"
+                def Record r
+                def Function foo/0
+                def Function bar/2
+                ref foo -> Function foo/0
+                ref bar -> Function bar/2
+                ref X -> Define X
+                ref r -> Record r
+                ref other -> Module other
+                ref baz -> Function baz/1
+                call foo/0 -> bar/2
+                call bar/2 -> baz/1
+                includes 1
+            "#]],
+        );
+    }
+}
@@ -0,0 +1,248 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::elp_base_db::FileRange;
+use elp_ide_db::RootDatabase;
+use elp_syntax::TextRange;
+use fxhash::FxHashMap;
+use hir::Name;
+use hir::Semantic;
+
+use crate::common_test;
+use crate::common_test::GroupName;
+use crate::runnables::runnables;
+use crate::runnables::Runnable;
+use crate::runnables::RunnableKind;
+
+// Feature: Code Lens
+//
+// Shows actionable items above tests: run or debug the test, or jump to
+// the definition of the Common Test group it is run in.
+//
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodeLensConfig {
+    pub run: bool,
+    pub debug: bool,
+    pub group_link: bool,
+}
+
+impl CodeLensConfig {
+    pub fn any(&self) -> bool {
+        self.run || self.debug || self.group_link
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CodeLens {
+    pub range: TextRange,
+    pub kind: CodeLensKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum CodeLensKind {
+    Run(Runnable),
+    Debug(Runnable),
+    /// Link from a testcase to the group it is run in, as defined in
+    /// `groups/0`
+    GroupLink {
+        name: Name,
+        target: FileRange,
+    },
+}
+
+pub(crate) fn code_lenses(
+    db: &RootDatabase,
+    file_id: FileId,
+    config: &CodeLensConfig,
+) -> Vec<CodeLens> {
+    let mut res = Vec::new();
+    if !config.any() {
+        return res;
+    }
+
+    let group_ranges = if config.group_link {
+        common_test::group_name_ranges(&Semantic::new(db), file_id)
+    } else {
+        FxHashMap::default()
+    };
+
+    for runnable in runnables(db, file_id) {
+        let range = runnable.nav.range();
+        if config.group_link {
+            if let RunnableKind::Test {
+                group: GroupName::Name(name),
+                ..
+            } = &runnable.kind
+            {
+                if let Some(group_range) = group_ranges.get(name) {
+                    res.push(CodeLens {
+                        range,
+                        kind: CodeLensKind::GroupLink {
+                            name: name.clone(),
+                            target: FileRange {
+                                file_id,
+                                range: *group_range,
+                            },
+                        },
+                    });
+                }
+            }
+        }
+        if config.run {
+            res.push(CodeLens {
+                range,
+                kind: CodeLensKind::Run(runnable.clone()),
+            });
+        }
+        if config.debug {
+            res.push(CodeLens {
+                range,
+                kind: CodeLensKind::Debug(runnable),
+            });
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use elp_ide_db::elp_base_db::FileRange;
+    use stdx::trim_indent;
+
+    use crate::fixture;
+    use crate::CodeLensConfig;
+    use crate::CodeLensKind;
+
+    #[track_caller]
+    fn check(config: CodeLensConfig, fixture: &str) {
+        let (analysis, pos, mut annotations) = fixture::annotations(trim_indent(fixture).as_str());
+        let lenses = analysis.code_lenses(pos.file_id, &config).unwrap();
+        let mut actual = Vec::new();
+        for lens in lenses {
+            let frange = FileRange {
+                file_id: pos.file_id,
+                range: lens.range,
+            };
+            match lens.kind {
+                CodeLensKind::Run(runnable) => actual.push((frange, runnable.run_title())),
+                CodeLensKind::Debug(runnable) => actual.push((frange, runnable.debug_title())),
+                CodeLensKind::GroupLink { name, target } => {
+                    actual.push((frange, format!("group {name}")));
+                    actual.push((target, format!("target {name}")));
+                }
+            }
+        }
+        // Remove all non-ascii character to avoid repeating Unicode variation selectors in every test
+        let mut actual: Vec<_> = actual
+            .into_iter()
+            .map(|(frange, text)| {
+                let text = text.replace(|c: char| !c.is_ascii(), "");
+                (frange, text.trim().to_string())
+            })
+            .collect();
+        let cmp = |(frange, text): &(FileRange, String)| {
+            (frange.file_id, frange.range.start(), text.clone())
+        };
+        actual.sort_by_key(cmp);
+        annotations.sort_by_key(cmp);
+        assert_eq!(actual, annotations);
+    }
+
+    const ALL: CodeLensConfig = CodeLensConfig {
+        run: true,
+        debug: true,
+        group_link: true,
+    };
+
+    #[test]
+    fn code_lenses_not_a_suite() {
+        check(
+            ALL,
+            r#"
+ //- /my_app/src/main.erl
+    ~
+    -module(main).
+    main() ->
+      ok.
+    "#,
+        );
+    }
+
+    #[test]
+    fn code_lenses_all() {
+        check(
+            ALL,
+            r#"
+ //- /my_app/test/my_common_test_SUITE.erl
+    ~
+    -module(my_common_test_SUITE).
+ %% ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ Run All Tests
+ %% ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ Debug
+    -export([all/0, groups/0]).
+    -export([a/1, c/1]).
+    all() -> [a, {group, gc1}].
+    groups() -> [{gc1, [], [c]}].
+ %%               ^^^ target gc1
+    a(_Config) ->
+ %% ^ Run Test
+ %% ^ Debug
+      ok.
+    c(_Config) ->
+ %% ^ Run Test (in gc1)
+ %% ^ Debug (in gc1)
+ %% ^ group gc1
+      ok.
+    "#,
+        );
+    }
+
+    #[test]
+    fn code_lenses_group_links_only() {
+        check(
+            CodeLensConfig {
+                run: false,
+                debug: false,
+                group_link: true,
+            },
+            r#"
+ //- /my_app/test/my_common_test_SUITE.erl
+    ~
+    -module(my_common_test_SUITE).
+    -export([all/0, groups/0]).
+    -export([a/1, c/1]).
+    all() -> [a, {group, gc1}].
+    groups() -> [{gc1, [], [c]}].
+ %%               ^^^ target gc1
+    a(_Config) ->
+      ok.
+    c(_Config) ->
+ %% ^ group gc1
+      ok.
+    "#,
+        );
+    }
+
+    #[test]
+    fn code_lenses_disabled() {
+        check(
+            CodeLensConfig::default(),
+            r#"
+ //- /my_app/test/my_common_test_SUITE.erl
+    ~
+    -module(my_common_test_SUITE).
+    -export([all/0]).
+    -export([a/1]).
+    all() -> [a].
+    a(_Config) ->
+      ok.
+    "#,
+        );
+    }
+}
@@ -0,0 +1,175 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! A codemod campaign read from a spec file, rather than configured in
+//! code: each call to a listed function is reported, with a fix
+//! replacing it, as `replace_call` does.
+//!
+//! The spec is TOML, or JSON if the file has a `.json` extension:
+//!
+//! ```toml
+//! [[replace_call]]
+//! function = "foo:fire_bombs/1"
+//! replacement = "use_ok"
+//!
+//! # Any arity
+//! [[replace_call]]
+//! function = "foo:identity"
+//! replacement = { use_call_arg = 0 }
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::bail;
+use anyhow::Result;
+use elp_ide_db::elp_base_db::FileId;
+use hir::Semantic;
+use serde::Deserialize;
+
+use crate::codemod_helpers::FunctionMatch;
+use crate::diagnostics::replace_call::replace_call_site;
+use crate::diagnostics::replace_call::Replacement;
+use crate::diagnostics::AdhocSemanticDiagnostics;
+use crate::diagnostics::Diagnostic;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CodemodSpec {
+    #[serde(default)]
+    replace_call: Vec<ReplaceCallSpec>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ReplaceCallSpec {
+    /// `module:function/arity`, `module:function` for any arity, or
+    /// `module` for any of its functions
+    function: String,
+    replacement: Replacement,
+}
+
+#[derive(Debug, Clone)]
+pub struct Codemod {
+    replace_calls: Vec<(FunctionMatch, Replacement)>,
+}
+
+impl Codemod {
+    pub fn load(path: &Path) -> Result<Codemod> {
+        let content = fs::read_to_string(path)?;
+        let is_json = path.extension().map_or(false, |ext| ext == "json");
+        Codemod::parse(&content, is_json)
+    }
+
+    pub fn parse(content: &str, is_json: bool) -> Result<Codemod> {
+        let spec: CodemodSpec = if is_json {
+            serde_json::from_str(content)?
+        } else {
+            toml::from_str(content)?
+        };
+        let replace_calls = spec
+            .replace_call
+            .into_iter()
+            .map(|rule| match FunctionMatch::parse(&rule.function) {
+                Some(function) => Ok((function, rule.replacement)),
+                None => bail!("Invalid function in codemod spec: '{}'", rule.function),
+            })
+            .collect::<Result<_>>()?;
+        Ok(Codemod { replace_calls })
+    }
+
+    /// The diagnostics of the campaign, to add to the
+    /// `adhoc_semantic_diagnostics` of a `DiagnosticsConfig`. They are
+    /// not experimental, as the campaign was asked for.
+    pub fn diagnostics(&self) -> impl AdhocSemanticDiagnostics + '_ {
+        move |acc: &mut Vec<Diagnostic>, sema: &Semantic, file_id: FileId, _ext: Option<&str>| {
+            let mut diags = Vec::new();
+            for (function, replacement) in &self.replace_calls {
+                replace_call_site(function, *replacement, &mut diags, sema, file_id);
+            }
+            acc.extend(diags.into_iter().map(|mut d| {
+                d.experimental = false;
+                d
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::check_fix_with_config;
+    use crate::DiagnosticsConfig;
+
+    #[test]
+    fn parse_spec() {
+        let codemod = Codemod::parse(
+            r#"
+[[replace_call]]
+function = "foo:fire_bombs/1"
+replacement = "use_ok"
+
+[[replace_call]]
+function = "foo:identity"
+replacement = { use_call_arg = 0 }
+"#,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            codemod.replace_calls,
+            vec![
+                (
+                    FunctionMatch::mfa("foo", "fire_bombs", 1),
+                    Replacement::UseOk
+                ),
+                (
+                    FunctionMatch::mf("foo", "identity"),
+                    Replacement::UseCallArg(0)
+                ),
+            ]
+        );
+        assert!(Codemod::parse(
+            "[[replace_call]]\nfunction = \"foo:bar/x\"\nreplacement = \"use_ok\"",
+            false
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn replace_call_from_json_spec() {
+        let codemod = Codemod::parse(
+            r#"{"replace_call": [{"function": "foo:fire_bombs/1", "replacement": "use_ok"}]}"#,
+            true,
+        )
+        .unwrap();
+        let diagnostics = codemod.diagnostics();
+        let mut config = DiagnosticsConfig::default();
+        config.disable_experimental = true;
+        config.adhoc_semantic_diagnostics.push(&diagnostics);
+        check_fix_with_config(
+            config,
+            r#"
+//- /src/main.erl
+-module(main).
+
+f(Config) ->
+    ~foo:fire_bombs(Config).
+//- /src/foo.erl
+-module(foo).
+"#,
+            r#"
+-module(main).
+
+f(Config) ->
+    ok.
+"#,
+        );
+    }
+}
@@ -251,6 +251,40 @@ impl FunctionMatch {
     pub fn m(m: &str) -> FunctionMatch {
         FunctionMatch::M { module: m.into() }
     }
+
+    /// Parse a match given as `module`, `module:function` or
+    /// `module:function/arity`, as found in the project config.
+    pub fn parse(spec: &str) -> Option<FunctionMatch> {
+        let spec = spec.trim();
+        match spec.split_once(':') {
+            None if !spec.is_empty() => Some(FunctionMatch::m(spec)),
+            None => None,
+            Some((m, fa)) => match fa.split_once('/') {
+                None if !m.is_empty() && !fa.is_empty() => Some(FunctionMatch::mf(m, fa)),
+                None => None,
+                Some((f, a)) if !m.is_empty() && !f.is_empty() => {
+                    Some(FunctionMatch::mfa(m, f, a.parse().ok()?))
+                }
+                Some(_) => None,
+            },
+        }
+    }
+
+    /// Whether every call matched by `other` is also matched by `self`.
+    pub fn covers(&self, other: &FunctionMatch) -> bool {
+        let (module, name, arity) = match other {
+            FunctionMatch::MFA(mfa) => (&mfa.module, Some(&mfa.name), Some(mfa.arity)),
+            FunctionMatch::MF { module, name } => (module, Some(name), None),
+            FunctionMatch::M { module } => (module, None, None),
+        };
+        match self {
+            FunctionMatch::MFA(mfa) => {
+                &mfa.module == module && Some(&mfa.name) == name && Some(mfa.arity) == arity
+            }
+            FunctionMatch::MF { module: m, name: n } => m == module && Some(n) == name,
+            FunctionMatch::M { module: m } => m == module,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -520,4 +554,31 @@ mod tests {
             "#,
         )
     }
+
+    #[test]
+    fn function_match_parse() {
+        assert_eq!(FunctionMatch::parse("foo"), Some(FunctionMatch::m("foo")));
+        assert_eq!(
+            FunctionMatch::parse("foo:bar"),
+            Some(FunctionMatch::mf("foo", "bar"))
+        );
+        assert_eq!(
+            FunctionMatch::parse(" foo:bar/2 "),
+            Some(FunctionMatch::mfa("foo", "bar", 2))
+        );
+        assert_eq!(FunctionMatch::parse(""), None);
+        assert_eq!(FunctionMatch::parse("foo:"), None);
+        assert_eq!(FunctionMatch::parse("foo:bar/x"), None);
+    }
+
+    #[test]
+    fn function_match_covers() {
+        let m = FunctionMatch::m("foo");
+        let mf = FunctionMatch::mf("foo", "bar");
+        let mfa = FunctionMatch::mfa("foo", "bar", 2);
+        assert!(m.covers(&mf) && m.covers(&mfa) && mf.covers(&mfa));
+        assert!(mfa.covers(&mfa));
+        assert!(!mfa.covers(&mf) && !mf.covers(&m));
+        assert!(!mf.covers(&FunctionMatch::mf("foo", "baz")));
+    }
 }
@@ -414,6 +414,32 @@ fn groups(sema: &Semantic, file_id: FileId) -> Result<FxHashMap<Name, GroupDef>,
     Ok(res)
 }
 
+// The ranges of the names of the groups defined in the groups/0 function,
+// so that a testcase can link to the group it is run in.
+// Only top-level group definitions are considered.
+pub fn group_name_ranges(sema: &Semantic, file_id: FileId) -> FxHashMap<Name, TextRange> {
+    let mut res = FxHashMap::default();
+
+    if let Some(expr) = top_level_expression(sema, file_id, known::groups, 0) {
+        let body = expr.body();
+        if let Expr::List { exprs, tail: _ } = &body[expr.value] {
+            for expr_id in exprs {
+                if let Expr::Tuple { exprs } = &body[*expr_id] {
+                    if let [group_name, _properties, _group_content] = exprs[..] {
+                        if let Some(atom) = body[group_name].as_atom() {
+                            if let Some(range) = expr.range_for_expr(sema.db, group_name) {
+                                res.insert(sema.db.lookup_atom(atom), range);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    res
+}
+
 // Parse each entry from the `groups/0` function.
 // See https://www.erlang.org/doc/man/ct_suite.html#Module:groups-0 for details
 fn parse_group(sema: &Semantic, body: &Body, expr_id: ExprId) -> Option<GroupDef> {
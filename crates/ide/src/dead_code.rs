@@ -0,0 +1,301 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Dead code: the private functions of a module which can't be reached
+//! from its entry points, and the records and macros used only by them,
+//! or not at all.
+//!
+//! The entry points of a module are its exported functions, the
+//! callbacks of its behaviours, the functions called by the runtime,
+//! such as the `-on_load` one, and `main/1` in an escript. Private
+//! functions are reachable through local calls and fun captures.
+
+use elp_ide_db::elp_base_db::AppType;
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::elp_base_db::ProjectId;
+use elp_ide_db::SymbolDefinition;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use elp_syntax::TextRange;
+use fxhash::FxHashSet;
+use hir::db::MinDefDatabase;
+use hir::Expr;
+use hir::File;
+use hir::FileKind;
+use hir::FunctionDef;
+use hir::InFile;
+use hir::Literal;
+use hir::Module;
+use hir::NameArity;
+use hir::Semantic;
+
+use crate::RootDatabase;
+use crate::SourceDatabase;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DeadCodeKind {
+    /// A private function not reachable from the entry points
+    Function,
+    /// A record not used by the reachable code
+    Record,
+    /// A macro not used by the reachable code
+    Macro,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadCode {
+    pub file_id: FileId,
+    pub kind: DeadCodeKind,
+    pub name: String,
+    /// The range of the name in the definition
+    pub range: TextRange,
+}
+
+/// The dead code of the modules of a project, leaving out its
+/// dependencies and generated files.
+pub(crate) fn dead_code(db: &RootDatabase, project_id: ProjectId) -> Vec<DeadCode> {
+    let module_index = db.module_index(project_id);
+    let mut res: Vec<DeadCode> = module_index
+        .iter_own()
+        .filter(|(_, _, file_id)| {
+            db.file_app_type(*file_id) != Some(AppType::Dep) && !db.is_generated(*file_id)
+        })
+        .flat_map(|(_, _, file_id)| file_dead_code(db, file_id))
+        .collect();
+    res.sort_by_key(|dead| (dead.file_id, dead.range.start()));
+    res
+}
+
+fn file_dead_code(db: &RootDatabase, file_id: FileId) -> Vec<DeadCode> {
+    let sema = Semantic::new(db);
+    let def_map = sema.def_map(file_id);
+    let reachable = reachable_functions(&sema, file_id);
+
+    let mut res = Vec::new();
+    let mut dead_ranges = Vec::new();
+    for (name, def) in def_map.get_functions() {
+        if def.file.file_id != file_id || reachable.contains(name) {
+            continue;
+        }
+        let source = def.source(db);
+        dead_ranges.push(source.syntax().text_range());
+        if let Some(range) = function_name_range(&source) {
+            res.push(DeadCode {
+                file_id,
+                kind: DeadCodeKind::Function,
+                name: name.to_string(),
+                range,
+            });
+        }
+    }
+
+    let is_live = |def: SymbolDefinition| {
+        def.usages(&sema)
+            .all()
+            .iter()
+            .any(|(usage_file_id, names)| {
+                usage_file_id != file_id
+                    || names.iter().any(|name| {
+                        let range = name.syntax().text_range();
+                        !dead_ranges.iter().any(|dead| dead.contains_range(range))
+                    })
+            })
+    };
+    for (name, def) in def_map.get_records() {
+        if def.file.file_id != file_id || is_live(SymbolDefinition::Record(def.clone())) {
+            continue;
+        }
+        if let Some(record_name) = def.source(db).name() {
+            res.push(DeadCode {
+                file_id,
+                kind: DeadCodeKind::Record,
+                name: name.to_string(),
+                range: record_name.syntax().text_range(),
+            });
+        }
+    }
+    for (name, def) in def_map.get_macros() {
+        if def.file.file_id != file_id || is_live(SymbolDefinition::Define(def.clone())) {
+            continue;
+        }
+        if let Some(macro_name) = def.source(db).name() {
+            res.push(DeadCode {
+                file_id,
+                kind: DeadCodeKind::Macro,
+                name: name.to_string(),
+                range: macro_name.syntax().text_range(),
+            });
+        }
+    }
+    res.sort_by_key(|dead| dead.range.start());
+    res
+}
+
+/// The functions of the file reachable from its entry points.
+fn reachable_functions(sema: &Semantic, file_id: FileId) -> FxHashSet<NameArity> {
+    let def_map = sema.def_map(file_id);
+    let callbacks = implemented_callbacks(sema, file_id);
+    let is_escript = File { file_id }.kind(sema.db.upcast()) == FileKind::Escript;
+    let mut todo: Vec<NameArity> = def_map
+        .get_functions()
+        .iter()
+        .filter(|(name, def)| {
+            def.file.file_id == file_id
+                && (def.exported
+                    || callbacks.contains(*name)
+                    || is_called_by_runtime(sema, def)
+                    || (is_escript && name.name().as_str() == "main" && name.arity() == 1))
+        })
+        .map(|(name, _)| name.clone())
+        .collect();
+    let mut reachable = FxHashSet::default();
+    while let Some(name) = todo.pop() {
+        if reachable.contains(&name) {
+            continue;
+        }
+        if let Some(def) = def_map.get_function(&name) {
+            todo.extend(local_callees(sema, file_id, def));
+        }
+        reachable.insert(name);
+    }
+    reachable
+}
+
+/// The functions of the file called or captured by `def`.
+fn local_callees(sema: &Semantic, file_id: FileId, def: &FunctionDef) -> Vec<NameArity> {
+    let function_id = InFile::new(file_id, def.function_id);
+    let function_body = sema.to_function_body(function_id);
+    let body = &function_body.body();
+    sema.fold_function(
+        function_id,
+        Vec::new(),
+        &mut |mut acc, _clause_id, ctx| {
+            let callee = match &ctx.expr {
+                Expr::Call { target, args } => {
+                    target.resolve_call(args.len() as u32, sema, file_id, body)
+                }
+                Expr::CaptureFun { target, arity } => match body[*arity] {
+                    Expr::Literal(Literal::Integer(int)) => int
+                        .try_into()
+                        .ok()
+                        .and_then(|arity| target.resolve_call(arity, sema, file_id, body)),
+                    _ => None,
+                },
+                _ => None,
+            };
+            if let Some(callee) = callee {
+                if callee.file.file_id == file_id {
+                    acc.push(callee.function.name);
+                }
+            }
+            acc
+        },
+        &mut |acc, _, _| acc,
+    )
+}
+
+fn implemented_callbacks(sema: &Semantic, file_id: FileId) -> FxHashSet<NameArity> {
+    let form_list = sema.db.file_form_list(file_id);
+    let mut res = FxHashSet::default();
+    for (_, behaviour) in form_list.behaviour_attributes() {
+        if let Some(module) = sema.resolve_module_name(file_id, behaviour.name.as_str()) {
+            let def_map = sema.def_map(module.file.file_id);
+            res.extend(def_map.get_callbacks().keys().cloned());
+        }
+    }
+    res
+}
+
+/// The `-on_load` function, and NIF stubs, are called by the runtime.
+fn is_called_by_runtime(sema: &Semantic, def: &FunctionDef) -> bool {
+    let module = Module { file: def.file };
+    match module.on_load(sema.db) {
+        Some((on_load, _)) if on_load == def.function.name => true,
+        _ => def.is_nif_stub(sema.db),
+    }
+}
+
+fn function_name_range(source: &ast::FunDecl) -> Option<TextRange> {
+    source.clauses().find_map(|clause| match clause {
+        ast::FunctionOrMacroClause::FunctionClause(clause) => {
+            Some(clause.name()?.syntax().text_range())
+        }
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use expect_test::Expect;
+
+    use crate::fixture;
+
+    fn check(fixture: &str, expect: Expect) {
+        let (analysis, position) = fixture::position(fixture);
+        let project_id = analysis.project_id(position.file_id).unwrap().unwrap();
+        let actual = analysis
+            .dead_code(project_id)
+            .unwrap()
+            .into_iter()
+            .map(|dead| {
+                let text = analysis.file_text(dead.file_id).unwrap();
+                format!("{:?} {} at {}\n", dead.kind, dead.name, &text[dead.range])
+            })
+            .collect::<String>();
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn unreachable_from_exports() {
+        check(
+            r#"
+-module(main).~
+-export([api/0]).
+-record(used, {a}).
+-record(dead_only, {a}).
+-record(unused, {a}).
+-define(USED, 1).
+-define(UNUSED, 2).
+api() -> helper(#used{a = ?USED}).
+helper(X) -> F = fun captured/1, F(X).
+captured(X) -> X.
+dead() -> loop(#dead_only{}).
+loop(X) -> loop(X).
+"#,
+            expect![[r#"
+                Record dead_only at dead_only
+                Record unused at unused
+                Macro UNUSED at UNUSED
+                Function dead/0 at dead
+                Function loop/1 at loop
+            "#]],
+        );
+    }
+
+    #[test]
+    fn entry_points() {
+        check(
+            r#"
+//- /src/main.erl
+-module(main).~
+-behaviour(my_behaviour).
+-on_load(init_nifs/0).
+-export([start/0]).
+start() -> ok.
+init_nifs() -> ok.
+init(Args) -> {ok, Args}.
+//- /src/my_behaviour.erl
+-module(my_behaviour).
+-callback init(term()) -> term().
+"#,
+            expect![[r#""#]],
+        );
+    }
+}
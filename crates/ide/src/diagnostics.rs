@@ -7,15 +7,20 @@
  * of this source tree.
  */
 
+use std::collections::BTreeMap;
 use std::collections::BTreeSet;
 use std::fmt;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
 
 use elp_ide_assists::AssistId;
 use elp_ide_assists::AssistKind;
 use elp_ide_db::assists::Assist;
 use elp_ide_db::docs::DocDatabase;
 use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::elp_base_db::ProjectData;
 use elp_ide_db::erlang_service;
 use elp_ide_db::erlang_service::DiagnosticLocation;
 use elp_ide_db::erlang_service::Location;
@@ -23,10 +28,13 @@ use elp_ide_db::erlang_service::ParseError;
 use elp_ide_db::erlang_service::StartLocation;
 use elp_ide_db::label::Label;
 use elp_ide_db::source_change::SourceChange;
+use elp_ide_db::EqwalizerDatabase;
 use elp_ide_db::ErlAstDatabase;
 use elp_ide_db::LineCol;
 use elp_ide_db::LineIndex;
 use elp_ide_db::LineIndexDatabase;
+use elp_project_model::lint_file::LintSeverity;
+use elp_project_model::AppName;
 use elp_syntax::algo;
 use elp_syntax::ast;
 use elp_syntax::ast::AstNode;
@@ -54,17 +62,35 @@ use crate::RootDatabase;
 use crate::SourceDatabase;
 
 mod application_env;
+mod behaviour_callbacks;
+mod catch_all;
 mod cross_node_eval;
+mod deprecated_function;
+mod dynamic_atom_creation;
 mod effect_free_statement;
+mod encoding_mismatch;
+mod explanations;
+mod guard_operators;
+mod guard_simplification;
 mod head_mismatch;
+mod include_cycle;
+mod line_in_error_tuple;
 // @fb-only: mod meta_only;
 mod missing_compile_warn_missing_spec;
+mod missing_generated_header;
 mod misspelled_attribute;
 mod module_mismatch;
 mod mutable_variable;
+mod on_load;
+mod plugins;
 mod redundant_assignment;
-mod replace_call;
+pub(crate) mod replace_call;
+mod spec_arg_name_mismatch;
+mod swallowed_exception;
 mod trivial_match;
+mod undefined_function;
+mod undefined_table_key;
+mod unused_exported_function;
 mod unused_function_args;
 mod unused_include;
 mod unused_macro;
@@ -132,11 +158,25 @@ impl Diagnostic {
         self
     }
 
-    pub(crate) fn should_be_ignored(&self, line_index: &LineIndex, source: &SyntaxNode) -> bool {
-        match prev_line_comment_text(&line_index, source, self.range.start()) {
-            Some(comment) => comment_contains_ignore_code(&comment, &self.code),
-            None => false,
-        }
+    /// The long-form, markdown explanation of the code of the diagnostic.
+    pub fn explanation(&self) -> Option<&'static str> {
+        self.code.explanation()
+    }
+
+    /// Whether the diagnostic is ignored by a `% elp:ignore` comment on
+    /// the line before it, or for the whole file, as given by the
+    /// `file_ignored_codes` of the file.
+    pub(crate) fn should_be_ignored(
+        &self,
+        line_index: &LineIndex,
+        source: &SyntaxNode,
+        file_ignored: &FxHashSet<DiagnosticCode>,
+    ) -> bool {
+        file_ignored.contains(&self.code)
+            || match prev_line_comment_text(&line_index, source, self.range.start()) {
+                Some(comment) => comment_contains_ignore_code(&comment, &self.code),
+                None => false,
+            }
     }
 
     pub(crate) fn with_ignore_fix(mut self, file_id: FileId) -> Diagnostic {
@@ -156,6 +196,7 @@ impl Diagnostic {
             target: self.range,
             source_change: Some(source_change),
             user_input: None,
+            disabled: None,
         };
         match &mut self.fixes {
             Some(fixes) => fixes.push(ignore_fix),
@@ -188,6 +229,8 @@ pub struct Ignore {
 
 #[derive(Debug, Clone)]
 pub struct RelatedInformation {
+    /// The file of the related code, if not the one of the diagnostic
+    pub file_id: Option<FileId>,
     pub range: TextRange,
     pub message: String,
 }
@@ -236,6 +279,22 @@ pub enum DiagnosticCode {
     MissingCompileWarnMissingSpec,
     MisspelledAttribute,
     CrossNodeEval,
+    EncodingMismatch,
+    SpecArgNameMismatch,
+    GuardSimplification,
+    UndefinedFunction,
+    UnusedExportedFunction,
+    OnLoadFunction,
+    BehaviourCallback,
+    UndefinedTableKey,
+    CatchAll,
+    GuardOperators,
+    MissingGeneratedHeader,
+    IncludeCycle,
+    DeprecatedFunction,
+    DynamicAtomCreation,
+    SwallowedException,
+    LineInErrorTuple,
 
     // Wrapper for erlang service diagnostic codes
     ErlangService(String),
@@ -273,6 +332,22 @@ impl DiagnosticCode {
             DiagnosticCode::MissingCompileWarnMissingSpec => "W0012".to_string(),
             DiagnosticCode::MisspelledAttribute => "W0013".to_string(), // misspelled-attribute
             DiagnosticCode::CrossNodeEval => "W0014".to_string(),       // cross-node-eval
+            DiagnosticCode::EncodingMismatch => "W0015".to_string(),    // encoding-mismatch
+            DiagnosticCode::SpecArgNameMismatch => "W0016".to_string(), // spec-arg-name-mismatch
+            DiagnosticCode::GuardSimplification => "W0017".to_string(), // guard-simplification
+            DiagnosticCode::UndefinedFunction => "W0018".to_string(),   // undefined-function
+            DiagnosticCode::UnusedExportedFunction => "W0019".to_string(), // unused-exported-function
+            DiagnosticCode::OnLoadFunction => "W0020".to_string(),         // on-load-function
+            DiagnosticCode::BehaviourCallback => "W0021".to_string(),      // behaviour-callback
+            DiagnosticCode::UndefinedTableKey => "W0022".to_string(),      // undefined-table-key
+            DiagnosticCode::CatchAll => "W0023".to_string(),               // catch-all
+            DiagnosticCode::GuardOperators => "W0024".to_string(),         // guard-operators
+            DiagnosticCode::MissingGeneratedHeader => "W0025".to_string(), // missing-generated-header
+            DiagnosticCode::IncludeCycle => "W0026".to_string(),           // include-cycle
+            DiagnosticCode::DeprecatedFunction => "W0027".to_string(),     // deprecated-function
+            DiagnosticCode::DynamicAtomCreation => "W0028".to_string(),    // dynamic-atom-creation
+            DiagnosticCode::SwallowedException => "W0029".to_string(),     // swallowed-exception
+            DiagnosticCode::LineInErrorTuple => "W0030".to_string(),       // line-in-error-tuple
             DiagnosticCode::ErlangService(c) => c.to_string(),
             DiagnosticCode::AdHoc(c) => format!("ad-hoc: {c}").to_string(),
             // @fb-only: DiagnosticCode::MetaOnly(c) => c.as_code(),
@@ -304,12 +379,34 @@ impl DiagnosticCode {
             DiagnosticCode::ApplicationGetEnv => "application_get_env".to_string(),
             DiagnosticCode::MisspelledAttribute => "misspelled_attribute".to_string(),
             DiagnosticCode::CrossNodeEval => "cross_node_eval".to_string(),
+            DiagnosticCode::EncodingMismatch => "encoding_mismatch".to_string(),
+            DiagnosticCode::SpecArgNameMismatch => "spec_arg_name_mismatch".to_string(),
+            DiagnosticCode::GuardSimplification => "guard_simplification".to_string(),
+            DiagnosticCode::UndefinedFunction => "undefined_function".to_string(),
+            DiagnosticCode::UnusedExportedFunction => "unused_exported_function".to_string(),
+            DiagnosticCode::OnLoadFunction => "on_load_function".to_string(),
+            DiagnosticCode::BehaviourCallback => "behaviour_callback".to_string(),
+            DiagnosticCode::UndefinedTableKey => "undefined_table_key".to_string(),
+            DiagnosticCode::CatchAll => "catch_all".to_string(),
+            DiagnosticCode::GuardOperators => "guard_operators".to_string(),
+            DiagnosticCode::MissingGeneratedHeader => "missing_generated_header".to_string(),
+            DiagnosticCode::IncludeCycle => "include_cycle".to_string(),
+            DiagnosticCode::DeprecatedFunction => "deprecated_function".to_string(),
+            DiagnosticCode::DynamicAtomCreation => "dynamic_atom_creation".to_string(),
+            DiagnosticCode::SwallowedException => "swallowed_exception".to_string(),
+            DiagnosticCode::LineInErrorTuple => "line_in_error_tuple".to_string(),
             DiagnosticCode::ErlangService(c) => c.to_string(),
             DiagnosticCode::AdHoc(c) => format!("ad-hoc: {c}").to_string(),
             // @fb-only: DiagnosticCode::MetaOnly(c) => c.as_label(),
         }
     }
 
+    /// The long-form, markdown explanation of the diagnostic, as shown by
+    /// `elp explain`.
+    pub fn explanation(&self) -> Option<&'static str> {
+        explanations::explanation(&self.as_code())
+    }
+
     pub fn maybe_from_string(s: &String) -> Option<DiagnosticCode> {
         if let Some(r) = DIAGNOSTIC_CODE_LOOKUPS.get(s) {
             Some(r.clone())
@@ -391,6 +488,8 @@ pub struct DiagnosticsConfig<'a> {
     pub disable_experimental: bool,
     disabled: FxHashSet<DiagnosticCode>,
     pub adhoc_semantic_diagnostics: Vec<&'a dyn AdhocSemanticDiagnostics>,
+    /// Severity to report a diagnostic with, instead of its own
+    pub severity_overrides: FxHashMap<DiagnosticCode, Severity>,
 }
 
 impl<'a> DiagnosticsConfig<'a> {
@@ -403,6 +502,7 @@ impl<'a> DiagnosticsConfig<'a> {
             disable_experimental,
             disabled,
             adhoc_semantic_diagnostics,
+            severity_overrides: FxHashMap::default(),
         }
     }
 
@@ -410,6 +510,15 @@ impl<'a> DiagnosticsConfig<'a> {
         self.disabled.insert(code);
         self
     }
+
+    pub fn override_severity(
+        mut self,
+        code: DiagnosticCode,
+        severity: Severity,
+    ) -> DiagnosticsConfig<'a> {
+        self.severity_overrides.insert(code, severity);
+        self
+    }
 }
 
 pub fn diagnostics(
@@ -417,11 +526,99 @@ pub fn diagnostics(
     config: &DiagnosticsConfig,
     file_id: FileId,
     include_generated: bool,
+) -> Vec<Diagnostic> {
+    diagnostics_with_timer(
+        db,
+        config,
+        file_id,
+        include_generated,
+        &mut PassTimer::disabled(),
+    )
+}
+
+/// How long each pass computing the diagnostics of a file took, so that
+/// slow lints can be identified and disabled.
+#[derive(Debug, Clone, Default)]
+pub struct DiagnosticsTiming {
+    /// In the order the passes ran. Queries shared between passes, such
+    /// as the def map, are charged to the first pass needing them.
+    pub passes: Vec<(String, Duration)>,
+}
+
+impl DiagnosticsTiming {
+    pub fn total(&self) -> Duration {
+        self.passes.iter().map(|(_, duration)| *duration).sum()
+    }
+}
+
+/// Records the duration of each pass when enabled, otherwise just runs
+/// them.
+struct PassTimer(Option<Vec<(String, Duration)>>);
+
+impl PassTimer {
+    fn disabled() -> PassTimer {
+        PassTimer(None)
+    }
+
+    fn enabled() -> PassTimer {
+        PassTimer(Some(Vec::new()))
+    }
+
+    fn run<T>(&mut self, name: &str, f: impl FnOnce() -> T) -> T {
+        match &mut self.0 {
+            None => f(),
+            Some(passes) => {
+                let start = Instant::now();
+                let res = f();
+                passes.push((name.to_string(), start.elapsed()));
+                res
+            }
+        }
+    }
+
+    fn finish(self) -> DiagnosticsTiming {
+        DiagnosticsTiming {
+            passes: self.0.unwrap_or_default(),
+        }
+    }
+}
+
+/// Time each pass of `diagnostics` for the given file, followed by the
+/// erlang_service, EDoc, lint plugins and, if enabled for the file,
+/// eqwalizer diagnostics.
+pub fn diagnostics_timing(
+    db: &RootDatabase,
+    config: &DiagnosticsConfig,
+    file_id: FileId,
+    include_generated: bool,
+) -> DiagnosticsTiming {
+    let mut timer = PassTimer::enabled();
+    diagnostics_with_timer(db, config, file_id, include_generated, &mut timer);
+    timer.run("erlang_service", || erlang_service_diagnostics(db, file_id));
+    timer.run("edoc", || edoc_diagnostics(db, file_id));
+    timer.run("plugins", || plugin_diagnostics(db, file_id));
+    if db.is_eqwalizer_enabled(file_id, include_generated) {
+        if let Some(app_data) = db.app_data(db.file_source_root(file_id)) {
+            timer.run("eqwalizer", || {
+                db.eqwalizer_diagnostics(app_data.project_id, vec![file_id])
+            });
+        }
+    }
+    timer.finish()
+}
+
+fn diagnostics_with_timer(
+    db: &RootDatabase,
+    config: &DiagnosticsConfig,
+    file_id: FileId,
+    include_generated: bool,
+    timer: &mut PassTimer,
 ) -> Vec<Diagnostic> {
     lazy_static! {
-        static ref EXTENSIONS: Vec<String> = vec!["erl".to_string(), "hrl".to_string(),];
+        static ref EXTENSIONS: Vec<String> =
+            vec!["erl".to_string(), "hrl".to_string(), "escript".to_string()];
     };
-    let parse = db.parse(file_id);
+    let parse = timer.run("parse", || db.parse(file_id));
     let root_id = db.file_source_root(file_id);
     let root = db.source_root(root_id);
     let path = root.path_for_file(&file_id).unwrap();
@@ -429,34 +626,64 @@ pub fn diagnostics(
     let ext = path.name_and_extension().unwrap_or_default().1;
     let report_diagnostics = EXTENSIONS.iter().any(|it| Some(it.as_str()) == ext);
 
+    let lint_file = LintFile::for_file(db, file_id);
+    // Experimental diagnostics enabled in the lint file must be computed
+    let disable_experimental = config.disable_experimental && !lint_file.enables_any();
+
     let mut res = Vec::new();
 
     if report_diagnostics {
         let is_erl_module = matches!(path.name_and_extension(), Some((_, Some("erl"))));
         let sema = Semantic::new(db);
+        timer.run("def_map", || sema.def_map(file_id));
 
         if is_erl_module {
             no_module_definition_diagnostic(&mut res, &parse);
             if include_generated || !db.is_generated(file_id) {
-                unused_include::unused_includes(&sema, db, &mut res, file_id);
+                timer.run("unused_include", || {
+                    unused_include::unused_includes(&sema, db, &mut res, file_id)
+                });
             }
             let is_test_suite = match path.name_and_extension() {
                 Some((name, _)) => name.ends_with("_SUITE"),
                 _ => false,
             };
             if is_test_suite {
-                common_test::unreachable_test(&mut res, &sema, file_id)
+                timer.run("unreachable_test", || {
+                    common_test::unreachable_test(&mut res, &sema, file_id)
+                });
             }
         }
 
+        timer.run("encoding_mismatch", || {
+            encoding_mismatch::encoding_mismatch(&mut res, db, file_id)
+        });
+        timer.run("missing_generated_header", || {
+            missing_generated_header::missing_generated_header(&mut res, db, file_id)
+        });
+        timer.run("include_cycle", || {
+            include_cycle::include_cycle(&mut res, db, file_id)
+        });
+        timer.run("deprecated_function", || {
+            deprecated_function::deprecated_function(&mut res, db, &sema, file_id)
+        });
+        if !disable_experimental {
+            timer.run("undefined_table_key", || {
+                undefined_table_key::undefined_table_key(&mut res, db, file_id)
+            });
+        }
         res.append(&mut form_missing_separator_diagnostics(&parse));
 
-        config
-            .adhoc_semantic_diagnostics
-            .iter()
-            .for_each(|f| f(&mut res, &sema, file_id, ext));
-        semantic_diagnostics(&mut res, &sema, file_id, ext, config.disable_experimental);
-        syntax_diagnostics(db, &parse, &mut res, file_id);
+        timer.run("adhoc", || {
+            config
+                .adhoc_semantic_diagnostics
+                .iter()
+                .for_each(|f| f(&mut res, &sema, file_id, ext))
+        });
+        semantic_diagnostics_with_timer(&mut res, &sema, file_id, ext, disable_experimental, timer);
+        timer.run("syntax", || {
+            syntax_diagnostics(db, &parse, &mut res, file_id)
+        });
 
         res.extend(parse.errors().iter().take(128).map(|err| {
             Diagnostic::error(
@@ -467,12 +694,120 @@ pub fn diagnostics(
         }));
     }
     let line_index = db.file_line_index(file_id);
+    let source = parse.syntax_node();
+    let file_ignored = file_ignored_codes(&source);
     res.retain(|d| {
         !config.disabled.contains(&d.code)
-            && !(config.disable_experimental && d.experimental)
-            && !d.should_be_ignored(&line_index, &parse.syntax_node())
+            && !lint_file.is_disabled(&d.code)
+            && !(config.disable_experimental && d.experimental && !lint_file.is_enabled(&d.code))
+            && !d.should_be_ignored(&line_index, &source, &file_ignored)
     });
+    for d in &mut res {
+        if let Some(severity) = config
+            .severity_overrides
+            .get(&d.code)
+            .copied()
+            .or_else(|| lint_file.severity(&d.code))
+        {
+            d.severity = severity;
+        }
+    }
+
+    res
+}
+
+/// The `.elp_lint.toml` configuration of the project of a file, for
+/// the app of the file.
+struct LintFile {
+    project: Option<(Arc<ProjectData>, AppName)>,
+}
+
+impl LintFile {
+    fn for_file(db: &RootDatabase, file_id: FileId) -> LintFile {
+        let project = db
+            .app_data(db.file_source_root(file_id))
+            .map(|app_data| (db.project_data(app_data.project_id), app_data.name.clone()));
+        LintFile { project }
+    }
+
+    fn enables_any(&self) -> bool {
+        match &self.project {
+            Some((project, _)) => !project.lint_file_config.enabled.is_empty(),
+            None => false,
+        }
+    }
+
+    fn is_enabled(&self, code: &DiagnosticCode) -> bool {
+        match &self.project {
+            Some((project, _)) => project
+                .lint_file_config
+                .is_enabled(&code.as_code(), &code.as_label()),
+            None => false,
+        }
+    }
+
+    fn is_disabled(&self, code: &DiagnosticCode) -> bool {
+        match &self.project {
+            Some((project, app)) => project.lint_file_config.is_disabled(
+                app.as_str(),
+                &code.as_code(),
+                &code.as_label(),
+            ),
+            None => false,
+        }
+    }
+
+    fn severity(&self, code: &DiagnosticCode) -> Option<Severity> {
+        let (project, _) = self.project.as_ref()?;
+        let severity = project
+            .lint_file_config
+            .severity(&code.as_code(), &code.as_label())?;
+        Some(match severity {
+            LintSeverity::Error => Severity::Error,
+            LintSeverity::Warning => Severity::Warning,
+            LintSeverity::WeakWarning => Severity::WeakWarning,
+        })
+    }
+}
+
+/// Diagnostics for remote calls to functions not exported by the
+/// called module, and for exported functions not called anywhere in
+/// the project, as `xref` would report them. These are not part of
+/// `diagnostics`, as projects commonly call modules ELP only knows
+/// partially, e.g. stubs for generated code.
+pub fn xref_diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic> {
+    let sema = Semantic::new(db);
+    let mut res = Vec::new();
+    undefined_function::undefined_function(&mut res, &sema, file_id);
+    unused_exported_function::unused_exported_function(&mut res, db, &sema, file_id);
+
+    let line_index = db.file_line_index(file_id);
+    let source = db.parse(file_id).syntax_node();
+    let file_ignored = file_ignored_codes(&source);
+    res.retain(|d| !d.should_be_ignored(&line_index, &source, &file_ignored));
+    res
+}
+
+/// Diagnostics of the lint plugins given in the `.elp_lint.toml` file
+/// of the project. These are not part of `diagnostics`, as each plugin
+/// is run as a separate process, once per file.
+pub fn plugin_diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic> {
+    let sema = Semantic::new(db);
+    let mut res = Vec::new();
+    plugins::plugin_diagnostics(&mut res, &sema, file_id);
 
+    let lint_file = LintFile::for_file(db, file_id);
+    let line_index = db.file_line_index(file_id);
+    let source = db.parse(file_id).syntax_node();
+    let file_ignored = file_ignored_codes(&source);
+    res.retain(|d| {
+        !lint_file.is_disabled(&d.code) && !d.should_be_ignored(&line_index, &source, &file_ignored)
+    });
+    for d in &mut res {
+        if let Some(severity) = lint_file.severity(&d.code) {
+            d.severity = severity;
+        }
+    }
     res
 }
 
@@ -482,21 +817,84 @@ pub fn semantic_diagnostics(
     file_id: FileId,
     ext: Option<&str>,
     disable_experimental: bool,
+) {
+    semantic_diagnostics_with_timer(
+        res,
+        sema,
+        file_id,
+        ext,
+        disable_experimental,
+        &mut PassTimer::disabled(),
+    )
+}
+
+fn semantic_diagnostics_with_timer(
+    res: &mut Vec<Diagnostic>,
+    sema: &Semantic,
+    file_id: FileId,
+    ext: Option<&str>,
+    disable_experimental: bool,
+    timer: &mut PassTimer,
 ) {
     // TODO: disable this check when T151727890 and T151605845 are resolved
     if !disable_experimental {
-        unused_function_args::unused_function_args(res, sema, file_id);
-        redundant_assignment::redundant_assignment(res, sema, file_id);
-        trivial_match::trivial_match(res, sema, file_id);
-    }
-    unused_macro::unused_macro(res, sema, file_id, ext);
-    unused_record_field::unused_record_field(res, sema, file_id, ext);
-    mutable_variable::mutable_variable_bug(res, sema, file_id);
-    effect_free_statement::effect_free_statement(res, sema, file_id);
-    application_env::application_env(res, sema, file_id);
+        timer.run("unused_function_args", || {
+            unused_function_args::unused_function_args(res, sema, file_id)
+        });
+        timer.run("redundant_assignment", || {
+            redundant_assignment::redundant_assignment(res, sema, file_id)
+        });
+        timer.run("trivial_match", || {
+            trivial_match::trivial_match(res, sema, file_id)
+        });
+        timer.run("guard_simplification", || {
+            guard_simplification::guard_simplification(res, sema, file_id)
+        });
+        timer.run("catch_all", || catch_all::catch_all(res, sema, file_id));
+        timer.run("swallowed_exception", || {
+            swallowed_exception::swallowed_exception(res, sema, file_id)
+        });
+        timer.run("guard_operators", || {
+            guard_operators::guard_operators(res, sema, file_id)
+        });
+    }
+    timer.run("unused_macro", || {
+        unused_macro::unused_macro(res, sema, file_id, ext)
+    });
+    timer.run("unused_record_field", || {
+        unused_record_field::unused_record_field(res, sema, file_id, ext)
+    });
+    timer.run("mutable_variable", || {
+        mutable_variable::mutable_variable_bug(res, sema, file_id)
+    });
+    timer.run("effect_free_statement", || {
+        effect_free_statement::effect_free_statement(res, sema, file_id)
+    });
+    timer.run("application_env", || {
+        application_env::application_env(res, sema, file_id)
+    });
     // @fb-only: meta_only::diagnostics(res, sema, file_id);
-    missing_compile_warn_missing_spec::missing_compile_warn_missing_spec(res, sema, file_id);
-    cross_node_eval::cross_node_eval(res, sema, file_id);
+    timer.run("missing_compile_warn_missing_spec", || {
+        missing_compile_warn_missing_spec::missing_compile_warn_missing_spec(res, sema, file_id)
+    });
+    timer.run("cross_node_eval", || {
+        cross_node_eval::cross_node_eval(res, sema, file_id)
+    });
+    timer.run("dynamic_atom_creation", || {
+        dynamic_atom_creation::dynamic_atom_creation(res, sema, file_id)
+    });
+    timer.run("line_in_error_tuple", || {
+        line_in_error_tuple::line_in_error_tuple(res, sema, file_id)
+    });
+    timer.run("spec_arg_name_mismatch", || {
+        spec_arg_name_mismatch::spec_arg_name_mismatch(res, sema, file_id)
+    });
+    timer.run("on_load_function", || {
+        on_load::on_load_function(res, sema, file_id)
+    });
+    timer.run("behaviour_callbacks", || {
+        behaviour_callbacks::behaviour_callbacks(res, sema, file_id)
+    });
 }
 
 pub fn syntax_diagnostics(
@@ -630,6 +1028,54 @@ fn record_decl_check_missing_comma(record: ast::RecordDecl) -> Vec<Diagnostic> {
     vec![]
 }
 
+/// An edit silencing the diagnostics with a `% elp:ignore` comment on
+/// the line before each of them, or by extending the ignore comment
+/// already there.
+pub fn ignore_all(text: &str, line_index: &LineIndex, diagnostics: &[Diagnostic]) -> TextEdit {
+    let mut lines: BTreeMap<u32, Vec<&DiagnosticCode>> = BTreeMap::new();
+    for d in diagnostics {
+        let codes = lines
+            .entry(line_index.line_col(d.range.start()).line)
+            .or_default();
+        if !codes.contains(&&d.code) {
+            codes.push(&d.code);
+        }
+    }
+
+    let mut builder = TextEdit::builder();
+    for (line, codes) in lines {
+        let ignores = codes
+            .iter()
+            .map(|code| format!("{} ({})", code.as_code(), code.as_label()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let start = match line_index.line_at(line as usize) {
+            Some(start) => start,
+            None => continue,
+        };
+        let prev_line = line
+            .checked_sub(1)
+            .and_then(|prev| line_index.line_at(prev as usize))
+            .map(|prev_start| {
+                let prev_text = &text[TextRange::new(prev_start, start)];
+                (prev_start, prev_text.trim_end_matches(&['\n', '\r'][..]))
+            });
+        match prev_line {
+            Some((prev_start, prev_text)) if prev_text.contains("% elp:ignore") => {
+                builder.insert(prev_start + TextSize::of(prev_text), format!(" {ignores}"));
+            }
+            _ => {
+                let indent: String = text[usize::from(start)..]
+                    .chars()
+                    .take_while(|c| *c == ' ' || *c == '\t')
+                    .collect();
+                builder.insert(start, format!("{indent}% elp:ignore {ignores}\n"));
+            }
+        }
+    }
+    builder.finish()
+}
+
 fn comment_contains_ignore_code(comment: &str, code: &DiagnosticCode) -> bool {
     let pattern = "% elp:ignore";
     match comment.find(pattern) {
@@ -646,6 +1092,59 @@ fn comment_contains_ignore_code(comment: &str, code: &DiagnosticCode) -> bool {
     }
 }
 
+/// The diagnostics ignored in the whole file, by a top level
+/// `% elp:ignore-file` comment, or an `-elp_ignore([...])` attribute.
+/// Computed once per file, rather than for each diagnostic.
+fn file_ignored_codes(source: &SyntaxNode) -> FxHashSet<DiagnosticCode> {
+    let pattern = "% elp:ignore-file";
+    let mut res = FxHashSet::default();
+    for child in source.children() {
+        match child.kind() {
+            SyntaxKind::COMMENT => {
+                let comment = child.text().to_string();
+                if let Some(start) = comment.find(pattern) {
+                    res.extend(
+                        comment[start + pattern.len()..]
+                            .split_whitespace()
+                            .filter_map(|code| DiagnosticCode::from_str(code).ok()),
+                    );
+                }
+            }
+            SyntaxKind::WILD_ATTRIBUTE => {
+                if let Some(attr) = ast::WildAttribute::cast(child) {
+                    res.extend(attribute_ignored_codes(&attr));
+                }
+            }
+            _ => {}
+        }
+    }
+    res
+}
+
+/// The codes in an `-elp_ignore([w0007, unused_macro])` attribute.
+fn attribute_ignored_codes(attr: &ast::WildAttribute) -> Vec<DiagnosticCode> {
+    let name = attr.name().and_then(|name| name.name()?.text());
+    if name.as_deref() != Some("elp_ignore") {
+        return Vec::new();
+    }
+    let list = match attr.value() {
+        Some(ast::Expr::ExprMax(ast::ExprMax::List(list))) => list,
+        _ => return Vec::new(),
+    };
+    list.exprs()
+        .filter_map(|expr| match expr {
+            ast::Expr::ExprMax(ast::ExprMax::Atom(atom)) => atom.text(),
+            _ => None,
+        })
+        .filter_map(|code| {
+            // Codes are written in lower case, to be atoms
+            DiagnosticCode::from_str(&code)
+                .or_else(|_| DiagnosticCode::from_str(&code.to_uppercase()))
+                .ok()
+        })
+        .collect()
+}
+
 fn prev_line(line_index: &LineIndex, current_line: u32) -> Option<TextSize> {
     match current_line {
         0 => None,
@@ -934,6 +1433,8 @@ fn parse_error_to_diagnostic_info(
             match parse_error.code.as_str() {
                 // For certain warnings, OTP returns a diagnostic for the entire definition of a function or record.
                 // That can be very verbose and distracting, so we try restricting the range to the function/record name only.
+                // The runtime calls the on_load function and NIF stubs
+                "L1230" if is_called_by_runtime(db, file_id, range) => None,
                 "L1230" | "L1309" => match function_name_range(db, file_id, range) {
                     Some(name_range) => Some((
                         file_id,
@@ -991,6 +1492,18 @@ fn function_name_range(db: &RootDatabase, file_id: FileId, range: TextRange) ->
     Some(function.name()?.syntax().text_range())
 }
 
+fn is_called_by_runtime(db: &RootDatabase, file_id: FileId, range: TextRange) -> bool {
+    let sema = Semantic::new(db);
+    sema.def_map(file_id)
+        .get_functions()
+        .values()
+        .find(|def| {
+            def.file.file_id == file_id
+                && def.source(db).syntax().text_range().contains(range.start())
+        })
+        .map_or(false, |def| on_load::is_called_by_runtime(&sema, def))
+}
+
 fn record_name_range(db: &RootDatabase, file_id: FileId, range: TextRange) -> Option<TextRange> {
     let sema = Semantic::new(db);
     let source_file = sema.parse(file_id);
@@ -1044,14 +1557,18 @@ fn location_range(location: Location, line_index: &LineIndex) -> TextRange {
 // cargo test --package elp_ide --lib
 #[cfg(test)]
 mod tests {
+    use elp_ide_db::elp_base_db::fixture::WithFixture;
+    use elp_project_model::lint_file::LintFileConfig;
     use elp_syntax::ast;
     use expect_test::expect;
 
     use super::*;
     use crate::codemod_helpers::FunctionMatch;
     use crate::codemod_helpers::MFA;
+    use crate::fixture;
     use crate::tests::check_diagnostics;
     use crate::tests::check_diagnostics_with_config;
+    use crate::tests::check_diagnostics_with_lint_file_config;
 
     #[test]
     fn fun_decl_missing_semi_no_warning() {
@@ -1200,6 +1717,20 @@ foo(2)->?baz.
         );
     }
 
+    #[test]
+    fn escript_diagnostics() {
+        check_diagnostics(
+            r#"
+//- /src/script.escript
+#!/usr/bin/env escript
+%%! -smp enable
+-define(UNUSED, 1).
+    %%  ^^^^^^ 💡 warning: Unused macro (UNUSED)
+main(_) -> ok.
+"#,
+        );
+    }
+
     #[test]
     fn fun_decl_module_decl_after_preprocessor() {
         check_diagnostics(
@@ -1232,6 +1763,7 @@ baz(1)->4.
         let mut config = DiagnosticsConfig {
             disable_experimental: false,
             disabled: FxHashSet::default(),
+            severity_overrides: FxHashMap::default(),
             adhoc_semantic_diagnostics: vec![&|acc, sema, file_id, _ext| {
                 replace_call::replace_call_site(
                     &FunctionMatch::MFA(MFA {
@@ -1351,4 +1883,175 @@ baz(1)->4.
         "#]]
         .assert_debug_eq(&codes);
     }
+
+    #[test]
+    fn ignore_file_comment() {
+        check_diagnostics(
+            r#"
+% elp:ignore-file W0010 unused_macro
+-module(main).
+-define(UNUSED, 42).
+foo(Unused) -> ok.
+bar() ->
+    1,
+%%  ^ 💡 warning: this statement has no effect
+    ok.
+"#,
+        );
+    }
+
+    #[test]
+    fn ignore_file_attribute() {
+        check_diagnostics(
+            r#"
+-module(main).
+-elp_ignore([w0010, unused_macro]).
+-define(UNUSED, 42).
+foo(Unused) -> ok.
+bar() ->
+    1,
+%%  ^ 💡 warning: this statement has no effect
+    ok.
+"#,
+        );
+    }
+
+    #[test]
+    fn ignore_all_diagnostics() {
+        let (analysis, file_id) = fixture::single_file(
+            r#"
+-module(main).
+-define(UNUSED, 42).
+foo(Unused) ->
+    % elp:ignore W0017 (undefined_function)
+    1,
+    ok.
+"#,
+        );
+        let config =
+            DiagnosticsConfig::default().disable(DiagnosticCode::MissingCompileWarnMissingSpec);
+        let diagnostics = analysis.diagnostics(&config, file_id, true).unwrap();
+        let mut text = analysis.file_text(file_id).unwrap().to_string();
+        let line_index = analysis.line_index(file_id).unwrap();
+        ignore_all(&text, &line_index, &diagnostics).apply(&mut text);
+        expect![[r#"
+            -module(main).
+            % elp:ignore W0002 (unused_macro)
+            -define(UNUSED, 42).
+            % elp:ignore W0010 (unused_function_arg)
+            foo(Unused) ->
+                % elp:ignore W0017 (undefined_function) W0006 (statement_has_no_effect)
+                1,
+                ok.
+        "#]]
+        .assert_eq(&text);
+
+        let (analysis, file_id) = fixture::single_file(&text);
+        let diagnostics = analysis.diagnostics(&config, file_id, true).unwrap();
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn severity_overrides() {
+        let config = DiagnosticsConfig::default()
+            .disable(DiagnosticCode::MissingCompileWarnMissingSpec)
+            .override_severity(DiagnosticCode::UnusedFunctionArg, Severity::Error)
+            .override_severity(DiagnosticCode::UnusedMacro, Severity::WeakWarning);
+        check_diagnostics_with_config(
+            config,
+            r#"
+-module(main).
+-define(UNUSED, 42).
+    %%  ^^^^^^ 💡 weak: Unused macro (UNUSED)
+foo(Unused) -> ok.
+%%  ^^^^^^ 💡 error: this variable is unused
+"#,
+        );
+    }
+
+    #[test]
+    fn lint_file_config() {
+        let lint_file_config = LintFileConfig::parse(
+            r#"
+[severity]
+W0010 = "error"
+
+[apps.main]
+disabled = [ "unused_macro" ]
+"#,
+        )
+        .unwrap();
+        check_diagnostics_with_lint_file_config(
+            lint_file_config,
+            r#"
+//- /main/src/main.erl app:main
+-module(main).
+-define(UNUSED, 42).
+foo(Unused) -> ok.
+%%  ^^^^^^ 💡 error: this variable is unused
+//- /other/src/other.erl app:other
+-module(other).
+-define(UNUSED, 42).
+    %%  ^^^^^^ 💡 warning: Unused macro (UNUSED)
+"#,
+        );
+    }
+
+    #[test]
+    fn timing_reports_each_pass() {
+        let (db, file_id) = RootDatabase::with_single_file(
+            r#"
+-module(main).
+foo(X) -> X.
+"#,
+        );
+        let mut timer = PassTimer::enabled();
+        diagnostics_with_timer(
+            &db,
+            &DiagnosticsConfig::default(),
+            file_id,
+            false,
+            &mut timer,
+        );
+        let timing = timer.finish();
+        let names: Vec<&str> = timing
+            .passes
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        expect![[r#"
+            [
+                "parse",
+                "def_map",
+                "unused_include",
+                "encoding_mismatch",
+                "missing_generated_header",
+                "include_cycle",
+                "deprecated_function",
+                "undefined_table_key",
+                "adhoc",
+                "unused_function_args",
+                "redundant_assignment",
+                "trivial_match",
+                "guard_simplification",
+                "catch_all",
+                "swallowed_exception",
+                "guard_operators",
+                "unused_macro",
+                "unused_record_field",
+                "mutable_variable",
+                "effect_free_statement",
+                "application_env",
+                "missing_compile_warn_missing_spec",
+                "cross_node_eval",
+                "dynamic_atom_creation",
+                "line_in_error_tuple",
+                "spec_arg_name_mismatch",
+                "on_load_function",
+                "behaviour_callbacks",
+                "syntax",
+            ]
+        "#]]
+        .assert_debug_eq(&names);
+    }
 }
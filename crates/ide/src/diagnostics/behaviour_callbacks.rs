@@ -0,0 +1,318 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Lint/fix: behaviour_callback
+//!
+//! Return a diagnostic if a module declaring a `-behaviour` does not
+//! implement one of its non-optional callbacks, implements it without
+//! exporting it, or only with a different arity. Offer to export the
+//! callback, or to create a stub for it.
+//!
+
+use elp_ide_assists::Assist;
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::source_change::SourceChange;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use elp_syntax::TextRange;
+use elp_syntax::TextSize;
+use hir::CallbackDef;
+use hir::NameArity;
+use hir::Semantic;
+use text_edit::TextEdit;
+
+use super::Diagnostic;
+use super::Severity;
+use crate::diagnostics::DiagnosticCode;
+use crate::fix;
+
+pub(crate) fn behaviour_callbacks(
+    diags: &mut Vec<Diagnostic>,
+    sema: &Semantic,
+    file_id: FileId,
+) -> Option<()> {
+    let form_list = sema.db.file_form_list(file_id);
+    let def_map = sema.def_map(file_id);
+    let source = sema.db.parse(file_id).tree();
+    for (_, behaviour) in form_list.behaviour_attributes() {
+        let module = match sema.resolve_module_name(file_id, behaviour.name.as_str()) {
+            Some(module) => module,
+            None => continue,
+        };
+        let attribute = behaviour.form_id.get(&source);
+        let attribute_range = attribute.syntax().text_range();
+        let behaviour_range = match attribute.name() {
+            Some(name) => name.syntax().text_range(),
+            None => continue,
+        };
+        let behaviour_def_map = sema.def_map(module.file.file_id);
+        let mut callbacks: Vec<(&NameArity, &CallbackDef)> = behaviour_def_map
+            .get_callbacks()
+            .iter()
+            .filter(|(_, callback)| !callback.optional)
+            .collect();
+        callbacks.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (name, callback) in callbacks {
+            if let Some(fun) = def_map.get_function(name) {
+                if !def_map.is_function_exported(name) {
+                    let range = function_name_range(sema, fun);
+                    diags.push(
+                        Diagnostic::new(
+                            DiagnosticCode::BehaviourCallback,
+                            format!(
+                                "Callback '{}' of behaviour '{}' is not exported",
+                                name, behaviour.name
+                            ),
+                            range,
+                        )
+                        .severity(Severity::Warning)
+                        .with_fixes(Some(vec![fix(
+                            "export_callback",
+                            &format!("Export callback {}", name),
+                            SourceChange::from_text_edit(
+                                file_id,
+                                TextEdit::insert(attribute_range.end(), export_text(name)),
+                            ),
+                            range,
+                        )])),
+                    );
+                }
+                continue;
+            }
+            let stub =
+                |range| create_callback(sema, file_id, attribute_range, name, callback, range);
+            let mismatched: Vec<TextRange> = def_map
+                .get_functions()
+                .iter()
+                .filter(|(fun_name, _)| fun_name.name() == name.name())
+                .map(|(_, fun)| function_name_range(sema, fun))
+                .collect();
+            if mismatched.is_empty() {
+                diags.push(
+                    Diagnostic::new(
+                        DiagnosticCode::BehaviourCallback,
+                        format!(
+                            "Missing callback '{}' of behaviour '{}'",
+                            name, behaviour.name
+                        ),
+                        behaviour_range,
+                    )
+                    .severity(Severity::Warning)
+                    .with_fixes(Some(vec![stub(behaviour_range)])),
+                );
+            } else {
+                for range in mismatched {
+                    diags.push(
+                        Diagnostic::new(
+                            DiagnosticCode::BehaviourCallback,
+                            format!(
+                                "Arity does not match callback '{}' of behaviour '{}'",
+                                name, behaviour.name
+                            ),
+                            range,
+                        )
+                        .severity(Severity::Warning)
+                        .with_fixes(Some(vec![stub(range)])),
+                    );
+                }
+            }
+        }
+    }
+    Some(())
+}
+
+fn function_name_range(sema: &Semantic, fun: &hir::FunctionDef) -> TextRange {
+    let source = fun.source(sema.db.upcast());
+    match source.name() {
+        Some(name) => name.syntax().text_range(),
+        None => source.syntax().text_range(),
+    }
+}
+
+fn export_text(name: &NameArity) -> String {
+    format!("\n-export([{}]).", name)
+}
+
+/// A fix exporting the callback after the `-behaviour` attribute, and
+/// defining a stub for it at the end of the file.
+fn create_callback(
+    sema: &Semantic,
+    file_id: FileId,
+    attribute_range: TextRange,
+    name: &NameArity,
+    callback: &CallbackDef,
+    range: TextRange,
+) -> Assist {
+    let text = sema.db.file_text(file_id);
+    let stub = format!(
+        "{}\n{}({}) ->\n    erlang:error(not_implemented).\n",
+        if text.ends_with('\n') { "" } else { "\n" },
+        name.name(),
+        stub_args(sema, name, callback).join(", ")
+    );
+    let mut edit = TextEdit::builder();
+    edit.insert(attribute_range.end(), export_text(name));
+    edit.insert(TextSize::of(text.as_str()), stub);
+    fix(
+        "create_callback",
+        &format!("Create callback {}", name),
+        SourceChange::from_text_edit(file_id, edit.finish()),
+        range,
+    )
+}
+
+/// Argument names for a stub, taken from the variables annotating the
+/// types of the callback, e.g. `Args` for `init(Args :: term())`.
+fn stub_args(sema: &Semantic, name: &NameArity, callback: &CallbackDef) -> Vec<String> {
+    let source = callback.source(sema.db.upcast());
+    let args: Vec<ast::Expr> = source
+        .sigs()
+        .next()
+        .and_then(|sig| sig.args())
+        .map(|args| args.args().collect())
+        .unwrap_or_default();
+    (0..name.arity() as usize)
+        .map(|idx| match args.get(idx) {
+            Some(ast::Expr::AnnType(ann)) => match ann.var().and_then(|var| var.var()) {
+                Some(var) => format!("_{}", var.text()),
+                None => format!("_Arg{}", idx + 1),
+            },
+            _ => format!("_Arg{}", idx + 1),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics;
+    use crate::tests::check_fix;
+
+    #[test]
+    fn callbacks_implemented() {
+        check_diagnostics(
+            r#"
+//- /src/main.erl
+-module(main).
+-behaviour(my_behaviour).
+-export([init/1]).
+init(_) -> ok.
+//- /src/my_behaviour.erl
+-module(my_behaviour).
+-callback init(Args :: term()) -> ok.
+-callback optional() -> ok.
+-optional_callbacks([optional/0]).
+            "#,
+        );
+    }
+
+    #[test]
+    fn missing_callback() {
+        check_diagnostics(
+            r#"
+//- /src/main.erl
+-module(main).
+-behaviour(my_behaviour).
+%%         ^^^^^^^^^^^^ 💡 warning: Missing callback 'init/1' of behaviour 'my_behaviour'
+//- /src/my_behaviour.erl
+-module(my_behaviour).
+-callback init(Args :: term()) -> ok.
+            "#,
+        );
+    }
+
+    #[test]
+    fn callback_not_exported() {
+        check_diagnostics(
+            r#"
+//- /src/main.erl
+-module(main).
+-behaviour(my_behaviour).
+  init(_) -> ok.
+%%^^^^ 💡 warning: Callback 'init/1' of behaviour 'my_behaviour' is not exported
+//- /src/my_behaviour.erl
+-module(my_behaviour).
+-callback init(Args :: term()) -> ok.
+            "#,
+        );
+    }
+
+    #[test]
+    fn callback_arity_mismatch() {
+        check_diagnostics(
+            r#"
+//- /src/main.erl
+-module(main).
+-behaviour(my_behaviour).
+-export([init/2]).
+  init(_, _) -> ok.
+%%^^^^ 💡 warning: Arity does not match callback 'init/1' of behaviour 'my_behaviour'
+//- /src/my_behaviour.erl
+-module(my_behaviour).
+-callback init(Args :: term()) -> ok.
+            "#,
+        );
+    }
+
+    #[test]
+    fn unknown_behaviour_is_ignored() {
+        check_diagnostics(
+            r#"
+//- /src/main.erl
+-module(main).
+-behaviour(not_in_project).
+            "#,
+        );
+    }
+
+    #[test]
+    fn export_callback() {
+        check_fix(
+            r#"
+//- /src/main.erl
+-module(main).
+-behaviour(my_behaviour).
+  in~it(_) -> ok.
+//- /src/my_behaviour.erl
+-module(my_behaviour).
+-callback init(Args :: term()) -> ok.
+            "#,
+            r#"
+-module(main).
+-behaviour(my_behaviour).
+-export([init/1]).
+  init(_) -> ok.
+"#,
+        );
+    }
+
+    #[test]
+    fn create_callback() {
+        check_fix(
+            r#"
+//- /src/main.erl
+-module(main).
+-behaviour(my_be~haviour).
+foo() -> ok.
+//- /src/my_behaviour.erl
+-module(my_behaviour).
+-callback init(Args :: term(), integer()) -> ok.
+            "#,
+            r#"
+-module(main).
+-behaviour(my_behaviour).
+-export([init/2]).
+foo() -> ok.
+
+init(_Args, _Arg2) ->
+    erlang:error(not_implemented).
+"#,
+        );
+    }
+}
@@ -0,0 +1,160 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Lint: catch_all
+//!
+//! Return an experimental diagnostic for a `_:_` exception handler
+//! swallowing every exception. Handlers re-raising the exception, and
+//! the ones in the top level `main/1` function of an escript, are not
+//! reported. The `narrow_catch_all` assist offers to narrow it.
+//!
+
+use elp_ide_assists::helpers::is_catch_all;
+use elp_ide_db::elp_base_db::FileId;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use elp_syntax::TextRange;
+use hir::Semantic;
+
+use super::Diagnostic;
+use super::Severity;
+use crate::diagnostics::DiagnosticCode;
+
+/// Calls in a handler which raise an exception again.
+const RAISES: &[&str] = &[
+    "error",
+    "exit",
+    "throw",
+    "erlang:error",
+    "erlang:exit",
+    "erlang:throw",
+    "erlang:raise",
+];
+
+pub(crate) fn catch_all(diags: &mut Vec<Diagnostic>, sema: &Semantic, file_id: FileId) {
+    let source_file = sema.parse(file_id);
+    for clause in source_file
+        .value
+        .syntax()
+        .descendants()
+        .filter_map(ast::CatchClause::cast)
+    {
+        if !is_catch_all(&clause) || is_top_level(&clause) || reraises(&clause) {
+            continue;
+        }
+        let start = clause.syntax().text_range().start();
+        let end = match (clause.stack(), clause.pat()) {
+            (Some(stack), _) => stack.syntax().text_range().end(),
+            (None, Some(pat)) => pat.syntax().text_range().end(),
+            (None, None) => continue,
+        };
+        diags.push(
+            Diagnostic::new(
+                DiagnosticCode::CatchAll,
+                "Catching all exceptions, consider catching the expected ones only",
+                TextRange::new(start, end),
+            )
+            .severity(Severity::Warning)
+            .experimental(),
+        );
+    }
+}
+
+pub(super) fn is_top_level(clause: &ast::CatchClause) -> bool {
+    match clause
+        .syntax()
+        .ancestors()
+        .find_map(ast::FunctionClause::cast)
+    {
+        Some(function) => {
+            function.name().map(|name| name.syntax().text().to_string()) == Some("main".to_string())
+                && function.args().map(|args| args.args().count()) == Some(1)
+        }
+        None => false,
+    }
+}
+
+pub(super) fn reraises(clause: &ast::CatchClause) -> bool {
+    clause.body().map_or(false, |body| {
+        body.syntax()
+            .descendants()
+            .filter_map(ast::Call::cast)
+            .filter_map(|call| call.expr())
+            .any(|callee| {
+                let callee = callee.syntax().text().to_string();
+                RAISES.contains(&callee.as_str())
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics;
+
+    #[test]
+    fn catch_all() {
+        check_diagnostics(
+            r#"
+-module(main).
+f() ->
+    try g()
+    catch
+        _:_ -> error
+%%      ^^^ warning: Catching all exceptions, consider catching the expected ones only
+%%      ^^^ 💡 warning: Exception discarded without being logged or re-raised
+    end.
+h() ->
+    try g()
+    catch
+        _:_:St -> {error, St}
+%%      ^^^^^^ warning: Catching all exceptions, consider catching the expected ones only
+    end.
+g() -> ok.
+            "#,
+        );
+    }
+
+    #[test]
+    fn specific_or_guarded() {
+        check_diagnostics(
+            r#"
+-module(main).
+f() ->
+    try g()
+    catch
+        error:_ -> error;
+        _:Reason when Reason =/= stop -> error;
+        Class:_ -> Class
+    end.
+g() -> ok.
+            "#,
+        );
+    }
+
+    #[test]
+    fn reraised_or_top_level() {
+        check_diagnostics(
+            r#"
+-module(main).
+f() ->
+    try g()
+    catch
+        _:_:St -> log(St), erlang:raise(error, failed, St)
+    end.
+main(_Args) ->
+    try g()
+    catch
+        _:_ -> halt(1)
+    end.
+g() -> ok.
+log(_) -> ok.
+            "#,
+        );
+    }
+}
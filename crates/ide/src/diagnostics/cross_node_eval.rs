@@ -11,49 +11,107 @@
 //!
 //! Return a diagnostic for rpc calls to remote nodes.
 //!
+//! The functions reported can be changed in the `[cross_node_eval]`
+//! section of the `.elp_lint.toml` file of the project, which can also declare wrapper
+//! functions of the project doing a cross node eval on behalf of their
+//! callers.
 
 use elp_ide_db::elp_base_db::FileId;
+use elp_project_model::lint_file::CrossNodeEvalConfig;
+use hir::Expr;
 use hir::FunctionDef;
+use hir::InFunctionBody;
 use hir::Semantic;
+use hir::Strategy;
 use lazy_static::lazy_static;
 
 use super::Diagnostic;
 use crate::codemod_helpers::find_call_in_function;
 use crate::codemod_helpers::FunctionMatch;
 use crate::diagnostics::DiagnosticCode;
+use crate::diagnostics::RelatedInformation;
 use crate::diagnostics::Severity;
 
+lazy_static! {
+    static ref BAD_MATCHES: Vec<FunctionMatch> = vec![
+        vec![FunctionMatch::m("rpc")],
+        vec![FunctionMatch::mf(
+            "erts_internal_dist",
+            "dist_spawn_request",
+        )],
+        vec![FunctionMatch::mf("sys", "install")],
+        FunctionMatch::mfas("erlang", "spawn", vec![2, 4]),
+        FunctionMatch::mfas("erlang", "spawn_link", vec![2, 4]),
+        FunctionMatch::mfas("erlang", "spawn_monitor", vec![2, 4]),
+        FunctionMatch::mfas("erlang", "spawn_opt", vec![3, 5]),
+        FunctionMatch::mfas("sys", "install", vec![2, 3]),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+}
+
 pub(crate) fn cross_node_eval(diags: &mut Vec<Diagnostic>, sema: &Semantic, file_id: FileId) {
     if sema.db.is_generated(file_id) {
         return;
     }
+    let config = project_config(sema, file_id);
+    let wrappers = parse_matches(&config.wrappers);
+    let bad_matches = bad_matches(&config, &wrappers);
+    let module = sema.module_name(file_id);
     sema.def_map(file_id)
         .get_functions()
         .iter()
-        .for_each(|(_arity, def)| check_function(diags, sema, def));
+        .filter(|(_arity, def)| match &module {
+            // The cross node eval done by a wrapper is reported at
+            // its call sites instead.
+            Some(module) => !is_wrapper(&wrappers, module.as_str(), def),
+            None => true,
+        })
+        .for_each(|(_arity, def)| process_badmatches(diags, sema, def, &bad_matches));
 }
 
-pub(crate) fn check_function(diags: &mut Vec<Diagnostic>, sema: &Semantic, def: &FunctionDef) {
-    lazy_static! {
-        static ref BAD_MATCHES: Vec<FunctionMatch> = vec![
-            vec![FunctionMatch::m("rpc")],
-            vec![FunctionMatch::mf(
-                "erts_internal_dist",
-                "dist_spawn_request",
-            )],
-            vec![FunctionMatch::mf("sys", "install")],
-            FunctionMatch::mfas("erlang", "spawn", vec![2, 4]),
-            FunctionMatch::mfas("erlang", "spawn_link", vec![2, 4]),
-            FunctionMatch::mfas("erlang", "spawn_monitor", vec![2, 4]),
-            FunctionMatch::mfas("erlang", "spawn_opt", vec![3, 5]),
-            FunctionMatch::mfas("sys", "install", vec![2, 3]),
-        ]
-        .into_iter()
-        .flatten()
-        .collect::<Vec<_>>();
+fn project_config(sema: &Semantic, file_id: FileId) -> CrossNodeEvalConfig {
+    match sema.db.app_data(sema.db.file_source_root(file_id)) {
+        Some(app_data) => sema
+            .db
+            .project_data(app_data.project_id)
+            .lint_file_config
+            .cross_node_eval
+            .clone(),
+        None => CrossNodeEvalConfig::default(),
     }
+}
+
+fn parse_matches(specs: &[String]) -> Vec<FunctionMatch> {
+    specs
+        .iter()
+        .filter_map(|spec| {
+            let res = FunctionMatch::parse(spec);
+            if res.is_none() {
+                log::warn!("cross_node_eval: ignoring invalid function `{spec}`");
+            }
+            res
+        })
+        .collect()
+}
 
-    process_badmatches(diags, sema, def, &BAD_MATCHES);
+/// The builtin matches, adjusted by the project config
+fn bad_matches(config: &CrossNodeEvalConfig, wrappers: &[FunctionMatch]) -> Vec<FunctionMatch> {
+    let remove = parse_matches(&config.remove);
+    BAD_MATCHES
+        .iter()
+        .filter(|bad| !remove.iter().any(|r| r.covers(bad)))
+        .cloned()
+        .chain(parse_matches(&config.add))
+        .chain(wrappers.iter().cloned())
+        .collect()
+}
+
+fn is_wrapper(wrappers: &[FunctionMatch], module: &str, def: &FunctionDef) -> bool {
+    let name = &def.function.name;
+    let mfa = FunctionMatch::mfa(module, name.name().as_str(), name.arity());
+    wrappers.iter().any(|w| w.covers(&mfa))
 }
 
 pub(crate) fn process_badmatches(
@@ -71,21 +129,52 @@ pub(crate) fn process_badmatches(
         &move |_mfa, _, _target, _args, _def_fb| {
             Some(r#"Production code must not use cross node eval (e.g. `rpc:call()`)"#.to_string())
         },
-        move |_sema, mut _def_fb, _target, _args, extra_info, range| {
+        move |sema, def_fb, _target, args, extra_info, range| {
+            let def_fb: &InFunctionBody<&FunctionDef> = def_fb;
+            let related = args
+                .iter()
+                .find(|arg| contains_fun(def_fb, **arg))
+                .and_then(|arg| def_fb.range_for_expr(sema.db, *arg))
+                .map(|range| {
+                    vec![RelatedInformation {
+                        file_id: None,
+                        range,
+                        message: "Remotely evaluated fun".to_string(),
+                    }]
+                });
             let diag = Diagnostic::new(DiagnosticCode::CrossNodeEval, extra_info, range.clone())
-                .severity(Severity::Warning);
+                .severity(Severity::Warning)
+                .with_related(related);
             Some(diag)
         },
     );
 }
 
+fn contains_fun(def_fb: &InFunctionBody<&FunctionDef>, expr_id: hir::ExprId) -> bool {
+    def_fb.fold_expr(
+        Strategy::TopDown,
+        expr_id,
+        false,
+        &mut |acc, ctx| acc || matches!(ctx.expr, Expr::Closure { .. } | Expr::CaptureFun { .. }),
+        &mut |acc, _| acc,
+    )
+}
+
 // ---------------------------------------------------------------------
 
 #[cfg(test)]
 mod tests {
+    use elp_ide_db::elp_base_db::fixture::WithFixture;
+    use elp_ide_db::elp_base_db::SourceDatabaseExt;
+    use elp_ide_db::RootDatabase;
+    use elp_project_model::lint_file::CrossNodeEvalConfig;
+    use elp_project_model::lint_file::LintFileConfig;
+
+    use crate::diagnostics;
     use crate::diagnostics::DiagnosticCode;
     use crate::diagnostics::DiagnosticsConfig;
     use crate::tests::check_diagnostics_with_config;
+    use crate::tests::check_diagnostics_with_lint_file_config;
 
     #[track_caller]
     pub(crate) fn check_diagnostics(ra_fixture: &str) {
@@ -96,6 +185,24 @@ mod tests {
         check_diagnostics_with_config(config, ra_fixture)
     }
 
+    #[track_caller]
+    fn check_diagnostics_with_cross_node_eval(config: CrossNodeEvalConfig, ra_fixture: &str) {
+        let lint_file_config = LintFileConfig {
+            cross_node_eval: config,
+            ..Default::default()
+        };
+        check_diagnostics_with_lint_file_config(lint_file_config, ra_fixture)
+    }
+
+    fn config(add: &[&str], remove: &[&str], wrappers: &[&str]) -> CrossNodeEvalConfig {
+        let to_vec = |specs: &[&str]| specs.iter().map(|s| s.to_string()).collect();
+        CrossNodeEvalConfig {
+            add: to_vec(add),
+            remove: to_vec(remove),
+            wrappers: to_vec(wrappers),
+        }
+    }
+
     #[test]
     fn local_ok() {
         check_diagnostics(
@@ -403,4 +510,102 @@ mod tests {
             "#,
         )
     }
+
+    #[test]
+    fn config_add() {
+        check_diagnostics_with_cross_node_eval(
+            config(&["my_rpc:call/4", "other_rpc"], &[], &[]),
+            r#"
+            -module(main).
+
+            foo(Node) ->
+                my_rpc:call(Node, mod, func, []),
+            %%  ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ warning: Production code must not use cross node eval (e.g. `rpc:call()`)
+                my_rpc:call(Node, mod, func),
+                other_rpc:anything(Node).
+            %%  ^^^^^^^^^^^^^^^^^^^^^^^^ warning: Production code must not use cross node eval (e.g. `rpc:call()`)
+            "#,
+        )
+    }
+
+    #[test]
+    fn config_remove() {
+        check_diagnostics_with_cross_node_eval(
+            config(&[], &["sys:install", "erlang:spawn/2"], &[]),
+            r#"
+            -module(main).
+
+            foo(Node, Name, FuncSpec) ->
+                sys:install(Name, FuncSpec),
+                sys:install(Name, FuncSpec, 500),
+                erlang:spawn(Node, fun() -> ok end),
+                erlang:spawn(Node, modu, ff, []).
+            %%  ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ warning: Production code must not use cross node eval (e.g. `rpc:call()`)
+            "#,
+        )
+    }
+
+    #[test]
+    fn config_wrappers() {
+        check_diagnostics_with_cross_node_eval(
+            config(&[], &[], &["my_app_rpc:call"]),
+            r#"
+            //- /src/my_app_rpc.erl
+            -module(my_app_rpc).
+            -export([call/2, other/1]).
+
+            call(Node, Fun) ->
+                rpc:call(Node, erlang, apply, [Fun, []]).
+
+            other(Node) ->
+                rpc:call(Node, mod, func, []).
+            %%  ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ warning: Production code must not use cross node eval (e.g. `rpc:call()`)
+
+            //- /src/main.erl
+            -module(main).
+
+            foo(Node) ->
+                my_app_rpc:call(Node, fun() -> ok end).
+            %%  ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ warning: Production code must not use cross node eval (e.g. `rpc:call()`)
+            "#,
+        )
+    }
+
+    #[test]
+    fn related_info_points_at_fun() {
+        let (db, file_id) = RootDatabase::with_single_file(
+            r#"
+            -module(main).
+
+            foo(Node) ->
+                erlang:spawn(Node, fun() -> ok end),
+                rpc:call(Node, erlang, apply, [fun main:bar/0, []]),
+                rpc:call(Node, mod, func, []).
+
+            bar() -> ok.
+            "#,
+        );
+        let config =
+            DiagnosticsConfig::default().disable(DiagnosticCode::MissingCompileWarnMissingSpec);
+        let text = db.file_text(file_id);
+        let related = diagnostics::diagnostics(&db, &config, file_id, true)
+            .into_iter()
+            .filter(|d| d.code == DiagnosticCode::CrossNodeEval)
+            .map(|d| {
+                d.related_info
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|r| text[r.range].to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            related,
+            vec![
+                vec!["fun() -> ok end".to_string()],
+                vec!["[fun main:bar/0, []]".to_string()],
+                vec![],
+            ]
+        );
+    }
 }
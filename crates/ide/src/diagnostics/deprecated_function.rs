@@ -0,0 +1,244 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Lint/fix: deprecated_function
+//!
+//! Return a diagnostic for remote calls to a function deprecated by a
+//! `-deprecated` attribute of the called module, or by the
+//! `[deprecated]` section of `.elp_lint.toml`, and offer to call the
+//! replacement given by the deprecation instead.
+//!
+
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::source_change::SourceChange;
+use elp_ide_db::DeprecationDatabase;
+use hir::CallTarget;
+use hir::Expr;
+use hir::ExprId;
+use hir::InFile;
+use hir::InFunctionBody;
+use hir::NameArity;
+use hir::Semantic;
+use text_edit::TextEdit;
+
+use super::Diagnostic;
+use crate::diagnostics::DiagnosticCode;
+use crate::diagnostics::Severity;
+use crate::fix;
+use crate::RootDatabase;
+
+pub(crate) fn deprecated_function(
+    diags: &mut Vec<Diagnostic>,
+    db: &RootDatabase,
+    sema: &Semantic,
+    file_id: FileId,
+) {
+    let def_map = sema.def_map(file_id);
+    for (_name, def) in def_map.get_functions() {
+        if def.file.file_id != file_id {
+            continue;
+        }
+        let function_id = InFile::new(file_id, def.function_id);
+        let function_body = sema.to_function_body(function_id);
+        sema.fold_function(
+            function_id,
+            (),
+            &mut |acc, _clause_id, ctx| {
+                if let Expr::Call {
+                    target: CallTarget::Remote { module, name },
+                    args,
+                } = ctx.expr
+                {
+                    if let Some(diag) = check_call(
+                        db,
+                        sema,
+                        file_id,
+                        &function_body,
+                        ctx.expr_id,
+                        (module, name, args.len() as u32),
+                    ) {
+                        diags.push(diag);
+                    }
+                }
+                acc
+            },
+            &mut |acc, _, _| acc,
+        );
+    }
+}
+
+fn check_call(
+    db: &RootDatabase,
+    sema: &Semantic,
+    file_id: FileId,
+    function_body: &InFunctionBody<()>,
+    call: ExprId,
+    (module, name, arity): (ExprId, ExprId, u32),
+) -> Option<Diagnostic> {
+    let module_name = sema.db.lookup_atom(function_body[module].as_atom()?);
+    let fun_name = sema.db.lookup_atom(function_body[name].as_atom()?);
+    let target = sema.resolve_module_name(file_id, module_name.as_str())?;
+    if target.is_in_otp(sema.db) {
+        // Already reported by the compiler
+        return None;
+    }
+    let na = NameArity::new(fun_name, arity);
+    let deprecations = db.module_deprecations(target.file.file_id);
+    let deprecation = deprecations.get(&na)?;
+
+    let range = function_body.range_for_expr(sema.db, call)?;
+    let message = match &deprecation.description {
+        Some(description) => format!(
+            "Function '{}:{}' is deprecated: {}",
+            module_name, na, description
+        ),
+        None => format!("Function '{}:{}' is deprecated.", module_name, na),
+    };
+    let diag = Diagnostic::new(DiagnosticCode::DeprecatedFunction, message, range)
+        .severity(Severity::Warning);
+
+    let replacement = match &deprecation.replacement {
+        Some(replacement) if replacement.arity == arity => replacement,
+        _ => return Some(diag),
+    };
+    // Keep the module as written, e.g. a macro, when it does not change
+    let (edit_range, text) = match &replacement.module {
+        Some(new_module) if new_module != module_name.as_str() => (
+            function_body
+                .range_for_expr(sema.db, module)?
+                .cover(function_body.range_for_expr(sema.db, name)?),
+            format!("{}:{}", new_module, replacement.function),
+        ),
+        _ => (
+            function_body.range_for_expr(sema.db, name)?,
+            replacement.function.to_string(),
+        ),
+    };
+    let new_module = replacement
+        .module
+        .clone()
+        .unwrap_or_else(|| module_name.raw());
+    Some(diag.with_fixes(Some(vec![fix(
+        "replace_deprecated_call",
+        &format!(
+            "Call '{}:{}/{}' instead",
+            new_module, replacement.function, replacement.arity
+        ),
+        SourceChange::from_text_edit(file_id, TextEdit::replace(edit_range, text)),
+        range,
+    )])))
+}
+
+#[cfg(test)]
+mod tests {
+    use elp_project_model::lint_file::DeprecatedConfig;
+    use elp_project_model::lint_file::LintFileConfig;
+
+    use crate::tests::check_diagnostics;
+    use crate::tests::check_diagnostics_with_lint_file_config;
+    use crate::tests::check_fix;
+
+    #[test]
+    fn deprecated_by_attribute() {
+        check_diagnostics(
+            r#"
+//- /src/main.erl
+-module(main).
+main() ->
+    other:old(1),
+%%  ^^^^^^^^^^^^ 💡 warning: Function 'other:old/1' is deprecated: use other:new/1 instead
+    other:old(1, 2),
+    other:gone(),
+%%  ^^^^^^^^^^^^ warning: Function 'other:gone/0' is deprecated: will be removed in the next version
+    other:new(1).
+//- /src/other.erl
+-module(other).
+-export([old/1, old/2, gone/0, new/1]).
+-deprecated([{old, 1, "use other:new/1 instead"}, {gone, '_', next_version}]).
+old(X) -> new(X).
+old(X, _) -> new(X).
+gone() -> ok.
+new(X) -> X.
+            "#,
+        );
+    }
+
+    #[test]
+    fn deprecated_module() {
+        check_diagnostics(
+            r#"
+//- /src/main.erl
+-module(main).
+main() ->
+    legacy:run().
+%%  ^^^^^^^^^^^^ warning: Function 'legacy:run/0' is deprecated.
+//- /src/legacy.erl
+-module(legacy).
+-deprecated(module).
+-export([run/0]).
+run() -> ok.
+            "#,
+        );
+    }
+
+    #[test]
+    fn replace_deprecated_call() {
+        check_fix(
+            r#"
+//- /src/main.erl
+-module(main).
+main() ->
+    other:o~ld(1).
+//- /src/other.erl
+-module(other).
+-export([old/1, new_one/1]).
+-deprecated({old, 1, "Use new_one/1."}).
+old(X) -> new_one(X).
+new_one(X) -> X.
+            "#,
+            r#"
+-module(main).
+main() ->
+    other:new_one(1).
+"#,
+        );
+    }
+
+    #[test]
+    fn deprecated_by_config() {
+        let lint_file_config = LintFileConfig {
+            deprecated: DeprecatedConfig {
+                functions: vec!["other:old".to_string()],
+                replacements: [("other:older/0".to_string(), "other:new/0".to_string())]
+                    .into_iter()
+                    .collect(),
+            },
+            ..Default::default()
+        };
+        check_diagnostics_with_lint_file_config(
+            lint_file_config,
+            r#"
+//- /src/main.erl
+-module(main).
+main() ->
+    other:old(1),
+%%  ^^^^^^^^^^^^ warning: Function 'other:old/1' is deprecated.
+    other:older(),
+%%  ^^^^^^^^^^^^^ 💡 warning: Function 'other:older/0' is deprecated.
+    other:new().
+//- /src/other.erl
+-module(other).
+-export([old/1, older/0, new/0]).
+old(X) -> X.
+older() -> ok.
+new() -> ok.
+            "#,
+        );
+    }
+}
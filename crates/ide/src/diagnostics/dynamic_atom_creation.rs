@@ -0,0 +1,270 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Lint/fix: dynamic_atom_creation
+//!
+//! Return an experimental diagnostic for calls creating atoms from strings or binaries
+//! only known at runtime, such as `list_to_atom/1`. Atoms are never
+//! garbage collected, so creating them from external input can exhaust
+//! the atom table and bring the node down. The fix calls the
+//! `_existing_` variant instead, which fails on unknown atoms.
+//!
+//! Functions allowed to create atoms, e.g. from trusted config at
+//! startup, can be given in the `[atom_creation]` section of the
+//! `.elp_lint.toml` file of the project.
+
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::source_change::SourceChange;
+use elp_project_model::lint_file::AtomCreationConfig;
+use hir::CallTarget;
+use hir::Expr;
+use hir::ExprId;
+use hir::FunctionDef;
+use hir::InFunctionBody;
+use hir::Semantic;
+use lazy_static::lazy_static;
+use text_edit::TextEdit;
+
+use super::Diagnostic;
+use crate::codemod_helpers::find_call_in_function;
+use crate::codemod_helpers::FunctionMatch;
+use crate::diagnostics::DiagnosticCode;
+use crate::diagnostics::Severity;
+use crate::fix;
+
+lazy_static! {
+    /// The functions creating atoms, with their `_existing_` variant
+    static ref ATOM_CREATORS: Vec<(FunctionMatch, &'static str)> = vec![
+        (
+            FunctionMatch::mfa("erlang", "list_to_atom", 1),
+            "list_to_existing_atom"
+        ),
+        (
+            FunctionMatch::mfa("erlang", "binary_to_atom", 1),
+            "binary_to_existing_atom"
+        ),
+        (
+            FunctionMatch::mfa("erlang", "binary_to_atom", 2),
+            "binary_to_existing_atom"
+        ),
+    ];
+}
+
+pub(crate) fn dynamic_atom_creation(diags: &mut Vec<Diagnostic>, sema: &Semantic, file_id: FileId) {
+    if sema.db.is_generated(file_id) {
+        return;
+    }
+    let allowed = allowed_functions(sema, file_id);
+    let module = sema.module_name(file_id);
+    let creators: Vec<_> = ATOM_CREATORS
+        .iter()
+        .map(|(creator, existing)| (creator, *existing))
+        .collect();
+    sema.def_map(file_id)
+        .get_functions()
+        .iter()
+        .filter(|(_, def)| def.file.file_id == file_id)
+        .filter(|(_, def)| match &module {
+            Some(module) => !is_allowed(&allowed, module.as_str(), def),
+            None => true,
+        })
+        .for_each(|(_, def)| {
+            find_call_in_function(
+                diags,
+                sema,
+                def,
+                &creators,
+                &|_mfa, existing, _target, args, def_fb| {
+                    if is_constant(def_fb, *args.first()?) {
+                        // The atom is known at compile time
+                        None
+                    } else {
+                        Some(existing.to_string())
+                    }
+                },
+                move |sema, def_fb, target, args, existing, range| {
+                    let name = match target {
+                        CallTarget::Local { name } => *name,
+                        CallTarget::Remote { name, .. } => *name,
+                    };
+                    let existing = format!("{existing}/{}", args.len());
+                    let diag = Diagnostic::new(
+                        DiagnosticCode::DynamicAtomCreation,
+                        format!(
+                            "Atoms are never garbage collected: creating them dynamically can exhaust the atom table, consider `{existing}`"
+                        ),
+                        range,
+                    )
+                    .severity(Severity::Warning)
+                    .experimental();
+                    Some(match replace_name(sema, file_id, def_fb, name, &existing) {
+                        Some(edit) => diag.with_fixes(Some(vec![fix(
+                            "use_existing_atom",
+                            &format!("Use `{existing}`"),
+                            SourceChange::from_text_edit(file_id, edit),
+                            range,
+                        )])),
+                        None => diag,
+                    })
+                },
+            );
+        });
+}
+
+fn allowed_functions(sema: &Semantic, file_id: FileId) -> Vec<FunctionMatch> {
+    let config = match sema.db.app_data(sema.db.file_source_root(file_id)) {
+        Some(app_data) => sema
+            .db
+            .project_data(app_data.project_id)
+            .lint_file_config
+            .atom_creation
+            .clone(),
+        None => AtomCreationConfig::default(),
+    };
+    config
+        .allowed
+        .iter()
+        .filter_map(|spec| {
+            let res = FunctionMatch::parse(spec);
+            if res.is_none() {
+                log::warn!("dynamic_atom_creation: ignoring invalid function `{spec}`");
+            }
+            res
+        })
+        .collect()
+}
+
+fn is_allowed(allowed: &[FunctionMatch], module: &str, def: &FunctionDef) -> bool {
+    let name = &def.function.name;
+    let mfa = FunctionMatch::mfa(module, name.name().as_str(), name.arity());
+    allowed.iter().any(|a| a.covers(&mfa))
+}
+
+/// A literal string or binary, e.g. `"ok"` or `<<"ok">>`
+fn is_constant(def_fb: &InFunctionBody<&FunctionDef>, expr: ExprId) -> bool {
+    match &def_fb[expr] {
+        Expr::Literal(_) => true,
+        Expr::Binary { segs } => segs
+            .iter()
+            .all(|seg| matches!(def_fb[seg.elem], Expr::Literal(_))),
+        _ => false,
+    }
+}
+
+/// The edit renaming the called function to `existing`, only when its
+/// name is written out at the call, rather than coming from a macro.
+fn replace_name(
+    sema: &Semantic,
+    file_id: FileId,
+    def_fb: &InFunctionBody<&FunctionDef>,
+    name: ExprId,
+    existing: &str,
+) -> Option<TextEdit> {
+    let function = sema.db.lookup_atom(def_fb[name].as_atom()?);
+    let range = def_fb.range_for_expr(sema.db, name)?;
+    if sema.db.file_text(file_id)[range] != *function.as_str() {
+        return None;
+    }
+    let (existing, _arity) = existing.split_once('/')?;
+    Some(TextEdit::replace(range, existing.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use elp_project_model::lint_file::AtomCreationConfig;
+    use elp_project_model::lint_file::LintFileConfig;
+
+    use crate::diagnostics::DiagnosticCode;
+    use crate::diagnostics::DiagnosticsConfig;
+    use crate::tests::check_diagnostics;
+    use crate::tests::check_diagnostics_with_config;
+    use crate::tests::check_diagnostics_with_lint_file_config;
+    use crate::tests::check_fix;
+
+    #[test]
+    fn dynamic_atoms() {
+        check_diagnostics(
+            r#"
+-module(main).
+foo(Name, Bin) ->
+    list_to_atom(Name),
+%%  ^^^^^^^^^^^^^^^^^^ 💡 warning: Atoms are never garbage collected: creating them dynamically can exhaust the atom table, consider `list_to_existing_atom/1`
+    erlang:binary_to_atom(Bin, utf8),
+%%  ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ 💡 warning: Atoms are never garbage collected: creating them dynamically can exhaust the atom table, consider `binary_to_existing_atom/2`
+    binary_to_atom(Bin).
+%%  ^^^^^^^^^^^^^^^^^^^ 💡 warning: Atoms are never garbage collected: creating them dynamically can exhaust the atom table, consider `binary_to_existing_atom/1`
+            "#,
+        );
+    }
+
+    #[test]
+    fn experimental() {
+        check_diagnostics_with_config(
+            DiagnosticsConfig {
+                disable_experimental: true,
+                ..Default::default()
+            }
+            .disable(DiagnosticCode::MissingCompileWarnMissingSpec),
+            r#"
+-module(main).
+foo(Name) -> list_to_atom(Name).
+            "#,
+        );
+    }
+
+    #[test]
+    fn constant_atoms() {
+        check_diagnostics(
+            r#"
+-module(main).
+foo() ->
+    list_to_atom("ok"),
+    binary_to_atom(<<"ok">>, utf8),
+    list_to_existing_atom(name()).
+name() -> "ok".
+            "#,
+        );
+    }
+
+    #[test]
+    fn use_existing_atom() {
+        check_fix(
+            r#"
+-module(main).
+foo(Bin) ->
+    erlang:binary_to_a~tom(Bin, utf8).
+            "#,
+            r#"
+-module(main).
+foo(Bin) ->
+    erlang:binary_to_existing_atom(Bin, utf8).
+            "#,
+        );
+    }
+
+    #[test]
+    fn allowed_by_config() {
+        let lint_file_config = LintFileConfig {
+            atom_creation: AtomCreationConfig {
+                allowed: vec!["main:load/1".to_string()],
+            },
+            ..Default::default()
+        };
+        check_diagnostics_with_lint_file_config(
+            lint_file_config,
+            r#"
+//- /src/main.erl
+-module(main).
+load(Name) -> list_to_atom(Name).
+get(Name) -> list_to_atom(Name).
+%%           ^^^^^^^^^^^^^^^^^^ 💡 warning: Atoms are never garbage collected: creating them dynamically can exhaust the atom table, consider `list_to_existing_atom/1`
+            "#,
+        );
+    }
+}
@@ -0,0 +1,103 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+// Diagnostic: encoding-mismatch
+//
+// Diagnostic for files declaring `%% coding: latin-1` whose content
+// does not look like latin-1.
+
+use elp_ide_db::elp_base_db::encoding;
+use elp_ide_db::elp_base_db::encoding::Encoding;
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::elp_base_db::SourceDatabaseExt;
+use elp_ide_db::RootDatabase;
+use elp_syntax::TextRange;
+use elp_syntax::TextSize;
+
+use crate::diagnostics::DiagnosticCode;
+use crate::diagnostics::Severity;
+use crate::Diagnostic;
+
+pub(crate) fn encoding_mismatch(acc: &mut Vec<Diagnostic>, db: &RootDatabase, file_id: FileId) {
+    let text = db.file_text(file_id);
+    if let Some((range, Encoding::Latin1)) = encoding::declaration(&text) {
+        if let Some(reason) = encoding::latin1_mismatch(&text) {
+            let range = TextRange::new(
+                TextSize::from(range.start as u32),
+                TextSize::from(range.end as u32),
+            );
+            acc.push(
+                Diagnostic::new(
+                    DiagnosticCode::EncodingMismatch,
+                    format!("File is declared as latin-1, but its content {reason}"),
+                    range,
+                )
+                .severity(Severity::Warning),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::tests::check_diagnostics;
+
+    #[test]
+    fn latin1_content_ok() {
+        check_diagnostics(
+            r#"
+//- /src/foo.erl
+% coding: latin-1
+-module(foo).
+-export([f/0]).
+f() -> "é".
+"#,
+        );
+    }
+
+    #[test]
+    fn utf8_content_in_latin1_file() {
+        check_diagnostics(
+            r#"
+//- /src/foo.erl
+% -*- coding: latin-1 -*-
+%%    ^^^^^^^^^^^^^^^ warning: File is declared as latin-1, but its content appears to be UTF-8
+-module(foo).
+-export([f/0]).
+f() -> "Ã©".
+"#,
+        );
+    }
+
+    #[test]
+    fn non_latin1_chars_in_latin1_file() {
+        check_diagnostics(
+            r#"
+//- /src/foo.erl
+-module(foo). % -*- coding: latin-1 -*-
+%%                  ^^^^^^^^^^^^^^^ warning: File is declared as latin-1, but its content contains characters that cannot be represented in latin-1
+-export([f/0]).
+f() -> "€".
+"#,
+        );
+    }
+
+    #[test]
+    fn no_declaration() {
+        check_diagnostics(
+            r#"
+//- /src/foo.erl
+-module(foo).
+-export([f/0]).
+f() -> "Ã©".
+"#,
+        );
+    }
+}
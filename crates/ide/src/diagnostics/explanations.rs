@@ -0,0 +1,80 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! The long-form explanations of the diagnostic codes, written in
+//! markdown in the `explanations` directory, one file per code.
+//!
+//! Each one says what the diagnostic reports and why, gives an example
+//! of code triggering it, and how to fix it.
+
+pub(crate) fn explanation(code: &str) -> Option<&'static str> {
+    let explanation = match code {
+        "L1201" => include_str!("explanations/L1201.md"),
+        "L1500" => include_str!("explanations/L1500.md"),
+        "P1700" => include_str!("explanations/P1700.md"),
+        "P1711" => include_str!("explanations/P1711.md"),
+        "W0000" => include_str!("explanations/W0000.md"),
+        "W0001" => include_str!("explanations/W0001.md"),
+        "W0002" => include_str!("explanations/W0002.md"),
+        "W0003" => include_str!("explanations/W0003.md"),
+        "W0004" => include_str!("explanations/W0004.md"),
+        "W0005" => include_str!("explanations/W0005.md"),
+        "W0006" => include_str!("explanations/W0006.md"),
+        "W0007" => include_str!("explanations/W0007.md"),
+        "W0008" => include_str!("explanations/W0008.md"),
+        "W0009" => include_str!("explanations/W0009.md"),
+        "W0010" => include_str!("explanations/W0010.md"),
+        "W0011" => include_str!("explanations/W0011.md"),
+        "W0012" => include_str!("explanations/W0012.md"),
+        "W0013" => include_str!("explanations/W0013.md"),
+        "W0014" => include_str!("explanations/W0014.md"),
+        "W0015" => include_str!("explanations/W0015.md"),
+        "W0016" => include_str!("explanations/W0016.md"),
+        "W0017" => include_str!("explanations/W0017.md"),
+        "W0018" => include_str!("explanations/W0018.md"),
+        "W0019" => include_str!("explanations/W0019.md"),
+        "W0020" => include_str!("explanations/W0020.md"),
+        "W0021" => include_str!("explanations/W0021.md"),
+        "W0022" => include_str!("explanations/W0022.md"),
+        "W0023" => include_str!("explanations/W0023.md"),
+        "W0024" => include_str!("explanations/W0024.md"),
+        "W0025" => include_str!("explanations/W0025.md"),
+        "W0026" => include_str!("explanations/W0026.md"),
+        "W0027" => include_str!("explanations/W0027.md"),
+        "W0028" => include_str!("explanations/W0028.md"),
+        "W0029" => include_str!("explanations/W0029.md"),
+        "W0030" => include_str!("explanations/W0030.md"),
+        _ => return None,
+    };
+    Some(explanation)
+}
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+
+    use crate::diagnostics::DiagnosticCode;
+
+    #[test]
+    fn every_code_is_explained() {
+        let missing: Vec<String> = DiagnosticCode::iter()
+            .filter(|code| {
+                !matches!(
+                    code,
+                    DiagnosticCode::DefaultCodeForEnumIter
+                        | DiagnosticCode::ErlangService(_)
+                        | DiagnosticCode::AdHoc(_)
+                )
+            })
+            .filter(|code| code.explanation().is_none())
+            .map(|code| code.as_code())
+            .collect();
+        assert_eq!(missing, Vec::<String>::new());
+    }
+}
@@ -0,0 +1,337 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Lint/fix: guard_operators
+//!
+//! Return a diagnostic if the strict `and`/`or` operators combine side
+//! effect free type tests in a guard, where `andalso`/`orelse` or a
+//! guard sequence is intended, or if a guard test is an `andalso`
+//! chain which can be written with `,` separators. Offer to rewrite
+//! the operators as a fix.
+//!
+
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::source_change::SourceChange;
+use elp_syntax::ast;
+use elp_syntax::ast::BinaryOp;
+use elp_syntax::ast::LogicOp;
+use elp_syntax::ast::UnaryOp;
+use elp_syntax::AstNode;
+use elp_syntax::SyntaxKind;
+use elp_syntax::SyntaxNode;
+use elp_syntax::SyntaxToken;
+use elp_syntax::TextRange;
+use hir::InFile;
+use hir::Semantic;
+use text_edit::TextEdit;
+
+use super::Diagnostic;
+use super::Severity;
+use crate::diagnostics::DiagnosticCode;
+use crate::fix;
+use crate::guard_helpers::is_safe_boolean;
+use crate::guard_helpers::type_test;
+
+pub(crate) fn guard_operators(diags: &mut Vec<Diagnostic>, sema: &Semantic, file_id: FileId) {
+    let source_file = sema.parse(file_id);
+    for guard_clause in source_file
+        .value
+        .syntax()
+        .descendants()
+        .filter_map(ast::GuardClause::cast)
+    {
+        let tests: Vec<ast::Expr> = guard_clause.exprs().collect();
+        for test in &tests {
+            check_expr(diags, sema, file_id, test, tests.len());
+        }
+    }
+}
+
+/// Operands of a chain of the same logic operator, and the tokens of
+/// the operators. Parenthesised operands are not split.
+struct Chain {
+    op: LogicOp,
+    operands: Vec<ast::Expr>,
+    tokens: Vec<SyntaxToken>,
+}
+
+impl Chain {
+    fn new(expr: &ast::Expr) -> Option<Chain> {
+        let op = match logic_op(expr)? {
+            (op @ (LogicOp::And { .. } | LogicOp::Or { .. }), _) => op,
+            _ => return None,
+        };
+        let mut chain = Chain {
+            op,
+            operands: Vec::new(),
+            tokens: Vec::new(),
+        };
+        chain.add(expr);
+        Some(chain)
+    }
+
+    fn add(&mut self, expr: &ast::Expr) {
+        if let (Some((op, token)), ast::Expr::BinaryOpExpr(binary)) = (logic_op(expr), expr) {
+            if let (Some(lhs), Some(rhs)) = (binary.lhs(), binary.rhs()) {
+                if op == self.op {
+                    self.add(&lhs);
+                    self.tokens.push(token);
+                    self.add(&rhs);
+                    return;
+                }
+            }
+        }
+        self.operands.push(expr.clone());
+    }
+}
+
+/// `expr` is a guard test if `guard_len` is not zero, and `guard_len`
+/// is the number of tests in its guard.
+fn check_expr(
+    diags: &mut Vec<Diagnostic>,
+    sema: &Semantic,
+    file_id: FileId,
+    expr: &ast::Expr,
+    guard_len: usize,
+) {
+    let chain = match expr {
+        ast::Expr::ExprMax(ast::ExprMax::ParenExpr(paren)) => {
+            if let Some(expr) = paren.expr() {
+                check_expr(diags, sema, file_id, &expr, 0);
+            }
+            return;
+        }
+        ast::Expr::UnaryOpExpr(unary) => {
+            if let (Some((UnaryOp::Not, _)), Some(expr)) = (unary.op(), unary.operand()) {
+                check_expr(diags, sema, file_id, &expr, 0);
+            }
+            return;
+        }
+        _ => match Chain::new(expr) {
+            Some(chain) => chain,
+            None => return,
+        },
+    };
+    match chain.op {
+        LogicOp::And { lazy: false } | LogicOp::Or { lazy: false } => {
+            strict_chain(diags, sema, file_id, expr, &chain, guard_len);
+        }
+        LogicOp::And { lazy: true } if guard_len > 0 => {
+            andalso_chain(diags, file_id, expr, &chain);
+        }
+        _ => {}
+    }
+    for operand in &chain.operands {
+        check_expr(diags, sema, file_id, operand, 0);
+    }
+}
+
+/// Report a chain of `and` or `or` operators whose operands are side
+/// effect free tests, at least one of them a type test.
+fn strict_chain(
+    diags: &mut Vec<Diagnostic>,
+    sema: &Semantic,
+    file_id: FileId,
+    expr: &ast::Expr,
+    chain: &Chain,
+    guard_len: usize,
+) -> Option<()> {
+    let mut has_type_test = false;
+    for operand in &chain.operands {
+        let in_body = sema.to_expr(InFile::new(file_id, operand))?;
+        let body = in_body.body();
+        if !is_safe_boolean(sema, &body, in_body.value) {
+            return None;
+        }
+        has_type_test |= type_test(sema, &body, in_body.value).is_some();
+    }
+    if !has_type_test {
+        return None;
+    }
+
+    let (strict, lazy, separator, separator_name) = match chain.op {
+        LogicOp::And { .. } => ("and", "andalso", ",", "a `,` guard sequence"),
+        _ => ("or", "orelse", ";", "a `;` guard sequence"),
+    };
+    let range = expr.syntax().text_range();
+    let mut fixes = Vec::new();
+    if lazy_replaces(chain.op, expr.syntax()) {
+        fixes.push(fix(
+            "use_lazy_operator",
+            &format!("Replace `{strict}` with `{lazy}`"),
+            replace_tokens(file_id, &chain.tokens, lazy),
+            range,
+        ));
+    }
+    // `;` separates whole guards, so it can only replace a test which
+    // is alone in its guard
+    if guard_len == 1 || (guard_len > 0 && separator == ",") {
+        fixes.push(fix(
+            "use_guard_separator",
+            &format!("Replace `{strict}` with `{separator}`"),
+            replace_tokens(file_id, &chain.tokens, separator),
+            range,
+        ));
+    }
+    diags.push(
+        Diagnostic::new(
+            DiagnosticCode::GuardOperators,
+            format!(
+                "`{strict}` evaluates all its operands: `{lazy}` or {separator_name} \
+                 has the same result for these side effect free tests"
+            ),
+            range,
+        )
+        .severity(Severity::Warning)
+        .experimental()
+        .with_fixes((!fixes.is_empty()).then_some(fixes)),
+    );
+    Some(())
+}
+
+/// Report a guard test which is a chain of `andalso` operators.
+fn andalso_chain(diags: &mut Vec<Diagnostic>, file_id: FileId, expr: &ast::Expr, chain: &Chain) {
+    let range = expr.syntax().text_range();
+    diags.push(
+        Diagnostic::new(
+            DiagnosticCode::GuardOperators,
+            "`andalso` chain can be written as a `,` guard sequence, \
+             which fails the guard in the same cases",
+            range,
+        )
+        .severity(Severity::WeakWarning)
+        .experimental()
+        .with_fixes(Some(vec![fix(
+            "use_guard_separator",
+            "Replace `andalso` with `,`",
+            replace_tokens(file_id, &chain.tokens, ","),
+            range,
+        )])),
+    );
+}
+
+/// Whether replacing the strict operators of a chain by the lazy ones
+/// keeps the meaning of the expression, given that the lazy operators
+/// have a lower precedence.
+fn lazy_replaces(op: LogicOp, node: &SyntaxNode) -> bool {
+    let parent = match node.parent() {
+        Some(parent) => parent,
+        None => return false,
+    };
+    match parent.kind() {
+        SyntaxKind::GUARD_CLAUSE | SyntaxKind::PAREN_EXPR => true,
+        _ => match ast::Expr::cast(parent).as_ref().and_then(logic_op) {
+            Some((LogicOp::Or { lazy: true }, _)) => true,
+            Some((LogicOp::And { lazy: true }, _)) => op == LogicOp::And { lazy: false },
+            _ => false,
+        },
+    }
+}
+
+fn logic_op(expr: &ast::Expr) -> Option<(LogicOp, SyntaxToken)> {
+    match expr {
+        ast::Expr::BinaryOpExpr(binary) => match binary.op()? {
+            (BinaryOp::LogicOp(op), token) => Some((op, token)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Replace the operator tokens. A `,` or `;` separator also replaces
+/// the whitespace before the operator.
+fn replace_tokens(file_id: FileId, tokens: &[SyntaxToken], replacement: &str) -> SourceChange {
+    let is_separator = replacement == "," || replacement == ";";
+    let mut builder = TextEdit::builder();
+    for token in tokens {
+        let start = match token.prev_token() {
+            Some(prev) if is_separator && prev.kind() == SyntaxKind::WHITESPACE => {
+                prev.text_range().start()
+            }
+            _ => token.text_range().start(),
+        };
+        builder.replace(
+            TextRange::new(start, token.text_range().end()),
+            replacement.to_string(),
+        );
+    }
+    SourceChange::from_text_edit(file_id, builder.finish())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::tests::check_diagnostics;
+    use crate::tests::check_fix;
+
+    #[test]
+    fn strict_operators() {
+        check_diagnostics(
+            r#"
+-module(main).
+
+foo(X) when is_integer(X) and (X > 1) -> X;
+%%          ^^^^^^^^^^^^^^^^^^^^^^^^^ 💡 warning: `and` evaluates all its operands: `andalso` or a `,` guard sequence has the same result for these side effect free tests
+foo(X) when is_atom(X) or is_list(X) or (X =:= 1) -> X;
+%%          ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ 💡 warning: `or` evaluates all its operands: `orelse` or a `;` guard sequence has the same result for these side effect free tests
+foo(X) when (X > 1) and (X < 3) -> X;
+foo(X) when is_list(X) and (length(X) > 1) -> X.
+            "#,
+        );
+    }
+
+    #[test]
+    fn andalso_chain() {
+        check_diagnostics(
+            r#"
+-module(main).
+
+foo(X) when is_integer(X) andalso X > 1 andalso X < 3 -> X;
+%%          ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ 💡 weak: `andalso` chain can be written as a `,` guard sequence, which fails the guard in the same cases
+foo(X) when is_atom(X) orelse (is_integer(X) andalso X > 1) -> X;
+foo(X) ->
+    case X andalso true of
+        _ -> X
+    end.
+            "#,
+        );
+    }
+
+    #[test]
+    fn fix_lazy_operator() {
+        check_fix(
+            r#"
+-module(main).
+
+foo(X) when X =:= a orelse is_atom(X) o~r is_list(X) -> X.
+            "#,
+            r#"
+-module(main).
+
+foo(X) when X =:= a orelse is_atom(X) orelse is_list(X) -> X.
+            "#,
+        );
+    }
+
+    #[test]
+    fn fix_guard_separator() {
+        check_fix(
+            r#"
+-module(main).
+
+foo(X) when is_integer(X) andalso X > 1 ~andalso X < 3 -> X.
+            "#,
+            r#"
+-module(main).
+
+foo(X) when is_integer(X), X > 1, X < 3 -> X.
+            "#,
+        );
+    }
+}
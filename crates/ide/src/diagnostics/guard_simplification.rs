@@ -0,0 +1,490 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Lint/fix: guard_simplification
+//!
+//! Return a diagnostic if a guard test is always true or can never
+//! succeed, given the clause patterns and the preceding tests of the
+//! guard, or if a boolean operator in it is redundant, as in
+//! `is_integer(X) andalso is_integer(X)`. Offer to remove or simplify
+//! the test as a fix.
+//!
+
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::source_change::SourceChange;
+use elp_syntax::ast;
+use elp_syntax::ast::BinaryOp;
+use elp_syntax::ast::LogicOp;
+use elp_syntax::AstNode;
+use elp_syntax::SourceFile;
+use elp_syntax::SyntaxKind;
+use elp_syntax::TextRange;
+use hir::Body;
+use hir::Expr;
+use hir::ExprId;
+use hir::FunctionDef;
+use hir::InFile;
+use hir::InFunctionBody;
+use hir::PatId;
+use hir::Semantic;
+use text_edit::TextEdit;
+
+use super::Diagnostic;
+use super::Severity;
+use crate::codemod_helpers::statement_range;
+use crate::diagnostics::DiagnosticCode;
+use crate::fix;
+use crate::guard_helpers::bool_literal;
+use crate::guard_helpers::is_safe_boolean;
+use crate::guard_helpers::pattern_facts;
+use crate::guard_helpers::same_expr;
+use crate::guard_helpers::type_test;
+use crate::guard_helpers::Facts;
+
+pub(crate) fn guard_simplification(diags: &mut Vec<Diagnostic>, sema: &Semantic, file_id: FileId) {
+    sema.def_map(file_id)
+        .get_functions()
+        .iter()
+        .for_each(|(_arity, def)| {
+            if def.file.file_id == file_id {
+                process_function(diags, sema, def)
+            }
+        });
+}
+
+/// A clause with guards, from a function, `case`, `if`, etc.
+struct GuardedClause {
+    pats: Vec<PatId>,
+    guards: Vec<Vec<ExprId>>,
+    /// `if` clauses have no `when` keyword
+    is_if: bool,
+}
+
+struct Ctx<'a> {
+    sema: &'a Semantic<'a>,
+    def_fb: &'a InFunctionBody<&'a FunctionDef>,
+    body: &'a Body,
+    source_file: InFile<SourceFile>,
+    text: &'a str,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Simplified {
+    /// Always evaluates to this boolean. `false` also covers the case
+    /// where evaluation fails, since the guard fails either way.
+    Const(bool),
+    /// Equivalent, as a guard test, to this expression
+    Expr(ExprId),
+}
+
+fn process_function(diags: &mut Vec<Diagnostic>, sema: &Semantic, def: &FunctionDef) {
+    let def_fb = def.in_function_body(sema.db, def);
+    let body = def_fb.body();
+    let mut clauses: Vec<GuardedClause> = def_fb
+        .clauses()
+        .map(|(_, clause)| GuardedClause {
+            pats: clause.pats.clone(),
+            guards: clause.guards.clone(),
+            is_if: false,
+        })
+        .collect();
+    def_fb.fold_function(
+        (),
+        &mut |_acc, _, ctx| match ctx.expr {
+            Expr::Case { clauses: crs, .. }
+            | Expr::Receive { clauses: crs, .. }
+            | Expr::Maybe {
+                else_clauses: crs, ..
+            } => clauses.extend(crs.into_iter().map(|cr| GuardedClause {
+                pats: vec![cr.pat],
+                guards: cr.guards,
+                is_if: false,
+            })),
+            Expr::Try {
+                of_clauses,
+                catch_clauses,
+                ..
+            } => {
+                clauses.extend(of_clauses.into_iter().map(|cr| GuardedClause {
+                    pats: vec![cr.pat],
+                    guards: cr.guards,
+                    is_if: false,
+                }));
+                clauses.extend(catch_clauses.into_iter().map(|cc| {
+                    GuardedClause {
+                        pats: cc
+                            .class
+                            .into_iter()
+                            .chain([cc.reason])
+                            .chain(cc.stack)
+                            .collect(),
+                        guards: cc.guards,
+                        is_if: false,
+                    }
+                }));
+            }
+            Expr::If { clauses: ifs } => {
+                clauses.extend(ifs.into_iter().map(|clause| GuardedClause {
+                    pats: vec![],
+                    guards: clause.guards,
+                    is_if: true,
+                }))
+            }
+            Expr::Closure { clauses: cs, .. } => {
+                clauses.extend(cs.into_iter().map(|clause| GuardedClause {
+                    pats: clause.pats,
+                    guards: clause.guards,
+                    is_if: false,
+                }))
+            }
+            _ => {}
+        },
+        &mut |_acc, _, _| (),
+    );
+
+    let text = sema.db.file_text(def.file.file_id);
+    let ctx = Ctx {
+        sema,
+        def_fb: &def_fb,
+        body: &body,
+        source_file: sema.parse(def.file.file_id),
+        text: &text,
+    };
+    for clause in &clauses {
+        check_clause(diags, &ctx, clause);
+    }
+}
+
+fn check_clause(diags: &mut Vec<Diagnostic>, ctx: &Ctx, clause: &GuardedClause) {
+    let initial_facts = pattern_facts(ctx.sema, ctx.body, &clause.pats);
+    for guard in &clause.guards {
+        let mut facts = initial_facts.clone();
+        for test in guard {
+            let mut nested = Vec::new();
+            let res = simplify(ctx, &facts, *test, &mut nested);
+            match res {
+                // `true` on its own is the usual catch-all `if` clause
+                Simplified::Const(true) if bool_literal(ctx.sema, ctx.body, *test).is_none() => {
+                    always_true(diags, ctx, clause, guard, *test);
+                }
+                Simplified::Const(false) => {
+                    if let Some(range) = ctx.def_fb.range_for_expr(ctx.sema.db, *test) {
+                        diags.push(make_diagnostic(
+                            "Guard test can never succeed".to_string(),
+                            range,
+                            None,
+                        ));
+                    }
+                }
+                Simplified::Expr(simplified) if simplified != *test => {
+                    simplify_diagnostic(diags, ctx, *test, res);
+                }
+                _ => {
+                    for (expr_id, res) in nested {
+                        simplify_diagnostic(diags, ctx, expr_id, res);
+                    }
+                }
+            }
+            if let Some(type_test) = type_test(ctx.sema, ctx.body, *test) {
+                type_test.add_to(&mut facts);
+            }
+        }
+    }
+}
+
+/// Simplify a guard test given the facts known about its variables.
+/// Sub-expressions that could be simplified even though the whole
+/// expression can not are collected in `nested`.
+fn simplify(
+    ctx: &Ctx,
+    facts: &Facts,
+    expr_id: ExprId,
+    nested: &mut Vec<(ExprId, Simplified)>,
+) -> Simplified {
+    let body = ctx.body;
+    if let Some(value) = bool_literal(ctx.sema, body, expr_id) {
+        return Simplified::Const(value);
+    }
+    if let Some(test) = type_test(ctx.sema, body, expr_id) {
+        return match test.truth(facts) {
+            Some(value) => Simplified::Const(value),
+            None => Simplified::Expr(expr_id),
+        };
+    }
+    let (lhs, rhs, op) = match &body[expr_id] {
+        Expr::BinaryOp {
+            lhs,
+            rhs,
+            op: BinaryOp::LogicOp(op @ (LogicOp::And { .. } | LogicOp::Or { .. })),
+        } => (*lhs, *rhs, *op),
+        _ => return Simplified::Expr(expr_id),
+    };
+    let l = simplify(ctx, facts, lhs, nested);
+    let r = match (op, type_test(ctx.sema, body, lhs)) {
+        // The right hand side is only relevant if the left succeeded
+        (LogicOp::And { .. }, Some(test)) => {
+            let mut facts = facts.clone();
+            test.add_to(&mut facts);
+            simplify(ctx, &facts, rhs, nested)
+        }
+        _ => simplify(ctx, facts, rhs, nested),
+    };
+    let safe = |expr_id| is_safe_boolean(ctx.sema, body, expr_id);
+    let res = match (op, l, r) {
+        (LogicOp::And { .. }, Simplified::Const(false), _)
+        | (LogicOp::And { .. }, _, Simplified::Const(false)) => Simplified::Const(false),
+        (LogicOp::And { .. }, Simplified::Const(true), r) => r,
+        (LogicOp::And { .. }, l, Simplified::Const(true)) => l,
+        (LogicOp::Or { lazy }, Simplified::Const(true), _) if lazy || safe(rhs) => {
+            Simplified::Const(true)
+        }
+        (LogicOp::Or { .. }, _, Simplified::Const(true)) if safe(lhs) => Simplified::Const(true),
+        (LogicOp::Or { .. }, Simplified::Const(false), r) if safe(lhs) => r,
+        (LogicOp::Or { .. }, l, Simplified::Const(false)) => l,
+        (_, Simplified::Expr(a), Simplified::Expr(b)) if same_expr(body, a, b) => {
+            Simplified::Expr(a)
+        }
+        _ => Simplified::Expr(expr_id),
+    };
+    if matches!(res, Simplified::Expr(id) if id == expr_id) {
+        for (child, simplified) in [(lhs, l), (rhs, r)] {
+            let is_literal = bool_literal(ctx.sema, body, child).is_some();
+            if !is_literal && !matches!(simplified, Simplified::Expr(id) if id == child) {
+                nested.push((child, simplified));
+            }
+        }
+    }
+    res
+}
+
+/// Report a guard test that is always true, with a fix removing it.
+/// If it is the only test of its guard, the whole guard sequence is
+/// always true.
+fn always_true(
+    diags: &mut Vec<Diagnostic>,
+    ctx: &Ctx,
+    clause: &GuardedClause,
+    guard: &[ExprId],
+    test: ExprId,
+) -> Option<()> {
+    let range = ctx.def_fb.range_for_expr(ctx.sema.db, test)?;
+    let ast_test = expr_ast(ctx, test)?;
+    let (label, edit_range, replacement) = if guard.len() > 1 {
+        (
+            "Remove redundant guard test",
+            statement_range(&ast_test),
+            String::new(),
+        )
+    } else if clause.is_if {
+        ("Replace guard with `true`", range, "true".to_string())
+    } else {
+        let guard = ast_test.syntax().ancestors().find_map(ast::Guard::cast)?;
+        ("Remove redundant guard", when_range(&guard)?, String::new())
+    };
+    let mut builder = TextEdit::builder();
+    builder.replace(edit_range, replacement);
+    diags.push(make_diagnostic(
+        "Guard test is always true".to_string(),
+        range,
+        Some(fix(
+            "remove_redundant_guard",
+            label,
+            SourceChange::from_text_edit(ctx.source_file.file_id, builder.finish()),
+            range,
+        )),
+    ));
+    Some(())
+}
+
+fn simplify_diagnostic(
+    diags: &mut Vec<Diagnostic>,
+    ctx: &Ctx,
+    expr_id: ExprId,
+    simplified: Simplified,
+) -> Option<()> {
+    let range = ctx.def_fb.range_for_expr(ctx.sema.db, expr_id)?;
+    let replacement = match simplified {
+        Simplified::Const(value) => value.to_string(),
+        Simplified::Expr(id) => {
+            let range = ctx.def_fb.range_for_expr(ctx.sema.db, id)?;
+            ctx.text
+                .get(range.start().into()..range.end().into())?
+                .to_string()
+        }
+    };
+    let mut builder = TextEdit::builder();
+    builder.replace(range, replacement.clone());
+    diags.push(make_diagnostic(
+        format!("Guard test can be simplified to `{replacement}`"),
+        range,
+        Some(fix(
+            "simplify_guard",
+            "Simplify guard test",
+            SourceChange::from_text_edit(ctx.source_file.file_id, builder.finish()),
+            range,
+        )),
+    ));
+    Some(())
+}
+
+fn expr_ast(ctx: &Ctx, expr_id: ExprId) -> Option<ast::Expr> {
+    ctx.def_fb
+        .get_body_map(ctx.sema.db)
+        .expr(expr_id)?
+        .to_node(&ctx.source_file)
+}
+
+/// The range of ` when Guard`, including the whitespace before `when`.
+fn when_range(guard: &ast::Guard) -> Option<TextRange> {
+    let mut token = guard.syntax().first_token()?.prev_token()?;
+    while token.kind() != SyntaxKind::ANON_WHEN {
+        token = token.prev_token()?;
+    }
+    let start = match token.prev_token() {
+        Some(prev) if prev.kind() == SyntaxKind::WHITESPACE => prev.text_range().start(),
+        _ => token.text_range().start(),
+    };
+    Some(TextRange::new(start, guard.syntax().text_range().end()))
+}
+
+fn make_diagnostic(
+    message: String,
+    range: TextRange,
+    fix: Option<elp_ide_assists::Assist>,
+) -> Diagnostic {
+    Diagnostic::new(DiagnosticCode::GuardSimplification, message, range)
+        .severity(Severity::Warning)
+        .experimental()
+        .with_fixes(fix.map(|fix| vec![fix]))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::tests::check_diagnostics;
+    use crate::tests::check_fix;
+
+    #[test]
+    fn duplicate_operands() {
+        check_diagnostics(
+            r#"
+-module(main).
+
+foo(X) when is_integer(X) andalso is_integer(X) -> X;
+%%          ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ 💡 warning: Guard test can be simplified to `is_integer(X)`
+foo(X) when is_atom(X) orelse is_list(X) -> X.
+            "#,
+        );
+    }
+
+    #[test]
+    fn constant_operands() {
+        check_diagnostics(
+            r#"
+-module(main).
+
+foo(X) when X =:= a orelse true -> X;
+%%          ^^^^^^^^^^^^^^^^^^^ 💡 warning: Guard test is always true
+foo(X) when hd(X) =:= a orelse true -> X;
+foo(X) when X > 1 andalso false -> X;
+%%          ^^^^^^^^^^^^^^^^^^^ warning: Guard test can never succeed
+foo(X) when true andalso X > 1 -> X.
+%%          ^^^^^^^^^^^^^^^^^^ 💡 warning: Guard test can be simplified to `X > 1`
+            "#,
+        );
+    }
+
+    #[test]
+    fn type_tests() {
+        check_diagnostics(
+            r#"
+-module(main).
+
+foo(X = {_, _}) when is_list(X) -> X;
+%%                   ^^^^^^^^^^ warning: Guard test can never succeed
+foo(X = {_, _}) when is_tuple(X) -> X;
+%%                   ^^^^^^^^^^^ 💡 warning: Guard test is always true
+foo(X) when is_integer(X), is_number(X) -> X;
+%%                         ^^^^^^^^^^^^ 💡 warning: Guard test is always true
+foo(X) when is_integer(X), is_atom(X) -> X;
+%%                         ^^^^^^^^^^ warning: Guard test can never succeed
+foo(X) when is_number(X), is_integer(X) -> X;
+foo(X) when is_integer(X); is_float(X) -> X.
+            "#,
+        );
+    }
+
+    #[test]
+    fn nested_clauses() {
+        check_diagnostics(
+            r#"
+-module(main).
+
+foo(X) ->
+    case X of
+        [_ | _] = Y when is_list(Y) -> Y;
+        %%               ^^^^^^^^^^ 💡 warning: Guard test is always true
+        _ -> ok
+    end,
+    if
+        is_atom(X), X =:= a -> a;
+        true -> b
+    end.
+            "#,
+        );
+    }
+
+    #[test]
+    fn fix_removes_guard() {
+        check_fix(
+            r#"
+-module(main).
+
+foo(X = {_, _}) when is_tuple~(X) -> X.
+            "#,
+            r#"
+-module(main).
+
+foo(X = {_, _}) -> X.
+            "#,
+        );
+    }
+
+    #[test]
+    fn fix_removes_test() {
+        check_fix(
+            r#"
+-module(main).
+
+foo(X) when is_integer(X), is_num~ber(X) -> X.
+            "#,
+            r#"
+-module(main).
+
+foo(X) when is_integer(X) -> X.
+            "#,
+        );
+    }
+
+    #[test]
+    fn fix_simplifies() {
+        check_fix(
+            r#"
+-module(main).
+
+foo(X) when is_atom(X) andalso ~is_atom(X) -> X.
+            "#,
+            r#"
+-module(main).
+
+foo(X) when is_atom(X) -> X.
+            "#,
+        );
+    }
+}
@@ -191,6 +191,7 @@ impl Validate<String> for Name {
             attr_loc,
         )
         .with_related(Some(vec![RelatedInformation {
+            file_id: None,
             range: ref_loc,
             message: "Mismatched clause name".to_string(),
         }]))
@@ -226,6 +227,7 @@ impl Validate<usize> for Arity {
             attr_loc,
         )
         .with_related(Some(vec![RelatedInformation {
+            file_id: None,
             range: ref_loc,
             message: "Mismatched clause".to_string(),
         }]))
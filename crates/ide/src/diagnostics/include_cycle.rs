@@ -0,0 +1,192 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+// Diagnostic: include-cycle
+//
+// Return a warning on an `-include` or `-include_lib` of a header which
+// includes the file back, directly or not. The other includes of the
+// cycle are given as related information.
+
+use elp_ide_db::elp_base_db::FileId;
+use elp_syntax::AstNode;
+use elp_syntax::TextRange;
+use hir::db::MinDefDatabase;
+use hir::InFile;
+use itertools::Itertools;
+
+use super::Diagnostic;
+use super::RelatedInformation;
+use super::Severity;
+use crate::diagnostics::DiagnosticCode;
+use crate::include_graph::file_include_graph;
+use crate::RootDatabase;
+use crate::SourceDatabase;
+
+pub(crate) fn include_cycle(diags: &mut Vec<Diagnostic>, db: &RootDatabase, file_id: FileId) {
+    let form_list = db.file_form_list(file_id);
+    if form_list.includes().next().is_none() {
+        return;
+    }
+    let graph = file_include_graph(db, file_id);
+    for (include_idx, attr) in form_list.includes() {
+        let header = match db.resolve_include(InFile::new(file_id, include_idx)) {
+            Some(header) => header,
+            None => continue,
+        };
+        // From the header back to this file
+        let path = if header == file_id {
+            vec![file_id]
+        } else {
+            match graph.include_path(header, file_id) {
+                Some(path) => path,
+                None => continue,
+            }
+        };
+        let source_file = db.parse(file_id);
+        let range = attr
+            .form_id()
+            .get(&source_file.tree())
+            .syntax()
+            .text_range();
+        let cycle = std::iter::once(file_id)
+            .chain(path.iter().copied())
+            .map(|file_id| file_name(db, file_id))
+            .join(" -> ");
+        let related = path
+            .iter()
+            .tuple_windows()
+            .filter_map(|(&from, &to)| {
+                Some(RelatedInformation {
+                    file_id: Some(from),
+                    range: include_range(db, from, to)?,
+                    message: format!("`{}` includes `{}`", file_name(db, from), file_name(db, to)),
+                })
+            })
+            .collect::<Vec<_>>();
+        diags.push(
+            Diagnostic::new(
+                DiagnosticCode::IncludeCycle,
+                format!("Include cycle: {cycle}"),
+                range,
+            )
+            .severity(Severity::Warning)
+            .with_related(Some(related).filter(|related| !related.is_empty())),
+        );
+    }
+}
+
+/// The range of the attribute of `file_id` including `header`
+fn include_range(db: &RootDatabase, file_id: FileId, header: FileId) -> Option<TextRange> {
+    let form_list = db.file_form_list(file_id);
+    let (_, attr) = form_list
+        .includes()
+        .find(|(idx, _)| db.resolve_include(InFile::new(file_id, *idx)) == Some(header))?;
+    let source_file = db.parse(file_id);
+    Some(
+        attr.form_id()
+            .get(&source_file.tree())
+            .syntax()
+            .text_range(),
+    )
+}
+
+fn file_name(db: &RootDatabase, file_id: FileId) -> String {
+    let root = db.source_root(db.file_source_root(file_id));
+    root.path_for_file(&file_id)
+        .and_then(|path| path.name_and_extension())
+        .map(|(name, ext)| match ext {
+            Some(ext) => format!("{name}.{ext}"),
+            None => name.to_string(),
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use elp_ide_db::elp_base_db::fixture::WithFixture;
+    use elp_ide_db::elp_base_db::SourceDatabase;
+    use elp_ide_db::RootDatabase;
+
+    use crate::diagnostics;
+    use crate::diagnostics::DiagnosticCode;
+    use crate::diagnostics::DiagnosticsConfig;
+    use crate::tests::check_diagnostics;
+
+    #[test]
+    fn include_cycle() {
+        check_diagnostics(
+            r#"
+//- /src/a.hrl
+  -include("b.hrl").
+%%^^^^^^^^^^^^^^^^^^ warning: Include cycle: a.hrl -> b.hrl -> a.hrl
+//- /src/b.hrl
+  -include("a.hrl").
+%%^^^^^^^^^^^^^^^^^^ warning: Include cycle: b.hrl -> a.hrl -> b.hrl
+"#,
+        );
+    }
+
+    #[test]
+    fn no_include_cycle() {
+        check_diagnostics(
+            r#"
+//- /src/a.hrl
+-include("b.hrl").
+-include("c.hrl").
+//- /src/b.hrl
+-include("c.hrl").
+//- /src/c.hrl
+-define(C, c).
+"#,
+        );
+    }
+
+    #[test]
+    fn related_info_points_at_other_includes() {
+        let (db, files) = RootDatabase::with_many_files(
+            r#"
+//- /src/a.hrl
+-include("b.hrl").
+//- /src/b.hrl
+-include("c.hrl").
+//- /src/c.hrl
+-include("a.hrl").
+"#,
+        );
+        let config = DiagnosticsConfig::default();
+        let related = diagnostics::diagnostics(&db, &config, files[0], true)
+            .into_iter()
+            .filter(|d| d.code == DiagnosticCode::IncludeCycle)
+            .flat_map(|d| d.related_info.unwrap_or_default())
+            .map(|r| {
+                let file_id = r.file_id.unwrap();
+                (
+                    file_id,
+                    db.file_text(file_id)[r.range].to_string(),
+                    r.message,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            related,
+            vec![
+                (
+                    files[1],
+                    "-include(\"c.hrl\").".to_string(),
+                    "`b.hrl` includes `c.hrl`".to_string()
+                ),
+                (
+                    files[2],
+                    "-include(\"a.hrl\").".to_string(),
+                    "`c.hrl` includes `a.hrl`".to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,127 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Lint/fix: line_in_error_tuple
+//!
+//! Return a diagnostic for `?LINE` used in an error tuple, e.g.
+//! `{error, {?MODULE, ?LINE}}`. The line changes with any edit above
+//! it, which breaks the callers and tests matching the error, and it
+//! says little about where the error comes from once the file changed.
+//! The fix uses `?FUNCTION_NAME` instead.
+
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::source_change::SourceChange;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use fxhash::FxHashSet;
+use hir::Semantic;
+use text_edit::TextEdit;
+
+use super::Diagnostic;
+use super::Severity;
+use crate::diagnostics::DiagnosticCode;
+use crate::fix;
+
+pub(crate) fn line_in_error_tuple(diags: &mut Vec<Diagnostic>, sema: &Semantic, file_id: FileId) {
+    let source_file = sema.parse(file_id);
+    // Nested error tuples report the same `?LINE` once
+    let mut seen = FxHashSet::default();
+    for tuple in source_file
+        .value
+        .syntax()
+        .descendants()
+        .filter_map(ast::Tuple::cast)
+        .filter(is_error_tuple)
+    {
+        for macro_call in tuple
+            .syntax()
+            .descendants()
+            .filter_map(ast::MacroCallExpr::cast)
+            .filter(is_line)
+        {
+            let range = macro_call.syntax().text_range();
+            if !seen.insert(range) {
+                continue;
+            }
+            let edit = TextEdit::replace(range, "?FUNCTION_NAME".to_string());
+            let diag = Diagnostic::new(
+                DiagnosticCode::LineInErrorTuple,
+                "`?LINE` in an error tuple changes with unrelated edits, consider `?FUNCTION_NAME`",
+                range,
+            )
+            .severity(Severity::WeakWarning)
+            .with_fixes(Some(vec![fix(
+                "use_function_name",
+                "Use `?FUNCTION_NAME`",
+                SourceChange::from_text_edit(file_id, edit),
+                range,
+            )]));
+            diags.push(diag);
+        }
+    }
+}
+
+fn is_error_tuple(tuple: &ast::Tuple) -> bool {
+    tuple
+        .expr()
+        .next()
+        .map_or(false, |tag| tag.syntax().text() == "error")
+}
+
+fn is_line(macro_call: &ast::MacroCallExpr) -> bool {
+    macro_call.args().is_none()
+        && macro_call
+            .name()
+            .map_or(false, |name| name.syntax().text() == "LINE")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics;
+    use crate::tests::check_fix;
+
+    #[test]
+    fn line_in_error_tuples() {
+        check_diagnostics(
+            r#"
+-module(main).
+f(X) when X > 0 -> {error, ?LINE};
+%%                         ^^^^^ 💡 weak: `?LINE` in an error tuple changes with unrelated edits, consider `?FUNCTION_NAME`
+f(_) -> {error, {?MODULE, ?LINE}}.
+%%                        ^^^^^ 💡 weak: `?LINE` in an error tuple changes with unrelated edits, consider `?FUNCTION_NAME`
+            "#,
+        );
+    }
+
+    #[test]
+    fn line_outside_error_tuples() {
+        check_diagnostics(
+            r#"
+-module(main).
+f() -> {ok, ?LINE}.
+g() -> {error, ?FUNCTION_NAME}.
+h() -> io:format("~p~n", [?LINE]).
+            "#,
+        );
+    }
+
+    #[test]
+    fn use_function_name() {
+        check_fix(
+            r#"
+-module(main).
+f() -> {error, {?MODULE, ?LI~NE}}.
+            "#,
+            r#"
+-module(main).
+f() -> {error, {?MODULE, ?FUNCTION_NAME}}.
+            "#,
+        );
+    }
+}
@@ -0,0 +1,114 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+// Diagnostic: missing-generated-header
+//
+// Return a warning if an `-include_lib` refers to a header which the
+// project model says is generated by a build target, but which has
+// not been generated yet, naming the target to build.
+
+use elp_ide_db::elp_base_db::FileId;
+use elp_syntax::AstNode;
+use hir::db::MinDefDatabase;
+use hir::InFile;
+use hir::IncludeAttribute;
+
+use super::Diagnostic;
+use super::Severity;
+use crate::diagnostics::DiagnosticCode;
+use crate::RootDatabase;
+use crate::SourceDatabase;
+
+pub(crate) fn missing_generated_header(
+    diags: &mut Vec<Diagnostic>,
+    db: &RootDatabase,
+    file_id: FileId,
+) {
+    let form_list = db.file_form_list(file_id);
+    for (include_idx, attr) in form_list.includes() {
+        let path = match attr {
+            IncludeAttribute::IncludeLib { path, .. } => path,
+            IncludeAttribute::Include { .. } => continue,
+        };
+        if db
+            .resolve_include(InFile::new(file_id, include_idx))
+            .is_some()
+        {
+            continue;
+        }
+        if let Some(target) = generator(db, file_id, path) {
+            let source_file = db.parse(file_id);
+            let range = attr
+                .form_id()
+                .get(&source_file.tree())
+                .syntax()
+                .text_range();
+            diags.push(
+                Diagnostic::new(
+                    DiagnosticCode::MissingGeneratedHeader,
+                    format!("Generated header `{path}` not found, build `{target}` to generate it"),
+                    range,
+                )
+                .severity(Severity::Warning),
+            );
+        }
+    }
+}
+
+/// The target generating the header at the `-include_lib` `path`,
+/// according to the generated include dirs of the app it names.
+fn generator(db: &RootDatabase, file_id: FileId, path: &str) -> Option<String> {
+    let project_id = db.app_data(db.file_source_root(file_id))?.project_id;
+    let project_data = db.project_data(project_id);
+    let (app_name, path) = path.split_once('/')?;
+    let source_root_id = project_data.app_roots.get(app_name)?;
+    let app_data = db.app_data(source_root_id)?;
+    app_data
+        .include_remaps
+        .iter()
+        .find(|remap| !remap.remap(path).is_empty())
+        .map(|remap| remap.target.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics;
+
+    #[test]
+    fn missing_generated_header() {
+        check_diagnostics(
+            r#"
+//- /main/src/main.erl app:main
+  -module(main).
+  -include_lib("another/include/generated.hrl").
+%%^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ warning: Generated header `another/include/generated.hrl` not found, build `//another:headers` to generate it
+  -include_lib("another/src/missing.hrl").
+//- /another-app/src/another.erl app:another generated:/out/gen/another generator://another:headers
+-module(another).
+            "#,
+        );
+    }
+
+    #[test]
+    fn generated_header_found() {
+        check_diagnostics(
+            r#"
+//- /main/src/main.erl app:main
+-module(main).
+-include_lib("another/include/generated.hrl").
+-export([f/0]).
+f() -> ?GENERATED.
+//- /another-app/src/another.erl app:another generated:/out/gen/another generator://another:headers
+-module(another).
+//- /out/gen/another/generated.hrl app:another
+-define(GENERATED, 1).
+            "#,
+        );
+    }
+}
@@ -125,6 +125,7 @@ fn make_diagnostic(
         attr_name_range,
     )
     .with_related(Some(vec![RelatedInformation {
+        file_id: None,
         range: attr_name_range,
         message: "Misspelled attribute".to_string(),
     }]))
@@ -140,6 +141,7 @@ fn make_diagnostic(
 // cargo test --package elp_ide --lib
 #[cfg(test)]
 mod tests {
+    use fxhash::FxHashMap;
     use fxhash::FxHashSet;
 
     use crate::diagnostics::DiagnosticCode;
@@ -181,6 +183,7 @@ mod tests {
             disable_experimental: true,
             disabled: FxHashSet::default(),
             adhoc_semantic_diagnostics: vec![],
+            severity_overrides: FxHashMap::default(),
         };
         config
             .disabled
@@ -209,6 +212,7 @@ mod tests {
             disable_experimental: true,
             disabled: FxHashSet::default(),
             adhoc_semantic_diagnostics: vec![],
+            severity_overrides: FxHashMap::default(),
         };
         config
             .disabled
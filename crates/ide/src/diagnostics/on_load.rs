@@ -0,0 +1,147 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Lint/fix: on_load_function
+//!
+//! Return a diagnostic if the function named by the `-on_load`
+//! attribute does not exist, or does not have arity 0, and offer to
+//! create a stub for a missing one.
+//!
+
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::source_change::SourceChange;
+use elp_syntax::AstNode;
+use elp_syntax::TextSize;
+use hir::File;
+use hir::FunctionDef;
+use hir::Module;
+use hir::Semantic;
+use text_edit::TextEdit;
+
+use super::Diagnostic;
+use super::Severity;
+use crate::diagnostics::DiagnosticCode;
+use crate::fix;
+
+pub(crate) fn on_load_function(
+    diags: &mut Vec<Diagnostic>,
+    sema: &Semantic,
+    file_id: FileId,
+) -> Option<()> {
+    let module = Module {
+        file: File { file_id },
+    };
+    let (name, attribute) = module.on_load(sema.db)?;
+    let range = attribute.value()?.syntax().text_range();
+    if name.arity() != 0 {
+        diags.push(
+            Diagnostic::new(
+                DiagnosticCode::OnLoadFunction,
+                format!(
+                    "The on_load function must have arity 0, not {}",
+                    name.arity()
+                ),
+                range,
+            )
+            .severity(Severity::Error),
+        );
+    } else if sema.def_map(file_id).get_function(&name).is_none() {
+        let text = sema.db.file_text(file_id);
+        let stub = format!(
+            "{}\n{}() ->\n    ok.\n",
+            if text.ends_with('\n') { "" } else { "\n" },
+            name.name()
+        );
+        let edit = TextEdit::insert(TextSize::of(text.as_str()), stub);
+        diags.push(
+            Diagnostic::new(
+                DiagnosticCode::OnLoadFunction,
+                format!("The on_load function {} is not defined", name),
+                range,
+            )
+            .severity(Severity::Error)
+            .with_fixes(Some(vec![fix(
+                "create_on_load_function",
+                &format!("Create function {}", name),
+                SourceChange::from_text_edit(file_id, edit),
+                range,
+            )])),
+        );
+    }
+    Some(())
+}
+
+/// Whether the function is called by the runtime rather than by
+/// Erlang code: the `-on_load` function of its module, or a NIF stub.
+pub(crate) fn is_called_by_runtime(sema: &Semantic, def: &FunctionDef) -> bool {
+    let module = Module { file: def.file };
+    match module.on_load(sema.db) {
+        Some((on_load, _)) if on_load == def.function.name => true,
+        _ => def.is_nif_stub(sema.db),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics;
+    use crate::tests::check_fix;
+
+    #[test]
+    fn on_load_function_defined() {
+        check_diagnostics(
+            r#"
+            -module(main).
+            -on_load(init/0).
+            init() -> erlang:load_nif("./main_nif", 0).
+            "#,
+        );
+    }
+
+    #[test]
+    fn on_load_function_missing() {
+        check_diagnostics(
+            r#"
+            -module(main).
+            -on_load(init/0).
+            %%       ^^^^^^ 💡 error: The on_load function init/0 is not defined
+            "#,
+        );
+    }
+
+    #[test]
+    fn on_load_function_with_arguments() {
+        check_diagnostics(
+            r#"
+            -module(main).
+            -on_load(init/1).
+            %%       ^^^^^^ error: The on_load function must have arity 0, not 1
+            init(_) -> ok.
+            "#,
+        );
+    }
+
+    #[test]
+    fn create_on_load_function() {
+        check_fix(
+            r#"
+            -module(main).
+            -on_load(in~it/0).
+            foo() -> ok.
+            "#,
+            r#"
+            -module(main).
+            -on_load(init/0).
+            foo() -> ok.
+
+            init() ->
+                ok.
+            "#,
+        );
+    }
+}
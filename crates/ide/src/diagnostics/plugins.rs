@@ -0,0 +1,584 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Lint rules shipped outside of ELP, as WASI modules given in the
+//! `[[plugins]]` section of the `.elp_lint.toml` file of a project.
+//!
+//! Plugins are only run on request: by the server when a file is opened
+//! or saved, if enabled, and by `elp lint --plugins`. Each plugin is
+//! run once per file, with `wasmtime run <module>`. It reads a `FileView` of the file as JSON on its standard
+//! input, and prints a JSON array of `PluginDiagnostic` on its standard
+//! output. A plugin failing, or running longer than its timeout, is
+//! killed and logged, and its diagnostics dropped.
+//!
+//! The view is versioned, and only ever extended with new fields or
+//! expression kinds within a version.
+
+use std::io::Read;
+use std::io::Write;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use elp_ide_db::elp_base_db::FileId;
+use elp_project_model::lint_file::LintPluginConfig;
+use elp_syntax::AstNode;
+use elp_syntax::TextRange;
+use hir::CallTarget;
+use hir::Expr;
+use hir::ExprId;
+use hir::FunctionDef;
+use hir::InFile;
+use hir::InFunctionBody;
+use hir::Literal;
+use hir::Semantic;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::Diagnostic;
+use super::DiagnosticCode;
+use super::Severity;
+
+/// Version of the view given to plugins
+const VIEW_VERSION: u32 = 1;
+
+/// The view of a file given to plugins.
+#[derive(Debug, Serialize)]
+struct FileView {
+    version: u32,
+    module: Option<String>,
+    functions: Vec<FunctionView>,
+}
+
+#[derive(Debug, Serialize)]
+struct FunctionView {
+    name: String,
+    arity: u32,
+    exported: bool,
+    range: (u32, u32),
+    /// Every expression of the function outside of macro expansions,
+    /// outer ones first.
+    exprs: Vec<ExprView>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct ExprView {
+    /// The kind of expression, e.g. `call`, `atom` or `case`
+    kind: &'static str,
+    range: (u32, u32),
+    /// The called module, for remote calls and captures
+    #[serde(skip_serializing_if = "Option::is_none")]
+    module: Option<String>,
+    /// The called function, for calls and captures, or the name of a
+    /// variable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    arity: Option<u32>,
+    /// The value of an atom, string or integer literal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+}
+
+/// A diagnostic reported by a plugin. Its range is given as offsets
+/// in the file.
+#[derive(Debug, Deserialize)]
+struct PluginDiagnostic {
+    /// Defaults to the name of the plugin
+    code: Option<String>,
+    message: String,
+    range: (u32, u32),
+    #[serde(default)]
+    severity: PluginSeverity,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum PluginSeverity {
+    Error,
+    #[default]
+    Warning,
+    Weak,
+}
+
+pub(crate) fn plugin_diagnostics(diags: &mut Vec<Diagnostic>, sema: &Semantic, file_id: FileId) {
+    let project_data = match sema.db.app_data(sema.db.file_source_root(file_id)) {
+        Some(app_data) => sema.db.project_data(app_data.project_id),
+        None => return,
+    };
+    let plugins = &project_data.lint_file_config.plugins;
+    if plugins.is_empty() || sema.db.is_generated(file_id) {
+        return;
+    }
+    let input = match serde_json::to_vec(&file_view(sema, file_id)) {
+        Ok(input) => input,
+        Err(err) => {
+            log::error!("plugins: could not serialize the file view: {err}");
+            return;
+        }
+    };
+    let text = sema.db.file_text(file_id);
+    for plugin in plugins {
+        let module = project_data.root_dir.join(&plugin.module);
+        match run_plugin(plugin, module.as_ref(), input.clone()) {
+            Ok(found) => diags.extend(
+                found
+                    .into_iter()
+                    .filter_map(|diag| to_diagnostic(plugin, &text, diag)),
+            ),
+            Err(err) => log::warn!("plugins: {} failed: {err:#}", plugin.name),
+        }
+    }
+}
+
+fn to_diagnostic(
+    plugin: &LintPluginConfig,
+    text: &str,
+    diag: PluginDiagnostic,
+) -> Option<Diagnostic> {
+    let (start, end) = diag.range;
+    // Offsets past the end of the text are not char boundaries either
+    if start > end || !text.is_char_boundary(start as usize) || !text.is_char_boundary(end as usize)
+    {
+        log::warn!(
+            "plugins: {} reported a diagnostic out of the file: {:?}",
+            plugin.name,
+            diag
+        );
+        return None;
+    }
+    let code = diag.code.unwrap_or_else(|| plugin.name.clone());
+    let severity = match diag.severity {
+        PluginSeverity::Error => Severity::Error,
+        PluginSeverity::Warning => Severity::Warning,
+        PluginSeverity::Weak => Severity::WeakWarning,
+    };
+    Some(
+        Diagnostic::new(
+            DiagnosticCode::AdHoc(code),
+            diag.message,
+            TextRange::new(start.into(), end.into()),
+        )
+        .severity(severity),
+    )
+}
+
+/// Runs `plugin` on `input`, killing it when it times out.
+fn run_plugin(
+    plugin: &LintPluginConfig,
+    module: &std::path::Path,
+    input: Vec<u8>,
+) -> Result<Vec<PluginDiagnostic>> {
+    let (program, args) = match plugin.runner.split_first() {
+        Some(runner) => runner,
+        None => bail!("no runner"),
+    };
+    let mut child = Command::new(program)
+        .args(args)
+        .arg(module)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("could not run {program}"))?;
+
+    // Write and read from separate threads, so that a plugin neither
+    // reading its input nor exiting cannot block the diagnostics.
+    let mut stdin = child.stdin.take().context("no stdin")?;
+    let mut stdout = child.stdout.take().context("no stdout")?;
+    thread::spawn(move || stdin.write_all(&input));
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        let res = stdout.read_to_end(&mut output).map(|_| output);
+        let _ = sender.send(res);
+    });
+    let output = match receiver.recv_timeout(Duration::from_millis(plugin.timeout_ms)) {
+        Ok(output) => output?,
+        Err(_) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("timed out after {}ms", plugin.timeout_ms);
+        }
+    };
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("exited with {status}");
+    }
+    serde_json::from_slice(&output).context("invalid output")
+}
+
+fn file_view(sema: &Semantic, file_id: FileId) -> FileView {
+    let mut functions: Vec<FunctionView> = sema
+        .def_map(file_id)
+        .get_functions()
+        .iter()
+        .filter(|(_, def)| def.file.file_id == file_id)
+        .map(|(_, def)| function_view(sema, file_id, def))
+        .collect();
+    functions.sort_by_key(|function| function.range);
+    FileView {
+        version: VIEW_VERSION,
+        module: sema.module_name(file_id).map(|name| name.to_string()),
+        functions,
+    }
+}
+
+fn function_view(sema: &Semantic, file_id: FileId, def: &FunctionDef) -> FunctionView {
+    let function_id = InFile::new(file_id, def.function_id);
+    let function_body = sema.to_function_body(function_id);
+    let exprs = sema.fold_function(
+        function_id,
+        Vec::new(),
+        &mut |mut acc, _clause_id, ctx| {
+            if let Some(view) = expr_view(sema, &function_body, ctx.expr_id, &ctx.expr) {
+                acc.push(view);
+            }
+            acc
+        },
+        &mut |acc, _, _| acc,
+    );
+    FunctionView {
+        name: def.function.name.name().to_string(),
+        arity: def.function.name.arity(),
+        exported: def.exported,
+        range: offsets(def.source(sema.db.upcast()).syntax().text_range()),
+        exprs,
+    }
+}
+
+fn expr_view(
+    sema: &Semantic,
+    function_body: &InFunctionBody<()>,
+    expr_id: ExprId,
+    expr: &Expr,
+) -> Option<ExprView> {
+    let range = offsets(function_body.range_for_expr(sema.db, expr_id)?);
+    let atom = |id: ExprId| {
+        function_body[id]
+            .as_atom()
+            .map(|atom| sema.db.lookup_atom(atom).to_string())
+    };
+    let target = |target: &CallTarget<ExprId>| match target {
+        CallTarget::Local { name } => (None, atom(*name)),
+        CallTarget::Remote { module, name } => (atom(*module), atom(*name)),
+    };
+    let view = match expr {
+        Expr::Call { target: call, args } => {
+            let (module, name) = target(call);
+            ExprView {
+                kind: "call",
+                module,
+                name,
+                arity: Some(args.len() as u32),
+                ..Default::default()
+            }
+        }
+        Expr::CaptureFun { target: fun, arity } => {
+            let (module, name) = target(fun);
+            ExprView {
+                kind: "capture",
+                module,
+                name,
+                arity: match &function_body[*arity] {
+                    Expr::Literal(Literal::Integer(arity)) => u32::try_from(*arity).ok(),
+                    _ => None,
+                },
+                ..Default::default()
+            }
+        }
+        Expr::Literal(literal) => {
+            let (kind, value) = match literal {
+                Literal::Atom(atom) => ("atom", sema.db.lookup_atom(*atom).to_string()),
+                Literal::String(string) => ("string", string.clone()),
+                Literal::Char(char) => ("char", char.to_string()),
+                Literal::Integer(integer) => ("integer", integer.to_string()),
+                Literal::Float(float) => ("float", f64::from_bits(*float).to_string()),
+            };
+            ExprView {
+                kind,
+                value: Some(value),
+                ..Default::default()
+            }
+        }
+        Expr::Var(var) => ExprView {
+            kind: "var",
+            name: Some(sema.db.lookup_var(*var).to_string()),
+            ..Default::default()
+        },
+        _ => ExprView {
+            kind: expr_kind(expr),
+            ..Default::default()
+        },
+    };
+    Some(ExprView { range, ..view })
+}
+
+fn expr_kind(expr: &Expr) -> &'static str {
+    match expr {
+        Expr::Missing => "missing",
+        Expr::Literal(_) => "literal",
+        Expr::Var(_) => "var",
+        Expr::Match { .. } => "match",
+        Expr::Tuple { .. } => "tuple",
+        Expr::List { .. } => "list",
+        Expr::Binary { .. } => "binary",
+        Expr::UnaryOp { .. } => "unary_op",
+        Expr::BinaryOp { .. } => "binary_op",
+        Expr::Record { .. } => "record",
+        Expr::RecordUpdate { .. } => "record_update",
+        Expr::RecordIndex { .. } => "record_index",
+        Expr::RecordField { .. } => "record_field",
+        Expr::Map { .. } => "map",
+        Expr::MapUpdate { .. } => "map_update",
+        Expr::Catch { .. } => "catch",
+        Expr::MacroCall { .. } => "macro_call",
+        Expr::Call { .. } => "call",
+        Expr::Comprehension { .. } => "comprehension",
+        Expr::Block { .. } => "block",
+        Expr::If { .. } => "if",
+        Expr::Case { .. } => "case",
+        Expr::Receive { .. } => "receive",
+        Expr::Try { .. } => "try",
+        Expr::CaptureFun { .. } => "capture",
+        Expr::Closure { .. } => "closure",
+        Expr::Maybe { .. } => "maybe",
+    }
+}
+
+fn offsets(range: TextRange) -> (u32, u32) {
+    (range.start().into(), range.end().into())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use elp_ide_db::elp_base_db::fixture::WithFixture;
+    use elp_ide_db::RootDatabase;
+    use elp_project_model::lint_file::LintFileConfig;
+    use elp_project_model::lint_file::LintPluginConfig;
+    use expect_test::expect;
+    use hir::Semantic;
+
+    use super::file_view;
+    use crate::tests::check_plugin_diagnostics;
+
+    /// A plugin written as a shell script, run with `sh`.
+    fn plugin(name: &str, script: &str, timeout_ms: u64) -> LintPluginConfig {
+        let path: PathBuf =
+            std::env::temp_dir().join(format!("elp_plugin_{}_{}.sh", name, std::process::id()));
+        fs::write(&path, script).unwrap();
+        LintPluginConfig {
+            name: name.to_string(),
+            module: path.to_string_lossy().to_string(),
+            runner: vec!["sh".to_string()],
+            timeout_ms,
+        }
+    }
+
+    #[test]
+    fn view_of_functions() {
+        let (db, file_id) = RootDatabase::with_single_file(
+            r#"
+-module(main).
+-export([foo/1]).
+foo(X) -> io:format("~p", [X]), fun bar/0.
+bar() -> ok.
+"#,
+        );
+        let sema = Semantic::new(&db);
+        let mut view = serde_json::to_string_pretty(&file_view(&sema, file_id)).unwrap();
+        view.push('\n');
+        expect![[r#"
+            {
+              "version": 1,
+              "module": "main",
+              "functions": [
+                {
+                  "name": "foo",
+                  "arity": 1,
+                  "exported": true,
+                  "range": [
+                    33,
+                    75
+                  ],
+                  "exprs": [
+                    {
+                      "kind": "call",
+                      "range": [
+                        43,
+                        63
+                      ],
+                      "module": "io",
+                      "name": "format",
+                      "arity": 2
+                    },
+                    {
+                      "kind": "atom",
+                      "range": [
+                        43,
+                        45
+                      ],
+                      "value": "io"
+                    },
+                    {
+                      "kind": "atom",
+                      "range": [
+                        46,
+                        52
+                      ],
+                      "value": "format"
+                    },
+                    {
+                      "kind": "string",
+                      "range": [
+                        53,
+                        57
+                      ],
+                      "value": "~p"
+                    },
+                    {
+                      "kind": "list",
+                      "range": [
+                        59,
+                        62
+                      ]
+                    },
+                    {
+                      "kind": "var",
+                      "range": [
+                        60,
+                        61
+                      ],
+                      "name": "X"
+                    },
+                    {
+                      "kind": "capture",
+                      "range": [
+                        65,
+                        74
+                      ],
+                      "name": "bar",
+                      "arity": 0
+                    },
+                    {
+                      "kind": "atom",
+                      "range": [
+                        69,
+                        72
+                      ],
+                      "value": "bar"
+                    },
+                    {
+                      "kind": "integer",
+                      "range": [
+                        73,
+                        74
+                      ],
+                      "value": "0"
+                    }
+                  ]
+                },
+                {
+                  "name": "bar",
+                  "arity": 0,
+                  "exported": false,
+                  "range": [
+                    76,
+                    88
+                  ],
+                  "exprs": [
+                    {
+                      "kind": "atom",
+                      "range": [
+                        85,
+                        87
+                      ],
+                      "value": "ok"
+                    }
+                  ]
+                }
+              ]
+            }
+        "#]]
+        .assert_eq(&view);
+    }
+
+    #[test]
+    fn reports_plugin_diagnostics() {
+        let lint_file_config = LintFileConfig {
+            plugins: vec![plugin(
+                "no_foo",
+                r#"cat > /dev/null; echo '[{"message": "No foo", "range": [17, 20]}]'"#,
+                10_000,
+            )],
+            ..Default::default()
+        };
+        check_plugin_diagnostics(
+            lint_file_config,
+            r#"
+//- /src/main.erl
+-module(main).
+  foo() -> ok.
+%%^^^ warning: No foo
+            "#,
+        );
+    }
+
+    #[test]
+    fn drops_diagnostics_out_of_the_file() {
+        let lint_file_config = LintFileConfig {
+            plugins: vec![plugin(
+                "bad_ranges",
+                r#"cat > /dev/null; echo '[{"message": "Past the end", "range": [30, 40]}, {"message": "In a char", "range": [23, 24]}]'"#,
+                10_000,
+            )],
+            ..Default::default()
+        };
+        check_plugin_diagnostics(
+            lint_file_config,
+            r#"
+//- /src/main.erl
+-module(main).
+f() -> "é".
+            "#,
+        );
+    }
+
+    #[test]
+    fn drops_failing_or_slow_plugins() {
+        let lint_file_config = LintFileConfig {
+            plugins: vec![
+                plugin("failing", "cat > /dev/null; exit 1", 10_000),
+                plugin("invalid", "cat > /dev/null; echo 'not json'", 10_000),
+                plugin(
+                    "slow",
+                    r#"sleep 10; echo '[{"message": "Too late", "range": [0, 1]}]'"#,
+                    100,
+                ),
+            ],
+            ..Default::default()
+        };
+        check_plugin_diagnostics(
+            lint_file_config,
+            r#"
+//- /src/main.erl
+-module(main).
+foo() -> ok.
+            "#,
+        );
+    }
+}
@@ -23,6 +23,7 @@ use hir::FunctionDef;
 use hir::InFunctionBody;
 use hir::Literal;
 use hir::Semantic;
+use serde::Deserialize;
 use text_edit::TextEdit;
 
 use super::Diagnostic;
@@ -35,7 +36,6 @@ use crate::codemod_helpers::MFA;
 use crate::diagnostics::DiagnosticCode;
 use crate::fix;
 
-#[allow(dead_code)]
 pub fn replace_call_site(
     mfa: &FunctionMatch,
     replacement: Replacement,
@@ -102,8 +102,8 @@ pub fn replace_call_site_if_args_match(
         });
 }
 
-#[allow(dead_code)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Replacement {
     UseOk,
     UseCallArg(u32),
@@ -0,0 +1,257 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Lint/fix: spec_arg_name_mismatch
+//!
+//! Return a diagnostic if an annotated argument in a `-spec`, such as
+//! `Arg :: t()`, does not match the name of the corresponding
+//! parameter in any of the function clauses. The spec names end up in
+//! generated documentation, so they should agree with the code. When
+//! the clauses agree on a single name, offer to rename the spec
+//! variable to it.
+//!
+
+use elp_ide_assists::Assist;
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::source_change::SourceChange;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use elp_syntax::TextRange;
+use fxhash::FxHashSet;
+use hir::FunctionDef;
+use hir::Semantic;
+use hir::SpecDef;
+use text_edit::TextEdit;
+
+use super::Diagnostic;
+use super::Severity;
+use crate::diagnostics::DiagnosticCode;
+use crate::fix;
+
+pub(crate) fn spec_arg_name_mismatch(
+    diags: &mut Vec<Diagnostic>,
+    sema: &Semantic,
+    file_id: FileId,
+) {
+    let def_map = sema.def_map(file_id);
+    for (name, spec_def) in def_map.get_specs() {
+        if spec_def.file.file_id != file_id {
+            continue;
+        }
+        if let Some(fun_def) = def_map.get_function(name) {
+            check_spec(diags, sema, file_id, spec_def, fun_def);
+        }
+    }
+}
+
+fn check_spec(
+    diags: &mut Vec<Diagnostic>,
+    sema: &Semantic,
+    file_id: FileId,
+    spec_def: &SpecDef,
+    fun_def: &FunctionDef,
+) -> Option<()> {
+    let arity = fun_def.function.name.arity() as usize;
+    let param_names = param_names(&fun_def.source(sema.db.upcast()), arity);
+    let spec = spec_def.source(sema.db.upcast());
+    for sig in spec.sigs() {
+        let sig_vars = sig_vars(&sig);
+        for (idx, arg) in sig.args()?.args().enumerate() {
+            let names = match param_names.get(idx) {
+                Some(names) if !names.is_empty() => names,
+                _ => continue,
+            };
+            if let ast::Expr::AnnType(ann) = arg {
+                let var = ann.var()?.var()?;
+                let spec_name = var.syntax().text().to_string();
+                if names.contains(strip_underscore(&spec_name)) {
+                    continue;
+                }
+                let range = var.syntax().text_range();
+                let (message, fixes) = if names.len() == 1 {
+                    let param_name = names.iter().next()?;
+                    let fixes = if sig_vars.contains(param_name) {
+                        None
+                    } else {
+                        Some(vec![rename_spec_var(
+                            file_id, &sig, &spec_name, param_name, range,
+                        )])
+                    };
+                    (
+                        format!(
+                            "Spec argument `{spec_name}` does not match the parameter name `{param_name}`"
+                        ),
+                        fixes,
+                    )
+                } else {
+                    (
+                        format!(
+                            "Spec argument `{spec_name}` does not match any parameter name in the function clauses"
+                        ),
+                        None,
+                    )
+                };
+                diags.push(
+                    Diagnostic::new(DiagnosticCode::SpecArgNameMismatch, message, range)
+                        .severity(Severity::WeakWarning)
+                        .with_fixes(fixes),
+                );
+            }
+        }
+    }
+    Some(())
+}
+
+/// For each argument position, the names of the clause parameters
+/// that are plain variables, ignoring any leading underscore.
+fn param_names(fun: &ast::FunDecl, arity: usize) -> Vec<FxHashSet<String>> {
+    let mut res = vec![FxHashSet::default(); arity];
+    for clause in fun.clauses() {
+        if let ast::FunctionOrMacroClause::FunctionClause(clause) = clause {
+            if let Some(args) = clause.args() {
+                for (idx, arg) in args.args().enumerate() {
+                    if let (Some(names), ast::Expr::ExprMax(ast::ExprMax::Var(var))) =
+                        (res.get_mut(idx), arg)
+                    {
+                        let name = var.syntax().text().to_string();
+                        let name = strip_underscore(&name);
+                        if !name.is_empty() {
+                            names.insert(name.to_string());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    res
+}
+
+fn sig_vars(sig: &ast::TypeSig) -> FxHashSet<String> {
+    sig.syntax()
+        .descendants()
+        .filter_map(ast::Var::cast)
+        .map(|var| var.syntax().text().to_string())
+        .collect()
+}
+
+fn strip_underscore(name: &str) -> &str {
+    name.trim_start_matches('_')
+}
+
+fn rename_spec_var(
+    file_id: FileId,
+    sig: &ast::TypeSig,
+    old_name: &str,
+    new_name: &str,
+    range: TextRange,
+) -> Assist {
+    let mut builder = TextEdit::builder();
+    sig.syntax()
+        .descendants()
+        .filter_map(ast::Var::cast)
+        .filter(|var| var.syntax().text() == old_name)
+        .for_each(|var| builder.replace(var.syntax().text_range(), new_name.to_string()));
+    fix(
+        "rename_spec_var",
+        &format!("Rename spec variable to `{new_name}`"),
+        SourceChange::from_text_edit(file_id, builder.finish()),
+        range,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::tests::check_diagnostics;
+    use crate::tests::check_fix;
+
+    #[test]
+    fn matching_names() {
+        check_diagnostics(
+            r#"
+-module(main).
+-export([f/2]).
+-spec f(Name :: atom(), _Opts :: list()) -> atom().
+f(Name, _Opts) -> Name.
+"#,
+        );
+    }
+
+    #[test]
+    fn mismatched_name() {
+        check_diagnostics(
+            r#"
+-module(main).
+-export([f/1]).
+-spec f(Key :: atom()) -> atom().
+%%      ^^^ 💡 weak: Spec argument `Key` does not match the parameter name `Name`
+f(Name) -> Name.
+"#,
+        );
+    }
+
+    #[test]
+    fn clauses_disagree() {
+        check_diagnostics(
+            r#"
+-module(main).
+-export([f/1]).
+-spec f(Key :: atom()) -> atom().
+%%      ^^^ weak: Spec argument `Key` does not match any parameter name in the function clauses
+f(Name) when is_atom(Name) -> Name;
+f(Other) -> Other.
+"#,
+        );
+    }
+
+    #[test]
+    fn non_variable_params_ignored() {
+        check_diagnostics(
+            r#"
+-module(main).
+-export([f/1]).
+-spec f(Key :: atom()) -> atom().
+f(foo) -> foo;
+f(_) -> bar.
+"#,
+        );
+    }
+
+    #[test]
+    fn no_fix_when_name_taken() {
+        check_diagnostics(
+            r#"
+-module(main).
+-export([f/2]).
+-spec f(Key :: atom(), Name :: atom()) -> atom().
+%%      ^^^ weak: Spec argument `Key` does not match the parameter name `Name`
+%%                     ^^^^ weak: Spec argument `Name` does not match the parameter name `Key`
+f(Name, Key) -> {Name, Key}.
+"#,
+        );
+    }
+
+    #[test]
+    fn fix_renames_all_occurrences() {
+        check_fix(
+            r#"
+-module(main).
+-export([f/1]).
+-spec f(K~ey :: T) -> T when Key :: atom(), T :: term().
+f(Name) -> Name.
+"#,
+            r#"
+-module(main).
+-export([f/1]).
+-spec f(Name :: T) -> T when Name :: atom(), T :: term().
+f(Name) -> Name.
+"#,
+        );
+    }
+}
@@ -0,0 +1,412 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Lint/fix: swallowed_exception
+//!
+//! Return an experimental diagnostic for an exception handler catching
+//! every class of exception, whose body neither uses the class, reason
+//! or stacktrace, nor logs or re-raises the exception, so that errors
+//! disappear silently. The fixes bind the exception and log it with
+//! `?LOG_ERROR`, or re-raise it.
+//!
+
+use elp_ide_assists::Assist;
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::source_change::SourceChange;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use elp_syntax::SyntaxNode;
+use elp_syntax::TextRange;
+use elp_syntax::TextSize;
+use fxhash::FxHashSet;
+use hir::IncludeAttribute;
+use hir::Semantic;
+use text_edit::TextEdit;
+
+use super::catch_all::is_top_level;
+use super::catch_all::reraises;
+use super::Diagnostic;
+use super::RelatedInformation;
+use super::Severity;
+use crate::diagnostics::DiagnosticCode;
+use crate::fix;
+
+/// Prefixes of the calls and macros logging a message
+const LOGGERS: &[&str] = &["logger:", "error_logger:", "lager:", "io:format", "?LOG"];
+
+pub(crate) fn swallowed_exception(diags: &mut Vec<Diagnostic>, sema: &Semantic, file_id: FileId) {
+    let source_file = sema.parse(file_id);
+    for clause in source_file
+        .value
+        .syntax()
+        .descendants()
+        .filter_map(ast::CatchClause::cast)
+    {
+        if let Some(diag) = check_clause(sema, file_id, &clause) {
+            diags.push(diag);
+        }
+    }
+}
+
+fn check_clause(sema: &Semantic, file_id: FileId, clause: &ast::CatchClause) -> Option<Diagnostic> {
+    let class = match clause.class()?.class()? {
+        ast::Name::Var(var) => var,
+        _ => return None,
+    };
+    let reason = match clause.pat()? {
+        ast::CatchPat::ExprMax(ast::ExprMax::Var(var)) => var,
+        _ => return None,
+    };
+    let body = clause.body()?;
+    if clause.guard().is_some() || is_top_level(clause) || reraises(clause) || logs(&body) {
+        return None;
+    }
+    let used = var_names(body.syntax());
+    let stack = clause.stack().and_then(|stack| stack.class());
+    let is_used = |var: &ast::Var| {
+        let name = var.syntax().text().to_string();
+        name != "_" && used.contains(&name)
+    };
+    if is_used(&class) || is_used(&reason) || stack.as_ref().map_or(false, is_used) {
+        return None;
+    }
+
+    let range = TextRange::new(
+        class.syntax().text_range().start(),
+        match &stack {
+            Some(stack) => stack.syntax().text_range().end(),
+            None => reason.syntax().text_range().end(),
+        },
+    );
+    let related = clause
+        .syntax()
+        .ancestors()
+        .find_map(ast::TryExpr::cast)
+        .and_then(|try_expr| {
+            let first = try_expr.exprs().next()?.syntax().text_range();
+            let last = try_expr.exprs().last()?.syntax().text_range();
+            Some(vec![RelatedInformation {
+                file_id: None,
+                range: first.cover(last),
+                message: "Exceptions raised here are discarded".to_string(),
+            }])
+        });
+    let diag = Diagnostic::new(
+        DiagnosticCode::SwallowedException,
+        "Exception discarded without being logged or re-raised",
+        range,
+    )
+    .severity(Severity::Warning)
+    .experimental()
+    .with_related(related);
+
+    let fixes = exception_fixes(sema, file_id, clause, &body, range);
+    Some(diag.with_fixes(fixes))
+}
+
+fn logs(body: &ast::ClauseBody) -> bool {
+    let is_logger = |callee: String| LOGGERS.iter().any(|prefix| callee.starts_with(prefix));
+    body.syntax().descendants().any(|node| {
+        if let Some(call) = ast::Call::cast(node.clone()) {
+            call.expr().map_or(false, |callee| {
+                is_logger(callee.syntax().text().to_string())
+            })
+        } else if let Some(macro_call) = ast::MacroCallExpr::cast(node) {
+            is_logger(format!(
+                "?{}",
+                macro_call
+                    .name()
+                    .map_or(String::new(), |name| name.syntax().text().to_string())
+            ))
+        } else {
+            false
+        }
+    })
+}
+
+fn var_names(node: &SyntaxNode) -> FxHashSet<String> {
+    node.descendants()
+        .filter_map(ast::Var::cast)
+        .map(|var| var.syntax().text().to_string())
+        .collect()
+}
+
+/// The fixes binding the exception to fresh variables, then logging or
+/// re-raising it.
+fn exception_fixes(
+    sema: &Semantic,
+    file_id: FileId,
+    clause: &ast::CatchClause,
+    body: &ast::ClauseBody,
+    range: TextRange,
+) -> Option<Vec<Assist>> {
+    let function = clause
+        .syntax()
+        .ancestors()
+        .find_map(ast::FunctionClause::cast)?;
+    let mut used = var_names(function.syntax());
+    let mut fresh = |base: &str| {
+        let name = (0..)
+            .map(|i| {
+                if i == 0 {
+                    base.to_string()
+                } else {
+                    format!("{base}{i}")
+                }
+            })
+            .find(|name| !used.contains(name))
+            .unwrap_or_default();
+        used.insert(name.clone());
+        name
+    };
+    let (class, reason, stack) = (fresh("Class"), fresh("Reason"), fresh("Stacktrace"));
+    let pattern = format!("{class}:{reason}:{stack}");
+    let first = body.exprs().next()?;
+    let last = body.exprs().last()?;
+    let text = sema.db.file_text(file_id);
+
+    let mut log = TextEdit::builder();
+    log.replace(range, pattern.clone());
+    log.insert(
+        first.syntax().text_range().start(),
+        format!(
+            "?LOG_ERROR(#{{class => {class}, reason => {reason}, stacktrace => {stack}}}),{}",
+            separator(&text, first.syntax().text_range().start())
+        ),
+    );
+    if let Some((offset, include)) = logger_include(sema, file_id) {
+        log.insert(offset, include);
+    }
+
+    let mut raise = TextEdit::builder();
+    raise.replace(range, pattern);
+    raise.insert(
+        last.syntax().text_range().end(),
+        format!(
+            ",{}erlang:raise({class}, {reason}, {stack})",
+            separator(&text, last.syntax().text_range().start())
+        ),
+    );
+
+    Some(vec![
+        fix(
+            "log_exception",
+            "Log the exception",
+            SourceChange::from_text_edit(file_id, log.finish()),
+            range,
+        ),
+        fix(
+            "reraise_exception",
+            "Re-raise the exception",
+            SourceChange::from_text_edit(file_id, raise.finish()),
+            range,
+        ),
+    ])
+}
+
+/// A new line with the indentation of the expression at `offset` if it
+/// starts its line, a space otherwise.
+fn separator(text: &str, offset: TextSize) -> String {
+    let line_start = text[..usize::from(offset)]
+        .rfind('\n')
+        .map_or(0, |it| it + 1);
+    let indent = &text[line_start..usize::from(offset)];
+    if indent.chars().all(|c| c == ' ' || c == '\t') {
+        format!("\n{indent}")
+    } else {
+        " ".to_string()
+    }
+}
+
+/// The include of the header defining `?LOG_ERROR`, with where to add
+/// it, unless the file already includes it.
+fn logger_include(sema: &Semantic, file_id: FileId) -> Option<(TextSize, String)> {
+    let form_list = sema.db.file_form_list(file_id);
+    let source = sema.parse(file_id).value;
+    let mut last_include = None;
+    for (_, include) in form_list.includes() {
+        let path = match include {
+            IncludeAttribute::Include { path, .. } => path,
+            IncludeAttribute::IncludeLib { path, .. } => path,
+        };
+        if path.ends_with("logger.hrl") {
+            return None;
+        }
+        last_include = Some(include.form_id().get(&source).syntax().text_range().end());
+    }
+    let offset = match last_include {
+        Some(offset) => offset,
+        None => form_list
+            .module_attribute()?
+            .form_id
+            .get(&source)
+            .syntax()
+            .text_range()
+            .end(),
+    };
+    Some((
+        offset,
+        "\n-include_lib(\"kernel/include/logger.hrl\").".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use elp_ide_db::elp_base_db::fixture::WithFixture;
+    use elp_ide_db::elp_base_db::SourceDatabaseExt;
+    use elp_ide_db::RootDatabase;
+
+    use crate::diagnostics;
+    use crate::diagnostics::DiagnosticCode;
+    use crate::diagnostics::DiagnosticsConfig;
+    use crate::tests::check_diagnostics;
+    use crate::tests::check_fix;
+    use crate::tests::check_nth_fix;
+
+    #[test]
+    fn swallowed() {
+        check_diagnostics(
+            r#"
+-module(main).
+f() ->
+    try g()
+    catch
+        _:_ -> error
+%%      ^^^ warning: Catching all exceptions, consider catching the expected ones only
+%%      ^^^ 💡 warning: Exception discarded without being logged or re-raised
+    end.
+h() ->
+    try g()
+    catch
+        Class:_Reason:_St -> error
+%%      ^^^^^^^^^^^^^^^^^ 💡 warning: Exception discarded without being logged or re-raised
+    end.
+g() -> ok.
+            "#,
+        );
+    }
+
+    #[test]
+    fn used_logged_or_narrowed() {
+        check_diagnostics(
+            r#"
+-module(main).
+-define(LOG_WARNING(Msg), logger:warning(Msg)).
+f() ->
+    try g()
+    catch
+        error:_ -> error;
+        Class:Reason when Class =/= exit -> {error, Reason}
+    end,
+    try g()
+    catch
+        _:Reason:St -> {error, Reason, St}
+%%      ^^^^^^^^^^^ warning: Catching all exceptions, consider catching the expected ones only
+    end,
+    try g()
+    catch
+        Kind:_ -> logger:error("g failed: ~p", [Kind]), error
+    end,
+    try g()
+    catch
+        _:_:_ -> ?LOG_WARNING("g failed"), error
+%%      ^^^^^ warning: Catching all exceptions, consider catching the expected ones only
+    end.
+g() -> ok.
+            "#,
+        );
+    }
+
+    #[test]
+    fn log_exception() {
+        check_fix(
+            r#"
+-module(main).
+f(Reason) ->
+    try g(Reason)
+    catch
+        _:~_ ->
+            error
+    end.
+g(_) -> ok.
+            "#,
+            r#"
+-module(main).
+-include_lib("kernel/include/logger.hrl").
+f(Reason) ->
+    try g(Reason)
+    catch
+        Class:Reason1:Stacktrace ->
+            ?LOG_ERROR(#{class => Class, reason => Reason1, stacktrace => Stacktrace}),
+            error
+    end.
+g(_) -> ok.
+            "#,
+        );
+    }
+
+    #[test]
+    fn reraise_exception() {
+        check_nth_fix(
+            1,
+            r#"
+-module(main).
+f() ->
+    try g()
+    catch
+        _:~_ -> error
+    end.
+g() -> ok.
+            "#,
+            r#"
+-module(main).
+f() ->
+    try g()
+    catch
+        Class:Reason:Stacktrace -> error, erlang:raise(Class, Reason, Stacktrace)
+    end.
+g() -> ok.
+            "#,
+            DiagnosticsConfig::default(),
+        );
+    }
+
+    #[test]
+    fn related_info_points_at_try_body() {
+        let (db, file_id) = RootDatabase::with_single_file(
+            r#"
+-module(main).
+f() ->
+    try
+        g(),
+        g()
+    catch
+        _:_ -> error
+    end.
+g() -> ok.
+            "#,
+        );
+        let config =
+            DiagnosticsConfig::default().disable(DiagnosticCode::MissingCompileWarnMissingSpec);
+        let text = db.file_text(file_id);
+        let related = diagnostics::diagnostics(&db, &config, file_id, true)
+            .into_iter()
+            .filter(|d| d.code == DiagnosticCode::SwallowedException)
+            .flat_map(|d| d.related_info.unwrap_or_default())
+            .map(|r| (text[r.range].to_string(), r.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            related,
+            vec![(
+                "g(),\n        g()".to_string(),
+                "Exceptions raised here are discarded".to_string()
+            )]
+        );
+    }
+}
@@ -0,0 +1,165 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Lint: undefined_function
+//!
+//! Return a diagnostic for remote calls to a function not exported by
+//! the called module, as reported by `xref`.
+//!
+
+use elp_ide_db::elp_base_db::FileId;
+use hir::known;
+use hir::CallTarget;
+use hir::Expr;
+use hir::ExprId;
+use hir::InFile;
+use hir::InFunctionBody;
+use hir::NameArity;
+use hir::Semantic;
+
+use super::Diagnostic;
+use crate::diagnostics::DiagnosticCode;
+use crate::diagnostics::Severity;
+
+pub(crate) fn undefined_function(diags: &mut Vec<Diagnostic>, sema: &Semantic, file_id: FileId) {
+    if sema.db.is_generated(file_id) {
+        return;
+    }
+    let def_map = sema.def_map(file_id);
+    for (_name, def) in def_map.get_functions() {
+        if def.file.file_id != file_id {
+            continue;
+        }
+        let function_id = InFile::new(file_id, def.function_id);
+        let function_body = sema.to_function_body(function_id);
+        sema.fold_function(
+            function_id,
+            (),
+            &mut |acc, _clause_id, ctx| {
+                if let Expr::Call {
+                    target: CallTarget::Remote { module, name },
+                    args,
+                } = ctx.expr
+                {
+                    if let Some(diag) = check_call(
+                        sema,
+                        file_id,
+                        &function_body,
+                        ctx.expr_id,
+                        (module, name, args.len() as u32),
+                    ) {
+                        diags.push(diag);
+                    }
+                }
+                acc
+            },
+            &mut |acc, _, _| acc,
+        );
+    }
+}
+
+fn check_call(
+    sema: &Semantic,
+    file_id: FileId,
+    function_body: &InFunctionBody<()>,
+    call: ExprId,
+    (module, name, arity): (ExprId, ExprId, u32),
+) -> Option<Diagnostic> {
+    let module_name = sema.db.lookup_atom(function_body[module].as_atom()?);
+    let fun_name = sema.db.lookup_atom(function_body[name].as_atom()?);
+    if fun_name == known::module_info && (arity == 0 || arity == 1) {
+        // Added to every module by the compiler
+        return None;
+    }
+    let target = sema.resolve_module_name(file_id, module_name.as_str())?;
+    let target_def_map = sema.def_map(target.file.file_id);
+    if target_def_map.parse_transform {
+        // The parse transform may add functions we know nothing about
+        return None;
+    }
+    let na = NameArity::new(fun_name, arity);
+    if target_def_map.is_function_exported(&na) {
+        return None;
+    }
+    let range = function_body.range_for_expr(sema.db, call)?;
+    let message = format!("Function '{}:{}' is undefined.", module_name, na);
+    Some(
+        Diagnostic::new(DiagnosticCode::UndefinedFunction, message, range)
+            .severity(Severity::Warning),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_xref_diagnostics;
+
+    #[test]
+    fn not_exported() {
+        check_xref_diagnostics(
+            r#"
+//- /src/main.erl
+-module(main).
+-export([main/0]).
+%%       ^^^^^^ 💡 weak: Exported function 'main/0' is not used anywhere in the project.
+main() ->
+    other:exported(),
+    other:local(),
+%%  ^^^^^^^^^^^^^ warning: Function 'other:local/0' is undefined.
+    other:missing(1).
+%%  ^^^^^^^^^^^^^^^^ warning: Function 'other:missing/1' is undefined.
+//- /src/other.erl
+-module(other).
+-export([exported/0]).
+exported() -> local().
+local() -> ok.
+            "#,
+        );
+    }
+
+    #[test]
+    fn unknown_module_and_dynamic_calls() {
+        check_xref_diagnostics(
+            r#"
+//- /src/main.erl
+-module(main).
+-export([main/1]).
+%%       ^^^^^^ 💡 weak: Exported function 'main/1' is not used anywhere in the project.
+main(M) ->
+    unknown_module:foo(),
+    M:foo(),
+    other:module_info(),
+    other:module_info(md5).
+//- /src/other.erl
+-module(other).
+            "#,
+        );
+    }
+
+    #[test]
+    fn export_all_and_parse_transform() {
+        check_xref_diagnostics(
+            r#"
+//- /src/main.erl
+-module(main).
+-export([main/0]).
+%%       ^^^^^^ 💡 weak: Exported function 'main/0' is not used anywhere in the project.
+main() ->
+    other:local(),
+    transformed:generated().
+//- /src/other.erl
+-module(other).
+-compile(export_all).
+local() -> ok.
+//- /src/transformed.erl
+-module(transformed).
+-compile({parse_transform, my_transform}).
+            "#,
+        );
+    }
+}
@@ -0,0 +1,111 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Lint: undefined_table_key
+//!
+//! Return an experimental diagnostic when a literal ETS table or
+//! `persistent_term` key is read, but never created anywhere in the
+//! project.
+//!
+
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::table_keys::TableKeyAccess;
+use elp_ide_db::TableKeyDatabase;
+
+use super::Diagnostic;
+use super::Severity;
+use crate::diagnostics::DiagnosticCode;
+use crate::RootDatabase;
+use crate::SourceDatabase;
+
+pub(crate) fn undefined_table_key(
+    diags: &mut Vec<Diagnostic>,
+    db: &RootDatabase,
+    file_id: FileId,
+) -> Option<()> {
+    let project_id = db.app_data(db.file_source_root(file_id))?.project_id;
+    let index = db.table_key_index(project_id);
+    for key_ref in db.file_table_keys(file_id).iter() {
+        if key_ref.access == TableKeyAccess::Read && !index.is_created(&key_ref.key) {
+            diags.push(
+                Diagnostic::new(
+                    DiagnosticCode::UndefinedTableKey,
+                    format!(
+                        "Table or persistent_term key '{}' is not created in the project",
+                        key_ref.key
+                    ),
+                    key_ref.range,
+                )
+                .severity(Severity::Warning)
+                .experimental(),
+            );
+        }
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use elp_project_model::lint_file::LintFileConfig;
+    use elp_project_model::lint_file::TableKeysConfig;
+
+    use crate::tests::check_diagnostics;
+    use crate::tests::check_diagnostics_with_lint_file_config;
+
+    #[test]
+    fn created_in_another_module() {
+        check_diagnostics(
+            r#"
+//- /src/main.erl
+-module(main).
+init() -> ets:new(my_table, [named_table]).
+//- /src/other.erl
+-module(other).
+get(K) -> ets:lookup(my_table, K).
+            "#,
+        );
+    }
+
+    #[test]
+    fn never_created() {
+        check_diagnostics(
+            r#"
+//- /src/main.erl
+-module(main).
+get(K) -> ets:lookup(my_table, K).
+%%                   ^^^^^^^^ warning: Table or persistent_term key 'my_table' is not created in the project
+count() -> persistent_term:get(counter).
+%%                             ^^^^^^^ warning: Table or persistent_term key 'counter' is not created in the project
+with_default() -> persistent_term:get(counter, 0).
+dynamic(T) -> ets:lookup(T, key).
+            "#,
+        );
+    }
+
+    #[test]
+    fn configured_constructor() {
+        let lint_file_config = LintFileConfig {
+            table_keys: TableKeysConfig {
+                constructors: vec!["my_cache:new/2".to_string()],
+                readers: vec!["my_cache:get".to_string()],
+            },
+            ..Default::default()
+        };
+        check_diagnostics_with_lint_file_config(
+            lint_file_config,
+            r#"
+//- /src/main.erl
+-module(main).
+init() -> my_cache:new(sessions, []).
+get(K) -> ets:lookup(sessions, K), my_cache:get(users, K).
+%%                                              ^^^^^ warning: Table or persistent_term key 'users' is not created in the project
+            "#,
+        );
+    }
+}
@@ -0,0 +1,269 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Lint: unused_exported_function
+//!
+//! Return a diagnostic for exported functions that are not referenced
+//! anywhere in the project, as reported by `xref` for `exports_not_used`.
+//!
+
+use elp_ide_assists::Assist;
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::source_change::SourceChange;
+use elp_ide_db::ReferenceIndexDatabase;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use elp_syntax::SyntaxKind;
+use elp_syntax::TextRange;
+use fxhash::FxHashSet;
+use hir::db::MinDefDatabase;
+use hir::NameArity;
+use hir::Semantic;
+use text_edit::TextEdit;
+
+use super::on_load::is_called_by_runtime;
+use super::Diagnostic;
+use crate::diagnostics::DiagnosticCode;
+use crate::diagnostics::Severity;
+use crate::fix;
+use crate::RootDatabase;
+use crate::SourceDatabase;
+
+pub(crate) fn unused_exported_function(
+    diags: &mut Vec<Diagnostic>,
+    db: &RootDatabase,
+    sema: &Semantic,
+    file_id: FileId,
+) -> Option<()> {
+    if db.is_generated(file_id) || db.is_test_suite_or_test_helper(file_id) == Some(true) {
+        return None;
+    }
+    let project_id = db.app_data(db.file_source_root(file_id))?.project_id;
+    let form_list = db.file_form_list(file_id);
+    let module = form_list.module_attribute()?.name.clone();
+    if module.ends_with("_SUITE") {
+        // Common Test suites are called by the test framework
+        return None;
+    }
+    let callbacks = implemented_callbacks(sema, file_id)?;
+    let index = db.function_reference_index(project_id);
+    let def_map = sema.def_map(file_id);
+    let source = db.parse(file_id).tree();
+
+    for (_, export) in form_list.exports() {
+        let export_ast = export.form_id.get(&source);
+        let funs: Vec<ast::Fa> = export_ast.funs().collect();
+        for entry in export.entries.clone() {
+            let name = &form_list[entry].name;
+            if callbacks.contains(name)
+                || is_test_entry_point(name)
+                || index.is_referenced(&module, name)
+                || def_map
+                    .get_function(name)
+                    .map_or(false, |def| is_called_by_runtime(sema, def))
+            {
+                continue;
+            }
+            let idx = form_list[entry].idx as usize;
+            if let Some(fa) = funs.get(idx) {
+                let delete_range = if funs.len() == 1 {
+                    extend_range_to_newline(export_ast.syntax())
+                } else if let Some(next) = funs.get(idx + 1) {
+                    TextRange::new(
+                        fa.syntax().text_range().start(),
+                        next.syntax().text_range().start(),
+                    )
+                } else {
+                    TextRange::new(
+                        funs[idx - 1].syntax().text_range().end(),
+                        fa.syntax().text_range().end(),
+                    )
+                };
+                diags.push(make_diagnostic(
+                    file_id,
+                    fa.syntax().text_range(),
+                    delete_range,
+                    name,
+                ));
+            }
+        }
+    }
+    Some(())
+}
+
+/// The callbacks of all the behaviours the module implements. Returns
+/// `None` if one of the behaviours cannot be found, as we then cannot
+/// tell which exports are called by it.
+fn implemented_callbacks(sema: &Semantic, file_id: FileId) -> Option<FxHashSet<NameArity>> {
+    let form_list = sema.db.file_form_list(file_id);
+    let mut res = FxHashSet::default();
+    for (_, behaviour) in form_list.behaviour_attributes() {
+        let module = sema.resolve_module_name(file_id, behaviour.name.as_str())?;
+        let def_map = sema.def_map(module.file.file_id);
+        res.extend(def_map.get_callbacks().keys().cloned());
+    }
+    Some(res)
+}
+
+/// EUnit calls `*_test/0` and `*_test_/0` functions itself.
+fn is_test_entry_point(name: &NameArity) -> bool {
+    name.arity() == 0 && (name.name().ends_with("_test") || name.name().ends_with("_test_"))
+}
+
+fn extend_range_to_newline(syntax: &elp_syntax::SyntaxNode) -> TextRange {
+    let range = syntax.text_range();
+    match syntax.last_token().and_then(|token| token.next_token()) {
+        Some(next) if next.kind() == SyntaxKind::WHITESPACE && next.text().starts_with('\n') => {
+            TextRange::new(range.start(), range.end() + elp_syntax::TextSize::from(1))
+        }
+        _ => range,
+    }
+}
+
+fn make_diagnostic(
+    file_id: FileId,
+    range: TextRange,
+    delete_range: TextRange,
+    name: &NameArity,
+) -> Diagnostic {
+    Diagnostic::new(
+        DiagnosticCode::UnusedExportedFunction,
+        format!("Exported function '{name}' is not used anywhere in the project."),
+        range,
+    )
+    .severity(Severity::WeakWarning)
+    .with_fixes(Some(vec![remove_export(
+        file_id,
+        delete_range,
+        range,
+        name,
+    )]))
+}
+
+fn remove_export(
+    file_id: FileId,
+    delete_range: TextRange,
+    target: TextRange,
+    name: &NameArity,
+) -> Assist {
+    let mut builder = TextEdit::builder();
+    builder.delete(delete_range);
+    let edit = builder.finish();
+    fix(
+        "remove_unused_export",
+        &format!("Remove '{name}' from the export list"),
+        SourceChange::from_text_edit(file_id, edit),
+        target,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_xref_diagnostics;
+    use crate::tests::check_xref_fix;
+
+    #[test]
+    fn unused_exports() {
+        check_xref_diagnostics(
+            r#"
+//- /src/main.erl
+-module(main).
+-export([used/0, unused/1, captured/0, applied/1, local/0]).
+%%               ^^^^^^^^ 💡 weak: Exported function 'unused/1' is not used anywhere in the project.
+used() -> ok.
+unused(_) -> ok.
+captured() -> ok.
+applied(_) -> ok.
+local() -> used().
+//- /src/other.erl
+-module(other).
+-export([run/0]).
+%%       ^^^^^ 💡 weak: Exported function 'run/0' is not used anywhere in the project.
+run() ->
+    main:used(),
+    F = fun main:captured/0,
+    F(),
+    erlang:apply(main, applied, [1]),
+    main:local().
+            "#,
+        );
+    }
+
+    #[test]
+    fn callbacks_and_tests_are_ignored() {
+        check_xref_diagnostics(
+            r#"
+//- /src/my_behaviour.erl
+-module(my_behaviour).
+-callback init(term()) -> ok.
+//- /src/impl.erl
+-module(impl).
+-behaviour(my_behaviour).
+-export([init/1, simple_test/0, gen_test_/0]).
+init(_) -> ok.
+simple_test() -> ok.
+gen_test_() -> [].
+//- /src/nif.erl
+-module(nif).
+-export([init/0, add/2]).
+-on_load(init/0).
+init() -> erlang:load_nif("./nif", 0).
+add(_A, _B) -> erlang:nif_error(not_loaded).
+//- /src/unknown_behaviour.erl
+-module(unknown_behaviour).
+-behaviour(not_in_project).
+-export([init/1]).
+init(_) -> ok.
+//- /test/main_SUITE.erl
+-module(main_SUITE).
+-export([all/0]).
+all() -> [].
+            "#,
+        );
+    }
+
+    #[test]
+    fn remove_export_entry() {
+        check_xref_fix(
+            r#"
+//- /src/main.erl
+-module(main).
+-export([used/0, un~used/0]).
+used() -> ok.
+unused() -> ok.
+//- /src/other.erl
+-module(other).
+-export([run/0]).
+run() -> main:used(), run().
+            "#,
+            r#"
+-module(main).
+-export([used/0]).
+used() -> ok.
+unused() -> ok.
+"#,
+        );
+    }
+
+    #[test]
+    fn remove_whole_export_attribute() {
+        check_xref_fix(
+            r#"
+//- /src/main.erl
+-module(main).
+-export([un~used/0]).
+unused() -> ok.
+            "#,
+            r#"
+-module(main).
+unused() -> ok.
+"#,
+        );
+    }
+}
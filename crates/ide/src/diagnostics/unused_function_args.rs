@@ -39,7 +39,7 @@ pub(crate) fn unused_function_args(diags: &mut Vec<Diagnostic>, sema: &Semantic,
         .get_functions()
         .iter()
         .for_each(|(_arity, def)| {
-            if def.file.file_id != file_id {
+            if def.file.file_id != file_id || def.is_nif_stub(sema.db) {
                 return;
             }
             let source_file = sema.parse(file_id);
@@ -184,6 +184,19 @@ mod tests {
     use crate::tests::check_diagnostics;
     use crate::tests::check_fix;
 
+    #[test]
+    fn nif_stubs_are_ignored() {
+        check_diagnostics(
+            r#"
+            -module(main).
+            -on_load(init/0).
+            init() -> erlang:load_nif("./main_nif", 0).
+            add(A, B) -> erlang:nif_error({not_loaded, A, B}).
+            sub(X, Y) -> erlang:nif_error(not_loaded).
+            "#,
+        );
+    }
+
     #[test]
     fn check_diagnostic_unused_unprefixed_variables() {
         check_diagnostics(
@@ -218,6 +231,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn check_diagnostic_unused_variables_map_comprehension() {
+        check_diagnostics(
+            r#"
+            -module(main).
+            do_something(Map, Unused, Factor) ->
+                     %%%      ^^^^^^ 💡 warning: this variable is unused
+                #{K => V * Factor || K := V <- Map}.
+            "#,
+        );
+    }
+
     #[test]
     fn check_prefixes_unused_unprefixed_variables() {
         check_fix(
@@ -32,7 +32,7 @@ pub(crate) fn unused_macro(
     file_id: FileId,
     ext: Option<&str>,
 ) -> Option<()> {
-    if Some("erl") == ext {
+    if matches!(ext, Some("erl") | Some("escript")) {
         let def_map = sema.def_map(file_id);
         for (name, def) in def_map.get_macros() {
             // Only run the check for macros defined in the local module,
@@ -26,7 +26,7 @@ pub(crate) fn unused_record_field(
     file_id: FileId,
     ext: Option<&str>,
 ) -> Option<()> {
-    if Some("erl") == ext {
+    if matches!(ext, Some("erl") | Some("escript")) {
         let def_map = sema.def_map(file_id);
         for (name, def) in def_map.get_records() {
             // Only run the check for records defined in the local module,
@@ -7,18 +7,30 @@
  * of this source tree.
  */
 
+use elp_ide_db::elp_base_db::FileId;
 use elp_ide_db::elp_base_db::FilePosition;
 use elp_ide_db::RootDatabase;
 use elp_ide_db::SymbolClass;
 use elp_ide_db::SymbolDefinition;
+use elp_syntax::ast;
+use elp_syntax::ast::is_erlang_fun;
 use elp_syntax::AstNode;
+use elp_syntax::SyntaxToken;
 use hir::InFile;
+use hir::Name;
+use hir::NameArity;
 use hir::Semantic;
 
-const OTP_BASE_URL: &str = "https://erlang.org";
+/// The base URL of the OTP documentation, unless configured otherwise,
+/// e.g. to use an offline mirror.
+pub(crate) const OTP_DOCS_BASE_URL: &str = "https://erlang.org";
 
 /// Retrieve a link to documentation for the given symbol.
-pub(crate) fn external_docs(db: &RootDatabase, position: &FilePosition) -> Option<Vec<String>> {
+pub(crate) fn external_docs(
+    db: &RootDatabase,
+    position: &FilePosition,
+    base_url: &str,
+) -> Option<Vec<String>> {
     let sema = Semantic::new(db);
     let source_file = sema.parse(position.file_id);
 
@@ -29,51 +41,87 @@ pub(crate) fn external_docs(db: &RootDatabase, position: &FilePosition) -> Optio
         .syntax()
         .token_at_offset(position.offset)
         .left_biased()?;
+    let token = InFile::new(position.file_id, token);
 
-    let doc_links = SymbolClass::classify(&sema, InFile::new(position.file_id, token))?
-        .into_iter()
-        .filter_map(|def| doc_links(&sema, def))
-        .flatten()
-        .collect();
+    let doc_links = match SymbolClass::classify(&sema, token.clone()) {
+        Some(class) => class
+            .into_iter()
+            .filter_map(|def| doc_link(&sema, def, base_url))
+            .collect(),
+        None => bif_doc_link(&sema, token, base_url).into_iter().collect(),
+    };
     Some(doc_links)
 }
 
-fn doc_links(sema: &Semantic, def: SymbolDefinition) -> Option<Vec<String>> {
+fn doc_link(sema: &Semantic, def: SymbolDefinition, base_url: &str) -> Option<String> {
     match def {
-        SymbolDefinition::Module(module) => {
-            if module.is_in_otp(sema.db) {
-                let url = format!("{}/doc/man/{}.html", OTP_BASE_URL, module.name(sema.db));
-                Some(vec![url])
-            } else {
-                None
-            }
+        SymbolDefinition::Module(module) if module.is_in_otp(sema.db) => {
+            module_url(sema, module.file.file_id, base_url)
         }
-        SymbolDefinition::Function(function_def) => {
-            if function_def.is_in_otp(sema.db) {
-                let module_name = sema.module_name(function_def.file.file_id)?;
-                let url = format!(
-                    "{}/doc/man/{}.html#{}-{}",
-                    OTP_BASE_URL,
-                    module_name.as_str(),
-                    function_def.function.name.name(),
-                    function_def.function.name.arity()
-                );
-                Some(vec![url])
-            } else {
-                None
-            }
+        SymbolDefinition::Function(function_def) if function_def.is_in_otp(sema.db) => {
+            let url = module_url(sema, function_def.file.file_id, base_url)?;
+            Some(format!(
+                "{}#{}-{}",
+                url,
+                function_def.function.name.name(),
+                function_def.function.name.arity()
+            ))
+        }
+        SymbolDefinition::Type(type_alias) if type_alias.is_in_otp(sema.db) => {
+            let url = module_url(sema, type_alias.file.file_id, base_url)?;
+            Some(format!("{}#type-{}", url, type_alias.name().name()))
         }
         _ => None,
     }
 }
 
+/// The page of an OTP module is found under the name of its app.
+fn module_url(sema: &Semantic, file_id: FileId, base_url: &str) -> Option<String> {
+    let app_name = sema.db.file_app_name(file_id)?;
+    let module_name = sema.module_name(file_id)?;
+    Some(format!(
+        "{}/doc/apps/{}/{}.html",
+        base_url.trim_end_matches('/'),
+        app_name,
+        module_name.as_str()
+    ))
+}
+
+/// A call of an auto-imported BIF does not resolve to a definition, as
+/// the module does not define nor import it, so it is looked up in the
+/// `erlang` module.
+fn bif_doc_link(sema: &Semantic, token: InFile<SyntaxToken>, base_url: &str) -> Option<String> {
+    let atom = ast::Atom::cast(token.value.parent()?)?;
+    let call = ast::Call::cast(atom.syntax().parent()?)?;
+    if call.expr()?.syntax() != atom.syntax() {
+        return None;
+    }
+    let name = atom.text()?;
+    let arity = call.args()?.args().count();
+    if !is_erlang_fun(&name, arity) {
+        return None;
+    }
+    let erlang = sema.resolve_module_name(token.file_id, "erlang")?;
+    let name_arity = NameArity::new(Name::from_erlang_service(&name), arity as u32);
+    let function_def = sema
+        .db
+        .def_map(erlang.file.file_id)
+        .get_function(&name_arity)?
+        .clone();
+    doc_link(sema, SymbolDefinition::Function(function_def), base_url)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::fixture;
 
     fn check(fixture: &str, expected_links: Vec<&str>) {
         let (analysis, position) = fixture::position(fixture);
-        let actual_links = analysis.external_docs(position).ok().unwrap().unwrap();
+        let actual_links = analysis
+            .external_docs(position, super::OTP_DOCS_BASE_URL)
+            .ok()
+            .unwrap()
+            .unwrap();
         assert_eq!(actual_links, expected_links);
     }
 
@@ -91,7 +139,7 @@ reverse([]) -> [].
 a() ->
   list~s:reverse([]).
         "#,
-            vec!["https://erlang.org/doc/man/lists.html"],
+            vec!["https://erlang.org/doc/apps/stdlib/lists.html"],
         )
     }
 
@@ -127,7 +175,7 @@ reverse([]) -> [].
 a() ->
   lists:rev~erse([]).
         "#,
-            vec!["https://erlang.org/doc/man/lists.html#reverse-1"],
+            vec!["https://erlang.org/doc/apps/stdlib/lists.html#reverse-1"],
         )
     }
 
@@ -148,4 +196,86 @@ a() ->
             vec![],
         )
     }
+
+    #[test]
+    fn otp_type_doc_links() {
+        check(
+            r#"
+//- /opt/lib/stdlib-3.17/src/sets.erl otp_app:/opt/lib/stdlib-3.17
+-module(sets).
+-export_type([set/1]).
+-opaque set(Element) :: [Element].
+
+//- /src/two.erl
+-module(two).
+-spec a() -> sets:s~et(atom()).
+a() -> [].
+        "#,
+            vec!["https://erlang.org/doc/apps/stdlib/sets.html#type-set"],
+        )
+    }
+
+    #[test]
+    fn otp_bif_doc_links() {
+        check(
+            r#"
+//- /opt/lib/erts-13.0/src/erlang.erl otp_app:/opt/lib/erts-13.0
+-module(erlang).
+-export([length/1]).
+length(_List) -> erlang:nif_error(undefined).
+
+//- /src/two.erl
+-module(two).
+a(L) ->
+  len~gth(L).
+        "#,
+            vec!["https://erlang.org/doc/apps/erts/erlang.html#length-1"],
+        )
+    }
+
+    #[test]
+    fn local_function_shadowing_bif_doc_links() {
+        check(
+            r#"
+//- /opt/lib/erts-13.0/src/erlang.erl otp_app:/opt/lib/erts-13.0
+-module(erlang).
+-export([length/1]).
+length(_List) -> erlang:nif_error(undefined).
+
+//- /src/two.erl
+-module(two).
+-compile({no_auto_import, [length/1]}).
+a(L) ->
+  len~gth(L).
+length(_) -> 0.
+        "#,
+            vec![],
+        )
+    }
+
+    #[test]
+    fn otp_doc_links_base_url() {
+        let (analysis, position) = fixture::position(
+            r#"
+//- /opt/lib/stdlib-3.17/src/lists.erl otp_app:/opt/lib/stdlib-3.17
+-module(lists).
+-export([reverse/1]).
+reverse([]) -> [].
+
+//- /src/two.erl
+-module(two).
+a() ->
+  lists:rev~erse([]).
+        "#,
+        );
+        let links = analysis
+            .external_docs(position, "file:///usr/share/doc/otp/")
+            .ok()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            links,
+            vec!["file:///usr/share/doc/otp/doc/apps/stdlib/lists.html#reverse-1"]
+        );
+    }
 }
@@ -0,0 +1,199 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::RootDatabase;
+use elp_syntax::AstNode;
+use elp_syntax::NodeOrToken;
+use elp_syntax::SyntaxKind;
+use elp_syntax::TextRange;
+use elp_syntax::TextSize;
+use hir::Semantic;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+#[derive(Clone, Debug, Default)]
+pub struct DocumentLinksConfig {
+    pub issue_patterns: Vec<IssuePattern>,
+}
+
+/// Issue ids matching `regex` link to `url_template`, in which `$0` is
+/// replaced by the id and `$1`, `$2`, ... by the groups of the match.
+#[derive(Clone, Debug)]
+pub struct IssuePattern {
+    pub regex: Regex,
+    pub url_template: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocumentLink {
+    pub range: TextRange,
+    pub target: String,
+}
+
+lazy_static! {
+    static ref URL: Regex = Regex::new(r#"https?://[^\s"'`<>()\[\]{}]+"#).unwrap();
+}
+
+// Feature: Document Links
+//
+// The http(s) URLs in comments and strings are links, as are the issue
+// ids matching the patterns of the `elp.documentLinks.issuePatterns`
+// setting.
+pub(crate) fn document_links(
+    db: &RootDatabase,
+    file_id: FileId,
+    config: &DocumentLinksConfig,
+) -> Vec<DocumentLink> {
+    let sema = Semantic::new(db);
+    let source_file = sema.parse(file_id);
+    let mut links = Vec::new();
+    for element in source_file.value.syntax().descendants_with_tokens() {
+        let token = match element {
+            NodeOrToken::Token(token) => token,
+            NodeOrToken::Node(_) => continue,
+        };
+        if !matches!(token.kind(), SyntaxKind::COMMENT | SyntaxKind::STRING) {
+            continue;
+        }
+        let start = token.text_range().start();
+        let range = |start_offset: usize, end_offset: usize| {
+            TextRange::new(
+                start + TextSize::from(start_offset as u32),
+                start + TextSize::from(end_offset as u32),
+            )
+        };
+        let text = token.text();
+        let mut token_links = Vec::new();
+        for url in URL.find_iter(text) {
+            // Punctuation ending a sentence is not part of the URL
+            let url = url
+                .as_str()
+                .trim_end_matches(|c| matches!(c, '.' | ',' | ';' | ':' | '!' | '?'));
+            let start_offset = url.as_ptr() as usize - text.as_ptr() as usize;
+            token_links.push(DocumentLink {
+                range: range(start_offset, start_offset + url.len()),
+                target: url.to_string(),
+            });
+        }
+        for pattern in &config.issue_patterns {
+            for captures in pattern.regex.captures_iter(text) {
+                let id = captures.get(0).unwrap();
+                let id_range = range(id.start(), id.end());
+                if id.as_str().is_empty()
+                    || token_links.iter().any(|link| {
+                        link.range
+                            .intersect(id_range)
+                            .map_or(false, |it| !it.is_empty())
+                    })
+                {
+                    continue;
+                }
+                let mut target = String::new();
+                captures.expand(&pattern.url_template, &mut target);
+                token_links.push(DocumentLink {
+                    range: id_range,
+                    target,
+                });
+            }
+        }
+        token_links.sort_by_key(|link| link.range.start());
+        links.extend(token_links);
+    }
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use expect_test::Expect;
+    use regex::Regex;
+
+    use super::DocumentLinksConfig;
+    use super::IssuePattern;
+    use crate::fixture;
+
+    #[track_caller]
+    fn check(fixture: &str, config: &DocumentLinksConfig, expect: Expect) {
+        let (analysis, file_id) = fixture::single_file(fixture);
+        let text = analysis.file_text(file_id).unwrap();
+        let actual: String = analysis
+            .document_links(file_id, config)
+            .unwrap()
+            .into_iter()
+            .map(|link| format!("{} -> {}\n", &text[link.range], link.target))
+            .collect();
+        expect.assert_eq(&actual);
+    }
+
+    fn issue_config() -> DocumentLinksConfig {
+        DocumentLinksConfig {
+            issue_patterns: vec![
+                IssuePattern {
+                    regex: Regex::new(r"\bT(\d+)\b").unwrap(),
+                    url_template: "https://tasks.example.com/$1".to_string(),
+                },
+                IssuePattern {
+                    regex: Regex::new(r"\bJIRA-\d+\b").unwrap(),
+                    url_template: "https://jira.example.com/browse/$0".to_string(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn urls() {
+        check(
+            r#"
+-module(main).
+%% See https://www.erlang.org/doc/man/lists.html, and
+%% http://example.com/a?b=c.
+foo() -> "(https://example.com/path)".
+bar() -> 'https://example.com/atom'.
+"#,
+            &DocumentLinksConfig::default(),
+            expect![[r#"
+                https://www.erlang.org/doc/man/lists.html -> https://www.erlang.org/doc/man/lists.html
+                http://example.com/a?b=c -> http://example.com/a?b=c
+                https://example.com/path -> https://example.com/path
+            "#]],
+        );
+    }
+
+    #[test]
+    fn issue_ids() {
+        check(
+            r#"
+-module(main).
+%% Fixes T123456 and JIRA-42, not T12a.
+foo() -> "Reverted in T654321".
+%% Not an id: https://example.com/T42
+"#,
+            &issue_config(),
+            expect![[r#"
+                T123456 -> https://tasks.example.com/123456
+                JIRA-42 -> https://jira.example.com/browse/JIRA-42
+                T654321 -> https://tasks.example.com/654321
+                https://example.com/T42 -> https://example.com/T42
+            "#]],
+        );
+    }
+
+    #[test]
+    fn no_issue_patterns() {
+        check(
+            r#"
+-module(main).
+%% Fixes T123456.
+"#,
+            &DocumentLinksConfig::default(),
+            expect![[r#""#]],
+        );
+    }
+}
@@ -12,6 +12,7 @@ use elp_ide_db::RootDatabase;
 use elp_syntax::ast;
 use elp_syntax::AstNode;
 use elp_syntax::SyntaxKind;
+use hir::InFile;
 use hir::Semantic;
 
 use crate::FilePosition;
@@ -22,9 +23,26 @@ pub struct ExpandedMacro {
     pub expansion: String,
 }
 
+/// How much of the code at the cursor is expanded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExpandMacroMode {
+    /// The macro at the cursor, and the macros used by its expansion,
+    /// recursively.
+    #[default]
+    Recursive,
+    /// Only the macro at the cursor: its replacement text, with the
+    /// arguments of the call substituted for its parameters. The macros
+    /// it uses are left as they are.
+    SingleStep,
+    /// The function enclosing the cursor, with all its macros expanded.
+    Function,
+}
+
 // Feature: Expand Macro Recursively
 //
-// Shows the full macro expansion of the macro at current cursor.
+// Shows the full macro expansion of the macro at current cursor, only
+// its first step, or the enclosing function with all its macros
+// expanded.
 //
 // |===
 // | Editor  | Action Name
@@ -32,7 +50,11 @@ pub struct ExpandedMacro {
 // | VS Code | **Erlang: Expand Macro**
 // |===
 //
-pub(crate) fn expand_macro(db: &RootDatabase, position: FilePosition) -> Option<ExpandedMacro> {
+pub(crate) fn expand_macro(
+    db: &RootDatabase,
+    position: FilePosition,
+    mode: ExpandMacroMode,
+) -> Option<ExpandedMacro> {
     let sema = Semantic::new(db);
     let source_file = sema.parse(position.file_id);
 
@@ -47,8 +69,24 @@ pub(crate) fn expand_macro(db: &RootDatabase, position: FilePosition) -> Option<
         },
     )?;
 
+    if mode == ExpandMacroMode::Function {
+        let function_id = sema.find_enclosing_function(position.file_id, &tok.parent()?)?;
+        let form_list = sema.db.file_form_list(position.file_id);
+        let function = &form_list[function_id];
+        let body = sema
+            .db
+            .function_body(InFile::new(position.file_id, function_id));
+        return Some(ExpandedMacro {
+            name: function.name.to_string(),
+            expansion: body.print(sema.db.upcast(), function),
+        });
+    }
+
     tok.parent_ancestors().find_map(|node| {
         let mac = ast::MacroCallExpr::cast(node)?;
+        if mode == ExpandMacroMode::SingleStep {
+            return expand_single_step(&sema, source_file.with_value(&mac));
+        }
         let (name, expansion) = sema.expand(source_file.with_value(&mac))?;
         Some(ExpandedMacro {
             name: name.to_string(),
@@ -57,17 +95,61 @@ pub(crate) fn expand_macro(db: &RootDatabase, position: FilePosition) -> Option<
     })
 }
 
+/// Substitute the arguments of the call for the parameters in the
+/// replacement text of the macro, token by token.
+fn expand_single_step(sema: &Semantic, call: InFile<&ast::MacroCallExpr>) -> Option<ExpandedMacro> {
+    let define = sema.to_def(call)?;
+    let source = define.source(sema.db.upcast());
+    let replacement = source.replacement()?;
+    let params: Vec<String> = source
+        .lhs()
+        .and_then(|lhs| lhs.args())
+        .map(|args| args.args().map(|var| var.text().to_string()).collect())
+        .unwrap_or_default();
+    let args: Vec<String> = call
+        .value
+        .args()
+        .map(|args| {
+            args.args()
+                .map(|arg| arg.syntax().text().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut expansion = String::new();
+    for token in replacement
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+    {
+        let param = params.iter().position(|param| param == token.text());
+        match param.and_then(|idx| args.get(idx)) {
+            Some(arg) if token.kind() == SyntaxKind::VAR => expansion.push_str(arg),
+            _ => expansion.push_str(token.text()),
+        }
+    }
+    Some(ExpandedMacro {
+        name: define.define.name.to_string(),
+        expansion: format!("\n{}\n", expansion.trim()),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use expect_test::expect;
     use expect_test::Expect;
 
+    use super::ExpandMacroMode;
     use crate::fixture;
 
     fn check(elp_fixture: &str, expect: Expect) {
+        check_mode(elp_fixture, ExpandMacroMode::Recursive, expect)
+    }
+
+    fn check_mode(elp_fixture: &str, mode: ExpandMacroMode, expect: Expect) {
         let (analysis, pos) = fixture::position(elp_fixture);
 
-        let expansion = match analysis.expand_macro(pos).unwrap() {
+        let expansion = match analysis.expand_macro(pos, mode).unwrap() {
             Some(it) => format!("{}{}", it.name, it.expansion),
             None => "***EXPANSION FAILED***".to_string(),
         };
@@ -583,4 +665,45 @@ bar() -> ok.
             expect!["***EXPANSION FAILED***"],
         );
     }
+
+    #[test]
+    fn macro_expand_single_step() {
+        check_mode(
+            r#"
+-module(foo).
+-define(BAZ, baz).
+-define(FOO(X, Y), {X, Y, ?BAZ}).
+bar() -> ?F~OO(4, [5]).
+"#,
+            ExpandMacroMode::SingleStep,
+            expect![[r#"
+                FOO/2
+                {4, [5], ?BAZ}
+            "#]],
+        );
+    }
+
+    #[test]
+    fn macro_expand_function() {
+        check_mode(
+            r#"
+-module(foo).
+-define(BAZ, baz).
+-define(FOO(X), foo + X + ?BAZ).
+bar() ->
+    X = ?FOO(4),
+    {X, ?B~AZ}.
+"#,
+            ExpandMacroMode::Function,
+            expect![[r#"
+                bar/0
+                bar() ->
+                    X = (('foo' + 4) + 'baz'),
+                    {
+                        X,
+                        'baz'
+                    }.
+            "#]],
+        );
+    }
 }
@@ -7,6 +7,7 @@
  * of this source tree.
  */
 
+use elp_ide_db::elp_base_db::FileId;
 use elp_ide_db::RootDatabase;
 use elp_syntax::ast::AstNode;
 use elp_syntax::Direction;
@@ -38,6 +39,34 @@ pub(crate) fn extend_selection(db: &RootDatabase, frange: FileRange) -> TextRang
     try_extend_selection(source_file.value.syntax(), frange).unwrap_or(frange.range)
 }
 
+/// For each offset, the chain of ranges obtained by extending the empty
+/// selection at the offset until the whole file is selected, innermost
+/// first.
+pub(crate) fn selection_ranges(
+    db: &RootDatabase,
+    file_id: FileId,
+    offsets: &[TextSize],
+) -> Vec<Vec<TextRange>> {
+    let sema = Semantic::new(db);
+    let source_file = sema.parse(file_id);
+    let root = source_file.value.syntax();
+    offsets
+        .iter()
+        .map(|&offset| {
+            let mut range = TextRange::empty(offset);
+            let mut ranges = vec![range];
+            while let Some(next) = try_extend_selection(root, FileRange { file_id, range }) {
+                if next == range {
+                    break;
+                }
+                ranges.push(next);
+                range = next;
+            }
+            ranges
+        })
+        .collect()
+}
+
 fn try_extend_selection(root: &SyntaxNode, frange: FileRange) -> Option<TextRange> {
     let range = frange.range;
 
@@ -273,6 +302,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_selection_ranges() {
+        let (analysis, position) = fixture::position(r#"foo() -> ~1 + 1"#);
+        let text = analysis.file_text(position.file_id).unwrap();
+        let ranges = analysis
+            .selection_ranges(position.file_id, vec![position.offset])
+            .unwrap();
+        let actual: Vec<Vec<&str>> = ranges
+            .iter()
+            .map(|chain| chain.iter().map(|&range| &text[range]).collect())
+            .collect();
+        assert_eq!(
+            actual,
+            vec![vec!["", "1", "1 + 1", "-> 1 + 1", "foo() -> 1 + 1"]]
+        );
+    }
+
     #[test]
     fn test_extend_selection_arith_expression() {
         do_check(
@@ -34,6 +34,13 @@ pub(crate) fn position(fixture: &str) -> (Analysis, FilePosition) {
     (host.analysis(), position)
 }
 
+/// Creates analysis from a multi-file fixture, returns the range marked with two [`CURSOR_MARKER`]s
+pub(crate) fn range(fixture: &str) -> (Analysis, FileRange) {
+    let (db, range) = RootDatabase::with_range(fixture);
+    let host = AnalysisHost { db };
+    (host.analysis(), range)
+}
+
 /// Creates analysis from a multi-file fixture
 pub(crate) fn multi_file(fixture: &str) -> Analysis {
     let (db, _) = RootDatabase::with_fixture(fixture);
@@ -10,6 +10,8 @@
 use elp_ide_db::elp_base_db::FileId;
 use elp_ide_db::RootDatabase;
 use elp_syntax::AstNode;
+use elp_syntax::NodeOrToken;
+use elp_syntax::SyntaxKind;
 use elp_syntax::TextRange;
 use hir::Semantic;
 
@@ -17,6 +19,12 @@ use hir::Semantic;
 pub enum FoldKind {
     Function,
     Record,
+    Comprehension,
+    /// A multi-line `case`, `if`, `receive` or `try` expression
+    Expr,
+    /// Consecutive comment lines
+    CommentBlock,
+    Export,
 }
 
 #[derive(Debug)]
@@ -27,7 +35,9 @@ pub struct Fold {
 
 // Feature: Folding
 //
-// Defines folding regions for functions.
+// Defines folding regions for functions, records, multi-line
+// comprehensions, `case`, `if`, `receive` and `try` expressions and
+// export lists, and for blocks of comment lines.
 pub(crate) fn folding_ranges(db: &RootDatabase, file_id: FileId) -> Vec<Fold> {
     let mut folds = Vec::new();
     let sema = Semantic::new(db);
@@ -46,9 +56,57 @@ pub(crate) fn folding_ranges(db: &RootDatabase, file_id: FileId) -> Vec<Fold> {
             range: def.source(db).syntax().text_range(),
         })
     }
+    // Comprehensions, expressions and export lists
+    let source = sema.parse(file_id).value;
+    for node in source.syntax().descendants() {
+        let kind = match node.kind() {
+            SyntaxKind::LIST_COMPREHENSION
+            | SyntaxKind::BINARY_COMPREHENSION
+            | SyntaxKind::MAP_COMPREHENSION => FoldKind::Comprehension,
+            SyntaxKind::CASE_EXPR
+            | SyntaxKind::IF_EXPR
+            | SyntaxKind::RECEIVE_EXPR
+            | SyntaxKind::TRY_EXPR => FoldKind::Expr,
+            SyntaxKind::EXPORT_ATTRIBUTE => FoldKind::Export,
+            _ => continue,
+        };
+        if node.text().contains_char('\n') {
+            folds.push(Fold {
+                kind,
+                range: node.text_range(),
+            })
+        }
+    }
+    // Comment blocks, of comments on consecutive lines
+    let mut block: Option<(TextRange, usize)> = None;
+    for element in source.syntax().descendants_with_tokens() {
+        let token = match element {
+            NodeOrToken::Token(token) => token,
+            NodeOrToken::Node(_) => continue,
+        };
+        match token.kind() {
+            SyntaxKind::COMMENT => {
+                block = match block {
+                    Some((range, count)) => Some((range.cover(token.text_range()), count + 1)),
+                    None => Some((token.text_range(), 1)),
+                }
+            }
+            SyntaxKind::WHITESPACE if token.text().matches('\n').count() <= 1 => {}
+            _ => folds.extend(block.take().and_then(comment_block)),
+        }
+    }
+    folds.extend(block.and_then(comment_block));
     folds
 }
 
+/// A fold for the comments, unless a single one
+fn comment_block((range, count): (TextRange, usize)) -> Option<Fold> {
+    (count > 1).then_some(Fold {
+        kind: FoldKind::CommentBlock,
+        range,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use elp_ide_db::elp_base_db::fixture::extract_tags;
@@ -81,7 +139,12 @@ mod tests {
             );
 
             let kind = match fold.kind {
-                FoldKind::Function | FoldKind::Record => "region",
+                FoldKind::Function
+                | FoldKind::Record
+                | FoldKind::Comprehension
+                | FoldKind::Expr
+                | FoldKind::Export => "region",
+                FoldKind::CommentBlock => "comment",
             };
             assert_eq!(kind, &attr.unwrap());
         }
@@ -123,6 +186,72 @@ mod tests {
 <fold region>two() ->
   ok,
   ok.</fold>
+"#,
+        );
+    }
+
+    #[test]
+    fn test_comprehensions() {
+        check(
+            r#"
+-module(my_module).
+<fold region>one(L, M) ->
+  [X || X <- L],
+  {<fold region>[X
+   || X <- L]</fold>,
+   <fold region><< <<X>>
+   || <<X>> <= L >></fold>,
+   <fold region>#{K => V
+   || K := V <- M}</fold>}.</fold>
+"#,
+        );
+    }
+
+    #[test]
+    fn test_expressions() {
+        check(
+            r#"
+-module(my_module).
+<fold region>one(X) ->
+  case X of a -> ok; _ -> error end,
+  <fold region>case X of
+    a -> <fold region>if
+      X -> ok
+    end</fold>;
+    _ -> error
+  end</fold>,
+  <fold region>receive
+    _ -> ok
+  end</fold>.</fold>
+"#,
+        );
+    }
+
+    #[test]
+    fn test_export() {
+        check(
+            r#"
+-module(my_module).
+-export([one/0]).
+<fold region>-export([
+  two/0
+]).</fold>
+"#,
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        check(
+            r#"
+-module(my_module).
+<fold comment>%% A block
+%% of comments</fold>
+
+%% A single comment
+
+<fold comment>%% Another block
+%% of comments</fold>
 "#,
         );
     }
@@ -0,0 +1,647 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::elp_base_db::FileRange;
+use elp_ide_db::elp_base_db::SourceDatabase;
+use elp_ide_db::elp_base_db::SourceDatabaseExt;
+use elp_ide_db::source_change::SourceChange;
+use elp_ide_db::RootDatabase;
+use elp_syntax::AstNode;
+use elp_syntax::Direction;
+use elp_syntax::SyntaxKind;
+use elp_syntax::SyntaxNode;
+use elp_syntax::SyntaxToken;
+use elp_syntax::TextRange;
+use elp_syntax::TextSize;
+use text_edit::TextEdit;
+use text_edit::TextEditBuilder;
+
+use crate::typing::is_block_opener;
+use crate::typing::is_form_end;
+
+const INDENT: usize = 4;
+
+// Feature: Formatting
+//
+// Formats a file, or the lines of a selection, in the style of erlfmt:
+//
+// - lines are indented by 4 spaces for each clause body, block and
+//   bracket they are nested in;
+// - operators, `,`, `;` and keywords are surrounded by single spaces,
+//   brackets are not;
+// - trailing whitespace and repeated blank lines are removed.
+//
+// As with erlfmt, the line breaks of the code are kept. Files with
+// syntax errors, and the bodies of macro definitions, are left alone.
+//
+// |===
+// | Editor  | Action Name
+//
+// | VS Code | **Format Document**, **Format Selection**
+// |===
+pub(crate) fn format_file(db: &RootDatabase, file_id: FileId) -> Option<SourceChange> {
+    format(db, file_id, None)
+}
+
+pub(crate) fn format_range(db: &RootDatabase, frange: FileRange) -> Option<SourceChange> {
+    format(db, frange.file_id, Some(frange.range))
+}
+
+/// Only the whitespace between tokens is rewritten, so that the edit
+/// can be restricted to a range by dropping the changes outside of it.
+fn format(db: &RootDatabase, file_id: FileId, range: Option<TextRange>) -> Option<SourceChange> {
+    let parse = db.parse(file_id);
+    if !parse.errors().is_empty() {
+        return None;
+    }
+    let text = db.file_text(file_id);
+    let mut formatter = Formatter {
+        range,
+        edit: TextEdit::builder(),
+        stack: Vec::new(),
+        line_indent: 0,
+    };
+    let mut prev: Option<SyntaxToken> = None;
+    let mut prev_code: Option<SyntaxToken> = None;
+    for token in parse
+        .tree()
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| token.kind() != SyntaxKind::WHITESPACE)
+    {
+        let gap_start = prev
+            .as_ref()
+            .map_or(TextSize::from(0), |prev| prev.text_range().end());
+        let gap = TextRange::new(gap_start, token.text_range().start());
+        let gap_text = &text[gap];
+        let is_code = token.kind() != SyntaxKind::COMMENT;
+        let new_gap = match &prev {
+            None => String::new(),
+            Some(prev)
+                if macro_definition(&token).is_some()
+                    && macro_definition(&token) == macro_definition(prev) =>
+            {
+                gap_text.to_string()
+            }
+            Some(prev) => {
+                let line_start_indent = if is_code {
+                    formatter.close(&token)
+                } else {
+                    None
+                };
+                let newlines = gap_text.matches('\n').count();
+                if newlines > 0 {
+                    let continuation = prev_code.as_ref().map_or(false, is_continued);
+                    let indent = line_start_indent.unwrap_or_else(|| {
+                        formatter.body_indent() + if continuation { INDENT } else { 0 }
+                    });
+                    formatter.line_indent = indent;
+                    format!("{}{}", "\n".repeat(newlines.min(2)), " ".repeat(indent))
+                } else {
+                    match space_between(prev, &token) {
+                        Some(true) => " ".to_string(),
+                        Some(false) => String::new(),
+                        None if gap_text.is_empty() => String::new(),
+                        None => " ".to_string(),
+                    }
+                }
+            }
+        };
+        formatter.replace(gap, gap_text, new_gap);
+        if is_code {
+            if macro_definition(&token).is_none() {
+                formatter.open(&token);
+            }
+            prev_code = Some(token.clone());
+        }
+        prev = Some(token);
+    }
+    if let Some(prev) = prev {
+        let gap = TextRange::new(prev.text_range().end(), TextSize::of(text.as_str()));
+        formatter.replace(gap, &text[gap], "\n".to_string());
+    }
+    let edit = formatter.edit.finish();
+    if edit.is_empty() {
+        return None;
+    }
+    Some(SourceChange::from_text_edit(file_id, edit))
+}
+
+struct Formatter {
+    range: Option<TextRange>,
+    edit: TextEditBuilder,
+    /// The constructs enclosing the current token, and the indentation
+    /// of the line each started on.
+    stack: Vec<(Open, usize)>,
+    line_indent: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Open {
+    Bracket,
+    /// A block closed by `end`, with the kind of its keyword
+    Block(SyntaxKind),
+    /// The body of a clause, after its `->`
+    Clause,
+}
+
+impl Formatter {
+    fn replace(&mut self, gap: TextRange, old: &str, new: String) {
+        if old == new {
+            return;
+        }
+        // A gap belongs to the token following it, e.g. the indentation
+        // to the first token of the line
+        if let Some(range) = self.range {
+            if !range.contains_inclusive(gap.end()) {
+                return;
+            }
+        }
+        self.edit.replace(gap, new);
+    }
+
+    fn body_indent(&self) -> usize {
+        self.stack.last().map_or(0, |(_, indent)| indent + INDENT)
+    }
+
+    /// Update the stack for a token closing constructs, returning the
+    /// indentation of its line should it start one.
+    fn close(&mut self, token: &SyntaxToken) -> Option<usize> {
+        match token.kind() {
+            SyntaxKind::ANON_RPAREN
+            | SyntaxKind::ANON_RBRACK
+            | SyntaxKind::ANON_RRACE
+            | SyntaxKind::ANON_GT_GT => self.pop_through(|open| open == Open::Bracket),
+            SyntaxKind::ANON_END => self.pop_through(|open| matches!(open, Open::Block(_))),
+            SyntaxKind::ANON_SEMI => {
+                if let Some((Open::Clause, _)) = self.stack.last() {
+                    self.stack.pop();
+                }
+                None
+            }
+            kind @ (SyntaxKind::ANON_OF
+            | SyntaxKind::ANON_CATCH
+            | SyntaxKind::ANON_AFTER
+            | SyntaxKind::ANON_ELSE) => {
+                let block = self
+                    .stack
+                    .iter()
+                    .rposition(|(open, _)| *open != Open::Clause)?;
+                let (open, indent) = self.stack[block];
+                let continues_block = match open {
+                    Open::Block(SyntaxKind::ANON_CASE) => kind == SyntaxKind::ANON_OF,
+                    Open::Block(SyntaxKind::ANON_TRY) => kind != SyntaxKind::ANON_ELSE,
+                    Open::Block(SyntaxKind::ANON_RECEIVE) => kind == SyntaxKind::ANON_AFTER,
+                    Open::Block(SyntaxKind::ANON_MAYBE) => kind == SyntaxKind::ANON_ELSE,
+                    _ => false,
+                };
+                if !continues_block {
+                    return None;
+                }
+                self.stack.truncate(block + 1);
+                Some(indent)
+            }
+            _ => None,
+        }
+    }
+
+    fn open(&mut self, token: &SyntaxToken) {
+        match token.kind() {
+            SyntaxKind::ANON_LPAREN
+            | SyntaxKind::ANON_LBRACK
+            | SyntaxKind::ANON_LBRACE
+            | SyntaxKind::ANON_LT_LT => self.stack.push((Open::Bracket, self.line_indent)),
+            SyntaxKind::ANON_DASH_GT => self.stack.push((Open::Clause, self.line_indent)),
+            SyntaxKind::ANON_DOT if is_form_end(token) => self.stack.clear(),
+            // `fun()` types are not closed by an `end`
+            SyntaxKind::ANON_FUN
+                if token.parent().map(|parent| parent.kind()) == Some(SyntaxKind::FUN_TYPE) => {}
+            kind if is_block_opener(token) => {
+                self.stack.push((Open::Block(kind), self.line_indent))
+            }
+            _ => {}
+        }
+    }
+
+    fn pop_through(&mut self, is_opener: impl Fn(Open) -> bool) -> Option<usize> {
+        // Unbalanced code, e.g. using macros, leaves the stack alone
+        let opener = self.stack.iter().rposition(|(open, _)| is_opener(*open))?;
+        let (_, indent) = self.stack[opener];
+        self.stack.truncate(opener);
+        Some(indent)
+    }
+}
+
+/// Whether there should be a space between two tokens on the same
+/// line, or `None` to keep the existing spacing.
+fn space_between(prev: &SyntaxToken, next: &SyntaxToken) -> Option<bool> {
+    if next.kind() == SyntaxKind::COMMENT {
+        return Some(true);
+    }
+    // `- -1` would read as `--1`, and `+ +1` as `++1`
+    if is_prefix_sign(prev) && is_prefix_sign(next) {
+        return Some(true);
+    }
+    let hugs_prev = match next.kind() {
+        SyntaxKind::ANON_RPAREN
+        | SyntaxKind::ANON_RBRACK
+        | SyntaxKind::ANON_RRACE
+        | SyntaxKind::ANON_GT_GT
+        | SyntaxKind::ANON_COMMA
+        | SyntaxKind::ANON_SEMI
+        | SyntaxKind::ANON_DOT
+        | SyntaxKind::ANON_COLON => true,
+        SyntaxKind::ANON_SLASH => !is_binary_operator(next),
+        _ => false,
+    };
+    let hugs_next = match prev.kind() {
+        SyntaxKind::ANON_LPAREN
+        | SyntaxKind::ANON_LBRACK
+        | SyntaxKind::ANON_LBRACE
+        | SyntaxKind::ANON_LT_LT
+        | SyntaxKind::ANON_POUND
+        | SyntaxKind::ANON_QMARK
+        | SyntaxKind::ANON_COLON => true,
+        // Record field access
+        SyntaxKind::ANON_DOT => !is_form_end(prev),
+        // Arities, prefix operators and attributes
+        SyntaxKind::ANON_SLASH | SyntaxKind::ANON_DASH | SyntaxKind::ANON_PLUS => {
+            !is_binary_operator(prev)
+        }
+        _ => false,
+    };
+    if hugs_prev || hugs_next {
+        return Some(false);
+    }
+    if matches!(prev.kind(), SyntaxKind::ANON_COMMA | SyntaxKind::ANON_SEMI) {
+        return Some(true);
+    }
+    if is_spaced_operator(prev) || is_spaced_operator(next) {
+        return Some(true);
+    }
+    if is_keyword(prev) {
+        return Some(
+            !(prev.kind() == SyntaxKind::ANON_FUN && next.kind() == SyntaxKind::ANON_LPAREN),
+        );
+    }
+    if is_keyword(next) {
+        return Some(true);
+    }
+    if next.kind() == SyntaxKind::ANON_LPAREN {
+        return Some(false);
+    }
+    None
+}
+
+fn is_spaced_operator(token: &SyntaxToken) -> bool {
+    match token.kind() {
+        SyntaxKind::ANON_EQ
+        | SyntaxKind::ANON_QMARK_EQ
+        | SyntaxKind::ANON_DASH_GT
+        | SyntaxKind::ANON_EQ_GT
+        | SyntaxKind::ANON_COLON_EQ
+        | SyntaxKind::ANON_COLON_COLON
+        | SyntaxKind::ANON_LT_DASH
+        | SyntaxKind::ANON_LT_EQ
+        | SyntaxKind::ANON_PIPE
+        | SyntaxKind::ANON_PIPE_PIPE
+        | SyntaxKind::ANON_BANG => true,
+        _ => is_binary_operator(token),
+    }
+}
+
+fn is_prefix_sign(token: &SyntaxToken) -> bool {
+    matches!(token.kind(), SyntaxKind::ANON_DASH | SyntaxKind::ANON_PLUS)
+        && !is_binary_operator(token)
+}
+
+fn is_binary_operator(token: &SyntaxToken) -> bool {
+    token
+        .parent()
+        .map_or(false, |parent| parent.kind() == SyntaxKind::BINARY_OP_EXPR)
+}
+
+/// A line ending with one of these continues the expression.
+fn is_continued(token: &SyntaxToken) -> bool {
+    token.kind() != SyntaxKind::ANON_DASH_GT && is_spaced_operator(token)
+}
+
+/// The keywords of expressions, excluding attribute names such as the
+/// `if` of `-if(...)`.
+fn is_keyword(token: &SyntaxToken) -> bool {
+    let is_keyword = matches!(
+        token.kind(),
+        SyntaxKind::ANON_AFTER
+            | SyntaxKind::ANON_AND
+            | SyntaxKind::ANON_ANDALSO
+            | SyntaxKind::ANON_BAND
+            | SyntaxKind::ANON_BEGIN
+            | SyntaxKind::ANON_BNOT
+            | SyntaxKind::ANON_BOR
+            | SyntaxKind::ANON_BSL
+            | SyntaxKind::ANON_BSR
+            | SyntaxKind::ANON_BXOR
+            | SyntaxKind::ANON_CASE
+            | SyntaxKind::ANON_CATCH
+            | SyntaxKind::ANON_DIV
+            | SyntaxKind::ANON_ELSE
+            | SyntaxKind::ANON_END
+            | SyntaxKind::ANON_FUN
+            | SyntaxKind::ANON_IF
+            | SyntaxKind::ANON_MAYBE
+            | SyntaxKind::ANON_NOT
+            | SyntaxKind::ANON_OF
+            | SyntaxKind::ANON_OR
+            | SyntaxKind::ANON_ORELSE
+            | SyntaxKind::ANON_RECEIVE
+            | SyntaxKind::ANON_REM
+            | SyntaxKind::ANON_TRY
+            | SyntaxKind::ANON_WHEN
+            | SyntaxKind::ANON_XOR
+    );
+    is_keyword && prev_token(token).map(|prev| prev.kind()) != Some(SyntaxKind::ANON_DASH)
+}
+
+fn prev_token(token: &SyntaxToken) -> Option<SyntaxToken> {
+    elp_syntax::algo::skip_trivia_token(token.prev_token()?, Direction::Prev)
+}
+
+fn macro_definition(token: &SyntaxToken) -> Option<SyntaxNode> {
+    token
+        .parent_ancestors()
+        .find(|node| node.kind() == SyntaxKind::PP_DEFINE)
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use expect_test::Expect;
+
+    use crate::fixture;
+
+    #[track_caller]
+    fn check(fixture: &str, expect: Expect) {
+        let (analysis, file_id) = fixture::single_file(fixture);
+        let mut actual = analysis.file_text(file_id).unwrap().to_string();
+        if let Some(change) = analysis.format_file(file_id).unwrap() {
+            change.source_file_edits[&file_id].apply(&mut actual);
+        }
+        expect.assert_eq(&actual);
+    }
+
+    #[track_caller]
+    fn check_range(fixture: &str, expect: Expect) {
+        let (analysis, frange) = fixture::range(fixture);
+        let file_id = frange.file_id;
+        let mut actual = analysis.file_text(file_id).unwrap().to_string();
+        let change = analysis.format_range(frange).unwrap().expect("no edit");
+        change.source_file_edits[&file_id].apply(&mut actual);
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn reindents_function() {
+        check(
+            r#"
+-module(main).
+foo(X) ->
+  case X of
+   a ->
+  ok;
+      b -> fun() ->
+     error
+          end
+end.
+"#,
+            expect![[r#"
+                -module(main).
+                foo(X) ->
+                    case X of
+                        a ->
+                            ok;
+                        b -> fun() ->
+                            error
+                        end
+                    end.
+            "#]],
+        );
+    }
+
+    #[test]
+    fn normalises_spacing() {
+        check(
+            r#"
+-module( main ).
+-export([foo/1 ,bar/0]).
+foo( X )->
+    Y=X+1 ,
+    Z = [ Y|- X ],
+    {ok,Z,#{a=>1},lists:map( fun(A)->A  end ,Z)}.
+bar() -> ?MODULE:foo( 1 ).
+"#,
+            expect![[r#"
+                -module(main).
+                -export([foo/1, bar/0]).
+                foo(X) ->
+                    Y = X + 1,
+                    Z = [Y | -X],
+                    {ok, Z, #{a => 1}, lists:map(fun(A) -> A end, Z)}.
+                bar() -> ?MODULE:foo(1).
+            "#]],
+        );
+    }
+
+    #[test]
+    fn repeated_prefix_operators_keep_their_space() {
+        check(
+            r#"
+-module(main).
+foo() -> {- -1, + +1, -  - 1, - +1, 1 - -1}.
+"#,
+            expect![[r#"
+                -module(main).
+                foo() -> {- -1, + +1, - -1, - +1, 1 - -1}.
+            "#]],
+        );
+    }
+
+    #[test]
+    fn try_and_receive_clauses() {
+        check(
+            r#"
+-module(main).
+foo() ->
+    try bar() of
+            ok -> ok
+            catch
+    _:_ -> error
+        after
+    done
+    end,
+    receive
+        M -> M
+        after 100 ->
+            timeout
+    end.
+"#,
+            expect![[r#"
+                -module(main).
+                foo() ->
+                    try bar() of
+                        ok -> ok
+                    catch
+                        _:_ -> error
+                    after
+                        done
+                    end,
+                    receive
+                        M -> M
+                    after 100 ->
+                        timeout
+                    end.
+            "#]],
+        );
+    }
+
+    #[test]
+    fn brackets_and_continuations() {
+        check(
+            r#"
+-module(main).
+-export([
+  foo/0
+  ]).
+foo() ->
+    X =
+  bar(1,
+  2),
+    [
+  X
+    ].
+"#,
+            expect![[r#"
+                -module(main).
+                -export([
+                    foo/0
+                ]).
+                foo() ->
+                    X =
+                        bar(1,
+                            2),
+                    [
+                        X
+                    ].
+            "#]],
+        );
+    }
+
+    #[test]
+    fn comments_and_blank_lines() {
+        check(
+            r#"
+-module(main).
+
+
+%% Foo
+foo() ->   % trailing
+ % inside
+    ok.   "#,
+            expect![[r#"
+                -module(main).
+
+                %% Foo
+                foo() -> % trailing
+                    % inside
+                    ok.
+            "#]],
+        );
+    }
+
+    #[test]
+    fn specs_and_attributes() {
+        check(
+            r#"
+-module(main).
+-record(rec, {a=1::integer()}).
+-spec foo(fun((a)->b)) -> #rec{} when
+  X::t().
+-if(true).
+foo(F) ->#rec{a=F}.
+-endif.
+"#,
+            expect![[r#"
+                -module(main).
+                -record(rec, {a = 1 :: integer()}).
+                -spec foo(fun((a) -> b)) -> #rec{} when
+                    X :: t().
+                -if(true).
+                foo(F) -> #rec{a = F}.
+                -endif.
+            "#]],
+        );
+    }
+
+    #[test]
+    fn macro_definitions_are_left_alone() {
+        check(
+            r#"
+-module(main).
+-define(ADD(A,B),   A+B).
+-define(  X,  1 ).
+foo( ) -> ?ADD(?X,2).
+"#,
+            expect![[r#"
+                -module(main).
+                -define(ADD(A,B),   A+B).
+                -define(  X,  1 ).
+                foo() -> ?ADD(?X, 2).
+            "#]],
+        );
+    }
+
+    #[test]
+    fn syntax_errors_are_left_alone() {
+        check(
+            r#"
+-module(main).
+foo( ) ->
+  case.
+"#,
+            expect![[r#"
+                -module(main).
+                foo( ) ->
+                  case.
+            "#]],
+        );
+    }
+
+    #[test]
+    fn range_formats_selected_lines() {
+        check_range(
+            r#"
+-module(main).
+foo( ) ->
+  ok.
+bar( ) ->
+~  X=1,
+  X.~
+"#,
+            expect![[r#"
+                -module(main).
+                foo( ) ->
+                  ok.
+                bar( ) ->
+                    X = 1,
+                    X.
+            "#]],
+        );
+    }
+}
@@ -0,0 +1,343 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Reasoning about guards: the types established for variables by
+//! clause patterns and type tests, and whether a guard test is known
+//! to always or never succeed.
+
+use elp_syntax::ast::BinaryOp;
+use elp_syntax::ast::UnaryOp;
+use fxhash::FxHashMap;
+use hir::Body;
+use hir::CallTarget;
+use hir::Expr;
+use hir::ExprId;
+use hir::Literal;
+use hir::Pat;
+use hir::PatId;
+use hir::Semantic;
+use hir::Var;
+
+/// A set of Erlang types, coarse enough to decide subtyping and
+/// disjointness of the type test BIFs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TypeSet(u16);
+
+impl TypeSet {
+    pub(crate) const BOOLEAN: TypeSet = TypeSet(1 << 0);
+    const OTHER_ATOM: TypeSet = TypeSet(1 << 1);
+    pub(crate) const ATOM: TypeSet = TypeSet(Self::BOOLEAN.0 | Self::OTHER_ATOM.0);
+    pub(crate) const INTEGER: TypeSet = TypeSet(1 << 2);
+    pub(crate) const FLOAT: TypeSet = TypeSet(1 << 3);
+    pub(crate) const NUMBER: TypeSet = TypeSet(Self::INTEGER.0 | Self::FLOAT.0);
+    pub(crate) const LIST: TypeSet = TypeSet(1 << 4);
+    pub(crate) const TUPLE: TypeSet = TypeSet(1 << 5);
+    pub(crate) const MAP: TypeSet = TypeSet(1 << 6);
+    pub(crate) const BINARY: TypeSet = TypeSet(1 << 7);
+    const OTHER_BITSTRING: TypeSet = TypeSet(1 << 8);
+    pub(crate) const BITSTRING: TypeSet = TypeSet(Self::BINARY.0 | Self::OTHER_BITSTRING.0);
+    pub(crate) const FUNCTION: TypeSet = TypeSet(1 << 9);
+    pub(crate) const PID: TypeSet = TypeSet(1 << 10);
+    pub(crate) const PORT: TypeSet = TypeSet(1 << 11);
+    pub(crate) const REFERENCE: TypeSet = TypeSet(1 << 12);
+
+    pub(crate) fn is_subset(self, other: TypeSet) -> bool {
+        self.0 & !other.0 == 0
+    }
+
+    pub(crate) fn is_disjoint(self, other: TypeSet) -> bool {
+        self.0 & other.0 == 0
+    }
+
+    pub(crate) fn intersect(self, other: TypeSet) -> TypeSet {
+        TypeSet(self.0 & other.0)
+    }
+}
+
+/// What is known about the types of variables at a guard test.
+pub(crate) type Facts = FxHashMap<Var, TypeSet>;
+
+/// A type test BIF such as `is_integer(X)` applied to a variable.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TypeTest {
+    pub(crate) var: Var,
+    pub(crate) types: TypeSet,
+    /// Whether passing the test means exactly being in `types`. For
+    /// tests such as `is_record/2` it only implies it.
+    pub(crate) exact: bool,
+}
+
+impl TypeTest {
+    /// Whether the test is known to succeed, or known to fail, given
+    /// the facts.
+    pub(crate) fn truth(&self, facts: &Facts) -> Option<bool> {
+        let known = facts.get(&self.var)?;
+        if known.is_disjoint(self.types) {
+            Some(false)
+        } else if self.exact && known.is_subset(self.types) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    /// Record that the test succeeded.
+    pub(crate) fn add_to(&self, facts: &mut Facts) {
+        let types = facts
+            .get(&self.var)
+            .map_or(self.types, |known| known.intersect(self.types));
+        facts.insert(self.var, types);
+    }
+}
+
+fn type_test_bif(name: &str, arity: usize) -> Option<(TypeSet, bool)> {
+    let res = match (name, arity) {
+        ("is_atom", 1) => (TypeSet::ATOM, true),
+        ("is_boolean", 1) => (TypeSet::BOOLEAN, true),
+        ("is_integer", 1) => (TypeSet::INTEGER, true),
+        ("is_float", 1) => (TypeSet::FLOAT, true),
+        ("is_number", 1) => (TypeSet::NUMBER, true),
+        ("is_list", 1) => (TypeSet::LIST, true),
+        ("is_tuple", 1) => (TypeSet::TUPLE, true),
+        ("is_record", 2 | 3) => (TypeSet::TUPLE, false),
+        ("is_map", 1) => (TypeSet::MAP, true),
+        ("is_binary", 1) => (TypeSet::BINARY, true),
+        ("is_bitstring", 1) => (TypeSet::BITSTRING, true),
+        ("is_function", 1) => (TypeSet::FUNCTION, true),
+        ("is_function", 2) => (TypeSet::FUNCTION, false),
+        ("is_pid", 1) => (TypeSet::PID, true),
+        ("is_port", 1) => (TypeSet::PORT, true),
+        ("is_reference", 1) => (TypeSet::REFERENCE, true),
+        _ => return None,
+    };
+    Some(res)
+}
+
+/// The name of a call to a local function or to an `erlang` BIF.
+fn bif_name(sema: &Semantic, body: &Body, target: &CallTarget<ExprId>) -> Option<String> {
+    let name = match target {
+        CallTarget::Local { name } => name,
+        CallTarget::Remote { module, name } => {
+            let module = body[*module].as_atom()?;
+            if sema.db.lookup_atom(module).as_str() != "erlang" {
+                return None;
+            }
+            name
+        }
+    };
+    let name = body[*name].as_atom()?;
+    Some(sema.db.lookup_atom(name).as_str().to_string())
+}
+
+/// If the expression is a type test on a variable, such as
+/// `is_integer(X)`, return it.
+pub(crate) fn type_test(sema: &Semantic, body: &Body, expr_id: ExprId) -> Option<TypeTest> {
+    match &body[expr_id] {
+        Expr::Call { target, args } => {
+            let (types, exact) = type_test_bif(&bif_name(sema, body, target)?, args.len())?;
+            let var = body[*args.first()?].as_var()?;
+            Some(TypeTest { var, types, exact })
+        }
+        Expr::MacroCall { expansion, .. } => type_test(sema, body, *expansion),
+        _ => None,
+    }
+}
+
+/// The type of values matching a pattern, if it is known from its
+/// shape alone.
+pub(crate) fn pat_type(sema: &Semantic, body: &Body, pat_id: PatId) -> Option<TypeSet> {
+    let res = match &body[pat_id] {
+        Pat::Literal(Literal::Atom(atom)) => match sema.db.lookup_atom(*atom).as_str() {
+            "true" | "false" => TypeSet::BOOLEAN,
+            _ => TypeSet::OTHER_ATOM,
+        },
+        Pat::Literal(Literal::Integer(_) | Literal::Char(_)) => TypeSet::INTEGER,
+        Pat::Literal(Literal::Float(_)) => TypeSet::FLOAT,
+        Pat::Literal(Literal::String(_)) => TypeSet::LIST,
+        Pat::Tuple { .. } | Pat::Record { .. } => TypeSet::TUPLE,
+        Pat::List { .. } => TypeSet::LIST,
+        Pat::Map { .. } => TypeSet::MAP,
+        Pat::Binary { .. } => TypeSet::BITSTRING,
+        Pat::UnaryOp { pat, .. } => return pat_type(sema, body, *pat),
+        Pat::Match { lhs, rhs } => {
+            return match (pat_type(sema, body, *lhs), pat_type(sema, body, *rhs)) {
+                (Some(lhs), Some(rhs)) => Some(lhs.intersect(rhs)),
+                (lhs, rhs) => lhs.or(rhs),
+            };
+        }
+        Pat::MacroCall { expansion, .. } => return pat_type(sema, body, *expansion),
+        _ => return None,
+    };
+    Some(res)
+}
+
+/// The types of variables that are matched against a pattern of
+/// known shape in clause patterns, as in `f(X = {_, _})`.
+pub(crate) fn pattern_facts(sema: &Semantic, body: &Body, pats: &[PatId]) -> Facts {
+    let mut facts = Facts::default();
+    let mut todo: Vec<PatId> = pats.to_vec();
+    while let Some(pat_id) = todo.pop() {
+        match &body[pat_id] {
+            Pat::Match { lhs, rhs } => {
+                for (var, other) in [(*lhs, *rhs), (*rhs, *lhs)] {
+                    if let (Some(var), Some(types)) =
+                        (body[var].as_var(), pat_type(sema, body, other))
+                    {
+                        facts.insert(var, types);
+                    }
+                }
+                todo.extend([*lhs, *rhs]);
+            }
+            Pat::Tuple { pats } => todo.extend(pats),
+            Pat::List { pats, tail } => {
+                todo.extend(pats);
+                todo.extend(tail);
+            }
+            Pat::Record { fields, .. } => todo.extend(fields.iter().map(|(_, pat)| *pat)),
+            Pat::Map { fields } => todo.extend(fields.iter().map(|(_, pat)| *pat)),
+            Pat::Binary { segs } => todo.extend(segs.iter().map(|seg| seg.elem)),
+            Pat::MacroCall { expansion, .. } => todo.push(*expansion),
+            _ => {}
+        }
+    }
+    facts
+}
+
+/// The value of an expression that is the atom `true` or `false`.
+pub(crate) fn bool_literal(sema: &Semantic, body: &Body, expr_id: ExprId) -> Option<bool> {
+    match sema.db.lookup_atom(body[expr_id].as_atom()?).as_str() {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// Whether the expression always evaluates to a boolean, without
+/// raising an exception.
+pub(crate) fn is_safe_boolean(sema: &Semantic, body: &Body, expr_id: ExprId) -> bool {
+    let is_simple = |expr_id: &ExprId| matches!(body[*expr_id], Expr::Var(_) | Expr::Literal(_));
+    if bool_literal(sema, body, expr_id).is_some() {
+        return true;
+    }
+    match &body[expr_id] {
+        Expr::Call { args, .. } => {
+            type_test(sema, body, expr_id).is_some() && args.iter().all(is_simple)
+        }
+        Expr::BinaryOp {
+            lhs,
+            rhs,
+            op: BinaryOp::CompOp(_),
+        } => is_simple(lhs) && is_simple(rhs),
+        Expr::BinaryOp {
+            lhs,
+            rhs,
+            op: BinaryOp::LogicOp(_),
+        } => is_safe_boolean(sema, body, *lhs) && is_safe_boolean(sema, body, *rhs),
+        Expr::UnaryOp {
+            expr,
+            op: UnaryOp::Not,
+        } => is_safe_boolean(sema, body, *expr),
+        _ => false,
+    }
+}
+
+/// Whether two expressions are structurally the same, for the side
+/// effect free subset of expressions allowed in guards.
+pub(crate) fn same_expr(body: &Body, a: ExprId, b: ExprId) -> bool {
+    let all_same = |a: &[ExprId], b: &[ExprId]| {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_expr(body, *a, *b))
+    };
+    match (&body[a], &body[b]) {
+        (Expr::Literal(a), Expr::Literal(b)) => a == b,
+        (Expr::Var(a), Expr::Var(b)) => a == b,
+        (Expr::Tuple { exprs: a }, Expr::Tuple { exprs: b }) => all_same(a, b),
+        (
+            Expr::List {
+                exprs: a,
+                tail: a_tail,
+            },
+            Expr::List {
+                exprs: b,
+                tail: b_tail,
+            },
+        ) => {
+            all_same(a, b)
+                && match (a_tail, b_tail) {
+                    (Some(a), Some(b)) => same_expr(body, *a, *b),
+                    (None, None) => true,
+                    _ => false,
+                }
+        }
+        (Expr::UnaryOp { expr: a, op: a_op }, Expr::UnaryOp { expr: b, op: b_op }) => {
+            a_op == b_op && same_expr(body, *a, *b)
+        }
+        (
+            Expr::BinaryOp {
+                lhs: a_lhs,
+                rhs: a_rhs,
+                op: a_op,
+            },
+            Expr::BinaryOp {
+                lhs: b_lhs,
+                rhs: b_rhs,
+                op: b_op,
+            },
+        ) => a_op == b_op && same_expr(body, *a_lhs, *b_lhs) && same_expr(body, *a_rhs, *b_rhs),
+        (
+            Expr::Call {
+                target: a_target,
+                args: a_args,
+            },
+            Expr::Call {
+                target: b_target,
+                args: b_args,
+            },
+        ) => {
+            let same_target = match (a_target, b_target) {
+                (CallTarget::Local { name: a }, CallTarget::Local { name: b }) => {
+                    same_expr(body, *a, *b)
+                }
+                (
+                    CallTarget::Remote {
+                        module: a_module,
+                        name: a_name,
+                    },
+                    CallTarget::Remote {
+                        module: b_module,
+                        name: b_name,
+                    },
+                ) => same_expr(body, *a_module, *b_module) && same_expr(body, *a_name, *b_name),
+                _ => false,
+            };
+            same_target && all_same(a_args, b_args)
+        }
+        (Expr::MacroCall { expansion: a, .. }, _) => same_expr(body, *a, b),
+        (_, Expr::MacroCall { expansion: b, .. }) => same_expr(body, a, *b),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type_set_relations() {
+        assert!(TypeSet::BOOLEAN.is_subset(TypeSet::ATOM));
+        assert!(!TypeSet::ATOM.is_subset(TypeSet::BOOLEAN));
+        assert!(TypeSet::INTEGER.is_subset(TypeSet::NUMBER));
+        assert!(TypeSet::BINARY.is_subset(TypeSet::BITSTRING));
+        assert!(TypeSet::LIST.is_disjoint(TypeSet::TUPLE));
+        assert!(!TypeSet::NUMBER.is_disjoint(TypeSet::FLOAT));
+        assert_eq!(
+            TypeSet::NUMBER.intersect(TypeSet::INTEGER),
+            TypeSet::INTEGER
+        );
+    }
+}
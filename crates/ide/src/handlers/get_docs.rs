@@ -29,3 +29,109 @@ pub(crate) fn get_doc_at_position(
     let doc = Doc::from_reference(&docs, &token);
     doc.map(|d| (d, range))
 }
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use expect_test::Expect;
+
+    use crate::fixture;
+
+    fn check(fixture: &str, expect: Expect) {
+        let (analysis, position) = fixture::position(fixture);
+        let doc = analysis
+            .get_docs_at_position(position)
+            .unwrap()
+            .map(|(doc, _range)| doc.markdown_text().to_string())
+            .unwrap_or_default();
+        expect.assert_eq(&doc);
+    }
+
+    #[test]
+    fn record_name() {
+        check(
+            r#"
+-module(main).
+-record(rec, {a = 1 :: integer(), b}).
+foo() -> #r~ec{}.
+"#,
+            expect![[r#"
+                ```erlang
+                -record(rec, {a = 1 :: integer(), b}).
+                ```"#]],
+        );
+    }
+
+    #[test]
+    fn record_definition() {
+        check(
+            r#"
+-module(main).
+-record(r~ec, {a = 1 :: integer(), b}).
+"#,
+            expect![[r#"
+                ```erlang
+                -record(rec, {a = 1 :: integer(), b}).
+                ```"#]],
+        );
+    }
+
+    #[test]
+    fn local_type() {
+        check(
+            r#"
+-module(main).
+-type t() :: {ok, integer()} | error.
+-spec foo() -> t~().
+foo() -> error.
+"#,
+            expect![[r#"
+                ```erlang
+                -type t() :: {ok, integer()} | error.
+                ```"#]],
+        );
+    }
+
+    #[test]
+    fn remote_type() {
+        check(
+            r#"
+//- /src/main.erl
+-module(main).
+-spec foo() -> other:t~().
+foo() -> error.
+//- /src/other.erl
+-module(other).
+-export_type([t/0]).
+-opaque t() :: error.
+"#,
+            expect![[r#"
+                ```erlang
+                -opaque t() :: error.
+                ```"#]],
+        );
+    }
+
+    #[test]
+    fn macro_in_expression() {
+        check(
+            r#"
+-module(main).
+-define(INC(X), X + 1).
+foo() -> ?I~NC(2).
+"#,
+            expect![[r#"
+                ```erlang
+                -define(INC(X), X + 1).
+                ```
+
+                -----
+
+                Expands to:
+
+                ```erlang
+                (2 + 1)
+                ```"#]],
+        );
+    }
+}
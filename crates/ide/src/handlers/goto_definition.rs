@@ -9,6 +9,8 @@
 
 use elp_ide_db::elp_base_db::FilePosition;
 use elp_ide_db::find_best_token;
+use elp_ide_db::table_keys;
+use elp_ide_db::table_keys::TableKeyAccess;
 use elp_ide_db::RootDatabase;
 use elp_ide_db::SymbolClass;
 use hir::Semantic;
@@ -21,6 +23,9 @@ pub(crate) fn goto_definition(
     db: &RootDatabase,
     position: FilePosition,
 ) -> Option<RangeInfo<Vec<NavigationTarget>>> {
+    if let Some(res) = goto_table_key_creation(db, position) {
+        return Some(res);
+    }
     let sema = Semantic::new(db);
     let token = find_best_token(&sema, position)?;
     let targets = SymbolClass::classify(&sema, token.clone())?
@@ -30,6 +35,24 @@ pub(crate) fn goto_definition(
     Some(RangeInfo::new(token.value.text_range(), targets))
 }
 
+/// From the key of an ETS table or `persistent_term` entry, to where
+/// it is created in the project.
+fn goto_table_key_creation(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<RangeInfo<Vec<NavigationTarget>>> {
+    let key_ref = table_keys::table_key_at(db, position)?;
+    let targets: Vec<_> = table_keys::table_key_references(db, position.file_id, &key_ref.key)
+        .into_iter()
+        .filter(|creation| creation.value.access == TableKeyAccess::Create)
+        .map(|creation| creation.to_nav(db))
+        .collect();
+    if targets.is_empty() {
+        return None;
+    }
+    Some(RangeInfo::new(key_ref.range, targets))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::fixture;
@@ -209,6 +232,20 @@ foo() -> b~ar().
         )
     }
 
+    #[test]
+    fn local_call_in_escript() {
+        check(
+            r#"
+//- /src/script.escript
+#!/usr/bin/env escript
+main(_) -> he~lper().
+
+  helper() -> ok.
+%%^^^^^^
+"#,
+        )
+    }
+
     #[test]
     fn local_call_from_record_def() {
         check(
@@ -1457,6 +1494,18 @@ foo() -> ?F~OO(1).
 //- /another-app/include/header.hrl app:another
 %% ^file
 -import(lists, [all/2]).
+"#,
+        );
+
+        check(
+            r#"
+//- /main/src/main.erl app:main
+-module(main).
+
+-include_lib("another/include/head~er.hrl").
+//- /another-app/include/header.hrl app:another
+%% ^file
+-import(lists, [all/2]).
 "#,
         );
     }
@@ -3455,4 +3504,29 @@ foo() ->
 "#,
         )
     }
+
+    #[test]
+    fn table_key() {
+        check(
+            r#"
+//- /src/main.erl
+-module(main).
+init() -> ets:new(my_table, [named_table]).
+%%                ^^^^^^^^
+//- /src/other.erl
+-module(other).
+get(K) -> ets:lookup(my_t~able, K).
+"#,
+        );
+
+        check(
+            r#"
+//- /src/main.erl
+-module(main).
+init() -> persistent_term:put(config, #{}).
+%%                            ^^^^^^
+get() -> persistent_term:get(conf~ig, #{}).
+"#,
+        );
+    }
 }
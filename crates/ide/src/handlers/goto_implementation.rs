@@ -0,0 +1,145 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Goto implementation: from a `-callback` of a behaviour to the
+//! functions implementing it, in the modules of the project declaring
+//! the behaviour.
+
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::elp_base_db::FilePosition;
+use elp_ide_db::elp_base_db::SourceDatabase;
+use elp_ide_db::RootDatabase;
+use elp_syntax::AstNode;
+use hir::db::MinDefDatabase;
+use hir::Semantic;
+
+use crate::navigation_target::NavigationTarget;
+use crate::navigation_target::ToNav;
+use crate::RangeInfo;
+
+pub(crate) fn goto_implementation(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<RangeInfo<Vec<NavigationTarget>>> {
+    let sema = Semantic::new(db);
+    let def_map = sema.def_map(position.file_id);
+    let (name, callback) = def_map.get_callbacks().iter().find_map(|(name, def)| {
+        if def.file.file_id != position.file_id {
+            return None;
+        }
+        let callback = def.source(db);
+        if callback.syntax().text_range().contains(position.offset) {
+            Some((name, callback))
+        } else {
+            None
+        }
+    })?;
+    let range = callback.fun()?.syntax().text_range();
+    let targets = implementing_modules(&sema, position.file_id)
+        .into_iter()
+        .filter_map(|file_id| {
+            let def_map = sema.def_map(file_id);
+            Some(def_map.get_function(name)?.to_nav(db))
+        })
+        .collect();
+    Some(RangeInfo::new(range, targets))
+}
+
+/// The modules of the project with a `-behaviour` attribute for the
+/// module of the file.
+fn implementing_modules(sema: &Semantic, behaviour: FileId) -> Vec<FileId> {
+    let project_id = match sema.db.app_data(sema.db.file_source_root(behaviour)) {
+        Some(app_data) => app_data.project_id,
+        None => return Vec::new(),
+    };
+    let module_index = sema.db.module_index(project_id);
+    let mut res: Vec<FileId> = module_index
+        .iter_own()
+        .map(|(_, _, file_id)| file_id)
+        .filter(|&file_id| {
+            let form_list = sema.db.file_form_list(file_id);
+            form_list.behaviour_attributes().any(|(_, attr)| {
+                sema.resolve_module_name(file_id, attr.name.as_str())
+                    .map_or(false, |module| module.file.file_id == behaviour)
+            })
+        })
+        .collect();
+    res.sort();
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixture;
+    use crate::tests::check_navs;
+
+    #[track_caller]
+    fn check(fixture: &str) {
+        let (analysis, position, expected) = fixture::annotations(fixture);
+        let navs = analysis
+            .goto_implementation(position)
+            .unwrap()
+            .expect("no callback found")
+            .info;
+        check_navs(navs, expected);
+    }
+
+    #[test]
+    fn callback_implementations() {
+        check(
+            r#"
+//- /src/my_behaviour.erl
+-module(my_behaviour).
+-callback in~it(term()) -> ok.
+-callback terminate() -> ok.
+//- /src/impl_a.erl
+-module(impl_a).
+-behaviour(my_behaviour).
+-export([init/1, terminate/0]).
+  init(_) -> ok.
+%%^^^^
+terminate() -> ok.
+//- /src/impl_b.erl
+-module(impl_b).
+-behavior(my_behaviour).
+-export([init/1]).
+  init(_) -> ok.
+%%^^^^
+//- /src/other.erl
+-module(other).
+-export([init/1]).
+init(_) -> ok.
+"#,
+        );
+    }
+
+    #[test]
+    fn no_implementations() {
+        check(
+            r#"
+//- /src/my_behaviour.erl
+-module(my_behaviour).
+-callback init(term()) -> o~k.
+"#,
+        );
+    }
+
+    #[test]
+    fn not_on_callback() {
+        let (analysis, position) = fixture::position(
+            r#"
+//- /src/my_behaviour.erl
+-module(my_behaviour).
+-callback init(term()) -> ok.
+in~it(_) -> ok.
+"#,
+        );
+        assert!(analysis.goto_implementation(position).unwrap().is_none());
+    }
+}
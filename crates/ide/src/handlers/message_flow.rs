@@ -0,0 +1,472 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Navigation along the messages of `gen_server` and `gen_statem`
+//! processes: from a `gen_server:call(Server, Msg)` to the
+//! `handle_call/3` clauses whose pattern may match `Msg`, and back from
+//! such a clause to the calls sending a message it may match.
+//!
+//! The server is only known when named by an atom, e.g. `?MODULE`;
+//! otherwise it is assumed to be the sending module, the API functions
+//! of a server usually being next to its callbacks.
+
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::elp_base_db::FilePosition;
+use elp_ide_db::elp_base_db::SourceDatabase;
+use elp_ide_db::RootDatabase;
+use elp_syntax::algo;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use elp_syntax::SyntaxToken;
+use elp_syntax::TextRange;
+use hir::db::MinDefDatabase;
+use hir::Body;
+use hir::Expr;
+use hir::ExprId;
+use hir::FunctionDef;
+use hir::InFile;
+use hir::Literal;
+use hir::Pat;
+use hir::PatId;
+use hir::Semantic;
+
+use crate::navigation_target::NavigationTarget;
+use crate::navigation_target::ToNav;
+use crate::RangeInfo;
+
+pub(crate) fn message_flow(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<RangeInfo<Vec<NavigationTarget>>> {
+    let sema = Semantic::new(db);
+    let source = sema.parse(position.file_id);
+    let token = source
+        .value
+        .syntax()
+        .token_at_offset(position.offset)
+        .right_biased()?;
+    from_call(&sema, position.file_id, &token).or_else(|| from_callback(&sema, position))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Message {
+    ServerCall,
+    ServerCast,
+    StatemCall,
+    StatemCast,
+}
+
+impl Message {
+    const ALL: [Message; 4] = [
+        Message::ServerCall,
+        Message::ServerCast,
+        Message::StatemCall,
+        Message::StatemCast,
+    ];
+
+    /// The message sent by a call to the function, the server being
+    /// its first argument and the message its second one
+    fn sent_by(label: &str) -> Option<Message> {
+        match label {
+            "gen_server:call/2" | "gen_server:call/3" => Some(Message::ServerCall),
+            "gen_server:cast/2" => Some(Message::ServerCast),
+            "gen_statem:call/2" | "gen_statem:call/3" => Some(Message::StatemCall),
+            "gen_statem:cast/2" => Some(Message::StatemCast),
+            _ => None,
+        }
+    }
+
+    fn behaviour(self) -> &'static str {
+        match self {
+            Message::ServerCall | Message::ServerCast => "gen_server",
+            Message::StatemCall | Message::StatemCast => "gen_statem",
+        }
+    }
+
+    /// The positions of the event type, if any, and of the message in
+    /// the arguments of a callback receiving it
+    fn callback_args(self, name: &str, arity: u32) -> Option<(Option<usize>, usize)> {
+        match (self, name, arity) {
+            (Message::ServerCall, "handle_call", 3) => Some((None, 0)),
+            (Message::ServerCast, "handle_cast", 2) => Some((None, 0)),
+            (Message::StatemCall | Message::StatemCast, "handle_event", 4) => Some((Some(0), 1)),
+            // A state function, in the `state_functions` callback mode
+            (Message::StatemCall | Message::StatemCast, name, 3) if name != "terminate" => {
+                Some((Some(0), 1))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A call sending a message
+struct SentMessage {
+    message: Message,
+    server: ExprId,
+    content: ExprId,
+}
+
+fn sent_message(sema: &Semantic, body: &Body, expr: &Expr) -> Option<SentMessage> {
+    match expr {
+        Expr::Call { target, args } => {
+            let label = target.label(args.len() as u32, sema, body)?;
+            Some(SentMessage {
+                message: Message::sent_by(&label)?,
+                server: *args.first()?,
+                content: *args.get(1)?,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// From a call sending a message, the clauses of the callbacks of the
+/// server which may receive it
+fn from_call(
+    sema: &Semantic,
+    file_id: FileId,
+    token: &SyntaxToken,
+) -> Option<RangeInfo<Vec<NavigationTarget>>> {
+    let (call, expr, sent) = token
+        .parent_ancestors()
+        .filter_map(ast::Call::cast)
+        .find_map(|call| {
+            let ast_expr = ast::Expr::Call(call.clone());
+            let expr = sema.to_expr(InFile::new(file_id, &ast_expr))?;
+            let sent = sent_message(sema, &expr.body(), &expr[expr.value])?;
+            Some((call, expr, sent))
+        })?;
+    let body = expr.body();
+    let server = server_module(sema, file_id, &body, sent.server)?;
+    if !implements(sema, server, sent.message.behaviour()) {
+        return None;
+    }
+    let def_map = sema.def_map(server);
+    let mut res = Vec::new();
+    for (name, def) in def_map.get_functions() {
+        if def.file.file_id != server {
+            continue;
+        }
+        let (event_type, content) = match sent
+            .message
+            .callback_args(name.name().as_str(), name.arity())
+        {
+            Some(args) => args,
+            None => continue,
+        };
+        let fun_body = def.in_function_body(sema.db, ());
+        let callback_body = fun_body.body();
+        for (_, clause) in fun_body.clauses() {
+            let event_type_matches = event_type.map_or(true, |idx| {
+                clause.pats.get(idx).map_or(false, |pat| {
+                    event_type_may_match(sema, &callback_body, *pat, sent.message)
+                })
+            });
+            let pat = match clause.pats.get(content) {
+                Some(pat) if event_type_matches => *pat,
+                _ => continue,
+            };
+            if hir::may_match(&body, sent.content, &callback_body, pat) {
+                let range = fun_body
+                    .get_body_map(sema.db)
+                    .pat(pat)
+                    .map(|pat| pat.range());
+                res.extend(range.map(|range| clause_nav(sema, def, range)));
+            }
+        }
+    }
+    res.sort_by_key(|nav| (nav.file_id, nav.full_range.start()));
+    Some(RangeInfo::new(call.syntax().text_range(), res))
+}
+
+/// From the head of a callback clause, the calls sending a message it
+/// may receive
+fn from_callback(
+    sema: &Semantic,
+    position: FilePosition,
+) -> Option<RangeInfo<Vec<NavigationTarget>>> {
+    let file_id = position.file_id;
+    let source = sema.parse(file_id);
+    let clause =
+        algo::find_node_at_offset::<ast::FunctionClause>(source.value.syntax(), position.offset)?;
+    if let Some(body) = clause.body() {
+        if body.syntax().text_range().start() <= position.offset {
+            return None;
+        }
+    }
+    let function_id = sema.find_enclosing_function(file_id, clause.syntax())?;
+    let clause_id = sema.find_enclosing_function_clause(clause.syntax())?;
+    let form_list = sema.db.file_form_list(file_id);
+    let name = &form_list[function_id].name;
+    let messages: Vec<(Message, Option<usize>, usize)> = Message::ALL
+        .into_iter()
+        .filter(|message| implements(sema, file_id, message.behaviour()))
+        .filter_map(|message| {
+            let (event_type, content) =
+                message.callback_args(name.name().as_str(), name.arity())?;
+            Some((message, event_type, content))
+        })
+        .collect();
+    let callback = sema.to_function_body(InFile::new(file_id, function_id));
+    let callback_body = callback.body();
+    let clause = &callback[clause_id];
+    let (_, _, content) = messages.first()?;
+    let range = callback
+        .get_body_map(sema.db)
+        .pat(*clause.pats.get(*content)?)?
+        .range();
+
+    let module_index = sema.db.module_index(
+        sema.db
+            .app_data(sema.db.file_source_root(file_id))?
+            .project_id,
+    );
+    let mut res = Vec::new();
+    for (_, _, sender) in module_index.iter_own() {
+        let text = sema.db.file_text(sender);
+        if !messages
+            .iter()
+            .any(|(message, _, _)| text.contains(message.behaviour()))
+        {
+            continue;
+        }
+        for def in sema.def_map(sender).get_functions().values() {
+            if def.file.file_id != sender {
+                continue;
+            }
+            let fun_body = def.in_function_body(sema.db, ());
+            let body = fun_body.body();
+            let calls: Vec<(ExprId, SentMessage)> = fun_body.fold_function(
+                Vec::new(),
+                &mut |mut acc, _, ctx| {
+                    if let Some(sent) = sent_message(sema, &body, &ctx.expr) {
+                        acc.push((ctx.expr_id, sent));
+                    }
+                    acc
+                },
+                &mut |acc, _, _| acc,
+            );
+            for (call, sent) in calls {
+                let receives = messages.iter().any(|(message, event_type, content)| {
+                    *message == sent.message
+                        && event_type.map_or(true, |idx| {
+                            clause.pats.get(idx).map_or(false, |pat| {
+                                event_type_may_match(sema, &callback_body, *pat, sent.message)
+                            })
+                        })
+                        && clause.pats.get(*content).map_or(false, |pat| {
+                            hir::may_match(&body, sent.content, &callback_body, *pat)
+                        })
+                });
+                if receives && server_module(sema, sender, &body, sent.server) == Some(file_id) {
+                    let range = fun_body.range_for_expr(sema.db, call);
+                    res.extend(range.map(|range| clause_nav(sema, def, range)));
+                }
+            }
+        }
+    }
+    res.sort_by_key(|nav| (nav.file_id, nav.full_range.start()));
+    Some(RangeInfo::new(range, res))
+}
+
+/// The module of the process a message is sent to, when named by an
+/// atom, or the sending module if not known from the syntax. Names
+/// registered through `global` or `via`, or on another node, are not
+/// followed.
+fn server_module(sema: &Semantic, file_id: FileId, body: &Body, server: ExprId) -> Option<FileId> {
+    match &body[server] {
+        Expr::MacroCall { expansion, .. } => server_module(sema, file_id, body, *expansion),
+        Expr::Literal(Literal::Atom(atom)) => {
+            let name = sema.db.lookup_atom(*atom);
+            Some(
+                sema.resolve_module_name(file_id, name.as_str())?
+                    .file
+                    .file_id,
+            )
+        }
+        Expr::Tuple { .. } => None,
+        _ => Some(file_id),
+    }
+}
+
+fn implements(sema: &Semantic, file_id: FileId, behaviour: &str) -> bool {
+    sema.db
+        .file_form_list(file_id)
+        .behaviour_attributes()
+        .any(|(_, attr)| attr.name.as_str() == behaviour)
+}
+
+/// Whether the event type pattern of a `gen_statem` callback may match
+/// `{call, From}` or `cast`, for the message.
+fn event_type_may_match(sema: &Semantic, body: &Body, pat: PatId, message: Message) -> bool {
+    let is_atom = |pat: PatId, name: &str| match &body[pat] {
+        Pat::Literal(Literal::Atom(atom)) => sema.db.lookup_atom(*atom).as_str() == name,
+        _ => false,
+    };
+    match &body[pat] {
+        Pat::Missing | Pat::Var(_) => true,
+        Pat::Match { lhs, rhs } => {
+            event_type_may_match(sema, body, *lhs, message)
+                && event_type_may_match(sema, body, *rhs, message)
+        }
+        Pat::MacroCall { expansion, .. } => event_type_may_match(sema, body, *expansion, message),
+        Pat::Literal(_) => message == Message::StatemCast && is_atom(pat, "cast"),
+        Pat::Tuple { pats } => match pats.as_slice() {
+            [tag, _] => {
+                message == Message::StatemCall
+                    && (matches!(body[*tag], Pat::Var(_)) || is_atom(*tag, "call"))
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// A navigation target in the function, focused on `range`, covering
+/// the function clause around it
+fn clause_nav(sema: &Semantic, def: &FunctionDef, range: TextRange) -> NavigationTarget {
+    let source = sema.parse(def.file.file_id);
+    let full_range =
+        algo::find_node_at_offset::<ast::FunctionClause>(source.value.syntax(), range.start())
+            .map_or(range, |clause| clause.syntax().text_range());
+    NavigationTarget {
+        full_range,
+        focus_range: Some(range),
+        ..def.to_nav(sema.db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixture;
+    use crate::tests::check_navs;
+
+    #[track_caller]
+    fn check(fixture: &str) {
+        let (analysis, position, expected) = fixture::annotations(fixture);
+        let navs = analysis
+            .message_flow(position)
+            .unwrap()
+            .expect("no message found")
+            .info;
+        check_navs(navs, expected);
+    }
+
+    #[test]
+    fn call_to_handle_call() {
+        check(
+            r#"
+//- /src/server.erl
+-module(server).
+-behaviour(gen_server).
+-export([get/1, handle_call/3, handle_cast/2]).
+get(Key) ->
+    gen_server:c~all(?MODULE, {get, Key}).
+handle_call({get, Key}, _From, State) ->
+%%          ^^^^^^^^^^
+    {reply, Key, State};
+handle_call({put, _, _}, _From, State) ->
+    {reply, ok, State};
+handle_call(get, _From, State) ->
+    {reply, ok, State};
+handle_call(Other, _From, State) ->
+%%          ^^^^^
+    {reply, Other, State}.
+handle_cast({get, _}, State) ->
+    {noreply, State}.
+"#,
+        );
+    }
+
+    #[test]
+    fn cast_to_other_module() {
+        check(
+            r#"
+//- /src/client.erl
+-module(client).
+-export([put/2]).
+put(Key, Value) ->
+    gen_server:cast(server, {put, ~Key, Value}).
+//- /src/server.erl
+-module(server).
+-behaviour(gen_server).
+-export([handle_call/3, handle_cast/2]).
+handle_call({put, _, _}, _From, State) ->
+    {reply, ok, State}.
+handle_cast({put, Key, _}, State) ->
+%%          ^^^^^^^^^^^^^
+    {noreply, State};
+handle_cast(#{put := _}, State) ->
+    {noreply, State}.
+"#,
+        );
+    }
+
+    #[test]
+    fn statem_event() {
+        check(
+            r#"
+//- /src/door.erl
+-module(door).
+-behaviour(gen_statem).
+-export([open/0, locked/3, terminate/3]).
+open() ->
+    gen_statem:ca~ll(?MODULE, {open, "1234"}).
+locked({call, From}, {open, Code}, Data) ->
+%%                   ^^^^^^^^^^^^
+    {keep_state, Data, [{reply, From, Code}]};
+locked(cast, {open, _}, Data) ->
+    {keep_state, Data};
+locked(info, Msg, Data) ->
+    {keep_state, Data}.
+terminate(_, _, _) ->
+    ok.
+"#,
+        );
+    }
+
+    #[test]
+    fn handle_call_to_calls() {
+        check(
+            r#"
+//- /src/client.erl
+-module(client).
+-export([get/1, put/2]).
+get(Key) ->
+    gen_server:call(server, {get, Key}).
+%%  ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+put(Key, Value) ->
+    gen_server:call(server, {put, Key, Value}).
+//- /src/server.erl
+-module(server).
+-behaviour(gen_server).
+-export([get/1, handle_call/3]).
+get(Key) ->
+    gen_server:call(?MODULE, {get, Key}, 1000).
+%%  ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+handle_call({g~et, Key}, _From, State) ->
+    {reply, Key, State}.
+"#,
+        );
+    }
+
+    #[test]
+    fn not_a_message() {
+        let (analysis, position) = fixture::position(
+            r#"
+//- /src/server.erl
+-module(server).
+-export([get/1]).
+get(Key) ->
+    lists:ke~yfind(Key, 1, []).
+"#,
+        );
+        assert!(analysis.message_flow(position).unwrap().is_none());
+    }
+}
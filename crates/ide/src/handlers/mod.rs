@@ -9,4 +9,6 @@
 
 pub mod get_docs;
 pub mod goto_definition;
+pub mod goto_implementation;
+pub mod message_flow;
 pub mod references;
@@ -19,6 +19,9 @@
 
 use elp_ide_db::elp_base_db::FileId;
 use elp_ide_db::find_best_token;
+use elp_ide_db::table_keys;
+use elp_ide_db::table_keys::TableKeyAccess;
+use elp_ide_db::RootDatabase;
 use elp_ide_db::SymbolClass;
 use elp_ide_db::SymbolDefinition;
 use elp_syntax::AstNode;
@@ -83,6 +86,36 @@ pub(crate) fn find_all_refs(
     }
 }
 
+/// All the uses of the key of an ETS table or `persistent_term` entry
+/// in the project. The first place creating it is the declaration.
+pub(crate) fn find_table_key_refs(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<Vec<ReferenceSearchResult>> {
+    let _p = profile::span("find_table_key_refs");
+    let key_ref = table_keys::table_key_at(db, position)?;
+    let mut refs = table_keys::table_key_references(db, position.file_id, &key_ref.key);
+    if refs.is_empty() {
+        return None;
+    }
+    let declaration_idx = refs
+        .iter()
+        .position(|key_ref| key_ref.value.access == TableKeyAccess::Create)
+        .unwrap_or(0);
+    let declaration = refs.remove(declaration_idx).to_nav(db);
+    let mut references: FxHashMap<FileId, Vec<TextRange>> = FxHashMap::default();
+    for key_ref in refs {
+        references
+            .entry(key_ref.file_id)
+            .or_default()
+            .push(key_ref.value.range);
+    }
+    Some(vec![ReferenceSearchResult {
+        declaration,
+        references,
+    }])
+}
+
 #[cfg(test)]
 mod tests {
     use elp_ide_db::elp_base_db::FileRange;
@@ -545,6 +578,84 @@ foo() -> ?FOO.
         );
     }
 
+    #[test]
+    fn test_record_in_nested_header() {
+        check(
+            r#"
+//- /src/records.hrl
+-record(rec~, {field}).
+%%      ^^^def
+
+//- /src/all.hrl
+-include("records.hrl").
+
+//- /src/helpers.hrl
+is_rec(#rec{}) -> true.
+%%      ^^^
+
+//- /src/main.erl
+-include("all.hrl").
+-include("helpers.hrl").
+
+foo() -> #rec{}.
+%%        ^^^
+
+//- /src/no_include.erl
+foo() -> #rec{}.
+"#,
+        );
+    }
+
+    #[test]
+    fn test_macro_in_nested_header() {
+        check(
+            r#"
+//- /foo/include/macros.hrl app:foo include_path:/foo/include
+-define(FOO~(X), {foo, X}).
+%%      ^^^^^^def
+
+//- /foo/include/wrappers.hrl app:foo
+-include("macros.hrl").
+
+wrap(X) -> ?FOO(X).
+%%          ^^^
+
+//- /foo/src/main.erl app:foo
+-include("wrappers.hrl").
+
+foo() -> ?FOO(1).
+%%        ^^^
+
+//- /bar/src/other.erl app:bar
+-include_lib("foo/include/macros.hrl").
+
+-type foo() :: ?FOO(integer()).
+%%              ^^^
+
+//- /bar/src/no_include.erl app:bar
+foo() -> ?FOO(1).
+"#,
+        );
+    }
+
+    #[test]
+    fn test_table_key() {
+        check(
+            r#"
+//- /src/main.erl
+-module(main).
+init() -> ets:new(my_table, [named_table]).
+%%                ^^^^^^^^def
+//- /src/other.erl
+-module(other).
+get(K) -> ets:lookup(my_t~able, K).
+%%                   ^^^^^^^^
+put(K, V) -> ets:insert(my_table, {K, V}), my_table.
+%%                      ^^^^^^^^
+"#,
+        );
+    }
+
     #[test]
     fn test_var() {
         check(
@@ -47,7 +47,11 @@ fn find_local_refs(sema: &Semantic<'_>, position: FilePosition) -> Option<Vec<Hi
     let search = move |def: SymbolDefinition| -> Vec<HighlightedRange> {
         let declaration = def.to_nav(sema.db);
         let (ref_category, decl_category) = match def {
-            SymbolDefinition::Var(_) => (
+            // The definition of a record or macro is a write, as for
+            // variables, so it stands out from its usages
+            SymbolDefinition::Var(_)
+            | SymbolDefinition::Record(_)
+            | SymbolDefinition::Define(_) => (
                 Some(ReferenceCategory::Read),
                 Some(ReferenceCategory::Write),
             ),
@@ -592,27 +596,27 @@ mod tests {
             -export([ 'PascalCaseFunction'/1 ]).
 
             -record(record_a, {field_a, field_b, 'Field C'}).
-            %%      ^^^^^^^^
+            %%      ^^^^^^^^write
 
             function_a() ->
               function_b(),
               #rec~ord_a{}.
-            %% ^^^^^^^^
+            %% ^^^^^^^^read
 
             function_c() ->
               code_navigation_extra:do(test),
               A = #record_a{ field_a = a },
-            %%     ^^^^^^^^
+            %%     ^^^^^^^^read
               _X = A#record_a.field_a, _Y = A#record_a.field_a,
-            %%       ^^^^^^^^
-            %%                                ^^^^^^^^
+            %%       ^^^^^^^^read
+            %%                                ^^^^^^^^read
               length([1, 2, 3]).
 
             %% quoted atoms
             -spec 'PascalCaseFunction'(T) -> 'Code.Navigation.Elixirish':'Type'(T).
             'PascalCaseFunction'(R) ->
               _ = R#record_a.'Field C',
-            %%      ^^^^^^^^
+            %%      ^^^^^^^^read
               F = fun 'Code.Navigation.Elixirish':do/1,
               F('Atom with whitespaces, "double quotes" and even some \'single quotes\'').
 
@@ -628,27 +632,27 @@ mod tests {
             -export([ 'PascalCaseFunction'/1 ]).
 
             -record(record_a, {field_a, field_b, 'Field C'}).
-            %%      ^^^^^^^^
+            %%      ^^^^^^^^write
 
             function_a() ->
               function_b(),
               #record_a{}.
-            %% ^^^^^^^^
+            %% ^^^^^^^^read
 
             function_c() ->
               code_navigation_extra:do(test),
               A = #record_a{ field_a = a },
-            %%     ^^^^^^^^
+            %%     ^^^^^^^^read
               _X = A#re~cord_a.field_a, _Y = A#record_a.field_a,
-            %%       ^^^^^^^^
-            %%                                ^^^^^^^^
+            %%       ^^^^^^^^read
+            %%                                ^^^^^^^^read
               length([1, 2, 3]).
 
             %% quoted atoms
             -spec 'PascalCaseFunction'(T) -> 'Code.Navigation.Elixirish':'Type'(T).
             'PascalCaseFunction'(R) ->
               _ = R#record_a.'Field C',
-            %%      ^^^^^^^^
+            %%      ^^^^^^^^read
               F = fun 'Code.Navigation.Elixirish':do/1,
               F('Atom with whitespaces, "double quotes" and even some \'single quotes\'').
 
@@ -664,27 +668,27 @@ mod tests {
             -export([ 'PascalCaseFunction'/1 ]).
 
             -record(rec~ord_a, {field_a, field_b, 'Field C'}).
-            %%      ^^^^^^^^
+            %%      ^^^^^^^^write
 
             function_a() ->
               function_b(),
               #record_a{}.
-            %% ^^^^^^^^
+            %% ^^^^^^^^read
 
             function_c() ->
               code_navigation_extra:do(test),
               A = #record_a{ field_a = a },
-            %%     ^^^^^^^^
+            %%     ^^^^^^^^read
               _X = A#record_a.field_a, _Y = A#record_a.field_a,
-            %%       ^^^^^^^^
-            %%                                ^^^^^^^^
+            %%       ^^^^^^^^read
+            %%                                ^^^^^^^^read
               length([1, 2, 3]).
 
             %% quoted atoms
             -spec 'PascalCaseFunction'(T) -> 'Code.Navigation.Elixirish':'Type'(T).
             'PascalCaseFunction'(R) ->
               _ = R#record_a.'Field C',
-            %%      ^^^^^^^^
+            %%      ^^^^^^^^read
               F = fun 'Code.Navigation.Elixirish':do/1,
               F('Atom with whitespaces, "double quotes" and even some \'single quotes\'').
 
@@ -911,12 +915,12 @@ mod tests {
           //- /src/main.erl
             -module(main).
             -define(MA~CRO_A, macro_a).
-            %%      ^^^^^^^
+            %%      ^^^^^^^write
             -define(MACRO_A(X), erlang:display(X)).
 
             function_b() ->
               ?MACRO_A.
-            %% ^^^^^^^
+            %% ^^^^^^^read
 
             function_d() ->
               ?MACRO_A(d).
@@ -924,7 +928,7 @@ mod tests {
             %% [#333] Record field accessors assumed to be atoms
             function_k() ->
               X#included_record_a.?MACRO_A,
-            %%                     ^^^^^^^
+            %%                     ^^^^^^^read
               <<"foo:">>.
 "#,
         );
@@ -937,12 +941,12 @@ mod tests {
           //- /src/main.erl
             -module(main).
             -define(MACRO_A, macro_a).
-            %%      ^^^^^^^
+            %%      ^^^^^^^write
             -define(MACRO_A(X), erlang:display(X)).
 
             function_b() ->
               ?MAC~RO_A.
-            %% ^^^^^^^
+            %% ^^^^^^^read
 
             function_d() ->
               ?MACRO_A(d).
@@ -950,7 +954,7 @@ mod tests {
             %% [#333] Record field accessors assumed to be atoms
             function_k() ->
               X#included_record_a.?MACRO_A,
-            %%                     ^^^^^^^
+            %%                     ^^^^^^^read
               <<"foo:">>.
 "#,
         );
@@ -1034,6 +1038,22 @@ mod tests {
                 ?a_macro(Args).
             %%           ^^^^read
 
+"#,
+        );
+    }
+
+    #[test]
+    fn local_variables_map_comprehension() {
+        check(
+            r#"
+          //- /src/main.erl
+            -module(main).
+
+            foo(M) ->
+              #{K => V~V || K := VV <- M}.
+           %%        ^^read
+           %%                   ^^write
+
 "#,
         );
     }
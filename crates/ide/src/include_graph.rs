@@ -0,0 +1,282 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! The include graph of a project: which files include which headers,
+//! directly or transitively, and the reverse. The headers of
+//! dependencies and OTP are part of it as soon as a file of the project
+//! includes them.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::VecDeque;
+
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::elp_base_db::ProjectId;
+use elp_ide_db::elp_base_db::SourceDatabase;
+use hir::db::MinDefDatabase;
+use hir::InFile;
+
+use crate::RootDatabase;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IncludeGraph {
+    /// The headers each file includes directly
+    includes: BTreeMap<FileId, BTreeSet<FileId>>,
+    /// The files including each header directly
+    included_by: BTreeMap<FileId, BTreeSet<FileId>>,
+}
+
+impl IncludeGraph {
+    /// The files of the graph, including or included
+    pub fn files(&self) -> impl Iterator<Item = FileId> + '_ {
+        self.includes
+            .keys()
+            .chain(self.included_by.keys())
+            .copied()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+    }
+
+    pub fn includes(&self, file_id: FileId) -> impl Iterator<Item = FileId> + '_ {
+        self.includes.get(&file_id).into_iter().flatten().copied()
+    }
+
+    pub fn included_by(&self, file_id: FileId) -> impl Iterator<Item = FileId> + '_ {
+        self.included_by
+            .get(&file_id)
+            .into_iter()
+            .flatten()
+            .copied()
+    }
+
+    /// The headers the file includes, directly or not
+    pub fn transitive_includes(&self, file_id: FileId) -> BTreeSet<FileId> {
+        reachable(&self.includes, file_id)
+    }
+
+    /// The files including the header, directly or not
+    pub fn transitive_included_by(&self, file_id: FileId) -> BTreeSet<FileId> {
+        reachable(&self.included_by, file_id)
+    }
+
+    /// The sets of files including each other, each of them a cycle or
+    /// several intertwined ones.
+    pub fn cycles(&self) -> Vec<BTreeSet<FileId>> {
+        let mut res: Vec<BTreeSet<FileId>> = Vec::new();
+        let mut seen = BTreeSet::new();
+        for file_id in self.includes.keys().copied() {
+            if seen.contains(&file_id) {
+                continue;
+            }
+            let reaching = self.transitive_includes(file_id);
+            if !reaching.contains(&file_id) {
+                continue;
+            }
+            // The files both reachable from and reaching this one
+            let cycle: BTreeSet<FileId> = reaching
+                .intersection(&self.transitive_included_by(file_id))
+                .copied()
+                .collect();
+            seen.extend(cycle.iter().copied());
+            res.push(cycle);
+        }
+        res
+    }
+
+    /// The shortest chain of includes from `from` to `to`, both
+    /// included, if `from` includes `to` directly or not.
+    pub fn include_path(&self, from: FileId, to: FileId) -> Option<Vec<FileId>> {
+        let mut parent: BTreeMap<FileId, FileId> = BTreeMap::new();
+        let mut todo = VecDeque::from([from]);
+        while let Some(file_id) = todo.pop_front() {
+            for next in self.includes(file_id) {
+                if next == to {
+                    let mut path = vec![to, file_id];
+                    let mut current = file_id;
+                    while current != from {
+                        current = parent[&current];
+                        path.push(current);
+                    }
+                    path.reverse();
+                    return Some(path);
+                }
+                if next != from && !parent.contains_key(&next) {
+                    parent.insert(next, file_id);
+                    todo.push_back(next);
+                }
+            }
+        }
+        None
+    }
+}
+
+fn reachable(edges: &BTreeMap<FileId, BTreeSet<FileId>>, file_id: FileId) -> BTreeSet<FileId> {
+    let mut res = BTreeSet::new();
+    let mut todo = vec![file_id];
+    while let Some(file_id) = todo.pop() {
+        for &next in edges.get(&file_id).into_iter().flatten() {
+            if res.insert(next) {
+                todo.push(next);
+            }
+        }
+    }
+    res
+}
+
+pub(crate) fn include_graph(db: &RootDatabase, project_id: ProjectId) -> IncludeGraph {
+    let files = db
+        .project_data(project_id)
+        .source_roots
+        .iter()
+        .flat_map(|&root_id| {
+            let root = db.source_root(root_id);
+            root.iter()
+                .filter(|file_id| {
+                    root.path_for_file(file_id)
+                        .and_then(|path| path.name_and_extension())
+                        .map_or(false, |(_, ext)| ext == Some("erl") || ext == Some("hrl"))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    walk_includes(db, files)
+}
+
+/// The graph of the headers the file includes, directly or not
+pub(crate) fn file_include_graph(db: &RootDatabase, file_id: FileId) -> IncludeGraph {
+    walk_includes(db, vec![file_id])
+}
+
+fn walk_includes(db: &RootDatabase, mut todo: Vec<FileId>) -> IncludeGraph {
+    let mut graph = IncludeGraph::default();
+    let mut visited = BTreeSet::new();
+    while let Some(file_id) = todo.pop() {
+        if !visited.insert(file_id) {
+            continue;
+        }
+        let form_list = db.file_form_list(file_id);
+        for (idx, _) in form_list.includes() {
+            if let Some(header) = db.resolve_include(InFile::new(file_id, idx)) {
+                graph.includes.entry(file_id).or_default().insert(header);
+                graph.included_by.entry(header).or_default().insert(file_id);
+                todo.push(header);
+            }
+        }
+    }
+    graph
+}
+
+#[cfg(test)]
+mod tests {
+    use elp_ide_db::elp_base_db::FileId;
+    use elp_ide_db::elp_base_db::SourceDatabase;
+    use expect_test::expect;
+    use expect_test::Expect;
+
+    use crate::fixture;
+
+    fn check(fixture: &str, expect: Expect) {
+        let (analysis, position) = fixture::position(fixture);
+        let project_id = analysis.project_id(position.file_id).unwrap().unwrap();
+        let graph = analysis.include_graph(project_id).unwrap();
+        let path = |file_id: FileId| {
+            analysis
+                .with_db(|db| {
+                    let root = db.source_root(db.file_source_root(file_id));
+                    root.path_for_file(&file_id).unwrap().to_string()
+                })
+                .unwrap()
+        };
+        let names = |files: &mut dyn Iterator<Item = FileId>| {
+            files
+                .map(|file_id| format!(" {}", path(file_id)))
+                .collect::<String>()
+        };
+        let mut actual = String::new();
+        for file_id in graph.files() {
+            actual.push_str(&format!(
+                "{}\n  includes:{}\n  included by:{}\n  transitively included by:{}\n",
+                path(file_id),
+                names(&mut graph.includes(file_id)),
+                names(&mut graph.included_by(file_id)),
+                names(&mut graph.transitive_included_by(file_id).into_iter()),
+            ));
+        }
+        for cycle in graph.cycles() {
+            actual.push_str(&format!("cycle:{}\n", names(&mut cycle.into_iter())));
+        }
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn transitive_includes() {
+        check(
+            r#"
+//- /src/main.erl
+-module(main).~
+-include("a.hrl").
+//- /src/other.erl
+-module(other).
+-include("b.hrl").
+//- /src/a.hrl
+-include("b.hrl").
+//- /src/b.hrl
+-define(B, b).
+"#,
+            expect![[r#"
+                /src/main.erl
+                  includes: /src/a.hrl
+                  included by:
+                  transitively included by:
+                /src/other.erl
+                  includes: /src/b.hrl
+                  included by:
+                  transitively included by:
+                /src/a.hrl
+                  includes: /src/b.hrl
+                  included by: /src/main.erl
+                  transitively included by: /src/main.erl
+                /src/b.hrl
+                  includes:
+                  included by: /src/other.erl /src/a.hrl
+                  transitively included by: /src/main.erl /src/other.erl /src/a.hrl
+            "#]],
+        );
+    }
+
+    #[test]
+    fn include_cycle() {
+        check(
+            r#"
+//- /src/main.erl
+-module(main).~
+-include("a.hrl").
+//- /src/a.hrl
+-include("b.hrl").
+//- /src/b.hrl
+-include("a.hrl").
+"#,
+            expect![[r#"
+                /src/main.erl
+                  includes: /src/a.hrl
+                  included by:
+                  transitively included by:
+                /src/a.hrl
+                  includes: /src/b.hrl
+                  included by: /src/main.erl /src/b.hrl
+                  transitively included by: /src/main.erl /src/a.hrl /src/b.hrl
+                /src/b.hrl
+                  includes: /src/a.hrl
+                  included by: /src/a.hrl
+                  transitively included by: /src/main.erl /src/a.hrl /src/b.hrl
+                cycle: /src/a.hrl /src/b.hrl
+            "#]],
+        );
+    }
+}
@@ -17,16 +17,25 @@ use hir::Semantic;
 use itertools::Itertools;
 use smallvec::smallvec;
 use smallvec::SmallVec;
+use text_edit::TextEdit;
 mod param_name;
+mod record_fields;
+mod return_type;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct InlayHintsConfig {
     pub parameter_hints: bool,
+    /// Only show parameter names for literal arguments
+    pub parameter_hints_literals_only: bool,
+    pub record_field_hints: bool,
+    pub show_return_types: bool,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum InlayKind {
     Parameter,
+    RecordFields,
+    ReturnType,
 }
 
 #[derive(Debug)]
@@ -38,6 +47,8 @@ pub struct InlayHint {
     pub kind: InlayKind,
     /// The actual label to show in the inlay hint.
     pub label: InlayHintLabel,
+    /// Text edit to apply when "accepting" this inlay hint.
+    pub text_edit: Option<TextEdit>,
 }
 
 #[derive(Debug)]
@@ -190,6 +201,8 @@ impl fmt::Debug for InlayHintLabelPart {
 // Available hints are:
 //
 // * names of function arguments
+// * number of fields omitted by a record pattern
+// * return types inferred by eqWAlizer, for functions without a spec
 pub(crate) fn inlay_hints(
     db: &RootDatabase,
     file_id: FileId,
@@ -202,6 +215,8 @@ pub(crate) fn inlay_hints(
     let mut acc = Vec::new();
 
     param_name::hints(&mut acc, &sema, config, file_id, range_limit);
+    record_fields::hints(&mut acc, &sema, config, file_id, range_limit);
+    return_type::hints(&mut acc, db, &sema, config, file_id, range_limit);
 
     acc
 }
@@ -216,6 +231,9 @@ mod tests {
 
     pub(super) const DISABLED_CONFIG: InlayHintsConfig = InlayHintsConfig {
         parameter_hints: false,
+        parameter_hints_literals_only: false,
+        record_field_hints: false,
+        show_return_types: false,
     };
 
     #[track_caller]
@@ -8,15 +8,20 @@
  */
 
 use elp_ide_db::elp_base_db::FileId;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
 use elp_syntax::TextRange;
 use hir::db::MinInternDatabase;
 use hir::Expr;
 use hir::ExprId;
+use hir::FunctionDef;
 use hir::InFile;
+use hir::Name;
 use hir::On;
 use hir::ParamName;
 use hir::Semantic;
 use hir::Strategy;
+use itertools::Itertools;
 
 use crate::InlayHint;
 use crate::InlayHintLabel;
@@ -53,35 +58,35 @@ pub(super) fn hints(
                                 if let Some(call_def) =
                                     target.resolve_call(arity, &sema, file_id, body)
                                 {
-                                    let param_names = call_def.function.param_names;
-                                    for (param_name, arg) in param_names.iter().zip(args) {
-                                        if should_hint(
+                                    let names = param_names(sema, &call_def);
+                                    for (name, arg) in names.iter().zip(args) {
+                                        let name = match name {
+                                            Some(name) => name,
+                                            None => continue,
+                                        };
+                                        if !should_hint(
                                             sema.db.upcast(),
-                                            param_name,
+                                            config,
+                                            name,
                                             &function_body[arg],
                                         ) {
-                                            if let Some(arg_range) =
-                                                def_fb.range_for_expr(sema.db, arg)
-                                            {
-                                                if range_limit.is_none()
-                                                    || range_limit
-                                                        .unwrap()
-                                                        .contains_range(arg_range)
-                                                {
-                                                    if let ParamName::Name(param_name) = param_name
-                                                    {
-                                                        let hint = InlayHint {
-                                                            range: arg_range,
-                                                            kind: InlayKind::Parameter,
-                                                            label: InlayHintLabel::simple(
-                                                                param_name.as_str(),
-                                                                None,
-                                                                None,
-                                                            ),
-                                                        };
-                                                        res.push(hint);
-                                                    }
-                                                }
+                                            continue;
+                                        }
+                                        if let Some(arg_range) = def_fb.range_for_expr(sema.db, arg)
+                                        {
+                                            if range_limit.map_or(true, |limit| {
+                                                limit.contains_range(arg_range)
+                                            }) {
+                                                res.push(InlayHint {
+                                                    range: arg_range,
+                                                    kind: InlayKind::Parameter,
+                                                    label: InlayHintLabel::simple(
+                                                        name.as_str(),
+                                                        None,
+                                                        None,
+                                                    ),
+                                                    text_edit: None,
+                                                });
                                             }
                                         }
                                     }
@@ -110,16 +115,60 @@ pub(super) fn hints(
     Some(())
 }
 
-fn should_hint(db: &dyn MinInternDatabase, param_name: &ParamName, expr: &Expr) -> bool {
-    match param_name {
-        ParamName::Name(name) => {
-            if let Some(var) = expr.as_var() {
-                var.as_string(db) != name.as_str()
-            } else {
-                true
+/// The name of each parameter of the called function: the variable
+/// used in the clause heads, or else the annotated variable of the spec.
+fn param_names(sema: &Semantic, call_def: &FunctionDef) -> Vec<Option<Name>> {
+    let spec_names = spec_param_names(sema, call_def);
+    call_def
+        .function
+        .param_names
+        .iter()
+        .enumerate()
+        .map(|(idx, param_name)| match param_name {
+            ParamName::Name(name) => Some(name.clone()),
+            ParamName::Default(_) => spec_names.get(idx).cloned().flatten(),
+        })
+        .collect()
+}
+
+/// The annotated variables of the arguments of the spec of a function,
+/// e.g. `Name` for `Name :: atom()`. A spec with several signatures has
+/// no single name per argument, so none is used.
+fn spec_param_names(sema: &Semantic, call_def: &FunctionDef) -> Vec<Option<Name>> {
+    let spec_def = sema
+        .def_map(call_def.file.file_id)
+        .get_spec(&call_def.function.name)
+        .cloned();
+    let sig =
+        spec_def.and_then(|spec_def| spec_def.source(sema.db.upcast()).sigs().exactly_one().ok());
+    let args = match sig.and_then(|sig| sig.args()) {
+        Some(args) => args,
+        None => return Vec::new(),
+    };
+    args.args()
+        .map(|arg| match arg {
+            ast::Expr::AnnType(ann) => {
+                let var = ann.var()?.var()?;
+                Some(Name::from_erlang_service(&var.syntax().text().to_string()))
             }
-        }
-        ParamName::Default(_) => false,
+            _ => None,
+        })
+        .collect()
+}
+
+fn should_hint(
+    db: &dyn MinInternDatabase,
+    config: &InlayHintsConfig,
+    name: &Name,
+    expr: &Expr,
+) -> bool {
+    if config.parameter_hints_literals_only {
+        return matches!(expr, Expr::Literal(_));
+    }
+    if let Some(var) = expr.as_var() {
+        var.as_string(db) != name.as_str()
+    } else {
+        true
     }
 }
 
@@ -290,6 +339,47 @@ call(One, Two, Three = {_, _, _}) ->
         );
     }
 
+    #[test]
+    fn param_hints_from_spec() {
+        check_params(
+            r#"
+-module(main).~
+-compile(export_all).
+-spec area({number(), number()}, Scale :: number()) -> number().
+area({W, H}, _) -> W * H.
+-spec both(Left :: term(), Right :: term()) -> ok;
+          (integer(), integer()) -> ok.
+both(_, _) -> ok.
+main() ->
+  area({1, 2}, 3),
+            %% ^Scale
+  both(1, 2).
+"#,
+        );
+    }
+
+    #[test]
+    fn param_hints_literals_only() {
+        check_with_config(
+            InlayHintsConfig {
+                parameter_hints: true,
+                parameter_hints_literals_only: true,
+                ..DISABLED_CONFIG
+            },
+            r#"
+-module(main).~
+-compile(export_all).
+sum(A, B) -> A + B.
+main() ->
+  X = 2,
+  sum(1,
+   %% ^A
+      X * 2
+     ).
+"#,
+        );
+    }
+
     #[test]
     fn param_hints_variables_skip_default_names() {
         check_params(
@@ -0,0 +1,106 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use elp_ide_assists::helpers::omitted_record_fields;
+use elp_ide_db::elp_base_db::FileId;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use elp_syntax::TextRange;
+use hir::Semantic;
+use text_edit::TextEdit;
+
+use crate::InlayHint;
+use crate::InlayHintLabel;
+use crate::InlayHintsConfig;
+use crate::InlayKind;
+use crate::InlayTooltip;
+
+/// Summarise the fields a record pattern does not match, e.g.
+/// `#person{name = N … 2 more fields}`.
+pub(super) fn hints(
+    res: &mut Vec<InlayHint>,
+    sema: &Semantic,
+    config: &InlayHintsConfig,
+    file_id: FileId,
+    range_limit: Option<TextRange>,
+) -> Option<()> {
+    if !config.record_field_hints {
+        return None;
+    }
+    let source = sema.parse(file_id).value;
+    for record in source
+        .syntax()
+        .descendants()
+        .filter_map(ast::RecordExpr::cast)
+    {
+        let range = record.syntax().text_range();
+        if range_limit.map_or(false, |limit| !limit.contains_range(range)) {
+            continue;
+        }
+        let omitted = match omitted_record_fields(sema, file_id, &record) {
+            // A pattern without fields is a plain record test
+            Some(omitted) if omitted.has_fields => omitted,
+            _ => continue,
+        };
+        let count = omitted.fields.len();
+        let label = if count == 1 {
+            "… 1 more field".to_string()
+        } else {
+            format!("… {count} more fields")
+        };
+        let tooltip = format!("Omitted fields: {}", omitted.fields.join(", "));
+        res.push(InlayHint {
+            range: omitted.rbrace,
+            kind: InlayKind::RecordFields,
+            label: InlayHintLabel::simple(label, Some(InlayTooltip::String(tooltip)), None),
+            text_edit: Some(TextEdit::insert(omitted.insert_at, omitted.render())),
+        });
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::inlay_hints::tests::check_with_config;
+    use crate::inlay_hints::tests::DISABLED_CONFIG;
+    use crate::inlay_hints::InlayHintsConfig;
+
+    #[track_caller]
+    fn check_record_fields(fixture: &str) {
+        check_with_config(
+            InlayHintsConfig {
+                record_field_hints: true,
+                ..DISABLED_CONFIG
+            },
+            fixture,
+        );
+    }
+
+    #[test]
+    fn record_patterns() {
+        check_record_fields(
+            r#"
+-module(main).~
+-record(person, {name, age, email}).
+-record(point, {x, y}).
+name(#person{name = N}) -> N.
+%%                   ^ … 2 more fields
+x(P) ->
+    case P of
+        #point{x = X} -> X;
+%%                  ^ … 1 more field
+        #person{} -> undefined
+    end.
+full(#point{x = X, y = Y}) -> {X, Y}.
+any(#person{name = N, _ = '_'}) -> N.
+make() -> #point{x = 1}.
+"#,
+        );
+    }
+}
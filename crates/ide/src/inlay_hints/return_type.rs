@@ -0,0 +1,148 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::EqwalizerDatabase;
+use elp_ide_db::EqwalizerTypeInfo;
+use elp_ide_db::RootDatabase;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use elp_syntax::SyntaxKind;
+use elp_syntax::TextRange;
+use hir::Semantic;
+
+use crate::InlayHint;
+use crate::InlayHintLabel;
+use crate::InlayHintsConfig;
+use crate::InlayKind;
+
+/// Show the return type eqWAlizer inferred for each clause of the
+/// functions without a spec, after the `->` of the clause head. The
+/// types come from the last eqWAlizer diagnostics run over the current
+/// text of the file, and there are no hints until there is one:
+/// eqWAlizer is never run for the hints.
+pub(super) fn hints(
+    res: &mut Vec<InlayHint>,
+    db: &RootDatabase,
+    sema: &Semantic,
+    config: &InlayHintsConfig,
+    file_id: FileId,
+    range_limit: Option<TextRange>,
+) -> Option<()> {
+    if !config.show_return_types {
+        return None;
+    }
+    let types = db.types_for_file(file_id)?;
+    let def_map = sema.def_map(file_id);
+    for (name, def) in def_map.get_functions() {
+        if def.file.file_id != file_id || def_map.get_spec(name).is_some() {
+            continue;
+        }
+        for clause in def.source(db).clauses() {
+            if let ast::FunctionOrMacroClause::FunctionClause(clause) = clause {
+                if let Some(hint) = clause_hint(&types, &clause, range_limit) {
+                    res.push(hint);
+                }
+            }
+        }
+    }
+    Some(())
+}
+
+fn clause_hint(
+    types: &[EqwalizerTypeInfo],
+    clause: &ast::FunctionClause,
+    range_limit: Option<TextRange>,
+) -> Option<InlayHint> {
+    let body = clause.body()?;
+    let arrow = body
+        .syntax()
+        .children_with_tokens()
+        .find(|it| it.kind() == SyntaxKind::ANON_DASH_GT)?;
+    let range = arrow.text_range();
+    if range_limit.map_or(false, |limit| !limit.contains_range(range)) {
+        return None;
+    }
+    // A clause returns the value of its last expression
+    let last = body.exprs().last()?.syntax().text_range();
+    let info = types.iter().find(|info| info.range == last)?;
+    Some(InlayHint {
+        range,
+        kind: InlayKind::ReturnType,
+        label: InlayHintLabel::simple(info.ty.as_str(), None, None),
+        text_edit: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use elp_ide_db::EqwalizerTypeInfo;
+    use elp_syntax::ast;
+    use elp_syntax::AstNode;
+    use elp_syntax::SourceFile;
+    use elp_syntax::TextRange;
+    use elp_syntax::TextSize;
+
+    use super::clause_hint;
+    use crate::inlay_hints::tests::check_with_config;
+    use crate::inlay_hints::tests::DISABLED_CONFIG;
+    use crate::inlay_hints::InlayHintsConfig;
+
+    fn clause_hints(text: &str, types: &[(&str, &str)]) -> Vec<(String, String)> {
+        let types: Vec<EqwalizerTypeInfo> = types
+            .iter()
+            .map(|(expr, ty)| {
+                let start = TextSize::from(text.find(expr).unwrap() as u32);
+                EqwalizerTypeInfo {
+                    range: TextRange::at(start, TextSize::of(*expr)),
+                    ty: ty.to_string(),
+                }
+            })
+            .collect();
+        let source = SourceFile::parse_text(text).tree();
+        source
+            .syntax()
+            .descendants()
+            .filter_map(ast::FunctionClause::cast)
+            .filter_map(|clause| clause_hint(&types, &clause, None))
+            .map(|hint| (text[hint.range].to_string(), hint.label.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn type_of_last_expression() {
+        let hints = clause_hints(
+            "-module(main).\nsize(0) -> zero;\nsize(N) -> log(N), N * 2.\n",
+            &[("zero", "'zero'"), ("log(N)", "ok"), ("N * 2", "number()")],
+        );
+        assert_eq!(
+            hints,
+            vec![
+                ("->".to_string(), "'zero'".to_string()),
+                ("->".to_string(), "number()".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn no_type_information() {
+        // No eqWAlizer diagnostics were computed for the file
+        check_with_config(
+            InlayHintsConfig {
+                show_return_types: true,
+                ..DISABLED_CONFIG
+            },
+            r#"
+-module(main).~
+-typing([eqwalizer]).
+sum(A, B) -> A + B.
+"#,
+        );
+    }
+}
@@ -14,15 +14,18 @@ use anyhow::Result;
 use call_hierarchy::CallItem;
 use diagnostics::Diagnostic;
 use diagnostics::DiagnosticsConfig;
+use diagnostics::DiagnosticsTiming;
 use elp_ide_assists::Assist;
 use elp_ide_assists::AssistConfig;
 use elp_ide_assists::AssistId;
 use elp_ide_assists::AssistKind;
 use elp_ide_assists::AssistResolveStrategy;
 use elp_ide_completion::Completion;
+use elp_ide_completion::CompletionConfig;
 use elp_ide_db::assists::AssistContextDiagnostic;
 use elp_ide_db::assists::AssistUserInput;
 use elp_ide_db::docs::Doc;
+use elp_ide_db::elp_base_db::fixture::ChangeFixture;
 use elp_ide_db::elp_base_db::salsa;
 use elp_ide_db::elp_base_db::salsa::ParallelDatabase;
 use elp_ide_db::elp_base_db::Change;
@@ -35,6 +38,8 @@ use elp_ide_db::elp_base_db::ProjectData;
 use elp_ide_db::elp_base_db::ProjectId;
 use elp_ide_db::elp_base_db::SourceDatabase;
 use elp_ide_db::elp_base_db::SourceDatabaseExt;
+use elp_ide_db::elp_base_db::VfsPath;
+use elp_ide_db::eqwalizer;
 use elp_ide_db::erlang_service::ParseResult;
 use elp_ide_db::label::Label;
 use elp_ide_db::rename::RenameError;
@@ -56,44 +61,72 @@ use elp_syntax::AstNode;
 use expand_macro::ExpandedMacro;
 use handlers::get_docs;
 use handlers::goto_definition;
+use handlers::goto_implementation;
+use handlers::message_flow;
 use handlers::references;
 use hir::db::MinDefDatabase;
 use hir::DefMap;
-use hir::File;
-use hir::Module;
 use hir::Semantic;
-use navigation_target::ToNav;
+use text_edit::TextEdit;
 
 mod annotations;
 mod call_hierarchy;
+mod code_index;
+mod code_lens;
 mod codemod_helpers;
 mod common_test;
+mod dead_code;
 mod doc_links;
+mod document_links;
 mod document_symbols;
 mod expand_macro;
 mod extend_selection;
 mod folding_ranges;
+mod formatting;
+mod guard_helpers;
 mod handlers;
 mod inlay_hints;
+mod linked_editing_ranges;
 mod navigation_target;
+mod organize_module;
 mod rename;
 mod runnables;
 mod signature_help;
+mod symbol_search;
 mod syntax_highlighting;
+mod test_results;
+mod type_hierarchy;
+mod typing;
 
 #[cfg(test)]
 mod fixture;
 #[cfg(test)]
 mod tests;
 
+pub mod apply_assist;
+pub mod codemod;
 pub mod diagnostics;
 pub mod diff;
 mod highlight_related;
+mod include_graph;
 // @fb-only: mod meta_only;
 
 pub use annotations::Annotation;
+pub use annotations::AnnotationConfig;
 pub use annotations::AnnotationKind;
+pub use annotations::AnnotationResolveStrategy;
+pub use code_index::CodeIndex;
+pub use code_index::IndexedCall;
+pub use code_index::IndexedReference;
+pub use code_lens::CodeLens;
+pub use code_lens::CodeLensConfig;
+pub use code_lens::CodeLensKind;
 pub use common_test::GroupName;
+pub use dead_code::DeadCode;
+pub use dead_code::DeadCodeKind;
+pub use document_links::DocumentLink;
+pub use document_links::DocumentLinksConfig;
+pub use document_links::IssuePattern;
 pub use document_symbols::DocumentSymbol;
 pub use elp_ide_assists;
 pub use elp_ide_completion;
@@ -101,10 +134,12 @@ pub use elp_ide_db;
 pub use elp_ide_db::erlang_service;
 pub use elp_syntax::TextRange;
 pub use elp_syntax::TextSize;
+pub use expand_macro::ExpandMacroMode;
 pub use folding_ranges::Fold;
 pub use folding_ranges::FoldKind;
 pub use handlers::references::ReferenceSearchResult;
 pub use highlight_related::HighlightedRange;
+pub use include_graph::IncludeGraph;
 pub use inlay_hints::InlayHint;
 pub use inlay_hints::InlayHintLabel;
 pub use inlay_hints::InlayHintLabelPart;
@@ -115,12 +150,19 @@ pub use navigation_target::NavigationTarget;
 pub use runnables::Runnable;
 pub use runnables::RunnableKind;
 pub use signature_help::SignatureHelp;
+pub use symbol_search::SymbolSearchResult;
 pub use syntax_highlighting::tags::Highlight;
 pub use syntax_highlighting::tags::HlMod;
 pub use syntax_highlighting::tags::HlMods;
 pub use syntax_highlighting::tags::HlTag;
 pub use syntax_highlighting::HighlightConfig;
+pub use syntax_highlighting::HighlightDelta;
+pub use syntax_highlighting::HighlightEdit;
 pub use syntax_highlighting::HlRange;
+pub use test_results::RunnableStatus;
+pub use test_results::TestResults;
+pub use test_results::TestStatus;
+pub use typing::ON_TYPE_FORMATTING_TRIGGERS;
 
 pub type Cancellable<T> = Result<T, salsa::Cancelled>;
 
@@ -144,6 +186,20 @@ pub struct AnalysisHost {
 }
 
 impl AnalysisHost {
+    /// Creates a host for a project supplied entirely in memory, with no
+    /// `Vfs` or loader involved. The project is described in the fixture
+    /// format, a `//- /path app:name` line starting each file, see
+    /// `elp_base_db::test_fixture`. File contents are taken verbatim.
+    /// Returns the ids of the files, in the order they are described,
+    /// or an error if the description is invalid.
+    pub fn from_project_description(description: &str) -> Result<(AnalysisHost, Vec<FileId>)> {
+        let (fixture, change) = ChangeFixture::parse_project(description)
+            .map_err(|err| anyhow::anyhow!("Invalid project description: {}", err))?;
+        let mut host = AnalysisHost::default();
+        host.apply_change(change);
+        Ok((host, fixture.files))
+    }
+
     /// Returns a snapshot of the current state, which you can query for
     /// semantic information.
     pub fn analysis(&self) -> Analysis {
@@ -201,6 +257,22 @@ impl Analysis {
         self.with_db(|db| diagnostics::diagnostics(db, config, file_id, include_generated))
     }
 
+    /// Times each pass computing the diagnostics of the given file,
+    /// including the erlang_service, EDoc and eqwalizer ones.
+    pub fn diagnostics_timing(
+        &self,
+        config: &DiagnosticsConfig,
+        file_id: FileId,
+        include_generated: bool,
+    ) -> Cancellable<DiagnosticsTiming> {
+        self.with_db(|db| diagnostics::diagnostics_timing(db, config, file_id, include_generated))
+    }
+
+    /// Computes the set of `xref` style diagnostics for the given file.
+    pub fn xref_diagnostics(&self, file_id: FileId) -> Cancellable<Vec<Diagnostic>> {
+        self.with_db(|db| diagnostics::xref_diagnostics(db, file_id))
+    }
+
     /// Computes the set of eqwalizer diagnostics for the given file.
     pub fn eqwalizer_diagnostics(
         &self,
@@ -210,6 +282,24 @@ impl Analysis {
         self.with_db(|db| db.eqwalizer_diagnostics(project_id, file_ids))
     }
 
+    /// Returns the type eqWAlizer inferred for the expression at the
+    /// given position, if the eqWAlizer diagnostics were computed for
+    /// the current text of the file. This never runs eqWAlizer.
+    pub fn type_at_position(
+        &self,
+        position: FilePosition,
+    ) -> Cancellable<Option<(String, FileRange)>> {
+        self.with_db(|db| {
+            eqwalizer::type_at_position(db, position).map(|info| {
+                let range = FileRange {
+                    file_id: position.file_id,
+                    range: info.range,
+                };
+                (info.ty, range)
+            })
+        })
+    }
+
     pub fn eqwalizer_stats(
         &self,
         project_id: ProjectId,
@@ -223,6 +313,11 @@ impl Analysis {
         self.with_db(|db| diagnostics::edoc_diagnostics(db, file_id))
     }
 
+    /// Computes the set of lint plugin diagnostics for the given file.
+    pub fn plugin_diagnostics(&self, file_id: FileId) -> Cancellable<Vec<Diagnostic>> {
+        self.with_db(|db| diagnostics::plugin_diagnostics(db, file_id))
+    }
+
     /// Computes the set of parse server diagnostics for the given file.
     pub fn erlang_service_diagnostics(
         &self,
@@ -277,6 +372,15 @@ impl Analysis {
         })
     }
 
+    /// Returns the path of the file, as known to its source root
+    pub fn file_path(&self, file_id: FileId) -> Cancellable<Option<VfsPath>> {
+        self.with_db(|db| {
+            db.source_root(db.file_source_root(file_id))
+                .path_for_file(&file_id)
+                .cloned()
+        })
+    }
+
     pub fn module_index(&self, project_id: ProjectId) -> Cancellable<Arc<ModuleIndex>> {
         self.with_db(|db| db.module_index(project_id))
     }
@@ -289,8 +393,56 @@ impl Analysis {
         self.with_db(|db| db.module_index(project_id).file_for_module(module))
     }
 
-    pub fn expand_macro(&self, position: FilePosition) -> Cancellable<Option<ExpandedMacro>> {
-        self.with_db(|db| expand_macro::expand_macro(db, position))
+    /// Returns the private functions of the project modules which are
+    /// not reachable from their exported functions and behaviour
+    /// callbacks, and the records and macros they alone use.
+    pub fn dead_code(&self, project_id: ProjectId) -> Cancellable<Vec<DeadCode>> {
+        self.with_db(|db| dead_code::dead_code(db, project_id))
+    }
+
+    /// Returns which files of the project include which headers, and
+    /// the reverse, following the includes of the headers too.
+    pub fn include_graph(&self, project_id: ProjectId) -> Cancellable<IncludeGraph> {
+        self.with_db(|db| include_graph::include_graph(db, project_id))
+    }
+
+    pub fn expand_macro(
+        &self,
+        position: FilePosition,
+        mode: ExpandMacroMode,
+    ) -> Cancellable<Option<ExpandedMacro>> {
+        self.with_db(|db| expand_macro::expand_macro(db, position, mode))
+    }
+
+    /// Returns the edit to apply after `typed_char` was inserted just
+    /// before `position`, e.g. to re-indent a closing `end`.
+    pub fn on_type_formatting(
+        &self,
+        position: FilePosition,
+        typed_char: char,
+    ) -> Cancellable<Option<TextEdit>> {
+        self.with_db(|db| typing::on_type_formatting(db, position, typed_char))
+    }
+
+    /// Formats a file, returning `None` if it has syntax errors or is
+    /// already formatted.
+    pub fn format_file(&self, file_id: FileId) -> Cancellable<Option<SourceChange>> {
+        self.with_db(|db| formatting::format_file(db, file_id))
+    }
+
+    /// Formats the lines of a file in `frange`.
+    pub fn format_range(&self, frange: FileRange) -> Cancellable<Option<SourceChange>> {
+        self.with_db(|db| formatting::format_range(db, frange))
+    }
+
+    /// Sorts and deduplicates the `-export`, `-export_type` and `-import`
+    /// attributes of a file, dropping the exports of undefined functions.
+    pub fn organize_module(
+        &self,
+        file_id: FileId,
+        config: &AssistConfig,
+    ) -> Cancellable<Option<SourceChange>> {
+        self.with_db(|db| organize_module::organize_module(db, file_id, config))
     }
 
     /// Selects the next syntactic nodes encompassing the range.
@@ -298,12 +450,27 @@ impl Analysis {
         self.with_db(|db| extend_selection::extend_selection(db, frange))
     }
 
+    /// Returns for each offset the chain of ranges that successive calls
+    /// to `extend_selection` would select, innermost first.
+    pub fn selection_ranges(
+        &self,
+        file_id: FileId,
+        offsets: Vec<TextSize>,
+    ) -> Cancellable<Vec<Vec<TextRange>>> {
+        self.with_db(|db| extend_selection::selection_ranges(db, file_id, &offsets))
+    }
+
     /// Returns a list of symbols in the file. Useful to draw a
     /// file outline.
     pub fn document_symbols(&self, file_id: FileId) -> Cancellable<Vec<DocumentSymbol>> {
         self.with_db(|db| document_symbols::document_symbols(db, file_id))
     }
 
+    /// Returns the definitions, references, calls and includes of a file
+    pub fn code_index(&self, file_id: FileId) -> Cancellable<CodeIndex> {
+        self.with_db(|db| code_index::code_index(db, file_id))
+    }
+
     /// Returns the contents of a file
     pub fn file_text(&self, file_id: FileId) -> Cancellable<Arc<String>> {
         self.with_db(|db| db.file_text(file_id))
@@ -370,33 +537,16 @@ impl Analysis {
         self.with_db(|db| db.is_test_suite_or_test_helper(file_id))
     }
 
-    /// Search symbols. Only module names are currently supported.
+    /// Search symbols, returning at most `limit` results starting at
+    /// `offset`. Only module names are currently supported.
     pub fn symbol_search(
         &self,
         project_id: ProjectId,
         query: &str,
-    ) -> Cancellable<Vec<NavigationTarget>> {
-        const LIMIT: i32 = 128;
-        self.with_db(|db| {
-            let module_index = self.module_index(project_id).unwrap();
-            let mut total = 0;
-            module_index
-                .all_modules()
-                .iter()
-                .filter_map(|name: &ModuleName| {
-                    if total <= LIMIT && name.as_str().contains(query) {
-                        let file_id = module_index.file_for_module(name)?;
-                        let module = Module {
-                            file: File { file_id },
-                        };
-                        total += 1;
-                        Some(module.to_nav(db))
-                    } else {
-                        None
-                    }
-                })
-                .collect()
-        })
+        offset: usize,
+        limit: usize,
+    ) -> Cancellable<SymbolSearchResult> {
+        self.with_db(|db| symbol_search::symbol_search(db, project_id, query, offset, limit))
     }
 
     pub fn goto_definition(
@@ -406,6 +556,24 @@ impl Analysis {
         self.with_db(|db| goto_definition::goto_definition(db, position))
     }
 
+    /// From a `-callback` of a behaviour, the functions implementing it
+    /// in the modules of the project
+    pub fn goto_implementation(
+        &self,
+        position: FilePosition,
+    ) -> Cancellable<Option<RangeInfo<Vec<NavigationTarget>>>> {
+        self.with_db(|db| goto_implementation::goto_implementation(db, position))
+    }
+
+    /// From a `gen_server` or `gen_statem` call or cast, the callback
+    /// clauses which may receive its message, and back
+    pub fn message_flow(
+        &self,
+        position: FilePosition,
+    ) -> Cancellable<Option<RangeInfo<Vec<NavigationTarget>>>> {
+        self.with_db(|db| message_flow::message_flow(db, position))
+    }
+
     /// Returns the docs for the symbol at the given position
     pub fn get_docs_at_position(
         &self,
@@ -419,21 +587,34 @@ impl Analysis {
         &self,
         position: FilePosition,
     ) -> Cancellable<Option<Vec<ReferenceSearchResult>>> {
-        self.with_db(|db| references::find_all_refs(&Semantic::new(db), position))
+        self.with_db(|db| {
+            references::find_table_key_refs(db, position)
+                .or_else(|| references::find_all_refs(&Semantic::new(db), position))
+        })
     }
 
     pub fn completions(
         &self,
+        config: &CompletionConfig,
         position: FilePosition,
         trigger_character: Option<char>,
     ) -> Cancellable<Vec<Completion>> {
-        self.with_db(|db| elp_ide_completion::completions(db, position, trigger_character))
+        self.with_db(|db| elp_ide_completion::completions(db, config, position, trigger_character))
     }
 
     pub fn resolved_includes(&self, file_id: FileId) -> Cancellable<Option<Includes>> {
         self.with_db(|db| db.resolved_includes(file_id))
     }
 
+    /// Returns the range and current name of the symbol at the position,
+    /// or an error if it cannot be renamed.
+    pub fn prepare_rename(
+        &self,
+        position: FilePosition,
+    ) -> Cancellable<Result<RangeInfo<String>, RenameError>> {
+        self.with_db(|db| rename::prepare_rename(db, position))
+    }
+
     /// Returns the edit required to rename the thing at the position to the new
     /// name.
     pub fn rename(
@@ -444,11 +625,42 @@ impl Analysis {
         self.with_db(|db| rename::rename(db, position, new_name))
     }
 
+    /// Returns the edits required when the file of a module is renamed
+    /// by the client, so that the module is renamed to `new_name`. The
+    /// file move itself is not part of the change.
+    pub fn will_rename_file(
+        &self,
+        file_id: FileId,
+        new_name: &str,
+    ) -> Cancellable<Result<Option<SourceChange>, RenameError>> {
+        self.with_db(|db| rename::will_rename_file(db, file_id, new_name))
+    }
+
     /// Returns the set of folding ranges.
     pub fn folding_ranges(&self, file_id: FileId) -> Cancellable<Vec<Fold>> {
         self.with_db(|db| folding_ranges::folding_ranges(db, file_id))
     }
 
+    /// Returns the links of the URLs and issue ids in the comments and
+    /// strings of the file.
+    pub fn document_links(
+        &self,
+        file_id: FileId,
+        config: &DocumentLinksConfig,
+    ) -> Cancellable<Vec<DocumentLink>> {
+        self.with_db(|db| document_links::document_links(db, file_id, config))
+    }
+
+    /// Returns the ranges of the names of all the clauses of the
+    /// function, when `position` is on one of them, to edit them
+    /// together.
+    pub fn linked_editing_ranges(
+        &self,
+        position: FilePosition,
+    ) -> Cancellable<Option<Vec<TextRange>>> {
+        self.with_db(|db| linked_editing_ranges::linked_editing_ranges(db, position))
+    }
+
     /// Computes call hierarchy candidates for the given file position.
     pub fn call_hierarchy_prepare(
         &self,
@@ -458,13 +670,35 @@ impl Analysis {
     }
 
     /// Computes incoming calls for the given file position.
-    pub fn incoming_calls(&self, position: FilePosition) -> Cancellable<Option<Vec<CallItem>>> {
-        self.with_db(|db| call_hierarchy::incoming_calls(db, position))
+    pub fn incoming_calls(
+        &self,
+        position: FilePosition,
+        include_dynamic: bool,
+    ) -> Cancellable<Option<Vec<CallItem>>> {
+        self.with_db(|db| call_hierarchy::incoming_calls(db, position, include_dynamic))
     }
 
     /// Computes outgoing calls for the given file position.
-    pub fn outgoing_calls(&self, position: FilePosition) -> Cancellable<Option<Vec<CallItem>>> {
-        self.with_db(|db| call_hierarchy::outgoing_calls(db, position))
+    pub fn outgoing_calls(
+        &self,
+        position: FilePosition,
+        include_dynamic: bool,
+    ) -> Cancellable<Option<Vec<CallItem>>> {
+        self.with_db(|db| call_hierarchy::outgoing_calls(db, position, include_dynamic))
+    }
+
+    /// Computes type hierarchy candidates for the given file position.
+    pub fn type_hierarchy_prepare(
+        &self,
+        position: FilePosition,
+    ) -> Cancellable<Option<RangeInfo<Vec<NavigationTarget>>>> {
+        self.with_db(|db| type_hierarchy::type_hierarchy_prepare(db, position))
+    }
+
+    /// Computes the specs, callbacks and types referring to the type at
+    /// the given file position.
+    pub fn incoming_types(&self, position: FilePosition) -> Cancellable<Option<Vec<CallItem>>> {
+        self.with_db(|db| type_hierarchy::incoming_types(db, position))
     }
 
     /// Computes parameter information at the given position.
@@ -490,6 +724,16 @@ impl Analysis {
         self.with_db(|db| syntax_highlighting::highlight(db, file_id, None))
     }
 
+    /// Computes syntax highlighting for the given file, and the edit
+    /// from the `previous` highlighting of the whole file.
+    pub fn highlight_delta(
+        &self,
+        file_id: FileId,
+        previous: &[HlRange],
+    ) -> Cancellable<HighlightDelta> {
+        self.with_db(|db| syntax_highlighting::highlight_delta(db, file_id, previous))
+    }
+
     /// Computes all ranges to highlight for a given item in a file.
     pub fn highlight_related(
         &self,
@@ -503,17 +747,52 @@ impl Analysis {
         self.with_db(|db| syntax_highlighting::highlight(db, frange.file_id, Some(frange.range)))
     }
 
-    pub fn annotations(&self, file_id: FileId) -> Cancellable<Vec<Annotation>> {
-        self.with_db(|db| annotations::annotations(db, file_id))
+    pub fn annotations(
+        &self,
+        file_id: FileId,
+        config: &AnnotationConfig,
+        resolve: AnnotationResolveStrategy,
+    ) -> Cancellable<Vec<Annotation>> {
+        self.with_db(|db| annotations::annotations(db, file_id, config, resolve))
+    }
+
+    /// Computes the payload of an annotation returned unresolved by
+    /// `annotations`.
+    pub fn resolve_annotation(&self, annotation: Annotation) -> Cancellable<Annotation> {
+        self.with_db(|db| annotations::resolve_annotation(db, annotation))
+    }
+
+    /// Computes the code lenses enabled in `config` for the given file.
+    pub fn code_lenses(
+        &self,
+        file_id: FileId,
+        config: &CodeLensConfig,
+    ) -> Cancellable<Vec<CodeLens>> {
+        self.with_db(|db| code_lens::code_lenses(db, file_id, config))
     }
 
     pub fn runnables(&self, file_id: FileId) -> Cancellable<Vec<Runnable>> {
         self.with_db(|db| runnables::runnables(db, file_id))
     }
 
+    /// Returns the runnables of the file, with their status in the
+    /// given results of a test run.
+    pub fn test_statuses(
+        &self,
+        file_id: FileId,
+        results: &TestResults,
+    ) -> Cancellable<Vec<RunnableStatus>> {
+        self.with_db(|db| test_results::test_statuses(db, file_id, results))
+    }
+
     /// Return URL(s) for the documentation of the symbol under the cursor.
-    pub fn external_docs(&self, position: FilePosition) -> Cancellable<Option<Vec<String>>> {
-        self.with_db(|db| doc_links::external_docs(db, &position))
+    /// Links to the OTP documentation start with `otp_docs_base_url`.
+    pub fn external_docs(
+        &self,
+        position: FilePosition,
+        otp_docs_base_url: &str,
+    ) -> Cancellable<Option<Vec<String>>> {
+        self.with_db(|db| doc_links::external_docs(db, &position, otp_docs_base_url))
     }
 
     /// Return TextRange for the form enclosing the given position
@@ -581,5 +860,6 @@ fn unresolved_fix(id: &'static str, label: &str, target: TextRange) -> Assist {
         target,
         source_change: None,
         user_input: None,
+        disabled: None,
     }
 }
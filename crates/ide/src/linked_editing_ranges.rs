@@ -0,0 +1,116 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use elp_ide_db::RootDatabase;
+use elp_syntax::algo;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use elp_syntax::TextRange;
+use hir::Semantic;
+
+use crate::FilePosition;
+
+// Feature: Linked Editing
+//
+// Editing the name of a clause of a function edits the names of its
+// other clauses too.
+pub(crate) fn linked_editing_ranges(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<Vec<TextRange>> {
+    let sema = Semantic::new(db);
+    let source_file = sema.parse(position.file_id);
+    let fun_decl =
+        algo::find_node_at_offset::<ast::FunDecl>(source_file.value.syntax(), position.offset)?;
+    let ranges: Vec<TextRange> = fun_decl
+        .clauses()
+        .filter_map(|clause| match clause {
+            ast::FunctionOrMacroClause::FunctionClause(clause) => {
+                Some(clause.name()?.syntax().text_range())
+            }
+            ast::FunctionOrMacroClause::MacroCallExpr(_) => None,
+        })
+        .collect();
+    if ranges.len() < 2
+        || !ranges
+            .iter()
+            .any(|range| range.contains_inclusive(position.offset))
+    {
+        return None;
+    }
+    Some(ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixture;
+
+    #[track_caller]
+    fn check(fixture: &str) {
+        let (analysis, position, expected) = fixture::annotations(fixture);
+        let mut expected: Vec<_> = expected.into_iter().map(|(range, _)| range.range).collect();
+        expected.sort_by_key(|range| range.start());
+        let actual = analysis
+            .linked_editing_ranges(position)
+            .unwrap()
+            .unwrap_or_default();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn function_clauses() {
+        check(
+            r#"
+-module(main).
+   fo~o(0) -> zero;
+%% ^^^
+   foo(N) when N > 0 -> positive;
+%% ^^^
+   foo(_) -> negative.
+%% ^^^
+bar() -> ok.
+"#,
+        );
+    }
+
+    #[test]
+    fn end_of_name() {
+        check(
+            r#"
+-module(main).
+bar() -> ok.
+   baz(a) -> ok;
+%% ^^^
+   baz~(b) -> ok.
+%% ^^^
+"#,
+        );
+    }
+
+    #[test]
+    fn single_clause() {
+        check(
+            r#"
+-module(main).
+fo~o() -> ok.
+"#,
+        );
+    }
+
+    #[test]
+    fn not_on_name() {
+        check(
+            r#"
+-module(main).
+foo(0) -> z~ero;
+foo(_) -> other.
+"#,
+        );
+    }
+}
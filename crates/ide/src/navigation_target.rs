@@ -13,8 +13,12 @@ use std::fmt;
 
 use elp_ide_db::elp_base_db::FileId;
 use elp_ide_db::elp_base_db::FileRange;
+use elp_ide_db::elp_base_db::SourceDatabase;
+use elp_ide_db::table_keys::TableKeyRef;
 use elp_ide_db::SymbolDefinition;
 use elp_ide_db::SymbolKind;
+use elp_project_model::AppName;
+use elp_project_model::AppType;
 use elp_syntax::ast;
 use elp_syntax::AstNode;
 use elp_syntax::SmolStr;
@@ -45,6 +49,10 @@ pub struct NavigationTarget {
     pub focus_range: Option<TextRange>,
     pub name: SmolStr,
     pub kind: SymbolKind,
+    /// The application defining the element, so results can be
+    /// grouped by application.
+    pub app_name: Option<AppName>,
+    pub app_type: Option<AppType>,
 }
 
 impl fmt::Debug for NavigationTarget {
@@ -112,6 +120,8 @@ impl ToNav for hir::Module {
             focus_range,
             name: self.name(db).raw(),
             kind: SymbolKind::Module,
+            app_name: db.file_app_name(file_id),
+            app_type: db.file_app_type(file_id),
         }
     }
 }
@@ -135,6 +145,28 @@ impl ToNav for hir::FunctionDef {
             focus_range,
             name: SmolStr::new(format!("{name}/{arity}")),
             kind: SymbolKind::Function,
+            app_name: db.file_app_name(file_id),
+            app_type: db.file_app_type(file_id),
+        }
+    }
+}
+
+impl ToNav for hir::SpecDef {
+    fn to_nav(&self, db: &dyn MinDefDatabase) -> NavigationTarget {
+        let file_id = self.file.file_id;
+        let source = self.source(db.upcast());
+        let full_range = source.syntax().text_range();
+        let focus_range = source.fun().map(|name| name.syntax().text_range());
+        let arity = self.spec.name.arity();
+        let name = self.spec.name.name().raw();
+        NavigationTarget {
+            file_id,
+            full_range,
+            focus_range,
+            name: SmolStr::new(format!("{name}/{arity}")),
+            kind: SymbolKind::Function,
+            app_name: db.file_app_name(file_id),
+            app_type: db.file_app_type(file_id),
         }
     }
 }
@@ -151,6 +183,8 @@ impl ToNav for hir::RecordDef {
             focus_range,
             name: self.record.name.raw(),
             kind: SymbolKind::Record,
+            app_name: db.file_app_name(file_id),
+            app_type: db.file_app_type(file_id),
         }
     }
 }
@@ -167,6 +201,8 @@ impl ToNav for hir::RecordFieldDef {
             focus_range,
             name: self.field.name.raw(),
             kind: SymbolKind::RecordField,
+            app_name: db.file_app_name(file_id),
+            app_type: db.file_app_type(file_id),
         }
     }
 }
@@ -183,6 +219,8 @@ impl ToNav for hir::TypeAliasDef {
             focus_range,
             name: self.type_alias.name().name().raw(),
             kind: SymbolKind::Type,
+            app_name: db.file_app_name(file_id),
+            app_type: db.file_app_type(file_id),
         }
     }
 }
@@ -199,6 +237,8 @@ impl ToNav for hir::CallbackDef {
             focus_range,
             name: self.callback.name.name().raw(),
             kind: SymbolKind::Callback,
+            app_name: db.file_app_name(file_id),
+            app_type: db.file_app_type(file_id),
         }
     }
 }
@@ -215,6 +255,22 @@ impl ToNav for hir::DefineDef {
             focus_range,
             name: self.define.name.name().raw(),
             kind: SymbolKind::Define,
+            app_name: db.file_app_name(file_id),
+            app_type: db.file_app_type(file_id),
+        }
+    }
+}
+
+impl ToNav for hir::InFile<TableKeyRef> {
+    fn to_nav(&self, db: &dyn MinDefDatabase) -> NavigationTarget {
+        NavigationTarget {
+            file_id: self.file_id,
+            full_range: self.value.range,
+            focus_range: None,
+            name: self.value.key.raw(),
+            kind: SymbolKind::TableKey,
+            app_name: db.file_app_name(self.file_id),
+            app_type: db.file_app_type(self.file_id),
         }
     }
 }
@@ -229,6 +285,8 @@ impl ToNav for hir::File {
             focus_range: None,
             name: self.name(db.upcast()),
             kind: SymbolKind::File,
+            app_name: db.file_app_name(self.file_id),
+            app_type: db.file_app_type(self.file_id),
         }
     }
 }
@@ -243,6 +301,8 @@ impl ToNav for hir::VarDef {
             focus_range: None,
             name: self.name(db.upcast()).raw(),
             kind: SymbolKind::Variable,
+            app_name: db.file_app_name(self.file.file_id),
+            app_type: db.file_app_type(self.file.file_id),
         }
     }
 }
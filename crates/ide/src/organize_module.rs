@@ -0,0 +1,367 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use elp_ide_assists::AssistConfig;
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::elp_base_db::SourceDatabase;
+use elp_ide_db::source_change::SourceChange;
+use elp_ide_db::RootDatabase;
+use elp_syntax::AstNode;
+use elp_syntax::NodeOrToken;
+use elp_syntax::SyntaxKind;
+use elp_syntax::SyntaxNode;
+use elp_syntax::TextRange;
+use elp_syntax::TextSize;
+use fxhash::FxHashMap;
+use fxhash::FxHashSet;
+use hir::db::MinDefDatabase;
+use hir::FaEntryId;
+use hir::FormIdx;
+use hir::FormList;
+use hir::NameArity;
+use text_edit::TextEdit;
+
+// Feature: Organize Imports
+//
+// Sorts the entries of the `-export`, `-export_type` and `-import`
+// attributes of a module, and removes the duplicated ones and the
+// exports of functions which are not defined. Exported functions and
+// types follow the order of their definitions, or the alphabetical
+// one if `elp.assists.sortExports.alphabetical` is set, the arities of
+// a name being kept together. An attribute left empty is removed.
+//
+// Attributes with comments or macros, or in conditional sections, and
+// files with syntax errors are left alone.
+//
+// |===
+// | Editor  | Action Name
+//
+// | VS Code | **Organize Imports**
+// |===
+pub(crate) fn organize_module(
+    db: &RootDatabase,
+    file_id: FileId,
+    config: &AssistConfig,
+) -> Option<SourceChange> {
+    let parse = db.parse(file_id);
+    if !parse.errors().is_empty() {
+        return None;
+    }
+    let source = parse.tree();
+    let form_list = db.file_form_list(file_id);
+
+    let mut functions: FxHashMap<NameArity, usize> = FxHashMap::default();
+    let mut types: FxHashMap<NameArity, usize> = FxHashMap::default();
+    for (idx, form) in form_list.forms().iter().enumerate() {
+        match form {
+            FormIdx::Function(id) => {
+                functions.entry(form_list[*id].name.clone()).or_insert(idx);
+            }
+            FormIdx::TypeAlias(id) => {
+                types.entry(form_list[*id].name().clone()).or_insert(idx);
+            }
+            _ => {}
+        }
+    }
+    let order = |defs: &FxHashMap<NameArity, usize>, entries: &mut Vec<NameArity>| {
+        if config.exports_alphabetical {
+            sort_alphabetically(entries);
+        } else {
+            // The first definition of any arity of a name
+            let first = |name: &NameArity| {
+                defs.iter()
+                    .filter(|(def, _)| def.name() == name.name())
+                    .map(|(_, idx)| *idx)
+                    .min()
+                    .unwrap_or(usize::MAX)
+            };
+            entries.sort_by_cached_key(|entry| (first(entry), entry.arity()));
+        }
+    };
+
+    let mut edit = TextEdit::builder();
+    let mut exported = FxHashSet::default();
+    let mut exported_types = FxHashSet::default();
+    let mut imported = FxHashSet::default();
+    for form in form_list.forms() {
+        let (syntax, original, entries, header) = match form {
+            FormIdx::Export(id) => {
+                let export = &form_list[*id];
+                if export.cond.is_some() {
+                    continue;
+                }
+                let syntax = export.form_id.get(&source).syntax().clone();
+                let original = names(&form_list, export.entries.clone());
+                let mut entries: Vec<NameArity> = original
+                    .iter()
+                    .filter(|name| functions.contains_key(name))
+                    .filter(|name| exported.insert((*name).clone()))
+                    .cloned()
+                    .collect();
+                order(&functions, &mut entries);
+                (syntax, original, entries, "-export(".to_string())
+            }
+            FormIdx::TypeExport(id) => {
+                let export = &form_list[*id];
+                if export.cond.is_some() {
+                    continue;
+                }
+                let syntax = export.form_id.get(&source).syntax().clone();
+                let original = names(&form_list, export.entries.clone());
+                let mut entries: Vec<NameArity> = original
+                    .iter()
+                    .filter(|name| exported_types.insert((*name).clone()))
+                    .cloned()
+                    .collect();
+                order(&types, &mut entries);
+                (syntax, original, entries, "-export_type(".to_string())
+            }
+            FormIdx::Import(id) => {
+                let import = &form_list[*id];
+                if import.cond.is_some() {
+                    continue;
+                }
+                let syntax = import.form_id.get(&source).syntax().clone();
+                let original = names(&form_list, import.entries.clone());
+                let mut entries: Vec<NameArity> = original
+                    .iter()
+                    .filter(|name| imported.insert((*name).clone()))
+                    .cloned()
+                    .collect();
+                sort_alphabetically(&mut entries);
+                let header = format!("-import({}, ", import.from.to_quoted_string());
+                (syntax, original, entries, header)
+            }
+            _ => continue,
+        };
+        if has_comment_or_macro(&syntax) || original == entries {
+            continue;
+        }
+        if entries.is_empty() {
+            edit.delete(with_trailing_newline(&syntax));
+        } else {
+            let multi_line = syntax.text().contains_char('\n');
+            edit.replace(syntax.text_range(), render(&header, &entries, multi_line));
+        }
+    }
+    let edit = edit.finish();
+    if edit.is_empty() {
+        return None;
+    }
+    Some(SourceChange::from_text_edit(file_id, edit))
+}
+
+fn sort_alphabetically(entries: &mut [NameArity]) {
+    entries.sort_by(|a, b| {
+        let name = |it: &NameArity| it.name().as_str().trim_matches('\'').to_string();
+        name(a).cmp(&name(b)).then(a.arity().cmp(&b.arity()))
+    });
+}
+
+fn names(form_list: &FormList, entries: impl Iterator<Item = FaEntryId>) -> Vec<NameArity> {
+    entries.map(|fa| form_list[fa].name.clone()).collect()
+}
+
+fn has_comment_or_macro(syntax: &SyntaxNode) -> bool {
+    syntax.descendants_with_tokens().any(|element| {
+        matches!(
+            element.kind(),
+            SyntaxKind::COMMENT | SyntaxKind::MACRO_CALL_EXPR
+        )
+    })
+}
+
+fn with_trailing_newline(syntax: &SyntaxNode) -> TextRange {
+    let range = syntax.text_range();
+    match syntax.next_sibling_or_token() {
+        Some(NodeOrToken::Token(token))
+            if token.kind() == SyntaxKind::WHITESPACE && token.text().starts_with('\n') =>
+        {
+            TextRange::new(
+                range.start(),
+                token.text_range().start() + TextSize::from(1),
+            )
+        }
+        _ => range,
+    }
+}
+
+fn render_entry(entry: &NameArity) -> String {
+    format!("{}/{}", entry.name().to_quoted_string(), entry.arity())
+}
+
+fn render(header: &str, entries: &[NameArity], multi_line: bool) -> String {
+    let single_line = format!(
+        "{header}[{}]).",
+        entries
+            .iter()
+            .map(render_entry)
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    if !multi_line && single_line.len() <= 80 {
+        return single_line;
+    }
+    let lines = entries
+        .iter()
+        .map(|entry| format!("    {}", render_entry(entry)))
+        .collect::<Vec<_>>()
+        .join(",\n");
+    format!("{header}[\n{lines}\n]).")
+}
+
+#[cfg(test)]
+mod tests {
+    use elp_ide_assists::AssistConfig;
+    use elp_ide_db::helpers::SnippetCap;
+    use expect_test::expect;
+    use expect_test::Expect;
+
+    use crate::fixture;
+
+    const CONFIG: AssistConfig = AssistConfig {
+        snippet_cap: SnippetCap::new(true),
+        allowed: None,
+        experimental: false,
+        record_fields_alphabetical: false,
+        exports_alphabetical: false,
+    };
+
+    #[track_caller]
+    fn check_with_config(config: &AssistConfig, fixture: &str, expect: Expect) {
+        let (analysis, file_id) = fixture::single_file(fixture);
+        let mut actual = analysis.file_text(file_id).unwrap().to_string();
+        if let Some(change) = analysis.organize_module(file_id, config).unwrap() {
+            change.source_file_edits[&file_id].apply(&mut actual);
+        }
+        expect.assert_eq(&actual);
+    }
+
+    #[track_caller]
+    fn check(fixture: &str, expect: Expect) {
+        check_with_config(&CONFIG, fixture, expect)
+    }
+
+    #[test]
+    fn sorts_and_deduplicates_exports() {
+        check(
+            r#"
+-module(main).
+-export([bar/0, foo/2, foo/1]).
+-export([baz/0, bar/0]).
+foo(A) -> A.
+bar() -> ok.
+foo(A, B) -> {A, B}.
+baz() -> ok.
+"#,
+            expect![[r#"
+                -module(main).
+                -export([foo/1, foo/2, bar/0]).
+                -export([baz/0]).
+                foo(A) -> A.
+                bar() -> ok.
+                foo(A, B) -> {A, B}.
+                baz() -> ok.
+            "#]],
+        );
+    }
+
+    #[test]
+    fn removes_exports_of_deleted_functions() {
+        check(
+            r#"
+-module(main).
+-export([foo/0, deleted/1]).
+-export([gone/0]).
+-export([
+    foo/0,
+    bar/0,
+    removed/2
+]).
+foo() -> ok.
+bar() -> ok.
+"#,
+            expect![[r#"
+                -module(main).
+                -export([foo/0]).
+                -export([
+                    bar/0
+                ]).
+                foo() -> ok.
+                bar() -> ok.
+            "#]],
+        );
+    }
+
+    #[test]
+    fn sorts_export_types_and_imports() {
+        check(
+            r#"
+-module(main).
+-export_type([t/0, s/0, t/0]).
+-import(lists, [reverse/1, map/2, append/1, map/2]).
+-type s() :: ok.
+-type t() :: ok.
+"#,
+            expect![[r#"
+                -module(main).
+                -export_type([s/0, t/0]).
+                -import(lists, [append/1, map/2, reverse/1]).
+                -type s() :: ok.
+                -type t() :: ok.
+            "#]],
+        );
+    }
+
+    #[test]
+    fn sorts_alphabetically() {
+        check_with_config(
+            &AssistConfig {
+                exports_alphabetical: true,
+                ..CONFIG
+            },
+            r#"
+-module(main).
+-export([foo/1, bar/0]).
+foo(A) -> A.
+bar() -> ok.
+"#,
+            expect![[r#"
+                -module(main).
+                -export([bar/0, foo/1]).
+                foo(A) -> A.
+                bar() -> ok.
+            "#]],
+        );
+    }
+
+    #[test]
+    fn leaves_attributes_with_comments() {
+        check(
+            r#"
+-module(main).
+-export([
+    bar/0, % the API
+    foo/0
+]).
+foo() -> ok.
+bar() -> ok.
+"#,
+            expect![[r#"
+                -module(main).
+                -export([
+                    bar/0, % the API
+                    foo/0
+                ]).
+                foo() -> ok.
+                bar() -> ok.
+            "#]],
+        );
+    }
+}
@@ -9,6 +9,7 @@
 
 //! Renaming functionality.
 
+use elp_ide_db::elp_base_db::FileId;
 use elp_ide_db::elp_base_db::FilePosition;
 use elp_ide_db::rename::format_err;
 use elp_ide_db::rename::rename_error;
@@ -27,6 +28,8 @@ use elp_syntax::SyntaxNode;
 use hir::InFile;
 use hir::Semantic;
 
+use crate::RangeInfo;
+
 // Feature: Rename
 //
 // Renames the item below the cursor and all of its references
@@ -60,6 +63,56 @@ pub(crate) fn rename(
         .ok_or_else(|| format_err!("No references found at position"))
 }
 
+/// The edits needed to rename the module defined in `file_id` when its
+/// file is renamed by the client, e.g. on `workspace/willRenameFiles`.
+/// The client moves the file itself, so no file move is included.
+pub(crate) fn will_rename_file(
+    db: &RootDatabase,
+    file_id: FileId,
+    new_name: &str,
+) -> RenameResult<Option<SourceChange>> {
+    let sema = Semantic::new(db);
+    match sema.module_name(file_id) {
+        Some(module_name) if module_name.as_str() != new_name => {}
+        _ => return Ok(None),
+    }
+    let def = SymbolDefinition::Module(hir::Module {
+        file: hir::File { file_id },
+    });
+    let mut change = def.rename(&sema, &|_| new_name.to_string(), SafetyChecks::Yes)?;
+    change.file_system_edits.clear();
+    Ok(Some(change))
+}
+
+/// Check that the symbol at the position can be renamed, returning the
+/// range of its name and the name itself, for the editor to show in
+/// the rename box.
+pub(crate) fn prepare_rename(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> RenameResult<RangeInfo<String>> {
+    let sema = Semantic::new(db);
+    let source_file = sema.parse(position.file_id);
+    let syntax = source_file.value.syntax();
+    let name = match algo::find_node_at_offset::<ast::Name>(syntax, position.offset) {
+        Some(name) => name,
+        None => rename_error!("No references found at position"),
+    };
+    if let ast::Name::Var(var) = &name {
+        if var.text() == "_" {
+            rename_error!("Cannot rename '_'");
+        }
+    }
+
+    let defs = find_definitions(&sema, syntax, position)?;
+    for def in &defs {
+        def.check_can_rename()?;
+    }
+
+    let range = name.syntax().text_range();
+    Ok(RangeInfo::new(range, name.syntax().text().to_string()))
+}
+
 fn find_definitions(
     sema: &Semantic,
     syntax: &SyntaxNode,
@@ -84,31 +137,11 @@ fn find_definitions(
                                 .collect::<Vec<_>>())),
                         }
                     } else {
-                        None
-                    }
-                }
-                ast::Name::Atom(atom) => {
-                    if let Some(token) = atom.syntax().first_token() {
-                        let location = InFile {
-                            file_id: position.file_id,
-                            value: token.clone(),
-                        };
-                        match SymbolClass::classify(sema, location) {
-                            Some(SymbolClass::Definition(def)) => Some(Ok(vec![def])),
-                            Some(SymbolClass::Reference { refs, typ: _ }) => match refs {
-                                ReferenceClass::Definition(def) => Some(Ok(vec![def])),
-                                ReferenceClass::MultiVar(defs) => Some(Ok(defs
-                                    .into_iter()
-                                    .map(|def| SymbolDefinition::Var(def))
-                                    .collect::<Vec<_>>())),
-                                ReferenceClass::MultiMacro(_) => None,
-                            },
-                            None => None,
-                        }
-                    } else {
-                        None
+                        // Macro names are variables too
+                        classify_name(sema, position.file_id, var.syntax())
                     }
                 }
+                ast::Name::Atom(atom) => classify_name(sema, position.file_id, atom.syntax()),
                 ast::Name::MacroCallExpr(_) => None,
             };
             res
@@ -123,10 +156,35 @@ fn find_definitions(
     }
 }
 
+fn classify_name(
+    sema: &Semantic,
+    file_id: FileId,
+    name: &SyntaxNode,
+) -> Option<RenameResult<Vec<SymbolDefinition>>> {
+    let token = name.first_token()?;
+    match SymbolClass::classify(sema, InFile::new(file_id, token))? {
+        SymbolClass::Definition(def) => Some(Ok(vec![def])),
+        SymbolClass::Reference { refs, typ: _ } => match refs {
+            ReferenceClass::Definition(def) => Some(Ok(vec![def])),
+            ReferenceClass::MultiVar(defs) => Some(Ok(defs
+                .into_iter()
+                .map(|def| SymbolDefinition::Var(def))
+                .collect::<Vec<_>>())),
+            ReferenceClass::MultiMacro(defs) => Some(Ok(defs
+                .into_iter()
+                .map(|def| SymbolDefinition::Define(def))
+                .collect::<Vec<_>>())),
+        },
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use elp_ide_db::elp_base_db::assert_eq_text;
     use elp_ide_db::elp_base_db::test_fixture::trim_indent;
+    use elp_ide_db::source_change::FileSystemEdit;
+    use expect_test::expect;
+    use expect_test::Expect;
     use text_edit::TextEdit;
 
     use crate::fixture;
@@ -1022,4 +1080,332 @@ mod tests {
              "#,
         );
     }
+
+    #[track_caller]
+    fn check_prepare(fixture: &str, expect: Expect) {
+        let (analysis, position) = fixture::position(fixture);
+        let actual = match analysis.prepare_rename(position).unwrap() {
+            Ok(info) => format!("{:?}: {}", info.range, info.info),
+            Err(err) => format!("error: {err}"),
+        };
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn prepare_rename_var() {
+        check_prepare(r#"main() -> I~ = 1, I."#, expect!["10..11: I"]);
+    }
+
+    #[test]
+    fn prepare_rename_function_reference() {
+        check_prepare(r#"foo() -> ok. bar() -> f~oo()."#, expect!["22..25: foo"]);
+    }
+
+    #[test]
+    fn prepare_rename_record() {
+        check_prepare(
+            r#"-record(rec, {a}). foo() -> #r~ec{}."#,
+            expect!["29..32: rec"],
+        );
+    }
+
+    #[test]
+    fn prepare_rename_record_field() {
+        check_prepare(
+            r#"-record(rec, {a}). foo(R) -> R#rec.~a."#,
+            expect!["35..36: a"],
+        );
+    }
+
+    #[test]
+    fn prepare_rename_anonymous_var() {
+        check_prepare(r#"foo(~_) -> ok."#, expect!["error: Cannot rename '_'"]);
+    }
+
+    #[test]
+    fn prepare_rename_no_symbol() {
+        check_prepare(
+            r#"foo() -~> ok."#,
+            expect!["error: No references found at position"],
+        );
+    }
+
+    #[test]
+    fn prepare_rename_module() {
+        check_prepare(r#"-module(ma~in)."#, expect!["8..12: main"]);
+    }
+
+    #[test]
+    fn test_rename_module_from_attribute() {
+        check(
+            "new_main",
+            r#"
+               //- /src/main.erl
+               -module(ma~in).
+               -export([foo/0]).
+               foo() -> ok.
+               //- /src/other.erl
+               -module(other).
+               -behaviour(main).
+               -import(main, [foo/0]).
+               bar() -> main:foo(), fun main:foo/0, main."#,
+            r#"
+               //- /src/main.erl
+               -module(new_main).
+               -export([foo/0]).
+               foo() -> ok.
+               //- /src/other.erl
+               -module(other).
+               -behaviour(new_main).
+               -import(new_main, [foo/0]).
+               bar() -> new_main:foo(), fun new_main:foo/0, main."#,
+        );
+    }
+
+    #[test]
+    fn test_rename_module_from_remote_call() {
+        check(
+            "new_main",
+            r#"
+               //- /src/main.erl
+               -module(main).
+               -export([foo/0]).
+               foo() -> ok.
+               //- /src/other.erl
+               -module(other).
+               bar() -> ma~in:foo()."#,
+            r#"
+               //- /src/main.erl
+               -module(new_main).
+               -export([foo/0]).
+               foo() -> ok.
+               //- /src/other.erl
+               -module(other).
+               bar() -> new_main:foo()."#,
+        );
+    }
+
+    #[test]
+    fn test_rename_module_moves_file() {
+        let (analysis, position) = fixture::position(
+            r#"
+               //- /src/main.erl
+               -module(ma~in)."#,
+        );
+        let source_change = analysis.rename(position, "new_main").unwrap().unwrap();
+        match &source_change.file_system_edits[..] {
+            [FileSystemEdit::MoveFile { src, dst }] => {
+                assert_eq!(*src, position.file_id);
+                assert_eq!(dst.anchor, position.file_id);
+                assert_eq!(dst.path, "new_main.erl");
+            }
+            edits => panic!("expected a single file move, got {:?}", edits),
+        }
+    }
+
+    #[test]
+    fn test_rename_module_invalid_name() {
+        check(
+            "'New main'",
+            r#"
+               //- /src/main.erl
+               -module(ma~in)."#,
+            r#"error: Invalid new module name: ''New main''"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_module_already_exists() {
+        check(
+            "other",
+            r#"
+               //- /src/main.erl
+               -module(ma~in).
+               //- /src/other.erl
+               -module(other)."#,
+            r#"error: Module 'other' already exists"#,
+        );
+    }
+
+    #[test]
+    fn test_will_rename_file() {
+        let (analysis, position) = fixture::position(
+            r#"
+               //- /src/main.erl
+               -module(ma~in).
+               //- /src/other.erl
+               -module(other).
+               bar() -> main:foo()."#,
+        );
+        let source_change = analysis
+            .will_rename_file(position.file_id, "new_main")
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert!(source_change.file_system_edits.is_empty());
+        assert_eq!(source_change.source_file_edits.len(), 2);
+
+        let unchanged = analysis
+            .will_rename_file(position.file_id, "main")
+            .unwrap()
+            .unwrap();
+        assert!(unchanged.is_none());
+    }
+
+    #[test]
+    fn test_rename_record_in_header() {
+        check(
+            "new_rec",
+            r#"
+               //- /src/records.hrl
+               -record(rec, {a, b}).
+               //- /src/all.hrl
+               -include("records.hrl").
+               //- /src/main.erl
+               -module(main).
+               -include("records.hrl").
+               foo() -> #r~ec{a = 1}.
+               //- /src/other.erl
+               -module(other).
+               -include("all.hrl").
+               bar(R) -> R#rec.a, #rec{}, {rec, 1}."#,
+            r#"
+               //- /src/records.hrl
+               -record(new_rec, {a, b}).
+               //- /src/all.hrl
+               -include("records.hrl").
+               //- /src/main.erl
+               -module(main).
+               -include("records.hrl").
+               foo() -> #new_rec{a = 1}.
+               //- /src/other.erl
+               -module(other).
+               -include("all.hrl").
+               bar(R) -> R#new_rec.a, #new_rec{}, {rec, 1}."#,
+        );
+    }
+
+    #[test]
+    fn test_rename_record_field_in_header() {
+        check(
+            "c",
+            r#"
+               //- /src/records.hrl
+               -record(rec, {a~ = 1 :: integer(), b}).
+               //- /src/main.erl
+               -module(main).
+               -include("records.hrl").
+               foo(#rec{a = A}) -> #rec{a = A, b = 2}.
+               //- /src/other.erl
+               -module(other).
+               -include("records.hrl").
+               -record(other, {a}).
+               bar(R) -> R#rec.a, #other{a = 1}."#,
+            r#"
+               //- /src/records.hrl
+               -record(rec, {c = 1 :: integer(), b}).
+               //- /src/main.erl
+               -module(main).
+               -include("records.hrl").
+               foo(#rec{c = A}) -> #rec{c = A, b = 2}.
+               //- /src/other.erl
+               -module(other).
+               -include("records.hrl").
+               -record(other, {a}).
+               bar(R) -> R#rec.c, #other{a = 1}."#,
+        );
+    }
+
+    #[test]
+    fn test_rename_record_already_in_scope() {
+        check(
+            "other",
+            r#"
+               //- /src/records.hrl
+               -record(r~ec, {a}).
+               //- /src/main.erl
+               -module(main).
+               -include("records.hrl").
+               -record(other, {b})."#,
+            r#"error: Record 'other' already in scope in module 'main'"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_record_invalid_name() {
+        check(
+            "Rec",
+            r#"-record(r~ec, {a})."#,
+            r#"error: Invalid new record name: 'Rec'"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_record_field_already_exists() {
+        check(
+            "b",
+            r#"-record(rec, {a~, b})."#,
+            r#"error: Field 'b' already exists in record 'rec'"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_macro_in_header() {
+        check(
+            "BAR",
+            r#"
+               //- /src/macros.hrl
+               -define(FOO(X), {foo, X}).
+               //- /src/wrappers.hrl
+               -include("macros.hrl").
+               wrap(X) -> ?FOO(X).
+               //- /src/main.erl
+               -module(main).
+               -include("wrappers.hrl").
+               foo() -> ?F~OO(1).
+               //- /src/other.erl
+               -module(other).
+               -define(FOO(X), X).
+               bar() -> ?FOO(1)."#,
+            r#"
+               //- /src/macros.hrl
+               -define(BAR(X), {foo, X}).
+               //- /src/wrappers.hrl
+               -include("macros.hrl").
+               wrap(X) -> ?BAR(X).
+               //- /src/main.erl
+               -module(main).
+               -include("wrappers.hrl").
+               foo() -> ?BAR(1).
+               //- /src/other.erl
+               -module(other).
+               -define(FOO(X), X).
+               bar() -> ?FOO(1)."#,
+        );
+    }
+
+    #[test]
+    fn test_rename_macro_already_in_scope() {
+        check(
+            "BAR",
+            r#"
+               //- /src/macros.hrl
+               -define(FOO, 1).
+               //- /src/main.erl
+               -module(main).
+               -include("macros.hrl").
+               -define(BAR, 2).
+               foo() -> ?F~OO."#,
+            r#"error: Macro 'BAR' already in scope"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_macro_invalid_name() {
+        check(
+            "bar baz",
+            r#"-define(F~OO, 1)."#,
+            r#"error: Invalid new macro name: 'bar baz'"#,
+        );
+    }
 }
@@ -0,0 +1,339 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::elp_base_db::ProjectId;
+use elp_ide_db::elp_base_db::SourceDatabase;
+use elp_ide_db::RootDatabase;
+use elp_ide_db::SymbolDefinition;
+use elp_ide_db::SymbolIndexDatabase;
+use elp_project_model::AppName;
+use elp_project_model::AppType;
+use elp_syntax::SmolStr;
+use hir::File;
+use hir::Module;
+
+use crate::navigation_target::ToNav;
+use crate::NavigationTarget;
+
+/// One page of workspace symbol search results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolSearchResult {
+    pub symbols: Vec<NavigationTarget>,
+    /// Offset of the next page, if there are more results than fit in
+    /// this one.
+    pub next_offset: Option<usize>,
+}
+
+/// Search the modules of a project, and the functions, types, records
+/// and macros defined in its files.
+///
+/// Symbols are matched by name, as `my_fun/2`, `my_type/1`,
+/// `#my_record{}` or `?MY_MACRO`, so any part of these can be used as a
+/// query. A query of the form `my_app/my_fun` only matches the symbols
+/// of the `my_app` application.
+///
+/// Matches of the project's own applications come first, followed by
+/// those of dependencies and then OTP. Within each group they are
+/// ordered by name, so the `offset` returned in `next_offset` is stable
+/// as long as the set of symbols does not change between requests.
+pub(crate) fn symbol_search(
+    db: &RootDatabase,
+    project_id: ProjectId,
+    query: &str,
+    offset: usize,
+    limit: usize,
+) -> SymbolSearchResult {
+    let module_index = db.module_index(project_id);
+    let symbol_index = db.symbol_index(project_id);
+    let (app, query) = match query.split_once('/') {
+        Some((app, rest))
+            if module_index.all_modules().iter().any(|name| {
+                module_index
+                    .file_for_module(name)
+                    .and_then(|file_id| db.file_app_name(file_id))
+                    == Some(AppName(app.to_string()))
+            }) =>
+        {
+            (Some(AppName(app.to_string())), rest)
+        }
+        _ => (None, query),
+    };
+    let mut matches: Vec<(SmolStr, SymbolDefinition)> = module_index
+        .all_modules()
+        .into_iter()
+        .filter(|name| name.as_str().contains(query))
+        .filter_map(|name| {
+            let file_id = module_index.file_for_module(&name)?;
+            let module = Module {
+                file: File { file_id },
+            };
+            Some((
+                SmolStr::new(name.as_str()),
+                SymbolDefinition::Module(module),
+            ))
+        })
+        .collect();
+    matches.extend(
+        symbol_index
+            .search(query)
+            .map(|symbol| (symbol.name.clone(), symbol.def.clone())),
+    );
+    if let Some(app) = &app {
+        matches.retain(|(_, def)| db.file_app_name(def.file().file_id).as_ref() == Some(app));
+    }
+    matches.sort_by_cached_key(|(name, def)| (app_rank(db, def.file().file_id), name.clone()));
+    let symbols = matches
+        .iter()
+        .skip(offset)
+        .take(limit)
+        .map(|(name, def)| {
+            let mut nav = def.to_nav(db);
+            nav.name = name.clone();
+            nav
+        })
+        .collect();
+    let end = offset.saturating_add(limit);
+    let next_offset = if end < matches.len() { Some(end) } else { None };
+    SymbolSearchResult {
+        symbols,
+        next_offset,
+    }
+}
+
+/// Files not belonging to any application are considered user code.
+fn app_rank(db: &RootDatabase, file_id: FileId) -> AppType {
+    db.file_app_type(file_id).unwrap_or(AppType::App)
+}
+
+#[cfg(test)]
+mod tests {
+    use elp_ide_db::elp_base_db::ProjectId;
+    use expect_test::expect;
+    use expect_test::Expect;
+
+    use crate::fixture;
+
+    fn check(fixture: &str, query: &str, offset: usize, limit: usize, expect: Expect) {
+        let analysis = fixture::multi_file(fixture);
+        let res = analysis
+            .symbol_search(ProjectId(0), query, offset, limit)
+            .unwrap();
+        let names: Vec<_> = res.symbols.iter().map(|nav| nav.name.to_string()).collect();
+        expect.assert_debug_eq(&(names, res.next_offset));
+    }
+
+    const FIXTURE: &str = r#"
+//- /src/foo_a.erl
+-module(foo_a).
+//- /src/bar.erl
+-module(bar).
+//- /src/foo_c.erl
+-module(foo_c).
+//- /src/foo_b.erl
+-module(foo_b).
+"#;
+
+    #[test]
+    fn all_results_fit() {
+        check(
+            FIXTURE,
+            "foo",
+            0,
+            10,
+            expect![[r#"
+                (
+                    [
+                        "foo_a",
+                        "foo_b",
+                        "foo_c",
+                    ],
+                    None,
+                )
+            "#]],
+        );
+    }
+
+    #[test]
+    fn paginated() {
+        check(
+            FIXTURE,
+            "foo",
+            0,
+            2,
+            expect![[r#"
+                (
+                    [
+                        "foo_a",
+                        "foo_b",
+                    ],
+                    Some(
+                        2,
+                    ),
+                )
+            "#]],
+        );
+        check(
+            FIXTURE,
+            "foo",
+            2,
+            2,
+            expect![[r#"
+                (
+                    [
+                        "foo_c",
+                    ],
+                    None,
+                )
+            "#]],
+        );
+    }
+
+    #[test]
+    fn functions_types_records_and_macros() {
+        let fixture = r#"
+//- /src/main.erl
+-module(main).
+-include("main.hrl").
+-type my_type(A) :: A.
+my_fun() -> ok.
+my_fun(A, B) -> {A, B}.
+//- /src/main.hrl
+-record(my_record, {a}).
+-define(MY_MACRO, 1).
+-define(MY_MACRO(X), X).
+"#;
+        check(
+            fixture,
+            "my_",
+            0,
+            10,
+            expect![[r#"
+                (
+                    [
+                        "#my_record{}",
+                        "my_fun/0",
+                        "my_fun/2",
+                        "my_type/1",
+                    ],
+                    None,
+                )
+            "#]],
+        );
+        check(
+            fixture,
+            "my_fun/2",
+            0,
+            10,
+            expect![[r#"
+                (
+                    [
+                        "my_fun/2",
+                    ],
+                    None,
+                )
+            "#]],
+        );
+        check(
+            fixture,
+            "#my_record{}",
+            0,
+            10,
+            expect![[r#"
+                (
+                    [
+                        "#my_record{}",
+                    ],
+                    None,
+                )
+            "#]],
+        );
+        check(
+            fixture,
+            "?MY_MACRO",
+            0,
+            10,
+            expect![[r#"
+                (
+                    [
+                        "?MY_MACRO",
+                        "?MY_MACRO/1",
+                    ],
+                    None,
+                )
+            "#]],
+        );
+    }
+
+    #[test]
+    fn user_code_first() {
+        check(
+            r#"
+//- /opt/lib/stdlib-3.17/src/foo_lists.erl otp_app:/opt/lib/stdlib-3.17
+-module(foo_lists).
+//- /src/foo_zzz.erl
+-module(foo_zzz).
+"#,
+            "foo",
+            0,
+            10,
+            expect![[r#"
+                (
+                    [
+                        "foo_zzz",
+                        "foo_lists",
+                    ],
+                    None,
+                )
+            "#]],
+        );
+    }
+
+    #[test]
+    fn app_qualified_query() {
+        let fixture = r#"
+//- /app_a/src/foo_a.erl app:app_a
+-module(foo_a).
+foo() -> ok.
+//- /app_b/src/foo_b.erl app:app_b
+-module(foo_b).
+foo() -> ok.
+"#;
+        check(
+            fixture,
+            "app_a/foo",
+            0,
+            10,
+            expect![[r#"
+                (
+                    [
+                        "foo/0",
+                        "foo_a",
+                    ],
+                    None,
+                )
+            "#]],
+        );
+        check(
+            fixture,
+            "foo/0",
+            0,
+            10,
+            expect![[r#"
+                (
+                    [
+                        "foo/0",
+                        "foo/0",
+                    ],
+                    None,
+                )
+            "#]],
+        );
+    }
+}
@@ -11,6 +11,7 @@ mod highlights;
 pub(crate) mod tags;
 
 use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::elp_base_db::SourceDatabase;
 use elp_ide_db::RootDatabase;
 use elp_ide_db::SymbolKind;
 use elp_syntax::ast;
@@ -28,10 +29,11 @@ use hir::Semantic;
 
 use self::highlights::Highlights;
 use self::tags::Highlight;
+use self::tags::HlMods;
 use crate::HlMod;
 use crate::HlTag;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct HlRange {
     pub range: TextRange,
     pub highlight: Highlight,
@@ -45,7 +47,9 @@ pub struct HighlightConfig {}
 //
 // ELP highlights some code semantically.
 //
-// Initially this is just used for bound variables in patterns
+// It is used for bound variables in patterns, exported and deprecated
+// functions, and the code of test suites, test helpers and generated
+// files.
 
 pub(crate) fn highlight(
     db: &RootDatabase,
@@ -75,7 +79,85 @@ pub(crate) fn highlight(
     bound_vars_in_pattern_highlight(&sema, file_id, range_to_highlight, &mut hl);
     functions_highlight(&sema, file_id, range_to_highlight, &mut hl);
     deprecated_func_highlight(&sema, file_id, range_to_highlight, &mut hl);
-    hl.to_vec()
+    let mut highlights = hl.to_vec();
+
+    // The ranges between the highlighted ones are included, so the
+    // modifiers of the file cover all its code
+    let file_mods = file_mods(db, file_id);
+    if !file_mods.is_empty() {
+        for hl_range in &mut highlights {
+            hl_range.highlight.mods |= file_mods;
+        }
+    }
+    highlights
+}
+
+/// The modifiers applying to the whole file.
+fn file_mods(db: &RootDatabase, file_id: FileId) -> HlMods {
+    let mut mods = HlMods::default();
+    if db.is_test_suite_or_test_helper(file_id) == Some(true) {
+        mods |= HlMod::TestCode;
+    }
+    if db.is_generated(file_id) {
+        mods |= HlMod::Generated;
+    }
+    mods
+}
+
+/// Replacing `delete_count` highlights starting at index `start` in
+/// a previous result by `insert` gives the current result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighlightEdit {
+    pub start: usize,
+    pub delete_count: usize,
+    pub insert: Vec<HlRange>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HighlightDelta {
+    pub highlights: Vec<HlRange>,
+    /// `None` if the highlights did not change
+    pub edit: Option<HighlightEdit>,
+}
+
+/// Highlights the whole file, and computes the edit from the `previous`
+/// full file highlights, so callers can send just the changes, as in
+/// the LSP `semanticTokens/full/delta` request.
+pub(crate) fn highlight_delta(
+    db: &RootDatabase,
+    file_id: FileId,
+    previous: &[HlRange],
+) -> HighlightDelta {
+    let highlights = highlight(db, file_id, None);
+    let edit = diff_highlights(previous, &highlights);
+    HighlightDelta { highlights, edit }
+}
+
+fn diff_highlights(old: &[HlRange], new: &[HlRange]) -> Option<HighlightEdit> {
+    let prefix = new
+        .iter()
+        .zip(old.iter())
+        .take_while(|(n, o)| n == o)
+        .count();
+    let (old, new) = (&old[prefix..], &new[prefix..]);
+
+    let suffix = new
+        .iter()
+        .rev()
+        .zip(old.iter().rev())
+        .take_while(|(n, o)| n == o)
+        .count();
+    let (old, new) = (&old[..old.len() - suffix], &new[..new.len() - suffix]);
+
+    if old.is_empty() && new.is_empty() {
+        None
+    } else {
+        Some(HighlightEdit {
+            start: prefix,
+            delete_count: old.len(),
+            insert: new.to_vec(),
+        })
+    }
 }
 
 fn bound_vars_in_pattern_highlight(
@@ -107,7 +189,8 @@ fn deprecated_func_highlight(
     hl: &mut Highlights,
 ) {
     let def_map = sema.def_map(file_id);
-    let highlight = HlTag::Symbol(SymbolKind::Function) | HlMod::DeprecatedFunction;
+    let highlight =
+        HlTag::Symbol(SymbolKind::Function) | HlMod::DeprecatedFunction | HlMod::Deprecated;
     for (_name, def) in def_map.get_functions() {
         if def.file.file_id == file_id {
             let function_id = InFile::new(file_id, def.function_id);
@@ -224,9 +307,12 @@ fn functions_highlight(
                                 HlTag::Symbol(SymbolKind::Function)
                                     | HlMod::ExportedFunction
                                     | HlMod::DeprecatedFunction
+                                    | HlMod::Deprecated
                             }
                             (false, true) => {
-                                HlTag::Symbol(SymbolKind::Function) | HlMod::DeprecatedFunction
+                                HlTag::Symbol(SymbolKind::Function)
+                                    | HlMod::DeprecatedFunction
+                                    | HlMod::Deprecated
                             }
                             (true, false) => {
                                 HlTag::Symbol(SymbolKind::Function) | HlMod::ExportedFunction
@@ -259,6 +345,8 @@ mod tests {
     use itertools::Itertools;
 
     use crate::syntax_highlighting::highlight;
+    use crate::syntax_highlighting::highlight_delta;
+    use crate::HlMod;
     use crate::HlTag;
 
     // These are tests of the specific modifier functionality.  When
@@ -295,6 +383,16 @@ mod tests {
         assert_eq!(expected, ranges);
     }
 
+    #[track_caller]
+    fn check_file_mod(fixture: &str, file_mod: HlMod, expected: bool) {
+        let (db, file_id) = RootDatabase::with_single_file(fixture);
+        let highlights = highlight(&db, file_id, None);
+        assert!(!highlights.is_empty());
+        for h in highlights {
+            assert_eq!(h.highlight.mods.contains(file_mod), expected, "{:?}", h);
+        }
+    }
+
     #[test]
     fn highlights_1() {
         check_highlights(
@@ -324,11 +422,11 @@ mod tests {
               -deprecated([{f, 1}, {g, 1}]).
               -export([g/1]).
               f(1) -> 1;
-           %% ^deprecated_function
+           %% ^deprecated_function,deprecated
               f(2) -> 2.
-           %% ^deprecated_function
+           %% ^deprecated_function,deprecated
               g(3) -> 3.
-           %% ^exported_function,deprecated_function"#,
+           %% ^exported_function,deprecated_function,deprecated"#,
         )
     }
 
@@ -340,12 +438,53 @@ mod tests {
               -module(deprecated_highlight).
               -deprecated([{f, 1}]).
               f(1) -> 1.
-           %% ^deprecated_function
+           %% ^deprecated_function,deprecated
               ga(Num) -> f(Num).
-           %%            ^deprecated_function"#,
+           %%            ^deprecated_function,deprecated"#,
         )
     }
 
+    #[test]
+    fn generated_highlight() {
+        check_file_mod(
+            r#"
+              %% @generated
+              -module(main).
+              f(Var1) ->
+                Var1 = 1.
+            "#,
+            HlMod::Generated,
+            true,
+        );
+    }
+
+    #[test]
+    fn not_generated_highlight() {
+        check_file_mod(
+            r#"
+              -module(main).
+              f(Var1) ->
+                Var1 = 1.
+            "#,
+            HlMod::Generated,
+            false,
+        );
+    }
+
+    #[test]
+    fn test_code_highlight() {
+        check_file_mod(
+            r#"
+              //- /my_app/test/main_SUITE.erl extra:test
+              -module(main_SUITE).
+              f(Var1) ->
+                Var1 = 1.
+            "#,
+            HlMod::TestCode,
+            true,
+        );
+    }
+
     #[test]
     fn highlights_in_range() {
         check_highlights(
@@ -362,4 +501,36 @@ mod tests {
               "#,
         )
     }
+
+    #[test]
+    fn highlight_delta_unchanged() {
+        let (db, file_id) = RootDatabase::with_single_file(
+            r#"
+              f(Var1) ->
+                Var1 = 1.
+            "#,
+        );
+        let previous = highlight(&db, file_id, None);
+        assert!(!previous.is_empty());
+        let delta = highlight_delta(&db, file_id, &previous);
+        assert_eq!(delta.highlights, previous);
+        assert_eq!(delta.edit, None);
+    }
+
+    #[test]
+    fn highlight_delta_changed() {
+        let (db, file_id) = RootDatabase::with_single_file(
+            r#"
+              f(Var1) ->
+                Var1 = 1.
+            "#,
+        );
+        let current = highlight(&db, file_id, None);
+        let last = current.len() - 1;
+        let delta = highlight_delta(&db, file_id, &current[..last]);
+        let edit = delta.edit.unwrap();
+        assert_eq!(edit.start, last);
+        assert_eq!(edit.delete_count, 0);
+        assert_eq!(edit.insert, current[last..].to_vec());
+    }
 }
@@ -47,6 +47,13 @@ pub enum HlMod {
     // Local vs exported function name.
     ExportedFunction,
     DeprecatedFunction,
+    /// Deprecated function, also given the standard LSP modifier, which
+    /// editors render struck through.
+    Deprecated,
+    /// Code of a test suite or test helper.
+    TestCode,
+    /// Code of a file with the generated marker.
+    Generated,
 }
 
 impl HlTag {
@@ -63,6 +70,7 @@ impl HlTag {
                 SymbolKind::Define => "constant",
                 SymbolKind::Variable => "variable",
                 SymbolKind::Callback => "function",
+                SymbolKind::TableKey => "key",
             },
             HlTag::None => "none",
         }
@@ -76,10 +84,13 @@ impl fmt::Display for HlTag {
 }
 
 impl HlMod {
-    const ALL: &'static [HlMod; 3] = &[
+    const ALL: &'static [HlMod; 6] = &[
         HlMod::Bound,
         HlMod::ExportedFunction,
         HlMod::DeprecatedFunction,
+        HlMod::Deprecated,
+        HlMod::TestCode,
+        HlMod::Generated,
     ];
 
     fn as_str(self) -> &'static str {
@@ -87,6 +98,9 @@ impl HlMod {
             HlMod::Bound => "bound",
             HlMod::ExportedFunction => "exported_function",
             HlMod::DeprecatedFunction => "deprecated_function",
+            HlMod::Deprecated => "deprecated",
+            HlMod::TestCode => "test_code",
+            HlMod::Generated => "generated",
         }
     }
 
@@ -143,6 +157,12 @@ impl ops::BitOrAssign<HlMod> for HlMods {
     }
 }
 
+impl ops::BitOrAssign<HlMods> for HlMods {
+    fn bitor_assign(&mut self, rhs: HlMods) {
+        self.0 |= rhs.0;
+    }
+}
+
 impl ops::BitOrAssign<HlMod> for Highlight {
     fn bitor_assign(&mut self, rhs: HlMod) {
         self.mods |= rhs;
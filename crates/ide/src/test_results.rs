@@ -0,0 +1,406 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! The results of a Common Test run, read from the `suite.log` of a
+//! suite or from the surefire XML report of `cth_surefire`, matched with
+//! the runnables of a test suite.
+
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::RootDatabase;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::runnables::runnables;
+use crate::Runnable;
+use crate::RunnableKind;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestStatus {
+    Passed,
+    Failed { reason: Option<String> },
+    Skipped { reason: Option<String> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TestResult {
+    suite: String,
+    /// The innermost group the testcase was run in
+    group: Option<String>,
+    case: String,
+    status: TestStatus,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TestResults {
+    results: Vec<TestResult>,
+}
+
+/// A runnable of a test suite, with the status of its last run, if any.
+#[derive(Debug, Clone)]
+pub struct RunnableStatus {
+    pub runnable: Runnable,
+    pub status: Option<TestStatus>,
+}
+
+lazy_static! {
+    static ref GROUP_NAME: Regex = Regex::new(r"\{name,\s*'?([^,'}]+)'?").unwrap();
+    static ref XML_ATTRIBUTE: Regex = Regex::new(r#"([\w:-]+)\s*=\s*"([^"]*)""#).unwrap();
+}
+
+impl TestResults {
+    /// Reads a surefire XML report or a `suite.log`, depending on the
+    /// content of `text`.
+    pub fn parse(text: &str) -> TestResults {
+        if text.trim_start().starts_with('<') {
+            TestResults::from_surefire(text)
+        } else {
+            TestResults::from_suite_log(text)
+        }
+    }
+
+    /// Reads the `suite.log` written by Common Test, made of entries
+    /// such as
+    ///
+    /// ```text
+    /// =case          my_SUITE:my_test
+    /// =result        failed: {error,...}
+    /// ```
+    ///
+    /// in which the group of a testcase is the innermost one of the
+    /// `init_per_group` entries not yet ended.
+    pub fn from_suite_log(text: &str) -> TestResults {
+        let mut results = Vec::new();
+        let mut groups: Vec<String> = Vec::new();
+        let mut current: Option<(String, String)> = None;
+        for line in text.lines() {
+            let (key, value) = match line.strip_prefix('=') {
+                Some(entry) => match entry.split_once(char::is_whitespace) {
+                    Some((key, value)) => (key, value.trim()),
+                    None => (entry, ""),
+                },
+                None => continue,
+            };
+            match key {
+                "case" => {
+                    current = value
+                        .split_once(':')
+                        .map(|(suite, case)| (suite.to_string(), case.to_string()));
+                    if let Some((_, case)) = &current {
+                        if case == "end_per_group" {
+                            groups.pop();
+                        }
+                    }
+                }
+                "group_props" => {
+                    if let Some((_, case)) = &current {
+                        if case == "init_per_group" {
+                            if let Some(name) = GROUP_NAME.captures(value) {
+                                groups.push(name[1].to_string());
+                            }
+                        }
+                    }
+                }
+                "result" => {
+                    if let Some((suite, case)) = current.take() {
+                        if is_configuration_function(&case) {
+                            continue;
+                        }
+                        let status = if value == "ok" || value.starts_with("ok:") {
+                            TestStatus::Passed
+                        } else if let Some(reason) = value
+                            .strip_prefix("skipped")
+                            .or_else(|| value.strip_prefix("auto_skipped"))
+                        {
+                            TestStatus::Skipped {
+                                reason: reason_of(reason),
+                            }
+                        } else {
+                            TestStatus::Failed {
+                                reason: reason_of(value.strip_prefix("failed").unwrap_or(value)),
+                            }
+                        };
+                        results.push(TestResult {
+                            suite,
+                            group: groups.last().cloned(),
+                            case,
+                            status,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        TestResults { results }
+    }
+
+    /// Reads a surefire XML report, as written by `cth_surefire`, in
+    /// which each `<testcase>` has a `classname` made of the suite and
+    /// of the groups it was run in, separated by dots.
+    pub fn from_surefire(text: &str) -> TestResults {
+        let mut results = Vec::new();
+        for (start, _) in text.match_indices("<testcase") {
+            let rest = &text[start + "<testcase".len()..];
+            let tag_end = match rest.find('>') {
+                Some(tag_end) => tag_end,
+                None => break,
+            };
+            let attributes = xml_attributes(&rest[..tag_end]);
+            let body = if rest[..tag_end].ends_with('/') {
+                ""
+            } else {
+                let body = &rest[tag_end + 1..];
+                &body[..body.find("</testcase>").unwrap_or(body.len())]
+            };
+            let case = match attributes.iter().find(|(name, _)| name == "name") {
+                Some((_, case)) if !is_configuration_function(case) => case.clone(),
+                _ => continue,
+            };
+            let classname = attributes
+                .iter()
+                .find(|(name, _)| name == "classname")
+                .map_or("", |(_, classname)| classname.as_str());
+            let segments: Vec<&str> = classname.split('.').collect();
+            let (suite, group) = match segments.iter().rposition(|it| it.ends_with("_SUITE")) {
+                Some(index) => (
+                    segments[index].to_string(),
+                    segments[index + 1..].last().map(|it| it.to_string()),
+                ),
+                None => (classname.to_string(), None),
+            };
+            let status = if let Some(reason) =
+                element_message(body, "failure").or_else(|| element_message(body, "error"))
+            {
+                TestStatus::Failed { reason }
+            } else if let Some(reason) = element_message(body, "skipped") {
+                TestStatus::Skipped { reason }
+            } else {
+                TestStatus::Passed
+            };
+            results.push(TestResult {
+                suite,
+                group,
+                case,
+                status,
+            });
+        }
+        TestResults { results }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// The status of the last run of `runnable`. A suite failed if one
+    /// of its testcases did, and passed if one at least did.
+    pub fn status(&self, runnable: &Runnable) -> Option<TestStatus> {
+        match &runnable.kind {
+            RunnableKind::Test {
+                suite, case, group, ..
+            } => {
+                let group = group.name();
+                self.results
+                    .iter()
+                    .rev()
+                    .find(|result| {
+                        &result.suite == suite
+                            && &result.case == case
+                            && result.group.as_deref().unwrap_or_default() == group
+                    })
+                    .map(|result| result.status.clone())
+            }
+            RunnableKind::Suite => {
+                let suite = runnable.nav.name.as_str();
+                let mut status = None;
+                for result in self.results.iter().filter(|result| result.suite == suite) {
+                    status = match (&result.status, status) {
+                        (TestStatus::Failed { .. }, _) | (_, Some(TestStatus::Failed { .. })) => {
+                            Some(TestStatus::Failed { reason: None })
+                        }
+                        (TestStatus::Passed, _) | (_, Some(TestStatus::Passed)) => {
+                            Some(TestStatus::Passed)
+                        }
+                        (TestStatus::Skipped { .. }, _) => {
+                            Some(TestStatus::Skipped { reason: None })
+                        }
+                    };
+                }
+                status
+            }
+        }
+    }
+}
+
+fn is_configuration_function(case: &str) -> bool {
+    case.starts_with("init_per_") || case.starts_with("end_per_")
+}
+
+/// The reason following the `:` of a `suite.log` result, if any.
+fn reason_of(text: &str) -> Option<String> {
+    let reason = text.trim_start_matches(':').trim();
+    (!reason.is_empty()).then(|| reason.to_string())
+}
+
+fn xml_attributes(tag: &str) -> Vec<(String, String)> {
+    XML_ATTRIBUTE
+        .captures_iter(tag)
+        .map(|captures| (captures[1].to_string(), unescape_xml(&captures[2])))
+        .collect()
+}
+
+/// The `message` of the element `name` of `body`, `Some(None)` if it has
+/// none, and `None` if `body` has no such element.
+fn element_message(body: &str, name: &str) -> Option<Option<String>> {
+    let start = body.find(&format!("<{name}"))?;
+    let rest = &body[start + name.len() + 1..];
+    let tag = &rest[..rest.find('>').unwrap_or(rest.len())];
+    Some(
+        xml_attributes(tag)
+            .into_iter()
+            .find(|(attribute, _)| attribute == "message")
+            .map(|(_, message)| message)
+            .filter(|message| !message.is_empty()),
+    )
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+pub(crate) fn test_statuses(
+    db: &RootDatabase,
+    file_id: FileId,
+    results: &TestResults,
+) -> Vec<RunnableStatus> {
+    runnables(db, file_id)
+        .into_iter()
+        .map(|runnable| {
+            let status = results.status(&runnable);
+            RunnableStatus { runnable, status }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use expect_test::Expect;
+    use stdx::trim_indent;
+
+    use super::TestResults;
+    use crate::fixture;
+
+    const SUITE: &str = r#"
+//- /my_app/test/my_SUITE.erl
+~
+-module(my_SUITE).
+-export([all/0, groups/0]).
+-export([a/1, b/1, c/1, d/1]).
+all() -> [a, b, {group, gc1}].
+groups() -> [{gc1, [], [c, d]}].
+a(_Config) -> ok.
+b(_Config) -> ok.
+c(_Config) -> ok.
+d(_Config) -> ok.
+"#;
+
+    #[track_caller]
+    fn check(results: &str, expect: Expect) {
+        let (analysis, pos, _) = fixture::annotations(SUITE);
+        let results = TestResults::parse(&trim_indent(results));
+        let actual: String = analysis
+            .test_statuses(pos.file_id, &results)
+            .unwrap()
+            .into_iter()
+            .map(|it| format!("{:?}: {:?}\n", it.runnable.id(), it.status))
+            .collect();
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn suite_log() {
+        check(
+            r#"
+            =cases         4
+            =user          me
+            =case          my_SUITE:init_per_suite
+            =result        ok
+            =case          my_SUITE:a
+            =logfile       my_suite.a.html
+            =result        ok
+            =elapsed       0.001
+            =case          my_SUITE:b
+            =result        failed: {my_SUITE,b,{badmatch,2}}
+            =case          my_SUITE:init_per_group
+            =group_props   [{name,gc1}]
+            =result        ok
+            =case          my_SUITE:c
+            =result        skipped: Not yet
+            =case          my_SUITE:end_per_group
+            =group_props   [{name,gc1}]
+            =result        ok
+            =case          my_SUITE:end_per_suite
+            =result        ok
+            "#,
+            expect![[r#"
+                "": Some(Failed { reason: None })
+                "my_SUITE - .a": Some(Passed)
+                "my_SUITE - .b": Some(Failed { reason: Some("{my_SUITE,b,{badmatch,2}}") })
+                "my_SUITE - gc1.c": Some(Skipped { reason: Some("Not yet") })
+                "my_SUITE - gc1.d": None
+            "#]],
+        );
+    }
+
+    #[test]
+    fn surefire() {
+        check(
+            r#"
+            <?xml version="1.0" encoding="UTF-8" ?>
+            <testsuites>
+              <testsuite name="my_app.my_SUITE" tests="4" failures="1" errors="0" skipped="1">
+                <testcase classname="my_app.my_SUITE" name="init_per_suite" time="0.001"/>
+                <testcase classname="my_app.my_SUITE" name="a" time="0.001"/>
+                <testcase classname="my_app.my_SUITE" name="b" time="0.001">
+                  <failure message="{badmatch,&lt;&lt;&quot;x&quot;&gt;&gt;}" type="error"/>
+                </testcase>
+                <testcase classname="my_app.my_SUITE.gc1" name="c" time="0.001">
+                  <skipped type="skip" message=""/>
+                </testcase>
+                <testcase classname="my_app.my_SUITE.gc1" name="d" time="0.001"></testcase>
+              </testsuite>
+            </testsuites>
+            "#,
+            expect![[r#"
+                "": Some(Failed { reason: None })
+                "my_SUITE - .a": Some(Passed)
+                "my_SUITE - .b": Some(Failed { reason: Some("{badmatch,<<\"x\">>}") })
+                "my_SUITE - gc1.c": Some(Skipped { reason: None })
+                "my_SUITE - gc1.d": Some(Passed)
+            "#]],
+        );
+    }
+
+    #[test]
+    fn no_results() {
+        check(
+            "",
+            expect![[r#"
+                "": None
+                "my_SUITE - .a": None
+                "my_SUITE - .b": None
+                "my_SUITE - gc1.c": None
+                "my_SUITE - gc1.d": None
+            "#]],
+        );
+    }
+}
@@ -10,14 +10,19 @@
 // To run the tests via cargo
 // cargo test --package elp_ide --lib
 
+use std::sync::Arc;
+
 use elp_ide_db::elp_base_db::assert_eq_text;
 use elp_ide_db::elp_base_db::fixture::extract_annotations;
 use elp_ide_db::elp_base_db::fixture::WithFixture;
 use elp_ide_db::elp_base_db::test_fixture::trim_indent;
 use elp_ide_db::elp_base_db::FileId;
 use elp_ide_db::elp_base_db::FileRange;
+use elp_ide_db::elp_base_db::ProjectData;
+use elp_ide_db::elp_base_db::SourceDatabase;
 use elp_ide_db::elp_base_db::SourceDatabaseExt;
 use elp_ide_db::RootDatabase;
+use elp_project_model::lint_file::LintFileConfig;
 use fxhash::FxHashSet;
 
 use crate::diagnostics;
@@ -27,6 +32,7 @@ use crate::fixture;
 use crate::Analysis;
 use crate::DiagnosticsConfig;
 use crate::NavigationTarget;
+use crate::RangeInfo;
 
 /// Takes a multi-file input fixture with annotated cursor positions,
 /// and checks that:
@@ -51,11 +57,38 @@ pub(crate) fn check_fix_with_config(
 }
 
 #[track_caller]
-fn check_nth_fix(nth: usize, fixture_before: &str, fixture_after: &str, config: DiagnosticsConfig) {
+pub(crate) fn check_nth_fix(
+    nth: usize,
+    fixture_before: &str,
+    fixture_after: &str,
+    config: DiagnosticsConfig,
+) {
+    check_nth_fix_with(nth, fixture_before, fixture_after, |db, file_id| {
+        diagnostics::diagnostics(db, &config, file_id, true)
+    })
+}
+
+#[track_caller]
+pub(crate) fn check_xref_fix(fixture_before: &str, fixture_after: &str) {
+    check_nth_fix_with(
+        0,
+        fixture_before,
+        fixture_after,
+        diagnostics::xref_diagnostics,
+    )
+}
+
+#[track_caller]
+fn check_nth_fix_with(
+    nth: usize,
+    fixture_before: &str,
+    fixture_after: &str,
+    get_diagnostics: impl Fn(&RootDatabase, FileId) -> Vec<diagnostics::Diagnostic>,
+) {
     let after = trim_indent(fixture_after);
 
     let (db, file_position) = RootDatabase::with_position(fixture_before);
-    let diagnostic = diagnostics::diagnostics(&db, &config, file_position.file_id, true)
+    let diagnostic = get_diagnostics(&db, file_position.file_id)
         .pop()
         .expect("no diagnostics");
     let fix = &diagnostic.fixes.expect("diagnostic misses fixes")[nth];
@@ -87,9 +120,80 @@ pub(crate) fn check_diagnostics(ra_fixture: &str) {
 
 #[track_caller]
 pub(crate) fn check_diagnostics_with_config(config: DiagnosticsConfig, elp_fixture: &str) {
+    check_annotated_diagnostics(elp_fixture, |db, file_id| {
+        diagnostics::diagnostics(db, &config, file_id, true)
+    })
+}
+
+/// Like `check_diagnostics`, with the given `.elp_lint.toml` config
+/// for the projects of the fixture.
+#[track_caller]
+pub(crate) fn check_diagnostics_with_lint_file_config(
+    lint_file_config: LintFileConfig,
+    elp_fixture: &str,
+) {
+    check_diagnostics_with_project_data(elp_fixture, |project_data| {
+        project_data.lint_file_config = lint_file_config.clone()
+    })
+}
+
+#[track_caller]
+pub(crate) fn check_plugin_diagnostics(lint_file_config: LintFileConfig, elp_fixture: &str) {
+    check_annotated_diagnostics_with_project_data(
+        elp_fixture,
+        |project_data| project_data.lint_file_config = lint_file_config.clone(),
+        diagnostics::plugin_diagnostics,
+    )
+}
+
+#[track_caller]
+fn check_diagnostics_with_project_data(elp_fixture: &str, update: impl Fn(&mut ProjectData)) {
+    let config =
+        DiagnosticsConfig::default().disable(DiagnosticCode::MissingCompileWarnMissingSpec);
+    check_annotated_diagnostics_with_project_data(elp_fixture, update, |db, file_id| {
+        diagnostics::diagnostics(db, &config, file_id, true)
+    })
+}
+
+#[track_caller]
+fn check_annotated_diagnostics_with_project_data(
+    elp_fixture: &str,
+    update: impl Fn(&mut ProjectData),
+    get_diagnostics: impl Fn(&RootDatabase, FileId) -> Vec<diagnostics::Diagnostic>,
+) {
+    let (mut db, files) = RootDatabase::with_many_files(elp_fixture);
+    for &file_id in &files {
+        if let Some(app_data) = db.app_data(db.file_source_root(file_id)) {
+            let mut project_data = (*db.project_data(app_data.project_id)).clone();
+            update(&mut project_data);
+            db.set_project_data(app_data.project_id, Arc::new(project_data));
+        }
+    }
+    check_annotated_diagnostics_in_db(&db, files, get_diagnostics)
+}
+
+#[track_caller]
+pub(crate) fn check_xref_diagnostics(elp_fixture: &str) {
+    check_annotated_diagnostics(elp_fixture, diagnostics::xref_diagnostics)
+}
+
+#[track_caller]
+fn check_annotated_diagnostics(
+    elp_fixture: &str,
+    get_diagnostics: impl Fn(&RootDatabase, FileId) -> Vec<diagnostics::Diagnostic>,
+) {
     let (db, files) = RootDatabase::with_many_files(elp_fixture);
+    check_annotated_diagnostics_in_db(&db, files, get_diagnostics)
+}
+
+#[track_caller]
+fn check_annotated_diagnostics_in_db(
+    db: &RootDatabase,
+    files: Vec<FileId>,
+    get_diagnostics: impl Fn(&RootDatabase, FileId) -> Vec<diagnostics::Diagnostic>,
+) {
     for file_id in files {
-        let diagnostics = diagnostics::diagnostics(&db, &config, file_id, true);
+        let diagnostics = get_diagnostics(db, file_id);
 
         let expected = extract_annotations(&*db.file_text(file_id));
         let mut actual = diagnostics
@@ -150,8 +254,15 @@ pub fn check_file_ranges(mut ranges: Vec<FileRange>, expected: Vec<(FileRange, S
 #[track_caller]
 pub fn check_call_hierarchy(prepare_fixture: &str, incoming_fixture: &str, outgoing_fixture: &str) {
     check_call_hierarchy_prepare(prepare_fixture);
-    check_call_hierarchy_incoming_calls(incoming_fixture);
-    check_call_hierarchy_outgoing_calls(outgoing_fixture);
+    check_call_hierarchy_incoming_calls(incoming_fixture, false);
+    check_call_hierarchy_outgoing_calls(outgoing_fixture, false);
+}
+
+/// Like `check_call_hierarchy`, with the dynamic calls included.
+#[track_caller]
+pub fn check_call_hierarchy_dynamic(incoming_fixture: &str, outgoing_fixture: &str) {
+    check_call_hierarchy_incoming_calls(incoming_fixture, true);
+    check_call_hierarchy_outgoing_calls(outgoing_fixture, true);
 }
 
 fn check_call_hierarchy_prepare(fixture: &str) {
@@ -168,9 +279,12 @@ fn check_call_hierarchy_prepare(fixture: &str) {
     assert_eq!(expected_range, actual_range);
 }
 
-fn check_call_hierarchy_incoming_calls(fixture: &str) {
+fn check_call_hierarchy_incoming_calls(fixture: &str, include_dynamic: bool) {
     let (analysis, pos, mut expected) = fixture::annotations(trim_indent(fixture).as_str());
-    let incoming_calls = analysis.incoming_calls(pos).unwrap().unwrap();
+    let incoming_calls = analysis
+        .incoming_calls(pos, include_dynamic)
+        .unwrap()
+        .unwrap();
     let mut actual = Vec::new();
     for call in incoming_calls {
         actual.push((
@@ -197,9 +311,12 @@ fn check_call_hierarchy_incoming_calls(fixture: &str) {
     assert_eq!(actual, expected);
 }
 
-fn check_call_hierarchy_outgoing_calls(fixture: &str) {
+fn check_call_hierarchy_outgoing_calls(fixture: &str, include_dynamic: bool) {
     let (analysis, pos, mut expected) = fixture::annotations(trim_indent(fixture).as_str());
-    let outgoing_calls = analysis.outgoing_calls(pos).unwrap().unwrap();
+    let outgoing_calls = analysis
+        .outgoing_calls(pos, include_dynamic)
+        .unwrap()
+        .unwrap();
     let mut actual = Vec::new();
     for call in outgoing_calls {
         actual.push((
@@ -225,3 +342,53 @@ fn check_call_hierarchy_outgoing_calls(fixture: &str) {
     expected.sort_by_key(cmp);
     assert_eq!(actual, expected);
 }
+
+/// Checks the type hierarchy candidates of the first fixture, with no
+/// annotation meaning there are none, and the incoming types of the
+/// second one.
+pub fn check_type_hierarchy(prepare_fixture: &str, incoming_fixture: &str) {
+    let (analysis, pos, mut annotations) =
+        fixture::annotations(trim_indent(prepare_fixture).as_str());
+    match analysis.type_hierarchy_prepare(pos).unwrap() {
+        None => assert!(annotations.is_empty()),
+        Some(RangeInfo { info: mut navs, .. }) => {
+            assert_eq!(navs.len(), 1);
+            assert_eq!(annotations.len(), 1);
+            let nav = navs.pop().unwrap();
+            let (expected_range, _text) = annotations.pop().unwrap();
+            let actual_range = FileRange {
+                file_id: nav.file_id,
+                range: nav.focus_range.unwrap(),
+            };
+            assert_eq!(expected_range, actual_range);
+        }
+    }
+
+    let (analysis, pos, mut expected) =
+        fixture::annotations(trim_indent(incoming_fixture).as_str());
+    let incoming_types = analysis.incoming_types(pos).unwrap().unwrap_or_default();
+    let mut actual = Vec::new();
+    for item in incoming_types {
+        actual.push((
+            FileRange {
+                file_id: item.target.file_id,
+                range: item.target.focus_range.unwrap(),
+            },
+            format!("from: {}", item.target.name),
+        ));
+        for range in item.ranges {
+            actual.push((
+                FileRange {
+                    file_id: item.target.file_id,
+                    range,
+                },
+                format!("from_range: {}", item.target.name),
+            ));
+        }
+    }
+    let cmp =
+        |(frange, text): &(FileRange, String)| (frange.file_id, frange.range.start(), text.clone());
+    actual.sort_by_key(cmp);
+    expected.sort_by_key(cmp);
+    assert_eq!(actual, expected);
+}
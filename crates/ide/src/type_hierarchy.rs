@@ -0,0 +1,217 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Type hierarchy: the specs, callbacks and types that refer to a type.
+//!
+//! Only one level is returned at a time, the items returned for a type
+//! can be used as the position of a further request.
+
+use elp_ide_db::elp_base_db::FilePosition;
+use elp_ide_db::FxIndexMap;
+use elp_ide_db::RootDatabase;
+use elp_ide_db::SymbolKind;
+use elp_syntax::algo;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use elp_syntax::SmolStr;
+use elp_syntax::TextRange;
+use hir::FormIdx;
+use hir::Semantic;
+
+use crate::call_hierarchy::CallItem;
+use crate::handlers::goto_definition;
+use crate::handlers::references;
+use crate::navigation_target::ToNav;
+use crate::NavigationTarget;
+use crate::RangeInfo;
+
+pub(crate) fn type_hierarchy_prepare(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<RangeInfo<Vec<NavigationTarget>>> {
+    let RangeInfo { range, info } = goto_definition::goto_definition(db, position)?;
+    let navs: Vec<_> = info
+        .into_iter()
+        .filter(|nav| nav.kind == SymbolKind::Type)
+        .collect();
+    if navs.is_empty() {
+        None
+    } else {
+        Some(RangeInfo::new(range, navs))
+    }
+}
+
+pub(crate) fn incoming_types(db: &RootDatabase, position: FilePosition) -> Option<Vec<CallItem>> {
+    let sema = Semantic::new(db);
+    let mut refs = TypeRefs::default();
+    let search_result = references::find_all_refs(&sema, position)?;
+    let search_result = search_result.first()?;
+    if search_result.declaration.kind != SymbolKind::Type {
+        return None;
+    }
+
+    for (file_id, ranges) in &search_result.references {
+        let file_id = *file_id;
+        let source_file = sema.parse(file_id);
+        let syntax = source_file.value.syntax();
+        let form_list = sema.db.file_form_list(file_id);
+        let def_map = sema.def_map(file_id);
+
+        for range in ranges {
+            let form = match algo::find_node_at_offset::<ast::Form>(syntax, range.start()) {
+                Some(form) => form,
+                None => continue,
+            };
+            let mut nav = match form_list.find_form(&form) {
+                Some(FormIdx::TypeAlias(idx)) => match def_map.get_type(form_list[idx].name()) {
+                    Some(def) => def.to_nav(db),
+                    None => continue,
+                },
+                Some(FormIdx::Spec(idx)) => match def_map.get_spec(&form_list[idx].name) {
+                    Some(def) => def.to_nav(db),
+                    None => continue,
+                },
+                Some(FormIdx::Callback(idx)) => match def_map.get_callback(&form_list[idx].name) {
+                    Some(def) => def.to_nav(db),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            if file_id != position.file_id {
+                if let Some(module_name) = sema.module_name(file_id) {
+                    nav.name = SmolStr::new(format!("{}:{}", module_name.as_str(), nav.name))
+                }
+            }
+            refs.add(nav, *range);
+        }
+    }
+
+    Some(refs.into_items())
+}
+
+#[derive(Default)]
+struct TypeRefs {
+    forms: FxIndexMap<NavigationTarget, Vec<TextRange>>,
+}
+
+impl TypeRefs {
+    fn add(&mut self, target: NavigationTarget, range: TextRange) {
+        self.forms.entry(target).or_default().push(range);
+    }
+
+    fn into_items(self) -> Vec<CallItem> {
+        let mut items: Vec<_> = self
+            .forms
+            .into_iter()
+            .map(|(target, ranges)| CallItem { target, ranges })
+            .collect();
+        items.sort_by(|a, b| {
+            (a.target.file_id, a.target.full_range.start())
+                .cmp(&(b.target.file_id, b.target.full_range.start()))
+        });
+        items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_type_hierarchy;
+
+    #[test]
+    fn specs_and_types() {
+        check_type_hierarchy(
+            r#"
+   -type po~int() :: {integer(), integer()}.
+%%       ^^^^^^^
+   -type line() :: {point(), point()}.
+   -spec origin() -> point().
+   origin() -> {0, 0}.
+   -spec length(line()) -> float().
+   length(_) -> 0.0.
+   "#,
+            r#"
+   -type po~int() :: {integer(), integer()}.
+   -type line() :: {point(), point()}.
+%%       ^^^^^^ from: line
+%%                  ^^^^^ from_range: line
+%%                           ^^^^^ from_range: line
+   -spec origin() -> point().
+%%       ^^^^^^ from: origin/0
+%%                   ^^^^^ from_range: origin/0
+   origin() -> {0, 0}.
+   -spec length(line()) -> float().
+   length(_) -> 0.0.
+   "#,
+        );
+    }
+
+    #[test]
+    fn on_reference() {
+        check_type_hierarchy(
+            r#"
+   -opaque id() :: integer().
+%%         ^^^^
+   -spec new() -> i~d().
+   new() -> 1.
+   "#,
+            r#"
+   -opaque id() :: integer().
+   -spec new() -> i~d().
+%%       ^^^ from: new/0
+%%                ^^ from_range: new/0
+   new() -> 1.
+   "#,
+        );
+    }
+
+    #[test]
+    fn callbacks_and_remote_types() {
+        check_type_hierarchy(
+            r#"
+//- /src/main.erl
+   -module(main).
+   -export_type([id/0]).
+   -type i~d() :: integer().
+%%       ^^^^
+   -callback init(id()) -> ok.
+//- /src/other.erl
+   -module(other).
+   -spec get() -> main:id().
+   get() -> 1.
+   "#,
+            r#"
+//- /src/main.erl
+   -module(main).
+   -export_type([id/0]).
+   -type i~d() :: integer().
+   -callback init(id()) -> ok.
+%%           ^^^^ from: init
+%%                ^^ from_range: init
+//- /src/other.erl
+   -module(other).
+   -spec get() -> main:id().
+%%       ^^^ from: other:get/0
+%%                     ^^ from_range: other:get/0
+   get() -> 1.
+   "#,
+        );
+    }
+
+    #[test]
+    fn not_a_type() {
+        check_type_hierarchy(
+            r#"
+   fo~o() -> ok.
+   "#,
+            r#"
+   fo~o() -> ok.
+   "#,
+        );
+    }
+}
@@ -0,0 +1,457 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use elp_ide_db::elp_base_db::FilePosition;
+use elp_ide_db::elp_base_db::SourceDatabaseExt;
+use elp_ide_db::RootDatabase;
+use elp_syntax::algo::skip_trivia_token;
+use elp_syntax::AstNode;
+use elp_syntax::Direction;
+use elp_syntax::SyntaxKind;
+use elp_syntax::SyntaxNode;
+use elp_syntax::SyntaxToken;
+use elp_syntax::TextRange;
+use elp_syntax::TextSize;
+use hir::Semantic;
+use text_edit::TextEdit;
+
+/// The characters on-type formatting is triggered by: `d` completes
+/// `end`, and `>` completes `->`.
+pub const ON_TYPE_FORMATTING_TRIGGERS: &[char] = &[';', '.', 'd', '>'];
+
+// Feature: On Typing Formatting
+//
+// Adjusts the code around the cursor as it is typed:
+//
+// - `end` is re-indented to the line of the `case`, `receive`, `fun`,
+//   etc. it closes.
+// - `;` and `->` align the head of the clause they belong to with the
+//   first clause of the same function or expression.
+// - `.` ending a function closes the blocks left open in it, with an
+//   `end` for each.
+//
+// |===
+// | Editor  | Setting
+//
+// | VS Code | `editor.formatOnType`
+// |===
+pub(crate) fn on_type_formatting(
+    db: &RootDatabase,
+    position: FilePosition,
+    typed_char: char,
+) -> Option<TextEdit> {
+    let sema = Semantic::new(db);
+    let source = sema.parse(position.file_id).value;
+    let text = db.file_text(position.file_id);
+    if !text[..usize::from(position.offset)].ends_with(typed_char) {
+        return None;
+    }
+    let token = source
+        .syntax()
+        .token_at_offset(position.offset)
+        .left_biased()?;
+    if token.text_range().end() != position.offset {
+        return None;
+    }
+    match typed_char {
+        'd' if token.text() == "end" => reindent_end(&text, &token),
+        ';' if token.kind() == SyntaxKind::ANON_SEMI => {
+            let prev = skip_trivia_token(token.prev_token()?, Direction::Prev)?;
+            let clause = prev
+                .parent_ancestors()
+                .find(|node| is_clause(node.kind()))?;
+            align_clause(&text, &clause)
+        }
+        '>' if token.kind() == SyntaxKind::ANON_DASH_GT => {
+            let clause = token
+                .parent_ancestors()
+                .find(|node| is_clause(node.kind()))?;
+            align_clause(&text, &clause)
+        }
+        '.' if is_form_end(&token) => close_blocks(&text, &token),
+        _ => None,
+    }
+}
+
+fn reindent_end(text: &str, end: &SyntaxToken) -> Option<TextEdit> {
+    let mut depth = 0;
+    let mut token = end.prev_token()?;
+    loop {
+        if is_form_end(&token) {
+            return None;
+        }
+        if token.text() == "end" {
+            depth += 1;
+        } else if is_block_opener(&token) {
+            if depth == 0 {
+                break;
+            }
+            depth -= 1;
+        }
+        token = token.prev_token()?;
+    }
+    let indent = line_indentation(text, token.text_range().start());
+    reindent(text, end.text_range().start(), &indent)
+}
+
+fn align_clause(text: &str, clause: &SyntaxNode) -> Option<TextEdit> {
+    let first = clause
+        .parent()?
+        .children()
+        .find(|node| node.kind() == clause.kind())?;
+    if &first == clause {
+        return None;
+    }
+    let first_start = first.text_range().start();
+    // Only align with a clause starting its own line
+    indentation_before(text, first_start)?;
+    let indent = line_indentation(text, first_start);
+    reindent(text, clause.text_range().start(), &indent)
+}
+
+/// Insert an `end` before the `.` for each block opened in the
+/// function but not closed, innermost first.
+fn close_blocks(text: &str, dot: &SyntaxToken) -> Option<TextEdit> {
+    let mut depth = 0;
+    let mut unclosed = Vec::new();
+    let mut first = None;
+    let mut token = dot.prev_token();
+    while let Some(current) = token {
+        if is_form_end(&current) {
+            break;
+        }
+        if current.text() == "end" {
+            depth += 1;
+        } else if is_block_opener(&current) {
+            if depth == 0 {
+                unclosed.push(current.text_range().start());
+            } else {
+                depth -= 1;
+            }
+        }
+        if !current.kind().is_trivia() {
+            first = Some(current.clone());
+        }
+        token = current.prev_token();
+    }
+    // Attributes, such as a `-spec` using `fun()` types, have no blocks
+    if unclosed.is_empty() || first?.kind() == SyntaxKind::ANON_DASH {
+        return None;
+    }
+    let insert: String = unclosed
+        .iter()
+        .map(|opener| format!("\n{}end", line_indentation(text, *opener)))
+        .collect();
+    Some(TextEdit::insert(dot.text_range().start(), insert))
+}
+
+fn is_clause(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::FUNCTION_CLAUSE
+            | SyntaxKind::CR_CLAUSE
+            | SyntaxKind::IF_CLAUSE
+            | SyntaxKind::CATCH_CLAUSE
+            | SyntaxKind::FUN_CLAUSE
+    )
+}
+
+/// Keywords are matched by text, as they are parsed as atoms in the
+/// incomplete code being typed.
+pub(crate) fn is_block_opener(token: &SyntaxToken) -> bool {
+    match token.text() {
+        "case" | "receive" | "try" | "begin" => true,
+        // `maybe` is only a keyword when the feature is enabled
+        "maybe" => token.kind() == SyntaxKind::ANON_MAYBE,
+        // Not the `-if(...)` preprocessor directive
+        "if" => {
+            adjacent_token(token, Direction::Prev).map(|prev| prev.kind())
+                != Some(SyntaxKind::ANON_DASH)
+        }
+        // Anonymous and named funs, but not `fun foo/1` or `fun m:f/1`
+        "fun" => match adjacent_token(token, Direction::Next) {
+            Some(next) if next.kind() == SyntaxKind::ANON_LPAREN => true,
+            Some(next) if next.kind() == SyntaxKind::VAR => {
+                adjacent_token(&next, Direction::Next).map(|next| next.kind())
+                    == Some(SyntaxKind::ANON_LPAREN)
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// A `.` terminating a form, as opposed to one in a record field access.
+pub(crate) fn is_form_end(token: &SyntaxToken) -> bool {
+    token.kind() == SyntaxKind::ANON_DOT
+        && token
+            .next_token()
+            .map_or(true, |next| next.kind().is_trivia())
+}
+
+fn adjacent_token(token: &SyntaxToken, direction: Direction) -> Option<SyntaxToken> {
+    let next = match direction {
+        Direction::Next => token.next_token()?,
+        Direction::Prev => token.prev_token()?,
+    };
+    skip_trivia_token(next, direction)
+}
+
+fn line_start(text: &str, offset: TextSize) -> TextSize {
+    let offset = usize::from(offset);
+    let start = text[..offset].rfind('\n').map_or(0, |newline| newline + 1);
+    TextSize::from(start as u32)
+}
+
+/// The whitespace between the start of the line and `offset`, or
+/// `None` if there is something else.
+fn indentation_before(text: &str, offset: TextSize) -> Option<&str> {
+    let before = &text[usize::from(line_start(text, offset))..usize::from(offset)];
+    before
+        .chars()
+        .all(|c| c == ' ' || c == '\t')
+        .then_some(before)
+}
+
+/// The leading whitespace of the line containing `offset`.
+fn line_indentation(text: &str, offset: TextSize) -> String {
+    text[usize::from(line_start(text, offset))..]
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect()
+}
+
+/// Replace the indentation of the line `offset` is on, if it starts at
+/// `offset`.
+fn reindent(text: &str, offset: TextSize, indent: &str) -> Option<TextEdit> {
+    let current = indentation_before(text, offset)?;
+    if current == indent {
+        return None;
+    }
+    Some(TextEdit::replace(
+        TextRange::new(line_start(text, offset), offset),
+        indent.to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use expect_test::Expect;
+
+    use crate::fixture;
+
+    #[track_caller]
+    fn check(typed_char: char, fixture: &str, expect: Expect) {
+        let (analysis, position) = fixture::position(fixture);
+        let edit = analysis
+            .on_type_formatting(position, typed_char)
+            .unwrap()
+            .expect("no edit");
+        let mut actual = analysis.file_text(position.file_id).unwrap().to_string();
+        edit.apply(&mut actual);
+        expect.assert_eq(&actual);
+    }
+
+    #[track_caller]
+    fn check_no_edit(typed_char: char, fixture: &str) {
+        let (analysis, position) = fixture::position(fixture);
+        let edit = analysis.on_type_formatting(position, typed_char).unwrap();
+        assert!(edit.is_none(), "unexpected edit: {:?}", edit);
+    }
+
+    #[test]
+    fn end_aligned_with_case() {
+        check(
+            'd',
+            r#"
+-module(main).
+foo(X) ->
+    case X of
+        a -> ok
+        end~.
+"#,
+            expect![[r#"
+                -module(main).
+                foo(X) ->
+                    case X of
+                        a -> ok
+                    end.
+            "#]],
+        );
+    }
+
+    #[test]
+    fn end_skips_nested_blocks() {
+        check(
+            'd',
+            r#"
+-module(main).
+foo(X) ->
+    case X of
+        a ->
+            receive
+                M -> M
+            end;
+        b -> fun() -> ok end
+  end~.
+"#,
+            expect![[r#"
+                -module(main).
+                foo(X) ->
+                    case X of
+                        a ->
+                            receive
+                                M -> M
+                            end;
+                        b -> fun() -> ok end
+                    end.
+            "#]],
+        );
+    }
+
+    #[test]
+    fn end_after_code_is_left_alone() {
+        check_no_edit(
+            'd',
+            r#"
+-module(main).
+foo(X) ->
+    case X of a -> ok end~.
+"#,
+        );
+    }
+
+    #[test]
+    fn atom_ending_in_end_is_left_alone() {
+        check_no_edit(
+            'd',
+            r#"
+-module(main).
+foo(X) ->
+    case X of
+        a -> ok
+    end,
+      send~.
+"#,
+        );
+    }
+
+    #[test]
+    fn semi_aligns_clause() {
+        check(
+            ';',
+            r#"
+-module(main).
+foo(X) ->
+    case X of
+        a -> ok;
+      b -> error;~
+        c -> ok
+    end.
+"#,
+            expect![[r#"
+                -module(main).
+                foo(X) ->
+                    case X of
+                        a -> ok;
+                        b -> error;
+                        c -> ok
+                    end.
+            "#]],
+        );
+    }
+
+    #[test]
+    fn arrow_aligns_clause() {
+        check(
+            '>',
+            r#"
+-module(main).
+foo() ->
+    receive
+        a -> ok;
+            b ->~ error
+    end.
+"#,
+            expect![[r#"
+                -module(main).
+                foo() ->
+                    receive
+                        a -> ok;
+                        b -> error
+                    end.
+            "#]],
+        );
+    }
+
+    #[test]
+    fn arrow_in_first_clause_is_left_alone() {
+        check_no_edit(
+            '>',
+            r#"
+-module(main).
+foo(X) ->
+    case X of
+          a ->~ ok
+    end.
+"#,
+        );
+    }
+
+    #[test]
+    fn dot_closes_open_blocks() {
+        check(
+            '.',
+            r#"
+-module(main).
+foo(X) ->
+    case X of
+        a ->
+            receive
+                M -> M.~
+"#,
+            expect![[r#"
+                -module(main).
+                foo(X) ->
+                    case X of
+                        a ->
+                            receive
+                                M -> M
+                            end
+                    end.
+            "#]],
+        );
+    }
+
+    #[test]
+    fn dot_after_closed_blocks_is_left_alone() {
+        check_no_edit(
+            '.',
+            r#"
+-module(main).
+foo(X) ->
+    case X of
+        a -> fun foo/1
+    end.~
+"#,
+        );
+    }
+
+    #[test]
+    fn dot_in_spec_is_left_alone() {
+        check_no_edit(
+            '.',
+            r#"
+-module(main).
+-spec foo(fun((a) -> b)) -> ok.~
+"#,
+        );
+    }
+}
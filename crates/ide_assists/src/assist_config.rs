@@ -21,4 +21,13 @@ use crate::AssistKind;
 pub struct AssistConfig {
     pub snippet_cap: Option<SnippetCap>,
     pub allowed: Option<Vec<AssistKind>>,
+    /// Whether to offer experimental assists, whose edits may need
+    /// more review than usual.
+    pub experimental: bool,
+    /// Whether `reorder_record_fields` sorts fields alphabetically,
+    /// rather than in the order of the record definition.
+    pub record_fields_alphabetical: bool,
+    /// Whether `sort_exports` sorts functions alphabetically, rather
+    /// than in the order of their definitions.
+    pub exports_alphabetical: bool,
 }
@@ -16,9 +16,11 @@ use elp_ide_db::elp_base_db::FileRange;
 use elp_ide_db::elp_base_db::SourceDatabase;
 use elp_ide_db::label::Label;
 use elp_ide_db::source_change::SourceChangeBuilder;
+use elp_ide_db::EqwalizerDatabase;
 use elp_ide_db::RootDatabase;
 use elp_ide_db::SymbolClass;
 use elp_syntax::algo;
+use elp_syntax::ast;
 use elp_syntax::ast::AstNode;
 use elp_syntax::Direction;
 use elp_syntax::SourceFile;
@@ -82,6 +84,8 @@ pub(crate) struct AssistContext<'a> {
     pub(crate) frange: FileRange,
     pub(crate) diagnostics: &'a [AssistContextDiagnostic],
     pub(crate) user_input: Option<AssistUserInput>,
+    /// For the queries beyond `MinDefDatabase`, e.g. eqWAlizer types
+    root_db: &'a RootDatabase,
     trimmed_range: TextRange,
     source_file: SourceFile,
 }
@@ -134,6 +138,7 @@ impl<'a> AssistContext<'a> {
             source_file,
             diagnostics,
             user_input,
+            root_db: db,
         }
     }
 
@@ -141,6 +146,17 @@ impl<'a> AssistContext<'a> {
         self.sema.db
     }
 
+    /// The type of the expression, as inferred by eqWAlizer if it
+    /// checked the file
+    pub(crate) fn eqwalizer_type(&self, expr: &ast::Expr) -> Option<String> {
+        let types = self.root_db.types_for_file(self.file_id())?;
+        let range = expr.syntax().text_range();
+        types
+            .iter()
+            .find(|info| info.range == range)
+            .map(|info| info.ty.clone())
+    }
+
     // NB, this ignores active selection.
     pub(crate) fn offset(&self) -> TextSize {
         // Temporary for T153426323
@@ -315,10 +331,35 @@ impl Assists {
             target,
             source_change: None,
             user_input,
+            disabled: None,
         };
         self.add_impl(assist, f)
     }
 
+    /// Offer an assist which can't be applied here, with the reason why,
+    /// rather than not offering it at all.
+    pub(crate) fn add_disabled(
+        &mut self,
+        id: AssistId,
+        label: impl Into<String>,
+        target: TextRange,
+        reason: impl Into<String>,
+    ) -> Option<()> {
+        if !self.is_allowed(&id) {
+            return None;
+        }
+        self.buf.push(Assist {
+            id,
+            label: Label::new(label.into()),
+            group: None,
+            target,
+            source_change: None,
+            user_input: None,
+            disabled: Some(reason.into()),
+        });
+        Some(())
+    }
+
     fn add_impl(
         &mut self,
         mut assist: Assist,
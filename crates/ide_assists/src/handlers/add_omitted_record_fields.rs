@@ -0,0 +1,130 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use elp_ide_db::assists::AssistId;
+use elp_ide_db::assists::AssistKind;
+use elp_syntax::algo;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+
+use crate::helpers::omitted_record_fields;
+use crate::AssistContext;
+use crate::Assists;
+
+// Assist: add_omitted_record_fields
+//
+// Spells out the fields a record pattern does not match, as `field = _`,
+// so that readers can see what is being ignored.
+//
+// ```
+// -record(person, {name, age, email}).
+// f(#person{~name = N}) -> N.
+// ```
+// ->
+// ```
+// -record(person, {name, age, email}).
+// f(#person{name = N, age = _, email = _}) -> N.
+// ```
+pub(crate) fn add_omitted_record_fields(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let file_id = ctx.file_id();
+    let source = ctx.sema.parse(file_id).value;
+    let (record, omitted) = algo::ancestors_at_offset(source.syntax(), ctx.offset())
+        .filter_map(ast::RecordExpr::cast)
+        .find_map(|record| {
+            let omitted = omitted_record_fields(&ctx.sema, file_id, &record)?;
+            Some((record, omitted))
+        })?;
+
+    acc.add(
+        AssistId("add_omitted_record_fields", AssistKind::RefactorRewrite),
+        "Add omitted record fields as `_`",
+        record.syntax().text_range(),
+        None,
+        |edit| edit.insert(omitted.insert_at, omitted.render()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn adds_omitted_fields() {
+        check_assist(
+            add_omitted_record_fields,
+            "Add omitted record fields as `_`",
+            r#"
+-record(person, {name, age, email}).
+f(#person{~email = E}) -> E.
+"#,
+            expect![[r#"
+                -record(person, {name, age, email}).
+                f(#person{email = E, name = _, age = _}) -> E.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn adds_to_empty_pattern() {
+        check_assist(
+            add_omitted_record_fields,
+            "Add omitted record fields as `_`",
+            r#"
+-record(person, {name, 'Age'}).
+f(P) ->
+    case P of
+        #pers~on{} -> ok
+    end.
+"#,
+            expect![[r#"
+                -record(person, {name, 'Age'}).
+                f(P) ->
+                    case P of
+                        #person{name = _, 'Age' = _} -> ok
+                    end.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn not_applicable_to_construction() {
+        check_assist_not_applicable(
+            add_omitted_record_fields,
+            r#"
+-record(person, {name, age}).
+f() -> #person{~name = "Joe"}.
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_with_wildcard_field() {
+        check_assist_not_applicable(
+            add_omitted_record_fields,
+            r#"
+-record(person, {name, age}).
+f(#person{~name = N, _ = '_'}) -> N.
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_when_complete() {
+        check_assist_not_applicable(
+            add_omitted_record_fields,
+            r#"
+-record(person, {name, age}).
+f(#person{~name = N, age = A}) -> {N, A}.
+"#,
+        );
+    }
+}
@@ -20,13 +20,16 @@ use crate::Assists;
 // Assist: add_spec
 //
 // Adds a spec stub above a function, if it doesn't already have one.
+// The types are the ones inferred by eqWAlizer for the arguments and
+// the last expression of the clause, if it checked the module, and
+// `term()` otherwise.
 //
 // ```
 // foo(Arg1, some_atom) -> ok.
 // ```
 // ->
 // ```
-// -spec foo(Arg1 :: arg1(), arg2()) -> return_type().
+// -spec foo(Arg1 :: term(), term()) -> term().
 // foo(Arg1, some_atom) -> ok.
 // ```
 pub(crate) fn add_spec(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
@@ -58,7 +61,7 @@ pub(crate) fn add_spec(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
         target,
         None,
         |builder| {
-            let type_names = source
+            let clause = source
                 .clauses()
                 .find_map(|c| match c {
                     ast::FunctionOrMacroClause::FunctionClause(ref clause) => {
@@ -70,42 +73,32 @@ pub(crate) fn add_spec(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
                     }
                     ast::FunctionOrMacroClause::MacroCallExpr(_) => None,
                 })
-                .unwrap()
+                .unwrap();
+            let type_names: Vec<String> = clause
                 .args()
                 .into_iter()
                 .flat_map(|args| args.args())
-                .enumerate()
-                .map(|(arg_idx, expr)| type_name(arg_idx + 1, expr));
-
+                .map(|expr| type_name(ctx, expr))
+                .collect();
+            let return_type = clause
+                .body()
+                .and_then(|body| body.exprs().last())
+                .and_then(|expr| ctx.eqwalizer_type(&expr))
+                .unwrap_or_else(|| "term()".to_string());
+
+            builder.edit_file(ctx.frange.file_id);
             match ctx.config.snippet_cap {
                 Some(cap) => {
-                    let mut snippet_idx = 0;
-                    let types_snippets = type_names
-                        .map(|arg_name| {
-                            snippet_idx += 1;
-                            format!("${{{}:{}}}, ", snippet_idx, arg_name)
-                        })
-                        .collect::<String>();
-                    snippet_idx += 1;
-                    let snippet = format!(
-                        "-spec {}({}) -> ${{{}:return_type()}}.\n",
-                        name_text,
-                        types_snippets.trim_end_matches(", "),
-                        snippet_idx
-                    );
-                    builder.edit_file(ctx.frange.file_id);
+                    let snippet = spec_snippet(&name_text, &type_names, &return_type);
                     builder.insert_snippet(cap, insert, snippet);
                 }
                 None => {
-                    let types_text = type_names
-                        .map(|arg_name| format!("{}, ", arg_name))
-                        .collect::<String>();
                     let text = format!(
-                        "-spec {}({}) -> return_type().\n",
+                        "-spec {}({}) -> {}.\n",
                         name_text,
-                        types_text.trim_end_matches(", ")
+                        type_names.join(", "),
+                        return_type
                     );
-                    builder.edit_file(ctx.frange.file_id);
                     builder.insert(insert, text)
                 }
             }
@@ -113,11 +106,39 @@ pub(crate) fn add_spec(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
     )
 }
 
-pub fn type_name(arg_idx: usize, expr: ast::Expr) -> String {
+/// The spec with a placeholder for each type. The types inferred by
+/// eqWAlizer can contain characters special in snippets, e.g. the `}`
+/// of `#{a => b}` or the `$` of `'$end_of_table'`, which are escaped.
+fn spec_snippet(name: &str, arg_types: &[String], return_type: &str) -> String {
+    let args = arg_types
+        .iter()
+        .enumerate()
+        .map(|(idx, ty)| format!("${{{}:{}}}", idx + 1, escape_snippet(ty)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "-spec {}({}) -> ${{{}:{}}}.\n",
+        name,
+        args,
+        arg_types.len() + 1,
+        escape_snippet(return_type)
+    )
+}
+
+fn escape_snippet(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('$', "\\$")
+        .replace('}', "\\}")
+}
+
+fn type_name(ctx: &AssistContext, expr: ast::Expr) -> String {
+    let ty = ctx
+        .eqwalizer_type(&expr)
+        .unwrap_or_else(|| "term()".to_string());
     if let ast::Expr::ExprMax(ast::ExprMax::Var(var)) = expr {
-        format!("{} :: type{}()", var.text().to_string(), arg_idx)
+        format!("{} :: {}", var.text(), ty)
     } else {
-        format!("type{}()", arg_idx)
+        ty
     }
 }
 
@@ -142,7 +163,7 @@ mod tests {
 ~foo(Foo, some_atom) -> ok.
 "#,
             expect![[r#"
-                -spec foo(${1:Foo :: type1()}, ${2:type2()}) -> ${3:return_type()}.
+                -spec foo(${1:Foo :: term()}, ${2:term()}) -> ${3:term()}.
                 foo(Foo, some_atom) -> ok.
             "#]],
         )
@@ -161,12 +182,25 @@ f~oo() -> ok.
             expect![[r#"
                 -spec bar() -> ok.
                 bar() -> ok.
-                -spec foo() -> ${1:return_type()}.
+                -spec foo() -> ${1:term()}.
                 foo() -> ok.
             "#]],
         )
     }
 
+    #[test]
+    fn snippet_of_eqwalizer_types() {
+        let snippet = spec_snippet(
+            "first",
+            &["Tab :: ets:tid()".to_string(), "#{a => b}".to_string()],
+            "'$end_of_table' | #{a => b}",
+        );
+        expect![[r#"
+            -spec first(${1:Tab :: ets:tid()}, ${2:#{a => b\}}) -> ${3:'\$end_of_table' | #{a => b\}}.
+        "#]]
+        .assert_eq(&snippet);
+    }
+
     #[test]
     fn test_already_has_spec_above() {
         check_assist_not_applicable(
@@ -0,0 +1,858 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use elp_ide_db::assists::AssistId;
+use elp_ide_db::assists::AssistKind;
+use elp_ide_db::assists::AssistUserInput;
+use elp_ide_db::assists::AssistUserInputType;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use elp_syntax::NodeOrToken;
+use elp_syntax::SyntaxKind;
+use elp_syntax::SyntaxNode;
+use elp_syntax::TextRange;
+use elp_syntax::TextSize;
+use fxhash::FxHashMap;
+use fxhash::FxHashSet;
+use hir::Name;
+
+use crate::AssistContext;
+use crate::Assists;
+
+/// Tuples smaller than this are easy enough to follow, and are left alone.
+const MIN_ARITY: usize = 3;
+const DEFAULT_RECORD_NAME: &str = "acc";
+/// The reserved words of Erlang, which must be quoted to be used as atoms.
+const RESERVED_WORDS: &[&str] = &[
+    "after", "and", "andalso", "band", "begin", "bnot", "bor", "bsl", "bsr", "bxor", "case",
+    "catch", "cond", "div", "else", "end", "fun", "if", "let", "maybe", "not", "of", "or",
+    "orelse", "receive", "rem", "try", "when", "xor",
+];
+
+// Assist: convert_foldl_acc_to_record
+//
+// Experimental. Converts the bare tuple accumulator of a `lists:foldl/3`
+// (or `lists:foldr/3`) with a fun literal into a record, generating the
+// `-record` declaration. The fun accumulator pattern, the tuples returned
+// by the fun, `element/2` and `setelement/3` accesses to the accumulator,
+// and the tuple pattern matching the result are all rewritten. The result
+// must be matched by such a pattern, so that its shape does not change
+// for the rest of the function. The label previews the fields.
+//
+// ```
+// f(L) ->
+//     {S, C, M} = lists:fo~ldl(fun(X, {Sum, Count, Max}) -> {Sum + X, Count + 1, max(X, Max)} end, {0, 0, 0}, L),
+//     S.
+// ```
+// ->
+// ```
+// -record(acc, {sum, count, max}).
+//
+// f(L) ->
+//     #acc{sum = S, count = C, max = M} = lists:foldl(fun(X, #acc{sum = Sum, count = Count, max = Max}) -> #acc{sum = Sum + X, count = Count + 1, max = max(X, Max)} end, #acc{sum = 0, count = 0, max = 0}, L),
+//     S.
+// ```
+pub(crate) fn convert_foldl_acc_to_record(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    if !ctx.config.experimental {
+        return None;
+    }
+    let fold = FoldCall::at_cursor(ctx)?;
+    let record_name = fresh_record_name(ctx, DEFAULT_RECORD_NAME);
+    let target = fold.call.syntax().text_range();
+    let label = format!(
+        "Convert accumulator tuple to record #{}{{{}}}",
+        record_name,
+        fold.fields.join(", ")
+    );
+    acc.add(
+        AssistId("convert_foldl_acc_to_record", AssistKind::RefactorRewrite),
+        label,
+        target,
+        Some(AssistUserInput {
+            input_type: AssistUserInputType::Atom,
+            value: record_name.clone(),
+        }),
+        |builder| {
+            // A name given by the user may clash with an existing record too
+            let name = fresh_record_name(ctx, &ctx.user_input_or(|| record_name));
+            let style = AccStyle::Record(name.clone());
+            for (range, text) in fold.edits(&style) {
+                builder.replace(range, text);
+            }
+            if let Some(form) = fold.call.syntax().ancestors().find_map(ast::FunDecl::cast) {
+                builder.insert(
+                    record_insert_offset(&form),
+                    format!("-record({}, {{{}}}).\n\n", name, fold.fields.join(", ")),
+                );
+            }
+        },
+    )
+}
+
+// Assist: convert_foldl_acc_to_map
+//
+// Experimental. Same as `convert_foldl_acc_to_record`, but uses a map with
+// atom keys instead of a record.
+//
+// ```
+// f(L) ->
+//     {S, C, M} = lists:fo~ldl(fun(X, {Sum, Count, Max}) -> {Sum + X, Count + 1, max(X, Max)} end, {0, 0, 0}, L),
+//     S.
+// ```
+// ->
+// ```
+// f(L) ->
+//     #{sum := S, count := C, max := M} = lists:foldl(fun(X, #{sum := Sum, count := Count, max := Max}) -> #{sum => Sum + X, count => Count + 1, max => max(X, Max)} end, #{sum => 0, count => 0, max => 0}, L),
+//     S.
+// ```
+pub(crate) fn convert_foldl_acc_to_map(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    if !ctx.config.experimental {
+        return None;
+    }
+    let fold = FoldCall::at_cursor(ctx)?;
+    let target = fold.call.syntax().text_range();
+    let label = format!(
+        "Convert accumulator tuple to map #{{{}}}",
+        fold.fields.join(", ")
+    );
+    acc.add(
+        AssistId("convert_foldl_acc_to_map", AssistKind::RefactorRewrite),
+        label,
+        target,
+        None,
+        |builder| {
+            for (range, text) in fold.edits(&AccStyle::Map) {
+                builder.replace(range, text);
+            }
+        },
+    )
+}
+
+/// Where to declare the record: before the function, along with the spec
+/// and comments directly preceding it.
+fn record_insert_offset(form: &ast::FunDecl) -> TextSize {
+    let mut offset = form.syntax().text_range().start();
+    let mut element = form.syntax().prev_sibling_or_token();
+    while let Some(current) = element {
+        match current.kind() {
+            SyntaxKind::WHITESPACE => {
+                // A blank line separates unrelated forms
+                if current.to_string().matches('\n').count() > 1 {
+                    break;
+                }
+            }
+            kind if kind == SyntaxKind::COMMENT || ast::Spec::can_cast(kind) => {
+                offset = current.text_range().start();
+            }
+            _ => break,
+        }
+        element = current.prev_sibling_or_token();
+    }
+    offset
+}
+
+fn fresh_record_name(ctx: &AssistContext, base: &str) -> String {
+    let def_map = ctx.sema.def_map(ctx.file_id());
+    let mut name = base.to_string();
+    let mut counter = 0;
+    while def_map
+        .get_record(&Name::from_erlang_service(&name))
+        .is_some()
+    {
+        counter += 1;
+        name = format!("{}{}", base, counter);
+    }
+    name
+}
+
+enum AccStyle {
+    Record(String),
+    Map,
+}
+
+impl AccStyle {
+    fn construct(&self, fields: &[(&str, String)]) -> String {
+        match self {
+            AccStyle::Record(name) => format!("#{}{{{}}}", name, join(fields, " = ")),
+            AccStyle::Map => format!("#{{{}}}", join(fields, " => ")),
+        }
+    }
+
+    fn pattern(&self, fields: &[(&str, String)]) -> String {
+        match self {
+            AccStyle::Record(name) => format!("#{}{{{}}}", name, join(fields, " = ")),
+            AccStyle::Map => format!("#{{{}}}", join(fields, " := ")),
+        }
+    }
+
+    fn get(&self, var: &str, field: &str) -> String {
+        match self {
+            AccStyle::Record(name) => format!("{}#{}.{}", var, name, field),
+            AccStyle::Map => format!("maps:get({}, {})", field, var),
+        }
+    }
+
+    fn set(&self, var: &str, field: &str, value: String) -> String {
+        match self {
+            AccStyle::Record(name) => format!("{}#{}{{{} = {}}}", var, name, field, value),
+            AccStyle::Map => format!("{}#{{{} := {}}}", var, field, value),
+        }
+    }
+}
+
+fn join(fields: &[(&str, String)], sep: &str) -> String {
+    fields
+        .iter()
+        .map(|(field, value)| format!("{}{}{}", field, sep, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+enum Rewrite {
+    /// A tuple built as a new accumulator value.
+    Construct(ast::Tuple),
+    /// A tuple pattern destructuring the accumulator.
+    Pattern(ast::Tuple),
+    /// `element(Idx, Base)`
+    Element { base: ast::Expr, idx: usize },
+    /// `setelement(Idx, Base, Value)`
+    SetElement {
+        base: ast::Expr,
+        idx: usize,
+        value: ast::Expr,
+    },
+}
+
+struct FoldCall {
+    call: ast::Call,
+    fields: Vec<String>,
+    rewrites: FxHashMap<SyntaxNode, Rewrite>,
+}
+
+impl FoldCall {
+    fn at_cursor(ctx: &AssistContext) -> Option<FoldCall> {
+        let token = ctx.token_at_offset().right_biased()?;
+        let call = token.parent_ancestors().find_map(ast::Call::cast)?;
+        // Only trigger on the `lists:foldl` part, not anywhere in the fun body
+        if !call
+            .expr()?
+            .syntax()
+            .text_range()
+            .contains_inclusive(ctx.offset())
+        {
+            return None;
+        }
+        FoldCall::new(call)
+    }
+
+    fn new(call: ast::Call) -> Option<FoldCall> {
+        match call.expr()? {
+            ast::Expr::Remote(remote) => {
+                let module = remote.module()?.module()?.name()?;
+                let fun = remote.fun()?.name()?;
+                if module != "lists" || !(fun == "foldl" || fun == "foldr") {
+                    return None;
+                }
+            }
+            _ => return None,
+        };
+        let (fun, acc0) = match &call.args()?.args().collect::<Vec<_>>()[..] {
+            [fun, acc0, _list] => (fun.clone(), as_tuple(acc0)?),
+            _ => return None,
+        };
+        let arity = acc0.expr().count();
+        if arity < MIN_ARITY {
+            return None;
+        }
+        let clause = match fun {
+            ast::Expr::ExprMax(ast::ExprMax::AnonymousFun(fun)) => {
+                let mut clauses = fun.clauses();
+                let clause = clauses.next()?;
+                if clauses.next().is_some() {
+                    return None;
+                }
+                clause
+            }
+            _ => return None,
+        };
+        let acc_param = match &clause.args()?.args().collect::<Vec<_>>()[..] {
+            [_elem, acc_param] => acc_param.clone(),
+            _ => return None,
+        };
+        let body = clause.body()?;
+
+        let mut rewrites = FxHashMap::default();
+        rewrites.insert(acc0.syntax().clone(), Rewrite::Construct(acc0));
+
+        // The accumulator is either destructured in the fun head, or bound
+        // to a single variable and accessed with element/setelement.
+        let fields = match &acc_param {
+            ast::Expr::ExprMax(ast::ExprMax::Var(var)) => {
+                let acc_var = var.text().to_string();
+                collect_element_accesses(&body, &acc_var, arity, &mut rewrites)?;
+                (1..=arity).map(|idx| format!("field{}", idx)).collect()
+            }
+            _ => {
+                let pattern = as_tuple(&acc_param)?;
+                if pattern.expr().count() != arity {
+                    return None;
+                }
+                let fields = field_names(&pattern);
+                rewrites.insert(pattern.syntax().clone(), Rewrite::Pattern(pattern));
+                fields
+            }
+        };
+
+        for tail in tail_exprs(&body)? {
+            if rewrites.contains_key(tail.syntax()) || is_var(&tail, &acc_param) {
+                continue;
+            }
+            let tuple = as_tuple(&tail)?;
+            if tuple.expr().count() != arity {
+                return None;
+            }
+            rewrites.insert(tuple.syntax().clone(), Rewrite::Construct(tuple));
+        }
+
+        // `{A, B, C} = lists:foldl(...), ...`: the result changes shape, so
+        // it must be destructured by a pattern rewritten with it, in a match
+        // whose own value is not used.
+        let result_match = call
+            .syntax()
+            .parent()
+            .and_then(ast::MatchExpr::cast)
+            .filter(|m| m.rhs().map_or(false, |rhs| rhs.syntax() == call.syntax()))?;
+        let pattern = result_match.lhs().as_ref().and_then(as_tuple)?;
+        if pattern.expr().count() != arity || !is_statement(&result_match) {
+            return None;
+        }
+        rewrites.insert(pattern.syntax().clone(), Rewrite::Pattern(pattern));
+
+        Some(FoldCall {
+            call,
+            fields,
+            rewrites,
+        })
+    }
+
+    /// The text edits for the outermost rewritten nodes. Nested rewrites
+    /// are folded into the replacement text of their enclosing node.
+    fn edits(&self, style: &AccStyle) -> Vec<(TextRange, String)> {
+        let mut res: Vec<_> = self
+            .rewrites
+            .keys()
+            .filter(|node| {
+                !node
+                    .ancestors()
+                    .skip(1)
+                    .any(|ancestor| self.rewrites.contains_key(&ancestor))
+            })
+            .map(|node| (node.text_range(), self.render(node, style)))
+            .collect();
+        res.sort_by_key(|(range, _)| range.start());
+        res
+    }
+
+    fn render(&self, node: &SyntaxNode, style: &AccStyle) -> String {
+        match self.rewrites.get(node) {
+            Some(Rewrite::Construct(tuple)) => style.construct(&self.zip_fields(tuple, style)),
+            Some(Rewrite::Pattern(tuple)) => {
+                let fields: Vec<_> = self
+                    .zip_fields(tuple, style)
+                    .into_iter()
+                    .filter(|(_, value)| value != "_")
+                    .collect();
+                style.pattern(&fields)
+            }
+            Some(Rewrite::Element { base, idx }) => {
+                style.get(&self.render(base.syntax(), style), &self.fields[idx - 1])
+            }
+            Some(Rewrite::SetElement { base, idx, value }) => style.set(
+                &self.render(base.syntax(), style),
+                &self.fields[idx - 1],
+                self.render(value.syntax(), style),
+            ),
+            None => node
+                .children_with_tokens()
+                .map(|child| match child {
+                    NodeOrToken::Node(node) => self.render(&node, style),
+                    NodeOrToken::Token(token) => token.text().to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    fn zip_fields<'a>(&'a self, tuple: &ast::Tuple, style: &AccStyle) -> Vec<(&'a str, String)> {
+        self.fields
+            .iter()
+            .map(|field| field.as_str())
+            .zip(tuple.expr().map(|expr| self.render(expr.syntax(), style)))
+            .collect()
+    }
+}
+
+/// Registers rewrites for all `element/2` and `setelement/3` calls on the
+/// accumulator variable. Fails if the variable is used in any other way,
+/// other than being returned as is.
+fn collect_element_accesses(
+    body: &ast::ClauseBody,
+    acc_var: &str,
+    arity: usize,
+    rewrites: &mut FxHashMap<SyntaxNode, Rewrite>,
+) -> Option<()> {
+    let tails = tail_exprs(body)?;
+    for var in body
+        .syntax()
+        .descendants()
+        .filter_map(ast::Var::cast)
+        .filter(|var| var.text() == acc_var)
+    {
+        if tails.iter().any(|tail| tail.syntax() == var.syntax()) {
+            continue;
+        }
+        // Walk up a chain of nested `setelement(I, setelement(J, Acc, ..), ..)`
+        let mut base: ast::Expr = ast::Expr::cast(var.syntax().clone())?;
+        loop {
+            let call = base.syntax().parent()?.parent().and_then(ast::Call::cast)?;
+            let args: Vec<ast::Expr> = call.args()?.args().collect();
+            if args.get(1).map(|arg| arg.syntax()) != Some(base.syntax()) {
+                return None;
+            }
+            let idx = match (fun_name(&call)?.as_str(), &args[..]) {
+                ("element", [idx, _]) | ("setelement", [idx, _, _]) => literal_index(idx)?,
+                _ => return None,
+            };
+            if idx == 0 || idx > arity {
+                return None;
+            }
+            if args.len() == 2 {
+                rewrites.insert(call.syntax().clone(), Rewrite::Element { base, idx });
+                break;
+            }
+            rewrites.insert(
+                call.syntax().clone(),
+                Rewrite::SetElement {
+                    base,
+                    idx,
+                    value: args[2].clone(),
+                },
+            );
+            base = ast::Expr::Call(call.clone());
+            let parent_call = call
+                .syntax()
+                .parent()
+                .and_then(|args| args.parent())
+                .and_then(ast::Call::cast);
+            match parent_call.and_then(|parent| fun_name(&parent)) {
+                Some(name) if name == "element" || name == "setelement" => {}
+                _ => break,
+            }
+        }
+    }
+    Some(())
+}
+
+fn fun_name(call: &ast::Call) -> Option<String> {
+    match call.expr()? {
+        ast::Expr::ExprMax(fun) => Some(fun.name()?.to_string()),
+        _ => None,
+    }
+}
+
+fn literal_index(expr: &ast::Expr) -> Option<usize> {
+    match expr {
+        ast::Expr::ExprMax(ast::ExprMax::Integer(idx)) => idx.text().parse().ok(),
+        _ => None,
+    }
+}
+
+/// An expression of a clause body other than the last one, so that its
+/// value is discarded.
+fn is_statement(m: &ast::MatchExpr) -> bool {
+    m.syntax()
+        .parent()
+        .and_then(ast::ClauseBody::cast)
+        .and_then(|body| body.exprs().last())
+        .map_or(false, |last| last.syntax() != m.syntax())
+}
+
+fn is_var(expr: &ast::Expr, var: &ast::Expr) -> bool {
+    match (expr, var) {
+        (
+            ast::Expr::ExprMax(ast::ExprMax::Var(expr)),
+            ast::Expr::ExprMax(ast::ExprMax::Var(var)),
+        ) => expr.text() == var.text(),
+        _ => false,
+    }
+}
+
+fn as_tuple(expr: &ast::Expr) -> Option<ast::Tuple> {
+    match expr {
+        ast::Expr::ExprMax(ast::ExprMax::Tuple(tuple)) => Some(tuple.clone()),
+        ast::Expr::ExprMax(ast::ExprMax::ParenExpr(paren)) => as_tuple(&paren.expr()?),
+        _ => None,
+    }
+}
+
+/// The expressions whose value is returned from the clause body,
+/// looking through `case`, `if` and `begin` blocks.
+/// Returns `None` if some return value cannot be determined.
+fn tail_exprs(body: &ast::ClauseBody) -> Option<Vec<ast::Expr>> {
+    let mut res = Vec::new();
+    collect_tail_exprs(body.exprs().last()?, &mut res)?;
+    Some(res)
+}
+
+fn collect_tail_exprs(expr: ast::Expr, acc: &mut Vec<ast::Expr>) -> Option<()> {
+    match &expr {
+        ast::Expr::ExprMax(ast::ExprMax::CaseExpr(case)) => {
+            for clause in case.clauses() {
+                match clause {
+                    ast::CrClauseOrMacro::CrClause(clause) => {
+                        collect_tail_exprs(clause.body()?.exprs().last()?, acc)?
+                    }
+                    ast::CrClauseOrMacro::MacroCallExpr(_) => return None,
+                }
+            }
+        }
+        ast::Expr::ExprMax(ast::ExprMax::IfExpr(if_expr)) => {
+            for clause in if_expr.clauses() {
+                collect_tail_exprs(clause.body()?.exprs().last()?, acc)?
+            }
+        }
+        ast::Expr::ExprMax(ast::ExprMax::BlockExpr(block)) => {
+            collect_tail_exprs(block.exprs().last()?, acc)?
+        }
+        ast::Expr::ExprMax(ast::ExprMax::ParenExpr(paren)) if as_tuple(&expr).is_none() => {
+            collect_tail_exprs(paren.expr()?, acc)?
+        }
+        _ => acc.push(expr),
+    }
+    Some(())
+}
+
+/// Derive field names from the variables in the accumulator pattern,
+/// e.g. `{TotalCount, _Max, 0}` gives `total_count`, `max`, `field3`.
+/// Reserved words are quoted, e.g. `When` gives `'when'`.
+fn field_names(pattern: &ast::Tuple) -> Vec<String> {
+    let mut seen = FxHashSet::default();
+    pattern
+        .expr()
+        .enumerate()
+        .map(|(i, expr)| {
+            let name = match &expr {
+                ast::Expr::ExprMax(ast::ExprMax::Var(var)) => snake_case(var.text().as_str()),
+                _ => String::new(),
+            };
+            if name.is_empty() || !seen.insert(name.clone()) {
+                format!("field{}", i + 1)
+            } else if RESERVED_WORDS.contains(&name.as_str()) {
+                format!("'{}'", name)
+            } else {
+                name
+            }
+        })
+        .collect()
+}
+
+fn snake_case(var: &str) -> String {
+    let mut res = String::new();
+    for c in var.trim_start_matches('_').chars() {
+        if c.is_uppercase() {
+            if !res.is_empty() && !res.ends_with('_') {
+                res.push('_');
+            }
+            res.extend(c.to_lowercase());
+        } else {
+            res.push(c);
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn to_record_destructured() {
+        check_assist_with_user_input(
+            convert_foldl_acc_to_record,
+            "Convert accumulator tuple to record #acc{sum, count, max_so_far}",
+            "stats",
+            r#"
+-module(main).
+f(L) ->
+    {S, C, _} = lists:fo~ldl(fun(X, {Sum, Count, MaxSoFar}) ->
+        {Sum + X, Count + 1, max(X, MaxSoFar)}
+    end, {0, 0, 0}, L),
+    S / C.
+"#,
+            expect![[r#"
+                -module(main).
+                -record(stats, {sum, count, max_so_far}).
+
+                f(L) ->
+                    #stats{sum = S, count = C} = lists:foldl(fun(X, #stats{sum = Sum, count = Count, max_so_far = MaxSoFar}) ->
+                        #stats{sum = Sum + X, count = Count + 1, max_so_far = max(X, MaxSoFar)}
+                    end, #stats{sum = 0, count = 0, max_so_far = 0}, L),
+                    S / C.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn to_record_case_tails() {
+        check_assist_with_user_input(
+            convert_foldl_acc_to_record,
+            "Convert accumulator tuple to record #acc{evens, odds, total}",
+            "acc",
+            r#"
+-module(main).
+f(L) ->
+    {E, O, T} = lists:fold~l(fun(X, {Evens, Odds, Total}) ->
+        case X rem 2 of
+            0 -> {[X | Evens], Odds, Total + X};
+            _ -> {Evens, [X | Odds], Total + X}
+        end
+    end, {[], [], 0}, L),
+    {E, O, T}.
+"#,
+            expect![[r#"
+                -module(main).
+                -record(acc, {evens, odds, total}).
+
+                f(L) ->
+                    #acc{evens = E, odds = O, total = T} = lists:foldl(fun(X, #acc{evens = Evens, odds = Odds, total = Total}) ->
+                        case X rem 2 of
+                            0 -> #acc{evens = [X | Evens], odds = Odds, total = Total + X};
+                            _ -> #acc{evens = Evens, odds = [X | Odds], total = Total + X}
+                        end
+                    end, #acc{evens = [], odds = [], total = 0}, L),
+                    {E, O, T}.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn to_record_element_access() {
+        check_assist_with_user_input(
+            convert_foldl_acc_to_record,
+            "Convert accumulator tuple to record #acc{field1, field2, field3}",
+            "acc",
+            r#"
+-module(main).
+f(L) ->
+    {Sum, _, Max} = lists:fol~dl(fun(X, Acc) ->
+        case X > element(3, Acc) of
+            true -> setelement(3, setelement(1, Acc, element(1, Acc) + X), X);
+            false -> Acc
+        end
+    end, {0, 0, 0}, L),
+    Sum + Max.
+"#,
+            expect![[r#"
+                -module(main).
+                -record(acc, {field1, field2, field3}).
+
+                f(L) ->
+                    #acc{field1 = Sum, field3 = Max} = lists:foldl(fun(X, Acc) ->
+                        case X > Acc#acc.field3 of
+                            true -> Acc#acc{field1 = Acc#acc.field1 + X}#acc{field3 = X};
+                            false -> Acc
+                        end
+                    end, #acc{field1 = 0, field2 = 0, field3 = 0}, L),
+                    Sum + Max.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn to_record_after_spec_and_comments() {
+        check_assist_with_user_input(
+            convert_foldl_acc_to_record,
+            "Convert accumulator tuple to record #acc{sum, count, max}",
+            "acc",
+            r#"
+-module(main).
+
+%% Sums the list.
+-spec f([integer()]) -> integer().
+f(L) ->
+    {S, _, _} = lists:fo~ldl(fun(X, {Sum, Count, Max}) ->
+        {Sum + X, Count + 1, max(X, Max)}
+    end, {0, 0, 0}, L),
+    S.
+"#,
+            expect![[r#"
+                -module(main).
+
+                -record(acc, {sum, count, max}).
+
+                %% Sums the list.
+                -spec f([integer()]) -> integer().
+                f(L) ->
+                    #acc{sum = S} = lists:foldl(fun(X, #acc{sum = Sum, count = Count, max = Max}) ->
+                        #acc{sum = Sum + X, count = Count + 1, max = max(X, Max)}
+                    end, #acc{sum = 0, count = 0, max = 0}, L),
+                    S.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn to_record_reserved_words() {
+        check_assist_with_user_input(
+            convert_foldl_acc_to_record,
+            "Convert accumulator tuple to record #acc{'when', 'end', count}",
+            "acc",
+            r#"
+-module(main).
+f(L) ->
+    {W, E, _} = lists:fo~ldl(fun(X, {When, End, Count}) ->
+        {min(X, When), max(X, End), Count + 1}
+    end, {0, 0, 0}, L),
+    {W, E}.
+"#,
+            expect![[r#"
+                -module(main).
+                -record(acc, {'when', 'end', count}).
+
+                f(L) ->
+                    #acc{'when' = W, 'end' = E} = lists:foldl(fun(X, #acc{'when' = When, 'end' = End, count = Count}) ->
+                        #acc{'when' = min(X, When), 'end' = max(X, End), count = Count + 1}
+                    end, #acc{'when' = 0, 'end' = 0, count = 0}, L),
+                    {W, E}.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn to_record_user_name_clash() {
+        check_assist_with_user_input(
+            convert_foldl_acc_to_record,
+            "Convert accumulator tuple to record #acc{sum, count, max}",
+            "stats",
+            r#"
+-module(main).
+-record(stats, {n}).
+f(L) ->
+    {S, _, _} = lists:fo~ldl(fun(X, {Sum, Count, Max}) ->
+        {Sum + X, Count + 1, max(X, Max)}
+    end, {0, 0, 0}, L),
+    S.
+"#,
+            expect![[r#"
+                -module(main).
+                -record(stats, {n}).
+                -record(stats1, {sum, count, max}).
+
+                f(L) ->
+                    #stats1{sum = S} = lists:foldl(fun(X, #stats1{sum = Sum, count = Count, max = Max}) ->
+                        #stats1{sum = Sum + X, count = Count + 1, max = max(X, Max)}
+                    end, #stats1{sum = 0, count = 0, max = 0}, L),
+                    S.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn to_map() {
+        check_assist(
+            convert_foldl_acc_to_map,
+            "Convert accumulator tuple to map #{sum, count, max}",
+            r#"
+-module(main).
+f(L) ->
+    {S, _, _} = lists:fo~ldl(fun(X, {Sum, Count, Max}) ->
+        {Sum + X, Count + 1, max(X, Max)}
+    end, {0, 0, 0}, L),
+    S.
+"#,
+            expect![[r#"
+                -module(main).
+                f(L) ->
+                    #{sum := S} = lists:foldl(fun(X, #{sum := Sum, count := Count, max := Max}) ->
+                        #{sum => Sum + X, count => Count + 1, max => max(X, Max)}
+                    end, #{sum => 0, count => 0, max => 0}, L),
+                    S.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn not_applicable_small_tuple() {
+        check_assist_not_applicable(
+            convert_foldl_acc_to_record,
+            r#"
+-module(main).
+f(L) ->
+    {S, _} = lists:fo~ldl(fun(X, {Sum, Count}) -> {Sum + X, Count + 1} end, {0, 0}, L),
+    S.
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_acc_escapes() {
+        check_assist_not_applicable(
+            convert_foldl_acc_to_record,
+            r#"
+-module(main).
+f(L) ->
+    {A, _, _} = lists:fo~ldl(fun(X, Acc) -> g(X, Acc) end, {0, 0, 0}, L),
+    A.
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_unknown_tail() {
+        check_assist_not_applicable(
+            convert_foldl_acc_to_map,
+            r#"
+-module(main).
+f(L) ->
+    {A, _, _} = lists:fo~ldl(fun(X, {A, B, C}) -> g(X, A, B, C) end, {0, 0, 0}, L),
+    A.
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_result_escapes() {
+        check_assist_not_applicable(
+            convert_foldl_acc_to_record,
+            r#"
+-module(main).
+f(L) ->
+    lists:fo~ldl(fun(X, {Sum, Count, Max}) ->
+        {Sum + X, Count + 1, max(X, Max)}
+    end, {0, 0, 0}, L).
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_result_match_is_returned() {
+        check_assist_not_applicable(
+            convert_foldl_acc_to_map,
+            r#"
+-module(main).
+f(L) ->
+    {_, _, _} = lists:fo~ldl(fun(X, {Sum, Count, Max}) ->
+        {Sum + X, Count + 1, max(X, Max)}
+    end, {0, 0, 0}, L).
+"#,
+        );
+    }
+}
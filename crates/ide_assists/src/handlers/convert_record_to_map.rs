@@ -0,0 +1,685 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::cmp::Reverse;
+
+use elp_ide_db::assists::AssistId;
+use elp_ide_db::assists::AssistKind;
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::elp_base_db::SourceDatabase;
+use elp_ide_db::search::NameLike;
+use elp_ide_db::SymbolDefinition;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use elp_syntax::SyntaxKind;
+use elp_syntax::SyntaxNode;
+use elp_syntax::TextRange;
+use elp_syntax::TextSize;
+use fxhash::FxHashMap;
+use hir::InFile;
+use hir::NameArity;
+use hir::RecordDef;
+use hir::Semantic;
+
+use crate::helpers::find_next_token;
+use crate::AssistContext;
+use crate::Assists;
+
+// Assist: convert_record_to_map
+//
+// Converts a record and all its usages in the project to a map with atom
+// keys: constructions, updates, field accesses and patterns, and the
+// record types of specs. The definition becomes a map type.
+//
+// ```
+// -record(~point, {x = 0 :: integer(), y :: integer()}).
+// origin() -> #point{y = 0}.
+// x(P) -> P#point.x.
+// ```
+// ->
+// ```
+// -type point() :: #{x := integer(), y := integer()}.
+// origin() -> #{y => 0, x => 0}.
+// x(P) -> maps:get(x, P).
+// ```
+//
+// Usages which can't be converted, e.g. `#point.x` or `is_record/2`,
+// disable the assist, their locations being given as the reason.
+pub(crate) fn convert_record_to_map(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let decl = ctx.find_node_at_offset::<ast::RecordDecl>()?;
+    let name = decl.name()?;
+    if !name.syntax().text_range().contains_inclusive(ctx.offset()) {
+        return None;
+    }
+    let def = ctx.sema.to_def(InFile::new(ctx.file_id(), &decl))?;
+    let record_name = def.record.name.to_quoted_string();
+    let conversion = Conversion::new(&ctx.sema, &def, &decl);
+    let target = decl.syntax().text_range();
+
+    if !conversion.conflicts.is_empty() {
+        let conflicts = conversion
+            .conflicts
+            .iter()
+            .map(|conflict| conflict.render(&ctx.sema))
+            .collect::<Vec<_>>()
+            .join(", ");
+        return acc.add_disabled(
+            AssistId("convert_record_to_map", AssistKind::RefactorRewrite),
+            format!("Convert record `{record_name}` to map"),
+            target,
+            format!("Cannot convert: {conflicts}"),
+        );
+    }
+
+    acc.add(
+        AssistId("convert_record_to_map", AssistKind::RefactorRewrite),
+        format!("Convert record `{record_name}` to map"),
+        target,
+        None,
+        move |builder| {
+            let mut files: Vec<_> = conversion.edits.into_iter().collect();
+            files.sort_by_key(|(file_id, _)| *file_id);
+            for (file_id, mut edits) in files {
+                builder.edit_file(file_id);
+                // Edits inserting at the same offset are applied in
+                // order, the outermost usage first
+                edits.sort_by_key(|edit| {
+                    (edit.range.start(), edit.range.end(), Reverse(edit.outer))
+                });
+                for edit in edits {
+                    builder.replace(edit.range, edit.text);
+                }
+            }
+        },
+    )
+}
+
+struct Edit {
+    range: TextRange,
+    text: String,
+    /// The length of the usage the edit belongs to
+    outer: TextSize,
+}
+
+struct Conflict {
+    file_id: FileId,
+    offset: TextSize,
+    reason: &'static str,
+}
+
+impl Conflict {
+    fn render(&self, sema: &Semantic) -> String {
+        let text = sema.db.file_text(self.file_id);
+        let line = text[..usize::from(self.offset)].matches('\n').count() + 1;
+        let source_root = sema.db.source_root(sema.db.file_source_root(self.file_id));
+        let file = source_root
+            .path_for_file(&self.file_id)
+            .and_then(|path| path.name_and_extension())
+            .map(|(name, ext)| match ext {
+                Some(ext) => format!("{name}.{ext}"),
+                None => name.to_string(),
+            })
+            .unwrap_or_default();
+        format!("{} at {}:{}", self.reason, file, line)
+    }
+}
+
+/// A field of the record definition
+struct Field {
+    name: String,
+    default: Option<String>,
+    /// Why the default value can't be copied to another file, as it
+    /// depends on the definitions visible where the record is defined
+    default_conflict: Option<&'static str>,
+    ty: Option<String>,
+}
+
+/// Where a record usage is
+enum Context {
+    Expr,
+    Pat,
+    Type,
+}
+
+#[derive(Default)]
+struct Conversion {
+    edits: FxHashMap<FileId, Vec<Edit>>,
+    conflicts: Vec<Conflict>,
+}
+
+impl Conversion {
+    fn new(sema: &Semantic, def: &RecordDef, decl: &ast::RecordDecl) -> Conversion {
+        let mut conversion = Conversion::default();
+        let file_id = def.file.file_id;
+        let decl_range = decl.syntax().text_range();
+        let type_name = def.record.name.to_quoted_string();
+
+        let mut fields = Vec::new();
+        for field in decl.fields() {
+            match field.name().as_ref().and_then(atom_text) {
+                Some(name) => fields.push(Field {
+                    name,
+                    default: field
+                        .expr()
+                        .and_then(|expr| expr.expr())
+                        .map(|expr| expr.syntax().text().to_string()),
+                    default_conflict: field
+                        .expr()
+                        .and_then(|expr| expr.expr())
+                        .and_then(|expr| default_conflict(expr.syntax())),
+                    ty: field
+                        .ty()
+                        .and_then(|ty| ty.expr())
+                        .map(|ty| ty.syntax().text().to_string()),
+                }),
+                None => conversion.conflict(file_id, field.syntax(), "field given by a macro"),
+            }
+        }
+        let type_arity = NameArity::new(def.record.name.clone(), 0);
+        if sema.def_map(file_id).get_type(&type_arity).is_some() {
+            conversion.conflict(file_id, decl.syntax(), "type already defined");
+        }
+        conversion.add_edit(
+            file_id,
+            decl_range,
+            render_type(&type_name, &fields),
+            decl_range.len(),
+        );
+
+        let usages = SymbolDefinition::Record(def.clone()).usages(sema).all();
+        let mut files = vec![file_id];
+        for (usage_file_id, names) in usages.iter() {
+            if !files.contains(&usage_file_id) {
+                files.push(usage_file_id);
+            }
+            for name in names {
+                let name = match name {
+                    NameLike::Name(name) => name,
+                    NameLike::String(_) => continue,
+                };
+                if usage_file_id == file_id && decl_range.contains_range(name.syntax().text_range())
+                {
+                    conversion.conflict(file_id, name.syntax(), "usage in its own definition");
+                    continue;
+                }
+                // A usage with syntax errors can't be converted either
+                let conflicts = conversion.conflicts.len();
+                if conversion
+                    .convert_usage(sema, file_id, usage_file_id, name, &type_name, &fields)
+                    .is_none()
+                    && conversion.conflicts.len() == conflicts
+                {
+                    conversion.conflict(usage_file_id, name.syntax(), "unsupported usage");
+                }
+            }
+        }
+        for file_id in files {
+            conversion.find_record_checks(sema, file_id, &def.record.name.to_quoted_string());
+        }
+        conversion
+    }
+
+    fn add_edit(&mut self, file_id: FileId, range: TextRange, text: String, outer: TextSize) {
+        self.edits
+            .entry(file_id)
+            .or_default()
+            .push(Edit { range, text, outer });
+    }
+
+    fn conflict(&mut self, file_id: FileId, syntax: &SyntaxNode, reason: &'static str) {
+        self.conflicts.push(Conflict {
+            file_id,
+            offset: syntax.text_range().start(),
+            reason,
+        });
+    }
+
+    fn convert_usage(
+        &mut self,
+        sema: &Semantic,
+        def_file_id: FileId,
+        file_id: FileId,
+        name: &ast::Name,
+        type_name: &str,
+        fields: &[Field],
+    ) -> Option<()> {
+        let usage = name
+            .syntax()
+            .parent()
+            .filter(|parent| parent.kind() == SyntaxKind::RECORD_NAME)?
+            .parent()?;
+        let expr = match ast::Expr::cast(usage.clone()) {
+            Some(expr) => expr,
+            None => {
+                self.conflict(file_id, &usage, "unsupported usage");
+                return None;
+            }
+        };
+        let context = match usage_context(sema, file_id, &expr) {
+            Some(context) => context,
+            None => {
+                self.conflict(file_id, &usage, "usage in a macro");
+                return None;
+            }
+        };
+        let outer = usage.text_range().len();
+        match (expr, context) {
+            (ast::Expr::RecordExpr(record), Context::Type) => {
+                if record.fields().next().is_some() {
+                    self.conflict(file_id, &usage, "record type with fields");
+                } else {
+                    self.add_edit(file_id, usage.text_range(), format!("{type_name}()"), outer);
+                }
+            }
+            (ast::Expr::RecordExpr(record), context) => {
+                let is_pat = matches!(context, Context::Pat);
+                let fields_set = self.convert_fields(file_id, &usage, record.fields(), is_pat)?;
+                self.replace_opening(file_id, &usage, usage.text_range().start())?;
+                if !is_pat {
+                    self.add_omitted_fields(def_file_id, file_id, &usage, fields, &fields_set)?;
+                }
+            }
+            (ast::Expr::RecordUpdateExpr(update), Context::Expr) => {
+                self.convert_fields(file_id, &usage, update.fields(), true)?;
+                let base = update.expr()?.syntax().text_range();
+                self.replace_opening(file_id, &usage, base.end())?;
+            }
+            (ast::Expr::RecordFieldExpr(access), Context::Expr) => {
+                let field = match access.field()?.name().as_ref().and_then(atom_text) {
+                    Some(field) => field,
+                    None => {
+                        self.conflict(file_id, &usage, "field given by a macro");
+                        return None;
+                    }
+                };
+                let base = access.expr()?.syntax().text_range();
+                let in_guard = usage
+                    .ancestors()
+                    .any(|node| node.kind() == SyntaxKind::GUARD);
+                // `maps:get/2` is not allowed in guards
+                let get = if in_guard { "map_get" } else { "maps:get" };
+                self.add_edit(
+                    file_id,
+                    TextRange::empty(base.start()),
+                    format!("{get}({field}, "),
+                    outer,
+                );
+                let pound = pound_token(&usage, base.end())?;
+                self.add_edit(
+                    file_id,
+                    TextRange::new(pound.start(), usage.text_range().end()),
+                    ")".to_string(),
+                    outer,
+                );
+            }
+            (ast::Expr::RecordIndexExpr(_), _) => {
+                self.conflict(file_id, &usage, "record index");
+            }
+            _ => {
+                self.conflict(file_id, &usage, "unsupported usage");
+            }
+        }
+        Some(())
+    }
+
+    /// Replaces the `=` of the fields by `=>`, or by `:=` for the fields
+    /// which must already be in the map. Returns the fields given.
+    fn convert_fields(
+        &mut self,
+        file_id: FileId,
+        usage: &SyntaxNode,
+        fields: impl Iterator<Item = ast::RecordField>,
+        exact: bool,
+    ) -> Option<Vec<String>> {
+        let outer = usage.text_range().len();
+        let mut names = Vec::new();
+        for field in fields {
+            match field.name()? {
+                ast::Name::Atom(atom) => names.push(atom.syntax().text().to_string()),
+                ast::Name::Var(_) => {
+                    self.conflict(file_id, field.syntax(), "wildcard field");
+                    return None;
+                }
+                ast::Name::MacroCallExpr(_) => {
+                    self.conflict(file_id, field.syntax(), "field given by a macro");
+                    return None;
+                }
+            }
+            let eq = field
+                .syntax()
+                .descendants_with_tokens()
+                .filter_map(|element| element.into_token())
+                .find(|token| token.kind() == SyntaxKind::ANON_EQ)?;
+            let op = if exact { ":=" } else { "=>" };
+            self.add_edit(file_id, eq.text_range(), op.to_string(), outer);
+        }
+        Some(names)
+    }
+
+    /// Replaces `#name{` by `#{`
+    fn replace_opening(
+        &mut self,
+        file_id: FileId,
+        usage: &SyntaxNode,
+        after: TextSize,
+    ) -> Option<()> {
+        let pound = pound_token(usage, after)?;
+        let lbrace = find_next_token(usage, SyntaxKind::ANON_LBRACE)?;
+        self.add_edit(
+            file_id,
+            TextRange::new(pound.start(), lbrace.end()),
+            "#{".to_string(),
+            usage.text_range().len(),
+        );
+        Some(())
+    }
+
+    /// A construction of the map has all the keys, with the default
+    /// values of the record.
+    fn add_omitted_fields(
+        &mut self,
+        def_file_id: FileId,
+        file_id: FileId,
+        usage: &SyntaxNode,
+        fields: &[Field],
+        given: &[String],
+    ) -> Option<()> {
+        let omitted: Vec<&Field> = fields
+            .iter()
+            .filter(|field| !given.contains(&field.name))
+            .collect();
+        if file_id != def_file_id {
+            if let Some(reason) = omitted.iter().find_map(|field| field.default_conflict) {
+                self.conflict(file_id, usage, reason);
+                return None;
+            }
+        }
+        let omitted: Vec<String> = omitted
+            .iter()
+            .map(|field| {
+                let default = field.default.as_deref().unwrap_or("undefined");
+                format!("{} => {}", field.name, default)
+            })
+            .collect();
+        if omitted.is_empty() {
+            return Some(());
+        }
+        let omitted = omitted.join(", ");
+        let last_field = usage
+            .children()
+            .filter(|node| node.kind() == SyntaxKind::RECORD_FIELD)
+            .last();
+        let (offset, text) = match last_field {
+            Some(field) => (field.text_range().end(), format!(", {omitted}")),
+            None => (
+                find_next_token(usage, SyntaxKind::ANON_LBRACE)?.end(),
+                omitted,
+            ),
+        };
+        self.add_edit(
+            file_id,
+            TextRange::empty(offset),
+            text,
+            usage.text_range().len(),
+        );
+        Some(())
+    }
+
+    /// Calls to `is_record` or `record_info` about the record, which
+    /// would no longer hold.
+    fn find_record_checks(&mut self, sema: &Semantic, file_id: FileId, record_name: &str) {
+        let source = sema.parse(file_id).value;
+        for call in source.syntax().descendants().filter_map(ast::Call::cast) {
+            let fun = match call.expr() {
+                Some(fun) => fun.syntax().text().to_string(),
+                None => continue,
+            };
+            let reason = match fun.as_str() {
+                "is_record" | "erlang:is_record" => "`is_record` check",
+                "record_info" => "`record_info` call",
+                _ => continue,
+            };
+            let uses_record = call.args().map_or(false, |args| {
+                args.args().any(|arg| arg.syntax().text() == record_name)
+            });
+            if uses_record {
+                self.conflict(file_id, call.syntax(), reason);
+            }
+        }
+    }
+}
+
+/// Why a default value can't be copied out of the file defining the
+/// record: its macros and records may not be defined in the other files.
+fn default_conflict(default: &SyntaxNode) -> Option<&'static str> {
+    default.descendants().find_map(|node| match node.kind() {
+        SyntaxKind::MACRO_CALL_EXPR => Some("default value using a macro"),
+        SyntaxKind::RECORD_EXPR
+        | SyntaxKind::RECORD_FIELD_EXPR
+        | SyntaxKind::RECORD_INDEX_EXPR
+        | SyntaxKind::RECORD_UPDATE_EXPR => Some("default value using a record"),
+        _ => None,
+    })
+}
+
+/// Whether a record usage is an expression, a pattern or a type. `None`
+/// for usages in macros.
+fn usage_context(sema: &Semantic, file_id: FileId, expr: &ast::Expr) -> Option<Context> {
+    let form = expr.syntax().ancestors().find_map(ast::Form::cast)?;
+    if matches!(form, ast::Form::PreprocessorDirective(_)) {
+        return None;
+    }
+    let (_body, source_map) = sema.find_body(file_id, expr.syntax())?;
+    let expr = InFile::new(file_id, expr);
+    if source_map.pat_id(expr).is_some() {
+        Some(Context::Pat)
+    } else if source_map.expr_id(expr).is_some() {
+        Some(Context::Expr)
+    } else if source_map.type_expr_id(expr).is_some() {
+        Some(Context::Type)
+    } else {
+        None
+    }
+}
+
+/// The `#` of the record usage, after its base expression if any
+fn pound_token(usage: &SyntaxNode, after: TextSize) -> Option<TextRange> {
+    usage
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .find(|token| token.kind() == SyntaxKind::ANON_POUND && token.text_range().start() >= after)
+        .map(|token| token.text_range())
+}
+
+fn atom_text(name: &ast::Name) -> Option<String> {
+    match name {
+        ast::Name::Atom(atom) => Some(atom.syntax().text().to_string()),
+        _ => None,
+    }
+}
+
+fn render_type(type_name: &str, fields: &[Field]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            let ty = field.ty.as_deref().unwrap_or("term()");
+            format!("{} := {}", field.name, ty)
+        })
+        .collect();
+    let single_line = format!("-type {type_name}() :: #{{{}}}.", fields.join(", "));
+    if single_line.len() <= 80 {
+        return single_line;
+    }
+    let lines = fields
+        .iter()
+        .map(|field| format!("    {field}"))
+        .collect::<Vec<_>>()
+        .join(",\n");
+    format!("-type {type_name}() :: #{{\n{lines}\n}}.")
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn converts_usages() {
+        check_assist(
+            convert_record_to_map,
+            "Convert record `point` to map",
+            r#"
+-module(main).
+-record(po~int, {x = 0 :: integer(), y :: integer(), label}).
+-spec origin() -> #point{}.
+origin() -> #point{y = 0}.
+move(#point{x = X} = P, Dx) -> P#point{x = X + Dx}.
+x(P) when P#point.x > 0 -> P#point.x.
+"#,
+            expect![[r#"
+                -module(main).
+                -type point() :: #{x := integer(), y := integer(), label := term()}.
+                -spec origin() -> point().
+                origin() -> #{y => 0, x => 0, label => undefined}.
+                move(#{x := X} = P, Dx) -> P#{x := X + Dx}.
+                x(P) when map_get(x, P) > 0 -> maps:get(x, P).
+            "#]],
+        )
+    }
+
+    #[test]
+    fn converts_nested_usages() {
+        check_assist(
+            convert_record_to_map,
+            "Convert record `cell` to map",
+            r#"
+-module(main).
+-record(ce~ll, {value, next}).
+f() -> #cell{next = #cell{}}.
+g(N) -> N#cell.next#cell.value.
+"#,
+            expect![[r#"
+                -module(main).
+                -type cell() :: #{value := term(), next := term()}.
+                f() -> #{next => #{value => undefined, next => undefined}, value => undefined}.
+                g(N) -> maps:get(value, maps:get(next, N)).
+            "#]],
+        )
+    }
+
+    #[test]
+    fn converts_usages_in_other_files() {
+        check_assist(
+            convert_record_to_map,
+            "Convert record `person` to map",
+            r#"
+//- /src/person.hrl
+-record(per~son, {name}).
+//- /src/main.erl
+-module(main).
+-include("person.hrl").
+name(#person{name = Name}) -> Name.
+"#,
+            expect![[r#"
+                //- /src/person.hrl
+                -type person() :: #{name := term()}.
+                //- /src/main.erl
+                -module(main).
+                -include("person.hrl").
+                name(#{name := Name}) -> Name.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn reports_conflicts() {
+        check_assist_disabled(
+            convert_record_to_map,
+            r#"
+//- /src/main.erl
+-module(main).
+-record(po~int, {x, y}).
+x() -> #point.x.
+is_point(P) -> is_record(P, point).
+"#,
+            expect![[
+                "Convert record `point` to map: Cannot convert: record index at main.erl:3, `is_record` check at main.erl:4"
+            ]],
+        )
+    }
+
+    #[test]
+    fn reports_usages_in_macros() {
+        check_assist_disabled(
+            convert_record_to_map,
+            r#"
+//- /src/main.erl
+-module(main).
+-record(po~int, {x, y}).
+-define(ORIGIN, #point{x = 0, y = 0}).
+origin() -> ?ORIGIN.
+"#,
+            expect![[
+                "Convert record `point` to map: Cannot convert: usage in a macro at main.erl:3"
+            ]],
+        )
+    }
+
+    #[test]
+    fn reports_defaults_using_macros_in_other_files() {
+        check_assist_disabled(
+            convert_record_to_map,
+            r#"
+//- /src/person.hrl
+-define(UNKNOWN, <<"unknown">>).
+-record(per~son, {name = ?UNKNOWN, age}).
+//- /src/main.erl
+-module(main).
+-include("person.hrl").
+new(Age) -> #person{age = Age}.
+"#,
+            expect![[
+                "Convert record `person` to map: Cannot convert: default value using a macro at main.erl:3"
+            ]],
+        )
+    }
+
+    #[test]
+    fn converts_defaults_using_macros_in_the_same_file() {
+        check_assist(
+            convert_record_to_map,
+            "Convert record `person` to map",
+            r#"
+-module(main).
+-define(UNKNOWN, <<"unknown">>).
+-record(per~son, {name = ?UNKNOWN, age}).
+new(Age) -> #person{age = Age}.
+"#,
+            expect![[r#"
+                -module(main).
+                -define(UNKNOWN, <<"unknown">>).
+                -type person() :: #{name := term(), age := term()}.
+                new(Age) -> #{age => Age, name => ?UNKNOWN}.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn not_applicable_outside_name() {
+        check_assist_not_applicable(
+            convert_record_to_map,
+            r#"
+-module(main).
+-record(point, {x~, y}).
+"#,
+        );
+    }
+}
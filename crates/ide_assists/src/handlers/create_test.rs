@@ -0,0 +1,316 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use elp_ide_db::assists::AssistId;
+use elp_ide_db::assists::AssistKind;
+use elp_ide_db::elp_base_db::AnchoredPathBuf;
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::source_change::SourceChangeBuilder;
+use elp_ide_db::SymbolClass;
+use elp_ide_db::SymbolDefinition;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use elp_syntax::TextSize;
+use hir::known;
+use hir::Name;
+use hir::NameArity;
+
+use crate::helpers;
+use crate::AssistContext;
+use crate::Assists;
+
+const SUITE_SUFFIX: &str = "_SUITE";
+
+// Assist: create_test
+//
+// Create a Common Test testcase calling an exported function, in the
+// test suite of its module, creating the suite in the `test` directory
+// of the application if needed
+//
+// ```
+// -module(main).
+// -export([foo/1]).
+// foo(X) -> X.
+// ```
+// ->
+// ```
+// -module(main_SUITE).
+//
+// -export([all/0, foo_test/1]).
+//
+// all() ->
+//     [foo_test].
+//
+// foo_test(_Config) ->
+//     main:foo(undefined).
+// ```
+pub(crate) fn create_test(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let fun = match ctx.classify_offset()? {
+        SymbolClass::Definition(SymbolDefinition::Function(fun)) => fun,
+        _ => return None,
+    };
+    if !fun.exported {
+        return None;
+    }
+    let module = ctx.sema.module_name(ctx.file_id())?;
+    if module.ends_with(SUITE_SUFFIX) {
+        return None;
+    }
+    let suite = format!("{}{SUITE_SUFFIX}", module.as_str());
+    let name_arity = &fun.function.name;
+    let call = format!(
+        "{}:{}({})",
+        module.to_quoted_string(),
+        name_arity.name().to_quoted_string(),
+        vec!["undefined"; name_arity.arity() as usize].join(", ")
+    );
+    let function_range = ctx.form_ast(fun.function.form_id).syntax().text_range();
+    let id = AssistId("create_test", AssistKind::Generate);
+    let message = format!("Create a test for `{name_arity}` in `{suite}`");
+
+    match ctx.sema.resolve_module_name(ctx.file_id(), &suite) {
+        Some(suite_module) => {
+            let suite_file_id = suite_module.file.file_id;
+            let case = unused_case_name(ctx, suite_file_id, name_arity.name());
+            acc.add(id, message, function_range, None, |builder| {
+                add_to_suite(ctx, suite_file_id, &case, &call, builder)
+            })
+        }
+        None => {
+            let case = case_name(name_arity.name(), 1);
+            let dst = AnchoredPathBuf {
+                anchor: ctx.file_id(),
+                path: suite_path(ctx, &suite),
+            };
+            let case = case.to_quoted_string();
+            let contents = format!(
+                "-module({suite}).\n\n\
+                 -export([all/0, {case}/1]).\n\n\
+                 all() ->\n    [{case}].\n\n\
+                 {case}(_Config) ->\n    {call}.\n"
+            );
+            acc.add(id, message, function_range, None, |builder| {
+                builder.create_file(dst, contents)
+            })
+        }
+    }
+}
+
+/// `<function>_test`, followed by `n` if not 1.
+fn case_name(function: &Name, n: usize) -> Name {
+    if n == 1 {
+        Name::from_erlang_service(&format!("{}_test", function.as_str()))
+    } else {
+        Name::from_erlang_service(&format!("{}_test{n}", function.as_str()))
+    }
+}
+
+fn unused_case_name(ctx: &AssistContext, suite_file_id: FileId, function: &Name) -> Name {
+    let def_map = ctx.sema.def_map(suite_file_id);
+    (1..)
+        .map(|n| case_name(function, n))
+        .find(|case| {
+            def_map
+                .get_function(&NameArity::new(case.clone(), 1))
+                .is_none()
+        })
+        .unwrap()
+}
+
+/// The path of the suite in the `test` directory of the application,
+/// relative to the directory of the file of the function.
+fn suite_path(ctx: &AssistContext, suite: &str) -> String {
+    let db = ctx.sema.db;
+    let source_root_id = db.file_source_root(ctx.file_id());
+    let depth = || -> Option<usize> {
+        let app_data = db.app_data(source_root_id)?;
+        let source_root = db.source_root(source_root_id);
+        let path = source_root.path_for_file(&ctx.file_id())?;
+        let relative = path.as_path()?.strip_prefix(&app_data.dir)?;
+        Some(relative.as_ref().components().count() - 1)
+    }()
+    .unwrap_or(1);
+    format!("{}test/{suite}.erl", "../".repeat(depth))
+}
+
+fn add_to_suite(
+    ctx: &AssistContext,
+    suite_file_id: FileId,
+    case: &Name,
+    call: &str,
+    builder: &mut SourceChangeBuilder,
+) {
+    let case_arity = [NameArity::new(case.clone(), 1)];
+    helpers::ExportBuilder::new(&ctx.sema, suite_file_id, &case_arity, builder).finish();
+
+    let case = case.to_quoted_string();
+    let source = ctx.sema.parse(suite_file_id).value;
+    let all = ctx
+        .sema
+        .def_map(suite_file_id)
+        .get_function(&NameArity::new(known::all, 0))
+        .and_then(|all| all_list(&all.source(ctx.db().upcast())));
+    if let Some(list) = all {
+        match list.exprs().last() {
+            Some(last) => builder.insert(last.syntax().text_range().end(), format!(", {case}")),
+            None => builder.insert(
+                list.syntax().text_range().start() + TextSize::from(1),
+                case.clone(),
+            ),
+        }
+    }
+
+    let end = source.syntax().text_range().end();
+    let separator = if source.syntax().text().to_string().ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    builder.insert(end, format!("{separator}{case}(_Config) ->\n    {call}.\n"));
+}
+
+/// The list returned by `all/0`, when a literal.
+fn all_list(all: &ast::FunDecl) -> Option<ast::List> {
+    let clause = match all.clauses().next()? {
+        ast::FunctionOrMacroClause::FunctionClause(clause) => clause,
+        ast::FunctionOrMacroClause::MacroCallExpr(_) => return None,
+    };
+    match clause.body()?.exprs().last()? {
+        ast::Expr::List(list) => Some(list),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn create_suite() {
+        check_assist(
+            create_test,
+            "Create a test for `foo/2` in `main_SUITE`",
+            r#"
+//- /my_app/src/main.erl
+-module(main).
+-export([foo/2]).
+f~oo(X, Y) -> {X, Y}.
+"#,
+            expect![[r#"
+                //- /my_app/test/main_SUITE.erl
+                -module(main_SUITE).
+
+                -export([all/0, foo_test/1]).
+
+                all() ->
+                    [foo_test].
+
+                foo_test(_Config) ->
+                    main:foo(undefined, undefined).
+            "#]],
+        )
+    }
+
+    #[test]
+    fn update_suite() {
+        check_assist(
+            create_test,
+            "Create a test for `foo/1` in `main_SUITE`",
+            r#"
+//- /my_app/src/main.erl
+-module(main).
+-export([foo/1]).
+f~oo(X) -> X.
+//- /my_app/test/main_SUITE.erl extra:test
+-module(main_SUITE).
+
+-export([all/0, bar_test/1, foo_test/1]).
+
+all() ->
+    [bar_test, foo_test].
+
+bar_test(_Config) ->
+    ok.
+
+foo_test(_Config) ->
+    ok.
+"#,
+            expect![[r#"
+                -module(main_SUITE).
+
+                -export([all/0, bar_test/1, foo_test/1, foo_test2/1]).
+
+                all() ->
+                    [bar_test, foo_test, foo_test2].
+
+                bar_test(_Config) ->
+                    ok.
+
+                foo_test(_Config) ->
+                    ok.
+
+                foo_test2(_Config) ->
+                    main:foo(undefined).
+            "#]],
+        )
+    }
+
+    #[test]
+    fn update_suite_empty_all() {
+        check_assist(
+            create_test,
+            "Create a test for `foo/0` in `main_SUITE`",
+            r#"
+//- /my_app/src/main.erl
+-module(main).
+-export([foo/0]).
+f~oo() -> ok.
+//- /my_app/test/main_SUITE.erl extra:test
+-module(main_SUITE).
+-export([all/0]).
+all() -> []."#,
+            expect![[r#"
+                -module(main_SUITE).
+                -export([all/0, foo_test/1]).
+                all() -> [foo_test].
+
+                foo_test(_Config) ->
+                    main:foo().
+            "#]],
+        )
+    }
+
+    #[test]
+    fn not_exported() {
+        check_assist_not_applicable(
+            create_test,
+            r#"
+//- /my_app/src/main.erl
+-module(main).
+f~oo() -> ok.
+"#,
+        )
+    }
+
+    #[test]
+    fn in_suite() {
+        check_assist_not_applicable(
+            create_test,
+            r#"
+//- /my_app/test/main_SUITE.erl extra:test
+-module(main_SUITE).
+-export([all/0]).
+a~ll() -> [].
+"#,
+        )
+    }
+}
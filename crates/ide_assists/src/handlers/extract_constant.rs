@@ -0,0 +1,221 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use elp_ide_db::assists::AssistId;
+use elp_ide_db::assists::AssistKind;
+use elp_ide_db::assists::AssistUserInput;
+use elp_ide_db::assists::AssistUserInputType;
+use elp_syntax::AstNode;
+use elp_syntax::SyntaxKind;
+use elp_syntax::SyntaxNode;
+use elp_syntax::TextSize;
+use fxhash::FxHashSet;
+
+use crate::assist_context::AssistContext;
+use crate::assist_context::Assists;
+
+// Assist: extract_constant
+//
+// Replaces a literal, and every identical literal in the module, with a
+// macro defined at the top of the module.
+//
+// ```
+// -module(foo).
+// foo(X) -> X * ~3600.
+// bar() -> 3600.
+// ```
+// ->
+// ```
+// -module(foo).
+//
+// -define(CONSTANT, 3600).
+// foo(X) -> X * ?CONSTANT.
+// bar() -> ?CONSTANT.
+// ```
+pub(crate) fn extract_constant(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let token = ctx.token_at_offset().right_biased()?;
+    let literal = token
+        .parent_ancestors()
+        .take_while(|node| node.kind() != SyntaxKind::FUN_DECL)
+        .find(|node| is_literal(node.kind()))?;
+    // Only literals in function bodies, so that we never rewrite the
+    // right-hand side of an existing `-define`.
+    literal
+        .ancestors()
+        .find(|node| node.kind() == SyntaxKind::FUN_DECL)?;
+
+    let source = ctx.sema.parse(ctx.file_id()).value;
+    let occurrences: Vec<SyntaxNode> = source
+        .syntax()
+        .children()
+        .filter(|form| form.kind() == SyntaxKind::FUN_DECL)
+        .flat_map(|form| form.descendants())
+        .filter(|node| node.kind() == literal.kind() && node.text() == literal.text())
+        .collect();
+
+    let label = match occurrences.len() {
+        1 => "Extract literal into macro (1 occurrence)".to_string(),
+        n => format!("Extract literal into macro ({n} occurrences)"),
+    };
+    let suggested = suggest_macro_name(&literal);
+    acc.add(
+        AssistId("extract_constant", AssistKind::RefactorExtract),
+        label,
+        literal.text_range(),
+        Some(AssistUserInput {
+            input_type: AssistUserInputType::Variable,
+            value: suggested.clone(),
+        }),
+        move |edit| {
+            let name = freshen_macro_name(ctx, ctx.user_input_or(|| suggested));
+            let insert_at = source
+                .syntax()
+                .children()
+                .filter(|form| form.kind() == SyntaxKind::PP_DEFINE)
+                .last()
+                .or_else(|| {
+                    source
+                        .syntax()
+                        .children()
+                        .find(|form| form.kind() == SyntaxKind::MODULE_ATTRIBUTE)
+                })
+                .map(|form| form.text_range().end() + TextSize::from(1))
+                .unwrap_or_else(|| TextSize::from(0));
+            edit.insert(
+                insert_at,
+                format!("\n-define({name}, {}).\n", literal.text()),
+            );
+            for occurrence in &occurrences {
+                edit.replace(occurrence.text_range(), format!("?{name}"));
+            }
+        },
+    )
+}
+
+fn is_literal(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::INTEGER | SyntaxKind::FLOAT | SyntaxKind::STRING | SyntaxKind::CHAR
+    )
+}
+
+/// Derive a macro name from the contents of a string literal, falling
+/// back to a generic name for anything else.
+fn suggest_macro_name(literal: &SyntaxNode) -> String {
+    if literal.kind() == SyntaxKind::STRING {
+        let words: Vec<String> = literal
+            .text()
+            .to_string()
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .take(4)
+            .map(|word| word.to_ascii_uppercase())
+            .collect();
+        if let Some(first) = words.first() {
+            if first.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                return words.join("_");
+            }
+        }
+    }
+    "CONSTANT".to_string()
+}
+
+/// Return either the original name if no macro of that name is visible
+/// in the file, or one with the smallest numeric suffix to be fresh.
+fn freshen_macro_name(ctx: &AssistContext, name: String) -> String {
+    let def_map = ctx.sema.def_map(ctx.file_id());
+    let existing: FxHashSet<&str> = def_map
+        .get_macros()
+        .keys()
+        .map(|macro_name| macro_name.name().as_str())
+        .collect();
+    if !existing.contains(name.as_str()) {
+        return name;
+    }
+    let mut i = 0;
+    loop {
+        let candidate_name = format!("{name}_{i}");
+        if !existing.contains(candidate_name.as_str()) {
+            return candidate_name;
+        }
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+    use crate::tests::check_assist;
+    use crate::tests::check_assist_not_applicable;
+
+    #[test]
+    fn extract_integer_all_occurrences() {
+        check_assist(
+            extract_constant,
+            "Extract literal into macro (3 occurrences)",
+            r#"
+-module(main).
+foo(X) -> X * ~3600.
+bar() -> {3600, 360, [3600]}.
+"#,
+            expect![[r#"
+                -module(main).
+
+                -define(CONSTANTEdited, 3600).
+                foo(X) -> X * ?CONSTANTEdited.
+                bar() -> {?CONSTANTEdited, 360, [?CONSTANTEdited]}.
+            "#]],
+        );
+    }
+
+    #[test]
+    fn extract_string_after_existing_defines() {
+        check_assist(
+            extract_constant,
+            "Extract literal into macro (1 occurrence)",
+            r#"
+-module(main).
+-define(TIMEOUT, 5000).
+foo() -> io:format(~"hello world").
+"#,
+            expect![[r#"
+                -module(main).
+                -define(TIMEOUT, 5000).
+
+                -define(HELLO_WORLDEdited, "hello world").
+                foo() -> io:format(?HELLO_WORLDEdited).
+            "#]],
+        );
+    }
+
+    #[test]
+    fn not_applicable_outside_function() {
+        check_assist_not_applicable(
+            extract_constant,
+            r#"
+-module(main).
+-define(TIMEOUT, ~5000).
+foo() -> ?TIMEOUT.
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_on_atom() {
+        check_assist_not_applicable(
+            extract_constant,
+            r#"
+-module(main).
+foo() -> ~ok.
+"#,
+        );
+    }
+}
@@ -92,7 +92,7 @@ pub(crate) fn extract_function(acc: &mut Assists, ctx: &AssistContext<'_>) -> Op
         move |builder| {
             let locals = body.analyze(&ctx);
             let outliving_locals = body.ret_values(ctx, &locals.bound);
-            let params = body.extracted_function_params(locals.free);
+            let params = body.extracted_function_params(ctx, locals.free);
             let name = freshen_function_name(
                 &ctx,
                 ctx.user_input_or(|| make_function_name(&ctx)),
@@ -382,7 +382,7 @@ impl FunctionBody {
         ctx: &'a AssistContext<'_>,
         locals_bound_in_body: &'a FxHashSet<Resolution>,
     ) -> Vec<Var> {
-        match &self {
+        let vars = match &self {
             FunctionBody::Expr(expr) => {
                 let parent = expr
                     .syntax()
@@ -403,14 +403,45 @@ impl FunctionBody {
             FunctionBody::Span { parent, text_range } => {
                 calculate_ret_values(parent, text_range, ctx, locals_bound_in_body)
             }
-        }
+        };
+        self.in_order_of_occurrence(ctx, vars)
     }
 
     /// find variables that should be extracted as params
     ///
     /// Computes additional info that affects param type and mutability
-    fn extracted_function_params(&self, free: FxHashSet<Resolution>) -> Vec<Param> {
-        free.into_iter().map(|(var, _)| Param { var }).collect()
+    fn extracted_function_params(
+        &self,
+        ctx: &AssistContext,
+        free: FxHashSet<Resolution>,
+    ) -> Vec<Param> {
+        let vars = free.into_iter().map(|(var, _)| var).collect();
+        self.in_order_of_occurrence(ctx, vars)
+            .into_iter()
+            .map(|var| Param { var })
+            .collect()
+    }
+
+    /// Sort the variables by their first occurrence in the body, so
+    /// the generated code does not depend on hashing order.
+    fn in_order_of_occurrence(&self, ctx: &AssistContext, mut vars: Vec<Var>) -> Vec<Var> {
+        let range = self.text_range();
+        let occurrences: Vec<String> = tokens(self.node())
+            .filter(|it| it.kind() == SyntaxKind::VAR && range.contains_range(it.text_range()))
+            .map(|it| it.text().to_string())
+            .unique()
+            .collect();
+        vars.sort_by_cached_key(|var| {
+            let name = var.as_string(ctx.db().upcast());
+            (
+                occurrences
+                    .iter()
+                    .position(|it| *it == name)
+                    .unwrap_or(usize::MAX),
+                name,
+            )
+        });
+        vars
     }
 }
 
@@ -760,6 +791,25 @@ foo() ->
         );
     }
 
+    #[test]
+    fn extract_with_args_in_order_of_use() {
+        check_assist(
+            extract_function,
+            "Extract into function",
+            r#"
+    foo(A, B, C) ->
+        ~{C, A, B, C}~.
+    "#,
+            expect![[r#"
+                foo(A, B, C) ->
+                    fun_name_edited(C,A,B).
+
+                $0fun_name_edited(C,A,B) ->
+                    {C, A, B, C}.
+            "#]],
+        );
+    }
+
     #[test]
     fn extract_with_args_return_multiple() {
         check_assist(
@@ -1070,7 +1120,7 @@ foo() ->
             expect![[r#"
                 foo() ->
                     X = 1,
-                    {J, Bar} = fun_name_edited(X),
+                    {Bar, J} = fun_name_edited(X),
                     J + Bar.
 
                 $0fun_name_edited(X) ->
@@ -1078,7 +1128,7 @@ foo() ->
                                   _ -> {ok, X+2}
                                 end,
                     J = Bar + X + 2,
-                    {J, Bar}.
+                    {Bar, J}.
             "#]],
         );
     }
@@ -1129,10 +1179,10 @@ foo() ->
                 foo() ->
                     M = 2,
                     N = 1,
-                    {V, W} = fun_name_edited(N,M),
+                    {V, W} = fun_name_edited(M,N),
                     V + W.
 
-                $0fun_name_edited(N,M) ->
+                $0fun_name_edited(M,N) ->
                     V = M * N,
                     W = 3,
                     {V, W}.
@@ -12,11 +12,14 @@ use elp_ide_db::assists::AssistKind;
 use elp_ide_db::assists::AssistUserInput;
 use elp_ide_db::assists::AssistUserInputType;
 use elp_syntax::ast;
+use elp_syntax::match_ast;
 use elp_syntax::AstNode;
 use elp_syntax::NodeOrToken;
 use elp_syntax::SyntaxKind;
 use elp_syntax::SyntaxNode;
+use hir::DefinitionOrReference;
 use hir::InFile;
+use hir::VarDef;
 use stdx::format_to;
 
 use crate::assist_context::AssistContext;
@@ -62,6 +65,9 @@ pub(crate) fn extract_variable(acc: &mut Assists, ctx: &AssistContext) -> Option
         .descendants()
         .take_while(|it| ctx.selection_trimmed().contains_range(it.text_range()))
         .find_map(valid_target_expr)?;
+    if uses_comprehension_vars(ctx, &to_extract) {
+        return None;
+    }
 
     let anchor = Anchor::from(&to_extract)?;
     let target = to_extract.syntax().text_range();
@@ -131,6 +137,46 @@ fn valid_extraction(node: &SyntaxNode) -> bool {
     }
 }
 
+/// Check whether the expression refers to a variable bound by a
+/// generator of an enclosing list, binary or map comprehension. Such an
+/// expression cannot be moved out of the comprehension.
+fn uses_comprehension_vars(ctx: &AssistContext, expr: &ast::Expr) -> bool {
+    let generators: Vec<_> = expr
+        .syntax()
+        .ancestors()
+        .filter_map(|node| {
+            let lc_exprs = match_ast! {
+                match node {
+                    ast::ListComprehension(it) => it.lc_exprs(),
+                    ast::BinaryComprehension(it) => it.lc_exprs(),
+                    ast::MapComprehension(it) => it.lc_exprs(),
+                    _ => None,
+                }
+            };
+            Some(lc_exprs?.syntax().text_range())
+        })
+        .collect();
+    if generators.is_empty() {
+        return false;
+    }
+    let in_generator = |def: &VarDef| {
+        let range = def.source(ctx.db().upcast()).syntax().text_range();
+        generators
+            .iter()
+            .any(|generator| generator.contains_range(range))
+    };
+    expr.syntax()
+        .descendants()
+        .filter_map(ast::Var::cast)
+        .any(|var| {
+            let def = ctx.sema.to_def(InFile::new(ctx.file_id(), &var));
+            match def {
+                Some(DefinitionOrReference::Reference(defs)) => defs.iter().any(in_generator),
+                _ => false,
+            }
+        })
+}
+
 #[derive(Debug)]
 struct Anchor(SyntaxNode);
 
@@ -209,6 +255,45 @@ foo(X) ->
         );
     }
 
+    #[test]
+    fn test_extract_var_map_comprehension_generator() {
+        check_assist(
+            extract_variable,
+            "Extract into variable",
+            r#"
+foo(M) ->
+  #{K => V || K := V <- ~maps:from_list(M)~}.
+"#,
+            expect![[r#"
+                foo(M) ->
+                  $0VarNameEdited = maps:from_list(M),
+                  #{K => V || K := V <- VarNameEdited}.
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_extract_var_map_comprehension_bound_var() {
+        check_assist_not_applicable(
+            extract_variable,
+            r#"
+foo(M) ->
+  #{K => ~V + 1~ || K := V <- M}.
+"#,
+        );
+    }
+
+    #[test]
+    fn test_extract_var_list_comprehension_filter() {
+        check_assist_not_applicable(
+            extract_variable,
+            r#"
+foo(L) ->
+  [X || X <- L, ~X > 1~].
+"#,
+        );
+    }
+
     #[test]
     fn test_extract_var_case_expr() {
         check_assist(
@@ -0,0 +1,453 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use elp_ide_db::assists::AssistId;
+use elp_ide_db::assists::AssistKind;
+use elp_syntax::ast;
+use elp_syntax::ast::BehaviourAttribute;
+use elp_syntax::AstNode;
+use elp_syntax::SyntaxKind;
+use elp_syntax::TextRange;
+use elp_syntax::TextSize;
+use hir::Name;
+use hir::NameArity;
+
+use crate::assist_context::AssistContext;
+use crate::assist_context::Assists;
+use crate::helpers;
+
+/// The `gen_server` callbacks, in the order they are conventionally
+/// defined. New functions are placed next to their siblings in this
+/// order.
+const CALLBACKS: &[(&str, u32)] = &[
+    ("init", 1),
+    ("handle_call", 3),
+    ("handle_cast", 2),
+    ("handle_info", 2),
+    ("terminate", 2),
+    ("code_change", 3),
+    ("format_status", 2),
+];
+
+const HANDLE_INFO_CLAUSE: &str = "handle_info(Info, State) ->
+    logger:warning(\"~p: unexpected message ~p\", [?MODULE, Info]),
+    {noreply, State}.";
+
+const FORMAT_STATUS: &str = "format_status(_Opt, [_PDict, State]) ->
+    [{data, [{\"State\", State}]}].";
+
+const CODE_CHANGE: &str = "code_change(_OldVsn, State, _Extra) ->
+    {ok, State}.";
+
+// Assist: add_handle_info_fallthrough
+//
+// Add a `handle_info/2` clause logging unexpected messages, when on the
+// behaviour attribute of a `gen_server`
+//
+// ```
+// -behaviour(gen_server).
+// handle_info(timeout, State) ->
+//     {noreply, State}.
+// ```
+// ->
+// ```
+// -behaviour(gen_server).
+// handle_info(timeout, State) ->
+//     {noreply, State};
+// handle_info(Info, State) ->
+//     logger:warning("~p: unexpected message ~p", [?MODULE, Info]),
+//     {noreply, State}.
+// ```
+pub(crate) fn add_handle_info_fallthrough(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let attr_range = gen_server_behaviour(ctx)?;
+    let name = callback_name("handle_info", 2);
+    let def_map = ctx.sema.def_map(ctx.file_id());
+    let id = AssistId("add_handle_info_fallthrough", AssistKind::Generate);
+    let message = "Add catch-all `handle_info/2` clause".to_string();
+    match def_map.get_function(&name) {
+        Some(fun) if fun.file.file_id == ctx.file_id() => {
+            let fun_decl = ctx.form_ast(fun.function.form_id);
+            if has_catch_all_clause(&fun_decl) {
+                return None;
+            }
+            let dot = fun_decl
+                .syntax()
+                .children_with_tokens()
+                .filter_map(|element| element.into_token())
+                .find(|token| token.kind() == SyntaxKind::ANON_DOT)?;
+            acc.add(id, message, attr_range, None, |builder| {
+                builder.replace(dot.text_range(), format!(";\n{HANDLE_INFO_CLAUSE}"));
+            })
+        }
+        Some(_) => None,
+        None => add_callback(acc, ctx, id, message, attr_range, name, HANDLE_INFO_CLAUSE),
+    }
+}
+
+// Assist: add_format_status
+//
+// Add a `format_status/2` stub, when on the behaviour attribute of a
+// `gen_server`
+//
+// ```
+// -behaviour(gen_server).
+// ```
+// ->
+// ```
+// -behaviour(gen_server).
+// -export([format_status/2]).
+//
+// format_status(_Opt, [_PDict, State]) ->
+//     [{data, [{"State", State}]}].
+// ```
+pub(crate) fn add_format_status(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    add_missing_callback(
+        acc,
+        ctx,
+        "add_format_status",
+        callback_name("format_status", 2),
+        FORMAT_STATUS,
+    )
+}
+
+// Assist: add_code_change
+//
+// Add a `code_change/3` stub, when on the behaviour attribute of a
+// `gen_server`
+//
+// ```
+// -behaviour(gen_server).
+// ```
+// ->
+// ```
+// -behaviour(gen_server).
+// -export([code_change/3]).
+//
+// code_change(_OldVsn, State, _Extra) ->
+//     {ok, State}.
+// ```
+pub(crate) fn add_code_change(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    add_missing_callback(
+        acc,
+        ctx,
+        "add_code_change",
+        callback_name("code_change", 3),
+        CODE_CHANGE,
+    )
+}
+
+fn add_missing_callback(
+    acc: &mut Assists,
+    ctx: &AssistContext,
+    id: &'static str,
+    name: NameArity,
+    text: &str,
+) -> Option<()> {
+    let attr_range = gen_server_behaviour(ctx)?;
+    let def_map = ctx.sema.def_map(ctx.file_id());
+    if def_map.get_function(&name).is_some() {
+        return None;
+    }
+    let id = AssistId(id, AssistKind::Generate);
+    let message = format!("Add `{name}` stub");
+    add_callback(acc, ctx, id, message, attr_range, name, text)
+}
+
+/// Add and export a new callback function, next to the callbacks
+/// already defined.
+fn add_callback(
+    acc: &mut Assists,
+    ctx: &AssistContext,
+    id: AssistId,
+    message: String,
+    attr_range: TextRange,
+    name: NameArity,
+    text: &str,
+) -> Option<()> {
+    let (insert_at, text, sibling) = placement(ctx, &name, text);
+    let funs = vec![name];
+    acc.add(id, message, attr_range, None, |builder| {
+        let mut export_builder =
+            helpers::ExportBuilder::new(&ctx.sema, ctx.file_id(), &funs, builder);
+        if let Some(sibling) = sibling {
+            export_builder = export_builder.group_with(sibling);
+        }
+        export_builder.finish();
+        builder.edit_file(ctx.file_id());
+        builder.insert(insert_at, text);
+    })
+}
+
+/// Where to insert a new callback, the text to insert there, and an
+/// exported sibling to group its export with.
+///
+/// The callback goes after the closest preceding callback that is
+/// defined, or else before the closest following one, or else at the
+/// end of the file.
+fn placement(
+    ctx: &AssistContext,
+    name: &NameArity,
+    text: &str,
+) -> (TextSize, String, Option<NameArity>) {
+    let def_map = ctx.sema.def_map(ctx.file_id());
+    let idx = CALLBACKS
+        .iter()
+        .position(|(n, a)| name.name().as_str() == *n && name.arity() == *a)
+        .unwrap_or(CALLBACKS.len());
+    let defined = |&(n, a): &(&str, u32)| {
+        let name = callback_name(n, a);
+        let fun = def_map.get_function(&name)?;
+        if fun.file.file_id != ctx.file_id() {
+            return None;
+        }
+        Some((name, fun.clone()))
+    };
+    let exported_sibling = CALLBACKS[..idx]
+        .iter()
+        .rev()
+        .chain(CALLBACKS[idx..].iter())
+        .filter_map(defined)
+        .find(|(name, _)| def_map.is_function_exported(name))
+        .map(|(name, _)| name);
+
+    if let Some((_, fun)) = CALLBACKS[..idx].iter().rev().find_map(defined) {
+        let end = ctx
+            .form_ast(fun.function.form_id)
+            .syntax()
+            .text_range()
+            .end();
+        return (end, format!("\n\n{text}"), exported_sibling);
+    }
+    if let Some((name, fun)) = CALLBACKS[idx..].iter().find_map(defined) {
+        let start = match def_map.get_spec(&name) {
+            Some(spec) => ctx
+                .form_ast(spec.spec.form_id)
+                .syntax()
+                .text_range()
+                .start(),
+            None => ctx
+                .form_ast(fun.function.form_id)
+                .syntax()
+                .text_range()
+                .start(),
+        };
+        return (start, format!("{text}\n\n"), exported_sibling);
+    }
+    let source = ctx.sema.parse(ctx.file_id()).value;
+    let end = source.syntax().text_range().end();
+    let text = if source.syntax().text().to_string().ends_with('\n') {
+        format!("\n{text}\n")
+    } else {
+        format!("\n\n{text}\n")
+    };
+    (end, text, exported_sibling)
+}
+
+/// The range of the behaviour attribute at the cursor, if it declares a
+/// `gen_server`.
+fn gen_server_behaviour(ctx: &AssistContext) -> Option<TextRange> {
+    let behaviour = ctx.find_node_at_offset::<BehaviourAttribute>()?;
+    if behaviour.name()?.syntax().text() != "gen_server" {
+        return None;
+    }
+    Some(behaviour.syntax().text_range())
+}
+
+/// Whether any clause of the function matches every message, i.e. has
+/// an unguarded variable as its first argument.
+fn has_catch_all_clause(fun_decl: &ast::FunDecl) -> bool {
+    fun_decl.clauses().any(|clause| match clause {
+        ast::FunctionOrMacroClause::FunctionClause(clause) => {
+            clause.guard().is_none()
+                && clause
+                    .args()
+                    .and_then(|args| args.args().next())
+                    .map_or(false, |arg| {
+                        matches!(arg, ast::Expr::ExprMax(ast::ExprMax::Var(_)))
+                    })
+        }
+        ast::FunctionOrMacroClause::MacroCallExpr(_) => true,
+    })
+}
+
+fn callback_name(name: &str, arity: u32) -> NameArity {
+    NameArity::new(Name::from_erlang_service(name), arity)
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn handle_info_fallthrough_existing() {
+        check_assist(
+            add_handle_info_fallthrough,
+            "Add catch-all `handle_info/2` clause",
+            r#"
+-module(main).
+-beh~aviour(gen_server).
+-export([handle_info/2]).
+
+handle_info(timeout, State) ->
+    {noreply, State}.
+"#,
+            expect![[r#"
+                -module(main).
+                -behaviour(gen_server).
+                -export([handle_info/2]).
+
+                handle_info(timeout, State) ->
+                    {noreply, State};
+                handle_info(Info, State) ->
+                    logger:warning("~p: unexpected message ~p", [?MODULE, Info]),
+                    {noreply, State}.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn handle_info_fallthrough_new() {
+        check_assist(
+            add_handle_info_fallthrough,
+            "Add catch-all `handle_info/2` clause",
+            r#"
+-module(main).
+-beh~aviour(gen_server).
+-export([init/1, handle_cast/2, terminate/2]).
+
+init(Args) -> {ok, Args}.
+
+handle_cast(_Msg, State) -> {noreply, State}.
+
+terminate(_Reason, _State) -> ok.
+"#,
+            expect![[r#"
+                -module(main).
+                -behaviour(gen_server).
+                -export([init/1, handle_cast/2, terminate/2, handle_info/2]).
+
+                init(Args) -> {ok, Args}.
+
+                handle_cast(_Msg, State) -> {noreply, State}.
+
+                handle_info(Info, State) ->
+                    logger:warning("~p: unexpected message ~p", [?MODULE, Info]),
+                    {noreply, State}.
+
+                terminate(_Reason, _State) -> ok.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn handle_info_fallthrough_already_present() {
+        check_assist_not_applicable(
+            add_handle_info_fallthrough,
+            r#"
+-module(main).
+-beh~aviour(gen_server).
+-export([handle_info/2]).
+
+handle_info(timeout, State) ->
+    {noreply, State};
+handle_info(_Info, State) ->
+    {noreply, State}.
+"#,
+        )
+    }
+
+    #[test]
+    fn not_a_gen_server() {
+        check_assist_not_applicable(
+            add_code_change,
+            r#"
+-module(main).
+-beh~aviour(supervisor).
+"#,
+        )
+    }
+
+    #[test]
+    fn format_status_after_code_change() {
+        check_assist(
+            add_format_status,
+            "Add `format_status/2` stub",
+            r#"
+-module(main).
+-beh~aviour(gen_server).
+-export([init/1]).
+-export([code_change/3]).
+
+init(Args) -> {ok, Args}.
+
+code_change(_OldVsn, State, _Extra) -> {ok, State}.
+
+helper() -> ok.
+"#,
+            expect![[r#"
+                -module(main).
+                -behaviour(gen_server).
+                -export([init/1]).
+                -export([code_change/3, format_status/2]).
+
+                init(Args) -> {ok, Args}.
+
+                code_change(_OldVsn, State, _Extra) -> {ok, State}.
+
+                format_status(_Opt, [_PDict, State]) ->
+                    [{data, [{"State", State}]}].
+
+                helper() -> ok.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn code_change_before_format_status() {
+        check_assist(
+            add_code_change,
+            "Add `code_change/3` stub",
+            r#"
+-module(main).
+-beh~aviour(gen_server).
+-export([format_status/2]).
+
+-spec format_status(term(), list()) -> term().
+format_status(_Opt, [_PDict, State]) -> State.
+"#,
+            expect![[r#"
+                -module(main).
+                -behaviour(gen_server).
+                -export([format_status/2, code_change/3]).
+
+                code_change(_OldVsn, State, _Extra) ->
+                    {ok, State}.
+
+                -spec format_status(term(), list()) -> term().
+                format_status(_Opt, [_PDict, State]) -> State.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn code_change_already_defined() {
+        check_assist_not_applicable(
+            add_code_change,
+            r#"
+-module(main).
+-beh~aviour(gen_server).
+
+code_change(_OldVsn, State, _Extra) -> {ok, State}.
+"#,
+        )
+    }
+}
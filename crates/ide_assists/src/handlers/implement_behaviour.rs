@@ -11,13 +11,16 @@ use std::cmp::max;
 
 use elp_ide_db::assists::AssistId;
 use elp_ide_db::assists::AssistKind;
+use elp_syntax::ast;
 use elp_syntax::ast::BehaviourAttribute;
 use elp_syntax::AstNode;
 use hir::Callback;
 use hir::CallbackId;
 use hir::InFile;
 use hir::Module;
+use hir::Name;
 use hir::NameArity;
+use text_edit::TextEdit;
 use text_edit::TextRange;
 use text_edit::TextSize;
 
@@ -27,7 +30,8 @@ use crate::helpers;
 
 // Assist: implement_behaviour
 //
-// Implement and export all callbacks when on behaviour attribute
+// Implement and export all callbacks when on behaviour attribute, with a
+// spec taken from each `-callback` attribute
 //
 // ```
 // -behaviour(gen_server).
@@ -230,11 +234,14 @@ fn make_implementation(
             if let Some(sig) = callback_body.sigs.iter().next() {
                 let function_args =
                     ctx.create_function_args_from_types(&sig.args, &callback_body.body);
+                let spec = callback_spec(ctx, behaviour, callback)
+                    .map(|spec| format!("\n{spec}"))
+                    .unwrap_or_default();
                 let addition = (
                     callback.name.clone(),
                     Some(format!(
-                        "\n{}({}) ->\n    erlang:error(not_implemented).",
-                        function_name, function_args
+                        "{}\n{}({}) ->\n    erlang:error(not_implemented).",
+                        spec, function_name, function_args
                     )),
                 );
                 Some(addition)
@@ -246,6 +253,56 @@ fn make_implementation(
     }
 }
 
+/// Turn the `-callback` attribute into a `-spec` for its
+/// implementation, qualifying the types defined in the behaviour
+/// module with its name.
+fn callback_spec(
+    ctx: &AssistContext<'_>,
+    behaviour: &Module,
+    callback: &Callback,
+) -> Option<String> {
+    let behaviour_file_id = behaviour.file.file_id;
+    let ast_callback = callback.form_id.get_ast(ctx.sema.db, behaviour_file_id);
+    let start = ast_callback.fun()?.syntax().text_range().start();
+    let behaviour_def_map = ctx.sema.def_map(behaviour_file_id);
+    let behaviour_name = behaviour.name(ctx.db());
+    let mut builder = TextEdit::builder();
+    ast_callback
+        .syntax()
+        .descendants()
+        .filter_map(ast::Call::cast)
+        .for_each(|call| {
+            if let (Some(ast::Expr::ExprMax(ast::ExprMax::Atom(atom))), Some(args)) =
+                (call.expr(), call.args())
+            {
+                if let Some(name) = atom.text() {
+                    let type_name = NameArity::new(
+                        Name::from_erlang_service(&name),
+                        args.args().count() as u32,
+                    );
+                    if behaviour_def_map.get_type(&type_name).is_some() {
+                        builder.insert(
+                            atom.syntax().text_range().start() - start,
+                            format!("{behaviour_name}:"),
+                        );
+                    }
+                }
+            }
+        });
+    let mut text = ast_callback
+        .syntax()
+        .text()
+        .slice(start - ast_callback.syntax().text_range().start()..)
+        .to_string();
+    builder.finish().apply(&mut text);
+    let text = text.trim_end();
+    if text.ends_with('.') {
+        Some(format!("-spec {text}"))
+    } else {
+        Some(format!("-spec {text}."))
+    }
+}
+
 // ---------------------------------------------------------------------
 
 #[cfg(test)]
@@ -281,6 +338,9 @@ mod tests {
                 %% Callbacks for `supervisor`
                 -export([init/1]).
 
+                -spec init(Args :: term()) ->
+                    {ok, {SupFlags :: sup_flags(), [ChildSpec :: child_spec()]}}
+                    | ignore.
                 init(Args) ->
                     erlang:error(not_implemented).
 
@@ -321,9 +381,20 @@ mod tests {
                 %% Callbacks for `gen_server`
                 -export([init/1, handle_call/3]).
 
+                -spec init(Args :: term()) ->
+                    {ok, State :: term()} | {ok, State :: term(), timeout() | hibernate | {continue, term()}} |
+                    {stop, Reason :: term()} | ignore.
                 init(Args) ->
                     erlang:error(not_implemented).
 
+                -spec handle_call(Request :: term(), From :: from(),
+                                      State :: term()) ->
+                    {reply, Reply :: term(), NewState :: term()} |
+                    {reply, Reply :: term(), NewState :: term(), timeout() | hibernate | {continue, term()}} |
+                    {noreply, NewState :: term()} |
+                    {noreply, NewState :: term(), timeout() | hibernate | {continue, term()}} |
+                    {stop, Reason :: term(), Reply :: term(), NewState :: term()} |
+                    {stop, Reason :: term(), NewState :: term()}.
                 handle_call(Request,From,State) ->
                     erlang:error(not_implemented).
 
@@ -356,6 +427,7 @@ mod tests {
                 -behaviour(my_behaviour).
                 -export([init/1, another/0]).
 
+                -spec another() -> ok.
                 another() ->
                     erlang:error(not_implemented).
 
@@ -398,6 +470,7 @@ mod tests {
                 %% Callbacks for `my_behaviour`
                 -export([init/1, another/0]).
 
+                -spec another() -> ok.
                 another() ->
                     erlang:error(not_implemented).
 
@@ -439,6 +512,7 @@ mod tests {
                 %% Callbacks for `my_behaviour`
                 -export([init/1, another/0]).
 
+                -spec another() -> ok.
                 another() ->
                     erlang:error(not_implemented).
 
@@ -478,6 +552,7 @@ mod tests {
                 %% Callbacks for `my_behaviour`
                 -export([another/0]).
 
+                -spec another() -> ok.
                 another() ->
                     erlang:error(not_implemented).
 
@@ -515,6 +590,7 @@ mod tests {
                 %% Optional callbacks for `my_behaviour`
                 -export([init/1, optional/0]).
 
+                -spec optional() -> ok.
                 optional() ->
                     erlang:error(not_implemented).
 
@@ -548,6 +624,7 @@ mod tests {
                 %% Callbacks for `my_behaviour`
                 -export([init/1, another/0]).
 
+                -spec another() -> ok.
                 another() ->
                     erlang:error(not_implemented).
 
@@ -556,4 +633,35 @@ mod tests {
             "#]],
         )
     }
+
+    #[test]
+    fn implement_behaviour_qualifies_types() {
+        check_assist(
+            implement_behaviour,
+            "Create callbacks for 'my_behaviour'",
+            r#"
+            //- /src/main.erl
+            -module(main).
+            -behaviour(my_b~ehaviour).
+
+            //- /src/my_behaviour.erl
+            -module(my_behaviour).
+            -export_type([state/0]).
+            -type state() :: #{atom() => term()}.
+            -callback init(Args :: list()) -> {ok, state()} | {error, term()}.
+             "#,
+            expect![[r#"
+                -module(main).
+                -behaviour(my_behaviour).
+
+                %% Callbacks for `my_behaviour`
+                -export([init/1]).
+
+                -spec init(Args :: list()) -> {ok, my_behaviour:state()} | {error, term()}.
+                init(Args) ->
+                    erlang:error(not_implemented).
+
+            "#]],
+        )
+    }
 }
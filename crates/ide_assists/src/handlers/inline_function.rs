@@ -24,11 +24,13 @@ use elp_syntax::ast::edit::IndentLevel;
 use elp_syntax::ast::HasArity;
 use elp_syntax::AstNode;
 use elp_syntax::NodeOrToken;
+use elp_syntax::SourceFile;
 use elp_syntax::SyntaxKind;
 use elp_syntax::SyntaxNode;
 use elp_syntax::SyntaxToken;
 use elp_syntax::TextRange;
 use elp_syntax::TextSize;
+use fxhash::FxHashMap;
 use fxhash::FxHashSet;
 use hir::Clause;
 use hir::FunctionDef;
@@ -44,6 +46,7 @@ use text_edit::TextEdit;
 use crate::assist_context::AssistContext;
 use crate::assist_context::Assists;
 use crate::helpers::change_indent;
+use crate::helpers::freshen_variable_name;
 use crate::helpers::parens_needed;
 use crate::helpers::ranges_for_delete_function;
 use crate::helpers::simple_param_vars;
@@ -86,10 +89,13 @@ pub(crate) fn inline_function(acc: &mut Assists, ctx: &AssistContext) -> Option<
         return None;
     }
 
-    if !is_safe(ctx, &fun, &references) {
-        cov_mark::hit!(inline_function_is_safe);
-        return None;
-    }
+    let renames = match clashing_vars(ctx, &fun, &references) {
+        Some(renames) => renames,
+        None => {
+            cov_mark::hit!(inline_function_is_safe);
+            return None;
+        }
+    };
 
     acc.add(
         AssistId("inline_function", AssistKind::RefactorInline),
@@ -104,6 +110,12 @@ pub(crate) fn inline_function(acc: &mut Assists, ctx: &AssistContext) -> Option<
                     delete_ranges.delete(builder);
                 }
             }
+            // Variables of the function clashing with those at a call
+            // site are renamed, and the text to inline taken from the
+            // renamed copy.
+            let renamed_fun = rename_vars(&ast_fun, &renames);
+            let is_renamed = renamed_fun.is_some();
+            let ast_fun = renamed_fun.unwrap_or(ast_fun);
             for call in references {
                 let infile_ast_fun = InFile::new(fun.file.file_id, &ast_fun);
                 let function_body = ctx
@@ -126,6 +138,7 @@ pub(crate) fn inline_function(acc: &mut Assists, ctx: &AssistContext) -> Option<
                                 &ast_clause,
                                 &call,
                                 &clause,
+                                is_renamed,
                             ) {
                                 builder.replace(range, replacement)
                             }
@@ -215,11 +228,15 @@ fn inline_single_function_clause(
     ast_clause: &ast::FunctionClause,
     call: &ast::Call,
     clause: &InFunctionBody<Clause>,
+    is_renamed: bool,
 ) -> Option<(TextRange, String)> {
     if ast_clause.guard().is_some() {
         inline_function_as_case(ast_fun, call)
     } else {
-        if ast_clause.body()?.exprs().count() == 1
+        // A renamed clause is not part of the file, so cannot be
+        // analysed. It always has variables, needing a `begin` block.
+        if !is_renamed
+            && ast_clause.body()?.exprs().count() == 1
             && !has_vars_in_clause(sema, ast_fun.file_id, ast_clause)
         {
             inline_simple_function_clause(sema, file_id, ast_clause, call)
@@ -734,76 +751,114 @@ fn get_call(syntax: &SyntaxNode) -> Option<ast::Call> {
     }
 }
 
-/// Check that all variables defined in the clauses of the `FunDecl`
-/// are unused in the call location.  This excludes single-variable
-/// parameters.
-fn is_safe(ctx: &AssistContext, fun: &FunctionDef, references: &[ast::Call]) -> bool {
-    fn check_is_safe(
-        ctx: &AssistContext,
-        fun: &FunctionDef,
-        references: &[ast::Call],
-    ) -> Option<bool> {
-        let function_body = ctx
-            .db()
-            .function_body(InFile::new(fun.file.file_id, fun.function_id));
-        let fun_vars = function_body
-            .clauses
-            .iter()
-            .filter_map(|(_, clause)| {
-                ScopeAnalysis::clause_vars_in_scope(
-                    &ctx.sema,
-                    &fun.in_function_body(ctx.db(), clause),
-                )
+/// Find the variables defined in the clauses of the `FunDecl` that
+/// are also used at a call location, and choose a fresh name for each
+/// of them, so that inlining does not capture or rebind a variable of
+/// the caller.  This excludes single-variable parameters.  Returns
+/// `None` if the variables in scope at a call site cannot be
+/// determined.
+fn clashing_vars(
+    ctx: &AssistContext,
+    fun: &FunctionDef,
+    references: &[ast::Call],
+) -> Option<FxHashMap<String, String>> {
+    let function_body = ctx
+        .db()
+        .function_body(InFile::new(fun.file.file_id, fun.function_id));
+    let all_fun_vars = function_body
+        .clauses
+        .iter()
+        .filter_map(|(_, clause)| {
+            ScopeAnalysis::clause_vars_in_scope(&ctx.sema, &fun.in_function_body(ctx.db(), clause))
+        })
+        .fold(FxHashSet::default(), move |mut acc, new: FxHashSet<Var>| {
+            acc.extend(new.into_iter());
+            acc
+        });
+    let simple_param_vars = function_body
+        .clauses
+        .iter()
+        .filter_map(|(_, clause)| simple_param_vars(&fun.in_function_body(ctx.db(), clause)))
+        .fold(FxHashSet::default(), move |mut acc, new: FxHashSet<Var>| {
+            acc.extend(new.into_iter());
+            acc
+        });
+    let fun_vars: FxHashSet<Var> = all_fun_vars
+        .difference(&simple_param_vars)
+        .map(|x| x.clone())
+        .collect();
+
+    // At each call site, collect the vars clashing with the fun_vars.
+    let file_id = ctx.file_id();
+    let mut clashes = FxHashSet::default();
+    let mut used_vars: FxHashSet<Var> = all_fun_vars.union(&simple_param_vars).cloned().collect();
+    for call in references {
+        // clause_vars is all the top-level vars in the function
+        // clause containing the call, before and after it, at the
+        // top level.
+
+        let expr = ast::Expr::Call(call.clone());
+        let clause_vars = || -> Option<FxHashSet<Var>> {
+            let clause_id = ctx.sema.find_enclosing_function_clause(call.syntax())?;
+            let call_function = ctx.sema.to_expr(InFile::new(ctx.file_id(), &expr))?;
+            let clause = &call_function[clause_id];
+            let clause_vars =
+                ScopeAnalysis::clause_vars_in_scope(&ctx.sema, &call_function.with_value(&clause));
+            clause_vars
+        }()
+        .unwrap_or_default();
+
+        // We also need to check in the actual scope we are
+        // currently in. e.g. a catch clause, where vars are not exported
+        let mut vars = call_vars_in_scope(&ctx.sema, file_id, call)?;
+        vars.extend(clause_vars.iter());
+        clashes.extend(vars.intersection(&fun_vars).cloned());
+        used_vars.extend(vars);
+    }
+
+    let used_vars = Some(used_vars);
+    Some(
+        clashes
+            .into_iter()
+            .map(|var| {
+                let name = var.as_string(ctx.db().upcast());
+                let fresh = freshen_variable_name(&ctx.sema, name.clone(), &used_vars);
+                (name, fresh)
             })
-            .fold(FxHashSet::default(), move |mut acc, new: FxHashSet<Var>| {
-                acc.extend(new.into_iter());
-                acc
-            });
-        let simple_param_vars = function_body
-            .clauses
-            .iter()
-            .filter_map(|(_, clause)| simple_param_vars(&fun.in_function_body(ctx.db(), clause)))
-            .fold(FxHashSet::default(), move |mut acc, new: FxHashSet<Var>| {
-                acc.extend(new.into_iter());
-                acc
-            });
-        let fun_vars: FxHashSet<Var> = fun_vars
-            .difference(&simple_param_vars)
-            .map(|x| x.clone())
-            .collect();
-
-        // At each call site, check that no param clashes with the fun_vars.
-        let file_id = ctx.file_id();
-        let ok = references.iter().all(|call| {
-            // clause_vars is all the top-level vars in the function
-            // clause containing the call, before and after it, at the
-            // top level.
-
-            let expr = ast::Expr::Call(call.clone());
-            let clause_vars = || -> Option<FxHashSet<Var>> {
-                let clause_id = ctx.sema.find_enclosing_function_clause(call.syntax())?;
-                let call_function = ctx.sema.to_expr(InFile::new(ctx.file_id(), &expr))?;
-                let clause = &call_function[clause_id];
-                let clause_vars = ScopeAnalysis::clause_vars_in_scope(
-                    &ctx.sema,
-                    &call_function.with_value(&clause),
-                );
-                clause_vars
-            }()
-            .unwrap_or_default();
-
-            // We also need to check in the actual scope we are
-            // currently in. e.g. a catch clause, where vars are not exported
-            if let Some(mut vars) = call_vars_in_scope(&ctx.sema, file_id, call) {
-                vars.extend(clause_vars.iter());
-                vars.intersection(&fun_vars).count() == 0
-            } else {
-                false
+            .collect(),
+    )
+}
+
+/// Return a copy of the `FunDecl`, with the given variables renamed.
+/// The copy is parsed in the context of the whole file, so that the
+/// indentation of its clauses is preserved.
+fn rename_vars(
+    ast_fun: &ast::FunDecl,
+    renames: &FxHashMap<String, String>,
+) -> Option<ast::FunDecl> {
+    if renames.is_empty() {
+        return None;
+    }
+    let range = ast_fun.syntax().text_range();
+    let mut builder = TextEdit::builder();
+    ast_fun
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|n| n.into_token())
+        .filter(|t| t.kind() == SyntaxKind::VAR)
+        .for_each(|t| {
+            if let Some(fresh) = renames.get(t.text()) {
+                builder.replace(t.text_range(), fresh.clone());
             }
         });
-        Some(ok)
-    }
-    check_is_safe(ctx, fun, references).unwrap_or(false)
+    let mut text = ast_fun.syntax().ancestors().last()?.text().to_string();
+    builder.finish().apply(&mut text);
+    SourceFile::parse_text(&text)
+        .tree()
+        .syntax()
+        .descendants()
+        .filter_map(ast::FunDecl::cast)
+        .find(|f| f.syntax().text_range().start() == range.start())
 }
 
 fn call_vars_in_scope(
@@ -1731,37 +1786,90 @@ mod tests {
 
     #[test]
     fn test_inline_function_check_variable_name_clash_1() {
-        cov_mark::check!(inline_function_is_safe);
-        check_assist_not_applicable(
+        check_assist(
             inline_function,
+            "Inline function",
             r#"
-              foo(X) ->
-                X = 1,
-                b~az(3, X).
-
               baz(A,B) ->
-                X = 1,
-                Y = 2,
-                (A + 1) * (B + Y).
-              "#,
+                  X = 1,
+                  Y = 2,
+                  (A + 1) * (B + Y).
+
+              foo(X) ->
+                  X = 1,
+                  Z = b~az(3, X),
+                  Z."#,
+            expect![[r#"
+                foo(X) ->
+                    X = 1,
+                    Z =
+                        begin
+                            A = 3,
+                            B = X,
+                            X0 = 1,
+                            Y = 2,
+                            (A + 1) * (B + Y)
+                        end,
+                    Z."#]],
         )
     }
 
     #[test]
     fn test_inline_function_check_variable_name_clash_2() {
-        cov_mark::check!(inline_function_is_safe);
-        check_assist_not_applicable(
+        check_assist(
             inline_function,
+            "Inline function",
             r#"
+              baz(A,B) ->
+                  X = 1,
+                  Y = 2,
+                  (A + 1) * (B + Y).
+
               foo(X) ->
-                Z = 1,
-                b~az(3, Z).
+                  Z = 1,
+                  W = b~az(3, Z),
+                  W + X."#,
+            expect![[r#"
+                foo(X) ->
+                    Z = 1,
+                    W =
+                        begin
+                            A = 3,
+                            B = Z,
+                            X0 = 1,
+                            Y = 2,
+                            (A + 1) * (B + Y)
+                        end,
+                    W + X."#]],
+        )
+    }
 
-              baz(A,B) ->
-                X = 1,
-                Y = 2,
-                (A + 1) * (B + Y).
-              "#,
+    #[test]
+    fn test_inline_function_check_variable_name_clash_case() {
+        check_assist(
+            inline_function,
+            "Inline function",
+            r#"
+              baz(0) ->
+                  0;
+              baz(A) ->
+                  Y = A * 2,
+                  Y + 1.
+
+              foo(Y) ->
+                  Z = b~az(Y),
+                  Z + Y."#,
+            expect![[r#"
+                foo(Y) ->
+                    Z =
+                        case Y of
+                          0 ->
+                                0;
+                          A ->
+                                Y0 = A * 2,
+                                Y0 + 1
+                        end,
+                    Z + Y."#]],
         )
     }
 
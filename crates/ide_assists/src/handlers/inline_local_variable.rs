@@ -355,6 +355,23 @@ bar(X) ->
         )
     }
 
+    #[test]
+    fn test_definition_map_comprehension() {
+        check_assist(
+            inline_local_variable,
+            "Inline variable",
+            r#"
+bar(M) ->
+  ~A = #{K => V + 1 || K := V <- M},
+  A#{a => 1}.
+"#,
+            expect![[r#"
+                bar(M) ->
+                  #{K => V + 1 || K := V <- M}#{a => 1}.
+            "#]],
+        )
+    }
+
     #[test]
     fn test_shadowed_variable_free_vars() {
         check_assist_not_applicable(
@@ -0,0 +1,751 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::cmp::Reverse;
+use std::ops::Range;
+
+use elp_ide_db::assists::AssistId;
+use elp_ide_db::assists::AssistKind;
+use elp_ide_db::assists::AssistUserInput;
+use elp_ide_db::assists::AssistUserInputType;
+use elp_ide_db::elp_base_db::AnchoredPathBuf;
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::elp_base_db::SourceDatabase;
+use elp_ide_db::rename::is_valid_module_name;
+use elp_ide_db::source_change::SourceChangeBuilder;
+use elp_ide_db::SearchScope;
+use elp_ide_db::SymbolClass;
+use elp_ide_db::SymbolDefinition;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use elp_syntax::SyntaxKind;
+use elp_syntax::SyntaxNode;
+use elp_syntax::TextRange;
+use fxhash::FxHashMap;
+use hir::FunctionDef;
+use hir::NameArity;
+
+use crate::helpers;
+use crate::helpers::FunctionRanges;
+use crate::AssistContext;
+use crate::Assists;
+
+// Assist: move_function
+//
+// Move a function to another module, along with the private functions
+// only used by it. The calls to the function become remote calls, and
+// the functions of the original module it calls are exported.
+//
+// Functions using the records, types or macros of their module, which
+// the target module would not have, can't be moved: the assist is
+// disabled, as it is for a target clashing with the function.
+//
+// ```
+// -module(main).
+// -export([foo/0, baz/0]).
+// f~oo() -> helper(bar()).
+// helper(X) -> {X}.
+// bar() -> ok.
+// baz() -> [foo(), bar()].
+// ```
+// ->
+// ```
+// -module(main).
+// -export([bar/0, baz/0]).
+// bar() -> ok.
+// baz() -> [util:foo(), bar()].
+// ```
+pub(crate) fn move_function(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let fun = match ctx.classify_offset()? {
+        SymbolClass::Definition(SymbolDefinition::Function(fun)) => fun,
+        _ => return None,
+    };
+    let module = ctx.sema.module_name(ctx.file_id())?;
+    let default_target = format!("{}_new", module.as_str());
+    let id = AssistId("move_function", AssistKind::Refactor);
+    let message = format!("Move `{}` to another module", fun.function.name);
+    let function_range = ctx.form_ast(fun.function.form_id).syntax().text_range();
+    let target = Target::new(
+        ctx,
+        fun.file.file_id,
+        ctx.user_input_or(|| default_target.clone()),
+    );
+    let moved = match check_move(ctx, &fun, module.as_str(), &target) {
+        Ok(moved) => moved,
+        Err(reason) => {
+            return acc.add_disabled(
+                id,
+                message,
+                function_range,
+                format!("Cannot move: {reason}"),
+            );
+        }
+    };
+    acc.add(
+        id,
+        message,
+        function_range,
+        Some(AssistUserInput {
+            input_type: AssistUserInputType::Atom,
+            value: default_target,
+        }),
+        |builder| {
+            let source = module.to_quoted_string();
+            move_function_to(ctx, fun, moved, &source, &target, builder);
+        },
+    )
+}
+
+/// The module the function is moved to, which may not exist yet.
+struct Target {
+    name: String,
+    file_id: Option<FileId>,
+    functions: Vec<NameArity>,
+}
+
+impl Target {
+    fn new(ctx: &AssistContext, file_id: FileId, name: String) -> Target {
+        let file_id = ctx
+            .sema
+            .resolve_module_name(file_id, &name)
+            .map(|module| module.file.file_id);
+        let functions = file_id.map_or_else(Vec::new, |file_id| {
+            ctx.sema
+                .def_map(file_id)
+                .get_functions()
+                .keys()
+                .cloned()
+                .collect()
+        });
+        Target {
+            name,
+            file_id,
+            functions,
+        }
+    }
+
+    fn clashes(&self, name: &NameArity) -> bool {
+        self.functions.contains(name)
+    }
+}
+
+/// The functions to move, or why the function can't be moved to the
+/// target.
+fn check_move(
+    ctx: &AssistContext,
+    fun: &FunctionDef,
+    module: &str,
+    target: &Target,
+) -> Result<Vec<MovedFunction>, String> {
+    if target.name == module {
+        return Err(format!(
+            "`{}` is already in module `{module}`",
+            fun.function.name
+        ));
+    }
+    if !is_valid_module_name(target.name.clone()) {
+        return Err(format!("`{}` is not a valid module name", target.name));
+    }
+    if target.clashes(&fun.function.name) {
+        return Err(format!(
+            "module `{}` already defines `{}`",
+            target.name, fun.function.name
+        ));
+    }
+    let moved = moved_functions(ctx, fun.clone(), &|name| target.clashes(name));
+    let dependencies = local_dependencies(ctx, fun.file.file_id, &moved);
+    if !dependencies.is_empty() {
+        return Err(format!("uses {}", dependencies.join(", ")));
+    }
+    Ok(moved)
+}
+
+/// The records, types and macros, defined in the module or in the files
+/// it includes, used by the moved functions or their specs.
+fn local_dependencies(
+    ctx: &AssistContext,
+    file_id: FileId,
+    moved: &[MovedFunction],
+) -> Vec<String> {
+    let scope = SearchScope::single_file(file_id, None);
+    let is_used = |def: SymbolDefinition| {
+        def.usages(&ctx.sema)
+            .set_scope(&scope)
+            .all()
+            .file_ranges()
+            .any(|usage| moved.iter().any(|moved| moved.contains(usage.range)))
+    };
+    let def_map = ctx.sema.def_map(file_id);
+    let mut dependencies = Vec::new();
+    for def in def_map.get_records().values() {
+        if is_used(SymbolDefinition::Record(def.clone())) {
+            dependencies.push(format!("record `#{}`", def.record.name));
+        }
+    }
+    for def in def_map.get_types().values() {
+        if is_used(SymbolDefinition::Type(def.clone())) {
+            dependencies.push(format!("type `{}`", def.type_alias.name()));
+        }
+    }
+    for def in def_map.get_macros().values() {
+        if is_used(SymbolDefinition::Define(def.clone())) {
+            dependencies.push(format!("macro `?{}`", def.define.name));
+        }
+    }
+    dependencies.sort();
+    dependencies
+}
+
+struct MovedFunction {
+    def: FunctionDef,
+    ranges: FunctionRanges,
+}
+
+impl MovedFunction {
+    fn new(ctx: &AssistContext, def: FunctionDef) -> MovedFunction {
+        let ast_fun = def.source(ctx.db().upcast());
+        let ranges = helpers::function_ranges(ctx, &def, &ast_fun);
+        MovedFunction { def, ranges }
+    }
+
+    fn contains(&self, range: TextRange) -> bool {
+        self.pieces().any(|piece| piece.contains_range(range))
+    }
+
+    /// The edoc comments, spec and function, in the order of the file.
+    fn pieces(&self) -> impl Iterator<Item = TextRange> + '_ {
+        let mut pieces: Vec<_> = self
+            .ranges
+            .edoc
+            .iter()
+            .copied()
+            .chain(self.ranges.spec)
+            .chain([self.ranges.function])
+            .collect();
+        pieces.sort_by_key(|piece| piece.start());
+        pieces.into_iter()
+    }
+}
+
+fn move_function_to(
+    ctx: &AssistContext,
+    fun: FunctionDef,
+    moved: Vec<MovedFunction>,
+    source: &str,
+    target: &Target,
+    builder: &mut SourceChangeBuilder,
+) {
+    let file_id = fun.file.file_id;
+    let name_arity = fun.function.name.clone();
+    let target_file_id = target.file_id;
+    let target = target.name.as_str();
+    let in_moved = |range: TextRange| moved.iter().any(|moved| moved.contains(range));
+
+    // Edits to the text of the moved functions, by range in the source file
+    let mut moved_edits: Vec<(TextRange, String)> = Vec::new();
+    let mut file_edits: FxHashMap<FileId, Vec<(TextRange, String)>> = FxHashMap::default();
+    let mut export_entries: Vec<ast::Fa> = Vec::new();
+
+    let usages = SymbolDefinition::Function(fun).usages(&ctx.sema).all();
+    for (usage_file_id, names) in usages.iter() {
+        for name in names {
+            let range = name.syntax().text_range();
+            let is_moved = usage_file_id == file_id && in_moved(range);
+            let parent = match name.syntax().parent() {
+                Some(parent) => parent,
+                None => continue,
+            };
+            if let Some(module_range) = module_qualifier(&parent) {
+                let edit = (module_range, target.to_string());
+                if is_moved {
+                    moved_edits.push(edit);
+                } else {
+                    file_edits.entry(usage_file_id).or_default().push(edit);
+                }
+            } else if usage_file_id == file_id && !is_moved {
+                match parent.kind() {
+                    SyntaxKind::CALL | SyntaxKind::INTERNAL_FUN => {
+                        let edit = (range, format!("{target}:{}", name.syntax().text()));
+                        file_edits.entry(file_id).or_default().push(edit);
+                    }
+                    SyntaxKind::FA => {
+                        if let Some(fa) = ast::Fa::cast(parent) {
+                            if fa
+                                .syntax()
+                                .parent()
+                                .and_then(ast::ExportAttribute::cast)
+                                .is_some()
+                            {
+                                export_entries.push(fa);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    // The functions staying in the source module, called by the moved ones
+    let mut new_exports = Vec::new();
+    let scope = SearchScope::single_file(file_id, None);
+    let def_map = ctx.sema.def_map(file_id);
+    for def in def_map.get_functions().values() {
+        if def.file.file_id != file_id
+            || moved
+                .iter()
+                .any(|moved| moved.def.function.name == def.function.name)
+        {
+            continue;
+        }
+        let usages = SymbolDefinition::Function(def.clone())
+            .usages(&ctx.sema)
+            .set_scope(&scope)
+            .all();
+        let mut is_called = false;
+        for (_, names) in usages.iter() {
+            for name in names {
+                let range = name.syntax().text_range();
+                let is_local = name.syntax().parent().map_or(false, |parent| {
+                    matches!(parent.kind(), SyntaxKind::CALL | SyntaxKind::INTERNAL_FUN)
+                });
+                if is_local && in_moved(range) {
+                    moved_edits.push((range, format!("{source}:{}", name.syntax().text())));
+                    is_called = true;
+                }
+            }
+        }
+        if is_called && !def.exported {
+            new_exports.push(def.function.name.clone());
+        }
+    }
+    new_exports.sort_by(|a, b| a.to_string().cmp(&b.to_string()));
+
+    let text = ctx.sema.db.file_text(file_id);
+    let moved_text = moved
+        .iter()
+        .map(|moved| {
+            let block: String = moved
+                .pieces()
+                .map(|piece| text_with_edits(&text, piece, &moved_edits))
+                .collect();
+            format!("{}\n", block.trim_end())
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // The source module
+    builder.edit_file(file_id);
+    for moved in &moved {
+        moved.ranges.delete(builder);
+    }
+    for (range, text) in file_edits.remove(&file_id).unwrap_or_default() {
+        builder.replace(range, text);
+    }
+    let mut replacement = (!new_exports.is_empty()).then(|| {
+        new_exports
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    });
+    let mut removed: Vec<(ast::ExportAttribute, Vec<TextRange>)> = Vec::new();
+    for fa in export_entries {
+        let fa_range = fa.syntax().text_range();
+        if let Some(text) = replacement.take() {
+            builder.replace(fa_range, text);
+            continue;
+        }
+        let export = match fa.syntax().parent().and_then(ast::ExportAttribute::cast) {
+            Some(export) => export,
+            None => continue,
+        };
+        match removed.iter_mut().find(|(other, _)| other == &export) {
+            Some((_, ranges)) => ranges.push(fa_range),
+            None => removed.push((export, vec![fa_range])),
+        }
+    }
+    for (export, ranges) in removed {
+        delete_export_entries(builder, &export, &ranges);
+    }
+    if replacement.is_some() {
+        helpers::ExportBuilder::new(&ctx.sema, file_id, &new_exports, builder).finish();
+    }
+
+    // The other modules referring to the function
+    let mut other_files: Vec<_> = file_edits.into_iter().collect();
+    other_files.sort_by_key(|(file_id, _)| *file_id);
+    for (other_file_id, edits) in other_files {
+        builder.edit_file(other_file_id);
+        for (range, text) in edits {
+            builder.replace(range, text);
+        }
+    }
+
+    // The target module
+    match target_file_id {
+        Some(target_file_id) => {
+            let exported = [name_arity];
+            helpers::ExportBuilder::new(&ctx.sema, target_file_id, &exported, builder).finish();
+            builder.edit_file(target_file_id);
+            let target_text = ctx.sema.db.file_text(target_file_id);
+            let separator = if target_text.ends_with('\n') {
+                "\n"
+            } else {
+                "\n\n"
+            };
+            let end = ctx
+                .sema
+                .parse(target_file_id)
+                .value
+                .syntax()
+                .text_range()
+                .end();
+            builder.insert(end, format!("{separator}{moved_text}"));
+        }
+        None => {
+            let dst = AnchoredPathBuf {
+                anchor: file_id,
+                path: format!("{target}.erl"),
+            };
+            builder.create_file(
+                dst,
+                format!("-module({target}).\n\n-export([{name_arity}]).\n\n{moved_text}"),
+            );
+        }
+    }
+}
+
+/// The function, and the private functions of its module only used by
+/// the functions moved with it, in the order of the file. Functions
+/// clashing with those of the target module are not moved.
+fn moved_functions(
+    ctx: &AssistContext,
+    fun: FunctionDef,
+    clashes: &dyn Fn(&NameArity) -> bool,
+) -> Vec<MovedFunction> {
+    let file_id = fun.file.file_id;
+    let candidates: Vec<FunctionDef> = ctx
+        .sema
+        .def_map(file_id)
+        .get_functions()
+        .values()
+        .filter(|def| {
+            def.file.file_id == file_id
+                && !def.exported
+                && def.function.name != fun.function.name
+                && !clashes(&def.function.name)
+        })
+        .cloned()
+        .collect();
+    let mut moved = vec![MovedFunction::new(ctx, fun)];
+    loop {
+        let mut found = None;
+        for candidate in &candidates {
+            if moved
+                .iter()
+                .any(|moved| moved.def.function.name == candidate.function.name)
+            {
+                continue;
+            }
+            let candidate = MovedFunction::new(ctx, candidate.clone());
+            let usages = SymbolDefinition::Function(candidate.def.clone())
+                .usages(&ctx.sema)
+                .all();
+            let in_moved = |usage_file_id: FileId, range: TextRange| {
+                usage_file_id == file_id && moved.iter().any(|moved| moved.contains(range))
+            };
+            // Recursive calls do not prevent moving a function
+            let only_used_by_moved = usages.file_ranges().all(|usage| {
+                in_moved(usage.file_id, usage.range) || candidate.contains(usage.range)
+            });
+            let used_by_moved = usages
+                .file_ranges()
+                .any(|usage| in_moved(usage.file_id, usage.range));
+            if only_used_by_moved && used_by_moved {
+                found = Some(candidate);
+                break;
+            }
+        }
+        match found {
+            Some(candidate) => moved.push(candidate),
+            None => break,
+        }
+    }
+    moved.sort_by_key(|moved| moved.ranges.function.start());
+    moved
+}
+
+/// The range of the module of a remote call or external fun, given the
+/// parent of the function name.
+fn module_qualifier(parent: &SyntaxNode) -> Option<TextRange> {
+    match parent.kind() {
+        SyntaxKind::REMOTE => {
+            let remote = ast::Remote::cast(parent.clone())?;
+            Some(remote.module()?.module()?.syntax().text_range())
+        }
+        SyntaxKind::EXTERNAL_FUN => {
+            let fun = ast::ExternalFun::cast(parent.clone())?;
+            Some(fun.module()?.name()?.syntax().text_range())
+        }
+        _ => None,
+    }
+}
+
+/// Delete the given entries of an export attribute, or the whole
+/// attribute if none is left.
+fn delete_export_entries(
+    builder: &mut SourceChangeBuilder,
+    export: &ast::ExportAttribute,
+    removed: &[TextRange],
+) {
+    let entries: Vec<TextRange> = export.funs().map(|fa| fa.syntax().text_range()).collect();
+    let last_kept = match entries.iter().rposition(|fa| !removed.contains(fa)) {
+        Some(last_kept) => last_kept,
+        None => {
+            builder.delete(helpers::extend_form_range_for_delete(export.syntax()));
+            return;
+        }
+    };
+    // Entries before the last kept one are deleted up to the next
+    // entry, the ones after it from the end of the kept one.
+    for (idx, fa) in entries.iter().enumerate().take(last_kept) {
+        if removed.contains(fa) {
+            builder.delete(TextRange::new(fa.start(), entries[idx + 1].start()));
+        }
+    }
+    if let Some(last) = entries.last() {
+        if last_kept + 1 < entries.len() {
+            builder.delete(TextRange::new(entries[last_kept].end(), last.end()));
+        }
+    }
+}
+
+/// The text of `range`, with the edits within it applied.
+fn text_with_edits(text: &str, range: TextRange, edits: &[(TextRange, String)]) -> String {
+    let mut res = text[range].to_string();
+    let mut edits: Vec<_> = edits
+        .iter()
+        .filter(|(edit_range, _)| range.contains_range(*edit_range))
+        .collect();
+    edits.sort_by_key(|(edit_range, _)| Reverse(edit_range.start()));
+    for (edit_range, replacement) in edits {
+        res.replace_range(
+            Range::<usize>::from(*edit_range - range.start()),
+            replacement,
+        );
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn move_to_new_module() {
+        check_assist_with_user_input(
+            move_function,
+            "Move `foo/0` to another module",
+            "util",
+            r#"
+//- /my_app/src/main.erl
+-module(main).
+-export([foo/0, baz/0]).
+
+f~oo() -> helper(bar()).
+
+helper(X) -> {X}.
+
+bar() -> ok.
+
+baz() -> [foo(), bar()].
+"#,
+            expect![[r#"
+                //- /my_app/src/main.erl
+                -module(main).
+                -export([bar/0, baz/0]).
+
+                bar() -> ok.
+
+                baz() -> [util:foo(), bar()].
+                //- /my_app/src/util.erl
+                -module(util).
+
+                -export([foo/0]).
+
+                foo() -> helper(main:bar()).
+
+                helper(X) -> {X}.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn move_to_existing_module() {
+        check_assist_with_user_input(
+            move_function,
+            "Move `foo/1` to another module",
+            "target",
+            r#"
+//- /my_app/src/main.erl
+-module(main).
+-export([foo/1]).
+
+-spec foo(integer()) -> integer().
+f~oo(X) -> X + 1.
+//- /my_app/src/other.erl
+-module(other).
+-export([bar/0]).
+
+bar() -> [main:foo(1), fun main:foo/1].
+//- /my_app/src/target.erl
+-module(target).
+-export([baz/0]).
+
+baz() -> ok.
+"#,
+            expect![[r#"
+                //- /my_app/src/main.erl
+                -module(main).
+                //- /my_app/src/other.erl
+                -module(other).
+                -export([bar/0]).
+
+                bar() -> [target:foo(1), fun target:foo/1].
+                //- /my_app/src/target.erl
+                -module(target).
+                -export([baz/0, foo/1]).
+
+                baz() -> ok.
+
+                -spec foo(integer()) -> integer().
+                foo(X) -> X + 1.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn helper_clashing_with_target_stays() {
+        check_assist_with_user_input(
+            move_function,
+            "Move `foo/0` to another module",
+            "target",
+            r#"
+//- /my_app/src/main.erl
+-module(main).
+-export([foo/0]).
+
+f~oo() -> helper().
+
+helper() -> ok.
+//- /my_app/src/target.erl
+-module(target).
+
+helper() -> error.
+"#,
+            expect![[r#"
+                //- /my_app/src/main.erl
+                -module(main).
+                -export([helper/0]).
+
+                helper() -> ok.
+                //- /my_app/src/target.erl
+                -module(target).
+
+                -export([foo/0]).
+
+                helper() -> error.
+
+                foo() -> main:helper().
+            "#]],
+        )
+    }
+
+    #[test]
+    fn reports_clashing_target() {
+        check_assist_disabled_with_user_input(
+            move_function,
+            "target",
+            r#"
+//- /my_app/src/main.erl
+-module(main).
+-export([foo/0]).
+
+f~oo() -> ok.
+//- /my_app/src/target.erl
+-module(target).
+
+foo() -> error.
+"#,
+            expect![[
+                "Move `foo/0` to another module: Cannot move: module `target` already defines `foo/0`"
+            ]],
+        )
+    }
+
+    #[test]
+    fn reports_invalid_target() {
+        check_assist_disabled_with_user_input(
+            move_function,
+            "Not a module",
+            r#"
+//- /my_app/src/main.erl
+-module(main).
+-export([foo/0]).
+
+f~oo() -> ok.
+"#,
+            expect![[
+                "Move `foo/0` to another module: Cannot move: `Not a module` is not a valid module name"
+            ]],
+        )
+    }
+
+    #[test]
+    fn reports_local_dependencies() {
+        check_assist_disabled_with_user_input(
+            move_function,
+            "target",
+            r#"
+//- /my_app/include/point.hrl
+-record(point, {x, y}).
+//- /my_app/src/main.erl
+-module(main).
+-include("point.hrl").
+-export([foo/1]).
+-define(ORIGIN, #point{x = 0, y = 0}).
+-type coord() :: integer().
+
+-spec foo(coord()) -> #point{}.
+f~oo(X) -> helper(X).
+
+helper(X) -> ?ORIGIN#point{x = X}.
+"#,
+            expect![[
+                "Move `foo/1` to another module: Cannot move: uses macro `?ORIGIN`, record `#point`, type `coord/0`"
+            ]],
+        )
+    }
+
+    #[test]
+    fn not_on_function() {
+        check_assist_not_applicable(
+            move_function,
+            r#"
+//- /my_app/src/main.erl
+-mod~ule(main).
+foo() -> ok.
+"#,
+        )
+    }
+}
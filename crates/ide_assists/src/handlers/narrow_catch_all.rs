@@ -0,0 +1,315 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use elp_ide_db::assists::AssistId;
+use elp_ide_db::assists::AssistKind;
+use elp_syntax::ast;
+use elp_syntax::ast::edit::IndentLevel;
+use elp_syntax::AstNode;
+use fxhash::FxHashSet;
+use hir::CallTarget;
+use hir::Expr;
+use hir::ExprId;
+use hir::InFile;
+use hir::InFunctionBody;
+use hir::Literal;
+use hir::Semantic;
+use hir::Strategy;
+
+use crate::helpers::freshen_variable_name;
+use crate::helpers::is_catch_all;
+use crate::AssistContext;
+use crate::Assists;
+
+// Assist: narrow_catch_all
+//
+// Replaces a catch-all `_:_` exception handler with one clause per
+// exception raised by the body of the `try`, keeping the handler body,
+// and a last clause re-raising any other exception.
+//
+// ```
+// f(X) ->
+//     try
+//         check(X),
+//         throw(not_found)
+//     catch
+//         ~_:_ -> default
+//     end.
+// ```
+// ->
+// ```
+// f(X) ->
+//     try
+//         check(X),
+//         throw(not_found)
+//     catch
+//         throw:not_found -> default;
+//         Class:Reason:Stacktrace -> erlang:raise(Class, Reason, Stacktrace)
+//     end.
+// ```
+pub(crate) fn narrow_catch_all(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let clause = ctx.find_node_at_offset::<ast::CatchClause>()?;
+    if !is_catch_all(&clause) {
+        return None;
+    }
+    let try_expr = clause.syntax().ancestors().find_map(ast::TryExpr::cast)?;
+    let try_expr = ast::Expr::cast(try_expr.syntax().clone())?;
+    let body = ctx.sema.to_expr(InFile::new(ctx.file_id(), &try_expr))?;
+    let exprs = match &body[body.value] {
+        Expr::Try { exprs, .. } => exprs.clone(),
+        _ => return None,
+    };
+    let raised = raised_exceptions(&ctx.sema, &body, &exprs);
+    if raised.is_empty() {
+        return None;
+    }
+
+    let handler = clause.body()?.syntax().text().to_string();
+    let stack = clause
+        .stack()
+        .and_then(|stack| stack.class())
+        .map(|var| format!(":{}", var.syntax().text()))
+        .unwrap_or_default();
+    let vars = ctx
+        .sema
+        .find_vars_in_clause_ast(&InFile::new(ctx.file_id(), &try_expr));
+    let fresh = |name: &str| freshen_variable_name(&ctx.sema, name.to_string(), &vars);
+    let (class, reason, stacktrace) = (fresh("Class"), fresh("Reason"), fresh("Stacktrace"));
+
+    let mut clauses: Vec<String> = raised
+        .iter()
+        .map(|(class, reason)| format!("{class}:{reason}{stack} {handler}"))
+        .collect();
+    clauses.push(format!(
+        "{class}:{reason}:{stacktrace} -> erlang:raise({class}, {reason}, {stacktrace})"
+    ));
+    let indent = IndentLevel::from_node(clause.syntax());
+    let target = clause.syntax().text_range();
+    acc.add(
+        AssistId("narrow_catch_all", AssistKind::RefactorRewrite),
+        "Catch the raised exceptions only",
+        target,
+        None,
+        |edit| edit.replace(target, clauses.join(&format!(";\n{indent}"))),
+    )
+}
+
+/// The `Class:Reason` patterns of the exceptions explicitly raised by
+/// `exprs`, in the order they are raised. Exceptions raised in nested
+/// `try` or `catch` expressions, or in funs, are not considered.
+fn raised_exceptions(
+    sema: &Semantic,
+    body: &InFunctionBody<ExprId>,
+    exprs: &[ExprId],
+) -> Vec<(String, String)> {
+    let mut nested = FxHashSet::default();
+    let mut calls = Vec::new();
+    for &expr_id in exprs {
+        body.fold_expr(
+            Strategy::TopDown,
+            expr_id,
+            (),
+            &mut |(), ctx| match &ctx.expr {
+                Expr::Try { .. } | Expr::Catch { .. } | Expr::Closure { .. } => {
+                    body.fold_expr(
+                        Strategy::TopDown,
+                        ctx.expr_id,
+                        (),
+                        &mut |(), inner| {
+                            if inner.expr_id != ctx.expr_id {
+                                nested.insert(inner.expr_id);
+                            }
+                        },
+                        &mut |(), _| (),
+                    );
+                }
+                Expr::Call { target, args } => {
+                    calls.push((ctx.expr_id, target.clone(), args.clone()))
+                }
+                _ => {}
+            },
+            &mut |(), _| (),
+        );
+    }
+
+    let mut res: Vec<(String, Vec<String>)> = Vec::new();
+    for (expr_id, target, args) in calls {
+        if nested.contains(&expr_id) {
+            continue;
+        }
+        let class = match raise_class(sema, body, &target, args.len()) {
+            Some(class) => class,
+            None => continue,
+        };
+        let reason = reason_pattern(sema, body, args[0]);
+        match res.iter_mut().find(|(c, _)| c.as_str() == class) {
+            Some((_, reasons)) => {
+                if !reasons.contains(&reason) {
+                    reasons.push(reason);
+                }
+            }
+            None => res.push((class.to_string(), vec![reason])),
+        }
+    }
+    res.into_iter()
+        .flat_map(|(class, reasons)| {
+            // A reason we can't tell matches them all
+            let reasons = if reasons.iter().any(|reason| reason == "_") {
+                vec!["_".to_string()]
+            } else {
+                reasons
+            };
+            reasons
+                .into_iter()
+                .map(move |reason| (class.clone(), reason))
+        })
+        .collect()
+}
+
+/// The class of the exception raised by a call to `throw/1`, `error/1,2,3`
+/// or `exit/1`, local or qualified by `erlang`.
+fn raise_class(
+    sema: &Semantic,
+    body: &InFunctionBody<ExprId>,
+    target: &CallTarget<ExprId>,
+    arity: usize,
+) -> Option<&'static str> {
+    let name = match target {
+        CallTarget::Local { name } => body.as_atom_name(sema.db, name)?,
+        CallTarget::Remote { module, name } => {
+            if body.as_atom_name(sema.db, module)?.as_str() != "erlang" {
+                return None;
+            }
+            body.as_atom_name(sema.db, name)?
+        }
+    };
+    match (name.as_str(), arity) {
+        ("throw", 1) => Some("throw"),
+        ("error", 1..=3) => Some("error"),
+        ("exit", 1) => Some("exit"),
+        _ => None,
+    }
+}
+
+/// A pattern matching the reason of a raise: the reason itself for an
+/// atom, the tag for a tuple tagged with an atom, `_` otherwise.
+fn reason_pattern(sema: &Semantic, body: &InFunctionBody<ExprId>, reason: ExprId) -> String {
+    match &body[reason] {
+        Expr::Literal(Literal::Atom(atom)) => sema.db.lookup_atom(*atom).to_quoted_string(),
+        Expr::Tuple { exprs } => match exprs.first().map(|tag| &body[*tag]) {
+            Some(Expr::Literal(Literal::Atom(tag))) => format!(
+                "{{{}{}}}",
+                sema.db.lookup_atom(*tag).to_quoted_string(),
+                ", _".repeat(exprs.len() - 1)
+            ),
+            _ => "_".to_string(),
+        },
+        _ => "_".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn narrows_to_raised_exceptions() {
+        check_assist(
+            narrow_catch_all,
+            "Catch the raised exceptions only",
+            r#"
+f(X) ->
+    try
+        check(X),
+        throw(not_found),
+        erlang:error({badarg, X}),
+        exit(shutdown)
+    catch
+        ~_:_ -> default
+    end.
+"#,
+            expect![[r#"
+                f(X) ->
+                    try
+                        check(X),
+                        throw(not_found),
+                        erlang:error({badarg, X}),
+                        exit(shutdown)
+                    catch
+                        throw:not_found -> default;
+                        error:{badarg, _} -> default;
+                        exit:shutdown -> default;
+                        Class:Reason:Stacktrace -> erlang:raise(Class, Reason, Stacktrace)
+                    end.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn unknown_reason_matches_class() {
+        check_assist(
+            narrow_catch_all,
+            "Catch the raised exceptions only",
+            r#"
+f(X, Class) ->
+    try
+        throw(not_found),
+        throw(X)
+    catch
+        _:_:St ->~ {error, Class, St}
+    end.
+"#,
+            expect![[r#"
+                f(X, Class) ->
+                    try
+                        throw(not_found),
+                        throw(X)
+                    catch
+                        throw:_:St -> {error, Class, St};
+                        Class0:Reason:Stacktrace -> erlang:raise(Class0, Reason, Stacktrace)
+                    end.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn ignores_nested_raises() {
+        check_assist_not_applicable(
+            narrow_catch_all,
+            r#"
+f() ->
+    try
+        F = fun() -> throw(in_fun) end,
+        catch throw(caught),
+        F()
+    catch
+        ~_:_ -> error
+    end.
+"#,
+        );
+    }
+
+    #[test]
+    fn not_catch_all() {
+        check_assist_not_applicable(
+            narrow_catch_all,
+            r#"
+f() ->
+    try
+        throw(not_found)
+    catch
+        ~throw:_ -> error
+    end.
+"#,
+        );
+    }
+}
@@ -0,0 +1,467 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::cmp::Ordering;
+
+use elp_ide_db::assists::AssistId;
+use elp_ide_db::assists::AssistKind;
+use elp_ide_db::elp_base_db::FileId;
+use elp_syntax::algo;
+use elp_syntax::ast;
+use elp_syntax::ast::AstNode;
+use elp_syntax::SyntaxKind;
+use elp_syntax::SyntaxNode;
+use elp_syntax::TextRange;
+use elp_syntax::TextSize;
+use hir::InFile;
+use hir::Semantic;
+
+use crate::AssistContext;
+use crate::Assists;
+
+// Assist: reorder_record_fields
+//
+// Reorders the fields of a record construction, update or pattern to
+// match the order of the `-record` definition, or alphabetically if
+// so configured. Comments attached to a field move with it.
+//
+// ```
+// -record(person, {name, age}).
+// f() -> #person{age = 42, ~name = "Joe"}.
+// ```
+// ->
+// ```
+// -record(person, {name, age}).
+// f() -> #person{name = "Joe", age = 42}.
+// ```
+pub(crate) fn reorder_record_fields(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let file_id = ctx.file_id();
+    let source = ctx.sema.parse(file_id).value;
+    let alphabetical = ctx.config.record_fields_alphabetical;
+    let (target, reorder) = algo::ancestors_at_offset(source.syntax(), ctx.offset())
+        .filter(is_record)
+        .find_map(|node| {
+            let reorder = Reorder::new(&ctx.sema, file_id, &node, alphabetical)?;
+            Some((node.text_range(), reorder))
+        })?;
+    let text = source.syntax().text().to_string();
+
+    let (label, module_label) = if alphabetical {
+        (
+            "Sort record fields alphabetically",
+            "Sort record fields alphabetically in module",
+        )
+    } else {
+        (
+            "Reorder record fields as in definition",
+            "Reorder record fields as in definition in module",
+        )
+    };
+    acc.add(
+        AssistId("reorder_record_fields", AssistKind::RefactorRewrite),
+        label,
+        target,
+        None,
+        |edit| edit.replace(reorder.range, reorder.render(&text, &[])),
+    );
+
+    let all: Vec<Reorder> = source
+        .syntax()
+        .descendants()
+        .filter(is_record)
+        .filter_map(|node| Reorder::new(&ctx.sema, file_id, &node, alphabetical))
+        .collect();
+    acc.add(
+        AssistId(
+            "reorder_record_fields_in_module",
+            AssistKind::RefactorRewrite,
+        ),
+        module_label,
+        target,
+        None,
+        |edit| {
+            // Records nested in the fields of another one are rendered
+            // as part of it, edits must not overlap.
+            let mut done = TextSize::from(0);
+            for reorder in &all {
+                if reorder.range.start() >= done {
+                    edit.replace(reorder.range, reorder.render(&text, &all));
+                    done = reorder.range.end();
+                }
+            }
+        },
+    )
+}
+
+fn is_record(node: &SyntaxNode) -> bool {
+    matches!(
+        node.kind(),
+        SyntaxKind::RECORD_EXPR | SyntaxKind::RECORD_UPDATE_EXPR
+    )
+}
+
+/// A field of a record expression, together with its comments.
+struct Slot {
+    /// Start of the field, or of the comments on the lines before it.
+    start: TextSize,
+    field: TextRange,
+    /// A comment following the field on the same line.
+    trailing: Option<TextRange>,
+    /// End of the field, its separator and trailing comment.
+    rest: TextSize,
+    /// The field name, unless it is a wildcard or a macro.
+    name: Option<String>,
+}
+
+/// The fields of a record expression which are not in the expected order.
+struct Reorder {
+    /// From the start of the first field up to the closing brace.
+    range: TextRange,
+    slots: Vec<Slot>,
+    /// The slot to move to each position.
+    order: Vec<usize>,
+}
+
+impl Reorder {
+    fn new(
+        sema: &Semantic,
+        file_id: FileId,
+        node: &SyntaxNode,
+        alphabetical: bool,
+    ) -> Option<Reorder> {
+        let record_name = match ast::Expr::cast(node.clone())? {
+            ast::Expr::RecordExpr(record) => record.name()?,
+            ast::Expr::RecordUpdateExpr(record) => record.name()?,
+            _ => return None,
+        };
+
+        let mut slots: Vec<Slot> = Vec::new();
+        let mut leading = None;
+        let mut newline = false;
+        let mut opened = false;
+        let mut end = None;
+        for element in node.children_with_tokens() {
+            match element.kind() {
+                SyntaxKind::ANON_LBRACE => opened = true,
+                _ if !opened => {}
+                SyntaxKind::WHITESPACE => newline |= element.to_string().contains('\n'),
+                SyntaxKind::COMMENT => {
+                    let range = element.text_range();
+                    match slots.last_mut() {
+                        Some(slot) if !newline && slot.trailing.is_none() => {
+                            slot.trailing = Some(range);
+                            slot.rest = range.end();
+                        }
+                        // A comment on the line of the opening brace stays there
+                        None if !newline => {}
+                        _ => {
+                            leading.get_or_insert(range.start());
+                        }
+                    }
+                }
+                SyntaxKind::RECORD_FIELD => {
+                    let field = element.text_range();
+                    let name = match ast::RecordField::cast(element.into_node()?)?.name()? {
+                        ast::Name::Atom(atom) => Some(atom.syntax().text().to_string()),
+                        _ => None,
+                    };
+                    slots.push(Slot {
+                        start: leading.take().unwrap_or(field.start()),
+                        field,
+                        trailing: None,
+                        rest: field.end(),
+                        name,
+                    });
+                    newline = false;
+                }
+                SyntaxKind::ANON_COMMA => {
+                    let slot = slots.last_mut()?;
+                    if slot.trailing.is_some() {
+                        return None;
+                    }
+                    slot.rest = element.text_range().end();
+                    newline = false;
+                }
+                SyntaxKind::ANON_RRACE => {
+                    end = Some(element.text_range().start());
+                    break;
+                }
+                _ => return None,
+            }
+        }
+        if slots.len() < 2 {
+            return None;
+        }
+
+        let mut order: Vec<usize> = (0..slots.len()).collect();
+        if alphabetical {
+            order.sort_by(|&a, &b| match (&slots[a].name, &slots[b].name) {
+                (Some(a), Some(b)) => a.trim_matches('\'').cmp(b.trim_matches('\'')),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            });
+        } else {
+            let def = sema.to_def(InFile::new(file_id, &record_name))?;
+            let declared: Vec<String> = def
+                .field_names(sema.db)
+                .map(|name| name.to_quoted_string())
+                .collect();
+            // Fields unknown to the definition go last
+            order.sort_by_key(|&i| {
+                slots[i]
+                    .name
+                    .as_ref()
+                    .and_then(|name| declared.iter().position(|it| it == name))
+                    .unwrap_or(usize::MAX)
+            });
+        }
+        if order.iter().enumerate().all(|(i, &slot)| i == slot) {
+            return None;
+        }
+
+        Some(Reorder {
+            range: TextRange::new(slots[0].start, end?),
+            slots,
+            order,
+        })
+    }
+
+    /// The new text of the range, rendering the `nested` records
+    /// within the fields too.
+    fn render(&self, text: &str, nested: &[Reorder]) -> String {
+        let mut out = String::new();
+        let last = self.slots.len() - 1;
+        for (i, slot) in self.slots.iter().enumerate() {
+            let moved = &self.slots[self.order[i]];
+            let item = TextRange::new(moved.start, moved.field.end());
+            out.push_str(&render_range(text, item, nested));
+            if i < last {
+                out.push(',');
+            }
+            let next = self
+                .slots
+                .get(i + 1)
+                .map_or(self.range.end(), |next| next.start);
+            let rest = &text[TextRange::new(slot.rest, next)];
+            match moved.trailing {
+                Some(comment) => {
+                    out.push(' ');
+                    out.push_str(&text[comment]);
+                    if rest.contains('\n') {
+                        out.push_str(rest);
+                    } else {
+                        out.push('\n');
+                        out.push_str(line_indent(text, slot.start));
+                    }
+                }
+                None => out.push_str(rest),
+            }
+        }
+        out
+    }
+}
+
+fn render_range(text: &str, range: TextRange, nested: &[Reorder]) -> String {
+    let mut out = String::new();
+    let mut pos = range.start();
+    for reorder in nested {
+        if range.contains_range(reorder.range) && reorder.range.start() >= pos {
+            out.push_str(&text[TextRange::new(pos, reorder.range.start())]);
+            out.push_str(&reorder.render(text, nested));
+            pos = reorder.range.end();
+        }
+    }
+    out.push_str(&text[TextRange::new(pos, range.end())]);
+    out
+}
+
+fn line_indent(text: &str, offset: TextSize) -> &str {
+    let line_start = text[..usize::from(offset)]
+        .rfind('\n')
+        .map_or(0, |it| it + 1);
+    let line = &text[line_start..];
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+    use crate::tests::*;
+    use crate::AssistConfig;
+
+    #[test]
+    fn reorders_construction() {
+        check_assist(
+            reorder_record_fields,
+            "Reorder record fields as in definition",
+            r#"
+-record(person, {name, age, email}).
+f() -> #person{email = "joe@example.com", ~age = 42, name = "Joe"}.
+"#,
+            expect![[r#"
+                -record(person, {name, age, email}).
+                f() -> #person{name = "Joe", age = 42, email = "joe@example.com"}.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn reorders_update_and_pattern() {
+        check_assist(
+            reorder_record_fields,
+            "Reorder record fields as in definition",
+            r#"
+-record(person, {name, age}).
+f(P) -> P#person{age = 42, ~name = "Joe"}.
+"#,
+            expect![[r#"
+                -record(person, {name, age}).
+                f(P) -> P#person{name = "Joe", age = 42}.
+            "#]],
+        );
+        check_assist(
+            reorder_record_fields,
+            "Reorder record fields as in definition",
+            r#"
+-record(person, {name, age}).
+f(#person{age = Age, ~name = Name}) -> {Name, Age}.
+"#,
+            expect![[r#"
+                -record(person, {name, age}).
+                f(#person{name = Name, age = Age}) -> {Name, Age}.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn keeps_attached_comments() {
+        check_assist(
+            reorder_record_fields,
+            "Reorder record fields as in definition",
+            r#"
+-record(person, {name, age, email}).
+f() ->
+    #person{
+        % how old
+        age = 42, % years
+        ~email = "joe@example.com",
+        name = "Joe" % first name
+    }.
+"#,
+            expect![[r#"
+                -record(person, {name, age, email}).
+                f() ->
+                    #person{
+                        name = "Joe", % first name
+                        % how old
+                        age = 42, % years
+                        email = "joe@example.com"
+                    }.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn wildcard_goes_last() {
+        check_assist(
+            reorder_record_fields,
+            "Reorder record fields as in definition",
+            r#"
+-record(person, {name, age, email}).
+f(#person{_ = '_', ~age = A, name = N}) -> {N, A}.
+"#,
+            expect![[r#"
+                -record(person, {name, age, email}).
+                f(#person{name = N, age = A, _ = '_'}) -> {N, A}.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn reorders_module() {
+        check_assist(
+            reorder_record_fields,
+            "Reorder record fields as in definition in module",
+            r#"
+-record(point, {x, y}).
+-record(line, {from, to}).
+f() -> #line{to = #point{y = 1, x = 2}, ~from = #point{y = 3, x = 4}}.
+g(#point{y = Y, x = X}) -> {X, Y}.
+h(#point{x = X, y = Y}) -> {X, Y}.
+"#,
+            expect![[r#"
+                -record(point, {x, y}).
+                -record(line, {from, to}).
+                f() -> #line{from = #point{x = 4, y = 3}, to = #point{x = 2, y = 1}}.
+                g(#point{x = X, y = Y}) -> {X, Y}.
+                h(#point{x = X, y = Y}) -> {X, Y}.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn picks_innermost_unordered_record() {
+        check_assist(
+            reorder_record_fields,
+            "Reorder record fields as in definition",
+            r#"
+-record(point, {x, y}).
+-record(line, {from, to}).
+f() -> #line{to = #point{x = 1, ~y = 2}, from = #point{y = 3, x = 4}}.
+"#,
+            expect![[r#"
+                -record(point, {x, y}).
+                -record(line, {from, to}).
+                f() -> #line{from = #point{y = 3, x = 4}, to = #point{x = 1, y = 2}}.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn sorts_alphabetically() {
+        check_assist_with_config(
+            reorder_record_fields,
+            AssistConfig {
+                record_fields_alphabetical: true,
+                ..TEST_CONFIG
+            },
+            "Sort record fields alphabetically",
+            r#"
+f() -> #person{name = "Joe", ~age = 42, _ = undefined}.
+"#,
+            expect![[r#"
+                f() -> #person{age = 42, name = "Joe", _ = undefined}.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn not_applicable_when_ordered() {
+        check_assist_not_applicable(
+            reorder_record_fields,
+            r#"
+-record(person, {name, age}).
+f() -> #person{name = "Joe", ~age = 42}.
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_for_unknown_record() {
+        check_assist_not_applicable(
+            reorder_record_fields,
+            r#"
+f() -> #person{age = 42, ~name = "Joe"}.
+"#,
+        );
+    }
+}
@@ -0,0 +1,366 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use elp_ide_db::assists::AssistId;
+use elp_ide_db::assists::AssistKind;
+use elp_syntax::ast;
+use elp_syntax::ast::AstNode;
+use elp_syntax::SyntaxKind;
+use elp_syntax::TextRange;
+use elp_syntax::TextSize;
+use fxhash::FxHashMap;
+use hir::db::MinDefDatabase;
+
+use crate::AssistContext;
+use crate::Assists;
+
+// Assist: sort_exports
+//
+// Sorts the functions of an `-export` attribute in the order of their
+// definitions in the file, or alphabetically if so configured, the
+// arities of a function being kept together in increasing order.
+// Comments attached to an entry move with it.
+//
+// ```
+// -export([~bar/0, foo/2, foo/1]).
+// foo(A) -> A.
+// foo(A, B) -> {A, B}.
+// bar() -> ok.
+// ```
+// ->
+// ```
+// -export([foo/1, foo/2, bar/0]).
+// foo(A) -> A.
+// foo(A, B) -> {A, B}.
+// bar() -> ok.
+// ```
+pub(crate) fn sort_exports(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let export = ctx.find_node_at_offset::<ast::ExportAttribute>()?;
+    let alphabetical = ctx.config.exports_alphabetical;
+    let definitions = if alphabetical {
+        FxHashMap::default()
+    } else {
+        definition_order(ctx)
+    };
+    let sort = Sort::new(&export, &definitions, alphabetical)?;
+    let text = ctx
+        .sema
+        .parse(ctx.file_id())
+        .value
+        .syntax()
+        .text()
+        .to_string();
+
+    let label = if alphabetical {
+        "Sort exports alphabetically"
+    } else {
+        "Sort exports as in definitions"
+    };
+    acc.add(
+        AssistId("sort_exports", AssistKind::RefactorRewrite),
+        label,
+        export.syntax().text_range(),
+        None,
+        |edit| edit.replace(sort.range, sort.render(&text)),
+    )
+}
+
+/// The position of the first definition of each function name
+fn definition_order(ctx: &AssistContext) -> FxHashMap<String, usize> {
+    let form_list = ctx.db().file_form_list(ctx.file_id());
+    let mut order = FxHashMap::default();
+    for (idx, (_, function)) in form_list.functions().enumerate() {
+        order
+            .entry(function.name.name().to_quoted_string())
+            .or_insert(idx);
+    }
+    order
+}
+
+/// An entry of the export list, together with its comments.
+struct Slot {
+    /// Start of the entry, or of the comments on the lines before it.
+    start: TextSize,
+    entry: TextRange,
+    /// A comment following the entry on the same line.
+    trailing: Option<TextRange>,
+    /// End of the entry, its separator and trailing comment.
+    rest: TextSize,
+    /// The function name and arity, unless given by a macro.
+    fa: Option<(String, u32)>,
+}
+
+/// The entries of an export list which are not in the expected order.
+struct Sort {
+    /// From the start of the first entry up to the closing bracket.
+    range: TextRange,
+    slots: Vec<Slot>,
+    /// The slot to move to each position.
+    order: Vec<usize>,
+}
+
+impl Sort {
+    fn new(
+        export: &ast::ExportAttribute,
+        definitions: &FxHashMap<String, usize>,
+        alphabetical: bool,
+    ) -> Option<Sort> {
+        let mut slots: Vec<Slot> = Vec::new();
+        let mut leading = None;
+        let mut newline = false;
+        let mut opened = false;
+        let mut end = None;
+        for element in export.syntax().children_with_tokens() {
+            match element.kind() {
+                SyntaxKind::ANON_LBRACK => opened = true,
+                _ if !opened => {}
+                SyntaxKind::WHITESPACE => newline |= element.to_string().contains('\n'),
+                SyntaxKind::COMMENT => {
+                    let range = element.text_range();
+                    match slots.last_mut() {
+                        Some(slot) if !newline && slot.trailing.is_none() => {
+                            slot.trailing = Some(range);
+                            slot.rest = range.end();
+                        }
+                        // A comment on the line of the opening bracket stays there
+                        None if !newline => {}
+                        _ => {
+                            leading.get_or_insert(range.start());
+                        }
+                    }
+                }
+                SyntaxKind::FA => {
+                    let entry = element.text_range();
+                    let fa = ast::Fa::cast(element.into_node()?)?;
+                    slots.push(Slot {
+                        start: leading.take().unwrap_or(entry.start()),
+                        entry,
+                        trailing: None,
+                        rest: entry.end(),
+                        fa: name_arity(&fa),
+                    });
+                    newline = false;
+                }
+                SyntaxKind::ANON_COMMA => {
+                    let slot = slots.last_mut()?;
+                    if slot.trailing.is_some() {
+                        return None;
+                    }
+                    slot.rest = element.text_range().end();
+                    newline = false;
+                }
+                SyntaxKind::ANON_RBRACK => {
+                    end = Some(element.text_range().start());
+                    break;
+                }
+                _ => return None,
+            }
+        }
+        if slots.len() < 2 {
+            return None;
+        }
+
+        let mut order: Vec<usize> = (0..slots.len()).collect();
+        // Entries given by macros, or of functions not defined in the
+        // file, go last
+        order.sort_by_key(|&i| match &slots[i].fa {
+            Some((name, arity)) if alphabetical => (0, name.trim_matches('\'').to_string(), *arity),
+            Some((name, arity)) => match definitions.get(name) {
+                Some(idx) => (*idx, String::new(), *arity),
+                None => (usize::MAX, String::new(), 0),
+            },
+            None => (usize::MAX, String::new(), 0),
+        });
+        if order.iter().enumerate().all(|(i, &slot)| i == slot) {
+            return None;
+        }
+
+        Some(Sort {
+            range: TextRange::new(slots[0].start, end?),
+            slots,
+            order,
+        })
+    }
+
+    /// The new text of the range
+    fn render(&self, text: &str) -> String {
+        let mut out = String::new();
+        let last = self.slots.len() - 1;
+        for (i, slot) in self.slots.iter().enumerate() {
+            let moved = &self.slots[self.order[i]];
+            out.push_str(&text[TextRange::new(moved.start, moved.entry.end())]);
+            if i < last {
+                out.push(',');
+            }
+            let next = self
+                .slots
+                .get(i + 1)
+                .map_or(self.range.end(), |next| next.start);
+            let rest = &text[TextRange::new(slot.rest, next)];
+            match moved.trailing {
+                Some(comment) => {
+                    out.push(' ');
+                    out.push_str(&text[comment]);
+                    if rest.contains('\n') {
+                        out.push_str(rest);
+                    } else {
+                        out.push('\n');
+                        out.push_str(line_indent(text, slot.start));
+                    }
+                }
+                None => out.push_str(rest),
+            }
+        }
+        out
+    }
+}
+
+fn name_arity(fa: &ast::Fa) -> Option<(String, u32)> {
+    let name = match fa.fun()? {
+        ast::Name::Atom(atom) => atom.syntax().text().to_string(),
+        _ => return None,
+    };
+    let arity = fa
+        .arity()?
+        .value()?
+        .syntax()
+        .text()
+        .to_string()
+        .parse()
+        .ok()?;
+    Some((name, arity))
+}
+
+fn line_indent(text: &str, offset: TextSize) -> &str {
+    let line_start = text[..usize::from(offset)]
+        .rfind('\n')
+        .map_or(0, |it| it + 1);
+    let line = &text[line_start..];
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+    use crate::tests::*;
+    use crate::AssistConfig;
+
+    #[test]
+    fn sorts_as_in_definitions() {
+        check_assist(
+            sort_exports,
+            "Sort exports as in definitions",
+            r#"
+-module(main).
+-export([~bar/0, foo/2, baz/1, foo/1]).
+foo(A) -> A.
+bar() -> ok.
+foo(A, B) -> {A, B}.
+baz(_) -> ok.
+"#,
+            expect![[r#"
+                -module(main).
+                -export([foo/1, foo/2, bar/0, baz/1]).
+                foo(A) -> A.
+                bar() -> ok.
+                foo(A, B) -> {A, B}.
+                baz(_) -> ok.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn keeps_attached_comments() {
+        check_assist(
+            sort_exports,
+            "Sort exports as in definitions",
+            r#"
+-module(main).
+-export([
+    % for tests
+    ~bar/0,
+    foo/1 % the API
+]).
+foo(A) -> A.
+bar() -> ok.
+"#,
+            expect![[r#"
+                -module(main).
+                -export([
+                    foo/1, % the API
+                    % for tests
+                    bar/0
+                ]).
+                foo(A) -> A.
+                bar() -> ok.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn undefined_functions_go_last() {
+        check_assist(
+            sort_exports,
+            "Sort exports as in definitions",
+            r#"
+-module(main).
+-export([~missing/0, bar/0, foo/0]).
+foo() -> ok.
+bar() -> ok.
+"#,
+            expect![[r#"
+                -module(main).
+                -export([foo/0, bar/0, missing/0]).
+                foo() -> ok.
+                bar() -> ok.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn sorts_alphabetically() {
+        check_assist_with_config(
+            sort_exports,
+            AssistConfig {
+                exports_alphabetical: true,
+                ..TEST_CONFIG
+            },
+            "Sort exports alphabetically",
+            r#"
+-module(main).
+-export([~foo/2, bar/0, foo/1]).
+foo(A) -> A.
+foo(A, B) -> {A, B}.
+bar() -> ok.
+"#,
+            expect![[r#"
+                -module(main).
+                -export([bar/0, foo/1, foo/2]).
+                foo(A) -> A.
+                foo(A, B) -> {A, B}.
+                bar() -> ok.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn not_applicable_when_sorted() {
+        check_assist_not_applicable(
+            sort_exports,
+            r#"
+-module(main).
+-export([~foo/1, bar/0]).
+foo(A) -> A.
+bar() -> ok.
+"#,
+        );
+    }
+}
@@ -0,0 +1,353 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use elp_ide_db::assists::AssistId;
+use elp_ide_db::assists::AssistKind;
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::elp_base_db::SourceDatabase;
+use elp_ide_db::SymbolDefinition;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use elp_syntax::NodeOrToken;
+use elp_syntax::SyntaxElement;
+use elp_syntax::SyntaxKind;
+use elp_syntax::SyntaxNode;
+use elp_syntax::TextRange;
+use hir::db::MinDefDatabase;
+use hir::Name;
+use hir::NameArity;
+use hir::Semantic;
+
+use crate::AssistContext;
+use crate::Assists;
+
+// Assist: split_exports
+//
+// Reorganize the export attributes of a module into an API block, a
+// block per implemented behaviour, and a block for the functions only
+// used by tests, each under a comment header. Exports in conditional
+// sections are left alone. Applying it again changes nothing.
+//
+// ```
+// -module(my_server).
+// -behaviour(gen_server).
+// -export([~start_link/0, init/1, handle_call/3]).
+// ```
+// ->
+// ```
+// -module(my_server).
+// -behaviour(gen_server).
+// %% API
+// -export([start_link/0]).
+//
+// %% Callbacks for `gen_server`
+// -export([init/1, handle_call/3]).
+// ```
+pub(crate) fn split_exports(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let file_id = ctx.file_id();
+    let form_list = ctx.db().file_form_list(file_id);
+    let source = ctx.sema.parse(file_id).value;
+    let exports: Vec<(Vec<NameArity>, ast::ExportAttribute)> = form_list
+        .exports()
+        .filter(|(_, export)| export.cond.is_none())
+        .map(|(_, export)| {
+            let names = export
+                .entries
+                .clone()
+                .map(|fa| form_list[fa].name.clone())
+                .collect();
+            (names, export.form_id.get(&source))
+        })
+        .collect();
+    let target = exports
+        .iter()
+        .map(|(_, attr)| attr.syntax().text_range())
+        .find(|range| range.contains_inclusive(ctx.offset()))?;
+    // The attributes are rewritten, comments inside them would be lost
+    if exports.iter().any(|(_, attr)| has_comment(attr.syntax())) {
+        return None;
+    }
+
+    let behaviours: Vec<(Name, Vec<NameArity>)> = form_list
+        .behaviour_attributes()
+        .filter_map(|(_, behaviour)| {
+            let module = ctx
+                .sema
+                .resolve_module_name(file_id, behaviour.name.as_str())?;
+            let def_map = ctx.sema.def_map(module.file.file_id);
+            let callbacks = def_map.get_callbacks().keys().cloned().collect();
+            Some((behaviour.name.clone(), callbacks))
+        })
+        .collect();
+    let is_test_file = ctx.db().is_test_suite_or_test_helper(file_id) == Some(true);
+
+    let mut api = Vec::new();
+    let mut callbacks = vec![Vec::new(); behaviours.len()];
+    let mut tests = Vec::new();
+    for name in exports.iter().flat_map(|(names, _)| names) {
+        if api.contains(name)
+            || tests.contains(name)
+            || callbacks.iter().any(|funs: &Vec<_>| funs.contains(name))
+        {
+            continue;
+        }
+        if let Some(idx) = behaviours.iter().position(|(_, funs)| funs.contains(name)) {
+            callbacks[idx].push(name.clone());
+        } else if !is_test_file && only_used_by_tests(&ctx.sema, file_id, name) {
+            tests.push(name.clone());
+        } else {
+            api.push(name.clone());
+        }
+    }
+
+    let mut blocks = vec![("API".to_string(), api)];
+    for ((behaviour, _), funs) in behaviours.iter().zip(callbacks) {
+        blocks.push((format!("Callbacks for `{behaviour}`"), funs));
+    }
+    blocks.push(("Exported for tests".to_string(), tests));
+    let text = blocks
+        .iter()
+        .filter(|(_, funs)| !funs.is_empty())
+        .map(|(header, funs)| format!("%% {header}\n{}", render_export(funs)))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let ranges: Vec<(SyntaxElement, TextRange)> = exports
+        .iter()
+        .map(|(_, attr)| {
+            let first = with_header(attr.syntax());
+            let range =
+                TextRange::new(first.text_range().start(), attr.syntax().text_range().end());
+            (first, range)
+        })
+        .collect();
+    let (first, last) = (ranges.first()?.1, ranges.last()?.1);
+    let current = &source.syntax().text().to_string()[TextRange::new(first.start(), last.end())];
+    if current == text {
+        return None;
+    }
+
+    acc.add(
+        AssistId("split_exports", AssistKind::RefactorRewrite),
+        "Split exports by category",
+        target,
+        None,
+        |edit| {
+            edit.replace(first, text);
+            for (element, range) in ranges.iter().skip(1) {
+                // Remove the line the attribute was on
+                let start = match element.prev_sibling_or_token() {
+                    Some(NodeOrToken::Token(token)) if token.kind() == SyntaxKind::WHITESPACE => {
+                        token.text_range().start()
+                    }
+                    _ => range.start(),
+                };
+                edit.delete(TextRange::new(start, range.end()));
+            }
+        },
+    )
+}
+
+/// Whether the function is used by other modules, all of them test
+/// suites or test helpers.
+fn only_used_by_tests(sema: &Semantic, file_id: FileId, name: &NameArity) -> bool {
+    let def = match sema.def_map(file_id).get_function(name) {
+        Some(def) => def.clone(),
+        None => return false,
+    };
+    let usages = SymbolDefinition::Function(def).usages(sema).all();
+    let mut used_by_tests = false;
+    for (usage_file_id, _) in usages.iter() {
+        if usage_file_id == file_id {
+            continue;
+        }
+        if sema.db.is_test_suite_or_test_helper(usage_file_id) != Some(true) {
+            return false;
+        }
+        used_by_tests = true;
+    }
+    used_by_tests
+}
+
+fn render_export(funs: &[NameArity]) -> String {
+    let single_line = format!(
+        "-export([{}]).",
+        funs.iter()
+            .map(|fun| fun.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    if single_line.len() <= 80 {
+        return single_line;
+    }
+    let lines = funs
+        .iter()
+        .map(|fun| format!("    {fun}"))
+        .collect::<Vec<_>>()
+        .join(",\n");
+    format!("-export([\n{lines}\n]).")
+}
+
+fn has_comment(node: &SyntaxNode) -> bool {
+    node.descendants_with_tokens()
+        .any(|it| it.kind() == SyntaxKind::COMMENT)
+}
+
+/// The first element of the attribute, which is a header comment on
+/// the line before it if there is one, as generated by this assist.
+fn with_header(node: &SyntaxNode) -> SyntaxElement {
+    let attr = NodeOrToken::Node(node.clone());
+    let whitespace = match node.prev_sibling_or_token() {
+        Some(NodeOrToken::Token(token)) if token.kind() == SyntaxKind::WHITESPACE => token,
+        _ => return attr,
+    };
+    if whitespace.text().matches('\n').count() != 1 {
+        return attr;
+    }
+    match whitespace.prev_sibling_or_token() {
+        Some(NodeOrToken::Node(comment))
+            if comment.kind() == SyntaxKind::COMMENT && is_header(&comment.text().to_string()) =>
+        {
+            NodeOrToken::Node(comment)
+        }
+        _ => attr,
+    }
+}
+
+fn is_header(comment: &str) -> bool {
+    match comment.strip_prefix("%% ") {
+        Some(header) => {
+            header == "API"
+                || header == "Exported for tests"
+                || (header.starts_with("Callbacks for `") && header.ends_with('`'))
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn split_by_category() {
+        check_assist(
+            split_exports,
+            "Split exports by category",
+            r#"
+//- /src/my_server.erl
+-module(my_server).
+-behaviour(my_behaviour).
+-export([~start_link/0, init/1]).
+-export([handle/2, helper/1]).
+
+start_link() -> ok.
+init(X) -> X.
+handle(X, Y) -> {X, Y}.
+helper(X) -> X.
+//- /src/my_behaviour.erl
+-module(my_behaviour).
+-callback init(term()) -> term().
+-callback handle(term(), term()) -> term().
+//- /test/my_server_SUITE.erl extra:test
+-module(my_server_SUITE).
+t() -> my_server:helper(1).
+"#,
+            expect![[r#"
+                -module(my_server).
+                -behaviour(my_behaviour).
+                %% API
+                -export([start_link/0]).
+
+                %% Callbacks for `my_behaviour`
+                -export([init/1, handle/2]).
+
+                %% Exported for tests
+                -export([helper/1]).
+
+                start_link() -> ok.
+                init(X) -> X.
+                handle(X, Y) -> {X, Y}.
+                helper(X) -> X.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn already_split() {
+        check_assist_not_applicable(
+            split_exports,
+            r#"
+-module(my_server).
+%% API
+-export([~start_link/0, stop/0]).
+
+start_link() -> ok.
+stop() -> ok.
+"#,
+        )
+    }
+
+    #[test]
+    fn keeps_conditional_exports() {
+        check_assist(
+            split_exports,
+            "Split exports by category",
+            r#"
+-module(my_server).
+-export([~start_link/0]).
+-ifdef(TEST).
+-export([helper/0]).
+-endif.
+-export([stop/0]).
+
+start_link() -> ok.
+stop() -> ok.
+helper() -> ok.
+"#,
+            expect![[r#"
+                -module(my_server).
+                %% API
+                -export([start_link/0, stop/0]).
+                -ifdef(TEST).
+                -export([helper/0]).
+                -endif.
+
+                start_link() -> ok.
+                stop() -> ok.
+                helper() -> ok.
+            "#]],
+        )
+    }
+
+    #[test]
+    fn long_export_list() {
+        check_assist(
+            split_exports,
+            "Split exports by category",
+            r#"
+-module(my_server).
+-export([~first_function/0, second_function/0, third_function/0, fourth_function/0]).
+"#,
+            expect![[r#"
+                -module(my_server).
+                %% API
+                -export([
+                    first_function/0,
+                    second_function/0,
+                    third_function/0,
+                    fourth_function/0
+                ]).
+            "#]],
+        )
+    }
+}
@@ -29,6 +29,8 @@ use fxhash::FxHashSet;
 use hir::Clause;
 use hir::CompileOption;
 use hir::FormList;
+use hir::FunctionDef;
+use hir::InFile;
 use hir::InFileAstPtr;
 use hir::InFunctionBody;
 use hir::NameArity;
@@ -167,6 +169,7 @@ pub(crate) fn parens_needed(expr: &ast::Expr, var: &ast::Var) -> Option<(TextRan
             | ast::Expr::ExprMax(ast::ExprMax::ListComprehension(_))
             | ast::Expr::ExprMax(ast::ExprMax::MacroCallExpr(_))
             | ast::Expr::ExprMax(ast::ExprMax::MacroString(_))
+            | ast::Expr::ExprMax(ast::ExprMax::MapComprehension(_))
             | ast::Expr::ExprMax(ast::ExprMax::ParenExpr(_))
             | ast::Expr::ExprMax(ast::ExprMax::ReceiveExpr(_))
             | ast::Expr::ExprMax(ast::ExprMax::String(_))
@@ -283,13 +286,23 @@ pub(crate) fn ranges_for_delete_function(
         },
         _ => None,
     }?;
+    Some(function_ranges(ctx, &function_def, ast_fun))
+}
 
-    let def_map = ctx.sema.def_map(ctx.file_id());
+/// The ranges of a function, its spec and its edoc comments, each
+/// extended to the end of its line.
+pub(crate) fn function_ranges(
+    ctx: &AssistContext,
+    function_def: &FunctionDef,
+    ast_fun: &ast::FunDecl,
+) -> FunctionRanges {
+    let file_id = function_def.file.file_id;
+    let def_map = ctx.sema.def_map(file_id);
     let spec = def_map.get_spec(&function_def.function.name);
 
     let edoc_comments: Vec<InFileAstPtr<ast::Comment>> = if let Some(file_edoc) =
         ctx.sema.form_edoc_comments(InFileAstPtr::new(
-            ctx.file_id(),
+            file_id,
             AstPtr::new(&ast::Form::FunDecl(ast_fun.clone())),
         )) {
         file_edoc.comments()
@@ -310,14 +323,14 @@ pub(crate) fn ranges_for_delete_function(
         extend_form_range_for_delete(ast_spec.syntax())
     });
 
-    Some(FunctionRanges {
+    FunctionRanges {
         function: extend_form_range_for_delete(ast_fun.syntax()),
         spec: spec_range,
         edoc,
-    })
+    }
 }
 
-fn extend_form_range_for_delete(syntax: &SyntaxNode) -> TextRange {
+pub(crate) fn extend_form_range_for_delete(syntax: &SyntaxNode) -> TextRange {
     let orig_range = syntax.text_range();
     let start = orig_range.start();
     let end = match skip_trailing_newline(syntax) {
@@ -545,3 +558,98 @@ fn add_to_export(
         Some((range.end(), export_text.clone()))
     }
 }
+
+// ---------------------------------------------------------------------
+
+/// The fields of a record definition which a record pattern does not
+/// mention.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OmittedRecordFields {
+    /// The closing brace of the pattern.
+    pub rbrace: TextRange,
+    /// Where to add the omitted fields: after the last field of the
+    /// pattern, or after the opening brace.
+    pub insert_at: TextSize,
+    /// Whether the pattern has any field.
+    pub has_fields: bool,
+    /// The omitted fields, in the order of the record definition.
+    pub fields: Vec<String>,
+}
+
+impl OmittedRecordFields {
+    /// The text adding the omitted fields as `field = _`.
+    pub fn render(&self) -> String {
+        let fields = self
+            .fields
+            .iter()
+            .map(|field| format!("{field} = _"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if self.has_fields {
+            format!(", {fields}")
+        } else {
+            fields
+        }
+    }
+}
+
+/// The fields omitted by `record`, if it is a pattern of a known
+/// record. Patterns with a `_ = ...` field, or fields given by macros,
+/// are ignored, since we can't tell which fields they match.
+pub fn omitted_record_fields(
+    sema: &Semantic,
+    file_id: FileId,
+    record: &ast::RecordExpr,
+) -> Option<OmittedRecordFields> {
+    let function_id = sema.find_enclosing_function(file_id, record.syntax())?;
+    let body = sema.to_function_body(InFile::new(file_id, function_id));
+    let expr = ast::Expr::RecordExpr(record.clone());
+    body.pat_id_ast(sema.db, InFile::new(file_id, &expr))?;
+
+    let def = sema.to_def(InFile::new(file_id, &record.name()?))?;
+    let mut present = Vec::new();
+    let mut insert_at = None;
+    for field in record.fields() {
+        match field.name()? {
+            ast::Name::Atom(atom) => present.push(atom.syntax().text().to_string()),
+            _ => return None,
+        }
+        insert_at = Some(field.syntax().text_range().end());
+    }
+    let fields: Vec<String> = def
+        .field_names(sema.db)
+        .map(|name| name.to_quoted_string())
+        .filter(|name| !present.contains(name))
+        .collect();
+    if fields.is_empty() {
+        return None;
+    }
+    let has_fields = insert_at.is_some();
+    let insert_at = match insert_at {
+        Some(insert_at) => insert_at,
+        None => find_next_token(record.syntax(), SyntaxKind::ANON_LBRACE)?.end(),
+    };
+    Some(OmittedRecordFields {
+        rbrace: find_next_token(record.syntax(), SyntaxKind::ANON_RRACE)?,
+        insert_at,
+        has_fields,
+        fields,
+    })
+}
+
+// ---------------------------------------------------------------------
+
+/// Whether a catch clause catches any exception, i.e. is `_:_ ->` or
+/// `_:_:Stacktrace ->` without a guard.
+pub fn is_catch_all(clause: &ast::CatchClause) -> bool {
+    let is_wildcard = |var: &ast::Var| var.syntax().text() == "_";
+    let class = match clause.class().and_then(|class| class.class()) {
+        Some(ast::Name::Var(var)) => is_wildcard(&var),
+        _ => false,
+    };
+    let reason = match clause.pat() {
+        Some(ast::CatchPat::ExprMax(ast::ExprMax::Var(var))) => is_wildcard(&var),
+        _ => false,
+    };
+    class && reason && clause.guard().is_none()
+}
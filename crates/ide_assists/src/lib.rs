@@ -68,18 +68,29 @@ mod handlers {
     mod add_edoc;
     mod add_format;
     mod add_impl;
+    mod add_omitted_record_fields;
     mod add_spec;
     mod bump_variables;
+    mod convert_foldl_accumulator;
+    mod convert_record_to_map;
     mod create_function;
+    mod create_test;
     mod delete_function;
     mod export_function;
+    mod extract_constant;
     mod extract_function;
     mod extract_variable;
     mod flip_sep;
+    mod gen_server_templates;
     mod ignore_variable;
     mod implement_behaviour;
     mod inline_function;
     mod inline_local_variable;
+    mod move_function;
+    mod narrow_catch_all;
+    mod reorder_record_fields;
+    mod sort_exports;
+    mod split_exports;
 
     pub(crate) fn all() -> &'static [Handler] {
         &[
@@ -87,18 +98,32 @@ mod handlers {
             add_edoc::add_edoc,
             add_format::add_format,
             add_impl::add_impl,
+            add_omitted_record_fields::add_omitted_record_fields,
             add_spec::add_spec,
             bump_variables::bump_variables,
+            convert_foldl_accumulator::convert_foldl_acc_to_map,
+            convert_foldl_accumulator::convert_foldl_acc_to_record,
+            convert_record_to_map::convert_record_to_map,
             create_function::create_function,
+            create_test::create_test,
             delete_function::delete_function,
             export_function::export_function,
+            extract_constant::extract_constant,
             extract_function::extract_function,
             extract_variable::extract_variable,
             flip_sep::flip_sep,
+            gen_server_templates::add_code_change,
+            gen_server_templates::add_format_status,
+            gen_server_templates::add_handle_info_fallthrough,
             ignore_variable::ignore_variable,
             implement_behaviour::implement_behaviour,
             inline_function::inline_function,
             inline_local_variable::inline_local_variable,
+            move_function::move_function,
+            narrow_catch_all::narrow_catch_all,
+            reorder_record_fields::reorder_record_fields,
+            sort_exports::sort_exports,
+            split_exports::split_exports,
             // These are manually sorted for better priorities. By default,
             // priority is determined by the size of the target range (smaller
             // target wins). If the ranges are equal, position in this list is
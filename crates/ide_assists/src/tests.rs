@@ -18,6 +18,8 @@ use elp_ide_db::assists::AssistUserInputType;
 use elp_ide_db::elp_base_db::fixture::extract_annotations;
 use elp_ide_db::elp_base_db::fixture::remove_annotations;
 use elp_ide_db::elp_base_db::fixture::WithFixture;
+use elp_ide_db::elp_base_db::AnchoredPathBuf;
+use elp_ide_db::elp_base_db::FileId;
 use elp_ide_db::elp_base_db::FileRange;
 use elp_ide_db::elp_base_db::SourceDatabase;
 use elp_ide_db::elp_base_db::SourceDatabaseExt;
@@ -45,6 +47,9 @@ use crate::Assists;
 pub(crate) const TEST_CONFIG: AssistConfig = AssistConfig {
     snippet_cap: SnippetCap::new(true),
     allowed: None,
+    experimental: true,
+    record_fields_alphabetical: false,
+    exports_alphabetical: false,
 };
 
 #[track_caller]
@@ -56,6 +61,26 @@ pub(crate) fn check_assist(
 ) {
     check(
         assist,
+        TEST_CONFIG,
+        fixture_before,
+        ExpectedResult::After(fixture_after),
+        Some(assist_label),
+        true,
+        None,
+    );
+}
+
+#[track_caller]
+pub(crate) fn check_assist_with_config(
+    assist: Handler,
+    config: AssistConfig,
+    assist_label: &str,
+    fixture_before: &str,
+    fixture_after: Expect,
+) {
+    check(
+        assist,
+        config,
         fixture_before,
         ExpectedResult::After(fixture_after),
         Some(assist_label),
@@ -74,6 +99,7 @@ pub(crate) fn check_assist_with_user_input(
 ) {
     check(
         assist,
+        TEST_CONFIG,
         fixture_before,
         ExpectedResult::After(fixture_after),
         Some(assist_label),
@@ -93,6 +119,7 @@ pub(crate) fn check_assist_expect_parse_error(
 ) {
     check(
         assist,
+        TEST_CONFIG,
         fixture_before,
         ExpectedResult::After(fixture_after),
         Some(assist_label),
@@ -105,6 +132,7 @@ pub(crate) fn check_assist_expect_parse_error(
 pub(crate) fn check_assist_not_applicable(assist: Handler, ra_fixture: &str) {
     check(
         assist,
+        TEST_CONFIG,
         ra_fixture,
         ExpectedResult::NotApplicable,
         None,
@@ -113,6 +141,58 @@ pub(crate) fn check_assist_not_applicable(assist: Handler, ra_fixture: &str) {
     );
 }
 
+/// The labels of the disabled assists offered, with the reasons they
+/// can't make any change.
+#[track_caller]
+pub(crate) fn check_assist_disabled(assist: Handler, ra_fixture: &str, expect: Expect) {
+    check_disabled(assist, ra_fixture, None, expect)
+}
+
+#[track_caller]
+pub(crate) fn check_assist_disabled_with_user_input(
+    assist: Handler,
+    user_input: &str,
+    ra_fixture: &str,
+    expect: Expect,
+) {
+    let user_input = AssistUserInput {
+        input_type: AssistUserInputType::Atom,
+        value: user_input.to_string(),
+    };
+    check_disabled(assist, ra_fixture, Some(user_input), expect)
+}
+
+#[track_caller]
+fn check_disabled(
+    assist: Handler,
+    ra_fixture: &str,
+    user_input: Option<AssistUserInput>,
+    expect: Expect,
+) {
+    let (db, file_id, range_or_offset) = RootDatabase::with_range_or_offset(ra_fixture);
+    let frange = FileRange {
+        file_id,
+        range: range_or_offset.into(),
+    };
+    let ctx = AssistContext::new(&db, &TEST_CONFIG, frange, &[], user_input);
+    let mut acc = Assists::new(&ctx, AssistResolveStrategy::None);
+    assist(&mut acc, &ctx);
+    let labels = acc
+        .finish()
+        .iter()
+        .map(|assist| {
+            assert!(assist.source_change.is_none());
+            let reason = assist
+                .disabled
+                .as_deref()
+                .expect("assist should be disabled");
+            format!("{}: {}", assist.label, reason)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    expect.assert_eq(&labels);
+}
+
 enum ExpectedResult {
     NotApplicable,
     After(Expect),
@@ -123,6 +203,7 @@ pub const SNIPPET_CURSOR_MARKER: &str = "$0";
 #[track_caller]
 fn check(
     handler: Handler,
+    config: AssistConfig,
     before: &str,
     expected: ExpectedResult,
     assist_label: Option<&str>,
@@ -137,7 +218,6 @@ fn check(
     };
 
     let sema = &db;
-    let config = TEST_CONFIG;
     let context_diagnostics = extract_annotations(&*db.file_text(file_with_caret_id));
     let mut diagnostics = vec![];
     for (range, text) in &context_diagnostics {
@@ -205,12 +285,18 @@ fn check(
             let source_change = assist
                 .source_change
                 .expect("Assist did not contain any source changes");
-            assert!(!source_change.source_file_edits.is_empty());
+            assert!(
+                !source_change.source_file_edits.is_empty()
+                    || !source_change.file_system_edits.is_empty()
+            );
             let skip_header = source_change.source_file_edits.len() == 1
                 && source_change.file_system_edits.len() == 0;
 
             let mut buf = String::new();
-            for (file_id, edit) in source_change.source_file_edits {
+            let mut source_file_edits: Vec<_> =
+                source_change.source_file_edits.into_iter().collect();
+            source_file_edits.sort_by_key(|(file_id, _)| *file_id);
+            for (file_id, edit) in source_file_edits {
                 let mut text = db.file_text(file_id).as_ref().to_owned();
                 edit.apply(&mut text);
                 if !skip_header {
@@ -222,25 +308,42 @@ fn check(
                 buf.push_str(&text);
             }
 
+            let file_path = |file_id: FileId| {
+                let sr = db.file_source_root(file_id);
+                let sr = db.source_root(sr);
+                sr.path_for_file(&file_id).unwrap().clone()
+            };
+            let anchored_path = |dst: &AnchoredPathBuf| {
+                let mut base = file_path(dst.anchor);
+                base.pop();
+                base.join(&dst.path).unwrap()
+            };
             for file_system_edit in source_change.file_system_edits {
-                if let FileSystemEdit::CreateFile {
-                    dst,
-                    initial_contents,
-                } = file_system_edit
-                {
-                    let sr = db.file_source_root(dst.anchor);
-                    let sr = db.source_root(sr);
-                    let mut base = sr.path_for_file(&dst.anchor).unwrap().clone();
-                    base.pop();
-                    let created_file_path = format!("{}{}", base.to_string(), &dst.path[1..]);
-                    format_to!(buf, "//- {}\n", created_file_path);
-                    buf.push_str(&initial_contents);
+                match file_system_edit {
+                    FileSystemEdit::CreateFile {
+                        dst,
+                        initial_contents,
+                    } => {
+                        format_to!(buf, "//- {}\n", anchored_path(&dst));
+                        buf.push_str(&initial_contents);
+                    }
+                    FileSystemEdit::DeleteFile { file } => {
+                        format_to!(buf, "//- delete {}\n", file_path(file));
+                    }
+                    FileSystemEdit::MoveFile { src, dst } => {
+                        format_to!(buf, "//- move {} {}\n", file_path(src), anchored_path(&dst));
+                    }
                 }
             }
 
             if check_parse_error {
-                // Check that we have introduced a syntactically valid result
-                let text = remove_annotations(Some(SNIPPET_CURSOR_MARKER), &buf);
+                // Check that we have introduced a syntactically valid result,
+                // leaving out the headers of the files
+                let text: String = remove_annotations(Some(SNIPPET_CURSOR_MARKER), &buf)
+                    .lines()
+                    .filter(|line| !line.starts_with("//- "))
+                    .map(|line| format!("{line}\n"))
+                    .collect();
                 let parse = SourceFile::parse_text(&text);
                 let errors = parse.errors();
                 if !errors.is_empty() {
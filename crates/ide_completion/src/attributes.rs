@@ -48,6 +48,8 @@ pub(crate) fn add_completions(
                             kind: Kind::Behavior,
                             contents: Contents::SameAsLabel,
                             position: None,
+                            detail: None,
+                            additional_edits: Vec::new(),
                             sort_text: None,
                             deprecated: false,
                         })
@@ -73,6 +75,8 @@ pub(crate) fn add_completions(
                             module.to_quoted_string()
                         )),
                         position: None,
+                        detail: None,
+                        additional_edits: Vec::new(),
                         sort_text: None,
                         deprecated: false,
                     });
@@ -86,6 +90,8 @@ pub(crate) fn add_completions(
                     label: "-typing([eqwalizer]).".to_string(),
                     contents: Contents::Snippet("typing([eqwalizer]).".to_string()),
                     position: None,
+                    detail: None,
+                    additional_edits: Vec::new(),
                     sort_text: None,
                     deprecated: false,
                 });
@@ -26,12 +26,16 @@ pub enum Ctx {
     Type,
     Export,
     ExportType,
+    /// The field names of a record construction or update
+    RecordFields,
     Other,
 }
 
 impl Ctx {
     pub fn new(node: &SyntaxNode, offset: TextSize) -> Self {
-        if Self::is_atom_colon(node, offset) && Self::is_expr(node, offset) {
+        if Self::is_record_fields(node, offset) {
+            Self::RecordFields
+        } else if Self::is_atom_colon(node, offset) && Self::is_expr(node, offset) {
             Self::Expr
         } else if Self::is_export(node, offset) {
             Self::Export
@@ -73,6 +77,44 @@ impl Ctx {
     fn is_export_type(node: &SyntaxNode, offset: TextSize) -> bool {
         algo::find_node_at_offset::<ast::ExportTypeAttribute>(node, offset).is_some()
     }
+    /// In the braces of a record construction or update, but not in
+    /// the value of a field
+    fn is_record_fields(node: &SyntaxNode, offset: TextSize) -> bool {
+        for ancestor in algo::ancestors_at_offset(node, offset) {
+            if let Some(field) = ast::RecordField::cast(ancestor.clone()) {
+                let in_name = field.name().map_or(false, |name| {
+                    name.syntax().text_range().contains_inclusive(offset)
+                });
+                return in_name
+                    && field
+                        .syntax()
+                        .parent()
+                        .map_or(false, |parent| Self::is_record_construction(&parent));
+            }
+            if Self::is_record_construction(&ancestor) {
+                return Self::in_record_braces(&ancestor, offset);
+            }
+        }
+        false
+    }
+    fn is_record_construction(node: &SyntaxNode) -> bool {
+        ast::RecordExpr::can_cast(node.kind()) || ast::RecordUpdateExpr::can_cast(node.kind())
+    }
+    /// Whether the offset is between the braces of the record
+    /// construction or update, the closing one being optional
+    pub(crate) fn in_record_braces(record: &SyntaxNode, offset: TextSize) -> bool {
+        let mut lbrace_end = None;
+        let mut rbrace_start = None;
+        for it in record.children_with_tokens() {
+            match it.kind() {
+                SyntaxKind::ANON_LBRACE => lbrace_end = Some(it.text_range().end()),
+                SyntaxKind::ANON_RBRACE => rbrace_start = Some(it.text_range().start()),
+                _ => (),
+            }
+        }
+        lbrace_end.map_or(false, |end| end <= offset)
+            && rbrace_start.map_or(true, |start| offset <= start)
+    }
     fn is_pp_define(node: &SyntaxNode, offset: TextSize) -> bool {
         algo::find_node_at_offset::<ast::PpDefine>(node, offset).is_some()
     }
@@ -443,6 +485,36 @@ mod ctx_tests {
         );
     }
 
+    #[test]
+    fn test_record_fields_ctx() {
+        assert_eq!(
+            ctx(r#"
+        -module(sample).
+        test() ->
+            #rec{~}.
+        "#),
+            Ctx::RecordFields
+        );
+
+        assert_eq!(
+            ctx(r#"
+        -module(sample).
+        test(X) ->
+            X#rec{a = 1, b~}.
+        "#),
+            Ctx::RecordFields
+        );
+
+        assert_eq!(
+            ctx(r#"
+        -module(sample).
+        test(X) ->
+            #rec{a = ~X}.
+        "#),
+            Ctx::Expr
+        );
+    }
+
     #[test]
     fn test_type_param_ctx() {
         assert_eq!(
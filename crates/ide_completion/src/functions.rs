@@ -7,9 +7,19 @@
  * of this source tree.
  */
 
+use std::sync::Arc;
+
+use elp_base_db::AppType;
 use elp_base_db::FileId;
 use elp_base_db::FilePosition;
+use elp_base_db::ModuleName;
+use elp_base_db::SourceDatabase;
+use elp_ide_db::SymbolDefinition;
+use elp_ide_db::SymbolIndex;
 use elp_syntax::AstNode;
+use elp_syntax::SyntaxKind;
+use elp_syntax::TextSize;
+use hir::db::MinDefDatabase;
 use hir::FunctionDef;
 use hir::NameArity;
 use hir::Semantic;
@@ -17,6 +27,7 @@ use hir::Semantic;
 use crate::helpers;
 use crate::Args;
 use crate::Completion;
+use crate::CompletionConfig;
 use crate::Contents;
 use crate::DoneFlag;
 use crate::Kind;
@@ -25,9 +36,11 @@ pub(crate) fn add_completions(
     acc: &mut Vec<Completion>,
     Args {
         sema,
+        config,
         trigger,
         file_position,
         previous_tokens,
+        project_symbols,
         ..
     }: &Args,
 ) -> DoneFlag {
@@ -124,18 +137,132 @@ pub(crate) fn add_completions(
                             file_id: def.file.file_id,
                             offset: fun_decl_ast.syntax().text_range().start(),
                         }),
+                        detail: None,
+                        additional_edits: Vec::new(),
                         sort_text: None,
                         deprecated,
                     }
                 });
 
             acc.extend(completions);
+            if config.auto_import {
+                complete_project_functions(
+                    sema,
+                    config,
+                    *project_symbols,
+                    file_position.file_id,
+                    function_prefix.text(),
+                    acc,
+                );
+            }
             false
         }
         _ => false,
     }
 }
 
+/// The exported functions of the other modules of the project's own
+/// apps. Accepting one qualifies the call with its module, or imports
+/// the function if so configured.
+fn complete_project_functions(
+    sema: &Semantic,
+    config: &CompletionConfig,
+    project_symbols: &dyn Fn() -> Option<Arc<SymbolIndex>>,
+    file_id: FileId,
+    prefix: &str,
+    acc: &mut Vec<Completion>,
+) -> Option<()> {
+    let symbols = project_symbols()?;
+    let def_map = sema.def_map(file_id);
+    for symbol in symbols.search(prefix) {
+        let def = match &symbol.def {
+            SymbolDefinition::Function(def) if def.exported => def,
+            _ => continue,
+        };
+        let def_file_id = def.file.file_id;
+        let na = &def.function.name;
+        if def_file_id == file_id
+            || sema.db.file_app_type(def_file_id) != Some(AppType::App)
+            || def_map.get_functions().contains_key(na)
+            || def_map.get_imports().contains_key(na)
+        {
+            continue;
+        }
+        let module = match sema.module_name(def_file_id) {
+            Some(module) => module,
+            None => continue,
+        };
+        let position = FilePosition {
+            file_id: def_file_id,
+            offset: def.source(sema.db.upcast()).syntax().text_range().start(),
+        };
+        let mut completion = match name_arity_to_call_completion(
+            Some(def),
+            na,
+            prefix,
+            Some(position),
+            def.deprecated,
+        ) {
+            Some(completion) => completion,
+            None => continue,
+        };
+        completion.detail = Some(format!("{}:{}", module.to_quoted_string(), na));
+        // After the functions of the module itself
+        completion.sort_text = Some(format!("~{}", completion.label));
+        if config.add_import {
+            completion.additional_edits = import_edit(sema, file_id, &module, na)
+                .into_iter()
+                .collect();
+        } else if let Contents::Snippet(call) = &completion.contents {
+            completion.contents =
+                Contents::Snippet(format!("{}:{}", module.to_quoted_string(), call));
+        }
+        acc.push(completion);
+    }
+    Some(())
+}
+
+/// Adds the function to the `-import` attribute of its module, if the
+/// file has one, or to a new one after the `-module` attribute.
+fn import_edit(
+    sema: &Semantic,
+    file_id: FileId,
+    module: &ModuleName,
+    na: &NameArity,
+) -> Option<(FilePosition, String)> {
+    let form_list = sema.db.file_form_list(file_id);
+    let source = sema.parse(file_id).value;
+    let existing = form_list
+        .imports()
+        .find(|(_, import)| import.from.as_str() == module.as_str());
+    let (offset, text) = match existing {
+        Some((_, import)) => {
+            let import = import.form_id.get(&source);
+            match import.funs().last() {
+                Some(fa) => (fa.syntax().text_range().end(), format!(", {}", na)),
+                None => {
+                    let lbrack = import
+                        .syntax()
+                        .children_with_tokens()
+                        .find(|it| it.kind() == SyntaxKind::ANON_LBRACK)?;
+                    (lbrack.text_range().end(), na.to_string())
+                }
+            }
+        }
+        None => {
+            let text = format!("-import({}, [{}]).", module.to_quoted_string(), na);
+            match form_list.module_attribute() {
+                Some(module_attr) => (
+                    module_attr.form_id.get(&source).syntax().text_range().end(),
+                    format!("\n{}", text),
+                ),
+                None => (TextSize::from(0), format!("{}\n", text)),
+            }
+        }
+    };
+    Some((FilePosition { file_id, offset }, text))
+}
+
 fn complete_remote_function_call<'a>(
     sema: &'a Semantic,
     from_file: FileId,
@@ -190,6 +317,8 @@ fn name_arity_to_call_completion(
             kind: Kind::Function,
             contents,
             position,
+            detail: None,
+            additional_edits: Vec::new(),
             sort_text: None,
             deprecated,
         })
@@ -204,7 +333,9 @@ mod test {
     use expect_test::Expect;
 
     use crate::tests::get_completions;
+    use crate::tests::get_completions_with_config;
     use crate::tests::render_completions;
+    use crate::CompletionConfig;
     use crate::Kind;
 
     // keywords are filtered out to avoid noise
@@ -689,4 +820,73 @@ mod test {
             ]],
         );
     }
+
+    #[test]
+    fn test_auto_import() {
+        let check_auto_import = |add_import: bool, code: &str, expect: Expect| {
+            let config = CompletionConfig {
+                auto_import: true,
+                add_import,
+                ..CompletionConfig::default()
+            };
+            let completions = get_completions_with_config(code, None, &config)
+                .into_iter()
+                .filter(|c| c.kind != Kind::Keyword)
+                .collect();
+            expect.assert_eq(&render_completions(completions));
+        };
+
+        check_auto_import(
+            false,
+            r#"
+    //- /src/sample1.erl
+    -module(sample1).
+    local() ->
+        fo~.
+    //- /src/sample2.erl
+    -module(sample2).
+    -export([foo/1, for/0]).
+    foo(X) -> X.
+    for() -> ok.
+    fob() -> ok.
+    "#,
+            expect![[r#"
+                {label:foo/1, kind:Function, contents:Snippet("sample2:foo(${1:X})"), position:Some(FilePosition { file_id: FileId(1), offset: 43 }), detail:"sample2:foo/1", sort_text:"~foo/1"}
+                {label:for/0, kind:Function, contents:Snippet("sample2:for()"), position:Some(FilePosition { file_id: FileId(1), offset: 56 }), detail:"sample2:for/0", sort_text:"~for/0"}"#]],
+        );
+
+        check_auto_import(
+            true,
+            r#"
+    //- /src/sample1.erl
+    -module(sample1).
+    local() ->
+        fo~.
+    //- /src/sample2.erl
+    -module(sample2).
+    -export([foo/1]).
+    foo(X) -> X.
+    "#,
+            expect![[r#"
+                {label:foo/1, kind:Function, contents:Snippet("foo(${1:X})"), position:Some(FilePosition { file_id: FileId(1), offset: 36 }), detail:"sample2:foo/1", additional_edits:[(FilePosition { file_id: FileId(0), offset: 17 }, "\n-import(sample2, [foo/1]).")], sort_text:"~foo/1"}"#]],
+        );
+
+        check_auto_import(
+            true,
+            r#"
+    //- /src/sample1.erl
+    -module(sample1).
+    -import(sample2, [bar/0]).
+    local() ->
+        fo~.
+    //- /src/sample2.erl
+    -module(sample2).
+    -export([foo/1, bar/0]).
+    foo(X) -> X.
+    bar() -> ok.
+    "#,
+            expect![[r#"
+                {label:foo/1, kind:Function, contents:Snippet("foo(${1:X})"), position:Some(FilePosition { file_id: FileId(1), offset: 43 }), detail:"sample2:foo/1", additional_edits:[(FilePosition { file_id: FileId(0), offset: 41 }, ", foo/1")], sort_text:"~foo/1"}"#]],
+        );
+    }
 }
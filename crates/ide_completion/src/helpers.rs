@@ -7,6 +7,9 @@
  * of this source tree.
  */
 
+use elp_ide_db::elp_base_db::FileId;
+use elp_ide_db::EqwalizerDatabase;
+use elp_ide_db::RootDatabase;
 use elp_syntax::ast;
 use elp_syntax::ast::ExprMax;
 use elp_syntax::match_ast;
@@ -15,8 +18,12 @@ use elp_syntax::SmolStr;
 use elp_syntax::SourceFile;
 use elp_syntax::SyntaxKind;
 use elp_syntax::TextSize;
+use hir::db::MinDefDatabase;
 use hir::InFile;
+use hir::Name;
 use hir::NameArity;
+use hir::Semantic;
+use hir::TypeAliasSource;
 
 use crate::Completion;
 use crate::Contents;
@@ -65,6 +72,8 @@ pub(crate) fn name_slash_arity_completion(
             kind,
             contents: Contents::SameAsLabel,
             position: None,
+            detail: None,
+            additional_edits: Vec::new(),
             sort_text: None,
             deprecated: false,
         })
@@ -81,3 +90,93 @@ pub(crate) fn split_remote(remote: &ast::Remote) -> Option<(ast::Atom, SmolStr)>
     let name: SmolStr = remote.fun().and_then(|f| f.name()).unwrap_or_default();
     Some((module_atom, name))
 }
+
+/// The type of an expression, as inferred by eqWAlizer if it checked
+/// the module, else as given by the spec of the enclosing function for
+/// a parameter or the returned expression.
+pub(crate) fn type_of_expr(db: &RootDatabase, file_id: FileId, expr: &ast::Expr) -> Option<String> {
+    let range = expr.syntax().text_range();
+    if let Some(types) = db.types_for_file(file_id) {
+        if let Some(info) = types.iter().find(|info| info.range == range) {
+            return Some(info.ty.clone());
+        }
+    }
+    spec_type_of_expr(db, file_id, expr)
+}
+
+fn spec_type_of_expr(db: &RootDatabase, file_id: FileId, expr: &ast::Expr) -> Option<String> {
+    let sema = Semantic::new(db);
+    let function_id = sema.find_enclosing_function(file_id, expr.syntax())?;
+    let form_list = db.file_form_list(file_id);
+    let def_map = sema.def_map(file_id);
+    let spec = def_map.get_spec(&form_list[function_id].name)?.source(db);
+    // Only the first signature is considered, for specs with several
+    let sig = spec.sigs().next()?;
+    let clause = expr
+        .syntax()
+        .ancestors()
+        .find_map(ast::FunctionClause::cast)?;
+    let ty = match expr {
+        ast::Expr::ExprMax(ExprMax::Var(var)) => {
+            let idx = clause
+                .args()?
+                .args()
+                .position(|arg| arg.syntax().text() == var.syntax().text())?;
+            sig.args()?.args().nth(idx)?
+        }
+        _ if clause.body()?.exprs().last()?.syntax() == expr.syntax() => sig.ty()?,
+        _ => return None,
+    };
+    // `Name :: Type` for an annotated argument
+    let mut ty = split_top_level(&ty.syntax().text().to_string(), "::")
+        .last()?
+        .trim()
+        .to_string();
+    // Local type aliases without parameters, e.g. `person()`
+    for _ in 0..MAX_ALIAS_DEPTH {
+        let name = match ty.strip_suffix("()") {
+            Some(name) if name.chars().all(|c| c.is_alphanumeric() || c == '_') => name,
+            _ => break,
+        };
+        let alias = match def_map.get_type(&NameArity::new(Name::from_erlang_service(name), 0)) {
+            Some(alias) => alias.source(db),
+            None => break,
+        };
+        ty = match alias {
+            TypeAliasSource::Regular(alias) => alias.ty()?.syntax().text().to_string(),
+            TypeAliasSource::Opaque(_) => break,
+        };
+    }
+    Some(ty)
+}
+
+const MAX_ALIAS_DEPTH: usize = 8;
+
+/// Splits `text` on the occurrences of `separator` outside of brackets
+/// and quotes.
+pub(crate) fn split_top_level<'a>(text: &'a str, separator: &str) -> Vec<&'a str> {
+    let mut res = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    let mut chars = text.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => {
+                chars.next();
+            }
+            (Some(q), _) if q == c => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth -= 1,
+            (None, _) if depth == 0 && text[idx..].starts_with(separator) && idx >= start => {
+                res.push(&text[start..idx]);
+                start = idx + separator.len();
+            }
+            _ => {}
+        }
+    }
+    res.push(&text[start..]);
+    res
+}
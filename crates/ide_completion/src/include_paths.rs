@@ -0,0 +1,285 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Completion of the path of an `-include` or `-include_lib`: the
+//! directories and headers next to the file and in the include path of
+//! its app, and for `-include_lib` the apps of the project and their
+//! directories.
+
+use std::collections::BTreeSet;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use elp_ide_db::elp_base_db::SourceRootId;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use elp_syntax::SyntaxKind;
+use elp_syntax::TextSize;
+
+use crate::Args;
+use crate::Completion;
+use crate::Contents;
+use crate::DoneFlag;
+use crate::Kind;
+
+pub(crate) fn add_completions(
+    acc: &mut Vec<Completion>,
+    Args {
+        db,
+        parsed,
+        file_position,
+        ..
+    }: &Args,
+) -> DoneFlag {
+    let token = match parsed
+        .value
+        .syntax()
+        .token_at_offset(file_position.offset)
+        .left_biased()
+    {
+        Some(token) if token.kind() == SyntaxKind::STRING => token,
+        _ => return false,
+    };
+    let is_lib = match token.parent().and_then(|string| string.parent()) {
+        Some(node) if ast::PpInclude::can_cast(node.kind()) => false,
+        Some(node) if ast::PpIncludeLib::can_cast(node.kind()) => true,
+        _ => return false,
+    };
+    // The part of the path before the cursor, after the opening quote
+    let start = token.text_range().start() + TextSize::of('"');
+    if file_position.offset < start {
+        return false;
+    }
+    let typed = &token.text()[usize::from(TextSize::of('"'))..]
+        [..usize::from(file_position.offset - start)];
+    if typed.contains('"') {
+        return false;
+    }
+    let (dir, prefix) = typed.rsplit_once('/').unwrap_or(("", typed));
+
+    let file_id = file_position.file_id;
+    let source_root_id = db.file_source_root(file_id);
+    let source_root = db.source_root(source_root_id);
+    let app_data = db.app_data(source_root_id);
+
+    let mut entries = BTreeSet::new();
+    let mut local_dirs: Vec<PathBuf> = Vec::new();
+    if let Some(parent) = source_root
+        .path_for_file(&file_id)
+        .and_then(|path| path.as_path())
+        .and_then(|path| path.as_ref().parent())
+    {
+        local_dirs.push(parent.to_path_buf());
+    }
+    if let Some(app_data) = &app_data {
+        local_dirs.extend(app_data.include_path.iter().cloned().map(PathBuf::from));
+    }
+    for local_dir in local_dirs {
+        add_entries(db, source_root_id, &local_dir.join(dir), &mut entries);
+    }
+    if is_lib {
+        if let Some(app_data) = &app_data {
+            let project_data = db.project_data(app_data.project_id);
+            match dir.split_once('/') {
+                None if dir.is_empty() => {
+                    entries.extend(
+                        project_data
+                            .source_roots
+                            .iter()
+                            .filter_map(|&root_id| db.app_data(root_id))
+                            .map(|app| Entry::Dir(app.name.to_string())),
+                    );
+                }
+                split => {
+                    let (app_name, app_dir) = split.unwrap_or((dir, ""));
+                    if let Some(root_id) = project_data.app_roots.get(app_name) {
+                        if let Some(target_app_data) = db.app_data(root_id) {
+                            let base = PathBuf::from(target_app_data.dir.join(app_dir));
+                            add_entries(db, root_id, &base, &mut entries);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    acc.extend(
+        entries
+            .into_iter()
+            .filter(|entry| entry.name().starts_with(prefix))
+            .map(|entry| {
+                let (label, kind) = match entry {
+                    Entry::Dir(name) => (format!("{name}/"), Kind::Folder),
+                    Entry::Header(name) => (name, Kind::File),
+                };
+                Completion {
+                    label,
+                    kind,
+                    contents: Contents::SameAsLabel,
+                    position: None,
+                    detail: None,
+                    additional_edits: Vec::new(),
+                    sort_text: None,
+                    deprecated: false,
+                }
+            }),
+    );
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Entry {
+    Dir(String),
+    Header(String),
+}
+
+impl Entry {
+    fn name(&self) -> &str {
+        match self {
+            Entry::Dir(name) | Entry::Header(name) => name,
+        }
+    }
+}
+
+/// The directories of the source root under `base`, and its headers
+/// directly in it.
+fn add_entries(
+    db: &dyn hir::db::MinDefDatabase,
+    source_root_id: SourceRootId,
+    base: &Path,
+    entries: &mut BTreeSet<Entry>,
+) {
+    let source_root = db.source_root(source_root_id);
+    for file_id in source_root.iter() {
+        let path = match source_root
+            .path_for_file(&file_id)
+            .and_then(|path| path.as_path())
+        {
+            Some(path) => path,
+            None => continue,
+        };
+        let rest = match path.as_ref().strip_prefix(base) {
+            Ok(rest) => rest,
+            Err(_) => continue,
+        };
+        let mut components = rest.components();
+        let first = match components.next() {
+            Some(Component::Normal(first)) => first.to_string_lossy().to_string(),
+            _ => continue,
+        };
+        if components.next().is_some() {
+            entries.insert(Entry::Dir(first));
+        } else if first.ends_with(".hrl") {
+            entries.insert(Entry::Header(first));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use expect_test::expect;
+    use expect_test::Expect;
+
+    use crate::tests::get_completions;
+    use crate::tests::render_completions;
+
+    fn check(code: &str, expect: Expect) {
+        let completions = get_completions(code, None);
+        let actual = &render_completions(completions);
+        expect.assert_eq(actual);
+    }
+
+    #[test]
+    fn include_relative() {
+        check(
+            r#"
+//- /src/main.erl
+-module(main).
+-include("~").
+//- /src/main.hrl
+-define(MAIN, main).
+//- /src/sub/sub.hrl
+-define(SUB, sub).
+//- /src/other.erl
+-module(other).
+"#,
+            expect![[r#"
+                {label:main.hrl, kind:File, contents:SameAsLabel, position:None}
+                {label:src/, kind:Folder, contents:SameAsLabel, position:None}
+                {label:sub/, kind:Folder, contents:SameAsLabel, position:None}"#]],
+        );
+    }
+
+    #[test]
+    fn include_subdirectory_with_prefix() {
+        check(
+            r#"
+//- /src/main.erl
+-module(main).
+-include("sub/b~").
+//- /src/sub/a.hrl
+-define(A, a).
+//- /src/sub/b.hrl
+-define(B, b).
+"#,
+            expect![[r#"
+                {label:b.hrl, kind:File, contents:SameAsLabel, position:None}"#]],
+        );
+    }
+
+    #[test]
+    fn include_path() {
+        check(
+            r#"
+//- /src/main.erl include_path:/include
+-module(main).
+-include("~").
+//- /include/header.hrl
+-define(HEADER, header).
+"#,
+            expect![[r#"
+                {label:header.hrl, kind:File, contents:SameAsLabel, position:None}
+                {label:include/, kind:Folder, contents:SameAsLabel, position:None}
+                {label:src/, kind:Folder, contents:SameAsLabel, position:None}"#]],
+        );
+    }
+
+    #[test]
+    fn include_lib_apps() {
+        check(
+            r#"
+//- /main/src/main.erl app:main
+-module(main).
+-include_lib("an~").
+//- /another-app/include/header.hrl app:another
+-define(HEADER, header).
+"#,
+            expect![[r#"
+                {label:another/, kind:Folder, contents:SameAsLabel, position:None}"#]],
+        );
+    }
+
+    #[test]
+    fn include_lib_app_dirs() {
+        check(
+            r#"
+//- /main/src/main.erl app:main
+-module(main).
+-include_lib("another/include/~").
+//- /another-app/include/header.hrl app:another
+-define(HEADER, header).
+//- /another-app/src/another.erl app:another
+-module(another).
+"#,
+            expect![[r#"
+                {label:header.hrl, kind:File, contents:SameAsLabel, position:None}"#]],
+        );
+    }
+}
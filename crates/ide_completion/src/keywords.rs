@@ -45,7 +45,7 @@ lazy_static! {
         "try",
         "when",
         "xor"
-    ].iter().map(|label| Completion{ label: label.to_string(), kind: crate::Kind::Keyword, contents: Contents::SameAsLabel, position: None, sort_text: None, deprecated: false}).collect();
+    ].iter().map(|label| Completion{ label: label.to_string(), kind: crate::Kind::Keyword, contents: Contents::SameAsLabel, position: None, detail: None, additional_edits: Vec::new(), sort_text: None, deprecated: false}).collect();
 }
 
 pub(crate) fn add_completions(acc: &mut Vec<Completion>, Args { trigger, .. }: &Args) -> DoneFlag {
@@ -8,10 +8,17 @@
  */
 
 use std::fmt;
+use std::sync::Arc;
 
 use ctx::Ctx;
 use elp_ide_db::elp_base_db::FilePosition;
+use elp_ide_db::elp_base_db::FileRange;
+use elp_ide_db::elp_base_db::SourceDatabase;
+use elp_ide_db::helpers::SnippetCap;
 use elp_ide_db::RootDatabase;
+use elp_ide_db::SymbolIndex;
+use elp_ide_db::SymbolIndexDatabase;
+use elp_syntax::ast;
 use elp_syntax::AstNode;
 use elp_syntax::SourceFile;
 use elp_syntax::SyntaxKind;
@@ -32,14 +39,22 @@ mod export_functions;
 mod export_types;
 mod functions;
 mod helpers;
+mod include_paths;
 mod keywords;
 mod macros;
+mod maps;
 // @fb-only: mod meta_only;
 mod modules;
 mod records;
+mod snippets;
+mod specs;
 mod types;
 mod vars;
 
+pub use snippets::default_snippets;
+pub use snippets::Snippet;
+pub use snippets::SnippetScope;
+
 /*
 For token-based completions, this is the maximum number of previous tokens we consider.
 */
@@ -52,24 +67,36 @@ pub struct Completion {
     pub contents: Contents,
     // The position is used in the 'resolve' phase to look for documentation
     pub position: Option<FilePosition>,
+    /// Shown next to the label, e.g. the default value and type of a
+    /// record field
+    pub detail: Option<String>,
+    /// Text inserted elsewhere in the file when the completion is
+    /// accepted, e.g. an `-import` attribute
+    pub additional_edits: Vec<(FilePosition, String)>,
     pub sort_text: Option<String>,
     pub deprecated: bool,
 }
 
 impl fmt::Display for Completion {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self.deprecated {
-            true => write!(
-                f,
-                "{{label:{}, kind:{:?}, contents:{:?}, position:{:?}, deprecated:{}}}",
-                self.label, self.kind, self.contents, self.position, self.deprecated
-            ),
-            false => write!(
-                f,
-                "{{label:{}, kind:{:?}, contents:{:?}, position:{:?}}}",
-                self.label, self.kind, self.contents, self.position
-            ),
+        write!(
+            f,
+            "{{label:{}, kind:{:?}, contents:{:?}, position:{:?}",
+            self.label, self.kind, self.contents, self.position
+        )?;
+        if let Some(detail) = &self.detail {
+            write!(f, ", detail:{:?}", detail)?;
+        }
+        if !self.additional_edits.is_empty() {
+            write!(f, ", additional_edits:{:?}", self.additional_edits)?;
+        }
+        if let Some(sort_text) = &self.sort_text {
+            write!(f, ", sort_text:{:?}", sort_text)?;
+        }
+        if self.deprecated {
+            write!(f, ", deprecated:{}", self.deprecated)?;
         }
+        write!(f, "}}")
     }
 }
 
@@ -78,6 +105,14 @@ pub enum Contents {
     SameAsLabel,
     String(String),
     Snippet(String),
+    /// A snippet replacing the range, rather than the word being
+    /// completed, e.g. the expression of a postfix snippet. The client
+    /// filters it by `filter`.
+    Postfix {
+        range: FileRange,
+        filter: String,
+        snippet: String,
+    },
 }
 
 /// More erlangy version of `lsp_types::completion::CompletionItemKind`
@@ -97,19 +132,53 @@ pub enum Kind {
     Variable,
     Attribute,
     AiAssist,
+    File,
+    Folder,
+    MapKey,
+    Snippet,
+}
+
+#[derive(Clone, Debug)]
+pub struct CompletionConfig {
+    /// Snippets are only offered if the client supports them
+    pub snippet_cap: Option<SnippetCap>,
+    pub snippets: Vec<Snippet>,
+    /// Whether completing a bare function name also offers the exported
+    /// functions of the other modules of the project
+    pub auto_import: bool,
+    /// Whether accepting such a function adds it to an `-import`
+    /// attribute, rather than qualifying the call with its module
+    pub add_import: bool,
+}
+
+impl Default for CompletionConfig {
+    fn default() -> Self {
+        CompletionConfig {
+            snippet_cap: None,
+            snippets: default_snippets(),
+            auto_import: false,
+            add_import: false,
+        }
+    }
 }
 
 struct Args<'a> {
     db: &'a dyn MinDefDatabase,
+    config: &'a CompletionConfig,
     sema: &'a Semantic<'a>,
     parsed: InFile<SourceFile>,
     trigger: Option<char>,
     previous_tokens: Option<Vec<(SyntaxKind, SyntaxToken)>>,
     file_position: FilePosition,
+    /// The type of an expression of the file, if known
+    type_of_expr: &'a dyn Fn(&ast::Expr) -> Option<String>,
+    /// The symbols of the project of the file
+    project_symbols: &'a dyn Fn() -> Option<Arc<SymbolIndex>>,
 }
 
 pub fn completions(
     db: &RootDatabase,
+    config: &CompletionConfig,
     file_position: FilePosition,
     trigger: Option<char>,
 ) -> Vec<Completion> {
@@ -132,26 +201,42 @@ pub fn completions(
     let ctx = Ctx::new(node, file_position.offset);
     let mut acc = Vec::new();
     let previous_tokens = get_previous_tokens(node, file_position);
+    let type_of_expr = |expr: &ast::Expr| helpers::type_of_expr(db, file_position.file_id, expr);
+    let project_symbols = || {
+        let source_root_id = db.file_source_root(file_position.file_id);
+        let project_id = db.app_data(source_root_id)?.project_id;
+        Some(db.symbol_index(project_id))
+    };
     let args = &Args {
         db,
+        config,
         sema,
         parsed,
         file_position,
         previous_tokens,
         trigger,
+        type_of_expr: &type_of_expr,
+        project_symbols: &project_symbols,
     };
 
     match ctx {
+        // Inside the path of an `-include` or `-include_lib`
+        _ if include_paths::add_completions(&mut acc, args) => {}
+        // After `Expr.`
+        _ if snippets::add_postfix_completions(&mut acc, args) => {}
         Ctx::Expr => {
             let _ = macros::add_completions(&mut acc, args)
                 || records::add_completions(&mut acc, args)
+                || maps::add_completions(&mut acc, args)
                 || functions::add_completions(&mut acc, args)
                 || vars::add_completions(&mut acc, args)
                 || modules::add_completions(&mut acc, args)
                 || keywords::add_completions(&mut acc, args);
+            snippets::add_completions(&mut acc, args);
         }
         Ctx::Type => {
-            let _ = macros::add_completions(&mut acc, args)
+            let _ = specs::add_completions(&mut acc, args)
+                || macros::add_completions(&mut acc, args)
                 || types::add_completions(&mut acc, args)
                 || modules::add_completions(&mut acc, args);
         }
@@ -161,10 +246,15 @@ pub fn completions(
         Ctx::ExportType => {
             export_types::add_completions(&mut acc, args);
         }
+        Ctx::RecordFields => {
+            records::add_in_create_or_update(&mut acc, args);
+        }
         Ctx::Other => {
             let _ = attributes::add_completions(&mut acc, args)
+                || specs::add_completions(&mut acc, args)
                 // @fb-only: || meta_only::add_completions(&mut acc, args)
                 || vars::add_completions(&mut acc, args);
+            snippets::add_form_completions(&mut acc, args);
         }
     }
     // Sort for maintainable snapshot tests:
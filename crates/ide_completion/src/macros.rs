@@ -72,6 +72,8 @@ fn macro_name_to_completion(macro_name: &MacroName) -> Completion {
                 kind: Kind::Macro,
                 contents,
                 position: None,
+                detail: None,
+                additional_edits: Vec::new(),
                 sort_text: None,
                 deprecated: false,
             }
@@ -81,6 +83,8 @@ fn macro_name_to_completion(macro_name: &MacroName) -> Completion {
             kind: Kind::Macro,
             contents: Contents::SameAsLabel,
             position: None,
+            detail: None,
+            additional_edits: Vec::new(),
             sort_text: None,
             deprecated: false,
         },
@@ -93,6 +97,8 @@ fn built_in_macro_name_to_completion(name: &Name) -> Completion {
         kind: Kind::Macro,
         contents: Contents::SameAsLabel,
         position: None,
+        detail: None,
+        additional_edits: Vec::new(),
         sort_text: None,
         deprecated: false,
     }
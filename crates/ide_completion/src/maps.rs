@@ -0,0 +1,275 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Completion of the keys of a map whose type is a shape, e.g.
+//! `#{name := binary(), age => integer()}`: in a map construction or
+//! update, and for the key argument of `maps:get/2,3` and the like.
+
+use elp_syntax::algo;
+use elp_syntax::ast;
+use elp_syntax::ast::ExprMax;
+use elp_syntax::AstNode;
+use elp_syntax::SyntaxNode;
+use elp_syntax::TextSize;
+
+use crate::helpers;
+use crate::helpers::split_top_level;
+use crate::Args;
+use crate::Completion;
+use crate::Contents;
+use crate::DoneFlag;
+use crate::Kind;
+
+/// Functions of the `maps` module taking a key, then a map
+const KEY_FUNCTIONS: &[&str] = &["get", "find", "is_key", "remove", "take"];
+
+pub(crate) fn add_completions(
+    acc: &mut Vec<Completion>,
+    Args {
+        parsed,
+        file_position,
+        trigger,
+        type_of_expr,
+        ..
+    }: &Args,
+) -> DoneFlag {
+    if trigger.is_some() {
+        return false;
+    }
+    let node = parsed.value.syntax();
+    let (map, present) = match map_at(node, file_position.offset) {
+        Some(it) => it,
+        None => return false,
+    };
+    let ty = match type_of_expr(&map) {
+        Some(ty) => ty,
+        None => return false,
+    };
+    let prefix = helpers::atom_value(parsed, file_position.offset).unwrap_or_default();
+    let completions: Vec<_> = shape_props(&ty)
+        .into_iter()
+        .filter(|prop| prop.key.starts_with(&prefix) && !present.contains(&prop.key))
+        .map(|prop| Completion {
+            label: prop.key,
+            kind: Kind::MapKey,
+            contents: Contents::SameAsLabel,
+            position: None,
+            detail: Some(format!(
+                "{} {}",
+                if prop.required { ":=" } else { "=>" },
+                prop.ty
+            )),
+            additional_edits: Vec::new(),
+            sort_text: None,
+            deprecated: false,
+        })
+        .collect();
+    if completions.is_empty() {
+        return false;
+    }
+    acc.extend(completions);
+    true
+}
+
+/// The map whose key is at the offset, and the keys already given
+fn map_at(node: &SyntaxNode, offset: TextSize) -> Option<(ast::Expr, Vec<String>)> {
+    if let Some(map) = key_function_map(node, offset) {
+        return Some((map, Vec::new()));
+    }
+    for ancestor in algo::ancestors_at_offset(node, offset) {
+        if let Some(field) = ast::MapField::cast(ancestor.clone()) {
+            let in_key = field.key().map_or(false, |key| {
+                key.syntax().text_range().contains_inclusive(offset)
+            });
+            if !in_key {
+                return None;
+            }
+        } else if let Some(map) = ast::MapExpr::cast(ancestor.clone()) {
+            let present = present_keys(map.fields(), offset);
+            return Some((ast::Expr::MapExpr(map), present));
+        } else if let Some(update) = ast::MapExprUpdate::cast(ancestor.clone()) {
+            let base = ast::Expr::cast(update.expr()?.syntax().clone())?;
+            return Some((base, present_keys(update.fields(), offset)));
+        }
+    }
+    None
+}
+
+/// `maps:get(Key~, Map)`
+fn key_function_map(node: &SyntaxNode, offset: TextSize) -> Option<ast::Expr> {
+    let call = algo::find_node_at_offset::<ast::Call>(node, offset)?;
+    let remote = match call.expr()? {
+        ast::Expr::Remote(remote) => remote,
+        _ => return None,
+    };
+    let (module, fun) = helpers::split_remote(&remote)?;
+    if module.text()? != "maps" || !KEY_FUNCTIONS.contains(&fun.as_str()) {
+        return None;
+    }
+    let args: Vec<_> = call.args()?.args().collect();
+    let (key, map) = (args.get(0)?, args.get(1)?);
+    if key.syntax().text_range().contains_inclusive(offset) {
+        Some(map.clone())
+    } else {
+        None
+    }
+}
+
+fn present_keys(fields: impl Iterator<Item = ast::MapField>, offset: TextSize) -> Vec<String> {
+    fields
+        .filter(|field| !field.syntax().text_range().contains_inclusive(offset))
+        .filter_map(|field| match field.key()? {
+            ast::Expr::ExprMax(ExprMax::Atom(atom)) => atom.text(),
+            _ => None,
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ShapeProp {
+    key: String,
+    required: bool,
+    ty: String,
+}
+
+/// The atom keys of the shapes of a type, as written in a spec or
+/// printed by eqWAlizer. The keys of each alternative of a union are
+/// given.
+fn shape_props(ty: &str) -> Vec<ShapeProp> {
+    let mut res: Vec<ShapeProp> = Vec::new();
+    for alternative in split_top_level(ty, "|") {
+        let alternative = alternative.trim();
+        let body = match alternative
+            .strip_prefix("#{")
+            .or_else(|| alternative.strip_prefix("#S{"))
+            .and_then(|body| body.strip_suffix('}'))
+        {
+            Some(body) => body,
+            None => continue,
+        };
+        for prop in split_top_level(body, ",") {
+            let (key, required, ty) = match split_top_level(prop, ":=").as_slice() {
+                [key, ty] => (key.trim(), true, ty.trim()),
+                _ => match split_top_level(prop, "=>").as_slice() {
+                    [key, ty] => (key.trim(), false, ty.trim()),
+                    _ => continue,
+                },
+            };
+            if is_atom(key) && !res.iter().any(|prop| prop.key == key) {
+                res.push(ShapeProp {
+                    key: key.to_string(),
+                    required,
+                    ty: ty.to_string(),
+                });
+            }
+        }
+    }
+    res
+}
+
+fn is_atom(text: &str) -> bool {
+    match text.chars().next() {
+        Some('\'') => text.len() > 1 && text.ends_with('\''),
+        Some(c) if c.is_ascii_lowercase() => text
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '@'),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use expect_test::expect;
+    use expect_test::Expect;
+
+    use super::*;
+    use crate::tests::get_completions;
+    use crate::tests::render_completions;
+
+    fn check(code: &str, expect: Expect) {
+        let completions = get_completions(code, None)
+            .into_iter()
+            .filter(|c| c.kind != Kind::Keyword)
+            .collect();
+        let actual = &render_completions(completions);
+        expect.assert_eq(actual);
+    }
+
+    #[test]
+    fn test_maps_get() {
+        check(
+            r#"
+        -module(sample).
+        -spec name(#{name := binary(), age => integer()}) -> binary().
+        name(Person) -> maps:get(n~, Person).
+        "#,
+            expect![[
+                r#"{label:name, kind:MapKey, contents:SameAsLabel, position:None, detail:":= binary()"}"#
+            ]],
+        );
+    }
+
+    #[test]
+    fn test_map_construction() {
+        check(
+            r#"
+        -module(sample).
+        -type person() :: #{name := binary(), age => integer(), 'e-mail' => binary()}.
+        -spec new() -> person().
+        new() -> #{~}.
+        "#,
+            expect![[r#"
+                {label:'e-mail', kind:MapKey, contents:SameAsLabel, position:None, detail:"=> binary()"}
+                {label:age, kind:MapKey, contents:SameAsLabel, position:None, detail:"=> integer()"}
+                {label:name, kind:MapKey, contents:SameAsLabel, position:None, detail:":= binary()"}"#]],
+        );
+    }
+
+    #[test]
+    fn test_map_update() {
+        check(
+            r#"
+        -module(sample).
+        -type person() :: #{name := binary(), age => integer(), 'e-mail' => binary()}.
+        -spec rename(Person :: person()) -> person().
+        rename(Person) -> Person#{name := <<"x">>, a~ => 1}.
+        "#,
+            expect![[
+                r#"{label:age, kind:MapKey, contents:SameAsLabel, position:None, detail:"=> integer()"}"#
+            ]],
+        );
+    }
+
+    #[test]
+    fn test_shape_props() {
+        assert_eq!(
+            shape_props(
+                "#{a := [x, y], 'b c' => fun((a, b) -> ok)} | #S{a => z, c := d} | undefined"
+            ),
+            vec![
+                ShapeProp {
+                    key: "a".to_string(),
+                    required: true,
+                    ty: "[x, y]".to_string()
+                },
+                ShapeProp {
+                    key: "'b c'".to_string(),
+                    required: false,
+                    ty: "fun((a, b) -> ok)".to_string()
+                },
+                ShapeProp {
+                    key: "c".to_string(),
+                    required: true,
+                    ty: "d".to_string()
+                },
+            ]
+        );
+        assert_eq!(shape_props("#{atom() => integer()}"), vec![]);
+    }
+}
@@ -36,6 +36,8 @@ pub(crate) fn add_completions(
                     kind: Kind::Module,
                     contents: Contents::SameAsLabel,
                     position: None,
+                    detail: None,
+                    additional_edits: Vec::new(),
                     sort_text: None,
                     deprecated: false,
                 })
@@ -10,9 +10,11 @@
 use elp_syntax::algo;
 use elp_syntax::ast;
 use elp_syntax::AstNode;
+use fxhash::FxHashSet;
 use hir::InFile;
 use hir::Name;
 
+use crate::ctx::Ctx;
 use crate::Args;
 use crate::Completion;
 use crate::Contents;
@@ -23,7 +25,9 @@ pub(crate) fn add_completions(acc: &mut Vec<Completion>, args: &Args) -> DoneFla
     add_in_create_or_update(acc, args) || add_token_based_completions(acc, args)
 }
 
-/// #rec{field1~} or X#rec{field1~}
+/// #rec{field1~} or X#rec{field1~}: the fields of the record, with
+/// their default value and type as detail. The fields already given
+/// are offered last.
 pub(crate) fn add_in_create_or_update(
     acc: &mut Vec<Completion>,
     Args {
@@ -42,22 +46,40 @@ pub(crate) fn add_in_create_or_update(
     };
 
     match algo::find_node_at_offset::<ast::RecordExpr>(node, file_position.offset)
-        .and_then(|e| e.name())
+        .and_then(|e| Some((e.name()?, e.syntax().clone(), e.fields())))
         .or_else(|| {
             algo::find_node_at_offset::<ast::RecordUpdateExpr>(node, file_position.offset)
-                .and_then(|e| e.name())
+                .and_then(|e| Some((e.name()?, e.syntax().clone(), e.fields())))
         }) {
         None => return false,
-        Some(record_name) => {
+        Some((record_name, record_expr, fields)) => {
             || -> Option<()> {
                 let record = sema.to_def(InFile::new(file_position.file_id, &record_name))?;
                 let field =
-                    algo::find_node_at_offset::<ast::RecordField>(node, file_position.offset)?;
-                let prefix = &field.name()?.text()?;
+                    algo::find_node_at_offset::<ast::RecordField>(node, file_position.offset);
+                let prefix = match &field {
+                    Some(field) => field.name()?.text()?,
+                    None if Ctx::in_record_braces(&record_expr, file_position.offset) => {
+                        String::new()
+                    }
+                    None => return None,
+                };
+                let present: FxHashSet<String> = fields
+                    .filter(|other| Some(other) != field.as_ref())
+                    .filter_map(|other| other.name()?.text())
+                    .collect();
                 let completions = record
-                    .field_names(*db)
-                    .filter(|field_name| field_name.starts_with(prefix))
-                    .map(field_name_to_completion_with_equals);
+                    .fields(*db)
+                    .filter(|(field_name, _)| field_name.starts_with(&prefix))
+                    .map(|(field_name, field_def)| {
+                        let is_present = present.contains(field_name.as_str());
+                        let mut completion = field_name_to_completion_with_equals(field_name);
+                        completion.detail = field_detail(&field_def.source(sema.db.upcast()));
+                        if is_present {
+                            completion.sort_text = Some(format!("~{}", completion.label));
+                        }
+                        completion
+                    });
 
                 acc.extend(completions);
                 Some(())
@@ -67,6 +89,23 @@ pub(crate) fn add_in_create_or_update(
     }
 }
 
+/// The default value and type of a field in its record declaration,
+/// e.g. `= 1 :: integer()`
+fn field_detail(field: &ast::RecordField) -> Option<String> {
+    let default = field
+        .expr()
+        .and_then(|expr| expr.expr())
+        .map(|expr| format!("= {}", expr.syntax().text()));
+    let ty = field
+        .ty()
+        .and_then(|ty| ty.expr())
+        .map(|ty| format!(":: {}", ty.syntax().text()));
+    match (default, ty) {
+        (Some(default), Some(ty)) => Some(format!("{default} {ty}")),
+        (default, ty) => default.or(ty),
+    }
+}
+
 fn add_token_based_completions(
     acc: &mut Vec<Completion>,
     Args {
@@ -89,6 +128,8 @@ fn add_token_based_completions(
                 kind: Kind::Record,
                 contents: Contents::SameAsLabel,
                 position: None,
+                detail: None,
+                additional_edits: Vec::new(),
                 sort_text: None,
                 deprecated: false,
             });
@@ -130,22 +171,15 @@ fn add_token_based_completions(
             add_record_name_completions("", acc)
         }
         // #rec_name.field_prefix
-        [
-            ..,
-            (K::ANON_POUND, _),
-            (K::ATOM, rec_name),
-            (K::ANON_DOT, _),
-            (K::ATOM, field_prefix),
-        ] if matches!(trigger, Some('.') | None) => {
+        [.., (K::ANON_POUND, _), (K::ATOM, rec_name), (K::ANON_DOT, _), (K::ATOM, field_prefix)]
+            if matches!(trigger, Some('.') | None) =>
+        {
             add_record_index_completions(rec_name.text(), field_prefix.text(), acc)
         }
         // #rec_name.
-        [
-            ..,
-            (K::ANON_POUND, _),
-            (K::ATOM, rec_name),
-            (K::ANON_DOT, _),
-        ] if matches!(trigger, Some('.') | None) => {
+        [.., (K::ANON_POUND, _), (K::ATOM, rec_name), (K::ANON_DOT, _)]
+            if matches!(trigger, Some('.') | None) =>
+        {
             add_record_index_completions(rec_name.text(), "", acc)
         }
 
@@ -159,6 +193,8 @@ fn field_name_to_completion_with_equals(field_name: Name) -> Completion {
         kind: Kind::RecordField,
         contents: Contents::String(format!("{} = ", &field_name)),
         position: None,
+        detail: None,
+        additional_edits: Vec::new(),
         sort_text: None,
         deprecated: false,
     }
@@ -170,6 +206,8 @@ fn field_name_to_completion(field_name: Name) -> Completion {
         kind: Kind::RecordField,
         contents: Contents::SameAsLabel,
         position: None,
+        detail: None,
+        additional_edits: Vec::new(),
         sort_text: None,
         deprecated: false,
     }
@@ -305,6 +343,49 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_remaining_fields() {
+        check(
+            r#"
+        -module(sample).
+        -record(rec, {field1 = 1 :: integer(), field2 :: atom(), other = []}).
+        foo(X) -> X#rec{field2 = ok, ~}.
+        "#,
+            None,
+            expect![[r#"
+                {label:field1, kind:RecordField, contents:String("field1 = "), position:None, detail:"= 1 :: integer()"}
+                {label:field2, kind:RecordField, contents:String("field2 = "), position:None, detail:":: atom()", sort_text:"~field2"}
+                {label:other, kind:RecordField, contents:String("other = "), position:None, detail:"= []"}"#]],
+        );
+
+        check(
+            r#"
+        -module(sample).
+        -record(rec, {field1, field2}).
+        foo() -> #rec{~}.
+        "#,
+            None,
+            expect![[r#"
+                {label:field1, kind:RecordField, contents:String("field1 = "), position:None}
+                {label:field2, kind:RecordField, contents:String("field2 = "), position:None}"#]],
+        );
+    }
+
+    #[test]
+    fn test_fields_in_pattern() {
+        check(
+            r#"
+        -module(sample).
+        -record(rec, {field1, field2}).
+        foo(#rec{field1 = X, f~}) -> X.
+        "#,
+            None,
+            expect![[r#"
+                {label:field1, kind:RecordField, contents:String("field1 = "), position:None, sort_text:"~field1"}
+                {label:field2, kind:RecordField, contents:String("field2 = "), position:None}"#]],
+        );
+    }
+
     #[test]
     fn test_record_name() {
         check(
@@ -347,7 +428,7 @@ mod test {
         "#,
             None,
             expect![[
-                r#"{label:field2, kind:RecordField, contents:String("field2 = "), position:None}"#
+                r#"{label:field2, kind:RecordField, contents:String("field2 = "), position:None, detail:"= 2"}"#
             ]],
         );
 
@@ -359,7 +440,7 @@ mod test {
         "#,
             None,
             expect![[
-                r#"{label:field2, kind:RecordField, contents:String("field2 = "), position:None}"#
+                r#"{label:field2, kind:RecordField, contents:String("field2 = "), position:None, detail:"= 2"}"#
             ]],
         );
 
@@ -396,7 +477,7 @@ mod test {
         "#,
             None,
             expect![[
-                r#"{label:field2, kind:RecordField, contents:String("field2 = "), position:None}"#
+                r#"{label:field2, kind:RecordField, contents:String("field2 = "), position:None, detail:"= 2"}"#
             ]],
         );
     }
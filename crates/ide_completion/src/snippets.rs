@@ -0,0 +1,377 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Snippet completions, from the templates of the `CompletionConfig`:
+//! skeletons of expressions and forms, e.g. `receive` or `rec` for a new
+//! `-record` attribute, and postfix snippets rewriting the expression
+//! before them, e.g. `Expr.case` becoming `case Expr of ... end`.
+//!
+//! They are only offered if the client supports snippets.
+
+use elp_ide_db::elp_base_db::FileRange;
+use elp_syntax::ast;
+use elp_syntax::AstNode;
+use elp_syntax::SyntaxKind;
+use elp_syntax::SyntaxToken;
+use elp_syntax::TextRange;
+use elp_syntax::TextSize;
+
+use crate::Args;
+use crate::Completion;
+use crate::Contents;
+use crate::DoneFlag;
+use crate::Kind;
+
+/// Where a snippet is offered
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SnippetScope {
+    /// Where an expression is expected
+    Expr,
+    /// At the start of a form
+    Form,
+    /// After an expression and a dot, e.g. `Expr.case`, the expression
+    /// being replaced
+    Postfix,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snippet {
+    /// What is typed to get the snippet, e.g. `case`
+    pub label: String,
+    pub scope: SnippetScope,
+    /// In the LSP snippet syntax. In a postfix snippet, `${receiver}`
+    /// stands for the expression it applies to.
+    pub body: String,
+    /// Shown next to the label
+    pub description: String,
+}
+
+impl Snippet {
+    pub fn new(label: &str, scope: SnippetScope, body: &str, description: &str) -> Snippet {
+        Snippet {
+            label: label.to_string(),
+            scope,
+            body: body.to_string(),
+            description: description.to_string(),
+        }
+    }
+}
+
+/// The snippets offered unless configured otherwise
+pub fn default_snippets() -> Vec<Snippet> {
+    use SnippetScope::*;
+    vec![
+        Snippet::new(
+            "case",
+            Expr,
+            "case ${1:Expr} of\n    ${2:_} ->\n        ${0:ok}\nend",
+            "case ... of ... end",
+        ),
+        Snippet::new(
+            "if",
+            Expr,
+            "if\n    ${1:true} ->\n        ${0:ok}\nend",
+            "if ... end",
+        ),
+        Snippet::new(
+            "receive",
+            Expr,
+            "receive\n    ${1:_} ->\n        ${0:ok}\nend",
+            "receive ... end",
+        ),
+        Snippet::new(
+            "try",
+            Expr,
+            "try ${1:Expr} of\n    ${2:_} ->\n        ${0:ok}\ncatch\n    ${3:_}:${4:_} ->\n        error\nend",
+            "try ... of ... catch ... end",
+        ),
+        Snippet::new("fun", Expr, "fun(${1}) -> ${0:ok} end", "fun(...) -> ... end"),
+        Snippet::new(
+            "rec",
+            Form,
+            "-record(${1:name}, {${0}}).",
+            "-record(name, {...}).",
+        ),
+        Snippet::new(
+            "def",
+            Form,
+            "-define(${1:NAME}, ${0}).",
+            "-define(NAME, ...).",
+        ),
+        Snippet::new(
+            "type",
+            Form,
+            "-type ${1:name}() :: ${0:term()}.",
+            "-type name() :: ....",
+        ),
+        Snippet::new(
+            "case",
+            Postfix,
+            "case ${receiver} of\n    ${1:_} ->\n        ${0:ok}\nend",
+            "case Expr of ... end",
+        ),
+        Snippet::new(
+            "begin",
+            Postfix,
+            "begin\n    ${receiver}${0}\nend",
+            "begin Expr end",
+        ),
+        Snippet::new("fun", Postfix, "fun() -> ${receiver} end", "fun() -> Expr end"),
+        Snippet::new(
+            "try",
+            Postfix,
+            "try ${receiver} of\n    ${1:_} ->\n        ${0:ok}\ncatch\n    ${2:_}:${3:_} ->\n        error\nend",
+            "try Expr of ... catch ... end",
+        ),
+        Snippet::new("var", Postfix, "${1:Var} = ${receiver}", "Var = Expr"),
+    ]
+}
+
+pub(crate) fn add_completions(acc: &mut Vec<Completion>, args: &Args) -> DoneFlag {
+    use elp_syntax::SyntaxKind as K;
+    let default = vec![];
+    let previous_tokens: &[_] = args.previous_tokens.as_ref().unwrap_or(&default);
+    match previous_tokens {
+        // Not after `mod:`, `#`, `?` or `fun`, where the word is a name
+        [.., (before, _), (kind, prefix)]
+            if is_word(*kind)
+                && !matches!(
+                    before,
+                    K::ANON_COLON | K::ANON_POUND | K::ANON_QMARK | K::ANON_FUN
+                ) =>
+        {
+            add_scoped(acc, args, SnippetScope::Expr, prefix);
+        }
+        _ => (),
+    }
+    false
+}
+
+/// Snippets at the start of a form, after the end of the previous one
+pub(crate) fn add_form_completions(acc: &mut Vec<Completion>, args: &Args) -> DoneFlag {
+    let default = vec![];
+    let previous_tokens: &[_] = args.previous_tokens.as_ref().unwrap_or(&default);
+    match previous_tokens {
+        [(kind, prefix)] if is_word(*kind) => {
+            add_scoped(acc, args, SnippetScope::Form, prefix);
+        }
+        // Not right after the dot, as in a postfix snippet
+        [.., (SyntaxKind::ANON_DOT, dot), (kind, prefix)]
+            if is_word(*kind) && dot.text_range().end() < prefix.text_range().start() =>
+        {
+            add_scoped(acc, args, SnippetScope::Form, prefix);
+        }
+        _ => (),
+    }
+    false
+}
+
+/// Postfix snippets, after `Expr.` and the start of their label
+pub(crate) fn add_postfix_completions(
+    acc: &mut Vec<Completion>,
+    Args {
+        config,
+        previous_tokens,
+        file_position,
+        trigger,
+        ..
+    }: &Args,
+) -> DoneFlag {
+    if config.snippet_cap.is_none() || trigger.is_some() {
+        return false;
+    }
+    let default = vec![];
+    let previous_tokens: &[_] = previous_tokens.as_ref().unwrap_or(&default);
+    let (before, dot, typed) = match previous_tokens {
+        [.., (_, before), (SyntaxKind::ANON_DOT, dot), (kind, typed)]
+            if is_word(*kind)
+                && before.text_range().end() == dot.text_range().start()
+                && dot.text_range().end() == typed.text_range().start() =>
+        {
+            (before, dot, typed)
+        }
+        _ => return false,
+    };
+    let receiver = match receiver(before, dot.text_range().start()) {
+        Some(receiver) => receiver,
+        None => return false,
+    };
+    let receiver_text = receiver.syntax().text().to_string();
+    let range = TextRange::new(
+        receiver.syntax().text_range().start(),
+        typed.text_range().end(),
+    );
+    let completions = config
+        .snippets
+        .iter()
+        .filter(|snippet| {
+            snippet.scope == SnippetScope::Postfix && snippet.label.starts_with(typed.text())
+        })
+        .map(|snippet| Completion {
+            label: snippet.label.clone(),
+            kind: Kind::Snippet,
+            contents: Contents::Postfix {
+                range: FileRange {
+                    file_id: file_position.file_id,
+                    range,
+                },
+                filter: format!("{}.{}", receiver_text, snippet.label),
+                snippet: snippet
+                    .body
+                    .replace("${receiver}", &escape_snippet(&receiver_text)),
+            },
+            position: None,
+            detail: Some(snippet.description.clone()),
+            additional_edits: Vec::new(),
+            sort_text: None,
+            deprecated: false,
+        })
+        .collect::<Vec<_>>();
+    if completions.is_empty() {
+        return false;
+    }
+    acc.extend(completions);
+    true
+}
+
+fn add_scoped(acc: &mut Vec<Completion>, args: &Args, scope: SnippetScope, prefix: &SyntaxToken) {
+    if args.config.snippet_cap.is_none()
+        || args.trigger.is_some()
+        || prefix.text_range().end() != args.file_position.offset
+    {
+        return;
+    }
+    let completions = args
+        .config
+        .snippets
+        .iter()
+        .filter(|snippet| snippet.scope == scope && snippet.label.starts_with(prefix.text()))
+        .map(|snippet| Completion {
+            label: snippet.label.clone(),
+            kind: Kind::Snippet,
+            contents: Contents::Snippet(snippet.body.clone()),
+            position: None,
+            detail: Some(snippet.description.clone()),
+            additional_edits: Vec::new(),
+            sort_text: None,
+            deprecated: false,
+        });
+    acc.extend(completions);
+}
+
+/// An atom, or a keyword being typed, e.g. `case`
+fn is_word(kind: SyntaxKind) -> bool {
+    kind == SyntaxKind::ATOM || kind.is_keyword()
+}
+
+/// The outermost expression ending right before the dot
+fn receiver(token: &SyntaxToken, end: TextSize) -> Option<ast::Expr> {
+    token
+        .parent()?
+        .ancestors()
+        .take_while(|node| node.text_range().end() == end)
+        .filter_map(ast::Expr::cast)
+        .last()
+}
+
+fn escape_snippet(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('$', "\\$")
+        .replace('}', "\\}")
+}
+
+#[cfg(test)]
+mod test {
+    use elp_ide_db::helpers::SnippetCap;
+    use expect_test::expect;
+    use expect_test::Expect;
+
+    use crate::tests::get_completions;
+    use crate::tests::get_completions_with_config;
+    use crate::tests::render_completions;
+    use crate::CompletionConfig;
+    use crate::Kind;
+
+    fn check(code: &str, expect: Expect) {
+        let config = CompletionConfig {
+            snippet_cap: SnippetCap::new(true),
+            ..CompletionConfig::default()
+        };
+        let completions = get_completions_with_config(code, None, &config)
+            .into_iter()
+            .filter(|c| c.kind == Kind::Snippet)
+            .collect();
+        let actual = &render_completions(completions);
+        expect.assert_eq(actual);
+    }
+
+    #[test]
+    fn test_postfix() {
+        check(
+            r#"
+-module(sample).
+foo(X) ->
+    bar(X).ca~
+"#,
+            expect![[r#"
+                {label:case, kind:Snippet, contents:Postfix { range: FileRange { file_id: FileId(0), range: 31..40 }, filter: "bar(X).case", snippet: "case bar(X) of\n    ${1:_} ->\n        ${0:ok}\nend" }, position:None, detail:"case Expr of ... end"}"#]],
+        );
+    }
+
+    #[test]
+    fn test_postfix_escapes_receiver() {
+        check(
+            r#"
+-module(sample).
+foo(X) ->
+    X + $}.f~
+"#,
+            expect![[r#"
+                {label:fun, kind:Snippet, contents:Postfix { range: FileRange { file_id: FileId(0), range: 31..39 }, filter: "X + $}.fun", snippet: "fun() -> X + \\$\\} end" }, position:None, detail:"fun() -> Expr end"}"#]],
+        );
+    }
+
+    #[test]
+    fn test_expr() {
+        check(
+            r#"
+-module(sample).
+foo(X) ->
+    rec~
+"#,
+            expect![[r#"
+                {label:receive, kind:Snippet, contents:Snippet("receive\n    ${1:_} ->\n        ${0:ok}\nend"), position:None, detail:"receive ... end"}"#]],
+        );
+    }
+
+    #[test]
+    fn test_form() {
+        check(
+            r#"
+-module(sample).
+rec~
+"#,
+            expect![[r#"
+                {label:rec, kind:Snippet, contents:Snippet("-record(${1:name}, {${0}})."), position:None, detail:"-record(name, {...})."}"#]],
+        );
+    }
+
+    #[test]
+    fn test_no_snippet_support() {
+        let completions = get_completions(
+            r#"
+-module(sample).
+foo(X) ->
+    X.ca~
+"#,
+            None,
+        );
+        assert!(completions.iter().all(|c| c.kind != Kind::Snippet));
+    }
+}
@@ -0,0 +1,130 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use hir::FunctionDef;
+use hir::NameArity;
+
+use crate::Args;
+use crate::Completion;
+use crate::Contents;
+use crate::DoneFlag;
+use crate::Kind;
+
+/// Complete the name of a function in a `-spec` attribute with a spec
+/// skeleton, built from the parameter names of its clauses.
+pub(crate) fn add_completions(
+    acc: &mut Vec<Completion>,
+    Args {
+        sema,
+        trigger,
+        file_position,
+        previous_tokens,
+        ..
+    }: &Args,
+) -> DoneFlag {
+    use elp_syntax::SyntaxKind as K;
+    let default = vec![];
+    let previous_tokens: &[_] = previous_tokens.as_ref().unwrap_or(&default);
+    match previous_tokens {
+        // -spec function_name_prefix~
+        [.., (K::ANON_DASH, _), (K::ANON_SPEC, _), (K::ATOM, function_prefix)]
+            if trigger.is_none() =>
+        {
+            let def_map = sema.def_map(file_position.file_id);
+            let completions = def_map
+                .get_functions()
+                .iter()
+                .filter(|(na, def)| {
+                    def.file.file_id == file_position.file_id
+                        && na.name().starts_with(function_prefix.text())
+                        && def_map.get_spec(na).is_none()
+                })
+                .map(|(na, def)| create_spec_completion(na, def));
+            acc.extend(completions);
+            true
+        }
+        _ => false,
+    }
+}
+
+fn create_spec_completion(name_arity: &NameArity, def: &FunctionDef) -> Completion {
+    let params = def
+        .function
+        .param_names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| format!("${{{}:{} :: term()}}", i + 1, name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let ret = def.function.param_names.len() + 1;
+    Completion {
+        label: name_arity.to_string(),
+        kind: Kind::Function,
+        contents: Contents::Snippet(format!(
+            "{}({}) -> ${{{}:term()}}.",
+            name_arity.name().to_quoted_string(),
+            params,
+            ret
+        )),
+        position: None,
+        detail: None,
+        additional_edits: Vec::new(),
+        sort_text: None,
+        deprecated: false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use expect_test::expect;
+    use expect_test::Expect;
+
+    use crate::tests::get_completions;
+    use crate::tests::render_completions;
+
+    fn check(code: &str, trigger: Option<char>, expect: Expect) {
+        let completions = get_completions(code, trigger);
+        let actual = &render_completions(completions);
+        expect.assert_eq(actual);
+    }
+
+    #[test]
+    fn spec_skeleton() {
+        check(
+            r#"
+        -module(sample).
+        foo(Name, {Age, _}) -> {Name, Age}.
+        foo() -> ok.
+        bar() -> ok.
+        -spec fo~
+        "#,
+            None,
+            expect![[r#"
+                {label:foo/0, kind:Function, contents:Snippet("foo() -> ${1:term()}."), position:None}
+                {label:foo/2, kind:Function, contents:Snippet("foo(${1:Name :: term()}, ${2:Arg2 :: term()}) -> ${3:term()}."), position:None}"#]],
+        );
+    }
+
+    #[test]
+    fn already_specced() {
+        check(
+            r#"
+        -module(sample).
+        -spec foo(atom()) -> ok.
+        foo(_) -> ok.
+        fob(X) -> X.
+        -spec fo~
+        "#,
+            None,
+            expect![[
+                r#"{label:fob/1, kind:Function, contents:Snippet("fob(${1:X :: term()}) -> ${2:term()}."), position:None}"#
+            ]],
+        );
+    }
+}
@@ -11,6 +11,7 @@ use elp_ide_db::elp_base_db::fixture::WithFixture;
 use elp_ide_db::RootDatabase;
 
 use crate::Completion;
+use crate::CompletionConfig;
 
 pub(crate) fn render_completions(completions: Vec<Completion>) -> String {
     completions
@@ -21,6 +22,14 @@ pub(crate) fn render_completions(completions: Vec<Completion>) -> String {
 }
 
 pub(crate) fn get_completions(code: &str, trigger_character: Option<char>) -> Vec<Completion> {
+    get_completions_with_config(code, trigger_character, &CompletionConfig::default())
+}
+
+pub(crate) fn get_completions_with_config(
+    code: &str,
+    trigger_character: Option<char>,
+    config: &CompletionConfig,
+) -> Vec<Completion> {
     let (db, position) = RootDatabase::with_position(code);
-    crate::completions(&db, position, trigger_character)
+    crate::completions(&db, config, position, trigger_character)
 }
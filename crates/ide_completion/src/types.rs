@@ -111,6 +111,8 @@ fn create_call_completion(name_arity: &NameArity) -> Completion {
         kind: Kind::Type,
         contents,
         position: None,
+        detail: None,
+        additional_edits: Vec::new(),
         sort_text: None,
         deprecated: false,
     }
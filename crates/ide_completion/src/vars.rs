@@ -69,6 +69,8 @@ fn complete_var(var: &SyntaxToken, candidate: &SyntaxToken, acc: &mut FxHashSet<
             kind: Kind::Variable,
             contents: Contents::SameAsLabel,
             position: None,
+            detail: None,
+            additional_edits: Vec::new(),
             sort_text: None,
             deprecated: false,
         });
@@ -21,3 +21,133 @@ impl RootDatabase {
         change.apply(self);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use elp_base_db::fixture::ChangeFixture;
+    use elp_base_db::AppStructure;
+    use elp_base_db::FileId;
+    use elp_base_db::FileSet;
+    use elp_base_db::ProjectId;
+    use elp_base_db::SourceDatabase;
+    use elp_base_db::SourceDatabaseExt;
+    use elp_base_db::SourceRootId;
+    use elp_base_db::SourceRoots;
+    use elp_base_db::VfsPath;
+
+    use crate::RootDatabase;
+
+    fn app_structure(fixture: &str) -> AppStructure {
+        let (_, change) = ChangeFixture::parse(fixture);
+        change.app_structure.unwrap()
+    }
+
+    fn app_names(db: &RootDatabase, roots: u32) -> Vec<Option<String>> {
+        let mut names: Vec<_> = (0..roots)
+            .map(|idx| {
+                db.app_data(SourceRootId(idx))
+                    .map(|data| data.name.to_string())
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn app_structure_changes_add_and_remove_apps() {
+        let mut db = RootDatabase::default();
+        let two_apps = app_structure(
+            r#"
+//- /a/src/a.erl app:a
+-module(a).
+//- /b/src/b.erl app:b
+-module(b).
+"#,
+        );
+        two_apps.clone().apply(&mut db);
+        assert_eq!(
+            app_names(&db, 3),
+            vec![None, Some("a".to_string()), Some("b".to_string())]
+        );
+
+        // Removing app `b` leaves its former source root without app
+        let one_app = app_structure(
+            r#"
+//- /a/src/a.erl app:a
+-module(a).
+"#,
+        );
+        let changed = one_app.clone().apply_changes(&two_apps, &mut db);
+        assert_eq!(changed, vec![ProjectId(0)]);
+        assert_eq!(app_names(&db, 3), vec![None, None, Some("a".to_string())]);
+
+        let three_apps = app_structure(
+            r#"
+//- /a/src/a.erl app:a
+-module(a).
+//- /b/src/b.erl app:b
+-module(b).
+//- /c/src/c.erl app:c
+-module(c).
+"#,
+        );
+        let changed = three_apps.clone().apply_changes(&one_app, &mut db);
+        assert_eq!(changed, vec![ProjectId(0)]);
+        assert_eq!(
+            app_names(&db, 4),
+            vec![
+                None,
+                Some("a".to_string()),
+                Some("b".to_string()),
+                Some("c".to_string())
+            ]
+        );
+        assert_eq!(db.project_data(ProjectId(0)).source_roots.len(), 3);
+
+        // Nothing is set again for an unchanged structure
+        let changed = three_apps.clone().apply_changes(&three_apps, &mut db);
+        assert!(changed.is_empty());
+    }
+
+    #[test]
+    fn source_roots_move_files() {
+        let mut db = RootDatabase::default();
+        let file_set = |files: &[(u32, &str)]| {
+            let mut file_set = FileSet::default();
+            for (file_id, path) in files {
+                file_set.insert(FileId(*file_id), VfsPath::new_real_path(path.to_string()));
+            }
+            file_set
+        };
+        let mut roots = SourceRoots::default();
+        roots.apply(
+            vec![
+                file_set(&[(0, "/a/src/a.erl"), (1, "/a/src/moved.erl")]),
+                file_set(&[(2, "/b/src/b.erl")]),
+            ],
+            &mut db,
+        );
+        assert_eq!(db.file_source_root(FileId(1)), SourceRootId(0));
+
+        roots.apply(
+            vec![
+                file_set(&[(0, "/a/src/a.erl")]),
+                file_set(&[(1, "/b/src/moved.erl"), (2, "/b/src/b.erl")]),
+            ],
+            &mut db,
+        );
+        assert_eq!(db.file_source_root(FileId(0)), SourceRootId(0));
+        assert_eq!(db.file_source_root(FileId(1)), SourceRootId(1));
+        assert_eq!(db.file_source_root(FileId(2)), SourceRootId(1));
+        assert!(db
+            .source_root(SourceRootId(0))
+            .path_for_file(&FileId(1))
+            .is_none());
+        assert_eq!(
+            db.source_root(SourceRootId(1))
+                .path_for_file(&FileId(1))
+                .map(|path| path.to_string()),
+            Some("/b/src/moved.erl".to_string())
+        );
+    }
+}
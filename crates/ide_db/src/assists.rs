@@ -44,6 +44,10 @@ pub struct Assist {
     /// Some assists require additional input from the user, such as the name
     /// of a newly-extracted function.
     pub user_input: Option<AssistUserInput>,
+    /// Why the assist can't be applied here, e.g. because of a conflict.
+    /// Such an assist has no source change, and is shown greyed out by
+    /// the clients supporting it.
+    pub disabled: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -0,0 +1,248 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Index of the functions deprecated by each module, from its
+//! `-deprecated` attributes and from the `[deprecated]` section of the
+//! `.elp_lint.toml` file of its project.
+//!
+//! The function to call instead is taken from the config, or from a
+//! description of the form `"use module:function/arity instead"`, as
+//! used throughout OTP.
+
+use std::fmt;
+use std::sync::Arc;
+
+use elp_base_db::salsa;
+use elp_base_db::FileId;
+use elp_syntax::SmolStr;
+use fxhash::FxHashMap;
+use hir::db::MinDefDatabase;
+use hir::DeprecatedAttribute;
+use hir::DeprecatedDesc;
+use hir::DeprecatedFa;
+use hir::NameArity;
+
+/// Why and how a function is deprecated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Deprecation {
+    pub description: Option<SmolStr>,
+    pub replacement: Option<Replacement>,
+}
+
+/// The function to call instead of a deprecated one. The module is
+/// `None` for a function of the module deprecating it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replacement {
+    pub module: Option<SmolStr>,
+    pub function: SmolStr,
+    pub arity: u32,
+}
+
+impl Replacement {
+    /// Parse a function given as `module:function/arity` or
+    /// `function/arity`.
+    pub fn parse(spec: &str) -> Option<Replacement> {
+        let (function, arity) = spec.trim().rsplit_once('/')?;
+        let (module, function) = match function.split_once(':') {
+            Some((module, function)) => (Some(module), function),
+            None => (None, function),
+        };
+        let is_atom = |name: &str| {
+            name.starts_with(|c: char| c.is_ascii_lowercase())
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@')
+        };
+        if !is_atom(function) || !module.map_or(true, is_atom) {
+            return None;
+        }
+        Some(Replacement {
+            module: module.map(SmolStr::new),
+            function: SmolStr::new(function),
+            arity: arity.parse().ok()?,
+        })
+    }
+
+    /// The replacement given by the description of a deprecated
+    /// function, e.g. `"use lists:flatten/1 instead"`.
+    fn from_description(description: &str) -> Option<Replacement> {
+        let mut words = description.split_whitespace();
+        while let Some(word) = words.next() {
+            if word.eq_ignore_ascii_case("use") {
+                let next = words
+                    .next()?
+                    .trim_end_matches(|c: char| ",.;)`'\"".contains(c));
+                let next = next.trim_start_matches(|c: char| "(`'\"".contains(c));
+                if let Some(replacement) = Replacement::parse(next) {
+                    return Some(replacement);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl fmt::Display for Replacement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.module {
+            Some(module) => write!(f, "{}:{}/{}", module, self.function, self.arity),
+            None => write!(f, "{}/{}", self.function, self.arity),
+        }
+    }
+}
+
+/// The functions deprecated by a module.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ModuleDeprecations {
+    /// Set when the whole module is deprecated
+    module: Option<Deprecation>,
+    /// Functions deprecated for all their arities
+    functions: FxHashMap<SmolStr, Deprecation>,
+    fas: FxHashMap<(SmolStr, u32), Deprecation>,
+}
+
+impl ModuleDeprecations {
+    /// How `function` is deprecated, if it is.
+    pub fn get(&self, function: &NameArity) -> Option<&Deprecation> {
+        let name = function.name().raw();
+        self.fas
+            .get(&(name.clone(), function.arity()))
+            .or_else(|| self.functions.get(&name))
+            .or(self.module.as_ref())
+    }
+
+    fn add(&mut self, name: &str, arity: Option<u32>, deprecation: Deprecation) {
+        let entry = match (name, arity) {
+            ("_", _) => self.module.get_or_insert_with(Default::default),
+            (_, Some(arity)) => self.fas.entry((SmolStr::new(name), arity)).or_default(),
+            (_, None) => self.functions.entry(SmolStr::new(name)).or_default(),
+        };
+        // Keep the first description, but take a replacement from the
+        // config over one guessed from a description.
+        if entry.description.is_none() {
+            entry.description = deprecation.description;
+        }
+        if deprecation.replacement.is_some() {
+            entry.replacement = deprecation.replacement;
+        }
+    }
+}
+
+#[salsa::query_group(DeprecationDatabaseStorage)]
+pub trait DeprecationDatabase: MinDefDatabase {
+    /// The functions deprecated by the module of a file.
+    fn module_deprecations(&self, file_id: FileId) -> Arc<ModuleDeprecations>;
+}
+
+fn module_deprecations(db: &dyn DeprecationDatabase, file_id: FileId) -> Arc<ModuleDeprecations> {
+    let mut res = ModuleDeprecations::default();
+    let form_list = db.file_form_list(file_id);
+    for (_, attribute) in form_list.deprecated_attributes() {
+        match attribute {
+            DeprecatedAttribute::Module { .. } => res.add("_", None, Deprecation::default()),
+            DeprecatedAttribute::Fa { fa, .. } => add_attribute_fa(&mut res, fa),
+            DeprecatedAttribute::Fas { fas, .. } => {
+                fas.iter().for_each(|fa| add_attribute_fa(&mut res, fa))
+            }
+        }
+    }
+
+    let module = form_list.module_attribute().map(|attr| attr.name.clone());
+    if let (Some(module), Some(app_data)) = (module, db.app_data(db.file_source_root(file_id))) {
+        let config = db
+            .project_data(app_data.project_id)
+            .lint_file_config
+            .deprecated
+            .clone();
+        let configured = config.functions.iter().map(|spec| (spec, None)).chain(
+            config
+                .replacements
+                .iter()
+                .map(|(spec, replacement)| (spec, Some(replacement))),
+        );
+        for (spec, replacement) in configured {
+            let replacement = match replacement {
+                Some(text) => {
+                    let replacement = Replacement::parse(text);
+                    if replacement.is_none() {
+                        log::warn!("deprecated: ignoring invalid replacement `{text}`");
+                    }
+                    replacement
+                }
+                None => None,
+            };
+            let (spec_module, function) = match spec.trim().split_once(':') {
+                Some((spec_module, function)) => (spec_module, Some(function)),
+                None => (spec.trim(), None),
+            };
+            if spec_module != module.as_str() {
+                continue;
+            }
+            let (function, arity) = match function.map(|it| (it, it.split_once('/'))) {
+                None => ("_", None),
+                Some((function, None)) => (function, None),
+                Some((_, Some((function, arity)))) => match arity.parse() {
+                    Ok(arity) => (function, Some(arity)),
+                    Err(_) => {
+                        log::warn!("deprecated: ignoring invalid function `{spec}`");
+                        continue;
+                    }
+                },
+            };
+            res.add(
+                function,
+                arity,
+                Deprecation {
+                    description: None,
+                    replacement,
+                },
+            );
+        }
+    }
+    Arc::new(res)
+}
+
+fn add_attribute_fa(res: &mut ModuleDeprecations, fa: &DeprecatedFa) {
+    let description = fa.desc.as_ref().map(|desc| match desc {
+        DeprecatedDesc::Str(desc) => desc.clone(),
+        // See https://www.erlang.org/doc/man/xref.html
+        DeprecatedDesc::Atom(atom) if atom == "next_version" => {
+            SmolStr::new("will be removed in the next version")
+        }
+        DeprecatedDesc::Atom(_) => SmolStr::new("will be removed in a future release"),
+    });
+    let replacement = description
+        .as_deref()
+        .and_then(Replacement::from_description);
+    res.add(
+        fa.name.as_str(),
+        fa.arity,
+        Deprecation {
+            description,
+            replacement,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replacement_from_description() {
+        let parse = |desc: &str| Replacement::from_description(desc).map(|it| it.to_string());
+        assert_eq!(
+            parse("use erlang:timestamp/0 instead"),
+            Some("erlang:timestamp/0".to_string())
+        );
+        assert_eq!(parse("Use `new_name/2`."), Some("new_name/2".to_string()));
+        assert_eq!(parse("will be removed in OTP 27"), None);
+        assert_eq!(parse("use the new API"), None);
+    }
+}
@@ -0,0 +1,158 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! The results kept on disk across runs, so that a cold start does not
+//! compute them again: the parse results of the Erlang service and the
+//! eqWAlizer results of modules.
+//!
+//! An entry is keyed by a SHA-256 digest over stable serializations of
+//! everything it depends on: paths and file texts, compile options in
+//! the external term format, and the beams of the parse transforms.
+
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+
+use elp_eqwalizer::EqwalizerDiagnostic;
+use elp_eqwalizer::EqwalizerTypeInfo;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use tempfile::Builder;
+
+/// Builds the key of a cache entry.
+pub(crate) struct CacheKey(Sha256);
+
+impl CacheKey {
+    /// `kind` keeps apart the keys of different caches.
+    pub(crate) fn new(kind: &str) -> CacheKey {
+        let mut key = CacheKey(Sha256::new());
+        key.add(kind.as_bytes());
+        key
+    }
+
+    /// Each field is length-prefixed, so that the bytes of consecutive
+    /// fields can't be split differently to give the same key.
+    pub(crate) fn add(&mut self, bytes: &[u8]) -> &mut CacheKey {
+        self.0.update((bytes.len() as u64).to_le_bytes());
+        self.0.update(bytes);
+        self
+    }
+
+    pub(crate) fn add_str(&mut self, text: &str) -> &mut CacheKey {
+        self.add(text.as_bytes())
+    }
+
+    pub(crate) fn add_term(&mut self, term: &eetf::Term) -> &mut CacheKey {
+        let mut bytes = Vec::new();
+        // Encoding to memory does not fail
+        let _ = term.encode(&mut bytes);
+        self.add(&bytes)
+    }
+
+    /// The hex-encoded digest, usable as a file name.
+    pub(crate) fn finish(self) -> String {
+        format!("{:x}", self.0.finalize())
+    }
+}
+
+/// The eqWAlizer results of a module, as stored in the cache.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct CachedModule {
+    pub(crate) errors: Vec<EqwalizerDiagnostic>,
+    pub(crate) type_info: Vec<EqwalizerTypeInfo>,
+}
+
+/// eqWAlizer results stored on disk, one JSON file per module.
+#[derive(Debug, Clone)]
+pub struct EqwalizerCache {
+    dir: PathBuf,
+}
+
+impl EqwalizerCache {
+    pub fn new(dir: PathBuf) -> EqwalizerCache {
+        EqwalizerCache { dir }
+    }
+
+    pub(crate) fn load(&self, key: &str) -> Option<CachedModule> {
+        let bytes = fs::read(self.dir.join(key)).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    pub(crate) fn store(&self, key: &str, module: &CachedModule) {
+        // Written to a temporary file first, as another process may be
+        // reading the entry
+        let res = serde_json::to_vec(module)
+            .map_err(io::Error::from)
+            .and_then(|bytes| {
+                fs::create_dir_all(&self.dir)?;
+                let mut file = Builder::new().tempfile_in(&self.dir)?;
+                file.write_all(&bytes)?;
+                file.persist(self.dir.join(key)).map_err(|err| err.error)?;
+                Ok(())
+            });
+        if let Err(err) = res {
+            log::warn!(
+                "Could not store eqWAlizer result in {:?}: {}",
+                self.dir,
+                err
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use elp_syntax::TextRange;
+
+    use super::*;
+
+    #[test]
+    fn keys_separate_fields() {
+        let key = |fields: &[&str]| {
+            let mut key = CacheKey::new("test");
+            for field in fields {
+                key.add_str(field);
+            }
+            key.finish()
+        };
+        assert_eq!(key(&["ab", "c"]), key(&["ab", "c"]));
+        assert_ne!(key(&["ab", "c"]), key(&["a", "bc"]));
+        assert_eq!(key(&[]).len(), 64);
+        assert_ne!(key(&[]), CacheKey::new("other").finish());
+    }
+
+    #[test]
+    fn eqwalizer_cache_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = EqwalizerCache::new(dir.path().join("eqwalizer"));
+        let module = CachedModule {
+            errors: vec![EqwalizerDiagnostic {
+                range: TextRange::new(1.into(), 5.into()),
+                message: "Expected: atom()".to_string(),
+                uri: "https://fb.me/eqwalizer_errors#incompatible_types".to_string(),
+                code: "incompatible_types".to_string(),
+                expression: Some("X".to_string()),
+                explanation: None,
+            }],
+            type_info: vec![EqwalizerTypeInfo {
+                range: TextRange::new(1.into(), 2.into()),
+                ty: "number()".to_string(),
+            }],
+        };
+        assert_eq!(cache.load("key"), None);
+        cache.store("key", &module);
+        assert_eq!(cache.load("key"), Some(module));
+
+        fs::write(dir.path().join("eqwalizer").join("garbage"), b"{").unwrap();
+        assert_eq!(cache.load("garbage"), None);
+    }
+}
@@ -22,15 +22,20 @@ use elp_erlang_service::DocOrigin;
 use elp_erlang_service::DocRequest;
 use elp_syntax::ast;
 use elp_syntax::match_ast;
+use elp_syntax::unescape;
 use elp_syntax::AstNode;
 use elp_syntax::SyntaxToken;
 use fxhash::FxHashMap;
 use hir::db::MinDefDatabase;
 use hir::CallDef;
+use hir::FormIdx;
 use hir::InFile;
 use hir::Name;
 use hir::NameArity;
+use hir::RecordDef;
 use hir::Semantic;
+use hir::TypeAliasDef;
+use stdx::trim_indent;
 
 pub trait DocLoader {
     /// when origin = eep-48:
@@ -126,7 +131,7 @@ impl ToDoc for InFile<&ast::Remote> {
                     let name_arity = fun_def.function.name;
                     docs.function_doc(file_id, name_arity)
                 }
-                CallDef::Type(_) => None,
+                CallDef::Type(type_alias) => Some(docs.type_alias_doc(&type_alias)),
             }
         } else {
             None
@@ -141,7 +146,7 @@ impl ToDoc for InFile<&ast::Call> {
                 CallDef::Function(fun_def) => {
                     docs.function_doc(fun_def.file.file_id, fun_def.function.name)
                 }
-                CallDef::Type(_) => None,
+                CallDef::Type(type_alias) => Some(docs.type_alias_doc(&type_alias)),
             }
         } else {
             None
@@ -164,6 +169,56 @@ impl ToDoc for InFile<&ast::FunctionClause> {
     }
 }
 
+impl ToDoc for InFile<&ast::RecordName> {
+    fn to_doc(docs: &Documentation<'_>, ast: Self) -> Option<Doc> {
+        let record_def = docs.sema.to_def(ast)?;
+        Some(docs.record_doc(&record_def))
+    }
+}
+
+impl ToDoc for InFile<&ast::RecordDecl> {
+    fn to_doc(docs: &Documentation<'_>, ast: Self) -> Option<Doc> {
+        let record_def = docs.sema.to_def(ast)?;
+        Some(docs.record_doc(&record_def))
+    }
+}
+
+impl ToDoc for InFile<&ast::TypeName> {
+    fn to_doc(docs: &Documentation<'_>, ast: Self) -> Option<Doc> {
+        let type_alias = docs.sema.to_def(ast)?;
+        Some(docs.type_alias_doc(&type_alias))
+    }
+}
+
+/// The definition of the macro, and what the call expands to, when it
+/// is in an expression.
+impl ToDoc for InFile<&ast::MacroCallExpr> {
+    fn to_doc(docs: &Documentation<'_>, ast: Self) -> Option<Doc> {
+        let definition = docs.sema.to_def(ast).map(|define| {
+            code_block(
+                &define
+                    .source(docs.sema.db.upcast())
+                    .syntax()
+                    .text()
+                    .to_string(),
+            )
+        });
+        let expansion = docs
+            .sema
+            .expand(ast)
+            .map(|(_name, expansion)| code_block(expansion.trim()));
+        let text = match (definition, expansion) {
+            (Some(definition), Some(expansion)) => {
+                format!("{definition}\n\n-----\n\nExpands to:\n\n{expansion}")
+            }
+            (Some(definition), None) => definition,
+            (None, Some(expansion)) => expansion,
+            (None, None) => return None,
+        };
+        Some(Doc::new(text))
+    }
+}
+
 // edocs can exist on either a module attribute or a function definition,
 // see https://www.erlang.org/doc/apps/edoc/chapter.html#introduction
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -212,6 +267,21 @@ impl<'db> Documentation<'db> {
         let file_docs = self.file_doc(file_id);
         file_docs.module_doc.clone()
     }
+
+    fn record_doc(&self, record_def: &RecordDef) -> Doc {
+        let source = record_def.source(self.sema.db.upcast());
+        Doc::new(code_block(&source.syntax().text().to_string()))
+    }
+
+    fn type_alias_doc(&self, type_alias: &TypeAliasDef) -> Doc {
+        let source = type_alias.source(self.sema.db.upcast());
+        Doc::new(code_block(&source.syntax().text().to_string()))
+    }
+}
+
+/// Erlang code, rendered as a markdown code block.
+fn code_block(code: &str) -> String {
+    format!("```erlang\n{}\n```", code)
 }
 
 // Some(true) -> file is in OTP
@@ -239,13 +309,82 @@ fn get_file_docs(db: &dyn DocDatabase, file_id: FileId) -> Arc<FileDoc> {
 
     let descriptions = db.load_doc_descriptions(file_id, origin);
     let specs = get_file_function_specs(db.upcast(), file_id);
+    // Docs from attributes are preferred over the edoc ones
+    let (attribute_module_doc, attribute_function_docs) =
+        get_file_doc_attributes(db.upcast(), file_id);
+    let mut function_docs = descriptions.function_docs;
+    function_docs.extend(attribute_function_docs);
     Arc::new(FileDoc {
-        module_doc: descriptions.module_doc,
-        function_docs: merge_descriptions_and_specs(descriptions.function_docs, specs),
+        module_doc: attribute_module_doc.or(descriptions.module_doc),
+        function_docs: merge_descriptions_and_specs(function_docs, specs),
         diagnostics: descriptions.diagnostics,
     })
 }
 
+/// The docs given by the OTP 27 `-moduledoc` and `-doc` attributes, as
+/// markdown. A `-doc` attribute documents the function following it.
+fn get_file_doc_attributes(
+    def_db: &dyn MinDefDatabase,
+    file_id: FileId,
+) -> (Option<Doc>, FxHashMap<NameArity, Doc>) {
+    let form_list = def_db.file_form_list(file_id);
+    let source = def_db.parse(file_id).tree();
+    let mut module_doc = None;
+    let mut function_docs = FxHashMap::default();
+    let mut pending_doc = None;
+    for form in form_list.forms() {
+        match form {
+            FormIdx::Attribute(idx) => {
+                let attribute = &form_list[*idx];
+                match attribute.name.as_str() {
+                    "moduledoc" => module_doc = attribute_doc(&attribute.form_id.get(&source)),
+                    "doc" => pending_doc = attribute_doc(&attribute.form_id.get(&source)),
+                    _ => {}
+                }
+            }
+            FormIdx::Function(idx) => {
+                if let Some(doc) = pending_doc.take() {
+                    function_docs.insert(form_list[*idx].name.clone(), doc);
+                }
+            }
+            // Types and callbacks can be documented too
+            FormIdx::TypeAlias(_) | FormIdx::Callback(_) => pending_doc = None,
+            _ => {}
+        }
+    }
+    (module_doc, function_docs)
+}
+
+/// The markdown text of a `-doc` or `-moduledoc` attribute, if it is a
+/// string. Triple-quoted strings are dedented.
+fn attribute_doc(attribute: &ast::WildAttribute) -> Option<Doc> {
+    let text = match attribute.value()? {
+        ast::Expr::ExprMax(ast::ExprMax::String(str)) => {
+            unescape::unescape_string(&str.text())?.to_string()
+        }
+        ast::Expr::ExprMax(ast::ExprMax::Concatables(concat)) => {
+            let mut buf = String::new();
+            for concatable in concat.elems() {
+                match concatable {
+                    ast::Concatable::String(str) => {
+                        buf.push_str(&unescape::unescape_string(&str.text())?)
+                    }
+                    _ => return None,
+                }
+            }
+            buf
+        }
+        _ => return None,
+    };
+    let text = trim_indent(&text);
+    let text = text.trim();
+    if text.is_empty() {
+        None
+    } else {
+        Some(Doc::new(text.to_string()))
+    }
+}
+
 fn merge_descriptions_and_specs(
     descriptions: FxHashMap<NameArity, Doc>,
     specs: FxHashMap<NameArity, Doc>,
@@ -381,8 +520,10 @@ impl Doc {
                 ast::ImportAttribute(_) => None,
                 ast::Fa(fa) =>
                     docdb.to_doc(token.with_value(&fa)),
-                ast::TypeName(_) => None,
-                ast::RecordDecl(_) => None,
+                ast::TypeName(ty) =>
+                    docdb.to_doc(token.with_value(&ty)),
+                ast::RecordDecl(record) =>
+                    docdb.to_doc(token.with_value(&record)),
                 ast::Spec(spec) =>
                     docdb.to_doc(token.with_value(&spec)),
                 ast::Callback(_) => None,
@@ -397,7 +538,8 @@ impl Doc {
                 ast::FunctionClause(clause) =>
                     docdb.to_doc(token.with_value(&clause)),
                 ast::BitTypeList(_) => None,
-                ast::RecordName(_) => None,
+                ast::RecordName(name) =>
+                    docdb.to_doc(token.with_value(&name)),
                 ast::RecordFieldName(_) => None,
                 ast::RecordField(_) => None,
                 ast::InternalFun(_) => None,
@@ -416,6 +558,8 @@ impl Doc {
                     docdb.to_doc(token.with_value(&remote)),
                 ast::Call(call) =>
                     docdb.to_doc(token.with_value(&call)),
+                ast::MacroCallExpr(macro_call) =>
+                    docdb.to_doc(token.with_value(&macro_call)),
                 _ => {
                     // Parent is nothing structured, it must be a raw atom or var literal
                     match_ast! {
@@ -432,3 +576,56 @@ impl Doc {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use elp_base_db::fixture::WithFixture;
+    use expect_test::expect;
+
+    use super::DocDatabase;
+    use crate::RootDatabase;
+
+    #[test]
+    fn doc_attributes() {
+        let (db, file_id) = RootDatabase::with_single_file(
+            r#"
+-module(main).
+-moduledoc "The main module.".
+-export([foo/0, bar/1]).
+-doc """
+    Returns `ok`.
+
+    Always.
+    """.
+foo() -> ok.
+-doc "Unused " "type.".
+-type t() :: ok.
+bar(X) -> X.
+"#,
+        );
+        let file_doc = db.file_doc(file_id);
+        expect![[r#"
+            Some(
+                Doc {
+                    markdown_text: "The main module.",
+                },
+            )
+        "#]]
+        .assert_debug_eq(&file_doc.module_doc);
+        let mut function_docs: Vec<_> = file_doc
+            .function_docs
+            .iter()
+            .map(|(name, doc)| (name.to_string(), doc.markdown_text().to_string()))
+            .collect();
+        function_docs.sort();
+        expect![[r#"
+            [
+                (
+                    "foo/0",
+                    "Returns `ok`.\n\nAlways.",
+                ),
+            ]
+        "#]]
+        .assert_debug_eq(&function_docs);
+    }
+}
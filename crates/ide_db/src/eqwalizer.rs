@@ -7,26 +7,44 @@
  * of this source tree.
  */
 
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::hash::Hasher;
+use std::io;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
 
 use elp_base_db::salsa;
 use elp_base_db::AbsPath;
 use elp_base_db::FileId;
+use elp_base_db::FilePosition;
 use elp_base_db::FileSource;
 use elp_base_db::ModuleName;
 use elp_base_db::ProjectId;
 use elp_base_db::SourceDatabase;
+use elp_base_db::SourceDatabaseExt;
 use elp_base_db::SourceRootId;
 use elp_eqwalizer::ast::db::EqwalizerASTDatabase;
 use elp_eqwalizer::ast::db::EqwalizerErlASTStorage;
 use elp_eqwalizer::ast::Error;
 use elp_eqwalizer::ipc::IpcHandle;
+use elp_eqwalizer::ipc::DEFAULT_READ_TIMEOUT;
 use elp_eqwalizer::EqwalizerDiagnostics;
 use elp_eqwalizer::EqwalizerDiagnosticsDatabase;
 use elp_eqwalizer::EqwalizerStats;
+use elp_eqwalizer::EqwalizerTypeInfo;
 use elp_syntax::ast;
+use elp_syntax::TextSize;
+use fxhash::FxHashMap;
 use parking_lot::Mutex;
+use salsa::Database;
 
+use crate::disk_cache::CacheKey;
+use crate::disk_cache::CachedModule;
 use crate::ErlAstDatabase;
 
 pub trait EqwalizerLoader {
@@ -36,6 +54,10 @@ pub trait EqwalizerLoader {
         build_info_path: &AbsPath,
         modules: Vec<FileId>,
     ) -> EqwalizerDiagnostics;
+
+    /// The types inferred by the last eqWAlizer run over a module, if
+    /// its text has not changed since.
+    fn checked_types(&self, file_id: FileId) -> Option<Arc<Vec<EqwalizerTypeInfo>>>;
 }
 
 impl EqwalizerLoader for crate::RootDatabase {
@@ -44,17 +66,265 @@ impl EqwalizerLoader for crate::RootDatabase {
         project_id: ProjectId,
         build_info_path: &AbsPath,
         modules: Vec<FileId>,
+    ) -> EqwalizerDiagnostics {
+        let cache = match &self.eqwalizer_cache {
+            Some(cache) if self.eqwalizer.is_bundled() => cache,
+            _ => return self.run_eqwalizer(project_id, build_info_path, modules),
+        };
+        let module_index = self.module_index(project_id);
+        let mut errors = FxHashMap::default();
+        let mut type_info = FxHashMap::default();
+        let mut unchecked = Vec::new();
+        for file_id in modules {
+            let module = module_index.module_for_file(file_id).unwrap().as_str();
+            let key = eqwalizer_cache_key(self, project_id, module);
+            match cache.load(&key) {
+                Some(cached) => {
+                    self.eqwalizer_types.record(
+                        file_id,
+                        SourceDatabaseExt::file_text(self, file_id),
+                        cached.type_info.clone(),
+                    );
+                    errors.insert(module.to_string(), cached.errors);
+                    type_info.insert(module.to_string(), cached.type_info);
+                }
+                None => unchecked.push((file_id, module, key)),
+            }
+        }
+        let cached = EqwalizerDiagnostics::Diagnostics { errors, type_info };
+        if unchecked.is_empty() {
+            return cached;
+        }
+        let diagnostics = self.run_eqwalizer(
+            project_id,
+            build_info_path,
+            unchecked.iter().map(|(file_id, _, _)| *file_id).collect(),
+        );
+        if let EqwalizerDiagnostics::Diagnostics { errors, type_info } = &diagnostics {
+            for (_, module, key) in &unchecked {
+                let module = CachedModule {
+                    errors: errors.get(*module).cloned().unwrap_or_default(),
+                    type_info: type_info.get(*module).cloned().unwrap_or_default(),
+                };
+                cache.store(key, &module);
+            }
+        }
+        cached.combine(&diagnostics)
+    }
+
+    fn checked_types(&self, file_id: FileId) -> Option<Arc<Vec<EqwalizerTypeInfo>>> {
+        let text = SourceDatabaseExt::file_text(self, file_id);
+        self.eqwalizer_types.get(file_id, &text)
+    }
+}
+
+impl crate::RootDatabase {
+    fn run_eqwalizer(
+        &self,
+        project_id: ProjectId,
+        build_info_path: &AbsPath,
+        modules: Vec<FileId>,
     ) -> EqwalizerDiagnostics {
         let module_index = self.module_index(project_id);
         let module_names: Vec<&str> = modules
             .iter()
             .map(|&f| module_index.module_for_file(f).unwrap().as_str())
             .collect();
-        self.eqwalizer
-            .typecheck(build_info_path.as_ref(), self, project_id, module_names)
+        let config = self.eqwalizer_client;
+        let mut attempt = 0;
+        loop {
+            let start = Instant::now();
+            let res = self.eqwalizer.typecheck(
+                build_info_path.as_ref(),
+                self,
+                project_id,
+                module_names.clone(),
+                config.timeout,
+            );
+            self.eqwalizer_health.record_call(start.elapsed());
+            let err = match res {
+                Ok(diagnostics) => {
+                    if let EqwalizerDiagnostics::Diagnostics { type_info, .. } = &diagnostics {
+                        for (&file_id, module) in modules.iter().zip(&module_names) {
+                            if let Some(types) = type_info.get(*module) {
+                                self.eqwalizer_types.record(
+                                    file_id,
+                                    SourceDatabaseExt::file_text(self, file_id),
+                                    types.clone(),
+                                );
+                            }
+                        }
+                    }
+                    return diagnostics;
+                }
+                Err(err) => err,
+            };
+            let timed_out = is_timeout(&err);
+            if timed_out {
+                self.eqwalizer_health
+                    .timeouts
+                    .fetch_add(1, Ordering::Relaxed);
+            }
+            if attempt < config.max_retries && (timed_out || is_transient(&err)) {
+                attempt += 1;
+                self.eqwalizer_health
+                    .retries
+                    .fetch_add(1, Ordering::Relaxed);
+                log::warn!(
+                    "eqWAlizer call failed, retrying ({}/{}): {:#}",
+                    attempt,
+                    config.max_retries,
+                    err
+                );
+                thread::sleep(retry_delay(config.retry_delay, attempt));
+                self.unwind_if_cancelled();
+                continue;
+            }
+            self.eqwalizer_health
+                .failures
+                .fetch_add(1, Ordering::Relaxed);
+            return EqwalizerDiagnostics::Error(format!("{}", err));
+        }
+    }
+}
+
+/// The results of a module depend on the modules it uses, so the key
+/// covers all the sources of the project and OTP, and the options they
+/// are compiled with.
+fn eqwalizer_cache_key(db: &dyn EqwalizerDatabase, project_id: ProjectId, module: &str) -> String {
+    let mut key = CacheKey::new("eqwalizer");
+    key.add_str(module)
+        .add_str(&db.eqwalizer_sources_digest(project_id));
+    key.finish()
+}
+
+/// The types inferred by the last eqWAlizer run over each module, shared
+/// by all the snapshots of the database. They are recorded as the
+/// eqWAlizer diagnostics are computed, so that hover, completions and
+/// assists can use them without ever starting a typecheck.
+#[derive(Debug, Default)]
+pub(crate) struct EqwalizerTypes {
+    by_file: Mutex<FxHashMap<FileId, (Arc<String>, Arc<Vec<EqwalizerTypeInfo>>)>>,
+}
+
+impl EqwalizerTypes {
+    fn record(&self, file_id: FileId, text: Arc<String>, mut types: Vec<EqwalizerTypeInfo>) {
+        types.sort_by_key(|info| (info.range.start(), info.range.end()));
+        self.by_file.lock().insert(file_id, (text, Arc::new(types)));
+    }
+
+    /// The types of a file, if they were inferred for `text`, as the
+    /// ranges of older ones may not match the expressions anymore.
+    fn get(&self, file_id: FileId, text: &Arc<String>) -> Option<Arc<Vec<EqwalizerTypeInfo>>> {
+        let by_file = self.by_file.lock();
+        let (checked_text, types) = by_file.get(&file_id)?;
+        if Arc::ptr_eq(checked_text, text) || checked_text == text {
+            Some(types.clone())
+        } else {
+            None
+        }
+    }
+}
+
+/// How ELP calls the eqWAlizer subprocess.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EqwalizerClientConfig {
+    /// How long eqWAlizer can go without sending anything before the
+    /// call is considered hung, and abandoned.
+    pub timeout: Duration,
+    /// How many times a call that timed out, or whose process died, is
+    /// retried.
+    pub max_retries: u32,
+    /// The delay before the first retry, doubled for each further one.
+    pub retry_delay: Duration,
+}
+
+impl Default for EqwalizerClientConfig {
+    fn default() -> Self {
+        EqwalizerClientConfig {
+            timeout: DEFAULT_READ_TIMEOUT,
+            max_retries: 2,
+            retry_delay: Duration::from_millis(500),
+        }
     }
 }
 
+/// Counters of the calls to eqWAlizer, shared by all the snapshots of
+/// the database.
+#[derive(Debug, Default)]
+pub struct EqwalizerHealth {
+    calls: AtomicU64,
+    timeouts: AtomicU64,
+    retries: AtomicU64,
+    failures: AtomicU64,
+    total_latency_us: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EqwalizerHealthReport {
+    /// Calls made, including retries
+    pub calls: u64,
+    pub timeouts: u64,
+    pub retries: u64,
+    /// Calls which failed even after being retried
+    pub failures: u64,
+    pub average_latency: Duration,
+}
+
+impl EqwalizerHealth {
+    fn record_call(&self, latency: Duration) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.total_latency_us
+            .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn report(&self) -> EqwalizerHealthReport {
+        let calls = self.calls.load(Ordering::Relaxed);
+        let total_latency_us = self.total_latency_us.load(Ordering::Relaxed);
+        EqwalizerHealthReport {
+            calls,
+            timeouts: self.timeouts.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+            average_latency: Duration::from_micros(
+                total_latency_us.checked_div(calls).unwrap_or(0),
+            ),
+        }
+    }
+}
+
+fn io_error_kinds(err: &anyhow::Error) -> impl Iterator<Item = io::ErrorKind> + '_ {
+    err.chain()
+        .filter_map(|cause| cause.downcast_ref::<io::Error>())
+        .map(|err| err.kind())
+}
+
+fn is_timeout(err: &anyhow::Error) -> bool {
+    io_error_kinds(err).any(|kind| kind == io::ErrorKind::TimedOut)
+}
+
+/// Failures which may not happen again, as opposed to e.g. the
+/// eqWAlizer executable not being found.
+fn is_transient(err: &anyhow::Error) -> bool {
+    io_error_kinds(err).any(|kind| {
+        matches!(
+            kind,
+            io::ErrorKind::TimedOut
+                | io::ErrorKind::UnexpectedEof
+                | io::ErrorKind::BrokenPipe
+                | io::ErrorKind::Interrupted
+        )
+    })
+}
+
+/// Exponential backoff, with up to 50% of jitter either way, so that
+/// concurrent calls failing together are not retried together.
+fn retry_delay(base: Duration, attempt: u32) -> Duration {
+    let backoff = base.saturating_mul(1 << attempt.saturating_sub(1).min(16));
+    let random = RandomState::new().build_hasher().finish();
+    backoff.mul_f64(0.5 + (random % 1000) as f64 / 1000.0)
+}
+
 #[salsa::query_group(EqwalizerDatabaseStorage)]
 pub trait EqwalizerDatabase:
     EqwalizerDiagnosticsDatabase
@@ -73,10 +343,19 @@ pub trait EqwalizerDatabase:
         project_id: ProjectId,
         file_id: FileId,
     ) -> Option<Arc<EqwalizerStats>>;
+    /// The types eqWAlizer inferred for the expressions of a module,
+    /// ordered by range, when the eqWAlizer diagnostics were last
+    /// computed for its current text. This never runs eqWAlizer, and
+    /// gives `None` until it has checked the module.
+    #[salsa::transparent]
+    fn types_for_file(&self, file_id: FileId) -> Option<Arc<Vec<EqwalizerTypeInfo>>>;
     fn has_eqwalizer_app_marker(&self, source_root_id: SourceRootId) -> bool;
     fn has_eqwalizer_module_marker(&self, file_id: FileId) -> bool;
     fn has_eqwalizer_ignore_marker(&self, file_id: FileId) -> bool;
     fn is_eqwalizer_enabled(&self, file_id: FileId, include_generated: bool) -> bool;
+    /// A digest of the files of the project and OTP, and of the compile
+    /// options of the apps, which the eqWAlizer results depend on.
+    fn eqwalizer_sources_digest(&self, project_id: ProjectId) -> String;
 }
 
 fn eqwalizer_diagnostics(
@@ -96,6 +375,42 @@ fn eqwalizer_diagnostics(
     }
 }
 
+fn eqwalizer_sources_digest(db: &dyn EqwalizerDatabase, project_id: ProjectId) -> String {
+    let project_data = db.project_data(project_id);
+    let otp_roots = project_data
+        .otp_project_id
+        .map(|otp_project_id| db.project_data(otp_project_id).source_roots.clone())
+        .unwrap_or_default();
+    let mut files: Vec<(String, FileId)> = Vec::new();
+    for root_id in project_data.source_roots.iter().chain(otp_roots.iter()) {
+        let root = db.source_root(*root_id);
+        for file_id in root.iter() {
+            if let Some(path) = root.path_for_file(&file_id) {
+                files.push((path.to_string(), file_id));
+            }
+        }
+    }
+    // File ids depend on the order files were loaded in
+    files.sort();
+    let mut key = CacheKey::new("eqwalizer_sources");
+    for (path, file_id) in files {
+        key.add_str(&path).add_str(&db.file_text(file_id));
+    }
+    for root_id in project_data.source_roots.iter() {
+        if let Some(app_data) = db.app_data(*root_id) {
+            key.add_str(app_data.name.as_str());
+            for include in &app_data.include_path {
+                key.add_str(&include.display().to_string());
+            }
+            for term in app_data.macros.iter().chain(&app_data.parse_transforms) {
+                key.add_term(term);
+            }
+            key.add_str(&db.parse_transforms_digest(*root_id));
+        }
+    }
+    key.finish()
+}
+
 fn eqwalizer_stats(
     db: &dyn EqwalizerDatabase,
     project_id: ProjectId,
@@ -106,6 +421,30 @@ fn eqwalizer_stats(
     db.compute_eqwalizer_stats(project_id, ModuleName::new(module_name))
 }
 
+fn types_for_file(
+    db: &dyn EqwalizerDatabase,
+    file_id: FileId,
+) -> Option<Arc<Vec<EqwalizerTypeInfo>>> {
+    db.checked_types(file_id)
+}
+
+/// The type of the innermost expression at the given position, as
+/// inferred by the last eqWAlizer run over the file.
+pub fn type_at_position(
+    db: &dyn EqwalizerDatabase,
+    position: FilePosition,
+) -> Option<EqwalizerTypeInfo> {
+    let types = db.types_for_file(position.file_id)?;
+    innermost_type(&types, position.offset).cloned()
+}
+
+fn innermost_type(types: &[EqwalizerTypeInfo], offset: TextSize) -> Option<&EqwalizerTypeInfo> {
+    types
+        .iter()
+        .filter(|info| info.range.contains_inclusive(offset))
+        .min_by_key(|info| info.range.len())
+}
+
 fn is_eqwalizer_enabled(
     db: &dyn EqwalizerDatabase,
     file_id: FileId,
@@ -286,6 +625,7 @@ fn is_ignore_or_fixme_atom(expr: ast::Expr) -> bool {
 #[cfg(test)]
 mod tests {
     use elp_base_db::fixture::WithFixture;
+    use elp_syntax::TextRange;
 
     use super::*;
     use crate::RootDatabase;
@@ -365,4 +705,99 @@ mod tests {
 
         assert!(db.has_eqwalizer_ignore_marker(file_id));
     }
+
+    #[test]
+    fn innermost_type_at_offset() {
+        let info = |start: u32, end: u32, ty: &str| EqwalizerTypeInfo {
+            range: TextRange::new(start.into(), end.into()),
+            ty: ty.to_string(),
+        };
+        let types = vec![
+            info(0, 20, "{ok, number()}"),
+            info(5, 10, "number()"),
+            info(12, 20, "atom()"),
+        ];
+        let ty_at =
+            |offset: u32| innermost_type(&types, offset.into()).map(|info| info.ty.as_str());
+        assert_eq!(ty_at(2), Some("{ok, number()}"));
+        assert_eq!(ty_at(7), Some("number()"));
+        assert_eq!(ty_at(12), Some("atom()"));
+        assert_eq!(ty_at(25), None);
+    }
+
+    #[test]
+    fn types_only_for_checked_text() {
+        let (db, file_id) = RootDatabase::with_single_file(
+            r#"
+-module(test).
+-typing([eqwalizer]).
+f() -> ok.
+"#,
+        );
+        assert_eq!(db.types_for_file(file_id), None);
+
+        let info = |start: u32, end: u32, ty: &str| EqwalizerTypeInfo {
+            range: TextRange::new(start.into(), end.into()),
+            ty: ty.to_string(),
+        };
+        let checked = SourceDatabaseExt::file_text(&db, file_id);
+        db.eqwalizer_types.record(
+            file_id,
+            checked.clone(),
+            vec![info(40, 42, "'ok'"), info(34, 42, "fun(() -> 'ok')")],
+        );
+        let types = db.types_for_file(file_id).unwrap();
+        assert_eq!(types[0].ty, "fun(() -> 'ok')");
+
+        db.eqwalizer_types.record(
+            file_id,
+            Arc::new(format!("{}\n", checked)),
+            vec![info(40, 42, "'ok'")],
+        );
+        assert_eq!(db.types_for_file(file_id), None);
+    }
+
+    #[test]
+    fn health_report_averages_latency() {
+        let health = EqwalizerHealth::default();
+        assert_eq!(health.report().average_latency, Duration::ZERO);
+        health.record_call(Duration::from_millis(10));
+        health.record_call(Duration::from_millis(30));
+        health.retries.fetch_add(1, Ordering::Relaxed);
+        let report = health.report();
+        assert_eq!(report.calls, 2);
+        assert_eq!(report.retries, 1);
+        assert_eq!(report.average_latency, Duration::from_millis(20));
+    }
+
+    #[test]
+    fn timeouts_and_crashes_are_transient() {
+        let timeout = anyhow::Error::new(io::Error::from(io::ErrorKind::TimedOut))
+            .context("receiving message");
+        assert!(is_timeout(&timeout));
+        assert!(is_transient(&timeout));
+
+        let crash = anyhow::Error::new(io::Error::from(io::ErrorKind::UnexpectedEof));
+        assert!(!is_timeout(&crash));
+        assert!(is_transient(&crash));
+
+        let missing = anyhow::Error::new(io::Error::from(io::ErrorKind::NotFound))
+            .context("starting eqWAlizer process");
+        assert!(!is_transient(&missing));
+        assert!(!is_transient(&anyhow::anyhow!("no eqWAlizer handle")));
+    }
+
+    #[test]
+    fn retry_delay_backs_off_with_jitter() {
+        let base = Duration::from_millis(100);
+        for attempt in 1..=3 {
+            let backoff = base * 2u32.pow(attempt - 1);
+            let delay = retry_delay(base, attempt);
+            assert!(
+                delay >= backoff / 2 && delay < backoff * 3 / 2,
+                "{:?}",
+                delay
+            );
+        }
+    }
 }
@@ -7,25 +7,33 @@
  * of this source tree.
  */
 
+use std::fs;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use elp_base_db::encoding;
 use elp_base_db::salsa;
 use elp_base_db::AbsPath;
 use elp_base_db::AbsPathBuf;
 use elp_base_db::FileId;
 use elp_base_db::ProjectId;
 use elp_base_db::SourceDatabase;
+use elp_base_db::SourceRootId;
 use elp_erlang_service::Format;
 use elp_erlang_service::ParseError;
 use elp_erlang_service::ParseResult;
+use hir::db::MinDefDatabase;
 
+use crate::disk_cache::CacheKey;
 use crate::erlang_service::CompileOption;
 use crate::erlang_service::ParseRequest;
 use crate::fixmes;
 use crate::LineIndexDatabase;
 
 pub trait AstLoader {
+    /// Parses the file with the Erlang service. If the database has a
+    /// parse cache, `cache_key` identifies the result in it.
+    #[allow(clippy::too_many_arguments)]
     fn load_ast(
         &self,
         project_id: ProjectId,
@@ -35,7 +43,10 @@ pub trait AstLoader {
         parse_transforms: &[eetf::Term],
         elp_metadata: eetf::Term,
         format: Format,
+        cache_key: Option<String>,
     ) -> ParseResult;
+
+    fn has_parse_cache(&self) -> bool;
 }
 
 impl AstLoader for crate::RootDatabase {
@@ -48,6 +59,7 @@ impl AstLoader for crate::RootDatabase {
         parse_transforms: &[eetf::Term],
         elp_metadata: eetf::Term,
         format: Format,
+        cache_key: Option<String>,
     ) -> ParseResult {
         let includes = include_path
             .iter()
@@ -67,7 +79,10 @@ impl AstLoader for crate::RootDatabase {
         };
 
         if let Some(erlang_service) = self.erlang_services.read().get(&project_id).cloned() {
-            erlang_service.request_parse(req)
+            match (&self.parse_cache, cache_key) {
+                (Some(cache), Some(key)) => erlang_service.request_parse_cached(req, cache, &key),
+                _ => erlang_service.request_parse(req),
+            }
         } else {
             log::error!("No parse server for project: {:?}", project_id);
             ParseResult::error(ParseError {
@@ -78,11 +93,24 @@ impl AstLoader for crate::RootDatabase {
             })
         }
     }
+
+    fn has_parse_cache(&self) -> bool {
+        self.parse_cache.is_some()
+    }
 }
 
 #[salsa::query_group(ErlAstDatabaseStorage)]
-pub trait ErlAstDatabase: SourceDatabase + AstLoader + LineIndexDatabase {
+pub trait ErlAstDatabase: SourceDatabase + AstLoader + LineIndexDatabase + MinDefDatabase {
     fn module_ast(&self, file_id: FileId, format: Format) -> Arc<ParseResult>;
+    /// A digest of the paths and texts of the headers a module includes,
+    /// directly or not.
+    fn headers_digest(&self, file_id: FileId) -> String;
+    /// A digest of the beams of the parse transforms of an app, as
+    /// found in the code path of the Erlang service. The beams are read
+    /// by `RootDatabase::update_parse_transforms_digests`, as they are
+    /// not tracked by salsa, and it is only set with a parse cache.
+    #[salsa::input]
+    fn parse_transforms_digest(&self, source_root_id: SourceRootId) -> Arc<String>;
 }
 
 fn module_ast(db: &dyn ErlAstDatabase, file_id: FileId, format: Format) -> Arc<ParseResult> {
@@ -104,6 +132,29 @@ fn module_ast(db: &dyn ErlAstDatabase, file_id: FileId, format: Format) -> Arc<P
         }));
     };
     let metadata = elp_metadata(db, file_id).into();
+    // The Erlang service parses the file on disk, so a result for a
+    // text being edited would be stored under the wrong key
+    let cache_key = if db.has_parse_cache() && text_on_disk(db, file_id, path) {
+        // The metadata is derived from the text
+        let mut key = CacheKey::new("ast");
+        key.add_str(&path.display().to_string())
+            .add_str(&db.file_text(file_id));
+        for include in &app_data.include_path {
+            key.add_str(&include.display().to_string());
+        }
+        for term in app_data.macros.iter().chain(&app_data.parse_transforms) {
+            key.add_term(term);
+        }
+        key.add_str(match format {
+            Format::OffsetEtf => "offset_etf",
+            Format::Text => "text",
+        })
+        .add_str(&db.headers_digest(file_id))
+        .add_str(&db.parse_transforms_digest(root_id));
+        Some(key.finish())
+    } else {
+        None
+    };
     Arc::new(db.load_ast(
         app_data.project_id,
         path,
@@ -112,16 +163,84 @@ fn module_ast(db: &dyn ErlAstDatabase, file_id: FileId, format: Format) -> Arc<P
         &app_data.parse_transforms,
         metadata,
         format,
+        cache_key,
     ))
 }
 
+/// The read is not tracked by salsa, but a file changed on disk is also
+/// changed in the VFS, and so its text.
+fn text_on_disk(db: &dyn ErlAstDatabase, file_id: FileId, path: &AbsPath) -> bool {
+    match fs::read(path) {
+        Ok(bytes) => encoding::decode(bytes) == *db.file_text(file_id),
+        Err(_) => false,
+    }
+}
+
+fn headers_digest(db: &dyn ErlAstDatabase, file_id: FileId) -> String {
+    let def_map = db.def_map(file_id);
+    let mut headers: Vec<(String, FileId)> = def_map
+        .get_included_files()
+        .filter_map(|file_id| {
+            let root = db.source_root(db.file_source_root(file_id));
+            let path = root.path_for_file(&file_id)?.to_string();
+            Some((path, file_id))
+        })
+        .collect();
+    // File ids depend on the order files were loaded in
+    headers.sort();
+    let mut key = CacheKey::new("headers");
+    for (path, file_id) in headers {
+        key.add_str(&path).add_str(&db.file_text(file_id));
+    }
+    key.finish()
+}
+
+/// Reads the beams of the parse transforms of an app, for
+/// `parse_transforms_digest`.
+pub(crate) fn read_parse_transforms_digest(
+    db: &dyn SourceDatabase,
+    source_root_id: SourceRootId,
+) -> String {
+    let mut key = CacheKey::new("parse_transforms");
+    let app_data = match db.app_data(source_root_id) {
+        Some(app_data) => app_data,
+        None => return key.finish(),
+    };
+    let project_data = db.project_data(app_data.project_id);
+    let ebins: Vec<_> = project_data
+        .deps_ebins
+        .iter()
+        .chain(app_data.ebin_path.iter())
+        .collect();
+    for term in &app_data.parse_transforms {
+        // A parse transform is given by its module, or `{Module, Options}`
+        let module = match term {
+            eetf::Term::Atom(atom) => &atom.name,
+            eetf::Term::Tuple(tuple) => match tuple.elements.first() {
+                Some(eetf::Term::Atom(atom)) => &atom.name,
+                _ => continue,
+            },
+            _ => continue,
+        };
+        key.add_str(module);
+        // The first beam found is the one loaded, as in the code path
+        let beam = ebins
+            .iter()
+            .find_map(|ebin| fs::read(ebin.join(format!("{module}.beam"))).ok());
+        key.add(&beam.unwrap_or_default());
+    }
+    key.finish()
+}
+
 fn elp_metadata(db: &dyn ErlAstDatabase, file_id: FileId) -> eetf::Term {
     let line_index = db.file_line_index(file_id);
     let file_text = db.file_text(file_id);
     let fixmes = fixmes::fixmes_eetf(&line_index, &file_text);
     // Erlang proplist: [{eqwalizer_fixmes, [Fixme1, Fixme2....]}]
-    eetf::List::from(vec![
-        eetf::Tuple::from(vec![eetf::Atom::from("eqwalizer_fixmes").into(), fixmes]).into(),
+    eetf::List::from(vec![eetf::Tuple::from(vec![
+        eetf::Atom::from("eqwalizer_fixmes").into(),
+        fixmes,
     ])
+    .into()])
     .into()
 }
@@ -28,6 +28,7 @@ use elp_syntax::AstNode;
 use elp_syntax::SyntaxKind;
 use elp_syntax::SyntaxToken;
 use erlang_service::Connection;
+use erlang_service::ParseCache;
 use fxhash::FxHashMap;
 use helpers::pick_best_token;
 use hir::db::MinDefDatabase;
@@ -42,12 +43,17 @@ use serde::Serialize;
 
 mod apply_change;
 mod defs;
+pub mod deprecations;
+mod disk_cache;
 pub mod docs;
 pub mod eqwalizer;
 mod erl_ast;
 mod fixmes;
 mod line_index;
+mod reference_index;
 mod search;
+mod symbol_index;
+pub mod table_keys;
 
 // ---------------------------------------------------------------------
 pub mod assists;
@@ -60,21 +66,34 @@ pub use defs::ReferenceClass;
 pub use defs::ReferenceType;
 pub use defs::SymbolClass;
 pub use defs::SymbolDefinition;
+pub use deprecations::DeprecationDatabase;
+pub use disk_cache::EqwalizerCache;
 pub use elp_base_db;
 pub use elp_base_db::impl_intern_key;
 pub use elp_eqwalizer::Eqwalizer;
 pub use elp_eqwalizer::EqwalizerDiagnostic;
 pub use elp_eqwalizer::EqwalizerDiagnostics;
 pub use elp_eqwalizer::EqwalizerStats;
+pub use elp_eqwalizer::EqwalizerTypeInfo;
 pub use elp_erlang_service as erlang_service;
+pub use eqwalizer::EqwalizerClientConfig;
 pub use eqwalizer::EqwalizerDatabase;
+pub use eqwalizer::EqwalizerHealth;
+pub use eqwalizer::EqwalizerHealthReport;
+use eqwalizer::EqwalizerTypes;
 pub use erl_ast::ErlAstDatabase;
 pub use line_index::LineCol;
 pub use line_index::LineIndex;
+pub use reference_index::FunctionReferenceIndex;
+pub use reference_index::ReferenceIndexDatabase;
 pub use search::FindUsages;
 pub use search::ReferenceCategory;
 pub use search::SearchScope;
 pub use search::UsageSearchResult;
+pub use symbol_index::FileSymbol;
+pub use symbol_index::SymbolIndex;
+pub use symbol_index::SymbolIndexDatabase;
+pub use table_keys::TableKeyDatabase;
 
 pub type FxIndexMap<K, V> =
     indexmap::IndexMap<K, V, std::hash::BuildHasherDefault<rustc_hash::FxHasher>>;
@@ -91,6 +110,7 @@ pub trait EqwalizerProgressReporter: Send + Sync + RefUnwindSafe {
 
 #[salsa::database(
     LineIndexDatabaseStorage,
+    deprecations::DeprecationDatabaseStorage,
     docs::DocDatabaseStorage,
     elp_base_db::SourceDatabaseExtStorage,
     elp_base_db::SourceDatabaseStorage,
@@ -99,14 +119,22 @@ pub trait EqwalizerProgressReporter: Send + Sync + RefUnwindSafe {
     elp_eqwalizer::EqwalizerDiagnosticsDatabaseStorage,
     erl_ast::ErlAstDatabaseStorage,
     hir::db::MinInternDatabaseStorage,
-    hir::db::MinDefDatabaseStorage
+    hir::db::MinDefDatabaseStorage,
+    reference_index::ReferenceIndexDatabaseStorage,
+    symbol_index::SymbolIndexDatabaseStorage,
+    table_keys::TableKeyDatabaseStorage
 )]
 pub struct RootDatabase {
     storage: salsa::Storage<Self>,
     erlang_services: Arc<AssertUnwindSafe<RwLock<FxHashMap<ProjectId, Connection>>>>,
     eqwalizer: Eqwalizer,
+    eqwalizer_client: EqwalizerClientConfig,
+    eqwalizer_health: Arc<EqwalizerHealth>,
+    eqwalizer_types: Arc<AssertUnwindSafe<EqwalizerTypes>>,
     eqwalizer_progress_reporter: EqwalizerProgressReporterBox,
     ipc_handles: Arc<AssertUnwindSafe<RwLock<FxHashMap<String, Arc<Mutex<IpcHandle>>>>>>,
+    parse_cache: Option<ParseCache>,
+    eqwalizer_cache: Option<EqwalizerCache>,
 }
 
 impl Default for RootDatabase {
@@ -115,8 +143,13 @@ impl Default for RootDatabase {
             storage: salsa::Storage::default(),
             erlang_services: Arc::default(),
             eqwalizer: Eqwalizer::default(),
+            eqwalizer_client: EqwalizerClientConfig::default(),
+            eqwalizer_health: Arc::default(),
+            eqwalizer_types: Arc::default(),
             eqwalizer_progress_reporter: EqwalizerProgressReporterBox::default(),
             ipc_handles: Arc::default(),
+            parse_cache: None,
+            eqwalizer_cache: None,
         };
         db.set_include_files_revision(0);
         db
@@ -161,8 +194,13 @@ impl salsa::ParallelDatabase for RootDatabase {
             storage: self.storage.snapshot(),
             erlang_services: self.erlang_services.clone(),
             eqwalizer: self.eqwalizer.clone(),
+            eqwalizer_client: self.eqwalizer_client,
+            eqwalizer_health: self.eqwalizer_health.clone(),
+            eqwalizer_types: self.eqwalizer_types.clone(),
             eqwalizer_progress_reporter: self.eqwalizer_progress_reporter.clone(),
             ipc_handles: self.ipc_handles.clone(),
+            parse_cache: self.parse_cache.clone(),
+            eqwalizer_cache: self.eqwalizer_cache.clone(),
         })
     }
 }
@@ -178,6 +216,14 @@ impl RootDatabase {
         self.erlang_services.write().clear();
     }
 
+    /// Stop the Erlang services of the projects not kept, e.g. once they
+    /// are unloaded.
+    pub fn retain_erlang_services(&mut self, keep: impl Fn(ProjectId) -> bool) {
+        self.erlang_services
+            .write()
+            .retain(|project_id, _| keep(*project_id));
+    }
+
     pub fn ensure_erlang_service(&self, project_id: ProjectId) -> Result<()> {
         let connection = Connection::start()?;
 
@@ -205,6 +251,21 @@ impl RootDatabase {
         }
     }
 
+    /// Read the beams of the parse transforms of the apps of a project,
+    /// and of OTP, again, e.g. once its dependencies are compiled. The
+    /// parse cache is keyed on them.
+    pub fn update_parse_transforms_digests(&mut self, project_id: ProjectId) {
+        let project_data = self.project_data(project_id);
+        let otp_roots = project_data
+            .otp_project_id
+            .map(|otp_project_id| self.project_data(otp_project_id).source_roots.clone())
+            .unwrap_or_default();
+        for &root_id in project_data.source_roots.iter().chain(otp_roots.iter()) {
+            let digest = erl_ast::read_parse_transforms_digest(self, root_id);
+            self.set_parse_transforms_digest(root_id, Arc::new(digest));
+        }
+    }
+
     pub fn set_eqwalizer_progress_reporter(
         &self,
         report: Option<Box<dyn EqwalizerProgressReporter>>,
@@ -212,10 +273,31 @@ impl RootDatabase {
         *self.eqwalizer_progress_reporter.lock() = report
     }
 
+    /// Keep the Erlang service parse results on disk, in `cache`, to
+    /// reuse them across runs.
+    pub fn set_parse_cache(&mut self, cache: Option<ParseCache>) {
+        self.parse_cache = cache
+    }
+
+    /// Keep the eqWAlizer results of modules on disk, in `cache`, to
+    /// reuse them across runs. Only the results of the eqWAlizer bundled
+    /// with ELP are kept.
+    pub fn set_eqwalizer_cache(&mut self, cache: Option<EqwalizerCache>) {
+        self.eqwalizer_cache = cache
+    }
+
     pub fn eqwalizer(&self) -> &Eqwalizer {
         &self.eqwalizer
     }
 
+    pub fn set_eqwalizer_client_config(&mut self, config: EqwalizerClientConfig) {
+        self.eqwalizer_client = config
+    }
+
+    pub fn eqwalizer_health(&self) -> EqwalizerHealthReport {
+        self.eqwalizer_health.report()
+    }
+
     pub fn in_shell(&mut self) -> () {
         self.eqwalizer.shell = true
     }
@@ -327,6 +409,7 @@ pub enum SymbolKind {
     Define,
     Variable,
     Callback,
+    TableKey,
 }
 
 // ---------------------------------------------------------------------
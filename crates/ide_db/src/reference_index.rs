@@ -0,0 +1,161 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Project wide reverse index of function references: for each
+//! `module:function/arity`, the files that call or capture it.
+//!
+//! The references of a file are derived from its function bodies, so
+//! editing a file only recomputes the references of that file before
+//! the project index is merged again.
+
+use std::sync::Arc;
+
+use elp_base_db::salsa;
+use elp_base_db::FileId;
+use elp_base_db::ProjectId;
+use fxhash::FxHashMap;
+use fxhash::FxHashSet;
+use hir::db::MinDefDatabase;
+use hir::Body;
+use hir::CallTarget;
+use hir::Expr;
+use hir::ExprId;
+use hir::InFile;
+use hir::Literal;
+use hir::Name;
+use hir::NameArity;
+
+#[salsa::query_group(ReferenceIndexDatabaseStorage)]
+pub trait ReferenceIndexDatabase: MinDefDatabase {
+    /// The functions called or captured by the function bodies of a
+    /// file, as `(module, function/arity)`.
+    fn file_function_references(&self, file_id: FileId) -> Arc<FxHashSet<(Name, NameArity)>>;
+
+    /// The function references of all the modules of a project.
+    fn function_reference_index(&self, project_id: ProjectId) -> Arc<FunctionReferenceIndex>;
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FunctionReferenceIndex {
+    callers: FxHashMap<(Name, NameArity), Vec<FileId>>,
+}
+
+impl FunctionReferenceIndex {
+    /// The files referencing `module:function/arity`, ordered by file.
+    pub fn callers(&self, module: &Name, function: &NameArity) -> &[FileId] {
+        self.callers
+            .get(&(module.clone(), function.clone()))
+            .map_or(&[], |files| files.as_slice())
+    }
+
+    pub fn is_referenced(&self, module: &Name, function: &NameArity) -> bool {
+        !self.callers(module, function).is_empty()
+    }
+}
+
+fn file_function_references(
+    db: &dyn ReferenceIndexDatabase,
+    file_id: FileId,
+) -> Arc<FxHashSet<(Name, NameArity)>> {
+    let mut res = FxHashSet::default();
+    let form_list = db.file_form_list(file_id);
+    let module = match form_list.module_attribute() {
+        Some(attr) => attr.name.clone(),
+        // Functions in header files are attributed to the modules
+        // including them.
+        None => return Arc::new(res),
+    };
+    for (function_id, _) in form_list.functions() {
+        let function_body = db.function_body(InFile::new(file_id, function_id));
+        let body = &function_body.body;
+        for (_, expr) in body.exprs.iter() {
+            match expr {
+                Expr::Call { target, args } => {
+                    if let Some(reference) = resolve_target(db, body, &module, target, args.len()) {
+                        res.insert(reference);
+                    }
+                    // `apply/3`, `spawn/3`, `rpc:call/4` and friends
+                    // take the module, function and arguments as
+                    // consecutive arguments.
+                    res.extend(
+                        args.windows(3)
+                            .filter_map(|mfa| resolve_mfa_args(db, body, mfa)),
+                    );
+                }
+                Expr::CaptureFun { target, arity } => {
+                    if let Expr::Literal(Literal::Integer(arity)) = &body[*arity] {
+                        if let Some(reference) =
+                            resolve_target(db, body, &module, target, *arity as usize)
+                        {
+                            res.insert(reference);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    Arc::new(res)
+}
+
+fn resolve_target(
+    db: &dyn ReferenceIndexDatabase,
+    body: &Body,
+    current_module: &Name,
+    target: &CallTarget<ExprId>,
+    arity: usize,
+) -> Option<(Name, NameArity)> {
+    let (module, name) = match target {
+        CallTarget::Local { name } => (current_module.clone(), body[*name].as_atom()?),
+        CallTarget::Remote { module, name } => (
+            db.lookup_atom(body[*module].as_atom()?),
+            body[*name].as_atom()?,
+        ),
+    };
+    Some((module, NameArity::new(db.lookup_atom(name), arity as u32)))
+}
+
+fn resolve_mfa_args(
+    db: &dyn ReferenceIndexDatabase,
+    body: &Body,
+    mfa: &[ExprId],
+) -> Option<(Name, NameArity)> {
+    let module = db.lookup_atom(body[mfa[0]].as_atom()?);
+    let name = db.lookup_atom(body[mfa[1]].as_atom()?);
+    let arity = list_length(body, mfa[2])?;
+    Some((module, NameArity::new(name, arity)))
+}
+
+fn list_length(body: &Body, expr: ExprId) -> Option<u32> {
+    match &body[expr] {
+        Expr::List { exprs, tail: None } => Some(exprs.len() as u32),
+        _ => None,
+    }
+}
+
+fn function_reference_index(
+    db: &dyn ReferenceIndexDatabase,
+    project_id: ProjectId,
+) -> Arc<FunctionReferenceIndex> {
+    let module_index = db.module_index(project_id);
+    let mut file_ids: Vec<FileId> = module_index
+        .iter_own()
+        .map(|(_name, _source, file_id)| file_id)
+        .collect();
+    file_ids.sort();
+    file_ids.dedup();
+
+    let mut callers: FxHashMap<(Name, NameArity), Vec<FileId>> = FxHashMap::default();
+    for file_id in file_ids {
+        for reference in db.file_function_references(file_id).iter() {
+            callers.entry(reference.clone()).or_default().push(file_id);
+        }
+    }
+    Arc::new(FunctionReferenceIndex { callers })
+}
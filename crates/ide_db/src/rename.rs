@@ -14,16 +14,20 @@
 use std::fmt;
 use std::iter::once;
 
+use elp_base_db::AnchoredPathBuf;
 use elp_base_db::FileId;
 use elp_base_db::FileRange;
 use elp_syntax::ast;
 use elp_syntax::ast::in_erlang_module;
 use elp_syntax::AstNode;
+use elp_syntax::SyntaxKind;
 use hir::InFile;
+use hir::Name;
 use hir::Semantic;
 use text_edit::TextEdit;
 
 use crate::search::NameLike;
+use crate::source_change::FileSystemEdit;
 use crate::source_change::SourceChange;
 use crate::SymbolDefinition;
 
@@ -77,6 +81,59 @@ pub fn is_valid_function_name(new_name: String) -> bool {
     }
 }
 
+// Delegate checking name validity to the parser. Quoted atoms are
+// not accepted, as the module name is also used for the file name.
+pub fn is_valid_module_name(new_name: String) -> bool {
+    let parse = ast::SourceFile::parse_text(format!("-module({}).", new_name).as_str());
+    match parse.tree().forms().next() {
+        Some(ast::Form::ModuleAttribute(attr)) => match attr.name() {
+            Some(ast::Name::Atom(atom)) => {
+                !new_name.starts_with('\'') && atom.syntax().text().to_string() == new_name
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+// Delegate checking name validity to the parser
+pub fn is_valid_record_name(new_name: String) -> bool {
+    let parse = ast::SourceFile::parse_text(format!("-record({}, {{}}).", new_name).as_str());
+    match parse.tree().forms().next() {
+        Some(ast::Form::RecordDecl(rec)) => match rec.name() {
+            Some(ast::Name::Atom(atom)) => atom.syntax().text().to_string() == new_name,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+// Delegate checking name validity to the parser
+pub fn is_valid_record_field_name(new_name: String) -> bool {
+    let parse = ast::SourceFile::parse_text(format!("-record(rec, {{{}}}).", new_name).as_str());
+    match parse.tree().forms().next() {
+        Some(ast::Form::RecordDecl(rec)) => match rec.fields().next().and_then(|f| f.name()) {
+            Some(ast::Name::Atom(atom)) => atom.syntax().text().to_string() == new_name,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+// Delegate checking name validity to the parser
+pub fn is_valid_macro_name(new_name: String) -> bool {
+    let parse = ast::SourceFile::parse_text(format!("-define({}, ok).", new_name).as_str());
+    match parse.tree().forms().next() {
+        Some(ast::Form::PreprocessorDirective(ast::PreprocessorDirective::PpDefine(define))) => {
+            match define.lhs().and_then(|lhs| lhs.name()) {
+                Some(name) => name.syntax().text().to_string() == new_name,
+                None => false,
+            }
+        }
+        _ => false,
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SafetyChecks {
     Yes,
@@ -90,10 +147,8 @@ impl SymbolDefinition {
         get_name: &dyn Fn(Option<&ast::Name>) -> String,
         safety_check: SafetyChecks,
     ) -> RenameResult<SourceChange> {
+        self.check_can_rename()?;
         match self.clone() {
-            SymbolDefinition::Module(_) => {
-                rename_error!("Cannot rename module")
-            }
             SymbolDefinition::Function(fun) => {
                 if safety_check == SafetyChecks::Yes {
                     if !is_valid_function_name(get_name(None)) {
@@ -110,33 +165,133 @@ impl SymbolDefinition {
                     self.rename_reference(sema, get_name, safety_check)
                 }
             }
-            SymbolDefinition::Record(_) => {
-                rename_error!("Cannot rename record")
+            SymbolDefinition::Var(_) => {
+                if safety_check == SafetyChecks::Yes {
+                    if !is_valid_var_name(get_name(None)) {
+                        rename_error!("Invalid new variable name: '{}'", get_name(None));
+                    }
+                }
+
+                self.rename_reference(sema, get_name, safety_check)
             }
-            SymbolDefinition::RecordField(_) => {
-                rename_error!("Cannot rename record field")
+            SymbolDefinition::Module(module) => {
+                let new_name = get_name(None);
+                if safety_check == SafetyChecks::Yes {
+                    if !is_valid_module_name(new_name.clone()) {
+                        rename_error!("Invalid new module name: '{}'", new_name);
+                    }
+                    if sema
+                        .resolve_module_name(module.file.file_id, &new_name)
+                        .is_some()
+                    {
+                        rename_error!("Module '{}' already exists", new_name);
+                    }
+                }
+
+                self.rename_reference(sema, get_name, safety_check)
             }
+            SymbolDefinition::Record(record) => {
+                let new_name = get_name(None);
+                if safety_check == SafetyChecks::Yes {
+                    if !is_valid_record_name(new_name.clone()) {
+                        rename_error!("Invalid new record name: '{}'", new_name);
+                    }
+                    // The record must not clash with another one in
+                    // any of the files it can be used in.
+                    let clash = self.search_scope(sema).into_iter().find(|(file_id, _)| {
+                        sema.db
+                            .def_map(*file_id)
+                            .get_records()
+                            .keys()
+                            .any(|name| is_same_name(name, &new_name))
+                    });
+                    if let Some((file_id, _)) = clash {
+                        if file_id == record.file.file_id {
+                            rename_error!("Record '{}' already in scope", new_name);
+                        }
+                        match sema.module_name(file_id) {
+                            Some(module_name) => rename_error!(
+                                "Record '{}' already in scope in module '{}'",
+                                new_name,
+                                module_name.as_str()
+                            ),
+                            None => rename_error!("Record '{}' already in scope", new_name),
+                        }
+                    }
+                }
+
+                self.rename_reference(sema, get_name, safety_check)
+            }
+            SymbolDefinition::RecordField(field) => {
+                let new_name = get_name(None);
+                if safety_check == SafetyChecks::Yes {
+                    if !is_valid_record_field_name(new_name.clone()) {
+                        rename_error!("Invalid new record field name: '{}'", new_name);
+                    }
+                    if field
+                        .record
+                        .field_names(sema.db)
+                        .any(|name| is_same_name(&name, &new_name))
+                    {
+                        rename_error!(
+                            "Field '{}' already exists in record '{}'",
+                            new_name,
+                            field.record.record.name
+                        );
+                    }
+                }
+
+                self.rename_reference(sema, get_name, safety_check)
+            }
+            SymbolDefinition::Define(define) => {
+                let new_name = get_name(None);
+                if safety_check == SafetyChecks::Yes {
+                    if !is_valid_macro_name(new_name.clone()) {
+                        rename_error!("Invalid new macro name: '{}'", new_name);
+                    }
+                    let arity = define.define.name.arity();
+                    let clash = self.search_scope(sema).into_iter().any(|(file_id, _)| {
+                        let form_list = sema.db.file_form_list(file_id);
+                        form_list
+                            .pp_stack()
+                            .iter()
+                            .filter_map(|(_, directive)| directive.as_define())
+                            .any(|idx| {
+                                let other = &form_list[idx].name;
+                                other.arity() == arity && is_same_name(other.name(), &new_name)
+                            })
+                    });
+                    if clash {
+                        rename_error!("Macro '{}' already in scope", new_name);
+                    }
+                }
+
+                self.rename_reference(sema, get_name, safety_check)
+            }
+            // Already rejected by `check_can_rename`
+            _ => rename_error!("rename not supported for {:?}", self),
+        }
+    }
+
+    /// Check that this kind of definition can be renamed at all,
+    /// whatever the new name.
+    pub fn check_can_rename(&self) -> RenameResult<()> {
+        match self {
+            SymbolDefinition::Module(_) => Ok(()),
+            SymbolDefinition::Function(_) => Ok(()),
+            SymbolDefinition::Record(_) => Ok(()),
+            SymbolDefinition::RecordField(_) => Ok(()),
             SymbolDefinition::Type(_) => {
                 rename_error!("Cannot rename type")
             }
             SymbolDefinition::Callback(_) => {
                 rename_error!("Cannot rename callback")
             }
-            SymbolDefinition::Define(_) => {
-                rename_error!("Cannot rename define")
-            }
+            SymbolDefinition::Define(_) => Ok(()),
             SymbolDefinition::Header(_) => {
                 rename_error!("Cannot rename header")
             }
-            SymbolDefinition::Var(_) => {
-                if safety_check == SafetyChecks::Yes {
-                    if !is_valid_var_name(get_name(None)) {
-                        rename_error!("Invalid new variable name: '{}'", get_name(None));
-                    }
-                }
-
-                self.rename_reference(sema, get_name, safety_check)
-            }
+            SymbolDefinition::Var(_) => Ok(()),
         }
     }
 
@@ -247,6 +402,88 @@ impl SymbolDefinition {
                 source_edit_from_usages(&mut source_change, usages, get_name);
                 Ok(source_change)
             }
+            SymbolDefinition::Module(module) => {
+                // Only rename the module where it is used as one, an
+                // atom with the same name elsewhere may just be data.
+                let usages = self.clone().usages(sema).all();
+                let usages: Vec<(FileId, Vec<NameLike>)> = usages
+                    .into_iter()
+                    .map(|(file_id, refs)| {
+                        let refs = refs.into_iter().filter(is_module_qualifier).collect();
+                        (file_id, refs)
+                    })
+                    .collect();
+                let mut def_usages = Vec::default();
+                if let Some(attr) = module.module_attribute(sema.db) {
+                    let source_file = sema.parse(file_id).value;
+                    if let Some(name) = attr.form_id.get(&source_file).name() {
+                        def_usages.push(NameLike::Name(name));
+                    }
+                }
+
+                let usages: Vec<_> = usages
+                    .iter()
+                    .map(|(file_id, refs)| (*file_id, &refs[..]))
+                    .chain(once((file_id, &def_usages[..])))
+                    .collect();
+                source_edit_from_usages(&mut source_change, usages, get_name);
+
+                source_change.push_file_system_edit(FileSystemEdit::MoveFile {
+                    src: file_id,
+                    dst: AnchoredPathBuf {
+                        anchor: file_id,
+                        path: format!("{}.erl", get_name(None)),
+                    },
+                });
+                Ok(source_change)
+            }
+            SymbolDefinition::Record(record) => {
+                let usages = self.clone().usages(sema).all();
+                let def_usages: Vec<_> = record
+                    .source(sema.db.upcast())
+                    .name()
+                    .map(NameLike::Name)
+                    .into_iter()
+                    .collect();
+                let usages: Vec<_> = usages
+                    .iter()
+                    .chain(once((file_id, &def_usages[..])))
+                    .collect();
+                source_edit_from_usages(&mut source_change, usages, get_name);
+                Ok(source_change)
+            }
+            SymbolDefinition::RecordField(field) => {
+                let usages = self.clone().usages(sema).all();
+                let def_usages: Vec<_> = field
+                    .source(sema.db.upcast())
+                    .name()
+                    .map(NameLike::Name)
+                    .into_iter()
+                    .collect();
+                let usages: Vec<_> = usages
+                    .iter()
+                    .chain(once((file_id, &def_usages[..])))
+                    .collect();
+                source_edit_from_usages(&mut source_change, usages, get_name);
+                Ok(source_change)
+            }
+            SymbolDefinition::Define(define) => {
+                let usages = self.clone().usages(sema).all();
+                let def_usages: Vec<_> = define
+                    .source(sema.db.upcast())
+                    .lhs()
+                    .and_then(|lhs| lhs.name())
+                    .and_then(|name| ast::Name::cast(name.syntax().clone()))
+                    .map(NameLike::Name)
+                    .into_iter()
+                    .collect();
+                let usages: Vec<_> = usages
+                    .iter()
+                    .chain(once((file_id, &def_usages[..])))
+                    .collect();
+                source_edit_from_usages(&mut source_change, usages, get_name);
+                Ok(source_change)
+            }
             // Note: This is basically an internal error, this function is called from
             // SymbolDefinition::rename which already weeds them out
             _ => {
@@ -256,6 +493,26 @@ impl SymbolDefinition {
     }
 }
 
+/// Compare a name with the text of a new name, which may be quoted.
+fn is_same_name(name: &Name, new_name: &str) -> bool {
+    name.as_str() == new_name || name.to_quoted_string() == new_name
+}
+
+/// A module name in a remote call or type, an external fun, or a
+/// `-behaviour` or `-import` attribute.
+fn is_module_qualifier(name: &NameLike) -> bool {
+    match name.syntax().parent() {
+        Some(parent) => matches!(
+            parent.kind(),
+            SyntaxKind::REMOTE_MODULE
+                | SyntaxKind::MODULE
+                | SyntaxKind::BEHAVIOUR_ATTRIBUTE
+                | SyntaxKind::IMPORT_ATTRIBUTE
+        ),
+        None => false,
+    }
+}
+
 fn source_edit_from_usages(
     source_change: &mut SourceChange,
     usages: Vec<(FileId, &[NameLike])>,
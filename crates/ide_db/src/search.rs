@@ -24,7 +24,10 @@ use elp_syntax::AstNode;
 use elp_syntax::TextRange;
 use elp_syntax::TextSize;
 use fxhash::FxHashMap;
+use fxhash::FxHashSet;
 use hir::db::MinDefDatabase;
+use hir::DefineDef;
+use hir::File;
 use hir::FileKind;
 use hir::InFile;
 use hir::Semantic;
@@ -84,7 +87,8 @@ pub enum ReferenceCategory {
 /// For module-local things (e.g. local function) it's a module + all included headers,
 /// for exported things it's the entire project (because xref violations are allowed).
 /// For things defined in a header, it's all the modules including the header, and all
-/// the other headers they include.
+/// the other headers they include. Macros defined in a header are looked up in the
+/// macro reference index of the project instead.
 /// In some cases, the location of the references is known to within a `TextRange`,
 /// e.g. for things like local variables.
 #[derive(Clone, Debug)]
@@ -135,7 +139,7 @@ impl IntoIterator for SearchScope {
 }
 
 impl SymbolDefinition {
-    fn search_scope(&self, sema: &Semantic) -> SearchScope {
+    pub(crate) fn search_scope(&self, sema: &Semantic) -> SearchScope {
         if let SymbolDefinition::Var(var) = self {
             let range = var
                 .source(sema.db.upcast())
@@ -155,17 +159,14 @@ impl SymbolDefinition {
         } else if self.is_local() {
             let file = self.file();
             match file.kind(sema.db.upcast()) {
-                FileKind::Module => SearchScope::files(
+                FileKind::Module | FileKind::Escript => SearchScope::files(
                     iter::once(file.file_id).chain(file.def_map(sema.db).get_included_files()),
                 ),
-                FileKind::Header => {
-                    let def_map = file.def_map(sema.db);
-                    let included = def_map.get_included_files();
-                    let header = SymbolDefinition::Header(file.clone());
-                    let usages = header.usages(sema).all();
-                    let includers = usages.iter().map(|(file_id, _)| file_id);
-                    SearchScope::files(iter::once(file.file_id).chain(included).chain(includers))
-                }
+                FileKind::Header => match self {
+                    SymbolDefinition::Define(define) => macro_search_scope(sema, define)
+                        .unwrap_or_else(|| header_search_scope(sema, file)),
+                    _ => header_search_scope(sema, file),
+                },
                 FileKind::Other => SearchScope::single_file(self.file().file_id, None),
             }
         } else {
@@ -191,6 +192,47 @@ impl SymbolDefinition {
     }
 }
 
+/// The files where something defined in a header can be used: the
+/// header itself, every module including it, directly or through other
+/// headers, and all the files these modules include.
+fn header_search_scope(sema: &Semantic, header: &File) -> SearchScope {
+    let mut files = FxHashSet::default();
+    files.insert(header.file_id);
+    files.extend(header.def_map(sema.db).get_included_files());
+    let mut visited = FxHashSet::default();
+    let mut to_visit = vec![header.clone()];
+    while let Some(header) = to_visit.pop() {
+        if !visited.insert(header.file_id) {
+            continue;
+        }
+        let usages = SymbolDefinition::Header(header).usages(sema).all();
+        for (file_id, _) in usages.iter() {
+            let includer = File { file_id };
+            files.insert(file_id);
+            files.extend(includer.def_map(sema.db).get_included_files());
+            if includer.kind(sema.db.upcast()) == FileKind::Header {
+                to_visit.push(includer);
+            }
+        }
+    }
+    SearchScope::files(files.into_iter())
+}
+
+/// The files using a macro defined in a header, according to the
+/// macro reference index of the project of the header.
+fn macro_search_scope(sema: &Semantic, define: &DefineDef) -> Option<SearchScope> {
+    let file_id = define.file.file_id;
+    let project_id = sema
+        .db
+        .app_data(sema.db.file_source_root(file_id))?
+        .project_id;
+    let index = sema.db.macro_reference_index(project_id);
+    let users = index.users(file_id, &define.define.name);
+    Some(SearchScope::files(
+        iter::once(file_id).chain(users.iter().copied()),
+    ))
+}
+
 #[derive(Clone)]
 pub struct FindUsages<'a> {
     def: SymbolDefinition,
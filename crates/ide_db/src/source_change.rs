@@ -180,6 +180,27 @@ impl SourceChangeBuilder {
         self.edit.replace(range, replace_with.into())
     }
 
+    /// Create a file with the given contents.
+    pub fn create_file(&mut self, dst: AnchoredPathBuf, contents: impl Into<String>) {
+        self.source_change
+            .push_file_system_edit(FileSystemEdit::CreateFile {
+                dst,
+                initial_contents: contents.into(),
+            });
+    }
+
+    /// Delete a file, after any edit to it.
+    pub fn delete_file(&mut self, file_id: FileId) {
+        self.source_change
+            .push_file_system_edit(FileSystemEdit::DeleteFile { file: file_id });
+    }
+
+    /// Move a file, after any edit to it.
+    pub fn move_file(&mut self, src: FileId, dst: AnchoredPathBuf) {
+        self.source_change
+            .push_file_system_edit(FileSystemEdit::MoveFile { src, dst });
+    }
+
     pub fn finish(mut self) -> SourceChange {
         self.commit();
         mem::take(&mut self.source_change)
@@ -198,12 +219,18 @@ impl SourceChangeBuilder {
 
 // ---------------------------------------------------------------------
 
+/// A change to the files themselves, applied after the text edits of
+/// the `SourceChange`, in order. Paths are relative to the directory of
+/// their anchor file.
 #[derive(Debug, Clone)]
 pub enum FileSystemEdit {
     CreateFile {
         dst: AnchoredPathBuf,
         initial_contents: String,
     },
+    DeleteFile {
+        file: FileId,
+    },
     MoveFile {
         src: FileId,
         dst: AnchoredPathBuf,
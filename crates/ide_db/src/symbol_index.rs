@@ -0,0 +1,126 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Project wide index of the symbols defined in each file, used for
+//! workspace symbol search.
+//!
+//! The symbols of a file are derived from its local def map, so editing
+//! the body of a function does not invalidate the index of the project.
+
+use std::sync::Arc;
+
+use elp_base_db::salsa;
+use elp_base_db::FileId;
+use elp_base_db::ProjectId;
+use elp_syntax::SmolStr;
+use hir::db::MinDefDatabase;
+
+use crate::SymbolDefinition;
+
+#[salsa::query_group(SymbolIndexDatabaseStorage)]
+pub trait SymbolIndexDatabase: MinDefDatabase {
+    /// The functions, types, records and macros defined in a file.
+    fn file_symbols(&self, file_id: FileId) -> Arc<Vec<FileSymbol>>;
+
+    /// The symbols defined in all the `.erl`, `.hrl` and `.escript`
+    /// files of a project.
+    fn symbol_index(&self, project_id: ProjectId) -> Arc<SymbolIndex>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSymbol {
+    /// The name the symbol is searched by, e.g. `my_fun/2`,
+    /// `#my_record{}` or `?MY_MACRO`
+    pub name: SmolStr,
+    pub def: SymbolDefinition,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SymbolIndex {
+    /// Sorted by name, then by file
+    symbols: Vec<FileSymbol>,
+}
+
+impl SymbolIndex {
+    /// All symbols whose name contains `query`, ordered by name.
+    pub fn search<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a FileSymbol> + 'a {
+        self.symbols
+            .iter()
+            .filter(move |symbol| symbol.name.contains(query))
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
+
+fn file_symbols(db: &dyn SymbolIndexDatabase, file_id: FileId) -> Arc<Vec<FileSymbol>> {
+    let def_map = db.local_def_map(file_id);
+    let mut res = Vec::new();
+    for (name, def) in def_map.get_functions() {
+        res.push(FileSymbol {
+            name: SmolStr::new(format!("{}/{}", name.name().raw(), name.arity())),
+            def: SymbolDefinition::Function(def.clone()),
+        });
+    }
+    for (name, def) in def_map.get_types() {
+        res.push(FileSymbol {
+            name: SmolStr::new(format!("{}/{}", name.name().raw(), name.arity())),
+            def: SymbolDefinition::Type(def.clone()),
+        });
+    }
+    for (name, def) in def_map.get_records() {
+        res.push(FileSymbol {
+            name: SmolStr::new(format!("#{}{{}}", name.raw())),
+            def: SymbolDefinition::Record(def.clone()),
+        });
+    }
+    for (name, def) in def_map.get_macros() {
+        res.push(FileSymbol {
+            name: SmolStr::new(format!("?{name}")),
+            def: SymbolDefinition::Define(def.clone()),
+        });
+    }
+    // A function and a type can share a name, the sort is stable so
+    // functions come first.
+    res.sort_by(|a, b| a.name.cmp(&b.name));
+    Arc::new(res)
+}
+
+fn symbol_index(db: &dyn SymbolIndexDatabase, project_id: ProjectId) -> Arc<SymbolIndex> {
+    let mut file_ids = Vec::new();
+    let project_data = db.project_data(project_id);
+    for &source_root_id in &project_data.source_roots {
+        let source_root = db.source_root(source_root_id);
+        for file_id in source_root.iter() {
+            let is_erlang = source_root
+                .path_for_file(&file_id)
+                .and_then(|path| path.name_and_extension())
+                .map_or(false, |(_, ext)| {
+                    matches!(ext, Some("erl") | Some("hrl") | Some("escript"))
+                });
+            if is_erlang {
+                file_ids.push(file_id);
+            }
+        }
+    }
+    file_ids.sort();
+    file_ids.dedup();
+
+    let mut symbols: Vec<FileSymbol> = file_ids
+        .into_iter()
+        .flat_map(|file_id| db.file_symbols(file_id).as_ref().clone())
+        .collect();
+    symbols.sort_by(|a, b| a.name.cmp(&b.name));
+    Arc::new(SymbolIndex { symbols })
+}
@@ -0,0 +1,320 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Experimental project wide index of the literal keys of ETS tables and
+//! `persistent_term` entries: for each key, the files creating and the
+//! files using it.
+//!
+//! A key is the atom given as the first argument of a tracked function,
+//! e.g. `ets:new(my_table, [named_table])` creates the key `my_table`
+//! and `ets:lookup(my_table, K)` reads it. Keys are matched by name
+//! only, ETS tables and `persistent_term` entries share a namespace.
+//! Additional functions to track can be given in the `[table_keys]`
+//! section of `.elp_lint.toml`.
+
+use std::sync::Arc;
+
+use elp_base_db::salsa;
+use elp_base_db::FileId;
+use elp_base_db::FilePosition;
+use elp_base_db::ProjectId;
+use elp_syntax::AstNode;
+use elp_syntax::TextRange;
+use fxhash::FxHashMap;
+use hir::db::MinDefDatabase;
+use hir::Body;
+use hir::CallTarget;
+use hir::Expr;
+use hir::ExprId;
+use hir::InFile;
+use hir::Literal;
+use hir::Name;
+
+/// How a tracked function uses its key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TableKeyAccess {
+    Create,
+    Read,
+    /// A read returning a default value when the key is missing, such
+    /// as `persistent_term:get/2`.
+    ReadWithDefault,
+}
+
+/// A literal key given to a tracked function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableKeyRef {
+    pub key: Name,
+    pub access: TableKeyAccess,
+    /// The range of the key argument
+    pub range: TextRange,
+}
+
+#[salsa::query_group(TableKeyDatabaseStorage)]
+pub trait TableKeyDatabase: MinDefDatabase {
+    /// The table keys given to tracked functions by the function
+    /// bodies of a file, in the order they appear.
+    fn file_table_keys(&self, file_id: FileId) -> Arc<Vec<TableKeyRef>>;
+
+    /// The table keys of all the files of a project.
+    fn table_key_index(&self, project_id: ProjectId) -> Arc<TableKeyIndex>;
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TableKeyIndex {
+    creators: FxHashMap<Name, Vec<FileId>>,
+    users: FxHashMap<Name, Vec<FileId>>,
+}
+
+impl TableKeyIndex {
+    /// The files creating `key`, ordered by file.
+    pub fn creators(&self, key: &Name) -> &[FileId] {
+        self.creators.get(key).map_or(&[], |files| files.as_slice())
+    }
+
+    /// The files using `key` in any way, ordered by file.
+    pub fn users(&self, key: &Name) -> &[FileId] {
+        self.users.get(key).map_or(&[], |files| files.as_slice())
+    }
+
+    pub fn is_created(&self, key: &Name) -> bool {
+        !self.creators(key).is_empty()
+    }
+}
+
+/// The functions tracked out of the box, taking the key as their first
+/// argument.
+const BUILTIN_FUNCTIONS: &[(&str, &str, u32, TableKeyAccess)] = &[
+    ("ets", "new", 2, TableKeyAccess::Create),
+    ("persistent_term", "put", 2, TableKeyAccess::Create),
+    ("ets", "delete", 1, TableKeyAccess::Read),
+    ("ets", "delete", 2, TableKeyAccess::Read),
+    ("ets", "delete_object", 2, TableKeyAccess::Read),
+    ("ets", "first", 1, TableKeyAccess::Read),
+    ("ets", "info", 1, TableKeyAccess::Read),
+    ("ets", "info", 2, TableKeyAccess::Read),
+    ("ets", "insert", 2, TableKeyAccess::Read),
+    ("ets", "insert_new", 2, TableKeyAccess::Read),
+    ("ets", "last", 1, TableKeyAccess::Read),
+    ("ets", "lookup", 2, TableKeyAccess::Read),
+    ("ets", "lookup_element", 3, TableKeyAccess::Read),
+    ("ets", "match", 2, TableKeyAccess::Read),
+    ("ets", "match_object", 2, TableKeyAccess::Read),
+    ("ets", "member", 2, TableKeyAccess::Read),
+    ("ets", "next", 2, TableKeyAccess::Read),
+    ("ets", "select", 2, TableKeyAccess::Read),
+    ("ets", "tab2list", 1, TableKeyAccess::Read),
+    ("ets", "take", 2, TableKeyAccess::Read),
+    ("ets", "update_counter", 3, TableKeyAccess::Read),
+    ("ets", "update_counter", 4, TableKeyAccess::Read),
+    ("ets", "update_element", 3, TableKeyAccess::Read),
+    ("persistent_term", "erase", 1, TableKeyAccess::Read),
+    ("persistent_term", "get", 1, TableKeyAccess::Read),
+    ("persistent_term", "get", 2, TableKeyAccess::ReadWithDefault),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TrackedFunction {
+    module: String,
+    function: String,
+    /// `None` for all arities
+    arity: Option<u32>,
+    access: TableKeyAccess,
+}
+
+impl TrackedFunction {
+    /// Parse a function given as `module:function` or
+    /// `module:function/arity` in the config.
+    fn parse(spec: &str, access: TableKeyAccess) -> Option<TrackedFunction> {
+        let (module, function) = spec.trim().split_once(':')?;
+        let (function, arity) = match function.split_once('/') {
+            Some((function, arity)) => (function, Some(arity.parse().ok()?)),
+            None => (function, None),
+        };
+        if module.is_empty() || function.is_empty() {
+            return None;
+        }
+        Some(TrackedFunction {
+            module: module.to_string(),
+            function: function.to_string(),
+            arity,
+            access,
+        })
+    }
+
+    fn matches(&self, module: &str, function: &str, arity: u32) -> bool {
+        self.module == module
+            && self.function == function
+            && self.arity.map_or(true, |a| a == arity)
+    }
+}
+
+/// The builtin functions, followed by the ones given in the config of
+/// the project of the file.
+fn tracked_functions(db: &dyn TableKeyDatabase, file_id: FileId) -> Vec<TrackedFunction> {
+    let mut res: Vec<TrackedFunction> = BUILTIN_FUNCTIONS
+        .iter()
+        .map(|(module, function, arity, access)| TrackedFunction {
+            module: module.to_string(),
+            function: function.to_string(),
+            arity: Some(*arity),
+            access: *access,
+        })
+        .collect();
+    if let Some(app_data) = db.app_data(db.file_source_root(file_id)) {
+        let config = db
+            .project_data(app_data.project_id)
+            .lint_file_config
+            .table_keys
+            .clone();
+        let configured = config
+            .constructors
+            .iter()
+            .map(|spec| (spec, TableKeyAccess::Create))
+            .chain(
+                config
+                    .readers
+                    .iter()
+                    .map(|spec| (spec, TableKeyAccess::Read)),
+            );
+        for (spec, access) in configured {
+            match TrackedFunction::parse(spec, access) {
+                Some(function) => res.push(function),
+                None => log::warn!("table_keys: ignoring invalid function `{spec}`"),
+            }
+        }
+    }
+    res
+}
+
+fn file_table_keys(db: &dyn TableKeyDatabase, file_id: FileId) -> Arc<Vec<TableKeyRef>> {
+    let mut res = Vec::new();
+    let form_list = db.file_form_list(file_id);
+    let module = form_list.module_attribute().map(|attr| attr.name.clone());
+    let tracked = tracked_functions(db, file_id);
+    let source_file = InFile::new(file_id, db.parse(file_id).tree());
+    for (function_id, _) in form_list.functions() {
+        let (function_body, source_map) =
+            db.function_body_with_source(InFile::new(file_id, function_id));
+        let body = &function_body.body;
+        for (_, expr) in body.exprs.iter() {
+            if let Expr::Call { target, args } = expr {
+                let access = match call_access(db, body, module.as_ref(), target, args, &tracked) {
+                    Some(access) => access,
+                    None => continue,
+                };
+                let key = match &body[args[0]] {
+                    Expr::Literal(Literal::Atom(atom)) => db.lookup_atom(*atom),
+                    _ => continue,
+                };
+                // Keys coming from a macro defined in another file have
+                // no range in this one.
+                if let Some(key_ast) = source_map
+                    .expr(args[0])
+                    .and_then(|source| source.to_node(&source_file))
+                {
+                    res.push(TableKeyRef {
+                        key,
+                        access,
+                        range: key_ast.syntax().text_range(),
+                    });
+                }
+            }
+        }
+    }
+    res.sort_by_key(|key_ref| key_ref.range.start());
+    Arc::new(res)
+}
+
+fn call_access(
+    db: &dyn TableKeyDatabase,
+    body: &Body,
+    current_module: Option<&Name>,
+    target: &CallTarget<ExprId>,
+    args: &[ExprId],
+    tracked: &[TrackedFunction],
+) -> Option<TableKeyAccess> {
+    if args.is_empty() {
+        return None;
+    }
+    let (module, function) = match target {
+        CallTarget::Local { name } => (current_module?.clone(), body[*name].as_atom()?),
+        CallTarget::Remote { module, name } => (
+            db.lookup_atom(body[*module].as_atom()?),
+            body[*name].as_atom()?,
+        ),
+    };
+    let function = db.lookup_atom(function);
+    tracked
+        .iter()
+        .find(|tracked| tracked.matches(module.as_str(), function.as_str(), args.len() as u32))
+        .map(|tracked| tracked.access)
+}
+
+fn table_key_index(db: &dyn TableKeyDatabase, project_id: ProjectId) -> Arc<TableKeyIndex> {
+    let mut file_ids: Vec<FileId> = db
+        .project_data(project_id)
+        .source_roots
+        .iter()
+        .flat_map(|&source_root_id| db.source_root(source_root_id).iter().collect::<Vec<_>>())
+        .collect();
+    file_ids.sort();
+    file_ids.dedup();
+
+    let mut index = TableKeyIndex::default();
+    for file_id in file_ids {
+        for key_ref in db.file_table_keys(file_id).iter() {
+            let files = match key_ref.access {
+                TableKeyAccess::Create => index.creators.entry(key_ref.key.clone()).or_default(),
+                _ => index.users.entry(key_ref.key.clone()).or_default(),
+            };
+            if files.last() != Some(&file_id) {
+                files.push(file_id);
+            }
+        }
+    }
+    // Creating a key is also a use of it
+    for (key, creators) in &index.creators {
+        let users = index.users.entry(key.clone()).or_default();
+        users.extend(creators);
+        users.sort();
+        users.dedup();
+    }
+    Arc::new(index)
+}
+
+/// The table key at the given position, if any.
+pub fn table_key_at(db: &dyn TableKeyDatabase, position: FilePosition) -> Option<TableKeyRef> {
+    db.file_table_keys(position.file_id)
+        .iter()
+        .find(|key_ref| key_ref.range.contains_inclusive(position.offset))
+        .cloned()
+}
+
+/// All the places using `key` in the project of `file_id`, ordered by
+/// file.
+pub fn table_key_references(
+    db: &dyn TableKeyDatabase,
+    file_id: FileId,
+    key: &Name,
+) -> Vec<InFile<TableKeyRef>> {
+    let file_ids = match db.app_data(db.file_source_root(file_id)) {
+        Some(app_data) => db.table_key_index(app_data.project_id).users(key).to_vec(),
+        None => vec![file_id],
+    };
+    file_ids
+        .into_iter()
+        .flat_map(|file_id| {
+            db.file_table_keys(file_id)
+                .iter()
+                .filter(|key_ref| &key_ref.key == key)
+                .map(|key_ref| InFile::new(file_id, key_ref.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
@@ -0,0 +1,1019 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Interface information read from compiled BEAM files.
+//!
+//! Some dependencies are shipped with only an `ebin` directory. For
+//! those we synthesize a stub module per BEAM file, containing the
+//! exported functions with their specs and docs where the BEAM file
+//! carries them, so that navigation, completion and hover have
+//! something to land on.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use eetf::Term;
+use fxhash::FxHashMap;
+use paths::AbsPath;
+use paths::AbsPathBuf;
+
+use crate::ProjectAppData;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BeamFile {
+    pub module: String,
+    /// Exported functions, without the generated `module_info`
+    pub exports: Vec<(String, u32)>,
+    pub behaviours: Vec<String>,
+    pub export_types: Vec<(String, u32)>,
+    /// Rendered `-type` and `-opaque` attributes
+    pub types: Vec<String>,
+    /// Rendered clauses of each function spec, e.g. `(A :: atom()) -> ok`
+    pub specs: FxHashMap<(String, u32), Vec<String>>,
+    pub params: FxHashMap<(String, u32), Vec<String>>,
+    pub module_doc: Option<String>,
+    pub docs: FxHashMap<(String, u32), String>,
+}
+
+impl BeamFile {
+    /// Read the interface of a module from the contents of its BEAM
+    /// file. The abstract code (`Dbgi` or `Abst` chunks) and the
+    /// EEP-48 `Docs` chunk are used when present.
+    pub fn parse(bytes: &[u8]) -> Result<BeamFile> {
+        let chunks = read_chunks(bytes)?;
+        let atoms = match chunks.get(b"AtU8").or_else(|| chunks.get(b"Atom")) {
+            Some(data) => read_atoms(data)?,
+            None => bail!("BEAM file has no atom table"),
+        };
+        let mut beam = BeamFile {
+            module: atoms
+                .first()
+                .cloned()
+                .context("BEAM file has no module name")?,
+            ..BeamFile::default()
+        };
+        if let Some(data) = chunks.get(b"ExpT") {
+            beam.exports = read_exports(data, &atoms)?
+                .into_iter()
+                .filter(|(name, _)| name != "module_info")
+                .collect();
+            beam.exports.sort();
+        }
+        if let Some(attrs) = chunks
+            .get(b"Attr")
+            .and_then(|data| Term::decode(*data).ok())
+        {
+            beam.read_attributes(&attrs);
+        }
+        if let Some(forms) = abstract_code(&chunks) {
+            forms.iter().for_each(|form| beam.read_form(form));
+        }
+        if let Some(docs) = chunks
+            .get(b"Docs")
+            .and_then(|data| Term::decode(*data).ok())
+        {
+            beam.read_docs(&docs);
+        }
+        Ok(beam)
+    }
+
+    /// Render the interface as an Erlang module with stub bodies.
+    pub fn to_stub(&self, source: &Path) -> String {
+        let mut res = format!(
+            "%% {} by ELP from {}\n%% Interface stub for a module without sources, do not edit.\n\n",
+            concat!("@", "generated"),
+            source.display()
+        );
+        if let Some(doc) = &self.module_doc {
+            res.push_str(&doc_comment(doc));
+        }
+        res.push_str(&format!("-module({}).\n", quote_atom(&self.module)));
+        for behaviour in &self.behaviours {
+            res.push_str(&format!("-behaviour({}).\n", quote_atom(behaviour)));
+        }
+        res.push('\n');
+        if !self.exports.is_empty() {
+            res.push_str(&format!("-export([{}]).\n", name_arities(&self.exports)));
+        }
+        if !self.export_types.is_empty() {
+            res.push_str(&format!(
+                "-export_type([{}]).\n",
+                name_arities(&self.export_types)
+            ));
+        }
+        for ty in &self.types {
+            res.push('\n');
+            res.push_str(ty);
+            res.push('\n');
+        }
+        for key @ (name, arity) in &self.exports {
+            res.push('\n');
+            if let Some(doc) = self.docs.get(key) {
+                res.push_str(&doc_comment(doc));
+            }
+            let name = quote_atom(name);
+            if let Some(sigs) = self.specs.get(key) {
+                let indent = " ".repeat("-spec ".len() + name.len());
+                res.push_str(&format!(
+                    "-spec {}{}.\n",
+                    name,
+                    sigs.join(&format!(";\n{indent}"))
+                ));
+            }
+            let params = self.param_names(key, *arity);
+            res.push_str(&format!(
+                "{}({}) ->\n    erlang:nif_error(undef).\n",
+                name,
+                params.join(", ")
+            ));
+        }
+        res
+    }
+
+    fn param_names(&self, key: &(String, u32), arity: u32) -> Vec<String> {
+        let mut names: Vec<String> = match self.params.get(key) {
+            Some(names) if names.len() == arity as usize => names.clone(),
+            _ => (1..=arity).map(|idx| format!("Arg{idx}")).collect(),
+        };
+        // Parameter names come from a single clause, or a doc
+        // signature, so make sure they are distinct variables.
+        for idx in 0..names.len() {
+            if names[idx] == "_" || names[..idx].contains(&names[idx]) {
+                names[idx] = format!("Arg{}", idx + 1);
+            }
+        }
+        names
+    }
+
+    fn read_attributes(&mut self, attrs: &Term) {
+        for attr in list(attrs).unwrap_or_default() {
+            if let Some([key, value]) = tuple(attr) {
+                if matches!(atom_name(key), Some("behaviour" | "behavior")) {
+                    self.behaviours.extend(
+                        list(value)
+                            .unwrap_or_default()
+                            .iter()
+                            .filter_map(atom_name)
+                            .map(|name| name.to_string()),
+                    );
+                }
+            }
+        }
+    }
+
+    fn read_form(&mut self, form: &Term) -> Option<()> {
+        let elements = tuple(form)?;
+        match (atom_name(elements.first()?)?, elements) {
+            ("attribute", [_, _, kind, value]) => match atom_name(kind)? {
+                "spec" => {
+                    let (fa, sigs) = match tuple(value)? {
+                        [fa, sigs] => (fa, sigs),
+                        _ => return None,
+                    };
+                    let key = match tuple(fa)? {
+                        [name, arity] | [_, name, arity] => {
+                            (atom_name(name)?.to_string(), int(arity)? as u32)
+                        }
+                        _ => return None,
+                    };
+                    let sigs = list(sigs)?.iter().map(render_sig).collect();
+                    self.specs.insert(key, sigs);
+                }
+                kind @ ("type" | "opaque") => {
+                    let (name, ty, params) = match tuple(value)? {
+                        [name, ty, params] => (name, ty, params),
+                        _ => return None,
+                    };
+                    let params: Vec<String> = list(params)?.iter().map(render_type).collect();
+                    self.types.push(format!(
+                        "-{kind} {}({}) :: {}.",
+                        quote_atom(atom_name(name)?),
+                        params.join(", "),
+                        render_type(ty)
+                    ));
+                }
+                "export_type" => {
+                    for fa in list(value)? {
+                        if let Some([name, arity]) = tuple(fa) {
+                            self.export_types
+                                .push((atom_name(name)?.to_string(), int(arity)? as u32));
+                        }
+                    }
+                }
+                _ => {}
+            },
+            ("function", [_, _, name, arity, clauses]) => {
+                let pats = match tuple(list(clauses)?.first()?)? {
+                    [_, _, pats, _, _] => pats,
+                    _ => return None,
+                };
+                let params = list(pats)?
+                    .iter()
+                    .map(|pat| match tuple(pat) {
+                        Some([tag, _, name]) if atom_name(tag) == Some("var") => {
+                            atom_name(name).unwrap_or("_").to_string()
+                        }
+                        _ => "_".to_string(),
+                    })
+                    .collect();
+                self.params
+                    .insert((atom_name(name)?.to_string(), int(arity)? as u32), params);
+            }
+            _ => {}
+        }
+        Some(())
+    }
+
+    fn read_docs(&mut self, docs: &Term) -> Option<()> {
+        // {docs_v1, Anno, BeamLanguage, Format, ModuleDoc, Metadata, Docs}
+        let (format, module_doc, entries) = match tuple(docs)? {
+            [_, _, _, format, module_doc, _, entries] => (format, module_doc, entries),
+            _ => return None,
+        };
+        let format = std::str::from_utf8(binary(format)?).ok()?;
+        if !format.starts_with("text/") {
+            return None;
+        }
+        self.module_doc = doc_text(module_doc);
+        for entry in list(entries)? {
+            // {{Kind, Name, Arity}, Anno, Signature, Doc, Metadata}
+            if let Some([kind_name_arity, _, signature, doc, _]) = tuple(entry) {
+                if let Some([kind, name, arity]) = tuple(kind_name_arity) {
+                    if atom_name(kind) != Some("function") {
+                        continue;
+                    }
+                    let key = (atom_name(name)?.to_string(), int(arity)? as u32);
+                    if let Some(text) = doc_text(doc) {
+                        self.docs.insert(key.clone(), text);
+                    }
+                    if !self.params.contains_key(&key) {
+                        if let Some(params) = signature_params(signature) {
+                            self.params.insert(key, params);
+                        }
+                    }
+                }
+            }
+        }
+        Some(())
+    }
+}
+
+/// Where the stub modules of the dependencies of a project are written,
+/// relative to the project root, in a directory per dependency.
+pub const BEAM_STUBS_DIR: &str = ".elp/beam_stubs";
+
+/// Add the stub directory of each dependency without Erlang sources as
+/// a source directory of the app. The stubs themselves are only written
+/// by `generate_stubs`, once the dependencies are compiled.
+pub fn add_stub_dirs(root: &AbsPath, apps: &mut [ProjectAppData]) {
+    for app in apps {
+        if app.ebin.is_none() || has_erlang_sources(app) {
+            continue;
+        }
+        let out_dir = stub_dir(root, app);
+        app.abs_src_dirs.push(out_dir);
+    }
+}
+
+/// Write the stub modules of the dependencies given a stub directory by
+/// `add_stub_dirs`, from their BEAM files, unless the stubs were written
+/// since the BEAM files last changed. The stubs written before are
+/// removed first, so that modules no longer in the dependency don't
+/// linger. Returns whether any stubs were written again.
+pub fn generate_stubs(root: &AbsPath, apps: &[ProjectAppData]) -> bool {
+    let mut changed = false;
+    for app in apps {
+        let out_dir = stub_dir(root, app);
+        let ebin = match &app.ebin {
+            Some(ebin) if app.abs_src_dirs.contains(&out_dir) => ebin,
+            _ => continue,
+        };
+        if stubs_up_to_date(ebin, &out_dir) {
+            continue;
+        }
+        changed = true;
+        match write_stubs(ebin, &out_dir) {
+            Ok(0) => {}
+            Ok(count) => log::info!(
+                "Generated {} BEAM stubs for {} in {}",
+                count,
+                app.name,
+                out_dir.display()
+            ),
+            Err(err) => log::warn!("Could not generate BEAM stubs for {}: {:#}", app.name, err),
+        }
+    }
+    changed
+}
+
+/// Whether the stubs in `out_dir` were written after the last change to
+/// the BEAM files in `ebin`. A removed BEAM file changes the modification
+/// time of `ebin` itself.
+fn stubs_up_to_date(ebin: &AbsPath, out_dir: &AbsPath) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    let written = match modified(out_dir.as_ref()) {
+        Some(written) => written,
+        None => return false,
+    };
+    let entries = match fs::read_dir(ebin) {
+        Ok(entries) => entries,
+        Err(_) => return false,
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "beam"))
+        .chain(std::iter::once(ebin.as_ref().to_path_buf()))
+        .all(|path| modified(&path).map_or(false, |time| time <= written))
+}
+
+/// Write a stub module for each BEAM file in `ebin` into `out_dir`,
+/// replacing its previous contents, and return the number of stubs
+/// written.
+pub fn write_stubs(ebin: &AbsPath, out_dir: &AbsPath) -> Result<usize> {
+    if out_dir.as_ref().exists() {
+        fs::remove_dir_all(out_dir)?;
+    }
+    if !ebin.as_ref().exists() {
+        return Ok(0);
+    }
+    let mut count = 0;
+    for entry in fs::read_dir(ebin)? {
+        let path = entry?.path();
+        if path.extension().map_or(true, |ext| ext != "beam") {
+            continue;
+        }
+        let beam = match fs::read(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| BeamFile::parse(&bytes))
+        {
+            Ok(beam) => beam,
+            Err(err) => {
+                log::warn!("Could not read {}: {:#}", path.display(), err);
+                continue;
+            }
+        };
+        if count == 0 {
+            fs::create_dir_all(out_dir)?;
+        }
+        let stub_path = out_dir.join(format!("{}.erl", beam.module));
+        fs::write(&stub_path, beam.to_stub(&path))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn has_erlang_sources(app: &ProjectAppData) -> bool {
+    app.abs_src_dirs.iter().any(|dir| {
+        fs::read_dir(dir).map_or(false, |entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .any(|entry| entry.path().extension().map_or(false, |ext| ext == "erl"))
+        })
+    })
+}
+
+fn stub_dir(root: &AbsPath, app: &ProjectAppData) -> AbsPathBuf {
+    root.join(BEAM_STUBS_DIR).join(app.name.as_str())
+}
+
+// ---------------------------------------------------------------------
+// Chunks
+
+fn read_chunks(bytes: &[u8]) -> Result<FxHashMap<[u8; 4], &[u8]>> {
+    if bytes.len() < 12 || &bytes[0..4] != b"FOR1" || &bytes[8..12] != b"BEAM" {
+        bail!("not a BEAM file");
+    }
+    let mut chunks = FxHashMap::default();
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let id: [u8; 4] = bytes[pos..pos + 4].try_into()?;
+        let size = read_u32(bytes, pos + 4)? as usize;
+        let start = pos + 8;
+        let data = bytes
+            .get(start..start + size)
+            .context("truncated BEAM chunk")?;
+        chunks.insert(id, data);
+        // Chunks are padded to a multiple of four bytes
+        pos = start + (size + 3) / 4 * 4;
+    }
+    Ok(chunks)
+}
+
+fn read_u32(bytes: &[u8], pos: usize) -> Result<u32> {
+    let bytes = bytes.get(pos..pos + 4).context("truncated BEAM chunk")?;
+    Ok(u32::from_be_bytes(bytes.try_into()?))
+}
+
+fn read_byte(bytes: &[u8], pos: &mut usize) -> Result<u8> {
+    let byte = *bytes.get(*pos).context("truncated BEAM chunk")?;
+    *pos += 1;
+    Ok(byte)
+}
+
+fn read_atoms(data: &[u8]) -> Result<Vec<String>> {
+    // Since OTP 28 a negative count means the lengths use the compact
+    // term encoding, rather than a single byte.
+    let count = read_u32(data, 0)? as i32;
+    let compact = count < 0;
+    let mut pos = 4;
+    let mut atoms = Vec::with_capacity(count.unsigned_abs() as usize);
+    for _ in 0..count.unsigned_abs() {
+        let first = read_byte(data, &mut pos)?;
+        let len = if !compact {
+            first as usize
+        } else if first & 0x08 == 0 {
+            (first >> 4) as usize
+        } else if first & 0x10 == 0 {
+            ((first as usize & 0xE0) << 3) | read_byte(data, &mut pos)? as usize
+        } else {
+            bail!("unsupported atom length encoding");
+        };
+        let name = data.get(pos..pos + len).context("truncated atom table")?;
+        atoms.push(String::from_utf8_lossy(name).into_owned());
+        pos += len;
+    }
+    Ok(atoms)
+}
+
+fn read_exports(data: &[u8], atoms: &[String]) -> Result<Vec<(String, u32)>> {
+    let count = read_u32(data, 0)? as usize;
+    (0..count)
+        .map(|idx| {
+            // {Function, Arity, Label}, with a 1-based atom index
+            let pos = 4 + idx * 12;
+            let atom_idx = read_u32(data, pos)? as usize;
+            let name = atom_idx
+                .checked_sub(1)
+                .and_then(|idx| atoms.get(idx))
+                .context("bad atom index in export table")?;
+            Ok((name.clone(), read_u32(data, pos + 4)?))
+        })
+        .collect()
+}
+
+fn abstract_code(chunks: &FxHashMap<[u8; 4], &[u8]>) -> Option<Vec<Term>> {
+    if let Some(data) = chunks.get(b"Dbgi") {
+        // {debug_info_v1, erl_abstract_code, {Forms | none, Options}}
+        let term = Term::decode(*data).ok()?;
+        let (backend, data) = match tuple(&term)? {
+            [_, backend, data] => (backend, data),
+            _ => return None,
+        };
+        if atom_name(backend)? != "erl_abstract_code" {
+            return None;
+        }
+        let forms = match tuple(data)? {
+            [forms, _] => forms,
+            _ => return None,
+        };
+        return list(forms).map(|forms| forms.to_vec());
+    }
+    // {raw_abstract_v1, Forms}, from BEAM files compiled before OTP 20
+    let term = Term::decode(*chunks.get(b"Abst")?).ok()?;
+    let forms = match tuple(&term)? {
+        [_, forms] => forms,
+        _ => return None,
+    };
+    list(forms).map(|forms| forms.to_vec())
+}
+
+// ---------------------------------------------------------------------
+// Terms
+
+fn tuple(term: &Term) -> Option<&[Term]> {
+    match term {
+        Term::Tuple(eetf::Tuple { elements }) => Some(elements),
+        _ => None,
+    }
+}
+
+fn list(term: &Term) -> Option<&[Term]> {
+    match term {
+        Term::List(eetf::List { elements }) => Some(elements),
+        _ => None,
+    }
+}
+
+fn atom_name(term: &Term) -> Option<&str> {
+    match term {
+        Term::Atom(eetf::Atom { name }) => Some(name),
+        _ => None,
+    }
+}
+
+fn int(term: &Term) -> Option<i64> {
+    match term {
+        Term::FixInteger(eetf::FixInteger { value }) => Some(*value as i64),
+        _ => None,
+    }
+}
+
+fn binary(term: &Term) -> Option<&[u8]> {
+    match term {
+        Term::Binary(eetf::Binary { bytes }) => Some(bytes),
+        _ => None,
+    }
+}
+
+/// The English text of an EEP-48 doc entry, `#{<<"en">> => Doc}`.
+fn doc_text(term: &Term) -> Option<String> {
+    match term {
+        Term::Map(eetf::Map { entries }) => entries.iter().find_map(|(key, value)| {
+            if binary(key)? == b"en" {
+                Some(String::from_utf8_lossy(binary(value)?).into_owned())
+            } else {
+                None
+            }
+        }),
+        _ => None,
+    }
+}
+
+/// Parameter names from an EEP-48 signature such as `<<"foo(A, B)">>`,
+/// if they are all plain variables.
+fn signature_params(term: &Term) -> Option<Vec<String>> {
+    let signature = std::str::from_utf8(binary(list(term)?.first()?)?).ok()?;
+    let args = &signature[signature.find('(')? + 1..signature.rfind(')')?];
+    if args.trim().is_empty() {
+        return Some(vec![]);
+    }
+    args.split(',')
+        .map(|arg| {
+            let arg = arg.trim();
+            let is_var = arg.starts_with(|c: char| c.is_ascii_uppercase() || c == '_')
+                && arg.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            is_var.then(|| arg.to_string())
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------
+// Rendering
+
+const RESERVED_WORDS: &[&str] = &[
+    "after", "and", "andalso", "band", "begin", "bnot", "bor", "bsl", "bsr", "bxor", "case",
+    "catch", "cond", "div", "else", "end", "fun", "if", "let", "maybe", "not", "of", "or",
+    "orelse", "receive", "rem", "try", "when", "xor",
+];
+
+fn quote_atom(name: &str) -> String {
+    let is_plain = name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@')
+        && !RESERVED_WORDS.contains(&name);
+    if is_plain {
+        name.to_string()
+    } else {
+        format!("'{}'", name.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+fn name_arities(items: &[(String, u32)]) -> String {
+    items
+        .iter()
+        .map(|(name, arity)| format!("{}/{}", quote_atom(name), arity))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn doc_comment(doc: &str) -> String {
+    let mut res = String::new();
+    for (idx, line) in doc.trim().lines().enumerate() {
+        let prefix = if idx == 0 { "%% @doc " } else { "%% " };
+        res.push_str(format!("{prefix}{line}").trim_end());
+        res.push('\n');
+    }
+    res
+}
+
+/// Render one clause of a spec, `(Args) -> Result`, optionally with
+/// `when` constraints.
+fn render_sig(sig: &Term) -> String {
+    match type_parts(sig) {
+        Some(("fun", [args, result])) => render_fun_sig(args, result),
+        Some(("bounded_fun", [fun, constraints])) => {
+            let sig = render_sig(fun);
+            let constraints: Vec<String> = list(constraints)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|constraint| match type_parts(constraint)? {
+                    ("constraint", [_is_subtype, args]) => match list(args)? {
+                        [var, ty] => Some(format!("{} :: {}", render_type(var), render_type(ty))),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect();
+            if constraints.is_empty() {
+                sig
+            } else {
+                format!("{} when {}", sig, constraints.join(", "))
+            }
+        }
+        _ => "(...) -> term()".to_string(),
+    }
+}
+
+fn render_fun_sig(args: &Term, result: &Term) -> String {
+    let args = match type_parts(args) {
+        Some(("product", args)) => render_types(args, ", "),
+        _ => "...".to_string(),
+    };
+    format!("({}) -> {}", args, render_type(result))
+}
+
+/// For `{type, Anno, Name, Args}`, return the name and the arguments.
+fn type_parts(term: &Term) -> Option<(&str, &[Term])> {
+    match tuple(term)? {
+        [tag, _, name, args] if atom_name(tag) == Some("type") => {
+            Some((atom_name(name)?, list(args).unwrap_or_default()))
+        }
+        _ => None,
+    }
+}
+
+fn render_types(types: &[Term], sep: &str) -> String {
+    types.iter().map(render_type).collect::<Vec<_>>().join(sep)
+}
+
+/// Render a type in the Erlang abstract format as source text. Forms
+/// that are not recognised are rendered as `term()`.
+fn render_type(ty: &Term) -> String {
+    render_type_opt(ty).unwrap_or_else(|| "term()".to_string())
+}
+
+fn render_type_opt(ty: &Term) -> Option<String> {
+    let elements = tuple(ty)?;
+    let res = match (atom_name(elements.first()?)?, elements) {
+        ("ann_type", [_, _, parts]) => match list(parts)? {
+            [var, ty] => format!("{} :: {}", render_type(var), render_type(ty)),
+            _ => return None,
+        },
+        ("var", [_, _, name]) => atom_name(name)?.to_string(),
+        ("atom", [_, _, name]) => quote_atom(atom_name(name)?),
+        ("integer" | "char", [_, _, value]) => int(value)?.to_string(),
+        ("op", [_, _, op, arg]) => format!("{}{}", atom_name(op)?, render_type(arg)),
+        ("op", [_, _, op, lhs, rhs]) => format!(
+            "{} {} {}",
+            render_type(lhs),
+            atom_name(op)?,
+            render_type(rhs)
+        ),
+        ("remote_type", [_, _, parts]) => match list(parts)? {
+            [module, name, args] => format!(
+                "{}:{}({})",
+                render_type(module),
+                render_type(name),
+                render_types(list(args)?, ", ")
+            ),
+            _ => return None,
+        },
+        ("user_type", [_, _, name, args]) => format!(
+            "{}({})",
+            quote_atom(atom_name(name)?),
+            render_types(list(args)?, ", ")
+        ),
+        ("type", [_, _, name, args]) => {
+            let name = atom_name(name)?;
+            match (name, list(args)) {
+                ("tuple" | "map", None) => format!("{}()", name),
+                ("fun", Some([])) => "fun()".to_string(),
+                ("fun", Some([args, result])) => match type_parts(args) {
+                    Some(("any", _)) => format!("fun((...) -> {})", render_type(result)),
+                    _ => format!("fun({})", render_fun_sig(args, result)),
+                },
+                ("tuple", Some(elements)) => format!("{{{}}}", render_types(elements, ", ")),
+                ("union", Some(types)) => render_types(types, " | "),
+                ("nil", _) => "[]".to_string(),
+                ("list", Some([elem])) => format!("[{}]", render_type(elem)),
+                ("nonempty_list", Some([elem])) => format!("[{}, ...]", render_type(elem)),
+                ("range", Some([low, high])) => {
+                    format!("{}..{}", render_type(low), render_type(high))
+                }
+                ("map", Some(fields)) => {
+                    let fields: Vec<String> = fields
+                        .iter()
+                        .map(|field| match type_parts(field) {
+                            Some(("map_field_assoc", [key, value])) => {
+                                format!("{} => {}", render_type(key), render_type(value))
+                            }
+                            Some(("map_field_exact", [key, value])) => {
+                                format!("{} := {}", render_type(key), render_type(value))
+                            }
+                            _ => "term() => term()".to_string(),
+                        })
+                        .collect();
+                    format!("#{{{}}}", fields.join(", "))
+                }
+                ("binary", Some([base, unit])) => match (render_type(base), render_type(unit)) {
+                    (base, unit) if base == "0" && unit == "0" => "<<>>".to_string(),
+                    (base, unit) if unit == "0" => format!("<<_:{}>>", base),
+                    (base, unit) if base == "0" => format!("<<_:_*{}>>", unit),
+                    (base, unit) => format!("<<_:{}, _:_*{}>>", base, unit),
+                },
+                ("record", Some([name, ..])) => format!("#{}{{}}", render_type(name)),
+                (name, Some(args)) => format!("{}({})", quote_atom(name), render_types(args, ", ")),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+
+    fn atom(name: &str) -> Term {
+        Term::from(eetf::Atom::from(name))
+    }
+
+    fn int(value: i32) -> Term {
+        Term::from(eetf::FixInteger { value })
+    }
+
+    fn tuple(elements: Vec<Term>) -> Term {
+        Term::from(eetf::Tuple { elements })
+    }
+
+    fn list(elements: Vec<Term>) -> Term {
+        Term::from(eetf::List { elements })
+    }
+
+    fn binary(bytes: &[u8]) -> Term {
+        Term::from(eetf::Binary {
+            bytes: bytes.to_vec(),
+        })
+    }
+
+    fn map(entries: Vec<(Term, Term)>) -> Term {
+        Term::from(eetf::Map { entries })
+    }
+
+    fn ty(name: &str, args: Vec<Term>) -> Term {
+        tuple(vec![atom("type"), int(1), atom(name), list(args)])
+    }
+
+    fn var(name: &str) -> Term {
+        tuple(vec![atom("var"), int(1), atom(name)])
+    }
+
+    fn beam_file(chunks: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut body = b"BEAM".to_vec();
+        for (id, data) in chunks {
+            body.extend_from_slice(*id);
+            body.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            body.extend_from_slice(data);
+            body.resize((body.len() + 3) / 4 * 4, 0);
+        }
+        let mut res = b"FOR1".to_vec();
+        res.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        res.extend(body);
+        res
+    }
+
+    fn atom_chunk(atoms: &[&str]) -> Vec<u8> {
+        let mut res = (atoms.len() as u32).to_be_bytes().to_vec();
+        for atom in atoms {
+            res.push(atom.len() as u8);
+            res.extend_from_slice(atom.as_bytes());
+        }
+        res
+    }
+
+    fn export_chunk(exports: &[(u32, u32)]) -> Vec<u8> {
+        let mut res = (exports.len() as u32).to_be_bytes().to_vec();
+        for (label, (atom, arity)) in exports.iter().enumerate() {
+            res.extend_from_slice(&atom.to_be_bytes());
+            res.extend_from_slice(&arity.to_be_bytes());
+            res.extend_from_slice(&(label as u32).to_be_bytes());
+        }
+        res
+    }
+
+    fn encode(term: &Term) -> Vec<u8> {
+        let mut buf = Vec::new();
+        term.encode(&mut buf).unwrap();
+        buf
+    }
+
+    /// The stub without its first line, which names the BEAM file.
+    fn stub_body(beam: &BeamFile) -> String {
+        let stub = beam.to_stub(Path::new("ebin/stub.beam"));
+        let (header, body) = stub.split_once('\n').unwrap();
+        assert!(header.ends_with("by ELP from ebin/stub.beam"));
+        body.to_string()
+    }
+
+    #[test]
+    fn exports_only() {
+        let bytes = beam_file(&[
+            (
+                b"AtU8",
+                atom_chunk(&["my_dep", "start", "module_info", "'weird'"]),
+            ),
+            (b"ExpT", export_chunk(&[(2, 1), (3, 0), (3, 1), (4, 0)])),
+        ]);
+        let beam = BeamFile::parse(&bytes).unwrap();
+        expect![[r#"
+            %% Interface stub for a module without sources, do not edit.
+
+            -module(my_dep).
+
+            -export(['\'weird\''/0, start/1]).
+
+            '\'weird\''() ->
+                erlang:nif_error(undef).
+
+            start(Arg1) ->
+                erlang:nif_error(undef).
+        "#]]
+        .assert_eq(&stub_body(&beam));
+    }
+
+    #[test]
+    fn abstract_code_and_docs() {
+        let spec = tuple(vec![
+            atom("attribute"),
+            int(3),
+            atom("spec"),
+            tuple(vec![
+                tuple(vec![atom("lookup"), int(2)]),
+                list(vec![ty(
+                    "bounded_fun",
+                    vec![
+                        ty(
+                            "fun",
+                            vec![
+                                ty(
+                                    "product",
+                                    vec![
+                                        tuple(vec![
+                                            atom("ann_type"),
+                                            int(3),
+                                            list(vec![var("Key"), ty("atom", vec![])]),
+                                        ]),
+                                        var("Map"),
+                                    ],
+                                ),
+                                ty(
+                                    "union",
+                                    vec![
+                                        tuple(vec![atom("atom"), int(3), atom("error")]),
+                                        ty(
+                                            "tuple",
+                                            vec![
+                                                tuple(vec![atom("atom"), int(3), atom("ok")]),
+                                                var("V"),
+                                            ],
+                                        ),
+                                    ],
+                                ),
+                            ],
+                        ),
+                        list(vec![ty(
+                            "constraint",
+                            vec![
+                                tuple(vec![atom("atom"), int(3), atom("is_subtype")]),
+                                list(vec![
+                                    var("Map"),
+                                    tuple(vec![atom("type"), int(3), atom("map"), atom("any")]),
+                                ]),
+                            ],
+                        )]),
+                    ],
+                )]),
+            ]),
+        ]);
+        let fun = tuple(vec![
+            atom("function"),
+            int(4),
+            atom("lookup"),
+            int(2),
+            list(vec![tuple(vec![
+                atom("clause"),
+                int(4),
+                list(vec![var("K"), var("_")]),
+                list(vec![]),
+                list(vec![atom("ok")]),
+            ])]),
+        ]);
+        let dbgi = tuple(vec![
+            atom("debug_info_v1"),
+            atom("erl_abstract_code"),
+            tuple(vec![list(vec![spec, fun]), list(vec![])]),
+        ]);
+        let docs = tuple(vec![
+            atom("docs_v1"),
+            int(0),
+            atom("erlang"),
+            binary(b"text/markdown"),
+            atom("none"),
+            map(vec![]),
+            list(vec![tuple(vec![
+                tuple(vec![atom("function"), atom("lookup"), int(2)]),
+                int(4),
+                list(vec![binary(b"lookup(K, M)")]),
+                map(vec![(
+                    binary(b"en"),
+                    binary(b"Look up a key.\n\nReturns `error`."),
+                )]),
+                map(vec![]),
+            ])]),
+        ]);
+        let attrs = list(vec![tuple(vec![
+            atom("behaviour"),
+            list(vec![atom("gen_server")]),
+        ])]);
+        let bytes = beam_file(&[
+            (b"AtU8", atom_chunk(&["kv", "lookup"])),
+            (b"ExpT", export_chunk(&[(2, 2)])),
+            (b"Attr", encode(&attrs)),
+            (b"Dbgi", encode(&dbgi)),
+            (b"Docs", encode(&docs)),
+        ]);
+        let beam = BeamFile::parse(&bytes).unwrap();
+        expect![[r#"
+            %% Interface stub for a module without sources, do not edit.
+
+            -module(kv).
+            -behaviour(gen_server).
+
+            -export([lookup/2]).
+
+            %% @doc Look up a key.
+            %%
+            %% Returns `error`.
+            -spec lookup(Key :: atom(), Map) -> error | {ok, V} when Map :: map().
+            lookup(K, Arg2) ->
+                erlang:nif_error(undef).
+        "#]]
+        .assert_eq(&stub_body(&beam));
+    }
+
+    #[test]
+    fn not_a_beam_file() {
+        assert!(BeamFile::parse(b"-module(foo).").is_err());
+    }
+
+    #[test]
+    fn stale_stubs_are_removed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = AbsPathBuf::assert(tmp.path().to_path_buf());
+        let ebin = root.join("ebin");
+        let out_dir = root.join(BEAM_STUBS_DIR).join("my_dep");
+        fs::create_dir_all(&ebin).unwrap();
+        fs::create_dir_all(&out_dir).unwrap();
+        fs::write(out_dir.join("removed.erl"), "-module(removed).").unwrap();
+        let bytes = beam_file(&[
+            (b"AtU8", atom_chunk(&["my_dep", "start"])),
+            (b"ExpT", export_chunk(&[(2, 0)])),
+        ]);
+        fs::write(ebin.join("my_dep.beam"), bytes).unwrap();
+
+        assert_eq!(write_stubs(&ebin, &out_dir).unwrap(), 1);
+        assert!(out_dir.join("my_dep.erl").as_ref().exists());
+        assert!(!out_dir.join("removed.erl").as_ref().exists());
+    }
+
+    #[test]
+    fn stubs_are_written_again_once_the_beams_change() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = AbsPathBuf::assert(tmp.path().to_path_buf());
+        let ebin = root.join("ebin");
+        let out_dir = root.join(BEAM_STUBS_DIR).join("my_dep");
+        fs::create_dir_all(&ebin).unwrap();
+        let bytes = beam_file(&[
+            (b"AtU8", atom_chunk(&["my_dep", "start"])),
+            (b"ExpT", export_chunk(&[(2, 0)])),
+        ]);
+        fs::write(ebin.join("my_dep.beam"), &bytes).unwrap();
+        assert!(!stubs_up_to_date(&ebin, &out_dir));
+
+        write_stubs(&ebin, &out_dir).unwrap();
+        assert!(stubs_up_to_date(&ebin, &out_dir));
+
+        let file = fs::File::options()
+            .write(true)
+            .open(ebin.join("my_dep.beam"))
+            .unwrap();
+        let later =
+            fs::metadata(&out_dir).unwrap().modified().unwrap() + std::time::Duration::from_secs(1);
+        file.set_modified(later).unwrap();
+        assert!(!stubs_up_to_date(&ebin, &out_dir));
+    }
+}
@@ -41,6 +41,7 @@ use crate::AppName;
 use crate::AppType;
 use crate::BuildInfoFile;
 use crate::CommandProxy;
+use crate::IncludeRemap;
 use crate::ProjectAppData;
 
 pub type TargetFullName = String;
@@ -56,8 +57,18 @@ pub const ELP_CONFIG_FILE: &str = ".elp.toml";
 // included_targets = [ "waserver//erl/..." ]
 // source_root = "erl"
 //
+// [[buck.include_remaps]]
+// target = "waserver//erl/chatd:chatd"
+// generator = "waserver//erl/chatd:chatd_headers"
+// prefix = "include"
+// dirs = [ "buck-out/v2/gen/erl/chatd/__chatd_headers__" ]
+//
 // [eqwalizer]
 // enable_all = true
+//
+// [ci]
+// checks = [ "lint", "eqwalizer", "xref" ]
+// fail_on = [ "eqwalizer", "xref" ]
 //```
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd, Deserialize)]
 pub struct ElpConfig {
@@ -66,6 +77,8 @@ pub struct ElpConfig {
     pub buck: BuckConfig,
     #[serde(default)]
     pub eqwalizer: EqwalizerConfig,
+    #[serde(default)]
+    pub ci: CiConfig,
 }
 
 impl ElpConfig {
@@ -110,6 +123,24 @@ pub struct BuckConfig {
     #[serde(default)]
     pub excluded_targets: Vec<String>,
     source_root: Option<PathBuf>,
+    #[serde(default)]
+    pub include_remaps: Vec<IncludeRemapConfig>,
+}
+
+/// Headers of the app built by `target` under `prefix`, which are
+/// generated by the `generator` target into `dirs`, relative to the
+/// buck root, rather than checked in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd, Deserialize)]
+pub struct IncludeRemapConfig {
+    pub target: String,
+    pub generator: String,
+    #[serde(default = "default_remap_prefix")]
+    pub prefix: String,
+    pub dirs: Vec<PathBuf>,
+}
+
+fn default_remap_prefix() -> String {
+    "include".to_string()
 }
 
 impl BuckConfig {
@@ -168,6 +199,82 @@ pub struct EqwalizerConfig {
     pub enable_all: bool,
 }
 
+/// Configuration of `elp ci`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd, Deserialize)]
+pub struct CiConfig {
+    /// The checks to run
+    #[serde(default = "CiCheck::all")]
+    pub checks: Vec<CiCheck>,
+    /// The checks whose findings make the run fail
+    #[serde(default = "CiCheck::all")]
+    pub fail_on: Vec<CiCheck>,
+}
+
+impl Default for CiConfig {
+    fn default() -> Self {
+        CiConfig {
+            checks: CiCheck::all(),
+            fail_on: CiCheck::all(),
+        }
+    }
+}
+
+impl CiConfig {
+    /// Reads the `[ci]` section of the `.elp.toml` file in `dir`, if
+    /// any. The rest of the file is ignored, so this can be used for
+    /// projects not built with buck.
+    pub fn load(dir: &AbsPath) -> Result<CiConfig> {
+        #[derive(Deserialize)]
+        struct Config {
+            #[serde(default)]
+            ci: CiConfig,
+        }
+
+        let path = dir.join(ELP_CONFIG_FILE);
+        if !path.as_ref().exists() {
+            return Ok(CiConfig::default());
+        }
+        let config_content = fs::read_to_string(&path)?;
+        let config: Config = toml::from_str(config_content.as_str())?;
+        Ok(config.ci)
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Ord,
+    PartialOrd,
+    Deserialize,
+    Serialize
+)]
+#[serde(rename_all = "snake_case")]
+pub enum CiCheck {
+    /// Native ELP diagnostics
+    Lint,
+    Eqwalizer,
+    /// Calls to undefined functions
+    Xref,
+}
+
+impl CiCheck {
+    pub fn all() -> Vec<CiCheck> {
+        vec![CiCheck::Lint, CiCheck::Eqwalizer, CiCheck::Xref]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CiCheck::Lint => "lint",
+            CiCheck::Eqwalizer => "eqwalizer",
+            CiCheck::Xref => "xref",
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub struct TargetInfo {
     pub targets: FxHashMap<TargetFullName, Target>,
@@ -231,6 +338,7 @@ pub struct Target {
     pub ebin: Option<AbsPathBuf>,
     pub target_type: TargetType,
     pub private_header: bool,
+    pub include_remaps: Vec<IncludeRemap>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
@@ -301,6 +409,7 @@ pub fn load_buck_targets(buck_config: &BuckConfig) -> Result<TargetInfo> {
                 };
                 (src_files, include_files, target_type, private_header, ebin)
             };
+        let include_remaps = include_remaps(buck_config, &name);
         let target = Target {
             name: name.clone(),
             app_name: target.name,
@@ -310,12 +419,30 @@ pub fn load_buck_targets(buck_config: &BuckConfig) -> Result<TargetInfo> {
             ebin,
             target_type,
             private_header,
+            include_remaps,
         };
         target_info.targets.insert(name, target);
     }
     Ok(target_info)
 }
 
+/// The configured generated include dirs of a target. The cell name
+/// of the targets is optional in the config.
+fn include_remaps(buck_config: &BuckConfig, name: &TargetFullName) -> Vec<IncludeRemap> {
+    let without_cell = |name: &str| name.split_once("//").map_or(name, |(_, path)| path);
+    let root = buck_config.buck_root();
+    buck_config
+        .include_remaps
+        .iter()
+        .filter(|remap| without_cell(&remap.target) == without_cell(name))
+        .map(|remap| IncludeRemap {
+            prefix: remap.prefix.clone(),
+            dirs: remap.dirs.iter().map(|dir| root.join(dir)).collect(),
+            target: remap.generator.clone(),
+        })
+        .collect()
+}
+
 fn find_root(buck_config: &BuckConfig) -> Result<AbsPathBuf> {
     let _timer = timeit!("loading root");
     let output = buck_config.buck_command().arg("root").output()?;
@@ -663,6 +790,7 @@ struct ProjectAppDataAcc {
     pub macros: Vec<Term>,
     pub app_type: Option<AppType>,
     pub include_path: FxHashSet<AbsPathBuf>,
+    pub include_remaps: Vec<IncludeRemap>,
 }
 
 impl ProjectAppDataAcc {
@@ -678,6 +806,7 @@ impl ProjectAppDataAcc {
             app_type: None,
             include_path: Default::default(),
             abs_src_dirs: Default::default(),
+            include_remaps: Default::default(),
         }
     }
 
@@ -801,6 +930,14 @@ impl ProjectAppDataAcc {
         self.include_dirs.extend(it);
     }
 
+    fn add_include_remaps(&mut self, target: &Target) {
+        for remap in &target.include_remaps {
+            if !self.include_remaps.contains(remap) {
+                self.include_remaps.push(remap.clone());
+            }
+        }
+    }
+
     fn set_macro(&mut self, target: &Target) {
         if target.target_type != TargetType::ThirdParty && self.macros.is_empty() {
             self.macros.push(Atom("TEST".into()));
@@ -817,6 +954,7 @@ impl ProjectAppDataAcc {
         self.add_src(target);
         self.add_include(target);
         self.add_include_path(target);
+        self.add_include_remaps(target);
     }
 }
 
@@ -836,6 +974,7 @@ impl From<ProjectAppDataAcc> for ProjectAppData {
                 .collect(),
             macros: acc.macros,
             parse_transforms: vec![],
+            include_remaps: acc.include_remaps,
             abs_src_dirs: acc.abs_src_dirs.into_iter().collect(),
             app_type: acc.app_type.unwrap(),
             // we sort to speed up parse-server
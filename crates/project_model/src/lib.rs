@@ -24,9 +24,11 @@ use std::sync::Arc;
 use anyhow::bail;
 use anyhow::Context;
 use anyhow::Result;
+use buck::CiConfig;
 use buck::EqwalizerConfig;
 use elp_log::timeit;
 use lazy_static::lazy_static;
+use lint_file::LintFileConfig;
 use parking_lot::MutexGuard;
 use paths::AbsPath;
 use paths::AbsPathBuf;
@@ -39,7 +41,9 @@ use crate::rebar::Profile;
 use crate::rebar::RebarConfig;
 use crate::rebar::RebarProject;
 
+pub mod beam;
 pub mod buck;
+pub mod lint_file;
 pub mod otp;
 pub mod rebar;
 
@@ -325,6 +329,88 @@ impl Project {
             ProjectBuildData::Rebar(_) => EqwalizerConfig::default(),
         }
     }
+
+    /// The manifest the project was loaded from, to load it again.
+    pub fn manifest(&self) -> Option<ProjectManifest> {
+        match &self.project_build_data {
+            ProjectBuildData::Buck(buck) => Some(ProjectManifest::BuckConfig(buck.config.clone())),
+            ProjectBuildData::Otp => None,
+            ProjectBuildData::Rebar(rebar) => {
+                Some(ProjectManifest::RebarConfig(rebar.rebar_config.clone()))
+            }
+        }
+    }
+
+    /// Glob patterns of the files defining the apps of the project, and
+    /// their source directories.
+    pub fn structure_file_globs(&self) -> Vec<String> {
+        let root = self.root();
+        match &self.project_build_data {
+            ProjectBuildData::Buck(buck) => vec![
+                buck.config.config_path().display().to_string(),
+                format!("{}/**/{{BUCK,TARGETS}}", root.display()),
+            ],
+            ProjectBuildData::Otp => vec![],
+            ProjectBuildData::Rebar(_) => vec![
+                format!("{}/{{rebar.config,rebar.config.script}}", root.display()),
+                format!("{}/**/*.app.src", root.display()),
+            ],
+        }
+    }
+
+    /// Whether `path` is one of the files matched by `structure_file_globs`.
+    pub fn is_structure_file(&self, path: &AbsPath) -> bool {
+        let name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name,
+            None => return false,
+        };
+        match &self.project_build_data {
+            ProjectBuildData::Buck(buck) => {
+                path == buck.config.config_path()
+                    || (path.starts_with(&*self.root()) && (name == "BUCK" || name == "TARGETS"))
+            }
+            ProjectBuildData::Otp => false,
+            ProjectBuildData::Rebar(rebar) => {
+                path.starts_with(&rebar.root)
+                    && (name.ends_with(".app.src")
+                        || (path.parent() == Some(&*rebar.root)
+                            && (name == "rebar.config" || name == "rebar.config.script")))
+            }
+        }
+    }
+
+    /// The `.elp_lint.toml` file of the project, next to its `.elp.toml`
+    /// or `rebar.config` file.
+    pub fn lint_file_path(&self) -> Option<AbsPathBuf> {
+        match &self.project_build_data {
+            ProjectBuildData::Buck(buck) => Some(
+                buck.config
+                    .config_path()
+                    .parent()?
+                    .join(lint_file::ELP_LINT_FILE),
+            ),
+            ProjectBuildData::Otp => None,
+            ProjectBuildData::Rebar(rebar) => Some(rebar.root.join(lint_file::ELP_LINT_FILE)),
+        }
+    }
+
+    pub fn lint_file_config(&self) -> LintFileConfig {
+        match self.lint_file_path() {
+            Some(path) => LintFileConfig::load(&path).unwrap_or_else(|err| {
+                log::warn!("could not read {}: {err}", path.display());
+                LintFileConfig::default()
+            }),
+            None => LintFileConfig::default(),
+        }
+    }
+
+    pub fn ci_config(&self) -> Result<CiConfig> {
+        match &self.project_build_data {
+            ProjectBuildData::Buck(buck) => Ok(buck.config.ci.clone()),
+            ProjectBuildData::Otp => Ok(CiConfig::default()),
+            ProjectBuildData::Rebar(rebar) => CiConfig::load(&rebar.root),
+        }
+    }
 }
 
 impl fmt::Debug for Project {
@@ -379,6 +465,32 @@ pub struct ProjectAppData {
     //list of directories required by module to compile
     //usually includes all dependencies include paths and otp
     pub include_path: Vec<AbsPathBuf>,
+    //headers generated by a build target outside the app layout
+    pub include_remaps: Vec<IncludeRemap>,
+}
+
+/// Headers of an app which are generated by a build target, in
+/// directories outside of the app layout. An `-include_lib` of
+/// `app/<prefix>/foo.hrl` is looked up as `foo.hrl` in `dirs`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IncludeRemap {
+    pub prefix: String,
+    pub dirs: Vec<AbsPathBuf>,
+    pub target: String,
+}
+
+impl IncludeRemap {
+    /// The places a header at `path`, relative to the app dir, is
+    /// generated at, if it is under `prefix`.
+    pub fn remap(&self, path: &str) -> Vec<AbsPathBuf> {
+        match path
+            .strip_prefix(self.prefix.as_str())
+            .and_then(|rest| rest.strip_prefix('/'))
+        {
+            Some(rest) => self.dirs.iter().map(|dir| dir.join(rest)).collect(),
+            None => vec![],
+        }
+    }
 }
 
 impl ProjectAppData {
@@ -399,6 +511,7 @@ impl ProjectAppData {
             parse_transforms: vec![],
             app_type: AppType::App,
             include_path: vec![],
+            include_remaps: vec![],
             abs_src_dirs: src_dirs,
         }
     }
@@ -422,6 +535,7 @@ impl ProjectAppData {
             parse_transforms: vec![],
             app_type: AppType::Otp,
             include_path: vec![include, src, parent],
+            include_remaps: vec![],
             abs_src_dirs: vec![abs_src_dir],
         }
     }
@@ -435,13 +549,22 @@ impl ProjectAppData {
     }
 
     /// Source directories for the application including the extra
-    /// sources and includes
+    /// sources, includes and generated includes
     pub fn all_source_dirs(&self) -> Vec<AbsPathBuf> {
         self.extra_src_dirs
             .iter()
             .map(|src_dir| self.dir.join(src_dir))
             .chain(self.abs_src_dirs.iter().cloned())
             .chain(self.include_dirs.iter().cloned())
+            .chain(self.generated_include_dirs())
+            .collect()
+    }
+
+    /// The directories the headers of the application are generated in
+    pub fn generated_include_dirs(&self) -> Vec<AbsPathBuf> {
+        self.include_remaps
+            .iter()
+            .flat_map(|remap| remap.dirs.iter().cloned())
             .collect()
     }
 
@@ -457,6 +580,8 @@ impl ProjectAppData {
         self.macros.dedup();
         self.parse_transforms.extend(other.parse_transforms);
         self.parse_transforms.dedup();
+        self.include_remaps.extend(other.include_remaps);
+        self.include_remaps.dedup();
     }
 }
 
@@ -477,6 +602,16 @@ impl Project {
         }
     }
 
+    /// Write the stub modules of the dependencies shipped without
+    /// Erlang sources, from their compiled BEAM files, if they changed
+    /// since. Returns whether any stubs were written again.
+    pub fn generate_beam_stubs(&self) -> bool {
+        match &self.project_build_data {
+            ProjectBuildData::Rebar(rebar) => beam::generate_stubs(&rebar.root, &rebar.deps),
+            ProjectBuildData::Otp | ProjectBuildData::Buck(_) => false,
+        }
+    }
+
     pub fn load(manifest: ProjectManifest) -> Result<Project> {
         let (project_build_info, build_info, otp_root) = match manifest {
             ProjectManifest::RebarConfig(ref rebar_setting) => {
@@ -497,7 +632,7 @@ impl Project {
                         rebar_version
                     )
                 })?;
-                let (rebar_project, otp_root) =
+                let (mut rebar_project, otp_root) =
                     RebarProject::from_rebar_build_info(&loaded, rebar_setting.clone())
                         .with_context(|| {
                             format!(
@@ -505,6 +640,7 @@ impl Project {
                                 manifest
                             )
                         })?;
+                beam::add_stub_dirs(&rebar_project.root, &mut rebar_project.deps);
                 (
                     ProjectBuildData::Rebar(rebar_project),
                     BuildInfoFile(Arc::new(loaded)),
@@ -0,0 +1,321 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! The `.elp_lint.toml` file at the root of a project, choosing which
+//! native diagnostics are reported for it, and how. Diagnostics are
+//! given by code or label, e.g. `W0017` or `undefined_function`.
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use anyhow::Result;
+use paths::AbsPath;
+use serde::Deserialize;
+
+pub const ELP_LINT_FILE: &str = ".elp_lint.toml";
+
+// Sample config:
+// ```
+// # Reported even if they are experimental
+// enabled = [ "W0011" ]
+// # Never reported
+// disabled = [ "W0017", "unused_macro" ]
+// # No diagnostics are reported for these apps
+// ignored_apps = [ "generated_app" ]
+//
+// [severity]
+// W0012 = "error"
+// unused_function_args = "weak_warning"
+//
+// [apps.my_app]
+// disabled = [ "W0006" ]
+//
+// [deprecated]
+// functions = [ "legacy_db", "my_db:query/2" ]
+//
+// [deprecated.replacements]
+// "my_db:query/2" = "my_db:run_query/2"
+//
+// [cross_node_eval]
+// add = [ "my_rpc:call/4" ]
+// remove = [ "sys:install" ]
+// wrappers = [ "my_app_rpc:call" ]
+//
+// [table_keys]
+// constructors = [ "my_cache:new/2" ]
+// readers = [ "my_cache:get/2" ]
+//
+// [atom_creation]
+// allowed = [ "my_app_config:load/1" ]
+//
+// [[plugins]]
+// name = "no_io_format"
+// module = "tools/lint/no_io_format.wasm"
+// timeout_ms = 500
+// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LintFileConfig {
+    #[serde(default)]
+    pub enabled: Vec<String>,
+    #[serde(default)]
+    pub disabled: Vec<String>,
+    #[serde(default)]
+    pub ignored_apps: Vec<String>,
+    /// Severity to report a diagnostic with, instead of its own
+    #[serde(default)]
+    pub severity: BTreeMap<String, LintSeverity>,
+    #[serde(default)]
+    pub apps: BTreeMap<String, AppLintConfig>,
+    #[serde(default)]
+    pub deprecated: DeprecatedConfig,
+    #[serde(default)]
+    pub cross_node_eval: CrossNodeEvalConfig,
+    #[serde(default)]
+    pub table_keys: TableKeysConfig,
+    #[serde(default)]
+    pub atom_creation: AtomCreationConfig,
+    #[serde(default)]
+    pub plugins: Vec<LintPluginConfig>,
+}
+
+/// Diagnostics not to report for a single app, in addition to the
+/// project wide ones.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppLintConfig {
+    #[serde(default)]
+    pub disabled: Vec<String>,
+}
+
+/// Functions reported by the `deprecated_function` lint, in addition
+/// to the ones deprecated by `-deprecated` attributes. Each one is given
+/// as `module`, `module:function` or `module:function/arity`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeprecatedConfig {
+    #[serde(default)]
+    pub functions: Vec<String>,
+    /// The `module:function/arity` to call instead of a deprecated
+    /// function, offered as a fix. The functions given here are
+    /// deprecated too.
+    #[serde(default)]
+    pub replacements: BTreeMap<String, String>,
+}
+
+/// Functions evaluating code on another node, reported by the
+/// `cross_node_eval` lint. Each one is given as `module`,
+/// `module:function` or `module:function/arity`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CrossNodeEvalConfig {
+    /// Functions to report in addition to the builtin ones
+    #[serde(default)]
+    pub add: Vec<String>,
+    /// Builtin functions not to report
+    #[serde(default)]
+    pub remove: Vec<String>,
+    /// Functions of the project wrapping a cross node eval. Calls to
+    /// them are reported, rather than the cross node eval they do.
+    #[serde(default)]
+    pub wrappers: Vec<String>,
+}
+
+/// Functions creating or reading ETS tables or `persistent_term`
+/// entries, tracked in addition to the builtin ones. Each one takes
+/// the key as its first argument, and is given as `module:function` or
+/// `module:function/arity`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TableKeysConfig {
+    #[serde(default)]
+    pub constructors: Vec<String>,
+    #[serde(default)]
+    pub readers: Vec<String>,
+}
+
+/// Functions of the project allowed to create atoms dynamically, e.g.
+/// from trusted config at startup, not reported by the
+/// `dynamic_atom_creation` lint. Each one is given as `module`,
+/// `module:function` or `module:function/arity`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AtomCreationConfig {
+    #[serde(default)]
+    pub allowed: Vec<String>,
+}
+
+/// A lint rule shipped as a WASI module, run on each file of the
+/// project with its functions serialized to JSON on the standard
+/// input, and printing the diagnostics found as JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LintPluginConfig {
+    pub name: String,
+    /// Path of the module, relative to the project root
+    pub module: String,
+    /// The command running the module, given as its last argument.
+    /// It is not read from the file, so that a project cannot run an
+    /// arbitrary command.
+    #[serde(skip_deserializing, default = "LintPluginConfig::default_runner")]
+    pub runner: Vec<String>,
+    /// Time after which the plugin is killed and its diagnostics dropped
+    #[serde(default = "LintPluginConfig::default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl LintPluginConfig {
+    fn default_runner() -> Vec<String> {
+        vec!["wasmtime".to_string(), "run".to_string()]
+    }
+
+    fn default_timeout_ms() -> u64 {
+        1000
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintSeverity {
+    Error,
+    Warning,
+    WeakWarning,
+}
+
+impl LintFileConfig {
+    /// Reads the `.elp_lint.toml` file at `path`, if it exists.
+    pub fn load(path: &AbsPath) -> Result<LintFileConfig> {
+        if !path.as_ref().exists() {
+            return Ok(LintFileConfig::default());
+        }
+        let config_content = fs::read_to_string(path)?;
+        LintFileConfig::parse(&config_content)
+    }
+
+    pub fn parse(content: &str) -> Result<LintFileConfig> {
+        Ok(toml::from_str(content)?)
+    }
+
+    /// Whether the diagnostic, given by code and label, is disabled
+    /// for the app.
+    pub fn is_disabled(&self, app: &str, code: &str, label: &str) -> bool {
+        let matches = |names: &[String]| names.iter().any(|name| name == code || name == label);
+        self.ignored_apps.iter().any(|ignored| ignored == app)
+            || matches(&self.disabled)
+            || self
+                .apps
+                .get(app)
+                .map_or(false, |app_config| matches(&app_config.disabled))
+    }
+
+    pub fn is_enabled(&self, code: &str, label: &str) -> bool {
+        self.enabled
+            .iter()
+            .any(|name| name == code || name == label)
+    }
+
+    pub fn severity(&self, code: &str, label: &str) -> Option<LintSeverity> {
+        self.severity
+            .get(code)
+            .or_else(|| self.severity.get(label))
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_config() {
+        let config = LintFileConfig::parse(
+            r#"
+enabled = [ "W0011" ]
+disabled = [ "unused_macro" ]
+ignored_apps = [ "generated_app" ]
+
+[severity]
+W0012 = "error"
+
+[apps.my_app]
+disabled = [ "W0006" ]
+
+[deprecated]
+functions = [ "legacy_db" ]
+
+[deprecated.replacements]
+"my_db:query/2" = "my_db:run_query/2"
+
+[cross_node_eval]
+wrappers = [ "my_app_rpc:call" ]
+
+[table_keys]
+readers = [ "my_cache:get/2" ]
+
+[atom_creation]
+allowed = [ "my_app_config:load/1" ]
+
+[[plugins]]
+name = "no_io_format"
+module = "tools/lint/no_io_format.wasm"
+"#,
+        )
+        .unwrap();
+        assert!(config.is_enabled("W0011", "application_get_env"));
+        assert!(config.is_disabled("other_app", "W0002", "unused_macro"));
+        assert!(config.is_disabled("my_app", "W0006", "statement_has_no_effect"));
+        assert!(!config.is_disabled("other_app", "W0006", "statement_has_no_effect"));
+        assert!(config.is_disabled("generated_app", "W0006", "statement_has_no_effect"));
+        assert_eq!(
+            config.severity("W0012", "compile-warn-missing-spec"),
+            Some(LintSeverity::Error)
+        );
+        assert_eq!(config.severity("W0006", "statement_has_no_effect"), None);
+        assert_eq!(config.deprecated.functions, vec!["legacy_db".to_string()]);
+        assert_eq!(
+            config.deprecated.replacements.get("my_db:query/2"),
+            Some(&"my_db:run_query/2".to_string())
+        );
+        assert_eq!(
+            config.cross_node_eval.wrappers,
+            vec!["my_app_rpc:call".to_string()]
+        );
+        assert_eq!(
+            config.table_keys.readers,
+            vec!["my_cache:get/2".to_string()]
+        );
+        assert_eq!(
+            config.atom_creation.allowed,
+            vec!["my_app_config:load/1".to_string()]
+        );
+        assert_eq!(
+            config.plugins,
+            vec![LintPluginConfig {
+                name: "no_io_format".to_string(),
+                module: "tools/lint/no_io_format.wasm".to_string(),
+                runner: vec!["wasmtime".to_string(), "run".to_string()],
+                timeout_ms: 1000,
+            }]
+        );
+    }
+
+    #[test]
+    fn unknown_key() {
+        assert!(LintFileConfig::parse("disable = [ \"W0017\" ]").is_err());
+        assert!(LintFileConfig::parse(
+            r#"
+[[plugins]]
+name = "no_io_format"
+module = "tools/lint/no_io_format.wasm"
+runner = [ "sh", "-c" ]
+"#
+        )
+        .is_err());
+    }
+}
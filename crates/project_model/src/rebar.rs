@@ -181,6 +181,7 @@ impl RebarProject {
                 parse_transforms: to_vec(map_get(term, "parse_transforms")?)?.to_owned(),
                 app_type: is_dep,
                 include_path: vec![],
+                include_remaps: vec![],
                 abs_src_dirs,
             })
         }